@@ -0,0 +1,65 @@
+//! Benchmarks of parsing, solving and rendering synthetic markets.
+//!
+//! The crate doesn't depend on a benchmark framework, each benchmark is run a few times and its
+//! median and fastest times are written, e.g. `parse/E3_C50 <median_us, 1520> <min_us, 1490>`.
+//! Run by `cargo bench`, `cargo bench -- solve` runs only the benchmarks of names containing
+//! `solve`.
+
+use exchange_rate::{synthetic, Request};
+use std::env;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Runs of each benchmark.
+const RUNS: usize = 11;
+
+/// Markets benchmarked: exchanges, currencies, density.
+const MARKETS: [(usize, usize, f64); 4] = [(3, 10, 1.0), (3, 40, 0.3), (5, 40, 0.1), (10, 30, 0.1)];
+
+fn bench<F: FnMut()>(name: &str, filter: &Option<String>, mut run: F) {
+    if let Some(filter) = filter {
+        if !name.contains(filter.as_str()) {
+            return;
+        }
+    }
+
+    let mut times: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+
+    println!(
+        "{} <median_us, {}> <min_us, {}>",
+        name,
+        times[RUNS / 2].as_micros(),
+        times[0].as_micros()
+    );
+}
+
+fn main() {
+    // Cargo passes `--bench` along with the arguments.
+    let filter = env::args()
+        .skip(1)
+        .find(|argument| !argument.starts_with("--"));
+
+    for &(exchanges, currencies, density) in MARKETS.iter() {
+        let market = format!("E{}_C{}", exchanges, currencies);
+        let lines = synthetic::market(exchanges, currencies, density, 42);
+        let request = Request::<String, f64>::from_lines(lines.clone());
+        let response = request.answer(&Default::default());
+
+        bench(&format!("parse/{}", market), &filter, || {
+            black_box(Request::<String, f64>::from_lines(lines.clone()));
+        });
+        bench(&format!("solve/{}", market), &filter, || {
+            black_box(request.answer(&Default::default()));
+        });
+        bench(&format!("render/{}", market), &filter, || {
+            black_box(response.get_output());
+        });
+    }
+}
@@ -0,0 +1,13 @@
+//! Arbitrary lines must be parsed into an `ExchangeRateRequest` or rejected by an error, never
+//! panic.
+
+#![no_main]
+
+use exchange_rate::ExchangeRateRequest;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(line) = std::str::from_utf8(data) {
+        let _ = ExchangeRateRequest::<String, f64>::parse_line(line);
+    }
+});
@@ -0,0 +1,15 @@
+//! Arbitrary bytes must be read into a `Request` without a panic.
+//!
+//! `Request::read_from` panics on a line that isn't valid by its contract, so the input is read
+//! the same way by `Request::read_with_hooks` with an error callback skipping such lines.
+
+#![no_main]
+
+use exchange_rate::{ReadHooks, Request};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = data;
+    let mut hooks = ReadHooks::<String, f64>::new().on_error(|_, _| {});
+    let _ = Request::read_with_hooks(&mut input, &mut hooks);
+});
@@ -1,18 +1,354 @@
 //! Exchange Rate Path (ERP) algorithm.
+use crate::clock;
+use crate::clock::Instant;
+use crate::error::Error;
+use crate::hash::NodeHashBuilder;
+use crate::memory;
+use crate::request::exchange_rate_request::ExchangeRateRequest;
+use crate::request::price_update::PriceUpdate;
 use crate::request::Request;
 use crate::response::best_rate_path::BestRatePath;
+use crate::response::unanswered_request::Reason::*;
+use crate::response::unanswered_request::{Reason, UnansweredRequest};
 use crate::response::Response;
+use crate::trace;
 use crate::IndexMapTrait;
-use floyd_warshall_alg::{FloydWarshall, FloydWarshallResult, FloydWarshallTrait};
+use chrono::{DateTime, FixedOffset};
+use floyd_warshall_alg::FloydWarshallTrait;
 use indexmap::map::{Entry, IndexMap};
 use indexmap::IndexSet;
 use num_traits::Num;
 use safe_graph::{Graph, NodeTrait};
 use std::clone::Clone;
-use std::cmp::Ordering::{Greater, Less};
 use std::fmt::{Debug, Display};
 use std::ops::AddAssign;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+mod amount_path;
+mod arbitrage;
+pub mod builder;
+pub mod cancellation;
+mod cycle_enumeration;
+pub mod fee_model;
+mod hop_path;
+pub mod paths;
+pub mod progress;
+pub mod settings;
+pub mod slippage_model;
+pub mod solver;
+mod timed_path;
+pub mod trading_rules;
+pub mod transfer_model;
+mod triangular_arbitrage;
+
+use self::builder::AlgorithmBuilder;
+use self::cancellation::{Cancellation, Cancelled};
+use self::fee_model::ExecutionStyle;
+use self::paths::Paths;
+use self::settings::{Backend, Settings};
+use self::solver::{FloydWarshallSolver, Solver};
+
+/// Best rate paths of the graph of `(exchange, currency)` node indexes.
+type PathsResult<I, E> = Paths<I, E>;
+
+/// Floyd-Warshall result solved once it's needed for the first time.
+type LazyPaths<I, E> = OnceLock<PathsResult<I, E>>;
+
+/// `Algorithm` of a graph together with its Floyd-Warshall result.
+type SolvedRates<N, E, I> = (Algorithm<N, E, I>, LazyPaths<I, E>);
+
+/// Factors the edges between `(exchange, currency)` node indexes were decayed by.
+type EdgeDecays<I, E> = IndexMap<((I, I), (I, I)), E, NodeHashBuilder>;
+
+/// The least number of `ExchangeRateRequest`s worth answering by multiple threads.
+const PARALLEL_MIN_RATE_REQUESTS: usize = 256;
+
+/// `Solution` structure.
+///
+/// The graph of `PriceUpdate`s solved by the Floyd-Warshall algorithm, together with the graphs
+/// of penalized and maker rates if they differ, answering requests without solving it again.
+/// It can be held on to and answer further batches of requests, until it's marked stale by new
+/// `PriceUpdate`s of the engine it was taken from.
+///
+/// # `Solution<N, E, I>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+/// - Index `I` for indexing of nodes `N`.
+pub struct Solution<N, E, I> {
+    alg: Algorithm<N, E, I>,
+    result: LazyPaths<I, E>,
+    penalized: Option<SolvedRates<N, E, I>>,
+    maker: Option<SolvedRates<N, E, I>>,
+    graph_build_time: Duration,
+    solve_time: Duration,
+    stale: AtomicBool,
+    version: u64,
+    with_maker: bool,
+    as_of: Option<DateTime<FixedOffset>>,
+}
+
+impl<N, E, I> Solution<N, E, I> {
+    /// Get whether `PriceUpdate`s arrived since it was solved, so its answers may be outdated.
+    pub fn is_stale(&self) -> bool {
+        self.stale.load(Ordering::Relaxed)
+    }
+
+    /// Mark it outdated by new `PriceUpdate`s.
+    pub fn mark_stale(&self) {
+        self.stale.store(true, Ordering::Relaxed);
+    }
+
+    /// Set version of the engine state it was solved of, stamped on the `Response`s it answers.
+    pub fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+
+    /// Get version of the engine state it was solved of, `0` if it wasn't solved by an engine.
+    pub fn get_version(&self) -> u64 {
+        self.version
+    }
+
+    /// Get whether rates of maker orders were solved, see `Algorithm::solve`.
+    pub fn is_solved_with_maker(&self) -> bool {
+        self.with_maker
+    }
+
+    /// Get the timestamp of the most recent quote it was solved of, `None` if there's none.
+    pub fn get_as_of(&self) -> Option<&DateTime<FixedOffset>> {
+        self.as_of.as_ref()
+    }
+
+    /// Get time spent by building its graph.
+    pub fn get_graph_build_time(&self) -> Duration {
+        self.graph_build_time
+    }
+
+    /// Get time spent by solving its best rate paths.
+    pub fn get_solve_time(&self) -> Duration {
+        self.solve_time
+    }
+}
+
+impl<N, E, I> Solution<N, E, I>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+    I: NodeTrait + Num + AddAssign,
+{
+    /// Answer the `ExchangeRateRequest`s and `ArbitrageRequest`s of the `Request`, its
+    /// `PriceUpdate`s are not used.
+    pub fn answer(&self, request: &Request<N, E>) -> Response<N, E> {
+        let rate_requests: Vec<_> = request.get_rate_requests().values().collect();
+        let mut response = self.answer_rate_requests(&rate_requests, &Cancellation::new());
+
+        self.finish_response(request, &mut response);
+        response
+    }
+
+    /// Answer the `Request` like `answer`, unless the computation is cancelled meanwhile.
+    ///
+    /// Once it's cancelled, the partial `Response` of the requests answered before is returned
+    /// as the `Cancelled` error.
+    pub fn answer_cancellable(
+        &self,
+        request: &Request<N, E>,
+        cancellation: &Cancellation,
+    ) -> Result<Response<N, E>, Cancelled<N, E>> {
+        let rate_requests: Vec<_> = request.get_rate_requests().values().collect();
+        let mut response = self.answer_rate_requests(&rate_requests, cancellation);
+
+        if cancellation.is_cancelled() {
+            response.set_graph_build_time(self.graph_build_time);
+            response.set_solve_time(self.solve_time);
+            response.set_graph_size(self.get_node_count(), self.get_edge_count());
+            response.set_version(self.version);
+            self.stamp_response(&mut response);
+            return Err(Cancelled::new(response));
+        }
+
+        self.finish_response(request, &mut response);
+        Ok(response)
+    }
+
+    /// Answer the single `ExchangeRateRequest` by its best rate path, `None` if there's none, or
+    /// `Error::UnknownNode` if a node of it isn't known.
+    pub fn best_rate(
+        &self,
+        rate_request: &ExchangeRateRequest<N, E>,
+    ) -> Result<Option<BestRatePath<N, E>>, Error> {
+        let response = self.answer_rate_requests(&[rate_request], &Cancellation::new());
+        if let Some(reason) = response
+            .get_unanswered_requests()
+            .iter()
+            .map(UnansweredRequest::get_reason)
+            .find(Reason::is_unknown_node)
+        {
+            return Err(Error::UnknownNode(reason));
+        }
+
+        Ok(response.into_iter().next())
+    }
+
+    /// Answer the `ExchangeRateRequest`s in their order, the ones left once the computation is
+    /// cancelled are unanswered.
+    fn answer_rate_requests(
+        &self,
+        rate_requests: &[&ExchangeRateRequest<N, E>],
+        cancellation: &Cancellation,
+    ) -> Response<N, E> {
+        let taker = self
+            .penalized
+            .as_ref()
+            .map_or((&self.alg, &self.result), |(taker_alg, taker_result)| {
+                (taker_alg, taker_result)
+            });
+
+        taker.0.form_response(
+            rate_requests,
+            taker.1,
+            self.maker
+                .as_ref()
+                .map(|(maker_alg, maker_result)| (maker_alg, maker_result)),
+            cancellation,
+        )
+    }
+
+    /// Add the answers of `ArbitrageRequest`s, the graph build and solve times and the graph size
+    /// to the `Response`.
+    fn finish_response(&self, request: &Request<N, E>, response: &mut Response<N, E>) {
+        if !request.get_arbitrage_requests().is_empty() {
+            self.alg
+                .form_arbitrage_response(request, self.alg.get_paths(&self.result), response);
+        }
+
+        response.set_graph_build_time(self.graph_build_time);
+        response.set_solve_time(self.solve_time);
+        response.set_graph_size(self.get_node_count(), self.get_edge_count());
+        response.set_version(self.version);
+        self.stamp_response(response);
+    }
+
+    /// Stamp the `Response` by the timestamp of the most recent quote and the current moment.
+    fn stamp_response(&self, response: &mut Response<N, E>) {
+        response.set_as_of(self.as_of);
+        response.set_computed_at(clock::now().fixed_offset());
+    }
+
+    /// Get printable dump of the indexes its graph was built with, see `Algorithm::dump_indexes`.
+    pub fn dump_indexes(&self) -> String
+    where
+        I: Display,
+    {
+        self.alg.dump_indexes()
+    }
+
+    /// Get count of the `(exchange, currency)` nodes of its graph.
+    pub fn get_node_count(&self) -> usize {
+        self.alg.graph.node_count()
+    }
+
+    /// Get count of the trade and transfer edges of its graph.
+    pub fn get_edge_count(&self) -> usize {
+        self.alg.graph.edge_count()
+    }
+
+    /// Update its graphs by the new or changed `PriceUpdate`s and solve them again, without
+    /// constructing the graphs from all `PriceUpdate`s, see `Algorithm::update_graph`.
+    ///
+    /// Return `false` and leave it unchanged if the graphs can't be updated, it has to be solved
+    /// from the `Request` again then.
+    pub(crate) fn update(&mut self, price_updates: &[&PriceUpdate<N, E>]) -> bool {
+        let start = Instant::now();
+
+        if self
+            .penalized
+            .iter()
+            .chain(self.maker.iter())
+            .any(|(alg, _)| alg.is_aged_by(price_updates))
+        {
+            return false;
+        }
+
+        // The other graphs have the same edges of `PriceUpdate`s, so they're updated as well.
+        if !self.alg.update_graph(price_updates) {
+            return false;
+        }
+        for (alg, _) in self.penalized.iter_mut().chain(self.maker.iter_mut()) {
+            alg.update_graph(price_updates);
+        }
+        self.graph_build_time = start.elapsed();
+
+        self.result = self.alg.solve_paths_uncancellable();
+        for (alg, result) in self.penalized.iter_mut().chain(self.maker.iter_mut()) {
+            *result = alg.solve_paths_uncancellable();
+        }
+        self.solve_time = start.elapsed() - self.graph_build_time;
+        self.stale.store(false, Ordering::Relaxed);
+        let timestamps = price_updates
+            .iter()
+            .filter(|price_update| !price_update.is_stale())
+            .map(|price_update| *price_update.get_timestamp());
+        self.as_of = self.as_of.into_iter().chain(timestamps).max();
+
+        true
+    }
+}
+
+impl<N, E, I> Solution<N, E, I>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + Sync,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + Sync + 'static,
+    <E as FromStr>::Err: Debug,
+    I: NodeTrait + Num + AddAssign + Send + Sync,
+{
+    /// Answer the `Request` like `answer`, but split its `ExchangeRateRequest`s among threads.
+    ///
+    /// The answers of independent requests are extracted concurrently and merged in the order
+    /// of the requests, so the `Response` is the same. Small batches are answered by the current
+    /// thread only, spawning threads would cost more than it saves.
+    pub fn answer_parallel(&self, request: &Request<N, E>) -> Response<N, E> {
+        let threads = thread::available_parallelism().map_or(1, usize::from);
+
+        self.answer_by_threads(request, threads)
+    }
+
+    /// Answer the `Request`, its `ExchangeRateRequest`s split among the number of threads.
+    fn answer_by_threads(&self, request: &Request<N, E>, threads: usize) -> Response<N, E> {
+        let rate_requests: Vec<_> = request.get_rate_requests().values().collect();
+
+        let mut response = if threads < 2 || rate_requests.len() < PARALLEL_MIN_RATE_REQUESTS {
+            self.answer_rate_requests(&rate_requests, &Cancellation::new())
+        } else {
+            let chunk_size = rate_requests.len().div_ceil(threads);
+
+            thread::scope(|scope| {
+                let handles: Vec<_> = rate_requests
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || self.answer_rate_requests(chunk, &Cancellation::new()))
+                    })
+                    .collect();
+
+                let mut response = Response::new();
+                for handle in handles {
+                    response.extend(handle.join().expect("Answering thread panicked!"));
+                }
+                response
+            })
+        };
+
+        self.finish_response(request, &mut response);
+        response
+    }
+}
 
 /// Exchange Rate Path `Algorithm` structure.
 ///
@@ -23,96 +359,563 @@ use std::str::FromStr;
 /// - Index `I` for indexing of nodes `N`.
 pub struct Algorithm<N, E, I> {
     graph: Graph<(I, I), E>,
-    node_to_index: IndexMap<N, I>,
-    index_to_node: IndexMap<I, N>,
+    node_to_index: IndexMap<N, I, NodeHashBuilder>,
+    index_to_node: IndexMap<I, N, NodeHashBuilder>,
     counter: I,
-    currency_exchanges: IndexMap<I, IndexSet<I>>,
+    exchanges: IndexSet<I, NodeHashBuilder>,
+    currency_exchanges: IndexMap<I, IndexSet<I, NodeHashBuilder>, NodeHashBuilder>,
+    settings: Settings<N, E>,
+    execution_style: ExecutionStyle,
+    transfer_penalty: E,
+    quote_decay: Option<(E, DateTime<FixedOffset>)>,
+    decays: EdgeDecays<I, E>,
 }
 
 impl<N, E, I> Algorithm<N, E, I>
 where
     N: Clone + Display + FromStr + IndexMapTrait + Debug,
     <N as FromStr>::Err: Debug,
-    E: Display + FloydWarshallTrait + FromStr + Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
     <E as FromStr>::Err: Debug,
     I: NodeTrait + Num + AddAssign,
 {
+    #[allow(dead_code)]
     fn new() -> Self {
+        Self::new_customized(Settings::new())
+    }
+
+    fn new_customized(settings: Settings<N, E>) -> Self {
         let graph = Graph::<(I, I), E>::new();
-        let node_to_index = IndexMap::<N, I>::new();
-        let index_to_node = IndexMap::<I, N>::new();
+        let node_to_index = IndexMap::default();
+        let index_to_node = IndexMap::default();
         let counter = I::zero();
-        let currency_exchanges = IndexMap::<I, IndexSet<I>>::new();
+        let exchanges = IndexSet::default();
+        let currency_exchanges = IndexMap::default();
 
         Self {
             graph,
             node_to_index,
             index_to_node,
             counter,
+            exchanges,
             currency_exchanges,
+            settings,
+            execution_style: ExecutionStyle::default(),
+            transfer_penalty: E::one(),
+            quote_decay: None,
+            decays: IndexMap::default(),
+        }
+    }
+
+    /// Create the `Algorithm` with the graph constructed from `PriceUpdate`s of the `Request`.
+    pub fn from_request(request: &Request<N, E>, settings: &Settings<N, E>) -> Self {
+        let mut alg = Self::new_customized(settings.clone());
+        alg.construct_graph(request);
+        alg
+    }
+
+    /// Get printable dump of the indexes of identifiers and of the exchanges bridged by each
+    /// currency, to inspect how the graph was indexed when a path looks wrong.
+    ///
+    /// # Format
+    ///
+    /// INDEXES_BEGIN
+    /// NODE_TO_INDEX <identifier> <index>
+    /// INDEX_TO_NODE <index> <identifier>
+    /// CURRENCY_EXCHANGES <currency index, currency> <exchange index, exchange> ...
+    /// INDEXES_END
+    ///
+    /// Mappings are in the order identifiers were indexed, an index without an identifier is
+    /// shown as `?`.
+    pub fn dump_indexes(&self) -> String
+    where
+        I: Display,
+    {
+        let mut output = String::from("INDEXES_BEGIN\n");
+        let indexed = |index: &I| match self.index_to_node(index) {
+            Some(node) => format!("<{}, {}>", index, node),
+            None => format!("<{}, ?>", index),
+        };
+
+        for (node, index) in self.node_to_index.iter() {
+            output.push_str(&format!("NODE_TO_INDEX <{}> <{}>\n", node, index));
+        }
+        for (index, node) in self.index_to_node.iter() {
+            output.push_str(&format!("INDEX_TO_NODE <{}> <{}>\n", index, node));
+        }
+        for (currency, exchanges) in self.currency_exchanges.iter() {
+            output.push_str("CURRENCY_EXCHANGES ");
+            output.push_str(&indexed(currency));
+            for exchange in exchanges.iter() {
+                output.push(' ');
+                output.push_str(&indexed(exchange));
+            }
+            output.push('\n');
         }
+        output.push_str("INDEXES_END\n");
+
+        output
+    }
+
+    pub fn get_settings(&self) -> &Settings<N, E> {
+        &self.settings
     }
 
+    #[allow(dead_code)]
     pub fn process(request: &Request<N, E>) -> Response<N, E> {
-        let mut alg = Algorithm::<N, E, I>::new();
+        Self::process_customized(request, &Settings::new())
+    }
+
+    /// Get the `AlgorithmBuilder` configuring the `Algorithm`.
+    pub fn builder() -> AlgorithmBuilder<N, E> {
+        AlgorithmBuilder::new()
+    }
+
+    /// Answer the `Request` using the `Settings` of the `Algorithm`.
+    pub fn answer(&self, request: &Request<N, E>) -> Response<N, E> {
+        Self::process_customized(request, &self.settings)
+    }
+
+    /// Process the `Request` using customized `Settings`.
+    pub fn process_customized(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+    ) -> Response<N, E> {
+        Self::process_with(request, settings, Solution::answer)
+    }
+
+    /// Process the `Request` using customized `Settings`, unless the computation is cancelled
+    /// meanwhile.
+    ///
+    /// The cancellation is checked before each Floyd-Warshall run, after each of its iterations
+    /// and before each request, so a deadline bounds the latency of large graphs as well.
+    /// Once it's cancelled, the partial `Response` of the requests answered before is returned
+    /// as the `Cancelled` error.
+    pub fn process_cancellable(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+        cancellation: &Cancellation,
+    ) -> Result<Response<N, E>, Cancelled<N, E>> {
+        Self::process_kept(
+            request,
+            settings,
+            &mut None,
+            &mut IndexSet::new(),
+            cancellation,
+        )
+    }
+
+    /// Process the `Request` like `process_cancellable`, answering it by the kept `Solution` of
+    /// its `PriceUpdate`s if there's one. Otherwise, or if it lacks rates of maker orders needed
+    /// now, the `Solution` is solved and kept for the next requests.
+    ///
+    /// Indexes of the `PriceUpdate`s changed since the `Solution` was kept are taken from the
+    /// `updated` ones, and the `Solution` is updated by them, see `Solution::update`. The caller
+    /// drops the kept `Solution` once `PriceUpdate`s change in any other way.
+    pub(crate) fn process_kept(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+        kept: &mut Option<Solution<N, E, I>>,
+        updated: &mut IndexSet<(N, N, N)>,
+        cancellation: &Cancellation,
+    ) -> Result<Response<N, E>, Cancelled<N, E>> {
+        let start = Instant::now();
+
+        let with_maker = request
+            .get_rate_requests()
+            .values()
+            .any(|rate_request| rate_request.get_execution_style() == ExecutionStyle::Maker);
+        let result = if request.get_rate_requests().is_empty()
+            && request.get_arbitrage_requests().is_empty()
+        {
+            Ok(Response::new())
+        } else {
+            if !updated.is_empty() {
+                let price_updates: Option<Vec<_>> = updated
+                    .drain(..)
+                    .map(|index| request.get_price_updates().get(&index))
+                    .collect();
+                let is_updated = match (kept.as_mut(), price_updates) {
+                    (Some(solution), Some(price_updates)) => solution.update(&price_updates),
+                    _ => false,
+                };
+                if !is_updated {
+                    *kept = None;
+                }
+            }
+
+            if kept
+                .as_ref()
+                .is_none_or(|solution| with_maker && !solution.with_maker)
+            {
+                *kept = Self::solve_cancellable(request, settings, with_maker, cancellation);
+            }
+
+            match kept {
+                Some(solution) => solution.answer_cancellable(request, cancellation),
+                None => {
+                    let rate_requests: Vec<_> = request.get_rate_requests().values().collect();
+                    Err(Cancelled::new(form_cancelled_response(&rate_requests)))
+                }
+            }
+        };
+
+        result
+            .map(|mut response| {
+                response.set_compute_time(start.elapsed());
+                response
+            })
+            .map_err(|cancelled| {
+                let mut response = cancelled.into_response();
+                response.set_compute_time(start.elapsed());
+                Cancelled::new(response)
+            })
+    }
+
+    /// Process the `Request`, answering it by the `answer` function of its `Solution`.
+    fn process_with(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+        answer: impl Fn(&Solution<N, E, I>, &Request<N, E>) -> Response<N, E>,
+    ) -> Response<N, E> {
+        let start = Instant::now();
+
+        // Avoid the costly Floyd-Warshall run if there is nothing to answer.
+        if request.get_rate_requests().is_empty() && request.get_arbitrage_requests().is_empty() {
+            let mut response = Response::new();
+            response.set_compute_time(start.elapsed());
+            return response;
+        }
+
+        let with_maker = request
+            .get_rate_requests()
+            .values()
+            .any(|rate_request| rate_request.get_execution_style() == ExecutionStyle::Maker);
+        let solution = Self::solve(request, settings, with_maker);
+
+        let mut response = answer(&solution, request);
+        response.set_compute_time(start.elapsed());
+        response
+    }
+
+    /// Solve the graph of `PriceUpdate`s of the `Request` once, so any requests can be answered
+    /// by the `Solution` later. Rates of maker orders are solved only if `with_maker` is set.
+    pub fn solve(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+        with_maker: bool,
+    ) -> Solution<N, E, I> {
+        Self::solve_cancellable(request, settings, with_maker, &Cancellation::new())
+            .expect("The computation without cancellation can't be cancelled!")
+    }
+
+    /// Solve the graph like `solve`, `None` is returned if the computation is cancelled before
+    /// or during any of the Floyd-Warshall runs.
+    fn solve_cancellable(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+        with_maker: bool,
+        cancellation: &Cancellation,
+    ) -> Option<Solution<N, E, I>> {
+        let start = Instant::now();
+
+        let mut alg = Algorithm::<N, E, I>::new_customized(settings.clone());
+        alg.construct_graph(request);
+        let graph_build_time = start.elapsed();
+        if cancellation.is_cancelled() {
+            return None;
+        }
+        let result = alg.solve_paths(cancellation)?;
+
+        // Transfer penalty and quote decay affect the choice of best rate paths only, not
+        // arbitrages.
+        let penalized = if settings.has_transfer_penalty() || settings.has_quote_decay() {
+            if cancellation.is_cancelled() {
+                return None;
+            }
+            Some(Self::prepare_rates(
+                request,
+                settings,
+                ExecutionStyle::Taker,
+                cancellation,
+            )?)
+        } else {
+            None
+        };
+
+        // Maker orders pay different fees, so their rates come from a graph of their own.
+        let maker = if settings.get_fee_model().has_maker_fees() && with_maker {
+            if cancellation.is_cancelled() {
+                return None;
+            }
+            Some(Self::prepare_rates(
+                request,
+                settings,
+                ExecutionStyle::Maker,
+                cancellation,
+            )?)
+        } else {
+            None
+        };
+
+        Some(Solution {
+            alg,
+            result,
+            penalized,
+            maker,
+            graph_build_time,
+            solve_time: start.elapsed() - graph_build_time,
+            stale: AtomicBool::new(false),
+            version: 0,
+            with_maker,
+            as_of: request.get_latest_timestamp(),
+        })
+    }
+
+    /// Prepare the `Algorithm` and its Floyd-Warshall result answering `ExchangeRateRequest`s of
+    /// the execution style, with the transfer penalty and the quote decay applied, `None` if the
+    /// computation is cancelled meanwhile.
+    fn prepare_rates(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+        execution_style: ExecutionStyle,
+        cancellation: &Cancellation,
+    ) -> Option<SolvedRates<N, E, I>> {
+        let mut alg = Algorithm::<N, E, I>::new_customized(settings.clone());
+        alg.execution_style = execution_style;
+        alg.transfer_penalty = *settings.get_transfer_penalty();
+        if let (Some(half_life), Some(now)) = (
+            settings.get_quote_half_life(),
+            request.get_latest_timestamp(),
+        ) {
+            alg.quote_decay = Some((*half_life, now));
+        }
         alg.construct_graph(request);
-        let result = alg.run_customized_floyd_warshall();
-        alg.form_response(request, &result)
+        let result = alg.solve_paths(cancellation)?;
+
+        Some((alg, result))
     }
 
     fn construct_graph(&mut self, request: &Request<N, E>) {
+        let _span = trace::span("construct_graph");
+        let _phase = memory::phase("construct_graph");
+
         // Process all `PriceUpdates`.
         for (_, price_update) in request.get_price_updates().iter() {
-            // Prepare indexes.
-            let exchange_index = self.node_to_index(price_update.get_exchange().clone());
-            let source_currency_index =
-                self.node_to_index(price_update.get_source_currency().clone());
-            let destination_currency_index =
-                self.node_to_index(price_update.get_destination_currency().clone());
+            self.add_price_update(price_update);
+        }
+
+        // For each currency add edges, so that each `(exchange, currency)` is connected to every
+        // other `(other_exchange, currency)` with an edge weight of the transfer factor.
+        self.add_currency_exchanges_edges();
+    }
+
+    /// Update the constructed graph by the new or changed `PriceUpdate`s only, instead of
+    /// constructing it again from all of them. Edges between exchanges are added for currencies
+    /// newly quoted by an exchange.
+    ///
+    /// Return `false` and leave the graph unchanged if a `PriceUpdate` went stale while its edges
+    /// are in the graph, edges can't be removed, so the graph has to be constructed again.
+    pub(crate) fn update_graph(&mut self, price_updates: &[&PriceUpdate<N, E>]) -> bool {
+        let _span = trace::span("update_graph");
+
+        if price_updates
+            .iter()
+            .any(|price_update| price_update.is_stale() && self.has_edges(price_update))
+        {
+            return false;
+        }
+
+        let mut currencies = IndexSet::<I>::new();
+        for price_update in price_updates {
+            currencies.extend(self.add_price_update(price_update));
+        }
+        for currency in currencies {
+            self.add_currency_edges(currency);
+        }
+
+        true
+    }
+
+    /// Get whether a `PriceUpdate` is more recent than the quotes are aged to, so the decay of
+    /// all the edges changes and the graph has to be constructed again.
+    fn is_aged_by(&self, price_updates: &[&PriceUpdate<N, E>]) -> bool {
+        self.quote_decay.is_some_and(|(_, now)| {
+            price_updates
+                .iter()
+                .any(|price_update| !price_update.is_stale() && *price_update.get_timestamp() > now)
+        })
+    }
+
+    /// Get whether the graph has the edge of the `PriceUpdate`.
+    fn has_edges(&self, price_update: &PriceUpdate<N, E>) -> bool {
+        match (
+            self.get_node_index(price_update.get_exchange()),
+            self.get_node_index(price_update.get_source_currency()),
+            self.get_node_index(price_update.get_destination_currency()),
+        ) {
+            (Some(exchange), Some(source_currency), Some(destination_currency)) => {
+                self.graph.contains_edge(
+                    (exchange, source_currency),
+                    (exchange, destination_currency),
+                )
+            }
+            _ => false,
+        }
+    }
 
-            // Get star and end node.
-            let a = (exchange_index, source_currency_index);
-            let b = (exchange_index, destination_currency_index);
+    /// Add edges of the `PriceUpdate` and collect its exchange and currencies, return the
+    /// currencies the exchange didn't quote before.
+    fn add_price_update(&mut self, price_update: &PriceUpdate<N, E>) -> Vec<I> {
+        // Prepare indexes.
+        let exchange_index = self.node_to_index(price_update.get_exchange().clone());
+        let source_currency_index = self.node_to_index(price_update.get_source_currency().clone());
+        let destination_currency_index =
+            self.node_to_index(price_update.get_destination_currency().clone());
+
+        // Get star and end node.
+        let a = (exchange_index, source_currency_index);
+        let b = (exchange_index, destination_currency_index);
+
+        // Pairs the exchange doesn't allow trading have no edges, but their nodes are known.
+        let is_pair_enabled = self.settings.get_trading_rules().is_pair_enabled(
+            price_update.get_exchange(),
+            price_update.get_source_currency(),
+            price_update.get_destination_currency(),
+        );
+
+        // Stale quotes give no rate either, the price may have moved since.
+        if is_pair_enabled && !price_update.is_stale() {
+            // Rates after paying the exchange trading fees, decayed by the quote age.
+            let decay = self.get_decay_factor(price_update);
+            let trade_factor = self.get_trade_factor(price_update.get_exchange()) * decay;
 
             // Add forward edge.
             self.graph
-                .add_edge(a, b, *price_update.get_forward_factor());
+                .add_edge(a, b, price_update.get_forward_factor() * trade_factor);
             // Add backward edge.
             self.graph
-                .add_edge(b, a, *price_update.get_backward_factor());
+                .add_edge(b, a, price_update.get_backward_factor() * trade_factor);
 
-            // Collect provided currencies.
-            self.collect_currency_exchanges(source_currency_index, exchange_index);
-            self.collect_currency_exchanges(destination_currency_index, exchange_index);
+            if self.quote_decay.is_some() {
+                self.decays.insert((a, b), decay);
+                self.decays.insert((b, a), decay);
+            }
         }
 
-        // For each currency add edges, so that each `(exchange, currency)` is connected to every
-        // other `(other_exchange, currency)` with an edge weight of 1.0.
-        self.add_currency_exchanges_edges();
+        // Collect provided exchange and currencies.
+        self.exchanges.insert(exchange_index);
+        let mut currencies = Vec::new();
+        for currency in [source_currency_index, destination_currency_index].iter() {
+            if self.collect_currency_exchanges(*currency, exchange_index) {
+                currencies.push(*currency);
+            }
+        }
+
+        currencies
+    }
+
+    /// Get the factor edges of the `PriceUpdate` are decayed by for the age of its quote, see
+    /// `Settings::set_quote_half_life`.
+    fn get_decay_factor(&self, price_update: &PriceUpdate<N, E>) -> E {
+        let (half_life, now) = match self.quote_decay {
+            Some(quote_decay) => quote_decay,
+            None => return E::one(),
+        };
+
+        let parse = |value: i64| {
+            value
+                .to_string()
+                .parse::<E>()
+                .expect("A count of milliseconds is a valid edge weight!")
+        };
+        let half_life = half_life * parse(1000);
+        let age = parse(
+            (now - *price_update.get_timestamp())
+                .num_milliseconds()
+                .max(0),
+        );
+
+        half_life / (half_life + age)
     }
 
-    fn collect_currency_exchanges(&mut self, currency: I, exchange: I) {
+    /// Get the factor a traded amount is multiplied by after paying the exchange fees.
+    fn get_trade_factor(&self, exchange: &N) -> E {
+        let fee_model = self.settings.get_fee_model();
+
+        if fee_model.is_empty() {
+            E::one()
+        } else {
+            fee_model.get_trade_factor(exchange, self.execution_style)
+        }
+    }
+
+    /// Collect the exchange of the currency, return whether it wasn't collected before.
+    fn collect_currency_exchanges(&mut self, currency: I, exchange: I) -> bool {
         match self.currency_exchanges.entry(currency) {
             // Return the index for existing entry.
             Entry::Occupied(o) => {
                 // Insert the provided exchange.
-                o.into_mut().insert(exchange);
+                o.into_mut().insert(exchange)
             }
             // Insert a new `IndexSet`.
             Entry::Vacant(v) => {
                 // Prepare a new `IndexSet` with the provided exchange.
-                let mut exchanges = IndexSet::<I>::with_capacity(1);
+                let mut exchanges = IndexSet::with_capacity_and_hasher(1, Default::default());
                 exchanges.insert(exchange);
 
                 // Insert the new `IndexSet`.
                 v.insert(exchanges);
+                true
             }
         }
     }
 
     fn add_currency_exchanges_edges(&mut self) {
         // Loop through all currencies.
-        for (currency, exchanges) in self.currency_exchanges.iter() {
+        let currencies: Vec<I> = self.currency_exchanges.keys().copied().collect();
+        for currency in currencies {
+            self.add_currency_edges(currency);
+        }
+    }
+
+    /// Add edges between all `(exchange, currency)` nodes of the currency, the same edges are
+    /// added again if it's called again.
+    fn add_currency_edges(&mut self, currency: I) {
+        let transfer_model = self.settings.get_transfer_model();
+        let transfer_penalty = self.transfer_penalty;
+        let index_to_node = &self.index_to_node;
+
+        if let Some(exchanges) = self.currency_exchanges.get(&currency) {
+            let currency = &currency;
+            let currency_node = index_to_node.get(currency);
+
+            // Skip currencies which can't be transferred between exchanges.
+            if !currency_node.is_none_or(|currency| transfer_model.is_transfer_enabled(currency)) {
+                return;
+            }
+
+            // Get the transfer edge weight including withdrawal and deposit fees, `None` if the
+            // route is disabled.
+            let get_bridge_factor = |source: &I, destination: &I| {
+                match (
+                    index_to_node.get(source),
+                    index_to_node.get(destination),
+                    currency_node,
+                ) {
+                    (Some(source), Some(destination), Some(currency)) => {
+                        if transfer_model.is_route_enabled(source, destination, currency) {
+                            Some(transfer_model.get_bridge_factor(source, destination, currency))
+                        } else {
+                            None
+                        }
+                    }
+                    // Nodes without names have no own transfer settings.
+                    _ => Some(E::one() - transfer_model.get_default_fee()),
+                }
+                .map(|factor| factor * transfer_penalty)
+            };
+
             let exchanges_count = exchanges.len();
 
             // Loop through exchanges of the current currency.
@@ -120,13 +923,20 @@ where
                 // Loop through all exchanges of the current currency following the previous
                 // top exchange.
                 for below in top + 1..exchanges_count {
-                    let a = (*exchanges.get_index(top).unwrap(), *currency);
-                    let b = (*exchanges.get_index(below).unwrap(), *currency);
+                    let top_exchange = exchanges.get_index(top).unwrap();
+                    let below_exchange = exchanges.get_index(below).unwrap();
+
+                    let a = (*top_exchange, *currency);
+                    let b = (*below_exchange, *currency);
 
                     // Add forward edge.
-                    self.graph.add_edge(a, b, E::one());
+                    if let Some(factor) = get_bridge_factor(top_exchange, below_exchange) {
+                        self.graph.add_edge(a, b, factor);
+                    }
                     // Add backward edge.
-                    self.graph.add_edge(b, a, E::one());
+                    if let Some(factor) = get_bridge_factor(below_exchange, top_exchange) {
+                        self.graph.add_edge(b, a, factor);
+                    }
                 }
             }
         }
@@ -153,6 +963,13 @@ where
         }
     }
 
+    /// Get index of the provided node `N` without indexing it.
+    ///
+    /// Return `None` if the `N` is not yet indexed.
+    fn get_node_index(&self, s: &N) -> Option<I> {
+        self.node_to_index.get(s).copied()
+    }
+
     /// Get node `N` for the provided index.
     ///
     /// Return `Option<n>` as it is possible that there's no `N` with the index.
@@ -160,74 +977,404 @@ where
         self.index_to_node.get(i)
     }
 
-    fn run_customized_floyd_warshall(&mut self) -> FloydWarshallResult<(I, I), E> {
-        let mul = Box::new(|x: E, y: E| x * y);
-        let sharp_greater = Box::new(|x: E, y: E| x.partial_cmp(&y).unwrap_or(Less) == Greater);
+    /// Get the `Solver` of the best rate paths between all nodes, chosen by the `Settings`.
+    fn get_solver(&self) -> Box<dyn Solver<I, E>> {
+        // The `BellmanFord` backend searches paths per request, the result it falls back to for
+        // arbitrages is solved by Floyd-Warshall as well.
+        match self.settings.get_backend() {
+            Backend::FloydWarshall | Backend::BellmanFord => Box::new(
+                FloydWarshallSolver::new(
+                    *self.settings.get_comparison_epsilon(),
+                    self.settings.get_discard_loops(),
+                )
+                .with_semantics(self.settings.get_path_semantics())
+                .with_progress_reporter(self.settings.get_progress_reporter().cloned()),
+            ),
+        }
+    }
+
+    /// Solve the best rate paths between all nodes by the `Solver` of the `Settings`, `None` if
+    /// the computation is cancelled meanwhile.
+    fn run_solver(&self, cancellation: &Cancellation) -> Option<PathsResult<I, E>> {
+        let _span = trace::span("find_paths");
+        let _phase = memory::phase("find_paths");
+        self.get_solver().solve(&self.graph, cancellation)
+    }
+
+    /// Solve the paths right away for the `FloydWarshall` backend, the `BellmanFord` one leaves
+    /// it to the first use of the result. `None` is returned if the computation is cancelled.
+    fn solve_paths(&self, cancellation: &Cancellation) -> Option<LazyPaths<I, E>> {
+        match self.settings.get_backend() {
+            Backend::FloydWarshall => self.run_solver(cancellation).map(OnceLock::from),
+            Backend::BellmanFord => Some(OnceLock::new()),
+        }
+    }
+
+    /// Solve the paths like `solve_paths`, without any cancellation.
+    fn solve_paths_uncancellable(&self) -> LazyPaths<I, E> {
+        self.solve_paths(&Cancellation::new())
+            .expect("The computation without cancellation can't be cancelled!")
+    }
+
+    /// Get the result of all paths, solving it if it's not solved yet.
+    fn get_paths<'a>(&self, paths: &'a LazyPaths<I, E>) -> &'a PathsResult<I, E> {
+        paths.get_or_init(|| {
+            self.run_solver(&Cancellation::new())
+                .expect("The computation without cancellation can't be cancelled!")
+        })
+    }
+
+    /// Find out why there is no path between `(exchange, currency)` nodes of a request.
+    ///
+    /// Unknown exchanges and currencies are checked before concluding there's simply no path.
+    fn find_unanswered_reason(&self, source: (&N, &N), destination: (&N, &N)) -> Reason {
+        let (source_exchange, source_currency) = source;
+        let (destination_exchange, destination_currency) = destination;
+
+        if !self.is_known_exchange(source_exchange) {
+            UnknownSourceExchange
+        } else if !self.is_known_currency(source_exchange, source_currency) {
+            UnknownSourceCurrency
+        } else if !self.is_known_exchange(destination_exchange) {
+            UnknownDestinationExchange
+        } else if !self.is_known_currency(destination_exchange, destination_currency) {
+            UnknownDestinationCurrency
+        } else {
+            NoPath
+        }
+    }
+
+    /// Check that the exchange was provided by any `PriceUpdate`.
+    fn is_known_exchange(&self, exchange: &N) -> bool {
+        self.get_node_index(exchange)
+            .map(|index| self.exchanges.contains(&index))
+            .unwrap_or(false)
+    }
 
-        let alg: FloydWarshall<E> = FloydWarshall::new_customized(mul, sharp_greater);
-        alg.find_paths(&self.graph)
+    /// Check that the currency was provided by any `PriceUpdate` of the exchange.
+    fn is_known_currency(&self, exchange: &N, currency: &N) -> bool {
+        match (self.get_node_index(exchange), self.get_node_index(currency)) {
+            (Some(exchange_index), Some(currency_index)) => self
+                .currency_exchanges
+                .get(&currency_index)
+                .map(|exchanges| exchanges.contains(&exchange_index))
+                .unwrap_or(false),
+            _ => false,
+        }
     }
 
+    /// Form the `Response` answering the `ExchangeRateRequest`s.
+    ///
+    /// Requests of maker orders are answered by the `maker` algorithm and its Floyd-Warshall
+    /// result if provided, the taker (`self`) ones are used otherwise. Requests left once the
+    /// computation is cancelled are unanswered.
     fn form_response(
-        &mut self,
-        request: &Request<N, E>,
-        fw_result: &FloydWarshallResult<(I, I), E>,
+        &self,
+        rate_requests: &[&ExchangeRateRequest<N, E>],
+        paths: &LazyPaths<I, E>,
+        maker: Option<(&Self, &LazyPaths<I, E>)>,
+        cancellation: &Cancellation,
     ) -> Response<N, E> {
+        let _span = trace::span("form_response");
+        let _phase = memory::phase("form_response");
         let mut response = Response::new();
 
-        // Process all `PriceUpdates`.
-        for (_, rate_request) in request.get_rate_requests().iter() {
-            // Prepare indexes.
-            let source_exchange_index =
-                self.node_to_index(rate_request.get_source_exchange().clone());
-            let source_currency_index =
-                self.node_to_index(rate_request.get_source_currency().clone());
-            let destination_exchange_index =
-                self.node_to_index(rate_request.get_destination_exchange().clone());
-            let destination_currency_index =
-                self.node_to_index(rate_request.get_destination_currency().clone());
-
-            // Get star and end node.
-            let a = (source_exchange_index, source_currency_index);
-            let b = (destination_exchange_index, destination_currency_index);
-
-            // Prepare `BestRatePath`.
-            let rate_raw = fw_result.get_path_rate(a, b);
-            let path = fw_result.collect_path_nodes(a, b);
-
-            // Re-map path from indexes `I` to nodes `N`.
-            let path = path
-                .into_iter()
-                .map(|(a, b)| {
-                    (
-                        self.index_to_node(&a).unwrap().clone(),
-                        self.index_to_node(&b).unwrap().clone(),
-                    )
-                })
-                .collect();
+        // Process all `ExchangeRateRequest`s.
+        for (index, rate_request) in rate_requests.iter().enumerate() {
+            if cancellation.is_cancelled() {
+                response.extend(form_cancelled_response(&rate_requests[index..]));
+                break;
+            }
 
-            #[allow(clippy::single_match)]
-            match rate_raw {
-                Some(&rate) => {
-                    let best_rate_path = BestRatePath::<N, E>::new(rate, path);
-                    response.add_best_rate_path(best_rate_path);
+            let (alg, paths) = match (rate_request.get_execution_style(), maker) {
+                (ExecutionStyle::Maker, Some(maker)) => maker,
+                _ => (self, paths),
+            };
+
+            alg.answer_rate_request(rate_request, paths, &mut response);
+        }
+
+        response
+    }
+
+    /// Add the best rate path of the `ExchangeRateRequest` or the reason of missing it.
+    fn answer_rate_request(
+        &self,
+        rate_request: &ExchangeRateRequest<N, E>,
+        paths: &LazyPaths<I, E>,
+        response: &mut Response<N, E>,
+    ) {
+        let source = (
+            rate_request.get_source_exchange(),
+            rate_request.get_source_currency(),
+        );
+        let destination = (
+            rate_request.get_destination_exchange(),
+            rate_request.get_destination_currency(),
+        );
+
+        // Past rates are answered from the history of price updates, there is none here.
+        if rate_request.get_as_of().is_some() {
+            response.add_unanswered_request(UnansweredRequest::new(
+                (source.0.clone(), source.1.clone()),
+                (destination.0.clone(), destination.1.clone()),
+                NoHistory,
+            ));
+            return;
+        }
+
+        // A known node converts to itself by the single-node path of rate 1.
+        if source == destination && self.find_unanswered_reason(source, destination) == NoPath {
+            response.add_best_rate_path(BestRatePath::new(
+                E::one(),
+                vec![(source.0.clone(), source.1.clone())],
+            ));
+            return;
+        }
+
+        // Rates of converting an amount depend on its size.
+        let best_rate_path = match (rate_request.get_time_budget(), rate_request.get_amount()) {
+            (Some(time_budget), amount) => {
+                self.find_best_timed_path(source, destination, amount.copied(), time_budget)
+            }
+            (None, Some(amount)) => self.find_best_amount_path(source, destination, *amount),
+            (None, None) => self.find_best_marginal_path(
+                source,
+                destination,
+                paths,
+                self.settings.get_max_hops(),
+            ),
+        };
+
+        match best_rate_path {
+            Some(best_rate_path) => response.add_best_rate_path(best_rate_path),
+            None => {
+                let mut reason = self.find_unanswered_reason(source, destination);
+                let is_limited = rate_request.get_time_budget().is_some()
+                    || (rate_request.get_amount().is_none()
+                        && self.settings.get_max_hops().is_some());
+                if reason == NoPath
+                    && is_limited
+                    && self
+                        .find_best_marginal_path(source, destination, paths, None)
+                        .is_some()
+                {
+                    reason = if rate_request.get_time_budget().is_some() {
+                        NoPathWithinTime
+                    } else {
+                        NoPathWithinHops
+                    };
                 }
-                None => {
-                    // It would be probably good to include information about non-existing
-                    // Rate request as a part of `Response` or at least log it.
+
+                let unanswered_request = UnansweredRequest::new(
+                    (source.0.clone(), source.1.clone()),
+                    (destination.0.clone(), destination.1.clone()),
+                    reason,
+                );
+                response.add_unanswered_request(unanswered_request);
+            }
+        }
+    }
+
+    /// Find `BestRatePath` of the marginal rate between `(exchange, currency)` nodes, of at most
+    /// `max_hops` edges if given, by the backend of the `Settings`.
+    fn find_best_marginal_path(
+        &self,
+        source: (&N, &N),
+        destination: (&N, &N),
+        paths: &LazyPaths<I, E>,
+        max_hops: Option<usize>,
+    ) -> Option<BestRatePath<N, E>> {
+        // A simple path has less edges than the count of nodes.
+        let all_hops = self.graph.node_count().saturating_sub(1);
+
+        match self.settings.get_backend() {
+            Backend::FloydWarshall => {
+                let best_rate_path =
+                    self.find_best_rate_path(source, destination, self.get_paths(paths));
+                match (best_rate_path, max_hops) {
+                    // The best path of all is the best one within the limit as well.
+                    (Some(best_rate_path), Some(max_hops))
+                        if best_rate_path.get_path().len() > max_hops + 1 =>
+                    {
+                        self.find_best_hop_path(source, destination, max_hops)
+                    }
+                    (best_rate_path, _) => best_rate_path,
                 }
             }
+            Backend::BellmanFord => {
+                self.find_best_hop_path(source, destination, max_hops.unwrap_or(all_hops))
+            }
         }
+    }
 
-        response
+    /// Find `BestRatePath` between `(exchange, currency)` nodes in the Floyd-Warshall result.
+    fn find_best_rate_path(
+        &self,
+        source: (&N, &N),
+        destination: (&N, &N),
+        fw_result: &PathsResult<I, E>,
+    ) -> Option<BestRatePath<N, E>> {
+        // Prepare indexes, not known nodes can't have any path.
+        let a = (
+            self.get_node_index(source.0)?,
+            self.get_node_index(source.1)?,
+        );
+        let b = (
+            self.get_node_index(destination.0)?,
+            self.get_node_index(destination.1)?,
+        );
+
+        let rate = *fw_result.get_path_rate(a, b)?;
+        let path = fw_result.collect_path_nodes(a, b);
+
+        Some(self.form_best_rate_path(rate, path))
+    }
+
+    /// Form the `BestRatePath` of the path of indexes, its rate and edge rates without the
+    /// transfer penalty and the quote decay the path was chosen with.
+    fn form_best_rate_path(&self, rate: E, path: Vec<(I, I)>) -> BestRatePath<N, E> {
+        let rates = self
+            .get_edge_rates(&path)
+            .unwrap_or_default()
+            .into_iter()
+            .zip(path.windows(2))
+            .map(|(rate, edge)| {
+                if edge[0].0 != edge[1].0 {
+                    rate / self.transfer_penalty
+                } else {
+                    rate / self.get_edge_decay(edge[0], edge[1])
+                }
+            })
+            .collect();
+
+        let mut best_rate_path = BestRatePath::new(
+            self.remove_quote_decay(self.remove_transfer_penalty(rate, &path), &path),
+            self.index_path_to_nodes(path),
+        );
+        best_rate_path.set_rates(rates);
+        best_rate_path
+    }
+
+    /// Get the actual rate of the path, without the transfer penalty the path was chosen with.
+    fn remove_transfer_penalty(&self, rate: E, path: &[(I, I)]) -> E {
+        path.windows(2)
+            .filter(|edge| edge[0].0 != edge[1].0)
+            .fold(rate, |rate, _| rate / self.transfer_penalty)
+    }
+
+    /// Get the actual rate of the path, without the decay of its quotes it was chosen with.
+    fn remove_quote_decay(&self, rate: E, path: &[(I, I)]) -> E {
+        path.windows(2).fold(rate, |rate, edge| {
+            rate / self.get_edge_decay(edge[0], edge[1])
+        })
+    }
+
+    /// Get the factor the edge was decayed by, `1` for transfer edges.
+    fn get_edge_decay(&self, a: (I, I), b: (I, I)) -> E {
+        self.decays.get(&(a, b)).copied().unwrap_or_else(E::one)
+    }
+
+    /// Re-map path from indexes `I` to nodes `N`.
+    fn index_path_to_nodes(&self, path: Vec<(I, I)>) -> Vec<(N, N)> {
+        path.into_iter()
+            .map(|(a, b)| {
+                (
+                    self.index_to_node(&a).unwrap().clone(),
+                    self.index_to_node(&b).unwrap().clone(),
+                )
+            })
+            .collect()
+    }
+}
+
+impl<N, E, I> Algorithm<N, E, I>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + Sync,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + Sync + 'static,
+    <E as FromStr>::Err: Debug,
+    I: NodeTrait + Num + AddAssign + Send + Sync,
+{
+    /// Process the `Request` using customized `Settings`, answering large batches of
+    /// `ExchangeRateRequest`s by multiple threads.
+    pub fn process_parallel(request: &Request<N, E>, settings: &Settings<N, E>) -> Response<N, E> {
+        Self::process_with(request, settings, Solution::answer_parallel)
+    }
+}
+
+/// Form the `Response` of the `ExchangeRateRequest`s left unanswered by a cancelled
+/// computation.
+fn form_cancelled_response<N, E>(rate_requests: &[&ExchangeRateRequest<N, E>]) -> Response<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    let mut response = Response::new();
+
+    for rate_request in rate_requests {
+        response.add_unanswered_request(UnansweredRequest::new(
+            (
+                rate_request.get_source_exchange().clone(),
+                rate_request.get_source_currency().clone(),
+            ),
+            (
+                rate_request.get_destination_exchange().clone(),
+                rate_request.get_destination_currency().clone(),
+            ),
+            Reason::Cancelled,
+        ));
     }
+
+    response
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::algorithm::cancellation::Cancellation;
+    use crate::algorithm::progress::ProgressReporter;
+    use crate::algorithm::settings::Settings;
     use crate::algorithm::Algorithm;
+    use crate::request::price_update::PriceUpdate;
     use crate::request::Request;
+    use crate::response::unanswered_request::Reason::*;
     use std::io::BufReader;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// Prepare a `Request` of price updates of the number of exchanges between the number of
+    /// currencies, with rate requests between all pairs of their nodes.
+    fn prepare_large_request(exchanges: usize, currencies: usize) -> Request<String, f32> {
+        let mut text_input = String::new();
+        for exchange in 0..exchanges {
+            for currency in 1..currencies {
+                text_input.push_str(&format!(
+                    "2019-01-20T09:42:23+00:00 E{} C{} C{} {} 0.001\n",
+                    exchange,
+                    currency - 1,
+                    currency,
+                    1.0 + (exchange * currency % 7) as f32 / 100.0
+                ));
+            }
+        }
+        let count = exchanges * currencies;
+        let nodes =
+            (0..count).flat_map(|source| (0..count).map(move |destination| (source, destination)));
+        for (source, destination) in nodes.filter(|(source, destination)| source != destination) {
+            text_input.push_str(&format!(
+                "EXCHANGE_RATE_REQUEST E{} C{} E{} C{}\n",
+                source / currencies,
+                source % currencies,
+                destination / currencies,
+                destination % currencies
+            ));
+        }
+
+        Request::read_from(&mut BufReader::new(text_input.as_bytes()))
+    }
 
     #[test]
     fn new() {
@@ -282,9 +1429,9 @@ mod tests {
         assert_eq!(alg.graph.edge_weight((4, 1), (3, 1)), Some(&1.0));
 
         // Test edges non-existence.
-        assert_eq!(alg.graph.contains_edge((2, 1), (6, 1)), false);
-        assert_eq!(alg.graph.contains_edge((6, 1), (2, 1)), false);
-        assert_eq!(alg.graph.contains_edge((6, 5), (2, 5)), false);
+        assert!(!alg.graph.contains_edge((2, 1), (6, 1)));
+        assert!(!alg.graph.contains_edge((6, 1), (2, 1)));
+        assert!(!alg.graph.contains_edge((6, 5), (2, 5)));
     }
 
     #[test]
@@ -366,42 +1513,306 @@ mod tests {
         );
 
         // Test USD edges non-existence.
+        assert!(!alg
+            .graph
+            .contains_edge((e1_index, usd_index), (e3_index, usd_index)));
+        assert!(!alg
+            .graph
+            .contains_edge((e3_index, usd_index), (e1_index, usd_index)));
+        assert!(!alg
+            .graph
+            .contains_edge((e2_index, usd_index), (e3_index, usd_index)));
+        assert!(!alg
+            .graph
+            .contains_edge((e3_index, usd_index), (e2_index, usd_index)));
+
+        // Test BTC edges existence.
         assert_eq!(
             alg.graph
-                .contains_edge((e1_index, usd_index), (e3_index, usd_index)),
-            false
+                .edge_weight((e1_index, btc_index), (e3_index, btc_index)),
+            Some(&1.0)
+        );
+        assert_eq!(
+            alg.graph
+                .edge_weight((e3_index, btc_index), (e1_index, btc_index)),
+            Some(&1.0)
+        );
+    }
+
+    #[test]
+    fn update_graph() {
+        let lines = vec![
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.0009",
+            "2019-01-20T09:42:23+00:00 E2 ETH USD 100.0 0.001",
+        ];
+        let mut request = Request::<String, f32>::from_lines(lines.clone());
+        let mut alg = Algorithm::<String, f32, u32>::from_request(&request, &Settings::new());
+
+        // Test that the updated graph equals the graph constructed from all price updates.
+        let updates = [
+            "2019-01-20T09:42:24+00:00 E1 BTC USD 1100.0 0.0008",
+            "2019-01-20T09:42:24+00:00 E2 BTC EUR 900.0 0.001",
+            "2019-01-20T09:42:24+00:00 E3 EUR USD 1.1 0.9",
+        ];
+        for line in updates.iter() {
+            request.add_price_update(PriceUpdate::parse_line(line).unwrap());
+        }
+        let price_updates: Vec<_> = request.get_price_updates().values().collect();
+        assert!(alg.update_graph(&price_updates));
+
+        let expected = Algorithm::<String, f32, u32>::from_request(
+            &Request::from_lines(lines.iter().chain(updates.iter())),
+            &Settings::new(),
+        );
+        let edges = |alg: &Algorithm<String, f32, u32>| {
+            let mut edges: Vec<_> = alg
+                .graph
+                .all_edges()
+                .map(|(a, b, weight)| (alg.index_path_to_nodes(vec![a, b]), *weight))
+                .map(|(nodes, weight)| format!("{:?} {}", nodes, weight))
+                .collect();
+            edges.sort();
+            edges
+        };
+        assert_eq!(edges(&alg), edges(&expected));
+
+        // Test that a stale price update of edges in the graph can't be applied.
+        let mut price_update = PriceUpdate::parse_line(updates[0]).unwrap();
+        price_update.set_stale(true);
+        assert!(!alg.update_graph(&[&price_update]));
+    }
+
+    #[test]
+    fn update_solution() {
+        let mut request = Request::<String, f32>::from_lines(vec![
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.0009",
+            "2019-01-20T09:42:23+00:00 E2 ETH USD 100.0 0.001",
+            "EXCHANGE_RATE_REQUEST E1 BTC E2 ETH",
+        ]);
+        let settings = Settings::new();
+        let mut solution = Algorithm::<String, f32, u32>::solve(&request, &settings, false);
+
+        let price_update = PriceUpdate::parse_line("2019-01-20T09:42:24+00:00 E2 BTC ETH 12 0.08");
+        request.add_price_update(price_update.unwrap());
+        let index = (String::from("E2"), String::from("BTC"), String::from("ETH"));
+        assert!(solution.update(&[&request.get_price_updates()[&index]]));
+
+        // Test that the updated solution answers the same as one solved from scratch.
+        assert_eq!(
+            solution.answer(&request).get_output(),
+            Algorithm::<String, f32, u32>::process_customized(&request, &settings).get_output()
+        );
+        assert!(solution
+            .answer(&request)
+            .get_output()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E2> <ETH> <12>"));
+    }
+
+    #[test]
+    fn dump_indexes() {
+        let request = Request::<String, f32>::from_lines(vec![
+            "2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001",
+            "2019-01-20T09:42:23+00:00 GDAX BTC EUR 900 0.0011",
+        ]);
+        let alg = Algorithm::<String, f32, u32>::from_request(&request, &Settings::new());
+
+        assert_eq!(
+            alg.dump_indexes(),
+            "INDEXES_BEGIN
+NODE_TO_INDEX <KRAKEN> <1>
+NODE_TO_INDEX <BTC> <2>
+NODE_TO_INDEX <USD> <3>
+NODE_TO_INDEX <GDAX> <4>
+NODE_TO_INDEX <EUR> <5>
+INDEX_TO_NODE <1> <KRAKEN>
+INDEX_TO_NODE <2> <BTC>
+INDEX_TO_NODE <3> <USD>
+INDEX_TO_NODE <4> <GDAX>
+INDEX_TO_NODE <5> <EUR>
+CURRENCY_EXCHANGES <2, BTC> <1, KRAKEN> <4, GDAX>
+CURRENCY_EXCHANGES <3, USD> <1, KRAKEN>
+CURRENCY_EXCHANGES <5, EUR> <4, GDAX>
+INDEXES_END
+"
         );
+    }
+
+    #[test]
+    fn construct_graph_with_fees() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.get_fee_model_mut().set_default_fee(0.5);
+        settings
+            .get_fee_model_mut()
+            .set_exchange_fee("E2".to_string(), 0.0);
+
+        let mut alg = Algorithm::<String, f32, u32>::new_customized(settings);
+
+        let text_input = "2017-11-01T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2017-11-01T09:42:23+00:00 E2 BTC USD 1000.0 0.001"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        alg.construct_graph(&request);
+
+        let e1_index = alg.node_to_index("E1".to_string());
+        let e2_index = alg.node_to_index("E2".to_string());
+        let btc_index = alg.node_to_index("BTC".to_string());
+        let usd_index = alg.node_to_index("USD".to_string());
+
+        // Test that the fee is applied to both trade edges of the exchange.
         assert_eq!(
             alg.graph
-                .contains_edge((e3_index, usd_index), (e1_index, usd_index)),
-            false
+                .edge_weight((e1_index, btc_index), (e1_index, usd_index)),
+            Some(&500.0)
         );
         assert_eq!(
             alg.graph
-                .contains_edge((e2_index, usd_index), (e3_index, usd_index)),
-            false
+                .edge_weight((e1_index, usd_index), (e1_index, btc_index)),
+            Some(&0.0005)
         );
+
+        // Test that the exchange fee overrides the default one.
         assert_eq!(
             alg.graph
-                .contains_edge((e3_index, usd_index), (e2_index, usd_index)),
-            false
+                .edge_weight((e2_index, btc_index), (e2_index, usd_index)),
+            Some(&1000.0)
         );
 
-        // Test BTC edges existence.
+        // Test that no fee is applied to transfer edges.
         assert_eq!(
             alg.graph
-                .edge_weight((e1_index, btc_index), (e3_index, btc_index)),
+                .edge_weight((e1_index, btc_index), (e2_index, btc_index)),
             Some(&1.0)
         );
+    }
+
+    #[test]
+    fn construct_graph_with_transfer_model() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.get_transfer_model_mut().set_default_fee(0.5);
+        settings
+            .get_transfer_model_mut()
+            .set_transfer_enabled("USD".to_string(), false);
+
+        let mut alg = Algorithm::<String, f32, u32>::new_customized(settings);
+
+        let text_input = "2017-11-01T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2017-11-01T09:42:23+00:00 E2 BTC USD 1000.0 0.001"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        alg.construct_graph(&request);
+
+        let e1_index = alg.node_to_index("E1".to_string());
+        let e2_index = alg.node_to_index("E2".to_string());
+        let btc_index = alg.node_to_index("BTC".to_string());
+        let usd_index = alg.node_to_index("USD".to_string());
+
+        // Test that the transfer fee is applied to transfer edges.
         assert_eq!(
             alg.graph
-                .edge_weight((e3_index, btc_index), (e1_index, btc_index)),
+                .edge_weight((e1_index, btc_index), (e2_index, btc_index)),
+            Some(&0.5)
+        );
+
+        // Test that withdrawal and deposit fees are applied in the transfer direction.
+        let mut settings = Settings::<String, f32>::new();
+        settings.get_transfer_model_mut().set_withdrawal_fee(
+            "E1".to_string(),
+            "BTC".to_string(),
+            0.5,
+        );
+
+        let mut alg_with_schedule = Algorithm::<String, f32, u32>::new_customized(settings);
+        alg_with_schedule.construct_graph(&request);
+
+        assert_eq!(
+            alg_with_schedule
+                .graph
+                .edge_weight((e1_index, btc_index), (e2_index, btc_index)),
+            Some(&0.5)
+        );
+        assert_eq!(
+            alg_with_schedule
+                .graph
+                .edge_weight((e2_index, btc_index), (e1_index, btc_index)),
             Some(&1.0)
         );
+
+        // Test that disabled currency transfer edges don't exist.
+        assert!(!alg
+            .graph
+            .contains_edge((e1_index, usd_index), (e2_index, usd_index)));
+
+        // Test that only the disabled direction of a route doesn't exist.
+        let mut settings = Settings::<String, f32>::new();
+        settings.get_transfer_model_mut().set_route_enabled(
+            "E1".to_string(),
+            "E2".to_string(),
+            "BTC".to_string(),
+            false,
+        );
+
+        let mut alg_with_route = Algorithm::<String, f32, u32>::new_customized(settings);
+        alg_with_route.construct_graph(&request);
+
+        assert!(!alg_with_route
+            .graph
+            .contains_edge((e1_index, btc_index), (e2_index, btc_index)));
+        assert!(alg_with_route
+            .graph
+            .contains_edge((e2_index, btc_index), (e1_index, btc_index)));
+    }
+
+    #[test]
+    fn construct_graph_with_trading_rules() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.get_trading_rules_mut().set_pair_enabled(
+            "E1".to_string(),
+            "USD".to_string(),
+            "BTC".to_string(),
+            false,
+        );
+
+        let mut alg = Algorithm::<String, f32, u32>::new_customized(settings);
+
+        let text_input = "2017-11-01T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2017-11-01T09:42:23+00:00 E2 BTC USD 1000.0 0.001
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        alg.construct_graph(&request);
+
+        let e1_index = alg.node_to_index("E1".to_string());
+        let e2_index = alg.node_to_index("E2".to_string());
+        let btc_index = alg.node_to_index("BTC".to_string());
+        let usd_index = alg.node_to_index("USD".to_string());
+
+        // Test that trade edges of the disabled pair don't exist in either direction.
+        assert!(!alg
+            .graph
+            .contains_edge((e1_index, btc_index), (e1_index, usd_index)));
+        assert!(!alg
+            .graph
+            .contains_edge((e1_index, usd_index), (e1_index, btc_index)));
+        assert!(alg
+            .graph
+            .contains_edge((e2_index, btc_index), (e2_index, usd_index)));
+
+        // Test that the path goes through the exchange allowing the trade.
+        let response = Algorithm::<String, f32, u32>::process_customized(&request, &alg.settings);
+        assert_eq!(response.get_best_rate_paths()[0].get_path().len(), 4);
     }
 
     #[test]
-    fn run_customized_floyd_warshall() {
+    fn run_solver() {
         let mut alg = Algorithm::<String, f32, u32>::new();
 
         let text_input = "2017-11-01T09:42:23+00:00 E1 BTC USD 1000.0 0.0009
@@ -415,7 +1826,7 @@ mod tests {
         let request = Request::<String, f32>::read_from(&mut input);
 
         alg.construct_graph(&request);
-        let result = alg.run_customized_floyd_warshall();
+        let result = alg.run_solver(&Cancellation::new()).unwrap();
 
         // Exchanges.
         let e1 = String::from("E1");
@@ -533,5 +1944,312 @@ EXCHANGE_RATE_REQUEST CoinBene ETH BiBox USD"
         );
 
         // &117.51
+
+        // Test that the graph build and the solving are timed, the parsing is left to the caller.
+        let phase_timings = response.get_phase_timings();
+        assert!(phase_timings.get_graph_build_time().is_some());
+        assert!(phase_timings.get_solve_time().is_some());
+        assert_eq!(phase_timings.get_parse_time(), None);
+    }
+
+    #[test]
+    fn process_unanswered_requests() {
+        let text_input = "2019-01-20T09:42:23+00:00 KRAKEN BTC USD 3531.0 0.00026
+2019-01-20T09:42:23+00:00 GDAX ETH EUR 117.36 0.0075
+
+EXCHANGE_RATE_REQUEST BINANCE BTC KRAKEN USD
+EXCHANGE_RATE_REQUEST KRAKEN ETH KRAKEN USD
+EXCHANGE_RATE_REQUEST KRAKEN BTC BINANCE USD
+EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX USD
+EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX EUR"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let response = Algorithm::<String, f32, u32>::process(&request);
+
+        // Test that none of the requests was answered.
+        assert!(response.get_best_rate_paths().is_empty());
+
+        // Test reasons of all unanswered requests.
+        let reasons: Vec<_> = response
+            .get_unanswered_requests()
+            .iter()
+            .map(|unanswered_request| unanswered_request.get_reason())
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                UnknownSourceExchange,
+                UnknownSourceCurrency,
+                UnknownDestinationExchange,
+                UnknownDestinationCurrency,
+                NoPath
+            ]
+        );
+    }
+
+    #[test]
+    fn process_identity_requests() {
+        let text_input = "2019-01-20T09:42:23+00:00 E0 C0 C1 2.0 0.4
+
+EXCHANGE_RATE_REQUEST E0 C0 E0 C0
+EXCHANGE_RATE_REQUEST E0 C1 E0 C1 2.5
+EXCHANGE_RATE_REQUEST E1 C0 E1 C0"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let response = Algorithm::<String, f32, u32>::process(&request);
+
+        // Test that known nodes convert to themselves by the single-node path of rate 1.
+        let best_rate_paths = response.get_best_rate_paths();
+        assert_eq!(best_rate_paths.len(), 2);
+        assert_eq!(*best_rate_paths[0].get_rate(), 1.0);
+        assert_eq!(
+            best_rate_paths[0].get_path(),
+            &[("E0".to_string(), "C0".to_string())]
+        );
+        assert_eq!(*best_rate_paths[1].get_rate(), 1.0);
+        assert_eq!(
+            best_rate_paths[1].get_path(),
+            &[("E0".to_string(), "C1".to_string())]
+        );
+
+        // Test that an unknown node is still unanswered by its reason.
+        let reasons: Vec<_> = response
+            .get_unanswered_requests()
+            .iter()
+            .map(|unanswered_request| unanswered_request.get_reason())
+            .collect();
+        assert_eq!(reasons, vec![UnknownSourceExchange]);
+    }
+
+    #[test]
+    fn process_with_maker_fees() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.get_fee_model_mut().set_default_fee(0.5);
+        settings
+            .get_fee_model_mut()
+            .set_default_maker_fee(Some(0.0));
+
+        let text_input = "2017-11-01T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD
+EXCHANGE_RATE_REQUEST E1 USD E1 BTC MAKER"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+        let rates: Vec<f32> = response.iter().map(|path| *path.get_rate()).collect();
+
+        // Test that the taker request pays the taker fee and the maker one the maker fee.
+        assert_eq!(rates, vec![500.0, 0.001]);
+    }
+
+    #[test]
+    fn process_with_transfer_penalty() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.set_transfer_penalty(0.5);
+
+        let process = |e2_rate: &str| {
+            let text_input = format!(
+                "2017-11-01T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2017-11-01T09:42:23+00:00 E2 BTC USD {} 0.0001
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD
+EXCHANGE_RATE_REQUEST E1 USD E1 BTC 1000.0",
+                e2_rate
+            );
+
+            let mut input = BufReader::new(text_input.as_bytes());
+            let request = Request::<String, f32>::read_from(&mut input);
+
+            let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+            response
+                .iter()
+                .map(|path| (*path.get_rate(), path.get_path().len()))
+                .collect::<Vec<(f32, usize)>>()
+        };
+
+        // Test that the better rate of two transfers away doesn't outweigh the penalty.
+        assert_eq!(process("3000.0"), vec![(1000.0, 2), (0.001, 2)]);
+
+        // Test that the much better rate does and its rate doesn't include the penalty.
+        assert_eq!(process("5000.0"), vec![(5000.0, 4), (0.001, 2)]);
+    }
+
+    #[test]
+    fn process_with_edge_rates() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.set_transfer_penalty(0.5);
+        let text_input = "2017-11-01T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2017-11-01T09:42:23+00:00 E2 BTC USD 5000.0 0.0001
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD";
+        let request = Request::<String, f32>::read_from(&mut BufReader::new(text_input.as_bytes()));
+
+        // Test that the edge rates don't include the penalty and multiply to the rate.
+        let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+        let best_rate_path = &response.get_best_rate_paths()[0];
+        assert_eq!(best_rate_path.get_rates(), &[1.0, 5000.0, 1.0]);
+        assert_eq!(
+            best_rate_path.get_rates().iter().product::<f32>(),
+            *best_rate_path.get_rate()
+        );
+    }
+
+    #[test]
+    fn process_with_quote_decay() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.set_quote_half_life(Some(600.0));
+
+        let process = |e2_rate: &str| {
+            let text_input = format!(
+                "2017-11-01T09:42:23+00:00 E1 BTC USD 1000.0 0.0009
+2017-11-01T09:32:23+00:00 E2 BTC USD {} 0.0001
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD",
+                e2_rate
+            );
+            let request =
+                Request::<String, f32>::read_from(&mut BufReader::new(text_input.as_bytes()));
+
+            let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+            let best_rate_path = &response.get_best_rate_paths()[0];
+            (
+                *best_rate_path.get_rate(),
+                best_rate_path.get_rates().to_vec(),
+            )
+        };
+
+        // Test that the better rate of the quote as old as the half-life doesn't outweigh the
+        // decay.
+        assert_eq!(process("1500.0"), (1000.0, vec![1000.0]));
+
+        // Test that the much better rate does and its rates don't include the decay.
+        assert_eq!(process("3000.0"), (3000.0, vec![1.0, 3000.0, 1.0]));
+    }
+
+    #[test]
+    fn answer_by_threads() {
+        let request = prepare_large_request(6, 10);
+        let settings = Settings::new();
+        let solution = Algorithm::<String, f32, u32>::solve(&request, &settings, false);
+        let output = solution.answer(&request).get_output();
+
+        // Test that the answers are the same and in the same order as the sequential ones.
+        for threads in 1..5 {
+            let response = solution.answer_by_threads(&request, threads);
+            assert_eq!(response.get_best_rate_paths().len(), 60 * 59);
+            assert_eq!(response.get_output(), output);
+        }
+
+        // Test that a batch too small to split is answered as well.
+        let small_request = Request::<String, f32>::read_from(&mut BufReader::new(
+            "EXCHANGE_RATE_REQUEST E0 C0 E5 C9\nEXCHANGE_RATE_REQUEST E5 C9 E0 C0".as_bytes(),
+        ));
+        assert_eq!(
+            solution.answer_by_threads(&small_request, 4).get_output(),
+            solution.answer(&small_request).get_output()
+        );
+    }
+
+    #[test]
+    fn process_parallel() {
+        let request = prepare_large_request(6, 10);
+        let settings = Settings::new();
+
+        assert_eq!(
+            Algorithm::<String, f32, u32>::process_parallel(&request, &settings).get_output(),
+            Algorithm::<String, f32, u32>::process_customized(&request, &settings).get_output()
+        );
+    }
+
+    #[test]
+    fn process_cancellable() {
+        let request = prepare_large_request(2, 4);
+        let settings = Settings::new();
+
+        let response = Algorithm::<String, f32, u32>::process_cancellable(
+            &request,
+            &settings,
+            &Cancellation::new(),
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(
+            response.get_output(),
+            Algorithm::<String, f32, u32>::process_customized(&request, &settings).get_output()
+        );
+
+        // Test that all rate requests are unanswered once cancelled before the computation.
+        let cancellation = Cancellation::new();
+        cancellation.cancel();
+        let response =
+            Algorithm::<String, f32, u32>::process_cancellable(&request, &settings, &cancellation)
+                .err()
+                .unwrap()
+                .into_response();
+        assert!(response.get_best_rate_paths().is_empty());
+        assert_eq!(
+            response.get_unanswered_requests().len(),
+            request.get_rate_requests().len()
+        );
+        assert!(response
+            .get_unanswered_requests()
+            .iter()
+            .all(|unanswered_request| unanswered_request.get_reason() == Cancelled));
+
+        // Test that a run already started is interrupted, by the report of its first iteration.
+        let cancellation = Cancellation::new();
+        let cancelling = cancellation.clone();
+        let iterations = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&iterations);
+        let mut settings = Settings::new();
+        settings.set_progress_reporter(Some(ProgressReporter::new(
+            Duration::default(),
+            move |_| {
+                counted.fetch_add(1, Ordering::Relaxed);
+                cancelling.cancel();
+            },
+        )));
+        let response =
+            Algorithm::<String, f32, u32>::process_cancellable(&request, &settings, &cancellation)
+                .err()
+                .unwrap()
+                .into_response();
+        assert_eq!(iterations.load(Ordering::Relaxed), 1);
+        assert!(response.get_best_rate_paths().is_empty());
+        assert_eq!(
+            response.get_unanswered_requests().len(),
+            request.get_rate_requests().len()
+        );
+    }
+
+    /// Compare the time of sequential and 4 threads answering of thousands of rate requests, run
+    /// by `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn benchmark_process_parallel() {
+        let request = prepare_large_request(10, 12);
+        let settings = Settings::new();
+        let solution = Algorithm::<String, f32, u32>::solve(&request, &settings, false);
+
+        let start = Instant::now();
+        let sequential = solution.answer(&request);
+        let sequential_time = start.elapsed();
+
+        let start = Instant::now();
+        let parallel = solution.answer_by_threads(&request, 4);
+        let parallel_time = start.elapsed();
+
+        assert_eq!(sequential.get_output(), parallel.get_output());
+        println!(
+            "{} rate requests answered sequentially in {:?}, in parallel in {:?}.",
+            request.get_rate_requests().len(),
+            sequential_time,
+            parallel_time
+        );
     }
 }
@@ -0,0 +1,224 @@
+//! Best path of converting an amount with order size slippage.
+//!
+//! Edge rates depend on the converted amount, so the Floyd-Warshall result can't be used.
+//! Received amounts are relaxed Bellman-Ford style instead, starting with the requested amount.
+
+use crate::algorithm::slippage_model::SlippageModel;
+use crate::algorithm::Algorithm;
+use crate::response::best_rate_path::BestRatePath;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use indexmap::{IndexMap, IndexSet};
+use num_traits::Num;
+use safe_graph::NodeTrait;
+use std::fmt::{Debug, Display};
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+impl<N, E, I> Algorithm<N, E, I>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+    I: NodeTrait + Num + AddAssign,
+{
+    /// Find the path converting the `amount` of the source into the most of the destination.
+    ///
+    /// The rate of the returned `BestRatePath` is the effective one, i.e. the received amount
+    /// divided by the `amount`.
+    pub(super) fn find_best_amount_path(
+        &self,
+        source: (&N, &N),
+        destination: (&N, &N),
+        amount: E,
+    ) -> Option<BestRatePath<N, E>> {
+        // Prepare indexes, not known nodes can't have any path.
+        let a = (
+            self.get_node_index(source.0)?,
+            self.get_node_index(source.1)?,
+        );
+        let b = (
+            self.get_node_index(destination.0)?,
+            self.get_node_index(destination.1)?,
+        );
+
+        let mut amounts: IndexMap<(I, I), E> = IndexMap::new();
+        let mut predecessors: IndexMap<(I, I), (I, I)> = IndexMap::new();
+        amounts.insert(a, amount);
+
+        // A simple path has less edges than the count of nodes.
+        for _ in 1..self.graph.node_count() {
+            let mut changed = false;
+
+            for (x, y, rate) in self.graph.all_edges() {
+                if y == a {
+                    continue;
+                }
+
+                let x_amount = match amounts.get(&x) {
+                    Some(x_amount) => *x_amount,
+                    None => continue,
+                };
+                if !self.is_trade_allowed(x, y, x_amount) {
+                    continue;
+                }
+                let received = self.get_received_amount(x, y, *rate, x_amount);
+
+                if amounts.get(&y).is_none_or(|y_amount| received > *y_amount) {
+                    amounts.insert(y, received);
+                    predecessors.insert(y, x);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let received = *amounts.get(&b)?;
+        let path = Self::collect_predecessors_path(a, b, &predecessors)?;
+
+        Some(self.form_best_rate_path(received / amount, path))
+    }
+
+    /// Get amount received for converting the `amount` over the edge from `x` to `y`.
+    ///
+    /// Slippage applies to trades only, i.e. edges between currencies of the same exchange.
+    pub(super) fn get_received_amount(&self, x: (I, I), y: (I, I), rate: E, amount: E) -> E {
+        let depth = self
+            .get_trade_nodes(x, y)
+            .and_then(|(exchange, source, destination)| {
+                self.settings
+                    .get_slippage_model()
+                    .get_depth(exchange, source, destination)
+            });
+
+        SlippageModel::<N, E>::get_received_amount(rate, amount, depth)
+    }
+
+    /// Check whether the `amount` can be converted over the edge from `x` to `y`.
+    ///
+    /// Trading rules apply to trades only, i.e. edges between currencies of the same exchange.
+    pub(super) fn is_trade_allowed(&self, x: (I, I), y: (I, I), amount: E) -> bool {
+        self.get_trade_nodes(x, y)
+            .is_none_or(|(exchange, source, destination)| {
+                self.settings.get_trading_rules().is_trade_allowed(
+                    exchange,
+                    source,
+                    destination,
+                    amount,
+                )
+            })
+    }
+
+    /// Get `(exchange, source_currency, destination_currency)` nodes of the edge from `x` to
+    /// `y`, `None` if the edge is not a trade.
+    fn get_trade_nodes(&self, x: (I, I), y: (I, I)) -> Option<(&N, &N, &N)> {
+        if x.0 != y.0 || x.1 == y.1 {
+            return None;
+        }
+
+        Some((
+            self.index_to_node(&x.0)?,
+            self.index_to_node(&x.1)?,
+            self.index_to_node(&y.1)?,
+        ))
+    }
+
+    /// Collect the path from `a` to `b` following the `predecessors` back from `b`.
+    ///
+    /// Return `None` if the path doesn't exist or is not simple, which is possible in presence
+    /// of profitable cycles.
+    fn collect_predecessors_path(
+        a: (I, I),
+        b: (I, I),
+        predecessors: &IndexMap<(I, I), (I, I)>,
+    ) -> Option<Vec<(I, I)>> {
+        let mut visited = IndexSet::new();
+        let mut node = b;
+        visited.insert(node);
+
+        while node != a {
+            node = *predecessors.get(&node)?;
+
+            if !visited.insert(node) {
+                return None;
+            }
+        }
+
+        Some(visited.into_iter().rev().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::algorithm::Algorithm;
+    use crate::request::Request;
+    use std::io::BufReader;
+
+    #[test]
+    fn process_rate_request_with_amount() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD 990.0 0.00101
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD 10.0"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        // Test that without slippage the marginal rate is used.
+        let response = Algorithm::<String, f32, u32>::process(&request);
+        let path = &response.get_best_rate_paths()[0];
+        assert_eq!(*path.get_rate(), 1000.0);
+        assert_eq!(path.get_path().len(), 2);
+
+        // Test that the shallow E1 market makes the path through the deeper E2 one better.
+        let mut settings = Settings::<String, f32>::new();
+        settings
+            .get_slippage_model_mut()
+            .set_exchange_depth("E1".to_string(), 10.0);
+        settings
+            .get_slippage_model_mut()
+            .set_exchange_depth("E2".to_string(), 1_000_000.0);
+
+        let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+        let path = &response.get_best_rate_paths()[0];
+        assert!((*path.get_rate() - 990.0).abs() < 0.1);
+        assert_eq!(path.get_path().len(), 4);
+    }
+
+    #[test]
+    fn process_rate_request_with_min_trade_amount() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.get_trading_rules_mut().set_min_trade_amount(
+            "E1".to_string(),
+            "BTC".to_string(),
+            "USD".to_string(),
+            50.0,
+        );
+
+        let process = |amount: &str| {
+            let text_input = format!(
+                "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD 990.0 0.00101
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD {}",
+                amount
+            );
+
+            let mut input = BufReader::new(text_input.as_bytes());
+            let request = Request::<String, f32>::read_from(&mut input);
+            let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+            let path = &response.get_best_rate_paths()[0];
+            (*path.get_rate(), path.get_path().len())
+        };
+
+        // Test that the amount below the E1 minimum is traded at E2.
+        assert_eq!(process("10.0"), (990.0, 4));
+
+        // Test that the amount above the E1 minimum is traded at E1.
+        assert_eq!(process("100.0"), (1000.0, 2));
+    }
+}
@@ -0,0 +1,310 @@
+//! Arbitrage detection.
+
+use crate::algorithm::paths::Paths;
+use crate::algorithm::Algorithm;
+use crate::request::Request;
+use crate::response::arbitrage::{Arbitrage, UnansweredArbitrageRequest};
+use crate::response::Response;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use indexmap::IndexSet;
+use num_traits::Num;
+use safe_graph::NodeTrait;
+use std::fmt::{Debug, Display};
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+impl<N, E, I> Algorithm<N, E, I>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+    I: NodeTrait + Num + AddAssign,
+{
+    /// Answer all `ArbitrageRequest`s of the `Request` using the Floyd-Warshall result.
+    pub(super) fn form_arbitrage_response(
+        &self,
+        request: &Request<N, E>,
+        fw_result: &Paths<I, E>,
+        response: &mut Response<N, E>,
+    ) {
+        for (_, arbitrage_request) in request.get_arbitrage_requests().iter() {
+            let exchange = arbitrage_request.get_exchange();
+            let currency = arbitrage_request.get_currency();
+
+            // Funding of the request takes precedence over the `Settings` one.
+            let funding = if arbitrage_request.get_funding().is_empty() {
+                self.settings.get_funding()
+            } else {
+                arbitrage_request.get_funding()
+            };
+
+            match self.find_arbitrage(exchange, currency, funding, fw_result) {
+                Some(arbitrage) => response.add_arbitrage(arbitrage),
+                None => response.add_unanswered_arbitrage_request(UnansweredArbitrageRequest::new(
+                    exchange.cloned(),
+                    currency.cloned(),
+                )),
+            }
+        }
+    }
+
+    /// Find the most profitable `Arbitrage` by scanning the Floyd-Warshall result.
+    ///
+    /// Every edge `(b, a)` of the graph closes the best path from `a` to `b` into a cycle
+    /// starting at `a`. The cycle with the highest rates product exceeding the
+    /// minimal profit factor is returned.
+    ///
+    /// Only cycles starting at the `(exchange, currency)` node and passing through one of the
+    /// `funding` nodes are considered, `None` stands for any exchange or any currency.
+    pub fn find_arbitrage(
+        &self,
+        exchange: Option<&N>,
+        currency: Option<&N>,
+        funding: &[(Option<N>, Option<N>)],
+        fw_result: &Paths<I, E>,
+    ) -> Option<Arbitrage<N, E>> {
+        // Prepare indexes, a not known node can't be a start of any cycle.
+        let exchange_index = self.get_optional_node_index(exchange)?;
+        let currency_index = self.get_optional_node_index(currency)?;
+
+        let mut best: Option<(E, Vec<(I, I)>)> = None;
+
+        for (b, a, _) in self.graph.all_edges() {
+            // Skip cycles not starting at the requested node.
+            if exchange_index.is_some_and(|index| index != a.0)
+                || currency_index.is_some_and(|index| index != a.1)
+            {
+                continue;
+            }
+
+            let cycle = match self.collect_cycle(a, b, fw_result) {
+                Some(cycle) => cycle,
+                None => continue,
+            };
+            if !self.is_funded(&cycle, funding) {
+                continue;
+            }
+            let profit_factor = match self.get_path_rate(&cycle) {
+                Some(rate) => rate,
+                None => continue,
+            };
+
+            if profit_factor > *self.settings.get_min_profit_factor()
+                && best
+                    .as_ref()
+                    .is_none_or(|(best_factor, _)| profit_factor > *best_factor)
+            {
+                best = Some((profit_factor, cycle));
+            }
+        }
+
+        best.map(|(profit_factor, cycle)| self.form_arbitrage(profit_factor, cycle))
+    }
+
+    /// Check whether the cycle passes through one of the `funding` nodes, `None` stands for any
+    /// exchange or any currency. No funding nodes means no restriction.
+    pub(super) fn is_funded(&self, cycle: &[(I, I)], funding: &[(Option<N>, Option<N>)]) -> bool {
+        if funding.is_empty() {
+            return true;
+        }
+
+        funding.iter().any(|(exchange, currency)| {
+            // A not known funding node can't be passed through.
+            let (exchange_index, currency_index) = match (
+                self.get_optional_node_index(exchange.as_ref()),
+                self.get_optional_node_index(currency.as_ref()),
+            ) {
+                (Some(exchange_index), Some(currency_index)) => (exchange_index, currency_index),
+                _ => return false,
+            };
+
+            cycle.iter().any(|&(a_exchange, a_currency)| {
+                exchange_index.is_none_or(|index| index == a_exchange)
+                    && currency_index.is_none_or(|index| index == a_currency)
+            })
+        })
+    }
+
+    /// Get index of the optional node, `Some(None)` stands for the `None` wildcard.
+    ///
+    /// Return `None` if the node is not yet indexed.
+    fn get_optional_node_index(&self, node: Option<&N>) -> Option<Option<I>> {
+        match node {
+            Some(node) => Some(Some(self.get_node_index(node)?)),
+            None => Some(None),
+        }
+    }
+
+    /// Get rates of the path edges, trading fees are already applied to the graph edge weights.
+    ///
+    /// Return `None` if any of the path edges doesn't exist.
+    pub(super) fn get_edge_rates(&self, path: &[(I, I)]) -> Option<Vec<E>> {
+        path.windows(2)
+            .map(|pair| self.graph.edge_weight(pair[0], pair[1]).copied())
+            .collect()
+    }
+
+    /// Get the rates product of the path.
+    ///
+    /// Return `None` if any of the path edges doesn't exist.
+    pub(super) fn get_path_rate(&self, path: &[(I, I)]) -> Option<E> {
+        let rates = self.get_edge_rates(path)?;
+
+        Some(
+            rates
+                .into_iter()
+                .fold(E::one(), |product, rate| product * rate),
+        )
+    }
+
+    /// Form the `Arbitrage` of the cycle of indexes, including its edge rates.
+    pub(super) fn form_arbitrage(&self, profit_factor: E, cycle: Vec<(I, I)>) -> Arbitrage<N, E> {
+        let rates = self.get_edge_rates(&cycle).unwrap_or_default();
+
+        Arbitrage::new(profit_factor, self.index_path_to_nodes(cycle), rates)
+    }
+
+    /// Collect a cycle formed by the best path from `a` to `b` and the edge `(b, a)`.
+    ///
+    /// Return `None` if there is no such path or the path is not simple (visits a node twice),
+    /// which is possible as Floyd-Warshall results are not well defined in presence of cycles.
+    fn collect_cycle(&self, a: (I, I), b: (I, I), fw_result: &Paths<I, E>) -> Option<Vec<(I, I)>> {
+        fw_result.get_path_rate(a, b)?;
+        let mut path = fw_result.collect_path_nodes(a, b);
+
+        if path.first() != Some(&a) || path.last() != Some(&b) {
+            return None;
+        }
+
+        let unique: IndexSet<&(I, I)> = path.iter().collect();
+        if unique.len() != path.len() {
+            return None;
+        }
+
+        path.push(a);
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::algorithm::Algorithm;
+    use crate::request::Request;
+    use std::io::BufReader;
+
+    #[test]
+    fn process_arbitrage_requests() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD 900.0 0.00111
+ARBITRAGE_REQUEST ANY ANY
+ARBITRAGE_REQUEST E2 BTC
+ARBITRAGE_REQUEST E3 BTC"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let response = Algorithm::<String, f32, u32>::process(&request);
+        let arbitrages = response.get_arbitrages();
+
+        // Test that both known start nodes have an arbitrage.
+        assert_eq!(arbitrages.len(), 2);
+
+        // Test the profit factor: sell BTC on E1, transfer USD, buy BTC on E2, transfer BTC.
+        assert!((arbitrages[0].get_profit_factor() - 1.11).abs() < 1e-5);
+
+        // Test the cycle is closed and visits all four nodes.
+        let cycle = arbitrages[0].get_cycle();
+        assert_eq!(cycle.len(), 5);
+        assert_eq!(cycle.first(), cycle.last());
+
+        // Test the cycle starts at the requested node.
+        assert_eq!(
+            arbitrages[1].get_start_node(),
+            Some(&("E2".to_string(), "BTC".to_string()))
+        );
+        assert!((arbitrages[1].get_profit_factor() - 1.11).abs() < 1e-5);
+
+        // Test that the arbitrage request for an unknown exchange is unanswered.
+        let unanswered = response.get_unanswered_arbitrage_requests();
+        assert_eq!(unanswered.len(), 1);
+        assert_eq!(unanswered[0].get_exchange(), Some(&"E3".to_string()));
+    }
+
+    #[test]
+    fn process_arbitrage_requests_without_opportunity() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.0009
+2019-01-20T09:42:23+00:00 E2 BTC USD 1000.0 0.0009
+ARBITRAGE_REQUEST ANY ANY"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let response = Algorithm::<String, f32, u32>::process(&request);
+
+        assert!(response.get_arbitrages().is_empty());
+        assert_eq!(response.get_unanswered_arbitrage_requests().len(), 1);
+    }
+
+    #[test]
+    fn process_arbitrage_requests_with_fees_and_threshold() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD 900.0 0.00111
+ARBITRAGE_REQUEST ANY ANY"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        // Test that fees are applied to both trades of the cycle: 1.11 * 0.95 * 0.95.
+        let mut settings = Settings::<String, f32>::new();
+        settings.get_fee_model_mut().set_default_fee(0.05);
+
+        let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+        let arbitrages = response.get_arbitrages();
+        assert_eq!(arbitrages.len(), 1);
+        assert!((arbitrages[0].get_profit_factor() - 1.001775).abs() < 1e-5);
+
+        // Test that the fee adjusted profit factor doesn't exceed the threshold.
+        settings.set_min_profit_factor(1.002);
+
+        let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+        assert!(response.get_arbitrages().is_empty());
+        assert_eq!(response.get_unanswered_arbitrage_requests().len(), 1);
+    }
+
+    #[test]
+    fn process_arbitrage_requests_with_funding() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD 900.0 0.00111
+2019-01-20T09:42:23+00:00 E3 ETH EUR 100.0 0.01
+2019-01-20T09:42:23+00:00 E4 ETH EUR 80.0 0.015
+ARBITRAGE_REQUEST E1 ANY ANY BTC
+ARBITRAGE_REQUEST ANY ANY"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        // Test that the request funding takes precedence over the settings one.
+        let mut settings = Settings::<String, f32>::new();
+        settings.set_funding(vec![(Some("E3".to_string()), None)]);
+
+        let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+        let arbitrages = response.get_arbitrages();
+        assert_eq!(arbitrages.len(), 2);
+        assert!((arbitrages[0].get_profit_factor() - 1.11).abs() < 1e-5);
+        assert!((arbitrages[1].get_profit_factor() - 1.5).abs() < 1e-5);
+
+        // Test that no cycle passes through an unknown funding node.
+        settings.set_funding(vec![(Some("E9".to_string()), None)]);
+
+        let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+        assert_eq!(response.get_arbitrages().len(), 1);
+        assert_eq!(response.get_unanswered_arbitrage_requests().len(), 1);
+    }
+}
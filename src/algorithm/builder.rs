@@ -0,0 +1,156 @@
+//! Builder of the `Algorithm` configuration.
+
+use crate::algorithm::settings::{Backend, PathSemantics, Settings};
+use crate::algorithm::Algorithm;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use num_traits::Num;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+/// `AlgorithmBuilder` structure.
+///
+/// Chains the choices of the `Algorithm` on top of its `Settings`, the ones not chosen keep
+/// their default values.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::{Algorithm, Backend, Request};
+/// use std::io::BufReader;
+///
+/// let alg = Algorithm::<String, f32, u32>::builder()
+///     .max_hops(Some(1))
+///     .comparison_epsilon(0.0001)
+///     .backend(Backend::BellmanFord)
+///     .build();
+///
+/// let text_input = "2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001\n\
+///                   2019-01-20T09:42:23+00:00 KRAKEN BTC EUR 1000 0.001\n\
+///                   2019-01-20T09:42:23+00:00 KRAKEN EUR USD 1.2 0.8\n\
+///                   EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD\n";
+/// let request = Request::read_from(&mut BufReader::new(text_input.as_bytes()));
+///
+/// // The better path through EUR has two edges.
+/// let response = alg.answer(&request);
+/// assert_eq!(*response.get_best_rate_paths()[0].get_rate(), 1000.0);
+/// ```
+///
+/// # `AlgorithmBuilder<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct AlgorithmBuilder<N, E> {
+    settings: Settings<N, E>,
+}
+
+impl<N, E> AlgorithmBuilder<N, E>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy,
+{
+    /// Create a new instance of `AlgorithmBuilder` structure with default `Settings`.
+    pub fn new() -> Self {
+        Self {
+            settings: Settings::new(),
+        }
+    }
+
+    /// Start from the `Settings` instead of the default ones.
+    pub fn settings(mut self, settings: Settings<N, E>) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Set the weight of edges between the same currency at different exchanges, `1` by
+    /// default. Currencies and routes with their own transfer fees keep them.
+    pub fn cross_exchange_weight(mut self, weight: E) -> Self {
+        self.settings
+            .get_transfer_model_mut()
+            .set_default_fee(E::one() - weight);
+        self
+    }
+
+    /// Set whether Floyd-Warshall discards paths through their own start or end node.
+    pub fn discard_loops(mut self, discard_loops: bool) -> Self {
+        self.settings.set_discard_loops(discard_loops);
+        self
+    }
+
+    /// Set how weights of edges combine along a path and which path is the best.
+    pub fn path_semantics(mut self, path_semantics: PathSemantics) -> Self {
+        self.settings.set_path_semantics(path_semantics);
+        self
+    }
+
+    /// Set the maximal count of edges of a best rate path, `None` for no limit.
+    pub fn max_hops(mut self, max_hops: Option<usize>) -> Self {
+        self.settings.set_max_hops(max_hops);
+        self
+    }
+
+    /// Set the difference a path's rate must exceed the known one by to replace it.
+    pub fn comparison_epsilon(mut self, comparison_epsilon: E) -> Self {
+        self.settings.set_comparison_epsilon(comparison_epsilon);
+        self
+    }
+
+    /// Set the algorithm finding best rate paths.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.settings.set_backend(backend);
+        self
+    }
+
+    /// Get the `Settings` chosen so far.
+    pub fn get_settings(&self) -> &Settings<N, E> {
+        &self.settings
+    }
+}
+
+impl<N, E> AlgorithmBuilder<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Build the `Algorithm` of the chosen `Settings`.
+    pub fn build(self) -> Algorithm<N, E, u32> {
+        Algorithm::new_customized(self.settings)
+    }
+}
+
+impl<N, E> Default for AlgorithmBuilder<N, E>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::builder::AlgorithmBuilder;
+    use crate::algorithm::settings::Backend;
+
+    #[test]
+    fn build() {
+        let builder = AlgorithmBuilder::<String, f32>::new()
+            .cross_exchange_weight(0.99)
+            .discard_loops(false)
+            .max_hops(Some(3))
+            .comparison_epsilon(0.001)
+            .backend(Backend::BellmanFord);
+
+        let settings = builder.get_settings();
+        assert!((settings.get_transfer_model().get_default_fee() - 0.01).abs() < 1e-6);
+        assert!(!settings.get_discard_loops());
+        assert_eq!(settings.get_max_hops(), Some(3));
+        assert_eq!(*settings.get_comparison_epsilon(), 0.001);
+        assert_eq!(settings.get_backend(), Backend::BellmanFord);
+
+        let alg = builder.build();
+        assert_eq!(alg.get_settings().get_max_hops(), Some(3));
+    }
+}
@@ -0,0 +1,101 @@
+//! Cancellation of long computations.
+
+use crate::response::Response;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// `Cancellation` structure.
+///
+/// Token cancelling a computation from another thread, cancelled by itself once its deadline
+/// passes. Clones share the token, so cancelling any of them cancels all.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Create a new instance of `Cancellation` structure, not cancelled and without deadline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the moment the computation is cancelled at, `None` for no deadline.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    pub fn get_deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Cancel the computation.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Get whether the computation is cancelled or its deadline passed.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// `Cancelled` structure.
+///
+/// Error of a cancelled computation, holding the partial `Response` of requests answered
+/// before. Rate requests left are unanswered for the `cancelled` reason, arbitrage requests are
+/// left out.
+///
+/// # `Cancelled<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct Cancelled<N, E> {
+    response: Box<Response<N, E>>,
+}
+
+impl<N, E> Cancelled<N, E> {
+    /// Create a new instance of `Cancelled` structure.
+    pub fn new(response: Response<N, E>) -> Self {
+        Self {
+            response: Box::new(response),
+        }
+    }
+
+    /// Get the partial `Response`.
+    pub fn get_response(&self) -> &Response<N, E> {
+        &self.response
+    }
+
+    /// Take the partial `Response`.
+    pub fn into_response(self) -> Response<N, E> {
+        *self.response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::cancellation::Cancellation;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn is_cancelled() {
+        let mut cancellation = Cancellation::new();
+        assert!(!cancellation.is_cancelled());
+
+        // Test that the deadline cancels it once it passes.
+        cancellation.set_deadline(Some(Instant::now() + Duration::from_secs(60)));
+        assert!(!cancellation.is_cancelled());
+        cancellation.set_deadline(Some(Instant::now()));
+        assert!(cancellation.is_cancelled());
+
+        // Test that clones share the token.
+        let cancellation = Cancellation::new();
+        cancellation.clone().cancel();
+        assert!(cancellation.is_cancelled());
+    }
+}
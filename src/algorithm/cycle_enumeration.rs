@@ -0,0 +1,378 @@
+//! Enumeration of all profitable cycles up to a maximal length.
+//!
+//! The graph is first pruned to its strongly connected components, as no other node can be a
+//! part of any cycle. Cycles are then enumerated the way Johnson's algorithm does, each one
+//! exactly once starting at its node with the lowest index, but with a depth limit instead of
+//! node blocking (blocking is not sound once the cycle length is bounded).
+
+use crate::algorithm::settings::Settings;
+use crate::algorithm::Algorithm;
+use crate::request::Request;
+use crate::response::arbitrage::Arbitrage;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use indexmap::IndexSet;
+use num_traits::Num;
+use safe_graph::NodeTrait;
+use std::cmp::Ordering::Equal;
+use std::fmt::{Debug, Display};
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+/// Dense representation of the graph used for the cycles enumeration.
+struct Subgraph<I> {
+    nodes: IndexSet<(I, I)>,
+    successors: Vec<Vec<usize>>,
+    components: Vec<usize>,
+}
+
+impl<N, E, I> Algorithm<N, E, I>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+    I: NodeTrait + Num + AddAssign,
+{
+    /// Find all profitable cycles for `PriceUpdate`s of the provided `Request`.
+    pub fn process_all_arbitrages(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+    ) -> Vec<Arbitrage<N, E>> {
+        let mut alg = Algorithm::<N, E, I>::new_customized(settings.clone());
+        alg.construct_graph(request);
+        alg.find_all_arbitrages()
+    }
+
+    /// Find all simple cycles up to the maximal cycle length with rates product
+    /// exceeding the minimal profit factor, passing through one of the funding nodes if any.
+    ///
+    /// `Arbitrage`s are ranked from the most profitable one.
+    pub fn find_all_arbitrages(&self) -> Vec<Arbitrage<N, E>> {
+        let subgraph = self.prepare_subgraph();
+        let mut found: Vec<(E, Vec<(I, I)>)> = Vec::new();
+        let mut path = Vec::with_capacity(self.settings.get_max_cycle_length() + 1);
+
+        for start in 0..subgraph.nodes.len() {
+            path.push(start);
+            self.collect_cycles(&subgraph, &mut path, &mut found);
+            path.pop();
+        }
+
+        // Rank from the most profitable cycle.
+        found.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Equal));
+
+        found
+            .into_iter()
+            .map(|(profit_factor, cycle)| self.form_arbitrage(profit_factor, cycle))
+            .collect()
+    }
+
+    /// Depth-first search of profitable cycles continuing the `path`.
+    fn collect_cycles(
+        &self,
+        subgraph: &Subgraph<I>,
+        path: &mut Vec<usize>,
+        found: &mut Vec<(E, Vec<(I, I)>)>,
+    ) {
+        let start = path[0];
+        let last = path[path.len() - 1];
+
+        for &next in subgraph.successors[last].iter() {
+            // Stay within the strongly connected component of the start node.
+            if subgraph.components[next] != subgraph.components[start] {
+                continue;
+            }
+
+            if next == start {
+                let cycle: Vec<(I, I)> = path
+                    .iter()
+                    .chain(Some(&start))
+                    .map(|&node| *subgraph.nodes.get_index(node).unwrap())
+                    .collect();
+
+                if let Some(profit_factor) = self.get_path_rate(&cycle) {
+                    if profit_factor > *self.settings.get_min_profit_factor()
+                        && self.is_funded(&cycle, self.settings.get_funding())
+                    {
+                        found.push((profit_factor, cycle));
+                    }
+                }
+            } else if next > start
+                && path.len() < self.settings.get_max_cycle_length()
+                && !path.contains(&next)
+            {
+                path.push(next);
+                self.collect_cycles(subgraph, path, found);
+                path.pop();
+            }
+        }
+    }
+
+    /// Find all profitable cycles up to the maximal cycle length passing through the
+    /// `(exchange, currency)` node.
+    ///
+    /// Only cycles passing through a node are affected by a change of its edges, which allows
+    /// to re-evaluate the opportunities incrementally after a `PriceUpdate`. Cycles are rotated
+    /// to start at their node with the lowest index, so the same cycle is always represented by
+    /// the same sequence of nodes.
+    pub fn find_arbitrages_through(&self, exchange: &N, currency: &N) -> Vec<Arbitrage<N, E>> {
+        let node = match (self.get_node_index(exchange), self.get_node_index(currency)) {
+            (Some(exchange), Some(currency)) => (exchange, currency),
+            _ => return Vec::new(),
+        };
+
+        if !self.graph.contains_node(node) {
+            return Vec::new();
+        }
+
+        let mut found: Vec<(E, Vec<(I, I)>)> = Vec::new();
+        let mut path = vec![node];
+        self.collect_cycles_through(&mut path, &mut found);
+
+        // Rank from the most profitable cycle.
+        found.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Equal));
+
+        found
+            .into_iter()
+            .map(|(profit_factor, cycle)| self.form_arbitrage(profit_factor, cycle))
+            .collect()
+    }
+
+    /// Get the rates product of the cycle of `(exchange, currency)` nodes.
+    ///
+    /// Return `None` if any of the cycle nodes or edges doesn't exist.
+    pub fn get_cycle_profit_factor(&self, cycle: &[(N, N)]) -> Option<E> {
+        let cycle = cycle
+            .iter()
+            .map(|(exchange, currency)| {
+                Some((
+                    self.get_node_index(exchange)?,
+                    self.get_node_index(currency)?,
+                ))
+            })
+            .collect::<Option<Vec<(I, I)>>>()?;
+
+        self.get_path_rate(&cycle)
+    }
+
+    /// Depth-first search of profitable cycles continuing the `path` back to its first node.
+    fn collect_cycles_through(&self, path: &mut Vec<(I, I)>, found: &mut Vec<(E, Vec<(I, I)>)>) {
+        let start = path[0];
+        let last = path[path.len() - 1];
+
+        for (_, next, _) in self.graph.edges(last) {
+            if next == start {
+                let cycle = rotate_cycle(path);
+
+                if let Some(profit_factor) = self.get_path_rate(&cycle) {
+                    if profit_factor > *self.settings.get_min_profit_factor()
+                        && self.is_funded(&cycle, self.settings.get_funding())
+                    {
+                        found.push((profit_factor, cycle));
+                    }
+                }
+            } else if path.len() < self.settings.get_max_cycle_length() && !path.contains(&next) {
+                path.push(next);
+                self.collect_cycles_through(path, found);
+                path.pop();
+            }
+        }
+    }
+
+    /// Prepare dense `Subgraph` with strongly connected components of the graph nodes.
+    fn prepare_subgraph(&self) -> Subgraph<I> {
+        let nodes: IndexSet<(I, I)> = self.graph.nodes().collect();
+        let mut successors = vec![Vec::new(); nodes.len()];
+        let mut predecessors = vec![Vec::new(); nodes.len()];
+
+        for (a, b, _) in self.graph.all_edges() {
+            let a = nodes.get_index_of(&a).unwrap();
+            let b = nodes.get_index_of(&b).unwrap();
+
+            successors[a].push(b);
+            predecessors[b].push(a);
+        }
+
+        let components = find_components(&successors, &predecessors);
+
+        Subgraph {
+            nodes,
+            successors,
+            components,
+        }
+    }
+}
+
+/// Rotate the cycle `path` to start at its lowest node and close it by repeating the node.
+fn rotate_cycle<T: Copy + Ord>(path: &[T]) -> Vec<T> {
+    let lowest = (0..path.len()).min_by_key(|&i| path[i]).unwrap_or(0);
+
+    path[lowest..]
+        .iter()
+        .chain(path[..=lowest].iter())
+        .copied()
+        .collect()
+}
+
+/// Find strongly connected components using the Kosaraju's algorithm.
+///
+/// Return component identifier of every node.
+fn find_components(successors: &[Vec<usize>], predecessors: &[Vec<usize>]) -> Vec<usize> {
+    let count = successors.len();
+
+    // Order nodes by the finish time of the depth-first search.
+    let mut visited = vec![false; count];
+    let mut order = Vec::with_capacity(count);
+
+    for root in 0..count {
+        if visited[root] {
+            continue;
+        }
+
+        visited[root] = true;
+        let mut stack = vec![(root, 0)];
+
+        while let Some((node, next)) = stack.pop() {
+            if let Some(&successor) = successors[node].get(next) {
+                stack.push((node, next + 1));
+
+                if !visited[successor] {
+                    visited[successor] = true;
+                    stack.push((successor, 0));
+                }
+            } else {
+                order.push(node);
+            }
+        }
+    }
+
+    // Assign components by searching the transposed graph in the reversed finish order.
+    let mut components = vec![usize::MAX; count];
+    let mut component = 0;
+
+    for &root in order.iter().rev() {
+        if components[root] != usize::MAX {
+            continue;
+        }
+
+        components[root] = component;
+        let mut stack = vec![root];
+
+        while let Some(node) = stack.pop() {
+            for &predecessor in predecessors[node].iter() {
+                if components[predecessor] == usize::MAX {
+                    components[predecessor] = component;
+                    stack.push(predecessor);
+                }
+            }
+        }
+
+        component += 1;
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::cycle_enumeration::{find_components, rotate_cycle};
+    use crate::algorithm::settings::Settings;
+    use crate::algorithm::Algorithm;
+    use crate::request::Request;
+    use std::io::BufReader;
+
+    #[test]
+    fn find_components_of_graph() {
+        // Cycle 0 -> 1 -> 2 -> 0, node 3 reachable only, node 4 isolated.
+        let successors = vec![vec![1], vec![2], vec![0, 3], vec![], vec![]];
+        let predecessors = vec![vec![2], vec![0], vec![1], vec![2], vec![]];
+
+        let components = find_components(&successors, &predecessors);
+
+        assert_eq!(components[0], components[1]);
+        assert_eq!(components[1], components[2]);
+        assert_ne!(components[0], components[3]);
+        assert_ne!(components[3], components[4]);
+    }
+
+    #[test]
+    fn rotate_cycle_to_lowest_node() {
+        assert_eq!(rotate_cycle(&[3, 1, 2]), vec![1, 2, 3, 1]);
+        assert_eq!(rotate_cycle(&[1, 2]), vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn process_all_arbitrages() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD 900.0 0.00111"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let mut settings = Settings::<String, f32>::new();
+
+        // Test that the only profitable cycle needs four nodes.
+        settings.set_max_cycle_length(3);
+        let arbitrages = Algorithm::<String, f32, u32>::process_all_arbitrages(&request, &settings);
+        assert!(arbitrages.is_empty());
+
+        settings.set_max_cycle_length(4);
+        let arbitrages = Algorithm::<String, f32, u32>::process_all_arbitrages(&request, &settings);
+        assert_eq!(arbitrages.len(), 1);
+        assert!((arbitrages[0].get_profit_factor() - 1.11).abs() < 1e-5);
+        assert_eq!(arbitrages[0].get_cycle().len(), 5);
+    }
+
+    #[test]
+    fn process_all_arbitrages_ranked() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD 900.0 0.00111
+2019-01-20T09:42:23+00:00 E3 BTC USD 950.0 0.00105"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let arbitrages =
+            Algorithm::<String, f32, u32>::process_all_arbitrages(&request, &Settings::new());
+
+        // Test that several cycles were found and ranked from the most profitable.
+        assert!(arbitrages.len() > 1);
+        for pair in arbitrages.windows(2) {
+            assert!(pair[0].get_profit_factor() >= pair[1].get_profit_factor());
+            assert!(*pair[1].get_profit_factor() > 1.0);
+        }
+    }
+
+    #[test]
+    fn find_arbitrages_through() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD 900.0 0.00111
+2019-01-20T09:42:23+00:00 E3 ETH USD 100.0 0.01"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+        let alg = Algorithm::<String, f32, u32>::from_request(&request, &Settings::new());
+
+        // Test that the cycle is found from any of its nodes with the same representation.
+        let from_e1 = alg.find_arbitrages_through(&"E1".to_string(), &"BTC".to_string());
+        let from_e2 = alg.find_arbitrages_through(&"E2".to_string(), &"USD".to_string());
+        assert_eq!(from_e1.len(), 1);
+        assert_eq!(from_e1[0].get_cycle(), from_e2[0].get_cycle());
+        assert_eq!(
+            alg.get_cycle_profit_factor(from_e1[0].get_cycle()),
+            Some(*from_e1[0].get_profit_factor())
+        );
+
+        // Test that no cycle passes through unrelated or unknown nodes.
+        assert!(alg
+            .find_arbitrages_through(&"E3".to_string(), &"ETH".to_string())
+            .is_empty());
+        assert!(alg
+            .find_arbitrages_through(&"E9".to_string(), &"BTC".to_string())
+            .is_empty());
+    }
+}
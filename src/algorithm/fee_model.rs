@@ -0,0 +1,347 @@
+//! Trading Fee Model.
+
+use self::ExecutionStyle::*;
+use crate::json;
+#[cfg(feature = "std")]
+use crate::json::value::{keyed_numbers, optional_number, Value};
+use crate::IndexMapTrait;
+use indexmap::IndexMap;
+use num_traits::Num;
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The way an order is executed, deciding whether maker or taker fees are paid.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum ExecutionStyle {
+    /// The order rests in the order book and provides liquidity.
+    Maker,
+    /// The order is matched immediately and takes liquidity.
+    #[default]
+    Taker,
+}
+
+impl ExecutionStyle {
+    pub fn get_label(&self) -> String {
+        match self {
+            Maker => "MAKER".to_string(),
+            Taker => "TAKER".to_string(),
+        }
+    }
+
+    /// Parse the execution style from its label, ignoring the case.
+    pub fn parse(label: &str) -> Option<Self> {
+        let label = label.to_uppercase();
+
+        if label == Maker.get_label() {
+            Some(Maker)
+        } else if label == Taker.get_label() {
+            Some(Taker)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ExecutionStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+/// Trading `FeeModel` structure.
+///
+/// Holds trading fees of exchanges as fractions of the traded amount (e.g. `0.001` for 0.1%)
+/// and fixed fees paid per trade. Exchanges without their own fee use the default fee.
+///
+/// The fees are taker fees. Maker fees are optional, exchanges without any maker fee pay the
+/// taker fee for maker orders as well.
+///
+/// Rates don't depend on the traded amount, so a fixed fee is approximated by its fraction of
+/// the reference amount of a single trade, both in units of the traded (source) currency.
+///
+/// # `FeeModel<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+#[derive(Clone, Debug)]
+pub struct FeeModel<N, E> {
+    default_fee: E,
+    exchange_fees: IndexMap<N, E>,
+    default_maker_fee: Option<E>,
+    exchange_maker_fees: IndexMap<N, E>,
+    default_fixed_fee: E,
+    exchange_fixed_fees: IndexMap<N, E>,
+    reference_amount: E,
+}
+
+impl<N, E> FeeModel<N, E>
+where
+    N: IndexMapTrait,
+    E: Num + Copy,
+{
+    /// Create a new instance of `FeeModel` structure without any fees.
+    pub fn new() -> Self {
+        Self {
+            default_fee: E::zero(),
+            exchange_fees: IndexMap::new(),
+            default_maker_fee: None,
+            exchange_maker_fees: IndexMap::new(),
+            default_fixed_fee: E::zero(),
+            exchange_fixed_fees: IndexMap::new(),
+            reference_amount: E::one(),
+        }
+    }
+
+    /// Set fee of all exchanges without their own fee.
+    pub fn set_default_fee(&mut self, fee: E) {
+        self.default_fee = fee;
+    }
+
+    /// Set fee of the exchange.
+    pub fn set_exchange_fee(&mut self, exchange: N, fee: E) {
+        self.exchange_fees.insert(exchange, fee);
+    }
+
+    /// Get fee of the exchange.
+    pub fn get_fee(&self, exchange: &N) -> E {
+        *self
+            .exchange_fees
+            .get(exchange)
+            .unwrap_or(&self.default_fee)
+    }
+
+    /// Set maker fee of all exchanges without their own maker fee.
+    pub fn set_default_maker_fee(&mut self, fee: Option<E>) {
+        self.default_maker_fee = fee;
+    }
+
+    /// Set maker fee of the exchange.
+    pub fn set_exchange_maker_fee(&mut self, exchange: N, fee: E) {
+        self.exchange_maker_fees.insert(exchange, fee);
+    }
+
+    /// Get maker fee of the exchange, falling back to its taker fee.
+    pub fn get_maker_fee(&self, exchange: &N) -> E {
+        self.exchange_maker_fees
+            .get(exchange)
+            .copied()
+            .or(self.default_maker_fee)
+            .unwrap_or_else(|| self.get_fee(exchange))
+    }
+
+    /// Get fee of the exchange paid for orders of the execution style.
+    pub fn get_style_fee(&self, exchange: &N, style: ExecutionStyle) -> E {
+        match style {
+            Maker => self.get_maker_fee(exchange),
+            Taker => self.get_fee(exchange),
+        }
+    }
+
+    /// Check whether any maker fee is set, i.e. maker orders may pay different fees.
+    pub fn has_maker_fees(&self) -> bool {
+        self.default_maker_fee.is_some() || !self.exchange_maker_fees.is_empty()
+    }
+
+    /// Set fixed fee per trade of all exchanges without their own fixed fee.
+    pub fn set_default_fixed_fee(&mut self, fixed_fee: E) {
+        self.default_fixed_fee = fixed_fee;
+    }
+
+    /// Set fixed fee per trade of the exchange.
+    pub fn set_exchange_fixed_fee(&mut self, exchange: N, fixed_fee: E) {
+        self.exchange_fixed_fees.insert(exchange, fixed_fee);
+    }
+
+    /// Get fixed fee per trade of the exchange.
+    pub fn get_fixed_fee(&self, exchange: &N) -> E {
+        *self
+            .exchange_fixed_fees
+            .get(exchange)
+            .unwrap_or(&self.default_fixed_fee)
+    }
+
+    /// Set the amount of a single trade fixed fees are related to.
+    pub fn set_reference_amount(&mut self, reference_amount: E) {
+        self.reference_amount = reference_amount;
+    }
+
+    pub fn get_reference_amount(&self) -> E {
+        self.reference_amount
+    }
+
+    /// Get the factor a traded amount is multiplied by after paying the exchange fees.
+    pub fn get_trade_factor(&self, exchange: &N, style: ExecutionStyle) -> E {
+        let fixed_fee_factor = E::one() - self.get_fixed_fee(exchange) / self.reference_amount;
+
+        (E::one() - self.get_style_fee(exchange, style)) * fixed_fee_factor
+    }
+
+    /// Check whether no fees are set at all.
+    pub fn is_empty(&self) -> bool {
+        self.default_fee.is_zero()
+            && self.exchange_fees.values().all(|fee| fee.is_zero())
+            && self.default_maker_fee.is_none_or(|fee| fee.is_zero())
+            && self.exchange_maker_fees.values().all(|fee| fee.is_zero())
+            && self.default_fixed_fee.is_zero()
+            && self.exchange_fixed_fees.values().all(|fee| fee.is_zero())
+    }
+}
+
+impl<N, E> FeeModel<N, E>
+where
+    N: IndexMapTrait + Display + FromStr,
+    E: Num + Copy + Display + FromStr,
+{
+    /// Get JSON representation of the `FeeModel`, in the format `parse_json` accepts.
+    ///
+    /// # Format
+    ///
+    /// {"fee":<number>,"maker_fee":<number or null>,"fixed_fee":<number>,
+    /// "reference_amount":<number>,"exchange_fees":{<exchange>:<number>, ...},
+    /// "exchange_maker_fees":{<exchange>:<number>, ...},
+    /// "exchange_fixed_fees":{<exchange>:<number>, ...}}
+    pub fn get_json(&self) -> String {
+        format!(
+            "{{\"fee\":{},\"maker_fee\":{},\"fixed_fee\":{},\"reference_amount\":{},\
+             \"exchange_fees\":{},\"exchange_maker_fees\":{},\"exchange_fixed_fees\":{}}}",
+            self.default_fee,
+            json::option(self.default_maker_fee),
+            self.default_fixed_fee,
+            self.reference_amount,
+            json::map(&self.exchange_fees),
+            json::map(&self.exchange_maker_fees),
+            json::map(&self.exchange_fixed_fees),
+        )
+    }
+
+    /// Parse JSON object of the format `get_json` gives, missing fields have no fees.
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_json(item: &Value) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let mut fee_model = Self::new();
+
+        if let Some(fee) = optional_number(item, "fee", &mut errors) {
+            fee_model.set_default_fee(fee);
+        }
+        fee_model.set_default_maker_fee(optional_number(item, "maker_fee", &mut errors));
+        if let Some(fixed_fee) = optional_number(item, "fixed_fee", &mut errors) {
+            fee_model.set_default_fixed_fee(fixed_fee);
+        }
+        if let Some(reference_amount) = optional_number(item, "reference_amount", &mut errors) {
+            fee_model.set_reference_amount(reference_amount);
+        }
+        for (exchange, fee) in keyed_numbers(item, "exchange_fees", &mut errors) {
+            fee_model.set_exchange_fee(exchange, fee);
+        }
+        for (exchange, fee) in keyed_numbers(item, "exchange_maker_fees", &mut errors) {
+            fee_model.set_exchange_maker_fee(exchange, fee);
+        }
+        for (exchange, fixed_fee) in keyed_numbers(item, "exchange_fixed_fees", &mut errors) {
+            fee_model.set_exchange_fixed_fee(exchange, fixed_fee);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(fee_model)
+    }
+}
+
+impl<N, E> Default for FeeModel<N, E>
+where
+    N: IndexMapTrait,
+    E: Num + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::fee_model::ExecutionStyle::*;
+    use crate::algorithm::fee_model::{ExecutionStyle, FeeModel};
+
+    #[test]
+    fn get_trade_factor() {
+        let mut fee_model = FeeModel::<String, f32>::new();
+        assert!(fee_model.is_empty());
+
+        fee_model.set_default_fee(0.002);
+        fee_model.set_exchange_fee("KRAKEN".to_string(), 0.001);
+        assert!(!fee_model.is_empty());
+
+        // Test the exchange fee.
+        assert_eq!(
+            fee_model.get_trade_factor(&"KRAKEN".to_string(), Taker),
+            0.999
+        );
+
+        // Test the default fee.
+        assert_eq!(
+            fee_model.get_trade_factor(&"GDAX".to_string(), Taker),
+            0.998
+        );
+    }
+
+    #[test]
+    fn get_trade_factor_with_fixed_fee() {
+        let mut fee_model = FeeModel::<String, f32>::new();
+        fee_model.set_exchange_fixed_fee("KRAKEN".to_string(), 5.0);
+        fee_model.set_reference_amount(1000.0);
+        assert!(!fee_model.is_empty());
+
+        // Test the fixed fee is related to the reference amount.
+        assert_eq!(
+            fee_model.get_trade_factor(&"KRAKEN".to_string(), Taker),
+            0.995
+        );
+        assert_eq!(fee_model.get_trade_factor(&"GDAX".to_string(), Taker), 1.0);
+
+        // Test the percentage and the fixed fee combined.
+        fee_model.set_exchange_fee("KRAKEN".to_string(), 0.5);
+        assert_eq!(
+            fee_model.get_trade_factor(&"KRAKEN".to_string(), Taker),
+            0.4975
+        );
+    }
+
+    #[test]
+    fn get_trade_factor_with_maker_fee() {
+        let mut fee_model = FeeModel::<String, f32>::new();
+        fee_model.set_default_fee(0.002);
+        fee_model.set_exchange_maker_fee("KRAKEN".to_string(), 0.001);
+        assert!(fee_model.has_maker_fees());
+
+        // Test the maker fee and the fallback to the taker fee.
+        assert_eq!(
+            fee_model.get_trade_factor(&"KRAKEN".to_string(), Maker),
+            0.999
+        );
+        assert_eq!(
+            fee_model.get_trade_factor(&"KRAKEN".to_string(), Taker),
+            0.998
+        );
+        assert_eq!(
+            fee_model.get_trade_factor(&"GDAX".to_string(), Maker),
+            0.998
+        );
+
+        // Test the default maker fee, a negative one is a rebate.
+        fee_model.set_default_maker_fee(Some(-0.001));
+        assert_eq!(
+            fee_model.get_trade_factor(&"GDAX".to_string(), Maker),
+            1.001
+        );
+    }
+
+    #[test]
+    fn parse_execution_style() {
+        assert_eq!(ExecutionStyle::parse("maker"), Some(Maker));
+        assert_eq!(ExecutionStyle::parse("TAKER"), Some(Taker));
+        assert_eq!(ExecutionStyle::parse("1.5"), None);
+        assert_eq!(ExecutionStyle::default(), Taker);
+    }
+}
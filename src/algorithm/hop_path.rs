@@ -0,0 +1,148 @@
+//! Best rate path of a limited count of edges.
+//!
+//! The Floyd-Warshall result holds the best path regardless of its length, so the best rates of
+//! paths from the source are relaxed Bellman-Ford style instead, a round per edge.
+
+use crate::algorithm::Algorithm;
+use crate::response::best_rate_path::BestRatePath;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use indexmap::IndexMap;
+use num_traits::Num;
+use safe_graph::NodeTrait;
+use std::fmt::{Debug, Display};
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+/// The best rate of reaching a node together with the path of it.
+type HopLabel<I, E> = (E, Vec<(I, I)>);
+
+impl<N, E, I> Algorithm<N, E, I>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+    I: NodeTrait + Num + AddAssign,
+{
+    /// Find the best rate path of at most `max_hops` edges between `(exchange, currency)` nodes.
+    pub(super) fn find_best_hop_path(
+        &self,
+        source: (&N, &N),
+        destination: (&N, &N),
+        max_hops: usize,
+    ) -> Option<BestRatePath<N, E>> {
+        // Prepare indexes, not known nodes can't have any path.
+        let a = (
+            self.get_node_index(source.0)?,
+            self.get_node_index(source.1)?,
+        );
+        let b = (
+            self.get_node_index(destination.0)?,
+            self.get_node_index(destination.1)?,
+        );
+
+        let epsilon = *self.settings.get_comparison_epsilon();
+        let mut rates: IndexMap<(I, I), HopLabel<I, E>> = IndexMap::new();
+        rates.insert(a, (E::one(), vec![a]));
+
+        for _ in 0..max_hops {
+            // Paths of the previous round are extended only, so each round adds a single edge.
+            let previous = rates.clone();
+            let mut changed = false;
+
+            for (x, y, rate) in self.graph.all_edges() {
+                let (x_rate, x_path) = match previous.get(&x) {
+                    Some(x_best) => x_best,
+                    None => continue,
+                };
+                if x_path.contains(&y) {
+                    continue;
+                }
+
+                let y_rate = *x_rate * *rate;
+                if rates
+                    .get(&y)
+                    .is_none_or(|(known_rate, _)| y_rate > *known_rate + epsilon)
+                {
+                    let mut path = x_path.clone();
+                    path.push(y);
+                    rates.insert(y, (y_rate, path));
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        // The same as by Floyd-Warshall, a node has no path to itself.
+        let (rate, path) = rates.swap_remove(&b).filter(|(_, path)| path.len() > 1)?;
+
+        Some(self.form_best_rate_path(rate, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::{Backend, Settings};
+    use crate::algorithm::Algorithm;
+    use crate::request::Request;
+    use crate::response::unanswered_request::Reason::*;
+    use std::io::BufReader;
+
+    fn prepare_request() -> Request<String, f32> {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001\n\
+                          2019-01-20T09:42:23+00:00 E1 BTC EUR 1000 0.001\n\
+                          2019-01-20T09:42:23+00:00 E1 EUR USD 1.2 0.8\n\
+                          EXCHANGE_RATE_REQUEST E1 BTC E1 USD\n";
+
+        Request::read_from(&mut BufReader::new(text_input.as_bytes()))
+    }
+
+    #[test]
+    fn find_best_hop_path() {
+        let request = prepare_request();
+        let mut settings = Settings::new();
+
+        for backend in &[Backend::FloydWarshall, Backend::BellmanFord] {
+            settings.set_backend(*backend);
+
+            // Test that the best path of all is found without any limit.
+            settings.set_max_hops(None);
+            let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+            let best_rate_path = &response.get_best_rate_paths()[0];
+            assert_eq!(*best_rate_path.get_rate(), 1200.0);
+            assert_eq!(best_rate_path.get_path().len(), 3);
+
+            // Test that a worse path is found within the limit.
+            settings.set_max_hops(Some(1));
+            let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+            let best_rate_path = &response.get_best_rate_paths()[0];
+            assert_eq!(*best_rate_path.get_rate(), 1000.0);
+            assert_eq!(best_rate_path.get_path().len(), 2);
+        }
+    }
+
+    #[test]
+    fn find_best_hop_path_none() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC EUR 1000 0.001\n\
+                          2019-01-20T09:42:23+00:00 E1 EUR USD 1.2 0.8\n\
+                          EXCHANGE_RATE_REQUEST E1 BTC E1 USD\n";
+        let request = Request::read_from(&mut BufReader::new(text_input.as_bytes()));
+        let mut settings = Settings::new();
+        settings.set_max_hops(Some(1));
+
+        // Test that the request is unanswered if the only path is too long.
+        for backend in &[Backend::FloydWarshall, Backend::BellmanFord] {
+            settings.set_backend(*backend);
+            let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+            assert!(response.get_best_rate_paths().is_empty());
+            assert_eq!(
+                response.get_unanswered_requests()[0].get_reason(),
+                NoPathWithinHops
+            );
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Best rate paths between all nodes of a graph.
+
+use crate::hash::NodeHashBuilder;
+use floyd_warshall_alg::FloydWarshallResult;
+use indexmap::IndexSet;
+use safe_graph::{Graph, NodeTrait};
+
+/// `Paths` structure.
+///
+/// The best rate and the next node on the best path of each pair of nodes connected by a path,
+/// as solved by a `Solver`. Nodes are numbered by their position among the nodes of the graph,
+/// rates and next nodes are held in dense matrices addressed by the positions of the start and
+/// end node, so a lookup is an array read once the positions are known.
+///
+/// # `Paths<I, E>` is parameterized over:
+///
+/// - Index `I` for indexing of nodes.
+/// - Edge weight `E`.
+pub struct Paths<I, E> {
+    nodes: IndexSet<(I, I), NodeHashBuilder>,
+    rates: Vec<Option<E>>,
+    next: Vec<Option<usize>>,
+}
+
+impl<I, E> Paths<I, E>
+where
+    I: NodeTrait,
+    E: Copy,
+{
+    /// Create a new instance of `Paths` structure of the nodes of the graph, without any path.
+    pub fn new(graph: &Graph<(I, I), E>) -> Self {
+        Self::of_nodes(graph.nodes().collect())
+    }
+
+    /// Create `Paths` of the edges of the graph, each edge being the path of its nodes.
+    pub fn from_edges(graph: &Graph<(I, I), E>) -> Self {
+        let mut paths = Self::new(graph);
+        for (a, b, &rate) in graph.all_edges() {
+            paths.set_path(a, b, rate, b);
+        }
+
+        paths
+    }
+
+    fn of_nodes(nodes: IndexSet<(I, I), NodeHashBuilder>) -> Self {
+        let count = nodes.len();
+
+        Self {
+            nodes,
+            rates: vec![None; count * count],
+            next: vec![None; count * count],
+        }
+    }
+
+    /// Get count of the nodes.
+    pub fn get_node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Get position of the node, `None` if it's not a node of the graph.
+    pub fn get_position(&self, node: (I, I)) -> Option<usize> {
+        self.nodes.get_index_of(&node)
+    }
+
+    /// Get the node at the position.
+    pub fn get_node(&self, position: usize) -> Option<(I, I)> {
+        self.nodes.get_index(position).copied()
+    }
+
+    /// Get rate of the best path from the node `a` to the node `b`.
+    pub fn get_path_rate(&self, a: (I, I), b: (I, I)) -> Option<&E> {
+        let cell = self.get_cell(a, b)?;
+        self.rates[cell].as_ref()
+    }
+
+    /// Get the node following the node `a` on the best path to the node `b`.
+    pub fn get_next(&self, a: (I, I), b: (I, I)) -> Option<(I, I)> {
+        let cell = self.get_cell(a, b)?;
+        self.next[cell].and_then(|position| self.get_node(position))
+    }
+
+    /// Set the best path from the node `a` to the node `b`, its rate and the node following `a`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the nodes isn't a node of the graph.
+    pub fn set_path(&mut self, a: (I, I), b: (I, I), rate: E, next: (I, I)) {
+        let position = |node| {
+            self.get_position(node)
+                .expect("The node of the path is a node of the graph!")
+        };
+        let (a, b, next) = (position(a), position(b), position(next));
+
+        self.set_at(a, b, rate, next);
+    }
+
+    /// Collect nodes of the best path from the node `a` to the node `b`.
+    ///
+    /// The nodes start with `a` and end with `b`, they're empty if there's no path. A path looping
+    /// forever, possible in presence of arbitrage cycles, is cut once it has more nodes than the
+    /// graph.
+    pub fn collect_path_nodes(&self, a: (I, I), b: (I, I)) -> Vec<(I, I)> {
+        let (start, end) = match (self.get_position(a), self.get_position(b)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return vec![],
+        };
+        let mut next = match self.get_next_at(start, end) {
+            Some(next) => next,
+            None => return vec![],
+        };
+
+        let mut nodes = vec![a];
+        while next != end {
+            nodes.push(self.nodes[next]);
+
+            // Break out of a loop, it would never reach the end node.
+            if nodes.len() > self.nodes.len() {
+                break;
+            }
+
+            match self.get_next_at(next, end) {
+                Some(new_next) => next = new_next,
+                None => break,
+            }
+        }
+        nodes.push(b);
+
+        nodes
+    }
+
+    /// Get rate of the best path between the nodes at the positions.
+    pub(crate) fn get_rate_at(&self, a: usize, b: usize) -> Option<E> {
+        self.rates[a * self.nodes.len() + b]
+    }
+
+    /// Get position of the node following the one at the position `a` on the best path to `b`.
+    pub(crate) fn get_next_at(&self, a: usize, b: usize) -> Option<usize> {
+        self.next[a * self.nodes.len() + b]
+    }
+
+    /// Set the best path between the nodes at the positions.
+    pub(crate) fn set_at(&mut self, a: usize, b: usize, rate: E, next: usize) {
+        let cell = a * self.nodes.len() + b;
+        self.rates[cell] = Some(rate);
+        self.next[cell] = Some(next);
+    }
+
+    fn get_cell(&self, a: (I, I), b: (I, I)) -> Option<usize> {
+        Some(self.get_position(a)? * self.nodes.len() + self.get_position(b)?)
+    }
+}
+
+impl<I, E> From<FloydWarshallResult<(I, I), E>> for Paths<I, E>
+where
+    I: NodeTrait,
+    E: Copy,
+{
+    /// Convert the result of `floyd-warshall-alg`, e.g. for a `Solver` built on it.
+    fn from(result: FloydWarshallResult<(I, I), E>) -> Self {
+        let nodes = result.path.nodes().chain(result.next.nodes()).collect();
+        let mut paths = Self::of_nodes(nodes);
+        for (a, b, &rate) in result.path.all_edges() {
+            if let Some(&next) = result.next.edge_weight(a, b) {
+                paths.set_path(a, b, rate, next);
+            }
+        }
+
+        paths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::paths::Paths;
+    use floyd_warshall_alg::FloydWarshallResult;
+    use safe_graph::Graph;
+
+    #[test]
+    fn collect_path_nodes() {
+        let mut graph = Graph::new();
+        graph.add_edge((0, 1), (0, 2), 1.2_f32);
+        graph.add_edge((0, 2), (0, 3), 0.2);
+        graph.add_edge((0, 1), (0, 4), 4.2);
+
+        let mut paths = Paths::from_edges(&graph);
+        assert_eq!(paths.get_node_count(), 4);
+        assert_eq!(paths.get_path_rate((0, 1), (0, 2)), Some(&1.2));
+        assert_eq!(
+            paths.collect_path_nodes((0, 1), (0, 2)),
+            vec![(0, 1), (0, 2)]
+        );
+
+        // Test that a path is collected by its next nodes.
+        paths.set_path((0, 1), (0, 3), 0.24, (0, 2));
+        assert_eq!(paths.get_next((0, 1), (0, 3)), Some((0, 2)));
+        assert_eq!(
+            paths.collect_path_nodes((0, 1), (0, 3)),
+            vec![(0, 1), (0, 2), (0, 3)]
+        );
+
+        // Test that pairs without a path and unknown nodes have no path.
+        assert_eq!(paths.get_path_rate((0, 3), (0, 1)), None);
+        assert!(paths.collect_path_nodes((0, 3), (0, 1)).is_empty());
+        assert_eq!(paths.get_path_rate((0, 1), (9, 9)), None);
+        assert!(paths.collect_path_nodes((9, 9), (0, 1)).is_empty());
+    }
+
+    #[test]
+    fn from_floyd_warshall_result() {
+        let mut path = Graph::new();
+        let mut next = Graph::new();
+        path.add_edge((0, 1), (0, 2), 1.2_f32);
+        path.add_edge((0, 1), (0, 3), 0.24);
+        path.add_edge((0, 2), (0, 3), 0.2);
+        next.add_edge((0, 1), (0, 2), (0, 2));
+        next.add_edge((0, 1), (0, 3), (0, 2));
+        next.add_edge((0, 2), (0, 3), (0, 3));
+
+        let result = FloydWarshallResult { path, next };
+        let paths = Paths::from(FloydWarshallResult {
+            path: result.path.clone(),
+            next: result.next.clone(),
+        });
+
+        // Test that the converted paths equal the result.
+        assert_eq!(
+            paths.get_path_rate((0, 1), (0, 3)),
+            result.get_path_rate((0, 1), (0, 3))
+        );
+        assert_eq!(
+            paths.collect_path_nodes((0, 1), (0, 3)),
+            result.collect_path_nodes((0, 1), (0, 3))
+        );
+    }
+}
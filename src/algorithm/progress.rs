@@ -0,0 +1,129 @@
+//! Progress of solving the best rate paths between all nodes.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Callback the `Progress` is reported to.
+type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+/// `Progress` structure.
+///
+/// Snapshot of a running Floyd-Warshall solve, taken once an iteration over the intermediate
+/// node `k` is finished.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    iteration: usize,
+    iterations: usize,
+    elapsed: Duration,
+}
+
+impl Progress {
+    /// Create a new instance of `Progress` structure.
+    pub fn new(iteration: usize, iterations: usize, elapsed: Duration) -> Self {
+        Self {
+            iteration,
+            iterations,
+            elapsed,
+        }
+    }
+
+    /// Get count of the finished iterations.
+    pub fn get_iteration(&self) -> usize {
+        self.iteration
+    }
+
+    /// Get count of all iterations, one per node of the graph.
+    pub fn get_iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Get time elapsed since the solve started.
+    pub fn get_elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Get estimated time left until the solve is finished, all iterations take about the same.
+    pub fn get_eta(&self) -> Duration {
+        if self.iteration == 0 {
+            return Duration::default();
+        }
+
+        self.elapsed
+            .mul_f64((self.iterations - self.iteration) as f64 / self.iteration as f64)
+    }
+
+    /// Get printable output representing the Progress.
+    ///
+    /// # Format
+    ///
+    /// SOLVE_PROGRESS <iteration> <iterations> <elapsed_ms> <eta_ms>
+    pub fn get_output(&self) -> String {
+        format!(
+            "SOLVE_PROGRESS <{}> <{}> <{}> <{}>\n",
+            self.iteration,
+            self.iterations,
+            self.elapsed.as_millis(),
+            self.get_eta().as_millis()
+        )
+    }
+}
+
+/// `ProgressReporter` structure.
+///
+/// Reports the `Progress` of solves to the callback, at most once per the interval, so a solve
+/// of a large graph doesn't look like a hang. Solves finished within the interval aren't
+/// reported at all.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    interval: Duration,
+    callback: ProgressCallback,
+}
+
+impl ProgressReporter {
+    /// Create a new instance of `ProgressReporter` structure.
+    pub fn new<F>(interval: Duration, callback: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        Self {
+            interval,
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Get the least time between two reports.
+    pub fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Pass the `Progress` to the callback.
+    pub fn report(&self, progress: &Progress) {
+        (self.callback)(progress);
+    }
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::progress::Progress;
+    use std::time::Duration;
+
+    #[test]
+    fn get_output() {
+        let progress = Progress::new(250, 1000, Duration::from_secs(10));
+
+        assert_eq!(progress.get_eta(), Duration::from_secs(30));
+        assert_eq!(
+            progress.get_output(),
+            "SOLVE_PROGRESS <250> <1000> <10000> <30000>\n"
+        );
+    }
+}
@@ -0,0 +1,494 @@
+//! Exchange Rate Path algorithm Settings.
+
+use crate::algorithm::fee_model::FeeModel;
+use crate::algorithm::progress::ProgressReporter;
+use crate::algorithm::slippage_model::SlippageModel;
+use crate::algorithm::trading_rules::TradingRules;
+use crate::algorithm::transfer_model::TransferModel;
+use crate::json;
+#[cfg(feature = "std")]
+use crate::json::value::{
+    field, items, optional_boolean, optional_identifier, optional_number, Value,
+};
+use crate::IndexMapTrait;
+use num_traits::Num;
+use std::cmp::Ordering::{Greater, Less};
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The algorithm finding best rate paths.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Backend {
+    /// Paths between all nodes are solved at once, before any request is answered.
+    #[default]
+    FloydWarshall,
+    /// Paths from the source node are searched for each request separately, paying off for
+    /// few requests over a large graph. Arbitrages are still found by Floyd-Warshall.
+    BellmanFord,
+}
+
+impl Backend {
+    pub fn get_label(&self) -> String {
+        match self {
+            Backend::FloydWarshall => "floyd-warshall".to_string(),
+            Backend::BellmanFord => "bellman-ford".to_string(),
+        }
+    }
+
+    /// Parse the backend from its label, ignoring the case.
+    pub fn parse(label: &str) -> Option<Self> {
+        let label = label.to_lowercase();
+
+        if label == Backend::FloydWarshall.get_label() {
+            Some(Backend::FloydWarshall)
+        } else if label == Backend::BellmanFord.get_label() {
+            Some(Backend::BellmanFord)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+/// How weights of edges combine along a path and which path is the best, in the Floyd-Warshall
+/// solve.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum PathSemantics {
+    /// Rates are multiplied along a path, the greatest rate is the best.
+    #[default]
+    BestRate,
+    /// Weights are added along a path, the least sum is the best, e.g. for costs of the edges.
+    MinCost,
+}
+
+impl PathSemantics {
+    pub fn get_label(&self) -> String {
+        match self {
+            PathSemantics::BestRate => "best-rate".to_string(),
+            PathSemantics::MinCost => "min-cost".to_string(),
+        }
+    }
+
+    /// Parse the semantics from its label, ignoring the case.
+    pub fn parse(label: &str) -> Option<Self> {
+        let label = label.to_lowercase();
+
+        if label == PathSemantics::BestRate.get_label() {
+            Some(PathSemantics::BestRate)
+        } else if label == PathSemantics::MinCost.get_label() {
+            Some(PathSemantics::MinCost)
+        } else {
+            None
+        }
+    }
+
+    /// Combine the weight of a path with the weight of the path following it.
+    pub fn combine<E: Num>(&self, a: E, b: E) -> E {
+        match self {
+            PathSemantics::BestRate => a * b,
+            PathSemantics::MinCost => a + b,
+        }
+    }
+
+    /// Check whether the weight of a path is better than the known one by more than the epsilon.
+    /// Weights not comparable, e.g. NaN, are never better.
+    pub fn is_better<E: Num + PartialOrd + Copy>(&self, weight: E, known: E, epsilon: E) -> bool {
+        match self {
+            PathSemantics::BestRate => {
+                weight.partial_cmp(&(known + epsilon)).unwrap_or(Less) == Greater
+            }
+            PathSemantics::MinCost => {
+                weight.partial_cmp(&(known - epsilon)).unwrap_or(Greater) == Less
+            }
+        }
+    }
+}
+
+impl fmt::Display for PathSemantics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+/// Exchange Rate Path algorithm `Settings` structure.
+///
+/// # `Settings<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+#[derive(Clone, Debug)]
+pub struct Settings<N, E> {
+    min_profit_factor: E,
+    max_cycle_length: usize,
+    alert_debounce: usize,
+    transfer_penalty: E,
+    quote_half_life: Option<E>,
+    discard_loops: bool,
+    path_semantics: PathSemantics,
+    comparison_epsilon: E,
+    max_hops: Option<usize>,
+    backend: Backend,
+    funding: Vec<(Option<N>, Option<N>)>,
+    fee_model: FeeModel<N, E>,
+    transfer_model: TransferModel<N, E>,
+    slippage_model: SlippageModel<N, E>,
+    trading_rules: TradingRules<N, E>,
+    progress_reporter: Option<ProgressReporter>,
+}
+
+impl<N, E> Settings<N, E>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy,
+{
+    /// The default maximal count of nodes of an enumerated cycle.
+    pub const DEFAULT_MAX_CYCLE_LENGTH: usize = 4;
+
+    /// Create a new instance of `Settings` structure with default values.
+    pub fn new() -> Self {
+        Self {
+            min_profit_factor: E::one(),
+            max_cycle_length: Self::DEFAULT_MAX_CYCLE_LENGTH,
+            alert_debounce: 0,
+            transfer_penalty: E::one(),
+            quote_half_life: None,
+            discard_loops: true,
+            path_semantics: PathSemantics::default(),
+            comparison_epsilon: E::zero(),
+            max_hops: None,
+            backend: Backend::default(),
+            funding: Vec::new(),
+            fee_model: FeeModel::new(),
+            transfer_model: TransferModel::new(),
+            slippage_model: SlippageModel::new(),
+            trading_rules: TradingRules::new(),
+            progress_reporter: None,
+        }
+    }
+
+    /// Set the profit factor an `Arbitrage` must exceed to be reported, e.g. `1.002`.
+    pub fn set_min_profit_factor(&mut self, min_profit_factor: E) {
+        self.min_profit_factor = min_profit_factor;
+    }
+
+    pub fn get_min_profit_factor(&self) -> &E {
+        &self.min_profit_factor
+    }
+
+    /// Set the maximal count of nodes of a cycle when enumerating all arbitrages.
+    pub fn set_max_cycle_length(&mut self, max_cycle_length: usize) {
+        self.max_cycle_length = max_cycle_length;
+    }
+
+    pub fn get_max_cycle_length(&self) -> usize {
+        self.max_cycle_length
+    }
+
+    /// Set the count of additional consecutive evaluations an arbitrage opportunity change must
+    /// persist for before it's alerted, to avoid flapping alerts.
+    pub fn set_alert_debounce(&mut self, alert_debounce: usize) {
+        self.alert_debounce = alert_debounce;
+    }
+
+    pub fn get_alert_debounce(&self) -> usize {
+        self.alert_debounce
+    }
+
+    /// Set the factor each cross-exchange transfer of a best rate path is penalized by, e.g.
+    /// `0.995`. A path with less transfers is preferred unless the rate of the other one is
+    /// better even after the penalty. Answered rates don't include the penalty.
+    pub fn set_transfer_penalty(&mut self, transfer_penalty: E) {
+        self.transfer_penalty = transfer_penalty;
+    }
+
+    pub fn get_transfer_penalty(&self) -> &E {
+        &self.transfer_penalty
+    }
+
+    /// Check whether cross-exchange transfers are penalized.
+    pub fn has_transfer_penalty(&self) -> bool {
+        !self.transfer_penalty.is_one()
+    }
+
+    /// Set the age in seconds a quote's edges lose half of their attractiveness at, `None` for no
+    /// decay. An edge of a quote of the age `a` is weighted by `h / (h + a)` of the half-life `h`,
+    /// the age measured to the most recent quote, so a fresher quote wins a tie and a very old one
+    /// is avoided unless it's much better. Answered rates don't include the decay.
+    pub fn set_quote_half_life(&mut self, quote_half_life: Option<E>) {
+        self.quote_half_life = quote_half_life;
+    }
+
+    pub fn get_quote_half_life(&self) -> Option<&E> {
+        self.quote_half_life.as_ref()
+    }
+
+    /// Check whether edges of older quotes are decayed.
+    pub fn has_quote_decay(&self) -> bool {
+        self.quote_half_life.is_some()
+    }
+
+    /// Set whether Floyd-Warshall discards paths through their own start or end node.
+    pub fn set_discard_loops(&mut self, discard_loops: bool) {
+        self.discard_loops = discard_loops;
+    }
+
+    pub fn get_discard_loops(&self) -> bool {
+        self.discard_loops
+    }
+
+    /// Set how weights of edges combine along a path and which path is the best in the
+    /// Floyd-Warshall solve, e.g. `PathSemantics::MinCost` for min-cost routing on the same
+    /// graph. Transfer penalty, quote decay, arbitrages and the paths searched per request, of the
+    /// `BellmanFord` backend, a hop limit, an amount or a time budget, assume the default
+    /// `PathSemantics::BestRate`.
+    pub fn set_path_semantics(&mut self, path_semantics: PathSemantics) {
+        self.path_semantics = path_semantics;
+    }
+
+    pub fn get_path_semantics(&self) -> PathSemantics {
+        self.path_semantics
+    }
+
+    /// Set the difference a path's rate must exceed the known one by to replace it, so rates
+    /// differing by rounding errors only don't change the path.
+    pub fn set_comparison_epsilon(&mut self, comparison_epsilon: E) {
+        self.comparison_epsilon = comparison_epsilon;
+    }
+
+    pub fn get_comparison_epsilon(&self) -> &E {
+        &self.comparison_epsilon
+    }
+
+    /// Set the maximal count of edges of a best rate path, `None` for no limit. Requests with
+    /// an amount or a time budget aren't limited.
+    pub fn set_max_hops(&mut self, max_hops: Option<usize>) {
+        self.max_hops = max_hops;
+    }
+
+    pub fn get_max_hops(&self) -> Option<usize> {
+        self.max_hops
+    }
+
+    /// Set the algorithm finding best rate paths.
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+    }
+
+    pub fn get_backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Set the funding `(exchange, currency)` nodes an arbitrage cycle must pass through one of,
+    /// `None` stands for any exchange or any currency. No funding nodes means no restriction.
+    pub fn set_funding(&mut self, funding: Vec<(Option<N>, Option<N>)>) {
+        self.funding = funding;
+    }
+
+    pub fn get_funding(&self) -> &[(Option<N>, Option<N>)] {
+        &self.funding
+    }
+
+    pub fn set_fee_model(&mut self, fee_model: FeeModel<N, E>) {
+        self.fee_model = fee_model;
+    }
+
+    pub fn get_fee_model(&self) -> &FeeModel<N, E> {
+        &self.fee_model
+    }
+
+    pub fn get_fee_model_mut(&mut self) -> &mut FeeModel<N, E> {
+        &mut self.fee_model
+    }
+
+    pub fn set_transfer_model(&mut self, transfer_model: TransferModel<N, E>) {
+        self.transfer_model = transfer_model;
+    }
+
+    pub fn get_transfer_model(&self) -> &TransferModel<N, E> {
+        &self.transfer_model
+    }
+
+    pub fn get_transfer_model_mut(&mut self) -> &mut TransferModel<N, E> {
+        &mut self.transfer_model
+    }
+
+    pub fn set_slippage_model(&mut self, slippage_model: SlippageModel<N, E>) {
+        self.slippage_model = slippage_model;
+    }
+
+    pub fn get_slippage_model(&self) -> &SlippageModel<N, E> {
+        &self.slippage_model
+    }
+
+    pub fn get_slippage_model_mut(&mut self) -> &mut SlippageModel<N, E> {
+        &mut self.slippage_model
+    }
+
+    pub fn set_trading_rules(&mut self, trading_rules: TradingRules<N, E>) {
+        self.trading_rules = trading_rules;
+    }
+
+    pub fn get_trading_rules(&self) -> &TradingRules<N, E> {
+        &self.trading_rules
+    }
+
+    pub fn get_trading_rules_mut(&mut self) -> &mut TradingRules<N, E> {
+        &mut self.trading_rules
+    }
+
+    /// Set the `ProgressReporter` of Floyd-Warshall solves, `None` for no reports. It's not a
+    /// part of the JSON representation.
+    pub fn set_progress_reporter(&mut self, progress_reporter: Option<ProgressReporter>) {
+        self.progress_reporter = progress_reporter;
+    }
+
+    pub fn get_progress_reporter(&self) -> Option<&ProgressReporter> {
+        self.progress_reporter.as_ref()
+    }
+}
+
+impl<N, E> Settings<N, E>
+where
+    N: IndexMapTrait + Clone + Display + FromStr,
+    E: Num + Copy + Display + FromStr,
+{
+    /// Get JSON representation of the `Settings`, in the format `parse_json` accepts.
+    ///
+    /// # Format
+    ///
+    /// {"min_profit_factor":<number>,"max_cycle_length":<count>,"alert_debounce":<count>,
+    /// "transfer_penalty":<number>,"quote_half_life":<number or null>,"discard_loops":<boolean>,
+    /// "path_semantics":<semantics>,"comparison_epsilon":<number>,"max_hops":<count or null>,"backend":<backend>,"funding":[{"exchange":<exchange or null>,
+    /// "currency":<currency or null>}, ...],"fees":<fee model>,"transfers":<transfer model>,
+    /// "slippage":<slippage model>,"trading_rules":<trading rules>}
+    ///
+    /// A `null` funding identifier stands for any exchange or any currency.
+    pub fn get_json(&self) -> String {
+        let funding: Vec<String> = self
+            .funding
+            .iter()
+            .map(|(exchange, currency)| {
+                format!(
+                    "{{\"exchange\":{},\"currency\":{}}}",
+                    json::option(exchange.as_ref().map(json::quote)),
+                    json::option(currency.as_ref().map(json::quote))
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"min_profit_factor\":{},\"max_cycle_length\":{},\"alert_debounce\":{},\
+             \"transfer_penalty\":{},\"quote_half_life\":{},\"discard_loops\":{},\
+             \"path_semantics\":{},\"comparison_epsilon\":{},\"max_hops\":{},\"backend\":{},\"funding\":[{}],\"fees\":{},\
+             \"transfers\":{},\"slippage\":{},\"trading_rules\":{}}}",
+            self.min_profit_factor,
+            self.max_cycle_length,
+            self.alert_debounce,
+            self.transfer_penalty,
+            json::option(self.quote_half_life),
+            self.discard_loops,
+            json::quote(&self.path_semantics),
+            self.comparison_epsilon,
+            json::option(self.max_hops),
+            json::quote(&self.backend),
+            funding.join(","),
+            self.fee_model.get_json(),
+            self.transfer_model.get_json(),
+            self.slippage_model.get_json(),
+            self.trading_rules.get_json(),
+        )
+    }
+
+    /// Parse JSON object of the format `get_json` gives, missing fields have default values.
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_json(item: &Value) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let mut settings = Self::new();
+
+        if let Some(min_profit_factor) = optional_number(item, "min_profit_factor", &mut errors) {
+            settings.set_min_profit_factor(min_profit_factor);
+        }
+        if let Some(max_cycle_length) = optional_number(item, "max_cycle_length", &mut errors) {
+            settings.set_max_cycle_length(max_cycle_length);
+        }
+        if let Some(alert_debounce) = optional_number(item, "alert_debounce", &mut errors) {
+            settings.set_alert_debounce(alert_debounce);
+        }
+        if let Some(transfer_penalty) = optional_number(item, "transfer_penalty", &mut errors) {
+            settings.set_transfer_penalty(transfer_penalty);
+        }
+        settings.set_quote_half_life(optional_number(item, "quote_half_life", &mut errors));
+        if let Some(discard_loops) = optional_boolean(item, "discard_loops", &mut errors) {
+            settings.set_discard_loops(discard_loops);
+        }
+        if item.get("path_semantics").is_some() {
+            if let Some(semantics) =
+                field(item, "path_semantics", &mut errors, PathSemantics::parse)
+            {
+                settings.set_path_semantics(semantics);
+            }
+        }
+        if let Some(epsilon) = optional_number(item, "comparison_epsilon", &mut errors) {
+            settings.set_comparison_epsilon(epsilon);
+        }
+        settings.set_max_hops(optional_number(item, "max_hops", &mut errors));
+        if item.get("backend").is_some() {
+            if let Some(backend) = field(item, "backend", &mut errors, Backend::parse) {
+                settings.set_backend(backend);
+            }
+        }
+        settings.set_funding(items(item, "funding", &mut errors, |item, errors| {
+            Some((
+                optional_identifier(item, "exchange", errors),
+                optional_identifier(item, "currency", errors),
+            ))
+        }));
+
+        if let Some(item) = item.get("fees") {
+            match FeeModel::parse_json(item) {
+                Ok(fee_model) => settings.set_fee_model(fee_model),
+                Err(model_errors) => errors.extend(model_errors),
+            }
+        }
+        if let Some(item) = item.get("transfers") {
+            match TransferModel::parse_json(item) {
+                Ok(transfer_model) => settings.set_transfer_model(transfer_model),
+                Err(model_errors) => errors.extend(model_errors),
+            }
+        }
+        if let Some(item) = item.get("slippage") {
+            match SlippageModel::parse_json(item) {
+                Ok(slippage_model) => settings.set_slippage_model(slippage_model),
+                Err(model_errors) => errors.extend(model_errors),
+            }
+        }
+        if let Some(item) = item.get("trading_rules") {
+            match TradingRules::parse_json(item) {
+                Ok(trading_rules) => settings.set_trading_rules(trading_rules),
+                Err(model_errors) => errors.extend(model_errors),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(settings)
+    }
+}
+
+impl<N, E> Default for Settings<N, E>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,213 @@
+//! Order Size Slippage Model.
+
+use crate::json;
+#[cfg(feature = "std")]
+use crate::json::value::{identifier, items, keyed_numbers, number, optional_number, Value};
+use crate::IndexMapTrait;
+use indexmap::IndexMap;
+use num_traits::Num;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Order size `SlippageModel` structure.
+///
+/// Holds liquidity depths of trades, in units of the traded (source) currency. The effective
+/// rate of trading an amount degrades with its size like the one of a constant product market:
+/// `rate * depth / (depth + amount)`. Trades without any depth have no slippage.
+///
+/// Depth of a specific `(exchange, source_currency, destination_currency)` trade takes
+/// precedence over the exchange depth, which takes precedence over the default depth.
+///
+/// # `SlippageModel<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+#[derive(Clone, Debug)]
+pub struct SlippageModel<N, E> {
+    default_depth: Option<E>,
+    exchange_depths: IndexMap<N, E>,
+    trade_depths: IndexMap<(N, N, N), E>,
+}
+
+impl<N, E> SlippageModel<N, E>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy,
+{
+    /// Create a new instance of `SlippageModel` structure without any slippage.
+    pub fn new() -> Self {
+        Self {
+            default_depth: None,
+            exchange_depths: IndexMap::new(),
+            trade_depths: IndexMap::new(),
+        }
+    }
+
+    /// Set depth of all trades without their own or their exchange depth.
+    pub fn set_default_depth(&mut self, depth: Option<E>) {
+        self.default_depth = depth;
+    }
+
+    /// Set depth of all trades of the exchange without their own depth.
+    pub fn set_exchange_depth(&mut self, exchange: N, depth: E) {
+        self.exchange_depths.insert(exchange, depth);
+    }
+
+    /// Set depth of trading the source currency for the destination one at the exchange.
+    pub fn set_trade_depth(
+        &mut self,
+        exchange: N,
+        source_currency: N,
+        destination_currency: N,
+        depth: E,
+    ) {
+        self.trade_depths
+            .insert((exchange, source_currency, destination_currency), depth);
+    }
+
+    /// Get depth of trading the source currency for the destination one at the exchange.
+    pub fn get_depth(
+        &self,
+        exchange: &N,
+        source_currency: &N,
+        destination_currency: &N,
+    ) -> Option<E> {
+        let trade = (
+            exchange.clone(),
+            source_currency.clone(),
+            destination_currency.clone(),
+        );
+
+        self.trade_depths
+            .get(&trade)
+            .or_else(|| self.exchange_depths.get(exchange))
+            .copied()
+            .or(self.default_depth)
+    }
+
+    /// Get amount received for trading the `amount` at the marginal `rate` with the `depth`.
+    pub fn get_received_amount(rate: E, amount: E, depth: Option<E>) -> E {
+        match depth {
+            Some(depth) => rate * amount * depth / (depth + amount),
+            None => rate * amount,
+        }
+    }
+}
+
+impl<N, E> SlippageModel<N, E>
+where
+    N: IndexMapTrait + Clone + Display + FromStr,
+    E: Num + Copy + Display + FromStr,
+{
+    /// Get JSON representation of the `SlippageModel`, in the format `parse_json` accepts.
+    ///
+    /// # Format
+    ///
+    /// {"depth":<number or null>,"exchange_depths":{<exchange>:<number>, ...},
+    /// "trade_depths":[{"exchange":<exchange>,"source_currency":<currency>,
+    /// "destination_currency":<currency>,"depth":<number>}, ...]}
+    pub fn get_json(&self) -> String {
+        let trade_depths: Vec<String> = self
+            .trade_depths
+            .iter()
+            .map(
+                |((exchange, source_currency, destination_currency), depth)| {
+                    format!(
+                        "{{\"exchange\":{},\"source_currency\":{},\"destination_currency\":{},\
+                     \"depth\":{}}}",
+                        json::quote(exchange),
+                        json::quote(source_currency),
+                        json::quote(destination_currency),
+                        depth
+                    )
+                },
+            )
+            .collect();
+
+        format!(
+            "{{\"depth\":{},\"exchange_depths\":{},\"trade_depths\":[{}]}}",
+            json::option(self.default_depth),
+            json::map(&self.exchange_depths),
+            trade_depths.join(",")
+        )
+    }
+
+    /// Parse JSON object of the format `get_json` gives, missing fields have no slippage.
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_json(item: &Value) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let mut slippage_model = Self::new();
+
+        slippage_model.set_default_depth(optional_number(item, "depth", &mut errors));
+        for (exchange, depth) in keyed_numbers(item, "exchange_depths", &mut errors) {
+            slippage_model.set_exchange_depth(exchange, depth);
+        }
+        let trade_depths = items(item, "trade_depths", &mut errors, |item, errors| {
+            match (
+                identifier(item, "exchange", errors),
+                identifier(item, "source_currency", errors),
+                identifier(item, "destination_currency", errors),
+                number(item, "depth", errors),
+            ) {
+                (Some(exchange), Some(source), Some(destination), Some(depth)) => {
+                    Some((exchange, source, destination, depth))
+                }
+                _ => None,
+            }
+        });
+        for (exchange, source, destination, depth) in trade_depths {
+            slippage_model.set_trade_depth(exchange, source, destination, depth);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(slippage_model)
+    }
+}
+
+impl<N, E> Default for SlippageModel<N, E>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::slippage_model::SlippageModel;
+
+    #[test]
+    fn get_depth() {
+        let mut slippage_model = SlippageModel::<String, f32>::new();
+        let (e1, e2) = ("E1".to_string(), "E2".to_string());
+        let (btc, usd) = ("BTC".to_string(), "USD".to_string());
+        assert_eq!(slippage_model.get_depth(&e1, &btc, &usd), None);
+
+        slippage_model.set_default_depth(Some(100.0));
+        slippage_model.set_exchange_depth(e1.clone(), 10.0);
+        slippage_model.set_trade_depth(e1.clone(), btc.clone(), usd.clone(), 1.0);
+
+        // Test precedence of trade, exchange and default depths.
+        assert_eq!(slippage_model.get_depth(&e1, &btc, &usd), Some(1.0));
+        assert_eq!(slippage_model.get_depth(&e1, &usd, &btc), Some(10.0));
+        assert_eq!(slippage_model.get_depth(&e2, &btc, &usd), Some(100.0));
+    }
+
+    #[test]
+    fn get_received_amount() {
+        // Test that the effective rate halves when the amount equals the depth.
+        assert_eq!(
+            SlippageModel::<String, f32>::get_received_amount(2.0, 10.0, Some(10.0)),
+            10.0
+        );
+        assert_eq!(
+            SlippageModel::<String, f32>::get_received_amount(2.0, 10.0, None),
+            20.0
+        );
+    }
+}
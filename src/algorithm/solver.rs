@@ -0,0 +1,452 @@
+//! Solvers of the best rate paths between all nodes of a graph.
+
+use crate::algorithm::cancellation::Cancellation;
+use crate::algorithm::paths::Paths;
+use crate::algorithm::progress::{Progress, ProgressReporter};
+use crate::algorithm::settings::PathSemantics;
+use crate::clock::Instant;
+use floyd_warshall_alg::FloydWarshallTrait;
+use safe_graph::{Graph, NodeTrait};
+use std::cmp::Ordering::Greater;
+
+/// `Solver` of the best rate paths between all `(exchange, currency)` nodes of a graph.
+///
+/// The result holds the best rate and the next node on the best path of each pair of nodes
+/// connected by a path. A backend other than Floyd-Warshall, e.g. Johnson's algorithm, forms it
+/// by `Paths::set_path` implementing this trait, or converts the `FloydWarshallResult` of
+/// `floyd-warshall-alg` into it.
+///
+/// # `Solver<I, E>` is parameterized over:
+///
+/// - Index `I` for indexing of nodes.
+/// - Edge weight `E`.
+pub trait Solver<I, E> {
+    /// Solve the best rate paths between all nodes of the graph, `None` if the computation is
+    /// cancelled meanwhile.
+    fn solve(&self, graph: &Graph<(I, I), E>, cancellation: &Cancellation) -> Option<Paths<I, E>>;
+}
+
+/// `FloydWarshallSolver` structure.
+///
+/// Solves the graph by the Floyd-Warshall algorithm, multiplying rates along the paths unless
+/// other `PathSemantics` are chosen. It runs the same as `FloydWarshall` of
+/// `floyd-warshall-alg`, but reports its progress to the `ProgressReporter` if there's one and
+/// stops once the `Cancellation` is cancelled, checked after each intermediate node.
+///
+/// # `FloydWarshallSolver<E>` is parameterized over:
+///
+/// - Edge weight `E`.
+pub struct FloydWarshallSolver<E> {
+    comparison_epsilon: E,
+    discard_loops: bool,
+    semantics: PathSemantics,
+    progress_reporter: Option<ProgressReporter>,
+}
+
+impl<E> FloydWarshallSolver<E> {
+    /// Create a new instance of `FloydWarshallSolver` structure.
+    ///
+    /// A path replaces the known one only if its rate is greater by more than the comparison
+    /// epsilon, paths through their own start or end node are discarded if `discard_loops` is set.
+    pub fn new(comparison_epsilon: E, discard_loops: bool) -> Self {
+        Self {
+            comparison_epsilon,
+            discard_loops,
+            semantics: PathSemantics::default(),
+            progress_reporter: None,
+        }
+    }
+
+    /// Combine and compare weights of paths by the `PathSemantics`.
+    pub fn with_semantics(mut self, semantics: PathSemantics) -> Self {
+        self.semantics = semantics;
+        self
+    }
+
+    /// Report the progress of solves to the `ProgressReporter`.
+    pub fn with_progress_reporter(mut self, progress_reporter: Option<ProgressReporter>) -> Self {
+        self.progress_reporter = progress_reporter;
+        self
+    }
+}
+
+impl<I, E> Solver<I, E> for FloydWarshallSolver<E>
+where
+    I: NodeTrait,
+    E: FloydWarshallTrait + 'static,
+{
+    fn solve(&self, graph: &Graph<(I, I), E>, cancellation: &Cancellation) -> Option<Paths<I, E>> {
+        let start = Instant::now();
+        let mut last_report = start;
+        let iterations = graph.node_count();
+
+        // Initialize the paths by the edges, the next step of each is its end node.
+        let mut paths = Paths::from_edges(graph);
+
+        // `k` is the intermediate node, `i` the start and `j` the end node of the path improved,
+        // all given by their positions among the nodes of the graph.
+        for k in 0..iterations {
+            for i in 0..iterations {
+                // Without a path `(i, k)` no path goes through `k`.
+                if paths.get_rate_at(i, k).is_none() {
+                    continue;
+                }
+
+                for j in 0..iterations {
+                    if self.discard_loops && (k == i || k == j || i == j) {
+                        continue;
+                    }
+
+                    let new_rate = match (paths.get_rate_at(i, k), paths.get_rate_at(k, j)) {
+                        (Some(left), Some(right)) => self.semantics.combine(left, right),
+                        _ => continue,
+                    };
+                    let improved = paths.get_rate_at(i, j).is_none_or(|old_rate| {
+                        self.semantics
+                            .is_better(new_rate, old_rate, self.comparison_epsilon)
+                    });
+
+                    if improved {
+                        // The path `(i, k)` is known, so is its next step.
+                        let direction = paths.get_next_at(i, k).unwrap();
+                        paths.set_at(i, j, new_rate, direction);
+                    }
+                }
+            }
+
+            if let Some(progress_reporter) = &self.progress_reporter {
+                if last_report.elapsed() >= progress_reporter.get_interval() {
+                    last_report = Instant::now();
+                    progress_reporter.report(&Progress::new(k + 1, iterations, start.elapsed()));
+                }
+            }
+
+            if cancellation.is_cancelled() {
+                return None;
+            }
+        }
+
+        Some(paths)
+    }
+}
+
+/// `BruteForceSolver` structure.
+///
+/// Reference solver enumerating every path without repeated nodes of at most the maximal count
+/// of edges, keeping the best rate of each pair of distinct nodes. It's exponential in the size
+/// of the graph, so it's meant for cross-checking other solvers on small graphs: with the count
+/// of nodes less one as the maximal count of edges, it finds the same best rates as
+/// `FloydWarshallSolver` on graphs without arbitrage cycles. Paths of a rate not comparable to
+/// others, e.g. NaN, are skipped.
+pub struct BruteForceSolver {
+    max_edges: usize,
+}
+
+impl BruteForceSolver {
+    /// Create a new instance of `BruteForceSolver` structure.
+    pub fn new(max_edges: usize) -> Self {
+        Self { max_edges }
+    }
+
+    /// Extend the path of the nodes and the rate by every edge leading out of its last node.
+    fn extend<I, E>(
+        &self,
+        graph: &Graph<(I, I), E>,
+        nodes: &mut Vec<(I, I)>,
+        rate: E,
+        result: &mut Paths<I, E>,
+    ) where
+        I: NodeTrait,
+        E: FloydWarshallTrait,
+    {
+        let start = nodes[0];
+        let last = nodes[nodes.len() - 1];
+
+        for (_, node, &edge_rate) in graph.edges(last) {
+            if nodes.contains(&node) {
+                continue;
+            }
+
+            let new_rate = if nodes.len() == 1 {
+                edge_rate
+            } else {
+                rate * edge_rate
+            };
+            if new_rate.partial_cmp(&new_rate).is_none() {
+                continue;
+            }
+
+            let improved = result
+                .get_path_rate(start, node)
+                .is_none_or(|&best_rate| new_rate.partial_cmp(&best_rate) == Some(Greater));
+            if improved {
+                result.set_path(start, node, new_rate, *nodes.get(1).unwrap_or(&node));
+            }
+
+            if nodes.len() < self.max_edges {
+                nodes.push(node);
+                self.extend(graph, nodes, new_rate, result);
+                nodes.pop();
+            }
+        }
+    }
+}
+
+impl<I, E> Solver<I, E> for BruteForceSolver
+where
+    I: NodeTrait,
+    E: FloydWarshallTrait + 'static,
+{
+    fn solve(&self, graph: &Graph<(I, I), E>, cancellation: &Cancellation) -> Option<Paths<I, E>> {
+        let mut result = Paths::new(graph);
+
+        if self.max_edges > 0 {
+            for start in graph.nodes() {
+                if cancellation.is_cancelled() {
+                    return None;
+                }
+                self.extend(graph, &mut vec![start], E::one(), &mut result);
+            }
+        }
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::cancellation::Cancellation;
+    use crate::algorithm::paths::Paths;
+    use crate::algorithm::progress::ProgressReporter;
+    use crate::algorithm::settings::PathSemantics;
+    use crate::algorithm::solver::{BruteForceSolver, FloydWarshallSolver, Solver};
+    use crate::synthetic::Random;
+    use floyd_warshall_alg::FloydWarshall;
+    use safe_graph::Graph;
+    use std::cmp::Ordering::{Greater, Less};
+    use std::sync::{Arc, Mutex, PoisonError};
+    use std::time::Duration;
+
+    /// Solver knowing the direct edges only.
+    struct DirectSolver;
+
+    impl Solver<u32, f32> for DirectSolver {
+        fn solve(
+            &self,
+            graph: &Graph<(u32, u32), f32>,
+            _cancellation: &Cancellation,
+        ) -> Option<Paths<u32, f32>> {
+            Some(Paths::from_edges(graph))
+        }
+    }
+
+    #[test]
+    fn solve() {
+        let mut graph = Graph::new();
+        graph.add_edge((0, 1), (0, 2), 1000.0_f32);
+        graph.add_edge((0, 2), (1, 2), 0.9);
+        graph.add_edge((0, 1), (1, 2), 800.0);
+
+        // Test that the better path of two edges replaces the direct one.
+        let solvers: Vec<Box<dyn Solver<u32, f32>>> = vec![
+            Box::new(FloydWarshallSolver::new(0.0, true)),
+            Box::new(DirectSolver),
+        ];
+        let rates: Vec<_> = solvers
+            .iter()
+            .map(|solver| {
+                let result = solver.solve(&graph, &Cancellation::new()).unwrap();
+                (
+                    *result.get_path_rate((0, 1), (1, 2)).unwrap(),
+                    result.collect_path_nodes((0, 1), (1, 2)).len(),
+                )
+            })
+            .collect();
+        assert_eq!(rates, vec![(900.0, 3), (800.0, 2)]);
+
+        // Test that a path better by no more than the epsilon doesn't replace the direct one.
+        let result = FloydWarshallSolver::new(150.0, true)
+            .solve(&graph, &Cancellation::new())
+            .unwrap();
+        assert_eq!(*result.get_path_rate((0, 1), (1, 2)).unwrap(), 800.0);
+
+        // Test that the path of the least cost is the best one of the min-cost semantics.
+        let result = FloydWarshallSolver::new(0.0, true)
+            .with_semantics(PathSemantics::MinCost)
+            .solve(&graph, &Cancellation::new())
+            .unwrap();
+        assert_eq!(*result.get_path_rate((0, 1), (1, 2)).unwrap(), 800.0);
+        graph.add_edge((0, 1), (0, 2), 10.0);
+        let result = FloydWarshallSolver::new(0.0, true)
+            .with_semantics(PathSemantics::MinCost)
+            .solve(&graph, &Cancellation::new())
+            .unwrap();
+        assert_eq!(*result.get_path_rate((0, 1), (1, 2)).unwrap(), 10.9);
+        assert_eq!(result.collect_path_nodes((0, 1), (1, 2)).len(), 3);
+    }
+
+    #[test]
+    fn solve_as_floyd_warshall() {
+        let mut graph = Graph::new();
+        for (a, b, rate) in &[
+            (0, 1, 2.0_f32),
+            (1, 2, 3.0),
+            (2, 0, 0.1),
+            (0, 2, 5.0),
+            (2, 3, 1.5),
+            (3, 1, 0.4),
+            (1, 0, 0.45),
+        ] {
+            graph.add_edge((0, *a), (0, *b), *rate);
+        }
+
+        let mul = Box::new(|x: f32, y: f32| x * y);
+        let sharp_greater = Box::new(|x: f32, y: f32| x.partial_cmp(&y).unwrap_or(Less) == Greater);
+        let expected =
+            FloydWarshall::new_fully_customized(mul, sharp_greater, true).find_paths(&graph);
+        let result = FloydWarshallSolver::new(0.0, true)
+            .solve(&graph, &Cancellation::new())
+            .unwrap();
+
+        // Test that all the rates and paths are the same as of `floyd-warshall-alg`.
+        for a in graph.nodes() {
+            for b in graph.nodes() {
+                assert_eq!(result.get_path_rate(a, b), expected.get_path_rate(a, b));
+                if expected.get_path_rate(a, b).is_some() {
+                    assert_eq!(
+                        result.collect_path_nodes(a, b),
+                        expected.collect_path_nodes(a, b)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solve_with_progress_reporter() {
+        let mut graph = Graph::new();
+        graph.add_edge((0, 1), (0, 2), 1000.0_f32);
+        graph.add_edge((0, 2), (1, 2), 0.9);
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&reports);
+        let progress_reporter = ProgressReporter::new(Duration::default(), move |progress| {
+            collected
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push((progress.get_iteration(), progress.get_iterations()));
+        });
+
+        FloydWarshallSolver::new(0.0, true)
+            .with_progress_reporter(Some(progress_reporter))
+            .solve(&graph, &Cancellation::new())
+            .unwrap();
+
+        // Test that each iteration is reported without any interval.
+        assert_eq!(
+            *reports.lock().unwrap_or_else(PoisonError::into_inner),
+            vec![(1, 3), (2, 3), (3, 3)]
+        );
+    }
+
+    #[test]
+    fn solve_cancelled() {
+        let mut graph = Graph::new();
+        graph.add_edge((0, 1), (0, 2), 1000.0_f32);
+        graph.add_edge((0, 2), (1, 2), 0.9);
+
+        // The token is cancelled by the report of the first iteration.
+        let cancellation = Cancellation::new();
+        let cancelling = cancellation.clone();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&reports);
+        let progress_reporter = ProgressReporter::new(Duration::default(), move |progress| {
+            cancelling.cancel();
+            collected
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(progress.get_iteration());
+        });
+
+        // Test that the run is stopped right after the iteration it was cancelled in.
+        let result = FloydWarshallSolver::new(0.0, true)
+            .with_progress_reporter(Some(progress_reporter))
+            .solve(&graph, &cancellation);
+        assert!(result.is_none());
+        assert_eq!(
+            *reports.lock().unwrap_or_else(PoisonError::into_inner),
+            vec![1]
+        );
+
+        assert!(BruteForceSolver::new(2)
+            .solve(&graph, &cancellation)
+            .is_none());
+    }
+
+    #[test]
+    fn solve_as_brute_force() {
+        for seed in 0..50 {
+            let mut random = Random::new(seed);
+            let nodes = 3 + random.below(4) as u32;
+
+            // Rates between nodes of random values, lowered by fees, can't form arbitrage cycles.
+            let values: Vec<f64> = (0..nodes).map(|_| 0.1 + random.unit() * 100.0).collect();
+            let mut graph = Graph::new();
+            for a in 0..nodes {
+                for b in 0..nodes {
+                    if a != b && random.unit() < 0.5 {
+                        let fee = 1.0 - random.unit() * 0.05;
+                        let rate = values[b as usize] / values[a as usize] * fee;
+                        graph.add_edge((a % 2, a), (b % 2, b), rate);
+                    }
+                }
+            }
+
+            let expected = BruteForceSolver::new(nodes as usize - 1)
+                .solve(&graph, &Cancellation::new())
+                .unwrap();
+            let result = FloydWarshallSolver::new(0.0, true)
+                .solve(&graph, &Cancellation::new())
+                .unwrap();
+
+            // Test that both find the same best rates and the paths deliver them.
+            for a in graph.nodes() {
+                for b in graph.nodes().filter(|&b| b != a) {
+                    match (result.get_path_rate(a, b), expected.get_path_rate(a, b)) {
+                        (Some(rate), Some(expected_rate)) => {
+                            assert!((rate - expected_rate).abs() <= 1e-9 * expected_rate);
+
+                            let nodes = result.collect_path_nodes(a, b);
+                            let path_rate = nodes.windows(2).fold(1.0, |rate, edge| {
+                                rate * graph.edge_weight(edge[0], edge[1]).unwrap()
+                            });
+                            assert!((path_rate - rate).abs() <= 1e-9 * rate);
+                        }
+                        (None, None) => {}
+                        rates => panic!("Rates of <{:?}> <{:?}> differ: {:?}!", a, b, rates),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn brute_force_max_edges() {
+        let mut graph = Graph::new();
+        graph.add_edge((0, 1), (0, 2), 1000.0_f32);
+        graph.add_edge((0, 2), (1, 2), 0.9);
+        graph.add_edge((0, 1), (1, 2), 800.0);
+
+        // Test that paths of more edges than the maximum aren't found.
+        let result = BruteForceSolver::new(1)
+            .solve(&graph, &Cancellation::new())
+            .unwrap();
+        assert_eq!(*result.get_path_rate((0, 1), (1, 2)).unwrap(), 800.0);
+
+        let result = BruteForceSolver::new(2)
+            .solve(&graph, &Cancellation::new())
+            .unwrap();
+        assert_eq!(*result.get_path_rate((0, 1), (1, 2)).unwrap(), 900.0);
+        assert_eq!(result.collect_path_nodes((0, 1), (1, 2)).len(), 3);
+    }
+}
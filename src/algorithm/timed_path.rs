@@ -0,0 +1,176 @@
+//! Best path settling within a time budget.
+//!
+//! The best rate path may take too long to settle while a worse one doesn't, so the
+//! Floyd-Warshall result can't be used. Each node keeps all paths reaching it that no other
+//! path beats in both the rate and the settlement time, relaxed Bellman-Ford style.
+
+use crate::algorithm::Algorithm;
+use crate::response::best_rate_path::BestRatePath;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use indexmap::IndexMap;
+use num_traits::Num;
+use safe_graph::NodeTrait;
+use std::cmp::Ordering::Equal;
+use std::fmt::{Debug, Display};
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+/// Path reaching a node with the amount received and minutes spent on the way.
+#[derive(Clone)]
+struct Label<I, E> {
+    time: u32,
+    amount: E,
+    path: Vec<(I, I)>,
+}
+
+impl<N, E, I> Algorithm<N, E, I>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+    I: NodeTrait + Num + AddAssign,
+{
+    /// Find the best rate path whose transfers settle within the `time_budget` minutes.
+    ///
+    /// With the `amount` the rate of converting the whole amount is found, the same as by
+    /// `find_best_amount_path`, otherwise the marginal one.
+    pub(super) fn find_best_timed_path(
+        &self,
+        source: (&N, &N),
+        destination: (&N, &N),
+        amount: Option<E>,
+        time_budget: u32,
+    ) -> Option<BestRatePath<N, E>> {
+        // Prepare indexes, not known nodes can't have any path.
+        let a = (
+            self.get_node_index(source.0)?,
+            self.get_node_index(source.1)?,
+        );
+        let b = (
+            self.get_node_index(destination.0)?,
+            self.get_node_index(destination.1)?,
+        );
+
+        let start = amount.unwrap_or_else(E::one);
+        let mut labels: IndexMap<(I, I), Vec<Label<I, E>>> = IndexMap::new();
+        labels.insert(
+            a,
+            vec![Label {
+                time: 0,
+                amount: start,
+                path: vec![a],
+            }],
+        );
+
+        // A simple path has less edges than the count of nodes.
+        for _ in 1..self.graph.node_count() {
+            let mut changed = false;
+
+            for (x, y, rate) in self.graph.all_edges() {
+                let x_labels = match labels.get(&x) {
+                    Some(x_labels) => x_labels.clone(),
+                    None => continue,
+                };
+
+                for label in x_labels {
+                    let time = label.time + self.get_edge_latency(x, y);
+                    if time > time_budget || label.path.contains(&y) {
+                        continue;
+                    }
+
+                    let received = match amount {
+                        Some(_) if !self.is_trade_allowed(x, y, label.amount) => continue,
+                        Some(_) => self.get_received_amount(x, y, *rate, label.amount),
+                        None => label.amount * *rate,
+                    };
+
+                    // Keep the path only if no other one is both faster and better.
+                    let y_labels = labels.entry(y).or_default();
+                    if y_labels
+                        .iter()
+                        .any(|other| other.time <= time && other.amount >= received)
+                    {
+                        continue;
+                    }
+                    y_labels.retain(|other| other.time < time || other.amount > received);
+
+                    let mut path = label.path;
+                    path.push(y);
+                    y_labels.push(Label {
+                        time,
+                        amount: received,
+                        path,
+                    });
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let best = labels
+            .get(&b)?
+            .iter()
+            .max_by(|x, y| x.amount.partial_cmp(&y.amount).unwrap_or(Equal))?;
+
+        Some(self.form_best_rate_path(best.amount / start, best.path.clone()))
+    }
+
+    /// Get settlement time in minutes of the edge from `x` to `y`, trades are instant.
+    fn get_edge_latency(&self, x: (I, I), y: (I, I)) -> u32 {
+        if x.0 == y.0 {
+            return 0;
+        }
+
+        self.index_to_node(&x.1).map_or(0, |currency| {
+            self.settings.get_transfer_model().get_latency(currency)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::algorithm::Algorithm;
+    use crate::request::Request;
+    use crate::response::unanswered_request::Reason::*;
+    use std::io::BufReader;
+
+    #[test]
+    fn process_rate_request_with_time_budget() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD 2000.0 0.0005
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD WITHIN 60
+EXCHANGE_RATE_REQUEST E1 BTC E2 USD 1.0 WITHIN 60
+EXCHANGE_RATE_REQUEST E2 BTC E1 BTC WITHIN 2"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let mut settings = Settings::<String, f32>::new();
+        settings.get_transfer_model_mut().set_default_latency(5);
+        settings
+            .get_transfer_model_mut()
+            .set_currency_latency("BTC".to_string(), 60);
+
+        let response = Algorithm::<String, f32, u32>::process_customized(&request, &settings);
+        let paths = response.get_best_rate_paths();
+
+        // Test that the better path over E2 taking 65 minutes is not used.
+        assert_eq!(*paths[0].get_rate(), 1000.0);
+        assert_eq!(paths[0].get_path().len(), 2);
+
+        // Test that the slow BTC transfer is used if it fits the budget.
+        assert_eq!(*paths[1].get_rate(), 2000.0);
+        assert_eq!(paths[1].get_path().len(), 3);
+
+        // Test that a path too slow to settle is reported.
+        let unanswered = &response.get_unanswered_requests()[0];
+        assert_eq!(unanswered.get_reason(), NoPathWithinTime);
+    }
+}
@@ -0,0 +1,246 @@
+//! Venue Trading Rules.
+
+use crate::json;
+#[cfg(feature = "std")]
+use crate::json::value::{identifier, items, number, Value};
+use crate::IndexMapTrait;
+use indexmap::{IndexMap, IndexSet};
+use num_traits::Num;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Venue `TradingRules` structure.
+///
+/// Holds currency pairs exchanges don't allow trading at all and minimal amounts of trades, in
+/// units of the traded (source) currency. A disabled pair can't be traded in either direction.
+///
+/// Minimal amounts apply to conversions of an amount only, as rates without any amount don't
+/// relate to a trade size.
+///
+/// # `TradingRules<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+#[derive(Clone, Debug)]
+pub struct TradingRules<N, E> {
+    disabled_pairs: IndexSet<(N, N, N)>,
+    min_trade_amounts: IndexMap<(N, N, N), E>,
+}
+
+impl<N, E> TradingRules<N, E>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy,
+{
+    /// Create a new instance of `TradingRules` structure allowing all trades.
+    pub fn new() -> Self {
+        Self {
+            disabled_pairs: IndexSet::new(),
+            min_trade_amounts: IndexMap::new(),
+        }
+    }
+
+    /// Set whether the pair of currencies can be traded at the exchange.
+    pub fn set_pair_enabled(&mut self, exchange: N, base: N, quote: N, enabled: bool) {
+        if enabled {
+            self.disabled_pairs
+                .shift_remove(&(exchange.clone(), quote.clone(), base.clone()));
+            self.disabled_pairs.shift_remove(&(exchange, base, quote));
+        } else {
+            self.disabled_pairs.insert((exchange, base, quote));
+        }
+    }
+
+    /// Check whether the pair of currencies can be traded at the exchange, in any order.
+    pub fn is_pair_enabled(&self, exchange: &N, base: &N, quote: &N) -> bool {
+        let pair = (exchange.clone(), base.clone(), quote.clone());
+        let reversed_pair = (exchange.clone(), quote.clone(), base.clone());
+
+        !self.disabled_pairs.contains(&pair) && !self.disabled_pairs.contains(&reversed_pair)
+    }
+
+    /// Set minimal amount of trading the source currency for the destination one at the
+    /// exchange.
+    pub fn set_min_trade_amount(
+        &mut self,
+        exchange: N,
+        source_currency: N,
+        destination_currency: N,
+        amount: E,
+    ) {
+        self.min_trade_amounts
+            .insert((exchange, source_currency, destination_currency), amount);
+    }
+}
+
+impl<N, E> TradingRules<N, E>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy + PartialOrd,
+{
+    /// Check whether the `amount` of the source currency can be traded for the destination one
+    /// at the exchange.
+    pub fn is_trade_allowed(
+        &self,
+        exchange: &N,
+        source_currency: &N,
+        destination_currency: &N,
+        amount: E,
+    ) -> bool {
+        let trade = (
+            exchange.clone(),
+            source_currency.clone(),
+            destination_currency.clone(),
+        );
+
+        self.is_pair_enabled(exchange, source_currency, destination_currency)
+            && self
+                .min_trade_amounts
+                .get(&trade)
+                .is_none_or(|min_amount| amount >= *min_amount)
+    }
+}
+
+impl<N, E> TradingRules<N, E>
+where
+    N: IndexMapTrait + Clone + Display + FromStr,
+    E: Num + Copy + Display + FromStr,
+{
+    /// Get JSON representation of the `TradingRules`, in the format `parse_json` accepts.
+    ///
+    /// # Format
+    ///
+    /// {"disabled_pairs":[{"exchange":<exchange>,"base":<currency>,"quote":<currency>}, ...],
+    /// "min_trade_amounts":[{"exchange":<exchange>,"source_currency":<currency>,
+    /// "destination_currency":<currency>,"amount":<number>}, ...]}
+    pub fn get_json(&self) -> String {
+        let disabled_pairs: Vec<String> = self
+            .disabled_pairs
+            .iter()
+            .map(|(exchange, base, quote)| {
+                format!(
+                    "{{\"exchange\":{},\"base\":{},\"quote\":{}}}",
+                    json::quote(exchange),
+                    json::quote(base),
+                    json::quote(quote)
+                )
+            })
+            .collect();
+        let min_trade_amounts: Vec<String> = self
+            .min_trade_amounts
+            .iter()
+            .map(
+                |((exchange, source_currency, destination_currency), amount)| {
+                    format!(
+                        "{{\"exchange\":{},\"source_currency\":{},\"destination_currency\":{},\
+                     \"amount\":{}}}",
+                        json::quote(exchange),
+                        json::quote(source_currency),
+                        json::quote(destination_currency),
+                        amount
+                    )
+                },
+            )
+            .collect();
+
+        format!(
+            "{{\"disabled_pairs\":[{}],\"min_trade_amounts\":[{}]}}",
+            disabled_pairs.join(","),
+            min_trade_amounts.join(",")
+        )
+    }
+
+    /// Parse JSON object of the format `get_json` gives, missing fields allow all trades.
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_json(item: &Value) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let mut trading_rules = Self::new();
+
+        let disabled_pairs = items(item, "disabled_pairs", &mut errors, |item, errors| {
+            match (
+                identifier(item, "exchange", errors),
+                identifier(item, "base", errors),
+                identifier(item, "quote", errors),
+            ) {
+                (Some(exchange), Some(base), Some(quote)) => Some((exchange, base, quote)),
+                _ => None,
+            }
+        });
+        for (exchange, base, quote) in disabled_pairs {
+            trading_rules.set_pair_enabled(exchange, base, quote, false);
+        }
+        let min_trade_amounts = items(
+            item,
+            "min_trade_amounts",
+            &mut errors,
+            |item, errors| match (
+                identifier(item, "exchange", errors),
+                identifier(item, "source_currency", errors),
+                identifier(item, "destination_currency", errors),
+                number(item, "amount", errors),
+            ) {
+                (Some(exchange), Some(source), Some(destination), Some(amount)) => {
+                    Some((exchange, source, destination, amount))
+                }
+                _ => None,
+            },
+        );
+        for (exchange, source, destination, amount) in min_trade_amounts {
+            trading_rules.set_min_trade_amount(exchange, source, destination, amount);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(trading_rules)
+    }
+}
+
+impl<N, E> Default for TradingRules<N, E>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::trading_rules::TradingRules;
+
+    #[test]
+    fn set_pair_enabled() {
+        let mut trading_rules = TradingRules::<String, f32>::new();
+        let e1 = "E1".to_string();
+        let (btc, usd) = ("BTC".to_string(), "USD".to_string());
+        assert!(trading_rules.is_pair_enabled(&e1, &btc, &usd));
+
+        // Test that the pair is disabled in both directions.
+        trading_rules.set_pair_enabled(e1.clone(), btc.clone(), usd.clone(), false);
+        assert!(!trading_rules.is_pair_enabled(&e1, &btc, &usd));
+        assert!(!trading_rules.is_pair_enabled(&e1, &usd, &btc));
+        assert!(trading_rules.is_pair_enabled(&"E2".to_string(), &btc, &usd));
+
+        trading_rules.set_pair_enabled(e1.clone(), usd.clone(), btc.clone(), true);
+        assert!(trading_rules.is_pair_enabled(&e1, &btc, &usd));
+    }
+
+    #[test]
+    fn is_trade_allowed() {
+        let mut trading_rules = TradingRules::<String, f32>::new();
+        let e1 = "E1".to_string();
+        let (btc, usd) = ("BTC".to_string(), "USD".to_string());
+        trading_rules.set_min_trade_amount(e1.clone(), btc.clone(), usd.clone(), 0.5);
+
+        // Test that the minimal amount applies to the direction only.
+        assert!(trading_rules.is_trade_allowed(&e1, &btc, &usd, 0.5));
+        assert!(!trading_rules.is_trade_allowed(&e1, &btc, &usd, 0.25));
+        assert!(trading_rules.is_trade_allowed(&e1, &usd, &btc, 0.25));
+
+        trading_rules.set_pair_enabled(e1.clone(), btc.clone(), usd.clone(), false);
+        assert!(!trading_rules.is_trade_allowed(&e1, &btc, &usd, 1.0));
+    }
+}
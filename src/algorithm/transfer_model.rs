@@ -0,0 +1,507 @@
+//! Cross-exchange Transfer Model.
+
+use crate::error::Error;
+use crate::json;
+#[cfg(feature = "std")]
+use crate::json::value::{identifier, items, keyed_numbers, number, optional_number, Value};
+use crate::IndexMapTrait;
+use indexmap::{IndexMap, IndexSet};
+use num_traits::Num;
+use std::fmt::{Debug, Display};
+use std::io::BufRead;
+use std::str::FromStr;
+
+/// Cross-exchange `TransferModel` structure.
+///
+/// Holds fees of moving a currency from one exchange to another as fractions of the transferred
+/// amount (e.g. `0.0005` for 0.05%). Currencies without their own fee use the default fee.
+/// Transfers of disabled currencies are not possible at all, transfers of a currency over a
+/// disabled route (from one specific exchange to another) are not possible either.
+///
+/// Withdrawal and deposit fees of a currency at a specific exchange (e.g. a network fee of a
+/// coin or a wire fee of a fiat currency) are applied on top of the currency transfer fee.
+///
+/// Transfers also take time to settle, latencies are counts of minutes. Currencies without their
+/// own latency use the default latency, transfers are instant by default.
+///
+/// # `TransferModel<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+#[derive(Clone, Debug)]
+pub struct TransferModel<N, E> {
+    default_fee: E,
+    currency_fees: IndexMap<N, E>,
+    disabled_currencies: IndexSet<N>,
+    disabled_routes: IndexSet<(N, N, N)>,
+    withdrawal_fees: IndexMap<(N, N), E>,
+    deposit_fees: IndexMap<(N, N), E>,
+    default_latency: u32,
+    currency_latencies: IndexMap<N, u32>,
+}
+
+impl<N, E> TransferModel<N, E>
+where
+    N: IndexMapTrait,
+    E: Num + Copy,
+{
+    /// Create a new instance of `TransferModel` structure with free transfers of all currencies.
+    pub fn new() -> Self {
+        Self {
+            default_fee: E::zero(),
+            currency_fees: IndexMap::new(),
+            disabled_currencies: IndexSet::new(),
+            disabled_routes: IndexSet::new(),
+            withdrawal_fees: IndexMap::new(),
+            deposit_fees: IndexMap::new(),
+            default_latency: 0,
+            currency_latencies: IndexMap::new(),
+        }
+    }
+
+    /// Set transfer fee of all currencies without their own fee.
+    pub fn set_default_fee(&mut self, fee: E) {
+        self.default_fee = fee;
+    }
+
+    pub fn get_default_fee(&self) -> E {
+        self.default_fee
+    }
+
+    /// Set transfer fee of the currency.
+    pub fn set_currency_fee(&mut self, currency: N, fee: E) {
+        self.currency_fees.insert(currency, fee);
+    }
+
+    /// Get transfer fee of the currency.
+    pub fn get_fee(&self, currency: &N) -> E {
+        *self
+            .currency_fees
+            .get(currency)
+            .unwrap_or(&self.default_fee)
+    }
+
+    /// Get the factor a transferred amount is multiplied by after paying the transfer fee.
+    pub fn get_transfer_factor(&self, currency: &N) -> E {
+        E::one() - self.get_fee(currency)
+    }
+
+    /// Set fee of withdrawing the currency from the exchange.
+    pub fn set_withdrawal_fee(&mut self, exchange: N, currency: N, fee: E) {
+        self.withdrawal_fees.insert((exchange, currency), fee);
+    }
+
+    /// Set fee of depositing the currency to the exchange.
+    pub fn set_deposit_fee(&mut self, exchange: N, currency: N, fee: E) {
+        self.deposit_fees.insert((exchange, currency), fee);
+    }
+
+    /// Set settlement time in minutes of transfers of all currencies without their own latency.
+    pub fn set_default_latency(&mut self, latency: u32) {
+        self.default_latency = latency;
+    }
+
+    /// Set settlement time in minutes of transfers of the currency.
+    pub fn set_currency_latency(&mut self, currency: N, latency: u32) {
+        self.currency_latencies.insert(currency, latency);
+    }
+
+    /// Get settlement time in minutes of transfers of the currency.
+    pub fn get_latency(&self, currency: &N) -> u32 {
+        *self
+            .currency_latencies
+            .get(currency)
+            .unwrap_or(&self.default_latency)
+    }
+
+    /// Set whether the currency can be transferred between exchanges.
+    pub fn set_transfer_enabled(&mut self, currency: N, enabled: bool) {
+        if enabled {
+            self.disabled_currencies.shift_remove(&currency);
+        } else {
+            self.disabled_currencies.insert(currency);
+        }
+    }
+
+    /// Check whether the currency can be transferred between exchanges.
+    pub fn is_transfer_enabled(&self, currency: &N) -> bool {
+        !self.disabled_currencies.contains(currency)
+    }
+
+    /// Set whether the currency can be transferred from the `source` exchange to the
+    /// `destination` exchange.
+    pub fn set_route_enabled(&mut self, source: N, destination: N, currency: N, enabled: bool) {
+        let route = (source, destination, currency);
+
+        if enabled {
+            self.disabled_routes.shift_remove(&route);
+        } else {
+            self.disabled_routes.insert(route);
+        }
+    }
+}
+
+impl<N, E> TransferModel<N, E>
+where
+    N: IndexMapTrait + Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: Num + Copy + FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    /// Check whether the currency can be transferred from the `source` exchange to the
+    /// `destination` exchange.
+    pub fn is_route_enabled(&self, source: &N, destination: &N, currency: &N) -> bool {
+        self.is_transfer_enabled(currency)
+            && !self.disabled_routes.contains(&(
+                source.clone(),
+                destination.clone(),
+                currency.clone(),
+            ))
+    }
+
+    /// Get the factor an amount of the currency transferred from the `source` exchange to the
+    /// `destination` exchange is multiplied by after paying all the fees.
+    pub fn get_bridge_factor(&self, source: &N, destination: &N, currency: &N) -> E {
+        let mut factor = self.get_transfer_factor(currency);
+
+        if let Some(fee) = self
+            .withdrawal_fees
+            .get(&(source.clone(), currency.clone()))
+        {
+            factor = factor * (E::one() - *fee);
+        }
+
+        if let Some(fee) = self
+            .deposit_fees
+            .get(&(destination.clone(), currency.clone()))
+        {
+            factor = factor * (E::one() - *fee);
+        }
+
+        factor
+    }
+
+    /// Read withdrawal and deposit fees schedule from the input.
+    ///
+    /// # Input format
+    ///
+    /// One `<exchange> <currency> <withdrawal_fee> <deposit_fee>` line per `(exchange, currency)`
+    /// pair, empty lines and lines starting with `#` are skipped.
+    ///
+    /// ## Example
+    ///
+    /// \# Network fee of BTC and wire fee of USD.
+    /// KRAKEN BTC 0.0005 0.0
+    /// KRAKEN USD 0.002 0.001
+    pub fn read_schedule_from<R: BufRead>(&mut self, input: &mut R) -> Result<(), Error> {
+        let mut errors: Vec<String> = Vec::new();
+
+        for (number, line) in input.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let items: Vec<&str> = line.split_whitespace().collect();
+            if items.len() != 4 {
+                errors.push(format!(
+                    "The fee schedule line {} doesn't have 4 items!",
+                    number + 1
+                ));
+                continue;
+            }
+
+            let exchange = items[0].to_uppercase().parse::<N>();
+            let currency = items[1].to_uppercase().parse::<N>();
+            let withdrawal_fee = items[2].parse::<E>();
+            let deposit_fee = items[3].parse::<E>();
+
+            match (exchange, currency, withdrawal_fee, deposit_fee) {
+                (Ok(exchange), Ok(currency), Ok(withdrawal_fee), Ok(deposit_fee)) => {
+                    self.withdrawal_fees
+                        .insert((exchange.clone(), currency.clone()), withdrawal_fee);
+                    self.deposit_fees.insert((exchange, currency), deposit_fee);
+                }
+                _ => errors.push(format!(
+                    "The fee schedule line {} can not be parsed (wrong format)!",
+                    number + 1
+                )),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(Error::Parse(errors));
+        }
+
+        Ok(())
+    }
+}
+
+impl<N, E> TransferModel<N, E>
+where
+    N: IndexMapTrait + Display + FromStr,
+    E: Num + Copy + Display + FromStr,
+{
+    /// Get JSON representation of the `TransferModel`, in the format `parse_json` accepts.
+    ///
+    /// # Format
+    ///
+    /// {"fee":<number>,"latency":<minutes>,"currency_fees":{<currency>:<number>, ...},
+    /// "currency_latencies":{<currency>:<minutes>, ...},"disabled_currencies":[<currency>, ...],
+    /// "disabled_routes":[{"source":<exchange>,"destination":<exchange>,"currency":<currency>},
+    /// ...],"withdrawal_fees":[{"exchange":<exchange>,"currency":<currency>,"fee":<number>},
+    /// ...],"deposit_fees":[{"exchange":<exchange>,"currency":<currency>,"fee":<number>}, ...]}
+    pub fn get_json(&self) -> String {
+        let disabled_currencies: Vec<String> =
+            self.disabled_currencies.iter().map(json::quote).collect();
+        let disabled_routes: Vec<String> = self
+            .disabled_routes
+            .iter()
+            .map(|(source, destination, currency)| {
+                format!(
+                    "{{\"source\":{},\"destination\":{},\"currency\":{}}}",
+                    json::quote(source),
+                    json::quote(destination),
+                    json::quote(currency)
+                )
+            })
+            .collect();
+        let exchange_fees = |fees: &IndexMap<(N, N), E>| {
+            let fees: Vec<String> = fees
+                .iter()
+                .map(|((exchange, currency), fee)| {
+                    format!(
+                        "{{\"exchange\":{},\"currency\":{},\"fee\":{}}}",
+                        json::quote(exchange),
+                        json::quote(currency),
+                        fee
+                    )
+                })
+                .collect();
+            fees.join(",")
+        };
+
+        format!(
+            "{{\"fee\":{},\"latency\":{},\"currency_fees\":{},\"currency_latencies\":{},\
+             \"disabled_currencies\":[{}],\"disabled_routes\":[{}],\"withdrawal_fees\":[{}],\
+             \"deposit_fees\":[{}]}}",
+            self.default_fee,
+            self.default_latency,
+            json::map(&self.currency_fees),
+            json::map(&self.currency_latencies),
+            disabled_currencies.join(","),
+            disabled_routes.join(","),
+            exchange_fees(&self.withdrawal_fees),
+            exchange_fees(&self.deposit_fees),
+        )
+    }
+
+    /// Parse JSON object of the format `get_json` gives, missing fields have free and instant
+    /// transfers of all currencies.
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_json(item: &Value) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let mut transfer_model = Self::new();
+
+        if let Some(fee) = optional_number(item, "fee", &mut errors) {
+            transfer_model.set_default_fee(fee);
+        }
+        if let Some(latency) = optional_number(item, "latency", &mut errors) {
+            transfer_model.set_default_latency(latency);
+        }
+        for (currency, fee) in keyed_numbers(item, "currency_fees", &mut errors) {
+            transfer_model.set_currency_fee(currency, fee);
+        }
+        for (currency, latency) in keyed_numbers(item, "currency_latencies", &mut errors) {
+            transfer_model.set_currency_latency(currency, latency);
+        }
+        let disabled_currencies =
+            items(item, "disabled_currencies", &mut errors, |item, errors| {
+                let currency = item
+                    .as_text()
+                    .and_then(|currency| currency.to_uppercase().parse().ok());
+                if currency.is_none() {
+                    errors.push("The currency can not be parsed (wrong format)!".to_string());
+                }
+                currency
+            });
+        for currency in disabled_currencies {
+            transfer_model.set_transfer_enabled(currency, false);
+        }
+        let disabled_routes = items(item, "disabled_routes", &mut errors, |item, errors| match (
+            identifier(item, "source", errors),
+            identifier(item, "destination", errors),
+            identifier(item, "currency", errors),
+        ) {
+            (Some(source), Some(destination), Some(currency)) => {
+                Some((source, destination, currency))
+            }
+            _ => None,
+        });
+        for (source, destination, currency) in disabled_routes {
+            transfer_model.set_route_enabled(source, destination, currency, false);
+        }
+        let parse_exchange_fee = |item: &Value, errors: &mut Vec<String>| match (
+            identifier(item, "exchange", errors),
+            identifier(item, "currency", errors),
+            number(item, "fee", errors),
+        ) {
+            (Some(exchange), Some(currency), Some(fee)) => Some((exchange, currency, fee)),
+            _ => None,
+        };
+        for (exchange, currency, fee) in
+            items(item, "withdrawal_fees", &mut errors, parse_exchange_fee)
+        {
+            transfer_model.set_withdrawal_fee(exchange, currency, fee);
+        }
+        for (exchange, currency, fee) in
+            items(item, "deposit_fees", &mut errors, parse_exchange_fee)
+        {
+            transfer_model.set_deposit_fee(exchange, currency, fee);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(transfer_model)
+    }
+}
+
+impl<N, E> Default for TransferModel<N, E>
+where
+    N: IndexMapTrait,
+    E: Num + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::transfer_model::TransferModel;
+    use std::io::BufReader;
+
+    #[test]
+    fn get_transfer_factor() {
+        let mut transfer_model = TransferModel::<String, f32>::new();
+        assert_eq!(transfer_model.get_transfer_factor(&"BTC".to_string()), 1.0);
+
+        transfer_model.set_default_fee(0.002);
+        transfer_model.set_currency_fee("BTC".to_string(), 0.001);
+
+        // Test the currency fee.
+        assert_eq!(
+            transfer_model.get_transfer_factor(&"BTC".to_string()),
+            0.999
+        );
+
+        // Test the default fee.
+        assert_eq!(
+            transfer_model.get_transfer_factor(&"USD".to_string()),
+            0.998
+        );
+    }
+
+    #[test]
+    fn get_latency() {
+        let mut transfer_model = TransferModel::<String, f32>::new();
+        assert_eq!(transfer_model.get_latency(&"BTC".to_string()), 0);
+
+        transfer_model.set_default_latency(30);
+        transfer_model.set_currency_latency("BTC".to_string(), 60);
+
+        // Test the currency and the default latency.
+        assert_eq!(transfer_model.get_latency(&"BTC".to_string()), 60);
+        assert_eq!(transfer_model.get_latency(&"USD".to_string()), 30);
+    }
+
+    #[test]
+    fn set_transfer_enabled() {
+        let mut transfer_model = TransferModel::<String, f32>::new();
+        assert!(transfer_model.is_transfer_enabled(&"USD".to_string()));
+
+        transfer_model.set_transfer_enabled("USD".to_string(), false);
+        assert!(!transfer_model.is_transfer_enabled(&"USD".to_string()));
+        assert!(transfer_model.is_transfer_enabled(&"BTC".to_string()));
+
+        transfer_model.set_transfer_enabled("USD".to_string(), true);
+        assert!(transfer_model.is_transfer_enabled(&"USD".to_string()));
+    }
+
+    #[test]
+    fn set_route_enabled() {
+        let mut transfer_model = TransferModel::<String, f32>::new();
+        let (e1, e2, btc) = ("E1".to_string(), "E2".to_string(), "BTC".to_string());
+        assert!(transfer_model.is_route_enabled(&e1, &e2, &btc));
+
+        // Test that the route is disabled in the direction only.
+        transfer_model.set_route_enabled(e1.clone(), e2.clone(), btc.clone(), false);
+        assert!(!transfer_model.is_route_enabled(&e1, &e2, &btc));
+        assert!(transfer_model.is_route_enabled(&e2, &e1, &btc));
+
+        transfer_model.set_route_enabled(e1.clone(), e2.clone(), btc.clone(), true);
+        assert!(transfer_model.is_route_enabled(&e1, &e2, &btc));
+
+        // Test that routes of a disabled currency are disabled as well.
+        transfer_model.set_transfer_enabled(btc.clone(), false);
+        assert!(!transfer_model.is_route_enabled(&e2, &e1, &btc));
+    }
+
+    #[test]
+    fn get_bridge_factor() {
+        let mut transfer_model = TransferModel::<String, f32>::new();
+        transfer_model.set_default_fee(0.5);
+        transfer_model.set_withdrawal_fee("E1".to_string(), "BTC".to_string(), 0.5);
+        transfer_model.set_deposit_fee("E2".to_string(), "BTC".to_string(), 0.5);
+
+        let factor = |source: &str, destination: &str| {
+            transfer_model.get_bridge_factor(
+                &source.to_string(),
+                &destination.to_string(),
+                &"BTC".to_string(),
+            )
+        };
+
+        // Test that both withdrawal and deposit fees are applied in the direction only.
+        assert_eq!(factor("E1", "E2"), 0.125);
+        assert_eq!(factor("E2", "E1"), 0.5);
+        assert_eq!(factor("E1", "E3"), 0.25);
+    }
+
+    #[test]
+    fn read_schedule_from() {
+        let text_input = "# Network fee of BTC and wire fee of USD.
+E1 btc 0.5 0.0
+
+E2 USD 0.25 0.5"
+            .as_bytes();
+
+        let mut transfer_model = TransferModel::<String, f32>::new();
+        let mut input = BufReader::new(text_input);
+        assert_eq!(transfer_model.read_schedule_from(&mut input), Ok(()));
+
+        assert_eq!(
+            transfer_model.get_bridge_factor(
+                &"E1".to_string(),
+                &"E2".to_string(),
+                &"BTC".to_string()
+            ),
+            0.5
+        );
+        assert_eq!(
+            transfer_model.get_bridge_factor(
+                &"E2".to_string(),
+                &"E2".to_string(),
+                &"USD".to_string()
+            ),
+            0.375
+        );
+
+        // Test that a line with missing items is reported.
+        let mut input = BufReader::new("E1 BTC 0.5".as_bytes());
+        assert!(transfer_model.read_schedule_from(&mut input).is_err());
+    }
+}
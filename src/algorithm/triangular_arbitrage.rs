@@ -0,0 +1,165 @@
+//! Intra-exchange (triangular) arbitrage detection.
+//!
+//! Only cycles of currencies traded within a single exchange are considered, so no
+//! cross-exchange transfer is needed to execute them and no Floyd-Warshall run is required.
+
+use crate::algorithm::settings::Settings;
+use crate::algorithm::Algorithm;
+use crate::request::Request;
+use crate::response::arbitrage::Arbitrage;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use num_traits::Num;
+use safe_graph::NodeTrait;
+use std::cmp::Ordering::Equal;
+use std::fmt::{Debug, Display};
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+/// The minimal count of currencies forming an intra-exchange cycle.
+const MIN_CYCLE_CURRENCIES: usize = 3;
+
+/// The maximal count of currencies forming an intra-exchange cycle.
+const MAX_CYCLE_CURRENCIES: usize = 4;
+
+impl<N, E, I> Algorithm<N, E, I>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+    I: NodeTrait + Num + AddAssign,
+{
+    /// Find all profitable intra-exchange cycles for `PriceUpdate`s of the provided `Request`.
+    pub fn process_triangular_arbitrage(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+    ) -> Vec<Arbitrage<N, E>> {
+        let mut alg = Algorithm::<N, E, I>::new_customized(settings.clone());
+        alg.construct_graph(request);
+        alg.find_triangular_arbitrages()
+    }
+
+    /// Find all profitable cycles of 3 or 4 currencies within a single exchange.
+    ///
+    /// Only cycles with rates product exceeding the minimal profit factor are
+    /// considered profitable. Cycles must pass through one of the funding nodes if any.
+    ///
+    /// `Arbitrage`s are grouped by exchange (in order of appearance) and sorted from the most
+    /// profitable one within each exchange.
+    pub fn find_triangular_arbitrages(&self) -> Vec<Arbitrage<N, E>> {
+        let mut arbitrages = Vec::new();
+
+        for &exchange in self.exchanges.iter() {
+            let mut found: Vec<(E, Vec<I>)> = Vec::new();
+
+            // Start a search from every currency provided by the exchange.
+            for (&currency, exchanges) in self.currency_exchanges.iter() {
+                if exchanges.contains(&exchange) {
+                    let mut currencies = vec![currency];
+                    self.collect_exchange_cycles(exchange, &mut currencies, &mut found);
+                }
+            }
+
+            // Sort from the most profitable cycle.
+            found.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Equal));
+
+            for (profit_factor, currencies) in found {
+                let cycle: Vec<(I, I)> = currencies
+                    .into_iter()
+                    .map(|currency| (exchange, currency))
+                    .collect();
+
+                if !self.is_funded(&cycle, self.settings.get_funding()) {
+                    continue;
+                }
+
+                arbitrages.push(self.form_arbitrage(profit_factor, cycle));
+            }
+        }
+
+        arbitrages
+    }
+
+    /// Depth-first search of profitable cycles of the exchange continuing the `currencies` path.
+    ///
+    /// Each cycle is collected only once, starting at its currency with the lowest index.
+    fn collect_exchange_cycles(
+        &self,
+        exchange: I,
+        currencies: &mut Vec<I>,
+        found: &mut Vec<(E, Vec<I>)>,
+    ) {
+        let start = currencies[0];
+        let last = currencies[currencies.len() - 1];
+
+        for (_, (next_exchange, next_currency), _) in self.graph.edges((exchange, last)) {
+            // Skip cross-exchange transfer edges.
+            if next_exchange != exchange {
+                continue;
+            }
+
+            if next_currency == start {
+                if currencies.len() >= MIN_CYCLE_CURRENCIES {
+                    let mut cycle = currencies.clone();
+                    cycle.push(start);
+
+                    let path: Vec<(I, I)> = cycle.iter().map(|&c| (exchange, c)).collect();
+                    if let Some(profit_factor) = self.get_path_rate(&path) {
+                        if profit_factor > *self.settings.get_min_profit_factor() {
+                            found.push((profit_factor, cycle));
+                        }
+                    }
+                }
+            } else if next_currency > start
+                && currencies.len() < MAX_CYCLE_CURRENCIES
+                && !currencies.contains(&next_currency)
+            {
+                currencies.push(next_currency);
+                self.collect_exchange_cycles(exchange, currencies, found);
+                currencies.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::algorithm::Algorithm;
+    use crate::request::Request;
+    use std::io::BufReader;
+
+    #[test]
+    fn process_triangular_arbitrage() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E1 ETH USD 100.0 0.01
+2019-01-20T09:42:23+00:00 E1 ETH BTC 0.11 9.09
+2019-01-20T09:42:23+00:00 E2 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 ETH USD 100.0 0.01
+2019-01-20T09:42:23+00:00 E2 ETH BTC 0.1 10.0"
+            .as_bytes();
+
+        let mut input = BufReader::new(text_input);
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let arbitrages =
+            Algorithm::<String, f32, u32>::process_triangular_arbitrage(&request, &Settings::new());
+
+        // Test that only the mispriced exchange provides an arbitrage.
+        assert_eq!(arbitrages.len(), 1);
+        assert!((arbitrages[0].get_profit_factor() - 1.1).abs() < 1e-5);
+
+        // Test the cycle: sell BTC for USD, buy ETH for USD, sell ETH for BTC.
+        let e1 = "E1".to_string();
+        assert_eq!(
+            arbitrages[0].get_cycle(),
+            &[
+                (e1.clone(), "BTC".to_string()),
+                (e1.clone(), "USD".to_string()),
+                (e1.clone(), "ETH".to_string()),
+                (e1.clone(), "BTC".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! Backtesting over historical input.
+//!
+//! A `Backtest` replays timestamped price updates in the order of their timestamps, answers
+//! standing rate requests at a regular cadence of the replayed time and reports statistics of
+//! the rates over the whole period, e.g. to tell how a route would have performed.
+
+use crate::algorithm::settings::Settings;
+use crate::error::Error;
+use crate::request::exchange_rate_request::ExchangeRateRequest;
+use crate::request::{Line, Request};
+use crate::stream::StreamProcessor;
+use crate::IndexMapTrait;
+use chrono::{DateTime, Duration, FixedOffset};
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::io::BufRead;
+use std::str::FromStr;
+
+/// `Backtest` structure.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::backtest::Backtest;
+/// use exchange_rate::Settings;
+///
+/// let input = "2019-01-20T09:42:00+00:00 KRAKEN BTC USD 1000 0.0009
+/// 2019-01-20T09:43:30+00:00 KRAKEN BTC USD 1020 0.0009
+/// EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD";
+///
+/// let backtest = Backtest::<String, f64>::new(Settings::new(), chrono::Duration::seconds(60));
+/// let report = backtest.run(&mut input.as_bytes()).unwrap();
+///
+/// assert_eq!(report.get_evaluations(), 3);
+/// assert_eq!(report.get_statistics()[0].get_max(), Some(&1020.0));
+/// ```
+///
+/// # `Backtest<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct Backtest<N, E> {
+    settings: Settings<N, E>,
+    cadence: Duration,
+    rate_requests: Vec<ExchangeRateRequest<N, E>>,
+}
+
+impl<N, E> Backtest<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of `Backtest` structure answering the standing rate requests each
+    /// cadence of the replayed time.
+    pub fn new(settings: Settings<N, E>, cadence: Duration) -> Self {
+        Self {
+            settings,
+            cadence,
+            rate_requests: Vec::new(),
+        }
+    }
+
+    /// Add the standing `ExchangeRateRequest`, in addition to the ones of the input.
+    pub fn add_rate_request(&mut self, rate_request: ExchangeRateRequest<N, E>) {
+        self.rate_requests.push(rate_request);
+    }
+
+    /// Replay the input and report the statistics of rates of the standing requests.
+    ///
+    /// Price updates of the input are replayed in the order of their timestamps, its exchange
+    /// rate requests are standing ones. Requests are answered at the timestamp of the first
+    /// price update and each cadence after, until all price updates are replayed, using the ones
+    /// up to the moment. All lines that can't be parsed are reported at once by the error.
+    pub fn run<R: BufRead>(&self, input: &mut R) -> Result<Report<N, E>, Error> {
+        let mut errors = Vec::new();
+        let mut price_updates = Vec::new();
+        let mut rate_requests = self.rate_requests.clone();
+
+        for (number, line) in input.lines().enumerate() {
+            match Request::<N, E>::parse_line(&line?) {
+                Ok(Some(Line::PriceUpdate(price_update))) => price_updates.push(price_update),
+                Ok(Some(Line::RateRequest(rate_request))) => rate_requests.push(rate_request),
+                Ok(_) => {}
+                Err(error) => errors.push(format!(
+                    "The line {} can not be parsed, errors: {:?}!",
+                    number + 1,
+                    error.get_messages()
+                )),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(Error::Parse(errors));
+        }
+
+        if self.cadence <= Duration::zero() {
+            errors.push("The cadence of the backtest has to be positive!".to_string());
+        }
+        if rate_requests.is_empty() {
+            errors.push("The backtest has no exchange rate request to answer!".to_string());
+        }
+        if price_updates.is_empty() {
+            errors.push("The backtest has no price update to replay!".to_string());
+        }
+        if !errors.is_empty() {
+            return Err(Error::Validation(errors));
+        }
+
+        // The sort is stable, price updates of the same moment keep their order.
+        price_updates.sort_by_key(|price_update| *price_update.get_timestamp());
+        let start = *price_updates[0].get_timestamp();
+
+        let mut statistics: Vec<RateStatistics<N, E>> = Vec::new();
+        for rate_request in rate_requests.iter() {
+            let pair = rate_request.get_index();
+            if statistics.iter().all(|statistics| statistics.pair != pair) {
+                statistics.push(RateStatistics::new(pair));
+            }
+        }
+
+        let mut processor = StreamProcessor::new(self.settings.clone());
+        let mut price_updates = price_updates.into_iter().peekable();
+        let mut moment = start;
+        let mut evaluations = 0;
+        loop {
+            while let Some(price_update) =
+                price_updates.next_if(|price_update| *price_update.get_timestamp() <= moment)
+            {
+                // Alerts aren't monitored and the graph size isn't limited, it's always added.
+                let _ = processor.add_price_update(price_update);
+            }
+
+            let lines = rate_requests
+                .iter()
+                .cloned()
+                .map(Line::RateRequest)
+                .collect();
+            let response = processor.answer(lines);
+            for statistics in statistics.iter_mut() {
+                let rate = response.get_best_rate_paths().iter().find_map(|path| {
+                    match (path.get_start_node(), path.get_end_node()) {
+                        (Some((a, b)), Some((c, d)))
+                            if (a, b, c, d)
+                                == (
+                                    &statistics.pair.0,
+                                    &statistics.pair.1,
+                                    &statistics.pair.2,
+                                    &statistics.pair.3,
+                                ) =>
+                        {
+                            Some(*path.get_rate())
+                        }
+                        _ => None,
+                    }
+                });
+                statistics.record(rate);
+            }
+            evaluations += 1;
+
+            if price_updates.peek().is_none() {
+                break;
+            }
+            moment += self.cadence;
+        }
+
+        Ok(Report {
+            start,
+            end: moment,
+            evaluations,
+            statistics,
+        })
+    }
+}
+
+/// `Report` structure.
+///
+/// The period a `Backtest` replayed and the statistics of rates of its standing requests.
+///
+/// # `Report<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct Report<N, E> {
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    evaluations: usize,
+    statistics: Vec<RateStatistics<N, E>>,
+}
+
+impl<N, E> Report<N, E>
+where
+    N: Display,
+    E: Display + FloydWarshallTrait + FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    /// Get the moment of the first evaluation of the standing requests.
+    pub fn get_start(&self) -> &DateTime<FixedOffset> {
+        &self.start
+    }
+
+    /// Get the moment of the last evaluation of the standing requests.
+    pub fn get_end(&self) -> &DateTime<FixedOffset> {
+        &self.end
+    }
+
+    /// Get count of evaluations of the standing requests.
+    pub fn get_evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    /// Get statistics of each standing request, in the order of the requests.
+    pub fn get_statistics(&self) -> &[RateStatistics<N, E>] {
+        &self.statistics
+    }
+
+    /// Get printable output representing the Report.
+    ///
+    /// # Format
+    ///
+    /// BACKTEST_BEGIN <start> <end> <evaluations>
+    /// RATE_STATISTICS <source_exchange> <source_currency> <destination_exchange>
+    /// <destination_currency> <answered, count> <min, rate> <mean, rate> <max, rate>
+    /// <first, rate> <last, rate>
+    /// ...
+    /// BACKTEST_END
+    ///
+    /// The rates are omitted if the request was never answered.
+    pub fn get_output(&self) -> String {
+        let mut output = format!(
+            "BACKTEST_BEGIN <{}> <{}> <{}>\n",
+            self.start.to_rfc3339(),
+            self.end.to_rfc3339(),
+            self.evaluations
+        );
+        for statistics in self.statistics.iter() {
+            output.push_str(&statistics.get_output());
+        }
+        output.push_str("BACKTEST_END\n");
+
+        output
+    }
+}
+
+/// Rates of a standing request, summed up as they're recorded.
+struct Rates<E> {
+    min: E,
+    max: E,
+    sum: E,
+    first: E,
+    last: E,
+}
+
+/// `RateStatistics` structure.
+///
+/// Statistics of the rates a standing request of a `Backtest` was answered by.
+///
+/// # `RateStatistics<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct RateStatistics<N, E> {
+    pair: (N, N, N, N),
+    answered: usize,
+    rates: Option<Rates<E>>,
+}
+
+impl<N, E> RateStatistics<N, E>
+where
+    N: Display,
+    E: Display + FloydWarshallTrait + FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    fn new(pair: (N, N, N, N)) -> Self {
+        Self {
+            pair,
+            answered: 0,
+            rates: None,
+        }
+    }
+
+    /// Record the rate of an evaluation, `None` if the request wasn't answered.
+    fn record(&mut self, rate: Option<E>) {
+        let rate = match rate {
+            Some(rate) => rate,
+            None => return,
+        };
+        self.answered += 1;
+
+        match self.rates.as_mut() {
+            Some(rates) => {
+                if rate < rates.min {
+                    rates.min = rate;
+                }
+                if rate > rates.max {
+                    rates.max = rate;
+                }
+                rates.sum = rates.sum + rate;
+                rates.last = rate;
+            }
+            None => {
+                self.rates = Some(Rates {
+                    min: rate,
+                    max: rate,
+                    sum: rate,
+                    first: rate,
+                    last: rate,
+                })
+            }
+        }
+    }
+
+    /// Get the `(source_exchange, source_currency, destination_exchange, destination_currency)`
+    /// pair of the request.
+    pub fn get_pair(&self) -> &(N, N, N, N) {
+        &self.pair
+    }
+
+    /// Get count of evaluations the request was answered by.
+    pub fn get_answered(&self) -> usize {
+        self.answered
+    }
+
+    pub fn get_min(&self) -> Option<&E> {
+        self.rates.as_ref().map(|rates| &rates.min)
+    }
+
+    pub fn get_max(&self) -> Option<&E> {
+        self.rates.as_ref().map(|rates| &rates.max)
+    }
+
+    /// Get the mean of the rates the request was answered by.
+    pub fn get_mean(&self) -> Option<E> {
+        let answered = self
+            .answered
+            .to_string()
+            .parse::<E>()
+            .expect("A count is a valid edge weight!");
+
+        self.rates.as_ref().map(|rates| rates.sum / answered)
+    }
+
+    pub fn get_first(&self) -> Option<&E> {
+        self.rates.as_ref().map(|rates| &rates.first)
+    }
+
+    pub fn get_last(&self) -> Option<&E> {
+        self.rates.as_ref().map(|rates| &rates.last)
+    }
+
+    fn get_output(&self) -> String {
+        let (source_exchange, source_currency, destination_exchange, destination_currency) =
+            &self.pair;
+        let mut output = format!(
+            "RATE_STATISTICS <{}> <{}> <{}> <{}> <answered, {}>",
+            source_exchange,
+            source_currency,
+            destination_exchange,
+            destination_currency,
+            self.answered
+        );
+        if let (Some(rates), Some(mean)) = (self.rates.as_ref(), self.get_mean()) {
+            output.push_str(&format!(
+                " <min, {}> <mean, {}> <max, {}> <first, {}> <last, {}>",
+                rates.min, mean, rates.max, rates.first, rates.last
+            ));
+        }
+        output.push('\n');
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::backtest::Backtest;
+    use crate::error::Error;
+    use crate::request::exchange_rate_request::ExchangeRateRequest;
+    use chrono::Duration;
+
+    #[test]
+    fn run() {
+        // The price updates aren't in the order of their timestamps.
+        let input = "EXCHANGE_RATE_REQUEST E1 BTC E1 USD
+2019-01-20T09:42:30+00:00 E1 BTC USD 1030 0.0009
+2019-01-20T09:42:00+00:00 E1 BTC USD 1000 0.0009
+2019-01-20T09:43:10+00:00 E1 BTC USD 1060 0.0009
+2019-01-20T09:42:50+00:00 E1 BTC USD 1010 0.0009";
+
+        let mut backtest = Backtest::<String, f32>::new(Settings::new(), Duration::seconds(20));
+        backtest.add_rate_request(ExchangeRateRequest::new(
+            "E1".to_string(),
+            "BTC".to_string(),
+            "E2".to_string(),
+            "USD".to_string(),
+        ));
+        let report = backtest.run(&mut input.as_bytes()).unwrap();
+
+        // Test that requests are answered each cadence until all price updates are replayed,
+        // by rates of 1000 at 09:42:00, 1000 at :20, 1030 at :40, 1010 at 09:43:00 and 1060 at :20.
+        assert_eq!(report.get_evaluations(), 5);
+        assert_eq!(
+            report.get_output(),
+            "BACKTEST_BEGIN <2019-01-20T09:42:00+00:00> <2019-01-20T09:43:20+00:00> <5>
+RATE_STATISTICS <E1> <BTC> <E2> <USD> <answered, 0>
+RATE_STATISTICS <E1> <BTC> <E1> <USD> <answered, 5> <min, 1000> <mean, 1020> <max, 1060> <first, 1000> <last, 1060>
+BACKTEST_END
+"
+        );
+    }
+
+    #[test]
+    fn run_invalid_input() {
+        let backtest = Backtest::<String, f32>::new(Settings::new(), Duration::zero());
+
+        // Test that all lines that can't be parsed are reported at once.
+        let input = "2019-01-20T09:42:00+00:00 E1 BTC\nEXCHANGE_RATE_REQUEST E1 BTC";
+        match backtest.run(&mut input.as_bytes()) {
+            Err(Error::Parse(messages)) => assert_eq!(messages.len(), 2),
+            _ => panic!("The input is reported by a parse error!"),
+        }
+
+        // Test that a zero cadence and an input without price updates are not valid.
+        match backtest.run(&mut "EXCHANGE_RATE_REQUEST E1 BTC E1 USD".as_bytes()) {
+            Err(Error::Validation(messages)) => assert_eq!(messages.len(), 2),
+            _ => panic!("The backtest is reported by a validation error!"),
+        }
+    }
+}
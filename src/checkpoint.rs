@@ -0,0 +1,229 @@
+//! Periodic checkpoints of the known price updates to the snapshot file.
+
+use crate::stream::StreamProcessor;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::ffi::OsString;
+use std::fmt::{Debug, Display};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest time between two checks whether a checkpoint is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `Checkpoints` structure.
+///
+/// Policy of writing the snapshot periodically, every interval or every count of accepted
+/// price updates, whichever comes first. A checkpoint is written only if price updates were
+/// accepted since the last one. Replaced snapshots are kept as `<path>.1` (the most recent one)
+/// up to `<path>.<retention>`.
+#[derive(Clone, Debug, Default)]
+pub struct Checkpoints {
+    interval: Option<Duration>,
+    updates: Option<u64>,
+    retention: usize,
+}
+
+impl Checkpoints {
+    /// Create a new instance of `Checkpoints` structure, writing no checkpoints and keeping no
+    /// replaced snapshots.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set time between two checkpoints, `None` for no periodic checkpoints.
+    pub fn set_interval(&mut self, interval: Option<Duration>) {
+        self.interval = interval;
+    }
+
+    /// Set count of accepted price updates between two checkpoints, `None` for no checkpoints
+    /// by count.
+    pub fn set_updates(&mut self, updates: Option<u64>) {
+        self.updates = updates;
+    }
+
+    /// Set count of replaced snapshots to keep.
+    pub fn set_retention(&mut self, retention: usize) {
+        self.retention = retention;
+    }
+
+    pub fn get_retention(&self) -> usize {
+        self.retention
+    }
+
+    /// Check whether any checkpoints are written.
+    pub fn is_enabled(&self) -> bool {
+        self.interval.is_some() || self.updates.is_some()
+    }
+
+    /// Check whether a checkpoint is due after the time elapsed and the count of price updates
+    /// accepted since the last one.
+    fn is_due(&self, elapsed: Duration, updates: u64) -> bool {
+        updates > 0
+            && (self.interval.is_some_and(|interval| elapsed >= interval)
+                || self.updates.is_some_and(|count| updates >= count))
+    }
+}
+
+/// Write the known `PriceUpdate`s to the snapshot file of the path whenever a checkpoint of the
+/// policy is due, from a thread of its own until the process exits.
+///
+/// The snapshot is collected with the processor locked and written after unlocking it, so the
+/// processing isn't held up by the file system. A failed checkpoint is reported and the next
+/// one is tried when due again.
+pub fn checkpoint_periodically<N, E>(
+    processor: Arc<Mutex<StreamProcessor<N, E>>>,
+    path: PathBuf,
+    checkpoints: Checkpoints,
+) where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    if !checkpoints.is_enabled() {
+        return;
+    }
+
+    let check_interval = checkpoints
+        .interval
+        .map_or(CHECK_INTERVAL, |interval| interval.min(CHECK_INTERVAL));
+    let get_price_updates =
+        |processor: &StreamProcessor<N, E>| processor.get_metrics().get_price_updates();
+    let mut last_price_updates =
+        get_price_updates(&processor.lock().unwrap_or_else(PoisonError::into_inner));
+
+    thread::spawn(move || {
+        let mut last_checkpoint = Instant::now();
+
+        loop {
+            thread::sleep(check_interval);
+
+            let (price_updates, snapshot) = {
+                let processor = processor.lock().unwrap_or_else(PoisonError::into_inner);
+                let price_updates = get_price_updates(&processor);
+                if !checkpoints.is_due(
+                    last_checkpoint.elapsed(),
+                    price_updates - last_price_updates,
+                ) {
+                    continue;
+                }
+
+                let mut snapshot = Vec::new();
+                // Writing to a vector can't fail.
+                let _ = processor.write_snapshot(&mut snapshot);
+                (price_updates, snapshot)
+            };
+
+            last_checkpoint = Instant::now();
+            match write_snapshot(&path, &snapshot, checkpoints.retention) {
+                Ok(()) => last_price_updates = price_updates,
+                Err(error) => eprintln!(
+                    "The checkpoint can not be written to <{}>, error: {}!",
+                    path.display(),
+                    error
+                ),
+            }
+        }
+    });
+}
+
+/// Replace the snapshot file of the path by the content atomically, keeping the count of
+/// replaced snapshots.
+///
+/// The content is written to `<path>.tmp` and synced first, so a crash leaves either the old
+/// snapshot or the new one in place, never a partial one.
+pub fn write_snapshot(path: &Path, content: &[u8], retention: usize) -> io::Result<()> {
+    let temporary = with_suffix(path, "tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+
+    if retention > 0 && path.exists() {
+        remove_if_exists(&with_suffix(path, &retention.to_string()))?;
+        for index in (1..retention).rev() {
+            let from = with_suffix(path, &index.to_string());
+            if from.exists() {
+                fs::rename(from, with_suffix(path, &(index + 1).to_string()))?;
+            }
+        }
+        // A link keeps the snapshot in place until it's replaced.
+        fs::hard_link(path, with_suffix(path, "1"))?;
+    }
+
+    fs::rename(temporary, path)
+}
+
+/// Get the path with the suffix appended after a dot, e.g. `snapshot.txt.1`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(".");
+    path.push(suffix);
+
+    PathBuf::from(path)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checkpoint::{with_suffix, write_snapshot, Checkpoints};
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn is_due() {
+        let mut checkpoints = Checkpoints::new();
+        assert!(!checkpoints.is_enabled());
+
+        checkpoints.set_interval(Some(Duration::from_secs(60)));
+        checkpoints.set_updates(Some(100));
+        assert!(checkpoints.is_enabled());
+
+        assert!(!checkpoints.is_due(Duration::from_secs(59), 99));
+        assert!(checkpoints.is_due(Duration::from_secs(60), 1));
+        assert!(checkpoints.is_due(Duration::from_secs(1), 100));
+
+        // Test that nothing is written without new price updates.
+        assert!(!checkpoints.is_due(Duration::from_secs(120), 0));
+    }
+
+    #[test]
+    fn write_snapshot_with_retention() {
+        let directory =
+            std::env::temp_dir().join(format!("exchange-rate-{}.checkpoint", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("snapshot.txt");
+        let read = |suffix: &str| fs::read_to_string(with_suffix(&path, suffix)).ok();
+
+        for content in ["1", "2", "3", "4"] {
+            write_snapshot(&path, content.as_bytes(), 2).unwrap();
+        }
+
+        // Test that the two most recent replaced snapshots are kept.
+        assert_eq!(fs::read_to_string(&path).unwrap(), "4");
+        assert_eq!(read("1"), Some("3".to_string()));
+        assert_eq!(read("2"), Some("2".to_string()));
+        assert_eq!(read("3"), None);
+        assert_eq!(read("tmp"), None);
+
+        // Test that no replaced snapshot is kept without retention.
+        write_snapshot(&path, b"5", 0).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "5");
+        assert_eq!(read("1"), Some("3".to_string()));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
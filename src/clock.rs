@@ -0,0 +1,44 @@
+//! Clock of the computation.
+//!
+//! `wasm32-unknown-unknown` has no clock, `std::time::Instant` and the system time panic there.
+//! So time stands still in WebAssembly: measured durations are zero and the computation moment
+//! is the Unix epoch.
+
+#[cfg(target_arch = "wasm32")]
+use chrono::TimeZone;
+use chrono::{DateTime, Utc};
+#[cfg(target_arch = "wasm32")]
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::Instant;
+
+/// `Instant` structure.
+///
+/// Moment of the standing clock, every one is the same.
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Instant;
+
+#[cfg(target_arch = "wasm32")]
+impl Instant {
+    pub fn now() -> Self {
+        Instant
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Get the current moment.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now() -> DateTime<Utc> {
+    Utc::now()
+}
+
+/// Get the current moment, the Unix epoch as there is no clock.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now() -> DateTime<Utc> {
+    Utc.timestamp_opt(0, 0).unwrap()
+}
@@ -0,0 +1,609 @@
+//! Fee and Transfer Configuration.
+
+use self::toml::{Entry, Table, Value};
+use crate::algorithm::fee_model::FeeModel;
+use crate::algorithm::settings::{Backend, PathSemantics, Settings};
+use crate::algorithm::trading_rules::TradingRules;
+use crate::algorithm::transfer_model::TransferModel;
+use crate::error::Error;
+use crate::IndexMapTrait;
+use indexmap::IndexMap;
+use num_traits::Num;
+use std::io::BufRead;
+use std::str::FromStr;
+
+mod toml;
+
+/// Fee and transfer `Config` structure.
+///
+/// Holds trading fees, trading rules, transfer fees and disabled transfer routes read from a TOML
+/// configuration (e.g. `fees.toml`), so they don't have to be passed as command line options.
+///
+/// Named profiles of algorithm settings can be held as well, e.g. a `fast` one for research and
+/// an `exact` one for production, so the same file serves both. The selected profile is applied
+/// together with the rest of the config.
+///
+/// # `Config<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+#[derive(Clone, Debug)]
+pub struct Config<N, E> {
+    fee_model: FeeModel<N, E>,
+    trading_rules: TradingRules<N, E>,
+    transfer_model: TransferModel<N, E>,
+    profiles: IndexMap<String, Vec<Entry>>,
+    profile: Option<String>,
+}
+
+impl<N, E> Config<N, E>
+where
+    N: IndexMapTrait + Clone + FromStr,
+    E: Num + Copy + FromStr,
+{
+    /// Create a new instance of `Config` structure without any fees.
+    pub fn new() -> Self {
+        Self {
+            fee_model: FeeModel::new(),
+            trading_rules: TradingRules::new(),
+            transfer_model: TransferModel::new(),
+            profiles: IndexMap::new(),
+            profile: None,
+        }
+    }
+
+    pub fn get_fee_model(&self) -> &FeeModel<N, E> {
+        &self.fee_model
+    }
+
+    pub fn get_trading_rules(&self) -> &TradingRules<N, E> {
+        &self.trading_rules
+    }
+
+    pub fn get_transfer_model(&self) -> &TransferModel<N, E> {
+        &self.transfer_model
+    }
+
+    /// Get names of the profiles in the order of appearance.
+    pub fn get_profile_names(&self) -> Vec<&str> {
+        self.profiles.keys().map(String::as_str).collect()
+    }
+
+    /// Select the profile of the name applied to the `Settings`, return `Error::Validation` if
+    /// the config has no such profile.
+    pub fn select_profile(&mut self, name: &str) -> Result<(), Error> {
+        if !self.profiles.contains_key(name) {
+            return Err(Error::Validation(vec![format!(
+                "The profile <{}> is not known, profiles are {:?}!",
+                name,
+                self.get_profile_names()
+            )]));
+        }
+
+        self.profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Use the fee model, trading rules and transfer model of the `Config` in the `Settings`,
+    /// and the settings of the selected profile if any.
+    pub fn apply_to(self, settings: &mut Settings<N, E>) {
+        settings.set_fee_model(self.fee_model);
+        settings.set_trading_rules(self.trading_rules);
+        settings.set_transfer_model(self.transfer_model);
+
+        let profiles = self.profiles;
+        let entries = self
+            .profile
+            .and_then(|profile| profiles.get(&profile))
+            .into_iter()
+            .flatten();
+        for entry in entries {
+            // Entries of profiles were validated once read.
+            let _ = apply_profile_entry(settings, entry);
+        }
+    }
+
+    /// Read the configuration from the TOML input.
+    ///
+    /// # Input format
+    ///
+    /// All tables are optional, fees are fractions (e.g. `0.001` for 0.1%), exchanges
+    /// and currencies are case insensitive.
+    ///
+    /// ## Example
+    ///
+    /// ```toml
+    /// [trading]
+    /// fee = 0.002
+    /// maker_fee = 0.001
+    /// fixed_fee = 0.0
+    /// reference_amount = 1.0
+    ///
+    /// [trading.exchanges.KRAKEN]
+    /// fee = 0.0026
+    /// maker_fee = 0.0016
+    /// fixed_fee = 0.0
+    ///
+    /// [[trading.disabled_pairs]]
+    /// exchange = "GDAX"
+    /// base = "ETH"
+    /// quote = "EUR"
+    ///
+    /// [[trading.min_trade_amounts]]
+    /// exchange = "KRAKEN"
+    /// source = "BTC"
+    /// destination = "USD"
+    /// amount = 0.0001
+    ///
+    /// [transfer]
+    /// fee = 0.0005
+    /// latency = 5
+    /// disabled_currencies = ["USD"]
+    ///
+    /// [transfer.currencies.BTC]
+    /// fee = 0.0002
+    /// latency = 60
+    ///
+    /// [transfer.exchanges.KRAKEN.BTC]
+    /// withdrawal_fee = 0.0005
+    /// deposit_fee = 0.0
+    ///
+    /// [[transfer.disabled_routes]]
+    /// source = "KRAKEN"
+    /// destination = "GDAX"
+    /// currency = "ETH"
+    ///
+    /// [profiles.fast]
+    /// backend = "bellman-ford"
+    /// comparison_epsilon = 0.0001
+    ///
+    /// [profiles.exact]
+    /// comparison_epsilon = 0.0
+    /// discard_loops = true
+    /// ```
+    ///
+    /// Keys of a profile are the ones of the JSON representation of `Settings` holding a single
+    /// value: `min_profit_factor`, `max_cycle_length`, `alert_debounce`, `transfer_penalty`,
+    /// `quote_half_life`, `discard_loops`, `path_semantics`, `comparison_epsilon`, `max_hops` and
+    /// `backend`.
+    pub fn read_from<R: BufRead>(&mut self, input: &mut R) -> Result<(), Error> {
+        let mut text = String::new();
+        input.read_to_string(&mut text)?;
+
+        let mut errors: Vec<String> = Vec::new();
+        for table in toml::parse(&text).map_err(Error::Parse)?.iter() {
+            errors.extend(self.read_table(table));
+        }
+
+        if !errors.is_empty() {
+            return Err(Error::Validation(errors));
+        }
+
+        Ok(())
+    }
+
+    fn read_table(&mut self, table: &Table) -> Vec<String> {
+        let path: Vec<&str> = table.path.iter().map(|key| key.as_str()).collect();
+
+        match (path.as_slice(), table.is_array_item) {
+            ([], false) => read_entries(table, |entry| Err(unknown_key(entry))),
+            (["trading"], false) => read_entries(table, |entry| {
+                let fee_model = &mut self.fee_model;
+
+                match entry.key.as_str() {
+                    "fee" => fee_model.set_default_fee(number(entry)?),
+                    "maker_fee" => fee_model.set_default_maker_fee(Some(number(entry)?)),
+                    "fixed_fee" => fee_model.set_default_fixed_fee(number(entry)?),
+                    "reference_amount" => fee_model.set_reference_amount(number(entry)?),
+                    _ => return Err(unknown_key(entry)),
+                }
+                Ok(())
+            }),
+            (["trading", "exchanges", exchange], false) => {
+                read_named_entries(table, &[*exchange], |mut names, entry| {
+                    let fee_model = &mut self.fee_model;
+                    let exchange = names.remove(0);
+
+                    match entry.key.as_str() {
+                        "fee" => fee_model.set_exchange_fee(exchange, number(entry)?),
+                        "maker_fee" => fee_model.set_exchange_maker_fee(exchange, number(entry)?),
+                        "fixed_fee" => fee_model.set_exchange_fixed_fee(exchange, number(entry)?),
+                        _ => return Err(unknown_key(entry)),
+                    }
+                    Ok(())
+                })
+            }
+            (["transfer"], false) => read_entries(table, |entry| {
+                let transfer_model = &mut self.transfer_model;
+
+                match entry.key.as_str() {
+                    "fee" => transfer_model.set_default_fee(number(entry)?),
+                    "latency" => transfer_model.set_default_latency(number(entry)?),
+                    "disabled_currencies" => {
+                        for currency in identifiers(entry)? {
+                            transfer_model.set_transfer_enabled(currency, false);
+                        }
+                    }
+                    _ => return Err(unknown_key(entry)),
+                }
+                Ok(())
+            }),
+            (["transfer", "currencies", currency], false) => {
+                read_named_entries(table, &[*currency], |mut names, entry| {
+                    let transfer_model = &mut self.transfer_model;
+                    let currency = names.remove(0);
+
+                    match entry.key.as_str() {
+                        "fee" => transfer_model.set_currency_fee(currency, number(entry)?),
+                        "latency" => transfer_model.set_currency_latency(currency, number(entry)?),
+                        _ => return Err(unknown_key(entry)),
+                    }
+                    Ok(())
+                })
+            }
+            (["transfer", "exchanges", exchange, currency], false) => {
+                read_named_entries(table, &[*exchange, *currency], |mut names, entry| {
+                    let transfer_model = &mut self.transfer_model;
+                    let currency = names.remove(1);
+                    let exchange = names.remove(0);
+
+                    match entry.key.as_str() {
+                        "withdrawal_fee" => {
+                            transfer_model.set_withdrawal_fee(exchange, currency, number(entry)?)
+                        }
+                        "deposit_fee" => {
+                            transfer_model.set_deposit_fee(exchange, currency, number(entry)?)
+                        }
+                        _ => return Err(unknown_key(entry)),
+                    }
+                    Ok(())
+                })
+            }
+            (["trading", "disabled_pairs"], true) => {
+                match read_item::<N, E, 3>(table, ["exchange", "base", "quote"], None) {
+                    Ok(([exchange, base, quote], _)) => {
+                        self.trading_rules
+                            .set_pair_enabled(exchange, base, quote, false);
+                        Vec::new()
+                    }
+                    Err(errors) => errors,
+                }
+            }
+            (["trading", "min_trade_amounts"], true) => {
+                let keys = ["exchange", "source", "destination"];
+
+                match read_item::<N, E, 3>(table, keys, Some("amount")) {
+                    Ok(([exchange, source, destination], Some(amount))) => {
+                        self.trading_rules.set_min_trade_amount(
+                            exchange,
+                            source,
+                            destination,
+                            amount,
+                        );
+                        Vec::new()
+                    }
+                    // The amount is required, so it's never missing.
+                    Ok((_, None)) => Vec::new(),
+                    Err(errors) => errors,
+                }
+            }
+            (["transfer", "disabled_routes"], true) => {
+                let keys = ["source", "destination", "currency"];
+
+                match read_item::<N, E, 3>(table, keys, None) {
+                    Ok(([source, destination, currency], _)) => {
+                        self.transfer_model
+                            .set_route_enabled(source, destination, currency, false);
+                        Vec::new()
+                    }
+                    Err(errors) => errors,
+                }
+            }
+            (["profiles", name], false) => {
+                // The entries are validated on settings of their own, they're applied later.
+                let mut settings = Settings::<N, E>::new();
+                let errors = read_entries(table, |entry| apply_profile_entry(&mut settings, entry));
+                if errors.is_empty() {
+                    self.profiles
+                        .entry(name.to_string())
+                        .or_default()
+                        .extend(table.entries.iter().cloned());
+                }
+                errors
+            }
+            _ => vec![format!("The table of line {} is not known!", table.line)],
+        }
+    }
+}
+
+impl<N, E> Default for Config<N, E>
+where
+    N: IndexMapTrait + Clone + FromStr,
+    E: Num + Copy + FromStr,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read all entries of the table, collecting errors.
+fn read_entries<F>(table: &Table, mut read_entry: F) -> Vec<String>
+where
+    F: FnMut(&Entry) -> Result<(), String>,
+{
+    table
+        .entries
+        .iter()
+        .filter_map(|entry| read_entry(entry).err())
+        .collect()
+}
+
+/// Read an item of an array of tables, all the identifier `keys` and the `number_key` are
+/// required.
+fn read_item<N, E, const COUNT: usize>(
+    table: &Table,
+    keys: [&str; COUNT],
+    number_key: Option<&str>,
+) -> Result<([N; COUNT], Option<E>), Vec<String>>
+where
+    N: FromStr,
+    E: FromStr,
+{
+    let mut identifiers: [Option<N>; COUNT] = std::array::from_fn(|_| None);
+    let mut value = None;
+
+    let errors = read_entries(table, |entry| {
+        if Some(entry.key.as_str()) == number_key {
+            value = Some(number(entry)?);
+        } else {
+            let position = keys
+                .iter()
+                .position(|key| *key == entry.key)
+                .ok_or_else(|| unknown_key(entry))?;
+
+            identifiers[position] = Some(parse_identifier(&string(entry)?, entry.line)?);
+        }
+        Ok(())
+    });
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    if identifiers.iter().any(Option::is_none) || (number_key.is_some() && value.is_none()) {
+        let required: Vec<&str> = keys.iter().copied().chain(number_key).collect();
+
+        return Err(vec![format!(
+            "The table of line {} needs all of the keys {:?}!",
+            table.line, required
+        )]);
+    }
+
+    Ok((identifiers.map(Option::unwrap), value))
+}
+
+/// Read all entries of the table named by exchange or currency identifiers of its path.
+fn read_named_entries<N, F>(table: &Table, names: &[&str], mut read_entry: F) -> Vec<String>
+where
+    N: FromStr,
+    F: FnMut(Vec<N>, &Entry) -> Result<(), String>,
+{
+    read_entries(table, |entry| {
+        let names = names
+            .iter()
+            .map(|name| parse_identifier(name, table.line))
+            .collect::<Result<Vec<N>, String>>()?;
+
+        read_entry(names, entry)
+    })
+}
+
+/// Apply the entry of a profile to the `Settings`.
+fn apply_profile_entry<N, E>(settings: &mut Settings<N, E>, entry: &Entry) -> Result<(), String>
+where
+    N: IndexMapTrait + Clone,
+    E: Num + Copy + FromStr,
+{
+    match entry.key.as_str() {
+        "min_profit_factor" => settings.set_min_profit_factor(number(entry)?),
+        "max_cycle_length" => settings.set_max_cycle_length(number(entry)?),
+        "alert_debounce" => settings.set_alert_debounce(number(entry)?),
+        "transfer_penalty" => settings.set_transfer_penalty(number(entry)?),
+        "quote_half_life" => settings.set_quote_half_life(Some(number(entry)?)),
+        "discard_loops" => settings.set_discard_loops(boolean(entry)?),
+        "path_semantics" => settings.set_path_semantics(label(entry, PathSemantics::parse)?),
+        "comparison_epsilon" => settings.set_comparison_epsilon(number(entry)?),
+        "max_hops" => settings.set_max_hops(Some(number(entry)?)),
+        "backend" => settings.set_backend(label(entry, Backend::parse)?),
+        _ => return Err(unknown_key(entry)),
+    }
+    Ok(())
+}
+
+fn unknown_key(entry: &Entry) -> String {
+    format!(
+        "The key <{}> of line {} is not known!",
+        entry.key, entry.line
+    )
+}
+
+fn parse_identifier<N: FromStr>(name: &str, line: usize) -> Result<N, String> {
+    name.to_uppercase()
+        .parse::<N>()
+        .map_err(|_| format!("The identifier <{}> of line {} is not valid!", name, line))
+}
+
+fn number<E: FromStr>(entry: &Entry) -> Result<E, String> {
+    match &entry.value {
+        Value::Number(number) => number.parse::<E>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("The value of line {} is not a valid number!", entry.line))
+}
+
+fn string(entry: &Entry) -> Result<String, String> {
+    match &entry.value {
+        Value::String(string) => Ok(string.clone()),
+        _ => Err(format!("The value of line {} is not a string!", entry.line)),
+    }
+}
+
+fn boolean(entry: &Entry) -> Result<bool, String> {
+    match &entry.value {
+        Value::Boolean(boolean) => Ok(*boolean),
+        _ => Err(format!(
+            "The value of line {} is not a boolean!",
+            entry.line
+        )),
+    }
+}
+
+/// Parse the string value by its label.
+fn label<T, F>(entry: &Entry, parse: F) -> Result<T, String>
+where
+    F: Fn(&str) -> Option<T>,
+{
+    parse(&string(entry)?)
+        .ok_or_else(|| format!("The value of line {} is not a valid choice!", entry.line))
+}
+
+fn identifiers<N: FromStr>(entry: &Entry) -> Result<Vec<N>, String> {
+    match &entry.value {
+        Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                Value::String(name) => parse_identifier(name, entry.line),
+                _ => Err(format!("The value of line {} is not a string!", entry.line)),
+            })
+            .collect(),
+        _ => Err(format!(
+            "The value of line {} is not an array of strings!",
+            entry.line
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::fee_model::ExecutionStyle::*;
+    use crate::algorithm::settings::{Backend, PathSemantics, Settings};
+    use crate::config::Config;
+    use std::io::BufReader;
+
+    #[test]
+    fn read_from() {
+        let text_input = "[trading]
+fee = 0.5
+maker_fee = 0.25
+
+[trading.exchanges.kraken]
+fee = 0.75
+
+[[trading.disabled_pairs]]
+exchange = \"E1\"
+base = \"BTC\"
+quote = \"USD\"
+
+[[trading.min_trade_amounts]]
+exchange = \"E2\"
+source = \"BTC\"
+destination = \"USD\"
+amount = 0.5
+
+[transfer]
+fee = 0.5
+disabled_currencies = [\"usd\"]
+
+[transfer.currencies.BTC]
+fee = 0.25
+latency = 60
+
+[transfer.exchanges.E1.BTC]
+withdrawal_fee = 0.5
+deposit_fee = 0.5
+
+[[transfer.disabled_routes]]
+source = \"E2\"
+destination = \"E1\"
+currency = \"BTC\"
+"
+        .as_bytes();
+
+        let mut config = Config::<String, f32>::new();
+        let mut input = BufReader::new(text_input);
+        assert_eq!(config.read_from(&mut input), Ok(()));
+
+        let fee_model = config.get_fee_model();
+        assert_eq!(fee_model.get_trade_factor(&"GDAX".to_string(), Taker), 0.5);
+        assert_eq!(fee_model.get_trade_factor(&"GDAX".to_string(), Maker), 0.75);
+        assert_eq!(
+            fee_model.get_trade_factor(&"KRAKEN".to_string(), Taker),
+            0.25
+        );
+
+        let trading_rules = config.get_trading_rules();
+        let (e1, e2, btc) = ("E1".to_string(), "E2".to_string(), "BTC".to_string());
+        let usd = "USD".to_string();
+        assert!(!trading_rules.is_pair_enabled(&e1, &usd, &btc));
+        assert!(!trading_rules.is_trade_allowed(&e2, &btc, &usd, 0.25));
+        assert!(trading_rules.is_trade_allowed(&e2, &btc, &usd, 0.5));
+
+        let transfer_model = config.get_transfer_model();
+        assert!(!transfer_model.is_transfer_enabled(&usd));
+        assert_eq!(transfer_model.get_transfer_factor(&"ETH".to_string()), 0.5);
+        assert_eq!(transfer_model.get_bridge_factor(&e1, &e1, &btc), 0.1875);
+        assert_eq!(transfer_model.get_latency(&btc), 60);
+        assert!(transfer_model.is_route_enabled(&e1, &e2, &btc));
+        assert!(!transfer_model.is_route_enabled(&e2, &e1, &btc));
+    }
+
+    #[test]
+    fn apply_profile() {
+        let text_input = "[trading]
+fee = 0.5
+
+[profiles.fast]
+backend = \"bellman-ford\"
+comparison_epsilon = 0.25
+
+[profiles.exact]
+path_semantics = \"min-cost\"
+discard_loops = false
+max_hops = 3
+"
+        .as_bytes();
+
+        let mut config = Config::<String, f32>::new();
+        assert_eq!(config.read_from(&mut BufReader::new(text_input)), Ok(()));
+        assert_eq!(config.get_profile_names(), vec!["fast", "exact"]);
+        assert!(config.select_profile("slow").is_err());
+
+        // Test that the selected profile is applied, on top of the rest of the config.
+        assert_eq!(config.select_profile("exact"), Ok(()));
+        let mut settings = Settings::new();
+        config.apply_to(&mut settings);
+        assert_eq!(settings.get_fee_model().get_fee(&"E1".to_string()), 0.5);
+        assert_eq!(settings.get_path_semantics(), PathSemantics::MinCost);
+        assert!(!settings.get_discard_loops());
+        assert_eq!(settings.get_max_hops(), Some(3));
+        assert_eq!(settings.get_backend(), Backend::FloydWarshall);
+    }
+
+    #[test]
+    fn read_from_errors() {
+        let read = |text_input: &str| {
+            let mut config = Config::<String, f32>::new();
+            config.read_from(&mut BufReader::new(text_input.as_bytes()))
+        };
+
+        // Test that unknown tables and keys, wrong values and incomplete routes are reported.
+        assert!(read("[trades]").is_err());
+        assert!(read("[trading]\nfees = 0.5").is_err());
+        assert!(read("[trading]\nfee = \"0.5\"").is_err());
+        assert!(read("[transfer]\ndisabled_currencies = \"USD\"").is_err());
+        assert!(read("[[transfer.disabled_routes]]\nsource = \"E1\"").is_err());
+        assert!(read("[profiles.fast]\nbackend = \"quick\"").is_err());
+        assert!(read("[profiles.fast]\ndiscard_loops = 1").is_err());
+        assert!(read("[[trading.min_trade_amounts]]\nexchange = \"E1\"\nsource = \"BTC\"\ndestination = \"USD\"").is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Minimal TOML parsing.
+//!
+//! Supports the subset of TOML configuration files need: `[table]` and `[[array]]` headers, bare
+//! and quoted keys, string, number and boolean values and single-line arrays of them.
+
+/// Value of a TOML key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    /// Number as written (without `_` separators), to be parsed into the required type.
+    Number(String),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+/// `key = value` entry of a TOML table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+/// TOML table, the root table has an empty path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub path: Vec<String>,
+    pub is_array_item: bool,
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+impl Table {
+    fn new(path: Vec<String>, is_array_item: bool, line: usize) -> Self {
+        Self {
+            path,
+            is_array_item,
+            line,
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// Parse the TOML text into its tables in the order of appearance, starting with the root one.
+pub fn parse(text: &str) -> Result<Vec<Table>, Vec<String>> {
+    let mut tables = vec![Table::new(Vec::new(), false, 0)];
+    let mut errors: Vec<String> = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let line = line.trim();
+        if is_end(line) {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix('[') {
+            let (is_array_item, rest) = match rest.strip_prefix('[') {
+                Some(rest) => (true, rest),
+                None => (false, rest),
+            };
+            let closing = if is_array_item { "]]" } else { "]" };
+
+            match parse_path(rest, closing) {
+                Some(path) => {
+                    let defined = tables
+                        .iter()
+                        .any(|table| table.path == path && !table.is_array_item);
+                    if defined && !is_array_item {
+                        errors.push(format!("The table of line {} is defined twice!", number));
+                    }
+
+                    tables.push(Table::new(path, is_array_item, number));
+                }
+                None => errors.push(format!("The table header of line {} is not valid!", number)),
+            }
+            continue;
+        }
+
+        match parse_entry(line) {
+            Some((key, value)) => {
+                // The root table is always present.
+                let table = tables.last_mut().unwrap();
+
+                if table.entries.iter().any(|entry| entry.key == key) {
+                    errors.push(format!("The key of line {} is defined twice!", number));
+                }
+
+                table.entries.push(Entry {
+                    key,
+                    value,
+                    line: number,
+                });
+            }
+            None => errors.push(format!(
+                "The line {} is not a valid key/value pair!",
+                number
+            )),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(tables)
+}
+
+/// Check whether nothing but an optional comment is left.
+fn is_end(s: &str) -> bool {
+    let s = s.trim_start();
+    s.is_empty() || s.starts_with('#')
+}
+
+/// Parse dot separated keys of a table header followed by the closing brackets.
+fn parse_path(s: &str, closing: &str) -> Option<Vec<String>> {
+    let mut path = Vec::new();
+    let mut rest = s;
+
+    loop {
+        let (key, after_key) = parse_key(rest)?;
+        path.push(key);
+
+        rest = after_key.trim_start();
+        match rest.strip_prefix('.') {
+            Some(after_dot) => rest = after_dot,
+            None => break,
+        }
+    }
+
+    let rest = rest.strip_prefix(closing)?;
+    if is_end(rest) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Parse `key = value` line.
+fn parse_entry(s: &str) -> Option<(String, Value)> {
+    let (key, rest) = parse_key(s)?;
+    let rest = rest.trim_start().strip_prefix('=')?;
+    let (value, rest) = parse_value(rest)?;
+
+    if is_end(rest) {
+        Some((key, value))
+    } else {
+        None
+    }
+}
+
+/// Parse bare or quoted key.
+fn parse_key(s: &str) -> Option<(String, &str)> {
+    let s = s.trim_start();
+    if s.starts_with('"') {
+        return parse_string(s);
+    }
+
+    let end = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(s.len());
+    if end == 0 {
+        return None;
+    }
+
+    Some((s[..end].to_string(), &s[end..]))
+}
+
+fn parse_value(s: &str) -> Option<(Value, &str)> {
+    let s = s.trim_start();
+
+    if s.starts_with('"') {
+        let (string, rest) = parse_string(s)?;
+        return Some((Value::String(string), rest));
+    }
+
+    if let Some(mut rest) = s.strip_prefix('[') {
+        let mut values = Vec::new();
+
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                return Some((Value::Array(values), after));
+            }
+
+            let (value, after_value) = parse_value(rest)?;
+            values.push(value);
+
+            rest = after_value.trim_start();
+            if let Some(after_comma) = rest.strip_prefix(',') {
+                rest = after_comma;
+            } else if !rest.starts_with(']') {
+                return None;
+            }
+        }
+    }
+
+    for (word, boolean) in [("true", true), ("false", false)] {
+        if let Some(rest) = s.strip_prefix(word) {
+            return Some((Value::Boolean(boolean), rest));
+        }
+    }
+
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || "+-._eE".contains(c)))
+        .unwrap_or(s.len());
+    if end == 0 {
+        return None;
+    }
+
+    Some((Value::Number(s[..end].replace('_', "")), &s[end..]))
+}
+
+/// Parse basic string starting with `"`, resolving escape sequences.
+fn parse_string(s: &str) -> Option<(String, &str)> {
+    let mut string = String::new();
+    let mut chars = s.char_indices().skip(1);
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((string, &s[index + 1..])),
+            '\\' => match chars.next()?.1 {
+                '"' => string.push('"'),
+                '\\' => string.push('\\'),
+                'n' => string.push('\n'),
+                'r' => string.push('\r'),
+                't' => string.push('\t'),
+                _ => return None,
+            },
+            c => string.push(c),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::toml::{parse, Value};
+
+    #[test]
+    fn parse_tables() {
+        let tables = parse(
+            "# Comment.
+title = \"Fees # not a comment\"
+
+[trading.exchanges.\"KRAKEN\"]
+fee = 0.002 # Comment.
+enabled = true
+
+[[routes]]
+currencies = [\"BTC\", 1_000.5]
+",
+        )
+        .unwrap();
+
+        assert_eq!(tables.len(), 3);
+        assert!(tables[0].path.is_empty());
+        assert_eq!(
+            tables[0].entries[0].value,
+            Value::String("Fees # not a comment".to_string())
+        );
+
+        assert_eq!(tables[1].path, vec!["trading", "exchanges", "KRAKEN"]);
+        assert_eq!(tables[1].line, 4);
+        assert_eq!(tables[1].entries[0].key, "fee");
+        assert_eq!(
+            tables[1].entries[0].value,
+            Value::Number("0.002".to_string())
+        );
+        assert_eq!(tables[1].entries[1].value, Value::Boolean(true));
+
+        assert!(tables[2].is_array_item);
+        assert_eq!(
+            tables[2].entries[0].value,
+            Value::Array(vec![
+                Value::String("BTC".to_string()),
+                Value::Number("1000.5".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("[table").is_err());
+        assert!(parse("key = ").is_err());
+        assert!(parse("key = 1 2").is_err());
+        assert!(parse("key = \"unterminated").is_err());
+        assert!(parse("key = 1\nkey = 2").is_err());
+        assert!(parse("[table]\n[table]").is_err());
+
+        // Test that array items may share the path.
+        assert!(parse("[[table]]\n[[table]]").is_ok());
+    }
+}
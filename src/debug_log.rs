@@ -0,0 +1,24 @@
+//! Debug log of the decisions taken silently otherwise.
+//!
+//! Records are structured lines, e.g. `PRICE_UPDATE_DROPPED <KRAKEN> <BTC> <USD> <reason>`, passed
+//! to the logger set by `set_debug_logger`. Nothing is logged nor formatted until it's set.
+
+use std::sync::OnceLock;
+
+/// Logger of the records, set once.
+static LOGGER: OnceLock<fn(&str)> = OnceLock::new();
+
+/// Set the logger the debug records are passed to, e.g. writing them to the standard error
+/// output.
+///
+/// Return `false` if a logger was set already, it's kept then.
+pub fn set_debug_logger(logger: fn(&str)) -> bool {
+    LOGGER.set(logger).is_ok()
+}
+
+/// Pass the record formed by the function to the logger, if it's set.
+pub(crate) fn debug(record: impl FnOnce() -> String) {
+    if let Some(logger) = LOGGER.get() {
+        logger(&record());
+    }
+}
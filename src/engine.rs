@@ -0,0 +1,684 @@
+//! Thread-safe engine answering concurrent queries.
+
+use crate::algorithm::settings::Settings;
+use crate::algorithm::{Algorithm, Solution};
+use crate::error::Error;
+use crate::identifier::{Currency, Exchange};
+use crate::request::exchange_rate_request::ExchangeRateRequest;
+use crate::request::price_update::PriceUpdate;
+use crate::request::{Line, Request};
+use crate::response::best_rate_path::BestRatePath;
+use crate::stream::metrics::Metrics;
+use crate::IndexMapTrait;
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use floyd_warshall_alg::FloydWarshallTrait;
+use num_traits::Zero;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Display};
+use std::mem;
+use std::ops::RangeBounds;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+
+pub mod background;
+pub mod exchange_rate_engine;
+
+/// Key of a cached best rate path: the source and destination exchanges and currencies.
+type PathKey<N> = (N, N, N, N);
+
+/// `PathCache` structure.
+///
+/// Best rate paths of the queries answered from the solution of the version, `None` for no path.
+struct PathCache<N, E> {
+    version: u64,
+    paths: HashMap<PathKey<N>, Option<BestRatePath<N, E>>>,
+}
+
+/// Best rates of a subscribed pair, each with the moment it was computed at.
+type RateSeries<E> = Vec<(DateTime<FixedOffset>, E)>;
+
+/// `QuoteWindow` structure.
+///
+/// Indexes of the known `PriceUpdate`s by their quote timestamps, expiring the ones older than
+/// the length of the window before the most recent quote.
+struct QuoteWindow<N> {
+    length: Duration,
+    latest: Option<DateTime<FixedOffset>>,
+    quotes: BTreeMap<DateTime<FixedOffset>, Vec<(N, N, N)>>,
+}
+
+impl<N> QuoteWindow<N>
+where
+    N: Clone + Display + FromStr + IndexMapTrait,
+    <N as FromStr>::Err: Debug,
+{
+    fn new(length: Duration) -> Self {
+        Self {
+            length,
+            latest: None,
+            quotes: BTreeMap::new(),
+        }
+    }
+
+    /// Add the quote of the accepted `PriceUpdate` and remove the `PriceUpdate`s of the quotes
+    /// left behind by the window, return their count.
+    fn slide<E>(
+        &mut self,
+        request: &mut Request<N, E>,
+        timestamp: DateTime<FixedOffset>,
+        index: (N, N, N),
+    ) -> usize
+    where
+        E: FromStr + Zero + PartialOrd,
+        <E as FromStr>::Err: Debug,
+    {
+        self.quotes.entry(timestamp).or_default().push(index);
+        let latest = match self.latest {
+            Some(latest) if latest >= timestamp => latest,
+            _ => timestamp,
+        };
+        self.latest = Some(latest);
+
+        let kept = self.quotes.split_off(&(latest - self.length));
+        let mut expired = 0;
+        for (timestamp, indexes) in mem::replace(&mut self.quotes, kept) {
+            for index in indexes {
+                // A more recent quote of the pair may have replaced it already.
+                let is_current = request
+                    .get_price_updates()
+                    .get(&index)
+                    .is_some_and(|price_update| *price_update.get_timestamp() == timestamp);
+                if is_current {
+                    request.remove_price_update(&index);
+                    expired += 1;
+                }
+            }
+        }
+
+        expired
+    }
+}
+
+/// `Engine` structure.
+///
+/// Shares the solved graph of the known `PriceUpdate`s between threads, so queries are
+/// answered concurrently by readers instead of one by one. A `PriceUpdate` outdates the
+/// solution, it's solved again by the first query after it, once for all the queries waiting.
+///
+/// With a window, see `with_window`, only recent quotes are known: a `PriceUpdate` whose quote
+/// falls out of the window is removed, and the solution is solved again without its edges.
+///
+/// Best rates of subscribed pairs, see `subscribe`, are recorded each time the solution is
+/// solved, so `history` tells how they evolved.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::{Engine, Settings};
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let engine = Arc::new(Engine::<String, f32>::new(Settings::new()));
+/// engine
+///     .process_line("2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001")
+///     .unwrap();
+///
+/// let query = {
+///     let engine = Arc::clone(&engine);
+///     thread::spawn(move || engine.process_line("EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD"))
+/// };
+/// assert!(query
+///     .join()
+///     .unwrap()
+///     .unwrap()
+///     .starts_with("BEST_RATES_BEGIN <KRAKEN> <BTC> <KRAKEN> <USD> <1000>"));
+/// ```
+///
+/// # `Engine<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct Engine<N, E> {
+    settings: Settings<N, E>,
+    request: Mutex<Request<N, E>>,
+    version: AtomicU64,
+    solution: RwLock<Option<Arc<Solution<N, E, u32>>>>,
+    cache: Mutex<PathCache<N, E>>,
+    metrics: Mutex<Metrics>,
+    window: Option<Mutex<QuoteWindow<N>>>,
+    series: Mutex<HashMap<PathKey<N>, RateSeries<E>>>,
+}
+
+impl<N, E> Engine<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of `Engine` structure.
+    pub fn new(settings: Settings<N, E>) -> Self {
+        Self {
+            settings,
+            request: Mutex::new(Request::new()),
+            version: AtomicU64::new(0),
+            solution: RwLock::new(None),
+            cache: Mutex::new(PathCache {
+                version: 0,
+                paths: HashMap::new(),
+            }),
+            metrics: Mutex::new(Metrics::new()),
+            window: None,
+            series: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keep only `PriceUpdate`s quoted within the length of the window before the most recent
+    /// quote, older ones are removed as more recent quotes arrive. The time is the one of the
+    /// quotes, so the window doesn't slide while no `PriceUpdate` arrives.
+    pub fn with_window(mut self, length: Duration) -> Self {
+        self.window = Some(Mutex::new(QuoteWindow::new(length)));
+        self
+    }
+
+    /// Process a single input line and return the printable output it caused.
+    pub fn process_line(&self, line: &str) -> Result<String, Error> {
+        let output = match Request::<N, E>::parse_line(line)? {
+            // An outdated `PriceUpdate` changes nothing.
+            Some(Line::PriceUpdate(price_update)) => {
+                self.add_price_update(price_update);
+                String::new()
+            }
+            Some(line) => {
+                let mut request = Request::new();
+                request.add_line(line);
+
+                self.get_solution().answer(&request).get_output()
+            }
+            None => String::new(),
+        };
+
+        Ok(output)
+    }
+
+    /// Add the `PriceUpdate` unless a more recent one of the same pair is already known, the
+    /// solution is outdated then. Return whether the `PriceUpdate` was accepted.
+    ///
+    /// With a window, the `PriceUpdate`s its quote leaves behind are removed, it's removed right
+    /// away itself if it's older than the window.
+    pub fn add_price_update(&self, price_update: PriceUpdate<N, E>) -> bool {
+        let mut request = self.request.lock().unwrap_or_else(PoisonError::into_inner);
+        let quote = self
+            .window
+            .as_ref()
+            .map(|_| (*price_update.get_timestamp(), price_update.get_index()));
+        let added = request.add_price_update(price_update);
+        self.lock_metrics().record_price_update(!added);
+
+        let expired = match (self.window.as_ref(), quote) {
+            (Some(window), Some((timestamp, index))) if added => window
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .slide(&mut request, timestamp, index),
+            _ => 0,
+        };
+        if expired > 0 {
+            self.lock_metrics().record_expired_price_updates(expired);
+        }
+
+        if added {
+            self.version.fetch_add(1, Ordering::SeqCst);
+            let solution = self
+                .solution
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            if let Some(solution) = solution {
+                solution.mark_stale();
+            }
+        }
+
+        added
+    }
+
+    /// Get a snapshot of the metrics of the price updates received and the recomputes so far.
+    pub fn get_metrics(&self) -> Metrics {
+        self.lock_metrics().clone()
+    }
+
+    /// Get the version of the known `PriceUpdate`s, increased by every accepted one. Responses
+    /// are stamped with the version they were answered from.
+    pub fn get_version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    /// Get the best rate path from the source exchange and currency to the destination ones,
+    /// `None` if there's none, or `Error::UnknownNode` if a node isn't known.
+    ///
+    /// The solution of the last query is reused, unless a `PriceUpdate` outdated it meanwhile,
+    /// so repeated queries don't solve the graph again. The best rate paths answered are cached
+    /// until then as well, so repeated identical queries don't extract the path again.
+    pub fn query(
+        &self,
+        source_exchange: &Exchange,
+        source_currency: &Currency,
+        destination_exchange: &Exchange,
+        destination_currency: &Currency,
+    ) -> Result<Option<BestRatePath<N, E>>, Error>
+    where
+        N: From<Exchange> + From<Currency>,
+    {
+        let key = (
+            N::from(source_exchange.clone()),
+            N::from(source_currency.clone()),
+            N::from(destination_exchange.clone()),
+            N::from(destination_currency.clone()),
+        );
+
+        let solution = self.get_solution();
+        {
+            let mut cache = self.lock_cache();
+            // Paths of an older solution are outdated.
+            if cache.version != solution.get_version() {
+                cache.version = solution.get_version();
+                cache.paths.clear();
+            }
+            if let Some(best_rate_path) = cache.paths.get(&key) {
+                let best_rate_path = best_rate_path.clone();
+                drop(cache);
+                self.lock_metrics().record_cache_hit();
+                return Ok(best_rate_path);
+            }
+        }
+
+        let rate_request =
+            ExchangeRateRequest::new(key.0.clone(), key.1.clone(), key.2.clone(), key.3.clone());
+        // Unknown nodes aren't cached, they're found out without extracting any path.
+        let best_rate_path = solution.best_rate(&rate_request)?;
+
+        let mut cache = self.lock_cache();
+        if cache.version == solution.get_version() {
+            cache.paths.insert(key, best_rate_path.clone());
+        }
+
+        Ok(best_rate_path)
+    }
+
+    /// Get the solution of the known `PriceUpdate`s, solving them if it's outdated. It may be held
+    /// on to and answer further requests, it's marked stale once a `PriceUpdate` outdates it.
+    pub fn get_solution(&self) -> Arc<Solution<N, E, u32>> {
+        let read_solution = || {
+            self.solution
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_ref()
+                .map(Arc::clone)
+        };
+        if let Some(solution) = read_solution() {
+            return solution;
+        }
+
+        // Price updates wait until it's solved, so the solution is of the latest ones.
+        let request = self.request.lock().unwrap_or_else(PoisonError::into_inner);
+        // Another query may have solved it in the meantime.
+        if let Some(solution) = read_solution() {
+            return solution;
+        }
+
+        let mut solution = Algorithm::solve(&request, &self.settings, true);
+        solution.set_version(self.get_version());
+        self.lock_metrics().record_recompute(
+            solution.get_node_count(),
+            solution.get_edge_count(),
+            solution.get_solve_time(),
+        );
+        self.record_series(&solution);
+        let solution = Arc::new(solution);
+        *self
+            .solution
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Arc::clone(&solution));
+
+        solution
+    }
+
+    /// Subscribe to the best rate of the pair of source exchange and currency and destination
+    /// exchange and currency, it's recorded each time the solution is solved from now on.
+    pub fn subscribe(&self, pair: (N, N, N, N)) {
+        self.lock_series().entry(pair).or_default();
+    }
+
+    /// Unsubscribe from the best rate of the pair, its recorded history is dropped.
+    pub fn unsubscribe(&self, pair: &(N, N, N, N)) {
+        self.lock_series().remove(pair);
+    }
+
+    /// Get the best rates of the subscribed pair computed within the range of moments, in the
+    /// order they were computed. Solutions without a path of the pair, e.g. before both nodes
+    /// were quoted, recorded no rate.
+    pub fn history(
+        &self,
+        pair: &(N, N, N, N),
+        range: impl RangeBounds<DateTime<FixedOffset>>,
+    ) -> Vec<(DateTime<FixedOffset>, E)> {
+        self.lock_series()
+            .get(pair)
+            .map(|series| {
+                series
+                    .iter()
+                    .filter(|(computed_at, _)| range.contains(computed_at))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Record the best rates of the subscribed pairs by the solution.
+    fn record_series(&self, solution: &Solution<N, E, u32>) {
+        let computed_at = Utc::now().fixed_offset();
+
+        for (pair, series) in self.lock_series().iter_mut() {
+            let rate_request = ExchangeRateRequest::new(
+                pair.0.clone(),
+                pair.1.clone(),
+                pair.2.clone(),
+                pair.3.clone(),
+            );
+            if let Ok(Some(best_rate_path)) = solution.best_rate(&rate_request) {
+                series.push((computed_at, *best_rate_path.get_rate()));
+            }
+        }
+    }
+
+    fn lock_series(&self) -> MutexGuard<'_, HashMap<PathKey<N>, RateSeries<E>>> {
+        self.series.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_cache(&self) -> MutexGuard<'_, PathCache<N, E>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_metrics(&self) -> MutexGuard<'_, Metrics> {
+        self.metrics.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::engine::Engine;
+    use crate::error::Error;
+    use crate::identifier::{Currency, Exchange};
+    use crate::request::Request;
+    use crate::response::unanswered_request::Reason;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn process_line() {
+        let engine = Engine::<String, f32>::new(Settings::new());
+        let request = "EXCHANGE_RATE_REQUEST E1 BTC E1 USD";
+
+        assert_eq!(
+            engine
+                .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001")
+                .unwrap(),
+            ""
+        );
+        assert!(engine
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+
+        // Test that a price update outdates the solution.
+        engine
+            .process_line("2019-01-20T09:42:24+00:00 E1 BTC USD 1001 0.001")
+            .unwrap();
+        assert!(engine
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1001>"));
+
+        assert!(engine.process_line("EXCHANGE_RATE_REQUEST E1").is_err());
+    }
+
+    #[test]
+    fn get_version() {
+        let engine = Engine::<String, f32>::new(Settings::new());
+        assert_eq!(engine.get_version(), 0);
+
+        engine
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001")
+            .unwrap();
+        // Test that an outdated price update doesn't increase the version.
+        engine
+            .process_line("2019-01-20T09:42:22+00:00 E1 BTC USD 999 0.001")
+            .unwrap();
+        engine
+            .process_line("2019-01-20T09:42:23+00:00 E1 ETH USD 100 0.01")
+            .unwrap();
+        assert_eq!(engine.get_version(), 2);
+
+        // Test that responses are stamped with the version they were answered from.
+        let request = Request::from_lines(["EXCHANGE_RATE_REQUEST E1 BTC E1 USD"]);
+        assert_eq!(engine.get_solution().answer(&request).get_version(), 2);
+    }
+
+    #[test]
+    fn get_metrics() {
+        let engine = Engine::<String, f32>::new(Settings::new());
+        for line in &[
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001",
+            "2019-01-20T09:42:22+00:00 E1 BTC USD 999 0.001",
+            "EXCHANGE_RATE_REQUEST E1 BTC E1 USD",
+            "EXCHANGE_RATE_REQUEST E1 USD E1 BTC",
+        ] {
+            engine.process_line(line).unwrap();
+        }
+
+        // Test that the solution reused by the second request is recomputed once.
+        let metrics = engine.get_metrics();
+        assert_eq!(metrics.get_received_price_updates(), 2);
+        assert_eq!(metrics.get_outdated_price_updates(), 1);
+        assert_eq!(metrics.get_recomputes(), 1);
+        assert_eq!((metrics.get_node_count(), metrics.get_edge_count()), (2, 2));
+    }
+
+    #[test]
+    fn query() {
+        let engine = Engine::<String, f32>::new(Settings::new());
+        let (e1, e2) = (Exchange::new("E1"), Exchange::new("E2"));
+        let (btc, usd) = (Currency::new("BTC"), Currency::new("USD"));
+        assert_eq!(
+            engine.query(&e1, &btc, &e1, &usd).err(),
+            Some(Error::UnknownNode(Reason::UnknownSourceExchange))
+        );
+
+        engine
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001")
+            .unwrap();
+        let best_rate_path = engine.query(&e1, &btc, &e1, &usd).unwrap().unwrap();
+        assert_eq!(*best_rate_path.get_rate(), 1000.0);
+        assert_eq!(
+            engine.query(&e1, &btc, &e2, &usd).err(),
+            Some(Error::UnknownNode(Reason::UnknownDestinationExchange))
+        );
+
+        // Test that the solution is reused until a price update outdates it.
+        let solution = engine.get_solution();
+        engine.query(&e1, &btc, &e1, &usd).unwrap();
+        assert!(Arc::ptr_eq(&solution, &engine.get_solution()));
+
+        assert!(!solution.is_stale());
+
+        engine
+            .process_line("2019-01-20T09:42:24+00:00 E1 BTC USD 1001 0.001")
+            .unwrap();
+        assert!(solution.is_stale());
+        assert_eq!(
+            *engine
+                .query(&e1, &btc, &e1, &usd)
+                .unwrap()
+                .unwrap()
+                .get_rate(),
+            1001.0
+        );
+        assert!(!Arc::ptr_eq(&solution, &engine.get_solution()));
+    }
+
+    #[test]
+    fn window() {
+        let engine =
+            Engine::<String, f32>::new(Settings::new()).with_window(chrono::Duration::seconds(60));
+        let answer = |line: &str| engine.process_line(line).unwrap();
+
+        answer("2019-01-20T09:42:00+00:00 KRAKEN BTC USD 1000 0.001");
+        answer("2019-01-20T09:42:30+00:00 KRAKEN BTC EUR 900 0.0011");
+        assert!(
+            answer("EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD").starts_with("BEST_RATES_BEGIN")
+        );
+
+        // Test that a quote more than the window after an older one removes it.
+        answer("2019-01-20T09:43:10+00:00 KRAKEN ETH EUR 100 0.0099");
+        assert!(
+            !answer("EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD").starts_with("BEST_RATES_BEGIN")
+        );
+        assert!(
+            answer("EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN EUR").starts_with("BEST_RATES_BEGIN")
+        );
+        assert_eq!(engine.get_metrics().get_expired_price_updates(), 1);
+
+        // Test that a replaced quote doesn't remove the more recent one of the pair.
+        answer("2019-01-20T09:43:20+00:00 KRAKEN BTC EUR 910 0.0011");
+        answer("2019-01-20T09:44:00+00:00 KRAKEN ETH EUR 101 0.0098");
+        assert!(answer("EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN EUR")
+            .starts_with("BEST_RATES_BEGIN <KRAKEN> <BTC> <KRAKEN> <EUR> <910>"));
+        assert_eq!(engine.get_metrics().get_expired_price_updates(), 1);
+
+        // Test that a quote already older than the window is removed right away.
+        answer("2019-01-20T09:40:00+00:00 KRAKEN BTC USD 1000 0.001");
+        assert!(
+            !answer("EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD").starts_with("BEST_RATES_BEGIN")
+        );
+        assert_eq!(engine.get_metrics().get_expired_price_updates(), 2);
+    }
+
+    #[test]
+    fn history() {
+        let engine = Engine::<String, f32>::new(Settings::new());
+        let pair = |destination: &str| {
+            (
+                String::from("KRAKEN"),
+                String::from("BTC"),
+                String::from("KRAKEN"),
+                String::from(destination),
+            )
+        };
+        engine.subscribe(pair("USD"));
+
+        let start = chrono::Utc::now().fixed_offset();
+        engine.get_solution();
+        for (line, destination) in &[
+            ("2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001", "USD"),
+            (
+                "2019-01-20T09:42:24+00:00 KRAKEN BTC USD 1100 0.0009",
+                "USD",
+            ),
+            ("2019-01-20T09:42:25+00:00 KRAKEN BTC EUR 900 0.0011", "EUR"),
+        ] {
+            engine.process_line(line).unwrap();
+            engine.get_solution();
+            engine.subscribe(pair(destination));
+        }
+
+        // Test that rates of each solution are recorded, once a path of the pair is known.
+        let rates = |pair| -> Vec<f32> {
+            engine
+                .history(&pair, start..)
+                .into_iter()
+                .map(|(_, rate)| rate)
+                .collect()
+        };
+        assert_eq!(rates(pair("USD")), vec![1000.0, 1100.0, 1100.0]);
+        assert!(rates(pair("EUR")).is_empty());
+        engine
+            .process_line("2019-01-20T09:42:26+00:00 KRAKEN BTC EUR 910 0.0011")
+            .unwrap();
+        engine.get_solution();
+        assert_eq!(rates(pair("EUR")), vec![910.0]);
+
+        // Test that rates are filtered by the range of moments.
+        let end = chrono::Utc::now().fixed_offset();
+        assert_eq!(engine.history(&pair("USD"), ..start).len(), 0);
+        assert_eq!(engine.history(&pair("USD"), start..=end).len(), 4);
+        assert!(engine.history(&pair("ETH"), ..).is_empty());
+
+        engine.unsubscribe(&pair("USD"));
+        assert!(engine.history(&pair("USD"), ..).is_empty());
+    }
+
+    #[test]
+    fn query_cache() {
+        let engine = Engine::<String, f32>::new(Settings::new());
+        let e1 = Exchange::new("E1");
+        let (btc, usd, eth) = (
+            Currency::new("BTC"),
+            Currency::new("USD"),
+            Currency::new("ETH"),
+        );
+        engine
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001")
+            .unwrap();
+        engine
+            .process_line("2019-01-20T09:42:23+00:00 E1 ETH BTC 0.1 10")
+            .unwrap();
+
+        // Test that a repeated identical query is answered from the cache.
+        let best_rate_path = engine.query(&e1, &eth, &e1, &usd).unwrap();
+        assert_eq!(engine.get_metrics().get_cache_hits(), 0);
+        assert_eq!(engine.query(&e1, &eth, &e1, &usd).unwrap(), best_rate_path);
+        engine.query(&e1, &btc, &e1, &usd).unwrap();
+        assert_eq!(engine.get_metrics().get_cache_hits(), 1);
+
+        // Test that a price update invalidates the cached paths.
+        engine
+            .process_line("2019-01-20T09:42:24+00:00 E1 BTC USD 1100 0.0009")
+            .unwrap();
+        let best_rate_path = engine.query(&e1, &eth, &e1, &usd).unwrap().unwrap();
+        assert_eq!(engine.get_metrics().get_cache_hits(), 1);
+        assert_eq!(*best_rate_path.get_rate(), 110.0);
+    }
+
+    #[test]
+    fn concurrent_queries() {
+        let engine = Arc::new(Engine::<String, f32>::new(Settings::new()));
+        engine
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001")
+            .unwrap();
+
+        // Test that queries are answered while price updates are applied.
+        let queries: Vec<_> = (0..4)
+            .map(|_| {
+                let engine = Arc::clone(&engine);
+                thread::spawn(move || {
+                    (0..50).all(|_| {
+                        engine
+                            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+                            .unwrap()
+                            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD>")
+                    })
+                })
+            })
+            .collect();
+        for second in 24..60 {
+            engine
+                .process_line(&format!(
+                    "2019-01-20T09:42:{}+00:00 E1 ETH USD 100 0.01",
+                    second
+                ))
+                .unwrap();
+        }
+
+        assert!(queries.into_iter().all(|query| query.join().unwrap()));
+    }
+}
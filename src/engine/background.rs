@@ -0,0 +1,299 @@
+//! Engine recomputing its solution by a background worker.
+
+use crate::algorithm::settings::Settings;
+use crate::algorithm::{Algorithm, Solution};
+use crate::error::Error;
+use crate::request::price_update::PriceUpdate;
+use crate::request::{Line, Request};
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+
+/// Immutable solution of the `PriceUpdate`s known at the time of its version.
+struct Snapshot<N, E> {
+    version: u64,
+    solution: Solution<N, E, u32>,
+}
+
+/// State shared between the `BackgroundEngine` and its worker.
+struct Shared<N, E> {
+    snapshot: RwLock<Arc<Snapshot<N, E>>>,
+    applied: Mutex<u64>,
+    published: Condvar,
+}
+
+/// `BackgroundEngine` structure.
+///
+/// Queues `PriceUpdate`s to a worker thread, which rebuilds the solution of all known ones and
+/// publishes it as a new immutable versioned snapshot. Queries are answered from the latest
+/// published snapshot, the lock is held only to clone its reference, so they never wait for a
+/// recomputation. Price updates queued during a recomputation are applied together by the next
+/// one, so answers may lag behind the most recent price updates.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::{BackgroundEngine, Settings};
+///
+/// let engine = BackgroundEngine::<String, f32>::new(Settings::new());
+/// engine
+///     .process_line("2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001")
+///     .unwrap();
+///
+/// // Wait for the snapshot of the price update queued above.
+/// assert_eq!(engine.sync(), 1);
+/// assert!(engine
+///     .process_line("EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD")
+///     .unwrap()
+///     .starts_with("BEST_RATES_BEGIN <KRAKEN> <BTC> <KRAKEN> <USD> <1000>"));
+/// ```
+///
+/// # `BackgroundEngine<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct BackgroundEngine<N, E> {
+    sender: Option<Sender<PriceUpdate<N, E>>>,
+    queued: AtomicU64,
+    shared: Arc<Shared<N, E>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl<N, E> BackgroundEngine<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + Sync + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + Sync + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of `BackgroundEngine` structure and start its worker.
+    pub fn new(settings: Settings<N, E>) -> Self {
+        let shared = Arc::new(Shared {
+            snapshot: RwLock::new(Arc::new(Snapshot {
+                version: 0,
+                solution: Algorithm::solve(&Request::new(), &settings, true),
+            })),
+            applied: Mutex::new(0),
+            published: Condvar::new(),
+        });
+        let (sender, receiver) = mpsc::channel();
+
+        let worker = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || Self::work(&shared, &receiver, &settings))
+        };
+
+        Self {
+            sender: Some(sender),
+            queued: AtomicU64::new(0),
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Process a single input line and return the printable output it caused.
+    pub fn process_line(&self, line: &str) -> Result<String, Error> {
+        let output = match Request::<N, E>::parse_line(line)? {
+            Some(Line::PriceUpdate(price_update)) => {
+                self.add_price_update(price_update);
+                String::new()
+            }
+            Some(line) => {
+                let mut request = Request::new();
+                request.add_line(line);
+
+                self.get_snapshot().solution.answer(&request).get_output()
+            }
+            None => String::new(),
+        };
+
+        Ok(output)
+    }
+
+    /// Queue the `PriceUpdate` for the worker, an outdated one changes nothing.
+    pub fn add_price_update(&self, price_update: PriceUpdate<N, E>) {
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        if let Some(sender) = &self.sender {
+            // The worker is gone only if it panicked, answers stay at its last snapshot then.
+            let _ = sender.send(price_update);
+        }
+    }
+
+    /// Get the version of the snapshot queries are answered from, it's increased by every
+    /// recomputation.
+    pub fn get_version(&self) -> u64 {
+        self.get_snapshot().version
+    }
+
+    /// Wait until all `PriceUpdate`s queued so far are applied and return the version of the
+    /// snapshot reflecting them.
+    pub fn sync(&self) -> u64 {
+        let queued = self.queued.load(Ordering::SeqCst);
+        let applied = self
+            .shared
+            .applied
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let _applied = self
+            .shared
+            .published
+            .wait_while(applied, |applied| *applied < queued)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        self.get_version()
+    }
+
+    /// Get the latest published snapshot.
+    fn get_snapshot(&self) -> Arc<Snapshot<N, E>> {
+        Arc::clone(
+            &self
+                .shared
+                .snapshot
+                .read()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    /// Apply the queued `PriceUpdate`s and publish snapshots of them until the engine is dropped.
+    fn work(
+        shared: &Shared<N, E>,
+        receiver: &Receiver<PriceUpdate<N, E>>,
+        settings: &Settings<N, E>,
+    ) {
+        let mut request = Request::new();
+        let mut version = 0;
+
+        while let Ok(price_update) = receiver.recv() {
+            let mut applied = 1;
+            let mut changed = request.add_price_update(price_update);
+            // Price updates queued meanwhile are applied by a single recomputation.
+            for price_update in receiver.try_iter() {
+                changed |= request.add_price_update(price_update);
+                applied += 1;
+            }
+
+            if changed {
+                version += 1;
+                let mut solution = Algorithm::solve(&request, settings, true);
+                solution.set_version(version);
+                let snapshot = Arc::new(Snapshot { version, solution });
+                *shared
+                    .snapshot
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner) = snapshot;
+            }
+
+            *shared
+                .applied
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) += applied;
+            shared.published.notify_all();
+        }
+    }
+}
+
+impl<N, E> Drop for BackgroundEngine<N, E> {
+    /// Stop the worker once it applied the queued `PriceUpdate`s.
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::engine::background::BackgroundEngine;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn process_line() {
+        let engine = BackgroundEngine::<String, f32>::new(Settings::new());
+        let request = "EXCHANGE_RATE_REQUEST E1 BTC E1 USD";
+
+        // Test that nothing is known before the first snapshot.
+        assert_eq!(engine.get_version(), 0);
+        assert!(engine
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_UNAVAILABLE"));
+
+        assert_eq!(
+            engine
+                .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001")
+                .unwrap(),
+            ""
+        );
+        assert_eq!(engine.sync(), 1);
+        assert!(engine
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+
+        // Test that an outdated price update doesn't publish a new snapshot.
+        engine
+            .process_line("2019-01-20T09:42:22+00:00 E1 BTC USD 999 0.001")
+            .unwrap();
+        assert_eq!(engine.sync(), 1);
+
+        engine
+            .process_line("2019-01-20T09:42:24+00:00 E1 BTC USD 1001 0.001")
+            .unwrap();
+        assert_eq!(engine.sync(), 2);
+        assert!(engine
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1001>"));
+
+        assert!(engine.process_line("EXCHANGE_RATE_REQUEST E1").is_err());
+    }
+
+    #[test]
+    fn concurrent_queries() {
+        let engine = Arc::new(BackgroundEngine::<String, f32>::new(Settings::new()));
+        engine
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001")
+            .unwrap();
+        engine.sync();
+
+        // Test that queries are answered from published snapshots while updates are applied.
+        let queries: Vec<_> = (0..4)
+            .map(|_| {
+                let engine = Arc::clone(&engine);
+                thread::spawn(move || {
+                    (0..50).all(|_| {
+                        engine
+                            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+                            .unwrap()
+                            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD>")
+                    })
+                })
+            })
+            .collect();
+        for second in 24..60 {
+            engine
+                .process_line(&format!(
+                    "2019-01-20T09:42:{}+00:00 E1 ETH USD 100 0.01",
+                    second
+                ))
+                .unwrap();
+        }
+
+        assert!(queries.into_iter().all(|query| query.join().unwrap()));
+
+        // Test that versions grow with the recomputations, coalesced ones publish fewer.
+        let version = engine.sync();
+        assert!((2..=37).contains(&version));
+        assert!(engine
+            .process_line("EXCHANGE_RATE_REQUEST E1 ETH E1 USD")
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <ETH> <E1> <USD> <100>"));
+    }
+}
@@ -0,0 +1,241 @@
+//! Engine answering best rates without the Request and Response plumbing.
+
+use crate::algorithm::settings::Settings;
+use crate::algorithm::{Algorithm, Solution};
+use crate::error::Error;
+use crate::identifier::{Currency, Exchange};
+use crate::request::exchange_rate_request::ExchangeRateRequest;
+use crate::request::price_update::PriceUpdate;
+use crate::request::Request;
+use crate::response::best_rate_path::BestRatePath;
+use crate::stream::metrics::Metrics;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// `ExchangeRateEngine` structure.
+///
+/// Keeps the known `PriceUpdate`s and answers the best rate between two `(exchange, currency)`
+/// nodes directly. Exchanges and currencies are given as `Exchange` and `Currency`, so they
+/// can't be swapped by mistake, and converted into the identifier data `N`. The graph is solved by `recompute`, or by the first query after the known
+/// `PriceUpdate`s changed, and the solution is reused by the queries until they change again.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::{Currency, Error, Exchange, ExchangeRateEngine, PriceUpdate, Settings};
+///
+/// let mut engine = ExchangeRateEngine::<String, f32>::new(Settings::new());
+/// engine.add_price_update(
+///     PriceUpdate::parse_line("2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001").unwrap(),
+/// );
+/// engine.recompute();
+///
+/// let kraken = Exchange::new("KRAKEN");
+/// let (btc, usd) = (Currency::new("BTC"), Currency::new("USD"));
+/// let best_rate_path = engine
+///     .best_rate((&kraken, &btc), (&kraken, &usd))
+///     .unwrap()
+///     .unwrap();
+/// assert_eq!(*best_rate_path.get_rate(), 1000.0);
+///
+/// // The nodes of a removed quote aren't known anymore.
+/// engine.remove_quote(&kraken, &btc, &usd);
+/// assert!(matches!(
+///     engine.best_rate((&kraken, &btc), (&kraken, &usd)),
+///     Err(Error::UnknownNode(_))
+/// ));
+/// ```
+///
+/// # `ExchangeRateEngine<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct ExchangeRateEngine<N, E> {
+    settings: Settings<N, E>,
+    request: Request<N, E>,
+    version: u64,
+    solution: Option<Arc<Solution<N, E, u32>>>,
+    metrics: Metrics,
+}
+
+impl<N, E> ExchangeRateEngine<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + From<Exchange> + From<Currency>,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of `ExchangeRateEngine` structure without any `PriceUpdate`.
+    pub fn new(settings: Settings<N, E>) -> Self {
+        Self {
+            settings,
+            request: Request::new(),
+            version: 0,
+            solution: None,
+            metrics: Metrics::new(),
+        }
+    }
+
+    /// Add the `PriceUpdate` unless a more recent one of the same pair is already known, the
+    /// solution is outdated then. Return whether the `PriceUpdate` was accepted.
+    pub fn add_price_update(&mut self, price_update: PriceUpdate<N, E>) -> bool {
+        let added = self.request.add_price_update(price_update);
+        self.metrics.record_price_update(!added);
+        if added {
+            self.outdate_solution();
+        }
+
+        added
+    }
+
+    /// Remove the known `PriceUpdate` of the pair at the exchange, the solution is outdated
+    /// then. Return whether it was known.
+    pub fn remove_quote(
+        &mut self,
+        exchange: &Exchange,
+        source_currency: &Currency,
+        destination_currency: &Currency,
+    ) -> bool {
+        let index = (
+            N::from(exchange.clone()),
+            N::from(source_currency.clone()),
+            N::from(destination_currency.clone()),
+        );
+        let removed = self.request.remove_price_update(&index).is_some();
+        if removed {
+            self.outdate_solution();
+        }
+
+        removed
+    }
+
+    /// Get the best rate path from the source to the destination `(exchange, currency)` node,
+    /// `None` if there's none, or `Error::UnknownNode` if a node isn't known. The graph is solved
+    /// first if the known `PriceUpdate`s changed.
+    pub fn best_rate(
+        &mut self,
+        source: (&Exchange, &Currency),
+        destination: (&Exchange, &Currency),
+    ) -> Result<Option<BestRatePath<N, E>>, Error> {
+        let rate_request = ExchangeRateRequest::new(
+            N::from(source.0.clone()),
+            N::from(source.1.clone()),
+            N::from(destination.0.clone()),
+            N::from(destination.1.clone()),
+        );
+
+        self.get_solution().best_rate(&rate_request)
+    }
+
+    /// Solve the graph of the known `PriceUpdate`s, so the following queries don't wait for it.
+    pub fn recompute(&mut self) {
+        let mut solution = Algorithm::solve(&self.request, &self.settings, false);
+        solution.set_version(self.version);
+        self.metrics.record_recompute(
+            solution.get_node_count(),
+            solution.get_edge_count(),
+            solution.get_solve_time(),
+        );
+        self.solution = Some(Arc::new(solution));
+    }
+
+    /// Get the solution of the known `PriceUpdate`s, solving them if it's outdated. It may be held
+    /// on to and answer further requests, it's marked stale once the known `PriceUpdate`s change.
+    pub fn get_solution(&mut self) -> Arc<Solution<N, E, u32>> {
+        if self.solution.is_none() {
+            self.recompute();
+        }
+
+        Arc::clone(
+            self.solution
+                .as_ref()
+                .expect("The solution is computed above!"),
+        )
+    }
+
+    /// Get metrics of the price updates received and the recomputes so far.
+    pub fn get_metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Get the version of the known `PriceUpdate`s, increased by every change of them. Responses
+    /// of the solution are stamped with the version it was solved of.
+    pub fn get_version(&self) -> u64 {
+        self.version
+    }
+
+    /// Increase the version, drop the solution and mark it stale for the ones holding on to it.
+    fn outdate_solution(&mut self) {
+        self.version += 1;
+        if let Some(solution) = self.solution.take() {
+            solution.mark_stale();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::engine::exchange_rate_engine::ExchangeRateEngine;
+    use crate::error::Error;
+    use crate::identifier::{Currency, Exchange};
+    use crate::request::price_update::PriceUpdate;
+    use crate::response::unanswered_request::Reason;
+
+    fn node(exchange: &str, currency: &str) -> (String, String) {
+        (exchange.to_string(), currency.to_string())
+    }
+
+    #[test]
+    fn best_rate() {
+        let mut engine = ExchangeRateEngine::<String, f32>::new(Settings::new());
+        let (e1, e2) = (Exchange::new("E1"), Exchange::new("E2"));
+        let (btc, usd) = (Currency::new("BTC"), Currency::new("USD"));
+        assert_eq!(
+            engine.best_rate((&e1, &btc), (&e1, &usd)).err(),
+            Some(Error::UnknownNode(Reason::UnknownSourceExchange))
+        );
+
+        for line in &[
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001",
+            "2019-01-20T09:42:23+00:00 E2 BTC USD 1100 0.0009",
+        ] {
+            assert!(engine.add_price_update(PriceUpdate::parse_line(line).unwrap()));
+        }
+        // Test that an outdated price update isn't accepted.
+        assert!(!engine.add_price_update(
+            PriceUpdate::parse_line("2019-01-20T09:42:22+00:00 E1 BTC USD 2000 0.0005").unwrap()
+        ));
+
+        let best_rate_path = engine.best_rate((&e1, &btc), (&e1, &usd)).unwrap().unwrap();
+        assert_eq!(*best_rate_path.get_rate(), 1100.0);
+        assert_eq!(best_rate_path.get_start_node(), Some(&node("E1", "BTC")));
+        assert_eq!(best_rate_path.get_end_node(), Some(&node("E1", "USD")));
+
+        // Test that a removed quote outdates the solution.
+        let solution = engine.get_solution();
+        assert!(!solution.is_stale());
+        assert_eq!(solution.get_version(), 2);
+        assert!(engine.remove_quote(&e2, &btc, &usd));
+        assert!(solution.is_stale());
+        assert_eq!(engine.get_version(), 3);
+        assert!(!engine.remove_quote(&e2, &btc, &usd));
+        engine.recompute();
+        assert_eq!(
+            *engine
+                .best_rate((&e1, &btc), (&e1, &usd))
+                .unwrap()
+                .unwrap()
+                .get_rate(),
+            1000.0
+        );
+
+        // Test that all solves are recorded, the last one of the graph without E2.
+        let metrics = engine.get_metrics();
+        assert_eq!(metrics.get_received_price_updates(), 3);
+        assert_eq!(metrics.get_recomputes(), 3);
+        assert_eq!((metrics.get_node_count(), metrics.get_edge_count()), (2, 2));
+    }
+}
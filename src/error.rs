@@ -0,0 +1,101 @@
+//! Errors of the public API.
+
+use crate::response::unanswered_request::Reason;
+use std::fmt;
+use std::io;
+
+/// `Error` of the public API, telling apart the categories of failures.
+///
+/// Parse and validation errors hold a message per problem found, so all problems of an input
+/// line or a file are reported at once.
+#[derive(Debug)]
+pub enum Error {
+    /// An input line or a value of it can not be parsed.
+    Parse(Vec<String>),
+    /// A parsed file, configuration or state is not valid.
+    Validation(Vec<String>),
+    /// The queried exchange or currency is not known, the reason tells which one.
+    UnknownNode(Reason),
+    /// Reading or writing failed.
+    Io(io::Error),
+}
+
+impl Error {
+    /// Get the messages describing the error.
+    pub fn get_messages(&self) -> Vec<String> {
+        match self {
+            Error::Parse(messages) | Error::Validation(messages) => messages.clone(),
+            Error::UnknownNode(reason) => vec![format!(
+                "The node of the query is not known, reason: <{}>!",
+                reason
+            )],
+            Error::Io(error) => vec![error.to_string()],
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_messages().join(" "))
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Error {
+    /// I/O errors are equal if their kinds are.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::Parse(a), Error::Parse(b)) | (Error::Validation(a), Error::Validation(b)) => {
+                a == b
+            }
+            (Error::UnknownNode(a), Error::UnknownNode(b)) => a == b,
+            (Error::Io(a), Error::Io(b)) => a.kind() == b.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+/// Internal functions collect messages of all problems found, whatever their category.
+impl From<Error> for Vec<String> {
+    fn from(error: Error) -> Self {
+        error.get_messages()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::response::unanswered_request::Reason;
+    use std::error::Error as _;
+    use std::io;
+
+    #[test]
+    fn get_messages() {
+        let error = Error::Parse(vec!["First!".to_string(), "Second!".to_string()]);
+        assert_eq!(error.to_string(), "First! Second!");
+        assert_eq!(Vec::<String>::from(error).len(), 2);
+
+        assert_eq!(
+            Error::UnknownNode(Reason::UnknownSourceExchange).to_string(),
+            "The node of the query is not known, reason: <unknown_source_exchange>!"
+        );
+
+        let error = Error::from(io::Error::new(io::ErrorKind::NotFound, "Missing!"));
+        assert_eq!(error.to_string(), "Missing!");
+        assert!(error.source().is_some());
+    }
+}
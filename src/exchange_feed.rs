@@ -0,0 +1,67 @@
+//! Live price feeds of exchanges.
+
+use crate::request::price_update::PriceUpdate;
+use std::io;
+use std::time::Duration;
+
+pub mod aggregator;
+pub mod binance;
+pub mod coinbase;
+pub mod ecb;
+mod http_client;
+pub mod kraken;
+pub mod queue;
+pub mod recording;
+mod websocket;
+
+pub use self::aggregator::{Aggregator, FeedEvent, FeedHealth, FeedState};
+pub use self::binance::BinanceConnector;
+pub use self::coinbase::CoinbaseConnector;
+pub use self::ecb::EcbConnector;
+pub use self::kraken::KrakenConnector;
+pub use self::queue::{EventReceiver, EventSender, OverflowPolicy, QueueMetrics};
+pub use self::recording::{Recorder, ReplayConnector};
+
+/// `Connector` trait.
+///
+/// Source of `PriceUpdate`s streamed live by an exchange, so the graph can be maintained
+/// directly from the exchange instead of an external feeder writing input lines. The stream is
+/// blocking, each connector is expected to run on a thread of its own.
+///
+/// # `Connector<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub trait Connector<N, E> {
+    /// Get name of the connector, used in messages about it.
+    fn get_name(&self) -> &str;
+
+    /// Set time without any message from the exchange, heartbeats included, after which the
+    /// connection is considered lost. It applies from the next `connect`.
+    fn set_heartbeat_timeout(&mut self, _timeout: Option<Duration>) {}
+
+    /// Connect to the exchange and subscribe to the prices, dropping any previous connection.
+    fn connect(&mut self) -> io::Result<()>;
+
+    /// Receive the next `PriceUpdate`s, blocking until any arrives.
+    ///
+    /// An error means the connection is lost, it has to be connected again.
+    fn receive(&mut self) -> io::Result<Vec<PriceUpdate<N, E>>>;
+
+    /// Get interval the `PriceUpdate`s are polled at, if they are, so the connector isn't
+    /// considered quiet between two polls.
+    fn get_update_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Check whether the connector has nothing more to receive, e.g. a replayed recording, so
+    /// it isn't connected again once `receive` fails.
+    fn is_finished(&self) -> bool {
+        false
+    }
+}
+
+/// Error of data received from an exchange that can't be used.
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,818 @@
+//! Aggregation of several exchange feeds.
+
+use crate::exchange_feed::queue::{self, EventReceiver, EventSender, OverflowPolicy, QueueMetrics};
+use crate::exchange_feed::{Connector, Recorder};
+use crate::request::price_update::PriceUpdate;
+use crate::IndexMapTrait;
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Aliases exchanges use instead of the common currency symbols.
+const SYMBOL_ALIASES: [(&str, &str); 2] = [("XBT", "BTC"), ("XDG", "DOGE")];
+
+/// Default time without any message after which a connection is considered lost.
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default delay before the first attempt to connect again, doubled by each failed attempt.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Default maximal delay between attempts to connect again.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Default time without any price update after which quotes of a connector are quarantined.
+const QUARANTINE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Default number of events waiting for the consumer of the stream.
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// State of a feed connector.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedState {
+    Connecting,
+    Connected,
+    /// The connection is lost, with the error.
+    Disconnected(String),
+    /// There is nothing more to receive, e.g. a recording is replayed.
+    Finished,
+}
+
+/// `FeedHealth` structure.
+///
+/// State of a feed connector and the price updates received by it so far.
+#[derive(Debug, Clone)]
+pub struct FeedHealth {
+    name: String,
+    state: FeedState,
+    price_updates: u64,
+    last_update: Option<Instant>,
+    reconnects: u64,
+    quarantined: bool,
+    update_interval: Option<Duration>,
+}
+
+impl FeedHealth {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_state(&self) -> &FeedState {
+        &self.state
+    }
+
+    /// Get count of the price updates received so far.
+    pub fn get_price_updates(&self) -> u64 {
+        self.price_updates
+    }
+
+    /// Get time the last price update was received, `None` if there is none yet.
+    pub fn get_last_update(&self) -> Option<Instant> {
+        self.last_update
+    }
+
+    /// Get count of attempts to connect again after the connection was lost.
+    pub fn get_reconnects(&self) -> u64 {
+        self.reconnects
+    }
+
+    /// Check whether quotes of the connector are quarantined, as it went quiet.
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantined
+    }
+}
+
+/// Event of the aggregated stream.
+pub enum FeedEvent<N, E> {
+    /// `PriceUpdate`s received by a connector.
+    PriceUpdates(Vec<PriceUpdate<N, E>>),
+    /// A connector lost its connection or went quiet, quotes of the `(exchange, source,
+    /// destination)` indexes it received are stale until it receives newer ones.
+    Gap(Vec<(N, N, N)>),
+}
+
+type BoxedConnector<N, E> = Box<dyn Connector<N, E> + Send>;
+
+/// `Aggregator` structure.
+///
+/// Runs each connector on a thread of its own and merges the `PriceUpdate`s they receive into
+/// a single stream. Currencies are normalized on the way, so e.g. `XBT` of Kraken and `BTC` of
+/// other exchanges are the same node of the graph.
+///
+/// A connector losing its connection, or silent for longer than the heartbeat timeout, is
+/// connected again after an exponential backoff. The gap is reported by the stream, as prices
+/// may have moved in the meantime.
+///
+/// A connector still connected but without any price update for longer than the quarantine
+/// timeout is quarantined, the gap is reported the same way, so answers never rely on quotes of
+/// a dead feed.
+///
+/// Events are passed to the consumer through a bounded lock-free queue, so connectors never
+/// wait for a slow consumer. A full queue drops events by its overflow policy, pairs of the
+/// dropped ones are reported as a gap as well.
+///
+/// # `Aggregator<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct Aggregator<N, E> {
+    /// Connectors not started yet, by their index in the health list.
+    connectors: Mutex<Vec<(usize, BoxedConnector<N, E>)>>,
+    symbols: Mutex<HashMap<N, N>>,
+    health: Mutex<Vec<FeedHealth>>,
+    /// Pairs received by each connector since its last gap, by its index in the health list.
+    received: Mutex<Vec<HashSet<(N, N, N)>>>,
+    supervision: Mutex<Supervision>,
+    recorder: Mutex<Option<Recorder>>,
+    queue: Mutex<(usize, OverflowPolicy)>,
+    queue_metrics: Arc<QueueMetrics>,
+}
+
+/// Heartbeat timeout, backoff of connecting again and quarantine timeout.
+#[derive(Clone, Copy)]
+struct Supervision {
+    heartbeat_timeout: Option<Duration>,
+    quarantine_timeout: Option<Duration>,
+    min_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl<N, E> Aggregator<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FromStr + Send + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of `Aggregator` structure, with aliases of common currencies.
+    pub fn new() -> Self {
+        let symbols = SYMBOL_ALIASES
+            .iter()
+            .filter_map(|(alias, symbol)| match (alias.parse(), symbol.parse()) {
+                (Ok(alias), Ok(symbol)) => Some((alias, symbol)),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            connectors: Mutex::new(Vec::new()),
+            symbols: Mutex::new(symbols),
+            health: Mutex::new(Vec::new()),
+            received: Mutex::new(Vec::new()),
+            supervision: Mutex::new(Supervision {
+                heartbeat_timeout: Some(HEARTBEAT_TIMEOUT),
+                quarantine_timeout: Some(QUARANTINE_TIMEOUT),
+                min_backoff: MIN_BACKOFF,
+                max_backoff: MAX_BACKOFF,
+            }),
+            recorder: Mutex::new(None),
+            queue: Mutex::new((QUEUE_CAPACITY, OverflowPolicy::default())),
+            queue_metrics: Arc::new(QueueMetrics::new()),
+        }
+    }
+
+    /// Set time without any message after which a connection is considered lost, `None` to
+    /// wait forever. It applies to connectors started by the next `start`.
+    pub fn set_heartbeat_timeout(&self, heartbeat_timeout: Option<Duration>) {
+        lock(&self.supervision).heartbeat_timeout = heartbeat_timeout;
+    }
+
+    /// Set delay before the first attempt to connect again, doubled by each failed attempt up
+    /// to the maximal delay.
+    pub fn set_backoff(&self, min_backoff: Duration, max_backoff: Duration) {
+        let mut supervision = lock(&self.supervision);
+        supervision.min_backoff = min_backoff;
+        supervision.max_backoff = max_backoff;
+    }
+
+    /// Set time without any price update after which quotes of a connected connector are
+    /// quarantined, `None` to rely on them forever. A connector polling at an interval is
+    /// quarantined after three missed polls at least. It applies from the next `start`.
+    pub fn set_quarantine_timeout(&self, quarantine_timeout: Option<Duration>) {
+        lock(&self.supervision).quarantine_timeout = quarantine_timeout;
+    }
+
+    /// Set recorder of the `PriceUpdate`s received by the connectors, as they are received
+    /// before any normalization, `None` for no recording.
+    pub fn set_recorder(&self, recorder: Option<Recorder>) {
+        *lock(&self.recorder) = recorder;
+    }
+
+    /// Set the number of events waiting for the consumer of the stream. It applies from the
+    /// next `start`.
+    pub fn set_queue_capacity(&self, capacity: usize) {
+        lock(&self.queue).0 = capacity;
+    }
+
+    /// Set what happens to a new event when the queue is full. It applies from the next
+    /// `start`.
+    pub fn set_overflow_policy(&self, overflow_policy: OverflowPolicy) {
+        lock(&self.queue).1 = overflow_policy;
+    }
+
+    /// Add the connector, it's started by the next `start`.
+    pub fn add_connector(&self, connector: BoxedConnector<N, E>) {
+        let mut health = lock(&self.health);
+        health.push(FeedHealth {
+            name: connector.get_name().to_string(),
+            state: FeedState::Connecting,
+            price_updates: 0,
+            last_update: None,
+            reconnects: 0,
+            quarantined: false,
+            update_interval: connector.get_update_interval(),
+        });
+        lock(&self.received).push(HashSet::new());
+
+        lock(&self.connectors).push((health.len() - 1, connector));
+    }
+
+    /// Add an alias of the currency symbol, the alias is replaced by the symbol.
+    pub fn add_symbol(&self, alias: N, symbol: N) {
+        lock(&self.symbols).insert(alias, symbol);
+    }
+
+    /// Get health of all the connectors, in the order they were added.
+    pub fn get_health(&self) -> Vec<FeedHealth> {
+        lock(&self.health).clone()
+    }
+
+    /// Get health of the connectors in the Prometheus text exposition format.
+    pub fn get_metrics(&self) -> String {
+        let health = self.get_health();
+        let mut output = String::new();
+
+        let _ = writeln!(
+            output,
+            "# HELP exchange_rate_feed_up Whether the feed connector is connected."
+        );
+        let _ = writeln!(output, "# TYPE exchange_rate_feed_up gauge");
+        for feed in health.iter() {
+            let up = u8::from(feed.state == FeedState::Connected);
+            let _ = writeln!(
+                output,
+                "exchange_rate_feed_up{{connector=\"{}\"}} {}",
+                feed.name, up
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP exchange_rate_feed_price_updates_total Price updates received by the feed connector."
+        );
+        let _ = writeln!(
+            output,
+            "# TYPE exchange_rate_feed_price_updates_total counter"
+        );
+        for feed in health.iter() {
+            let _ = writeln!(
+                output,
+                "exchange_rate_feed_price_updates_total{{connector=\"{}\"}} {}",
+                feed.name, feed.price_updates
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP exchange_rate_feed_reconnects_total Attempts of the feed connector to connect again."
+        );
+        let _ = writeln!(output, "# TYPE exchange_rate_feed_reconnects_total counter");
+        for feed in health.iter() {
+            let _ = writeln!(
+                output,
+                "exchange_rate_feed_reconnects_total{{connector=\"{}\"}} {}",
+                feed.name, feed.reconnects
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP exchange_rate_feed_quarantined Whether quotes of the feed connector are quarantined, as it went quiet."
+        );
+        let _ = writeln!(output, "# TYPE exchange_rate_feed_quarantined gauge");
+        for feed in health.iter() {
+            let _ = writeln!(
+                output,
+                "exchange_rate_feed_quarantined{{connector=\"{}\"}} {}",
+                feed.name,
+                u8::from(feed.quarantined)
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP exchange_rate_feed_last_update_age_seconds Time since the last price update of the feed connector."
+        );
+        let _ = writeln!(
+            output,
+            "# TYPE exchange_rate_feed_last_update_age_seconds gauge"
+        );
+        for feed in health.iter() {
+            if let Some(last_update) = feed.last_update {
+                let _ = writeln!(
+                    output,
+                    "exchange_rate_feed_last_update_age_seconds{{connector=\"{}\"}} {}",
+                    feed.name,
+                    last_update.elapsed().as_secs_f64()
+                );
+            }
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP exchange_rate_feed_queue_depth Events of the feeds waiting to be processed."
+        );
+        let _ = writeln!(output, "# TYPE exchange_rate_feed_queue_depth gauge");
+        let _ = writeln!(
+            output,
+            "exchange_rate_feed_queue_depth {}",
+            self.queue_metrics.get_depth()
+        );
+
+        let _ = writeln!(
+            output,
+            "# HELP exchange_rate_feed_queue_dropped_total Events of the feeds dropped as the queue was full."
+        );
+        let _ = writeln!(
+            output,
+            "# TYPE exchange_rate_feed_queue_dropped_total counter"
+        );
+        let _ = writeln!(
+            output,
+            "exchange_rate_feed_queue_dropped_total {}",
+            self.queue_metrics.get_dropped()
+        );
+
+        output
+    }
+
+    /// Start the connectors added so far, each on a thread of its own, and get the stream of
+    /// events they cause.
+    ///
+    /// Connectors keep connecting again until nobody listens to the stream anymore.
+    pub fn start(self: &Arc<Self>) -> EventReceiver<N, E> {
+        let (capacity, overflow_policy) = *lock(&self.queue);
+        let (sender, receiver) =
+            queue::bounded(capacity, overflow_policy, Arc::clone(&self.queue_metrics));
+
+        if let Some(quarantine_timeout) = lock(&self.supervision).quarantine_timeout {
+            let aggregator = Arc::clone(self);
+            let sender = sender.clone();
+            thread::spawn(move || aggregator.watch(quarantine_timeout, &sender));
+        }
+
+        for (index, mut connector) in lock(&self.connectors).drain(..) {
+            let aggregator = Arc::clone(self);
+            let sender = sender.clone();
+            thread::spawn(move || aggregator.run(index, connector.as_mut(), &sender));
+        }
+
+        receiver
+    }
+
+    fn run(
+        &self,
+        index: usize,
+        connector: &mut (dyn Connector<N, E> + Send),
+        sender: &EventSender<N, E>,
+    ) {
+        let supervision = *lock(&self.supervision);
+        connector.set_heartbeat_timeout(supervision.heartbeat_timeout);
+        let mut backoff = supervision.min_backoff;
+
+        loop {
+            let result = connector.connect().and_then(|()| {
+                self.update_health(index, |health| health.state = FeedState::Connected);
+
+                loop {
+                    let mut price_updates = connector.receive()?;
+                    self.record(&price_updates);
+                    self.normalize(&mut price_updates);
+
+                    // A connection delivering prices is a healthy one again.
+                    backoff = supervision.min_backoff;
+
+                    if !self.deliver(index, price_updates, sender) {
+                        return Ok(());
+                    }
+                }
+            });
+
+            let error = match result {
+                // Nobody listens anymore.
+                Ok(()) => return,
+                Err(error) => error,
+            };
+
+            if connector.is_finished() {
+                self.update_health(index, |health| health.state = FeedState::Finished);
+                return;
+            }
+
+            eprintln!(
+                "The connector <{}> lost the connection, error: {}, connecting again in {:?}!",
+                connector.get_name(),
+                error,
+                backoff
+            );
+            self.update_health(index, |health| {
+                health.state = FeedState::Disconnected(error.to_string())
+            });
+
+            if !self.report_gap(&mut lock(&self.received)[index], sender) {
+                return;
+            }
+
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(supervision.max_backoff);
+            self.update_health(index, |health| {
+                health.state = FeedState::Connecting;
+                health.reconnects += 1;
+            });
+        }
+    }
+
+    /// Send the `PriceUpdate`s received by the connector, return `false` if nobody listens
+    /// anymore.
+    fn deliver(
+        &self,
+        index: usize,
+        price_updates: Vec<PriceUpdate<N, E>>,
+        sender: &EventSender<N, E>,
+    ) -> bool {
+        // Held until they are sent, so a quarantine at the same time can't mark them stale.
+        let mut received = lock(&self.received);
+
+        self.update_health(index, |health| {
+            health.price_updates += price_updates.len() as u64;
+            health.last_update = Some(Instant::now());
+            health.quarantined = false;
+        });
+        received[index].extend(price_updates.iter().map(PriceUpdate::get_index));
+
+        sender.send(FeedEvent::PriceUpdates(price_updates))
+    }
+
+    /// Report the gap of the received pairs, return `false` if nobody listens anymore.
+    fn report_gap(&self, received: &mut HashSet<(N, N, N)>, sender: &EventSender<N, E>) -> bool {
+        if received.is_empty() {
+            return true;
+        }
+
+        sender.send(FeedEvent::Gap(received.drain().collect()))
+    }
+
+    /// Quarantine quotes of connected connectors quiet for longer than the timeout, until all
+    /// the connectors are finished or nobody listens anymore.
+    fn watch(&self, quarantine_timeout: Duration, sender: &EventSender<N, E>) {
+        loop {
+            thread::sleep((quarantine_timeout / 4).min(Duration::from_secs(1)));
+
+            let mut received = lock(&self.received);
+            let mut health = lock(&self.health);
+            if health.iter().all(|feed| feed.state == FeedState::Finished) {
+                return;
+            }
+
+            for (index, feed) in health.iter_mut().enumerate() {
+                // A polling connector is quiet once it misses three polls at least.
+                let timeout = feed.update_interval.map_or(quarantine_timeout, |interval| {
+                    quarantine_timeout.max(interval * 3)
+                });
+                let is_quiet = feed
+                    .last_update
+                    .is_some_and(|last_update| last_update.elapsed() > timeout);
+                if feed.state != FeedState::Connected || feed.quarantined || !is_quiet {
+                    continue;
+                }
+
+                eprintln!(
+                    "The connector <{}> is quiet for longer than {:?}, its quotes are quarantined!",
+                    feed.name, timeout
+                );
+                feed.quarantined = true;
+
+                if !self.report_gap(&mut received[index], sender) {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Record the `PriceUpdate`s, the recording stops if it fails.
+    fn record(&self, price_updates: &[PriceUpdate<N, E>]) {
+        let mut recorder = lock(&self.recorder);
+
+        if let Some(error) = recorder.as_mut().and_then(|recorder| {
+            recorder
+                .record(Utc::now().fixed_offset(), price_updates)
+                .err()
+        }) {
+            eprintln!("The recording failed and stops, error: {}!", error);
+            *recorder = None;
+        }
+    }
+
+    fn normalize(&self, price_updates: &mut [PriceUpdate<N, E>]) {
+        let symbols = lock(&self.symbols);
+
+        for price_update in price_updates.iter_mut() {
+            if let Some(symbol) = symbols.get(price_update.get_source_currency()) {
+                price_update.set_source_currency(symbol.clone());
+            }
+            if let Some(symbol) = symbols.get(price_update.get_destination_currency()) {
+                price_update.set_destination_currency(symbol.clone());
+            }
+        }
+    }
+
+    fn update_health<F: FnOnce(&mut FeedHealth)>(&self, index: usize, update: F) {
+        if let Some(health) = lock(&self.health).get_mut(index) {
+            update(health);
+        }
+    }
+}
+
+impl<N, E> Default for Aggregator<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FromStr + Send + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_feed::aggregator::{Aggregator, FeedEvent, FeedState};
+    use crate::exchange_feed::{Connector, Recorder, ReplayConnector};
+    use crate::request::price_update::PriceUpdate;
+    use std::fs;
+    use std::fs::File;
+    use std::io;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Connector receiving the lines of each connection one by one, then losing the connection.
+    /// It can't connect once all the connections are over.
+    struct LinesConnector {
+        name: &'static str,
+        connections: Vec<Vec<&'static str>>,
+        lines: Vec<&'static str>,
+        heartbeat_timeout: Option<Duration>,
+    }
+
+    impl LinesConnector {
+        fn new(name: &'static str, mut connections: Vec<Vec<&'static str>>) -> Self {
+            connections.reverse();
+            Self {
+                name,
+                connections,
+                lines: Vec::new(),
+                heartbeat_timeout: None,
+            }
+        }
+    }
+
+    impl Connector<String, f32> for LinesConnector {
+        fn get_name(&self) -> &str {
+            self.name
+        }
+
+        fn set_heartbeat_timeout(&mut self, timeout: Option<Duration>) {
+            self.heartbeat_timeout = timeout;
+        }
+
+        fn connect(&mut self) -> io::Result<()> {
+            assert_eq!(self.heartbeat_timeout, Some(Duration::from_secs(5)));
+
+            let mut lines = self
+                .connections
+                .pop()
+                .ok_or(io::ErrorKind::ConnectionRefused)?;
+            lines.reverse();
+            self.lines = lines;
+
+            Ok(())
+        }
+
+        fn receive(&mut self) -> io::Result<Vec<PriceUpdate<String, f32>>> {
+            match self.lines.pop() {
+                Some(line) => Ok(vec![PriceUpdate::parse_line(line).unwrap()]),
+                None => Err(io::ErrorKind::UnexpectedEof.into()),
+            }
+        }
+    }
+
+    /// Connector receiving the lines one by one, each after its delay.
+    struct DelayedLinesConnector {
+        lines: Vec<(Duration, &'static str)>,
+    }
+
+    impl Connector<String, f32> for DelayedLinesConnector {
+        fn get_name(&self) -> &str {
+            "DELAYED"
+        }
+
+        fn connect(&mut self) -> io::Result<()> {
+            self.lines.reverse();
+            Ok(())
+        }
+
+        fn receive(&mut self) -> io::Result<Vec<PriceUpdate<String, f32>>> {
+            // Quiet forever after the last line.
+            let (delay, line) = self.lines.pop().unwrap_or((Duration::from_secs(3600), ""));
+            thread::sleep(delay);
+
+            Ok(vec![PriceUpdate::parse_line(line).unwrap()])
+        }
+    }
+
+    fn get_lines(event: FeedEvent<String, f32>) -> Vec<String> {
+        match event {
+            FeedEvent::PriceUpdates(price_updates) => price_updates
+                .iter()
+                .map(|price_update| price_update.get_line())
+                .collect(),
+            FeedEvent::Gap(indexes) => indexes
+                .iter()
+                .map(|(exchange, source, destination)| {
+                    format!("GAP {} {} {}", exchange, source, destination)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn aggregate() {
+        let aggregator = Arc::new(Aggregator::<String, f32>::new());
+        aggregator.set_heartbeat_timeout(Some(Duration::from_secs(5)));
+        aggregator.add_symbol("USDT".to_string(), "USD".to_string());
+        aggregator.add_connector(Box::new(LinesConnector::new(
+            "E1",
+            vec![vec![
+                "2019-01-20T09:42:23+00:00 E1 XBT USD BID_ASK 999.0 1001.0",
+                "2019-01-20T09:42:24+00:00 E1 ETH XBT BID_ASK 0.1 0.2",
+            ]],
+        )));
+        aggregator.add_connector(Box::new(LinesConnector::new(
+            "E2",
+            vec![vec![
+                "2019-01-20T09:42:23+00:00 E2 BTC USDT BID_ASK 998.0 1002.0",
+            ]],
+        )));
+
+        let events = aggregator.start();
+        let mut lines: Vec<String> = (0..5)
+            .flat_map(|_| get_lines(events.recv().unwrap()))
+            .collect();
+        lines.sort();
+
+        // Test that updates of both connectors are merged and their currencies normalized, and
+        // that the pairs of each lost connection are reported as a gap.
+        assert_eq!(
+            lines,
+            vec![
+                "2019-01-20T09:42:23+00:00 E1 BTC USD BID_ASK 999 1001",
+                "2019-01-20T09:42:23+00:00 E2 BTC USD BID_ASK 998 1002",
+                "2019-01-20T09:42:24+00:00 E1 ETH BTC BID_ASK 0.1 0.2",
+                "GAP E1 BTC USD",
+                "GAP E1 ETH BTC",
+                "GAP E2 BTC USD",
+            ]
+        );
+
+        let health = aggregator.get_health();
+        assert_eq!(health[0].get_name(), "E1");
+        assert_eq!(health[0].get_price_updates(), 2);
+        assert_eq!(health[1].get_price_updates(), 1);
+        assert!(matches!(health[1].get_state(), FeedState::Disconnected(_)));
+
+        let metrics = aggregator.get_metrics();
+        assert!(metrics.contains("\nexchange_rate_feed_up{connector=\"E1\"} 0\n"));
+        assert!(metrics.contains("\nexchange_rate_feed_price_updates_total{connector=\"E1\"} 2\n"));
+        assert!(metrics.contains("\nexchange_rate_feed_last_update_age_seconds{connector=\"E2\"} "));
+        assert!(metrics.contains("\nexchange_rate_feed_queue_depth 0\n"));
+        assert!(metrics.contains("\nexchange_rate_feed_queue_dropped_total 0\n"));
+    }
+
+    #[test]
+    fn reconnect() {
+        let aggregator = Arc::new(Aggregator::<String, f32>::new());
+        aggregator.set_heartbeat_timeout(Some(Duration::from_secs(5)));
+        aggregator.set_backoff(Duration::from_millis(1), Duration::from_millis(10));
+        aggregator.add_connector(Box::new(LinesConnector::new(
+            "E1",
+            vec![
+                vec!["2019-01-20T09:42:23+00:00 E1 BTC USD BID_ASK 999.0 1001.0"],
+                vec![],
+                vec!["2019-01-20T09:42:25+00:00 E1 BTC USD BID_ASK 998.0 1002.0"],
+            ],
+        )));
+
+        let events = aggregator.start();
+        let lines: Vec<String> = (0..4)
+            .flat_map(|_| get_lines(events.recv().unwrap()))
+            .collect();
+
+        // Test that the gap is reported once, as the empty connection received no pairs.
+        assert_eq!(
+            lines,
+            vec![
+                "2019-01-20T09:42:23+00:00 E1 BTC USD BID_ASK 999 1001",
+                "GAP E1 BTC USD",
+                "2019-01-20T09:42:25+00:00 E1 BTC USD BID_ASK 998 1002",
+                "GAP E1 BTC USD",
+            ]
+        );
+        assert!(aggregator.get_health()[0].get_reconnects() >= 2);
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let path = |name| {
+            std::env::temp_dir().join(format!("exchange-rate-{}.{}", std::process::id(), name))
+        };
+        let (replay_path, record_path) = (path("aggregator-replay"), path("aggregator-record"));
+        let record =
+            "2019-01-20T09:42:23.5+00:00 2019-01-20T09:42:23+00:00 KRAKEN XBT USD BID_ASK 999.5 1000.5\n";
+        fs::write(&replay_path, record).unwrap();
+
+        let aggregator = Arc::new(Aggregator::<String, f32>::new());
+        aggregator.set_quarantine_timeout(None);
+        aggregator.set_recorder(Some(Recorder::new(Box::new(
+            File::create(&record_path).unwrap(),
+        ))));
+        let mut connector = ReplayConnector::new(replay_path.clone());
+        connector.set_speed(None);
+        aggregator.add_connector(Box::new(connector));
+
+        // Test that the stream ends with the replay, instead of connecting again.
+        let lines: Vec<String> = aggregator.start().iter().flat_map(get_lines).collect();
+        assert_eq!(
+            lines,
+            vec!["2019-01-20T09:42:23+00:00 KRAKEN BTC USD BID_ASK 999.5 1000.5"]
+        );
+        assert_eq!(aggregator.get_health()[0].get_state(), &FeedState::Finished);
+
+        // Test that the replayed update is recorded again as it was received, not normalized.
+        let recording = fs::read_to_string(&record_path).unwrap();
+        assert!(
+            recording.ends_with(" 2019-01-20T09:42:23+00:00 KRAKEN XBT USD BID_ASK 999.5 1000.5\n")
+        );
+
+        fs::remove_file(replay_path).unwrap();
+        fs::remove_file(record_path).unwrap();
+    }
+
+    #[test]
+    fn quarantine() {
+        let aggregator = Arc::new(Aggregator::<String, f32>::new());
+        aggregator.set_quarantine_timeout(Some(Duration::from_millis(40)));
+        aggregator.add_connector(Box::new(DelayedLinesConnector {
+            lines: vec![
+                (
+                    Duration::ZERO,
+                    "2019-01-20T09:42:23+00:00 E1 BTC USD BID_ASK 999.0 1001.0",
+                ),
+                (
+                    Duration::from_millis(300),
+                    "2019-01-20T09:42:24+00:00 E1 BTC USD BID_ASK 998.0 1002.0",
+                ),
+            ],
+        }));
+
+        let events = aggregator.start();
+        let next_lines = || get_lines(events.recv().unwrap());
+        assert_eq!(
+            next_lines(),
+            vec!["2019-01-20T09:42:23+00:00 E1 BTC USD BID_ASK 999 1001"]
+        );
+
+        // Test that quotes of the quiet connector are reported as a gap, while it's connected.
+        assert_eq!(next_lines(), vec!["GAP E1 BTC USD"]);
+        let health = aggregator.get_health();
+        assert!(health[0].is_quarantined());
+        assert_eq!(health[0].get_state(), &FeedState::Connected);
+        assert!(aggregator
+            .get_metrics()
+            .contains("\nexchange_rate_feed_quarantined{connector=\"DELAYED\"} 1\n"));
+
+        assert_eq!(
+            next_lines(),
+            vec!["2019-01-20T09:42:24+00:00 E1 BTC USD BID_ASK 998 1002"]
+        );
+    }
+}
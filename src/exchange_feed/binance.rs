@@ -0,0 +1,224 @@
+//! Binance REST polling connector.
+
+use crate::exchange_feed::http_client;
+use crate::exchange_feed::{invalid_data, Connector};
+use crate::json::value::{parse, Value};
+use crate::request::price_update::{PriceUpdate, Quote};
+use chrono::{DateTime, FixedOffset, Utc};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Host of the Binance REST API.
+const HOST: &str = "api.binance.com";
+
+/// Exchange identifier of the `PriceUpdate`s.
+const EXCHANGE: &str = "BINANCE";
+
+/// `BinanceConnector` structure.
+///
+/// Polls the book ticker of the pairs at the interval and turns each ticker into a bid and ask
+/// `PriceUpdate` of the pair, timestamped by arrival of the response.
+pub struct BinanceConnector {
+    address: String,
+    /// Pairs by their Binance symbol, e.g. `BTCUSDT` of `(BTC, USDT)`.
+    pairs: HashMap<String, (String, String)>,
+    interval: Duration,
+    next_poll: Instant,
+    heartbeat_timeout: Option<Duration>,
+}
+
+impl BinanceConnector {
+    /// Create a new instance of `BinanceConnector` structure, polling every second.
+    ///
+    /// The address is a TLS terminating proxy of `api.binance.com:443`, the pairs are
+    /// `(<base>, <quote>)` currencies, e.g. `(BTC, USDT)`.
+    pub fn new(address: String, pairs: Vec<(String, String)>) -> Self {
+        Self {
+            address,
+            pairs: pairs
+                .into_iter()
+                .map(|(base, quote)| (format!("{}{}", base, quote), (base, quote)))
+                .collect(),
+            interval: Duration::from_secs(1),
+            next_poll: Instant::now(),
+            heartbeat_timeout: None,
+        }
+    }
+
+    /// Set interval between starts of two polls.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Get request target of the book ticker of all the pairs.
+    fn get_target(&self) -> String {
+        let mut symbols: Vec<String> = self
+            .pairs
+            .keys()
+            .map(|symbol| format!("%22{}%22", symbol))
+            .collect();
+        symbols.sort();
+
+        format!(
+            "/api/v3/ticker/bookTicker?symbols=%5B{}%5D",
+            symbols.join(",")
+        )
+    }
+}
+
+impl<N, E> Connector<N, E> for BinanceConnector
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    fn get_name(&self) -> &str {
+        EXCHANGE
+    }
+
+    /// Responses have to arrive within the timeout.
+    fn set_heartbeat_timeout(&mut self, timeout: Option<Duration>) {
+        self.heartbeat_timeout = timeout;
+    }
+
+    fn get_update_interval(&self) -> Option<Duration> {
+        Some(self.interval)
+    }
+
+    /// There is no connection to keep, the next poll is just due right away.
+    fn connect(&mut self) -> io::Result<()> {
+        self.next_poll = Instant::now();
+
+        Ok(())
+    }
+
+    fn receive(&mut self) -> io::Result<Vec<PriceUpdate<N, E>>> {
+        thread::sleep(self.next_poll.saturating_duration_since(Instant::now()));
+        self.next_poll = Instant::now() + self.interval;
+
+        let target = self.get_target();
+        let body = http_client::get(&self.address, HOST, &target, self.heartbeat_timeout)?;
+        let timestamp = Utc::now().fixed_offset();
+
+        parse_tickers(&body, &self.pairs, timestamp).map_err(invalid_data)
+    }
+}
+
+/// Parse the response body into `PriceUpdate`s of the pairs.
+///
+/// # `body` format
+///
+/// `[{"symbol": "<symbol>", "bidPrice": "<bid>", "askPrice": "<ask>", ...}, ...]`
+fn parse_tickers<N, E>(
+    body: &str,
+    pairs: &HashMap<String, (String, String)>,
+    timestamp: DateTime<FixedOffset>,
+) -> Result<Vec<PriceUpdate<N, E>>, String>
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    let tickers = match parse(body)? {
+        Value::Array(tickers) => tickers,
+        _ => return Err("The book tickers are not an array!".to_string()),
+    };
+
+    let mut price_updates = Vec::new();
+    for ticker in tickers.iter() {
+        let symbol = ticker
+            .get("symbol")
+            .and_then(Value::as_text)
+            .unwrap_or_default();
+        let price = |field: &str| {
+            ticker
+                .get(field)
+                .and_then(Value::as_text)
+                .and_then(|price| price.parse::<E>().ok())
+        };
+
+        // An empty book has zero prices, no rate is known then.
+        let bid_price = ticker.get("bidPrice").and_then(Value::as_text);
+        if bid_price.and_then(|price| price.parse::<f64>().ok()) == Some(0.0) {
+            continue;
+        }
+
+        match (pairs.get(symbol), price("bidPrice"), price("askPrice")) {
+            (Some((base, quote)), Some(bid), Some(ask)) => {
+                match (EXCHANGE.parse(), base.parse(), quote.parse()) {
+                    (Ok(exchange), Ok(base), Ok(quote)) => price_updates.push(PriceUpdate::new(
+                        timestamp,
+                        exchange,
+                        base,
+                        quote,
+                        Quote::BidAsk { bid, ask },
+                    )),
+                    _ => return Err(format!("The ticker pair <{}> can not be parsed!", symbol)),
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "The book ticker of <{}> can not be parsed (wrong format)!",
+                    symbol
+                ))
+            }
+        }
+    }
+
+    Ok(price_updates)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_feed::binance::{parse_tickers, BinanceConnector};
+    use chrono::DateTime;
+
+    #[test]
+    fn get_target() {
+        let connector = BinanceConnector::new(
+            String::new(),
+            vec![
+                ("ETH".to_string(), "BTC".to_string()),
+                ("BTC".to_string(), "USDT".to_string()),
+            ],
+        );
+
+        assert_eq!(
+            connector.get_target(),
+            "/api/v3/ticker/bookTicker?symbols=%5B%22BTCUSDT%22,%22ETHBTC%22%5D"
+        );
+    }
+
+    #[test]
+    fn parse_book_tickers() {
+        let timestamp = DateTime::parse_from_rfc3339("2019-01-20T09:42:23+00:00").unwrap();
+        let connector =
+            BinanceConnector::new(String::new(), vec![("BTC".to_string(), "USDT".to_string())]);
+        let parse = |body| parse_tickers::<String, f32>(body, &connector.pairs, timestamp);
+
+        let body = "[{\"symbol\":\"BTCUSDT\",\"bidPrice\":\"999.50000000\",\"bidQty\":\"1.0\",\"askPrice\":\"1000.50000000\",\"askQty\":\"2.0\"}]";
+        let lines: Vec<String> = parse(body)
+            .unwrap()
+            .iter()
+            .map(|price_update| price_update.get_line())
+            .collect();
+        assert_eq!(
+            lines,
+            vec!["2019-01-20T09:42:23+00:00 BINANCE BTC USDT BID_ASK 999.5 1000.5"]
+        );
+
+        // Test that an empty book is skipped.
+        let body =
+            "[{\"symbol\":\"BTCUSDT\",\"bidPrice\":\"0.00000000\",\"askPrice\":\"0.00000000\"}]";
+        assert!(parse(body).unwrap().is_empty());
+
+        assert!(parse("[{\"symbol\":\"ETHBTC\",\"bidPrice\":\"1\",\"askPrice\":\"1\"}]").is_err());
+        assert!(parse("{\"code\":-1121,\"msg\":\"Invalid symbol.\"}").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Coinbase Exchange WebSocket ticker connector.
+
+use crate::exchange_feed::websocket;
+use crate::exchange_feed::websocket::WebSocket;
+use crate::exchange_feed::{invalid_data, Connector};
+use crate::json;
+use crate::json::value::{parse, Value};
+use crate::request::price_update::{PriceUpdate, Quote};
+use chrono::{DateTime, FixedOffset, Utc};
+use std::fmt::Debug;
+use std::io;
+use std::io::BufReader;
+use std::net::TcpStream;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Host of the Coinbase Exchange WebSocket feed.
+const HOST: &str = "ws-feed.exchange.coinbase.com";
+
+/// Exchange identifier of the `PriceUpdate`s.
+const EXCHANGE: &str = "COINBASE";
+
+/// `CoinbaseConnector` structure.
+///
+/// Subscribes to the ticker channel of the products and turns each ticker message into a bid and
+/// ask `PriceUpdate` of the product, timestamped by the time of the ticker. The heartbeat channel
+/// keeps quiet products from looking like a lost connection.
+pub struct CoinbaseConnector {
+    address: String,
+    pairs: Vec<(String, String)>,
+    heartbeat_timeout: Option<Duration>,
+    socket: Option<WebSocket<BufReader<TcpStream>, TcpStream>>,
+}
+
+impl CoinbaseConnector {
+    /// Create a new instance of `CoinbaseConnector` structure.
+    ///
+    /// The address is a TLS terminating proxy of `ws-feed.exchange.coinbase.com:443`, the pairs
+    /// are `(<base>, <quote>)` currencies of the `<base>-<quote>` products, e.g. `(BTC, USD)`.
+    pub fn new(address: String, pairs: Vec<(String, String)>) -> Self {
+        Self {
+            address,
+            pairs,
+            heartbeat_timeout: None,
+            socket: None,
+        }
+    }
+}
+
+impl<N, E> Connector<N, E> for CoinbaseConnector
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    fn get_name(&self) -> &str {
+        EXCHANGE
+    }
+
+    fn set_heartbeat_timeout(&mut self, timeout: Option<Duration>) {
+        self.heartbeat_timeout = timeout;
+    }
+
+    fn connect(&mut self) -> io::Result<()> {
+        self.socket = None;
+
+        let mut socket = websocket::connect(&self.address, HOST, "/", self.heartbeat_timeout)?;
+        socket.write_text(&get_subscribe_message(&self.pairs))?;
+        self.socket = Some(socket);
+
+        Ok(())
+    }
+
+    fn receive(&mut self) -> io::Result<Vec<PriceUpdate<N, E>>> {
+        let socket = self
+            .socket
+            .as_mut()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+
+        loop {
+            let message = socket.read_text()?;
+
+            match parse_message(&message, Utc::now().fixed_offset()) {
+                Ok(Some(price_update)) => return Ok(vec![price_update]),
+                // Subscriptions, heartbeats and other messages.
+                Ok(None) => {}
+                Err(error) => return Err(invalid_data(error)),
+            }
+        }
+    }
+}
+
+/// Get ID of the product of the pair, e.g. `BTC-USD`.
+fn get_product_id(base: &str, quote: &str) -> String {
+    format!("{}-{}", base, quote)
+}
+
+fn get_subscribe_message(pairs: &[(String, String)]) -> String {
+    let product_ids: Vec<String> = pairs
+        .iter()
+        .map(|(base, quote)| json::quote(&get_product_id(base, quote)))
+        .collect();
+
+    format!(
+        "{{\"type\":\"subscribe\",\"product_ids\":[{}],\"channels\":[\"ticker\",\"heartbeat\"]}}",
+        product_ids.join(",")
+    )
+}
+
+/// Parse the message into a `PriceUpdate` if it's a ticker one.
+///
+/// The arrival time is used if the ticker has no time of its own.
+///
+/// # `message` format
+///
+/// `{"type": "ticker", "product_id": "<base>-<quote>", "best_bid": "<bid>", "best_ask": "<ask>", "time": "<timestamp>", ...}`
+fn parse_message<N, E>(
+    message: &str,
+    arrival: DateTime<FixedOffset>,
+) -> Result<Option<PriceUpdate<N, E>>, String>
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    let message = parse(message)?;
+
+    match message.get("type").and_then(Value::as_text) {
+        Some("ticker") => {}
+        // A rejected subscription would leave the feed silent forever.
+        Some("error") => {
+            let error = message
+                .get("reason")
+                .or_else(|| message.get("message"))
+                .and_then(Value::as_text)
+                .unwrap_or_default();
+            return Err(format!("The subscription is rejected <{}>!", error));
+        }
+        _ => return Ok(None),
+    }
+
+    let product_id = message
+        .get("product_id")
+        .and_then(Value::as_text)
+        .unwrap_or_default();
+    let price = |field: &str| {
+        message
+            .get(field)
+            .and_then(Value::as_text)
+            .and_then(|price| price.parse::<E>().ok())
+    };
+    let timestamp = match message.get("time").and_then(Value::as_text) {
+        Some(time) => DateTime::parse_from_rfc3339(time)
+            .map_err(|_| format!("The ticker time <{}> can not be parsed!", time))?,
+        None => arrival,
+    };
+
+    match (
+        product_id.split_once('-'),
+        price("best_bid"),
+        price("best_ask"),
+    ) {
+        (Some((base, quote)), Some(bid), Some(ask)) => {
+            match (EXCHANGE.parse(), base.parse(), quote.parse()) {
+                (Ok(exchange), Ok(base), Ok(quote)) => Ok(Some(PriceUpdate::new(
+                    timestamp,
+                    exchange,
+                    base,
+                    quote,
+                    Quote::BidAsk { bid, ask },
+                ))),
+                _ => Err(format!(
+                    "The ticker product <{}> can not be parsed!",
+                    product_id
+                )),
+            }
+        }
+        _ => Err(format!(
+            "The ticker of <{}> can not be parsed (wrong format)!",
+            product_id
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_feed::coinbase::{get_subscribe_message, parse_message};
+    use chrono::DateTime;
+
+    #[test]
+    fn subscribe_message() {
+        assert_eq!(
+            get_subscribe_message(&[
+                ("BTC".to_string(), "USD".to_string()),
+                ("ETH".to_string(), "EUR".to_string())
+            ]),
+            "{\"type\":\"subscribe\",\"product_ids\":[\"BTC-USD\",\"ETH-EUR\"],\"channels\":[\"ticker\",\"heartbeat\"]}"
+        );
+    }
+
+    #[test]
+    fn parse_ticker_message() {
+        let arrival = DateTime::parse_from_rfc3339("2019-01-20T09:42:23+00:00").unwrap();
+        let parse = |message| parse_message::<String, f32>(message, arrival);
+        let get_line = |message| parse(message).unwrap().map(|update| update.get_line());
+
+        let message = "{\"type\":\"ticker\",\"sequence\":1,\"product_id\":\"BTC-USD\",\"price\":\"1000.0\",\"best_bid\":\"999.5\",\"best_ask\":\"1000.5\",\"time\":\"2019-01-20T09:42:24.5Z\"}";
+        assert_eq!(
+            get_line(message),
+            Some("2019-01-20T09:42:24.500+00:00 COINBASE BTC USD BID_ASK 999.5 1000.5".to_string())
+        );
+
+        // Test that the arrival time is used without the ticker time.
+        let message = "{\"type\":\"ticker\",\"product_id\":\"BTC-USD\",\"best_bid\":\"999.5\",\"best_ask\":\"1000.5\"}";
+        assert_eq!(
+            get_line(message),
+            Some("2019-01-20T09:42:23+00:00 COINBASE BTC USD BID_ASK 999.5 1000.5".to_string())
+        );
+
+        // Test that other messages are skipped, but a rejected subscription is an error.
+        assert!(parse("{\"type\":\"subscriptions\",\"channels\":[]}")
+            .unwrap()
+            .is_none());
+        assert!(parse("{\"type\":\"error\",\"message\":\"Failed to subscribe\",\"reason\":\"BTC-XYZ is not a valid product\"}").is_err());
+
+        let message =
+            "{\"type\":\"ticker\",\"product_id\":\"BTCUSD\",\"best_bid\":\"1\",\"best_ask\":\"1\"}";
+        assert!(parse(message).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! ECB euro foreign exchange reference rates connector.
+
+use crate::exchange_feed::http_client;
+use crate::exchange_feed::{invalid_data, Connector};
+use crate::request::price_update::{PriceUpdate, Quote};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use std::fmt::Debug;
+use std::io;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Host of the ECB website.
+const HOST: &str = "www.ecb.europa.eu";
+
+/// Target of the daily reference rates.
+const TARGET: &str = "/stats/eurofxref/eurofxref-daily.xml";
+
+/// Exchange identifier of the `PriceUpdate`s, a synthetic one of the reference rates.
+const EXCHANGE: &str = "ECB";
+
+/// Base currency of the reference rates.
+const BASE: &str = "EUR";
+
+/// `EcbConnector` structure.
+///
+/// Polls the daily euro reference rates published by the European Central Bank at the interval
+/// and turns each rate into a `PriceUpdate` of `EUR` to the currency, so fiat currencies bridge
+/// the crypto exchanges. The reference rate is a mid rate, so its bid and ask are the same, and
+/// it's timestamped by the start of its day in UTC.
+pub struct EcbConnector {
+    address: String,
+    currencies: Vec<String>,
+    interval: Duration,
+    next_poll: Instant,
+    heartbeat_timeout: Option<Duration>,
+}
+
+impl EcbConnector {
+    /// Create a new instance of `EcbConnector` structure, polling every hour.
+    ///
+    /// The address is a TLS terminating proxy of `www.ecb.europa.eu:443`, the currencies are
+    /// the ones quoted against `EUR`, e.g. `USD`, all of them if empty.
+    pub fn new(address: String, currencies: Vec<String>) -> Self {
+        Self {
+            address,
+            currencies,
+            interval: Duration::from_secs(60 * 60),
+            next_poll: Instant::now(),
+            heartbeat_timeout: None,
+        }
+    }
+
+    /// Set interval between starts of two polls, the rates change once a working day.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+}
+
+impl<N, E> Connector<N, E> for EcbConnector
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    fn get_name(&self) -> &str {
+        EXCHANGE
+    }
+
+    /// Responses have to arrive within the timeout.
+    fn set_heartbeat_timeout(&mut self, timeout: Option<Duration>) {
+        self.heartbeat_timeout = timeout;
+    }
+
+    fn get_update_interval(&self) -> Option<Duration> {
+        Some(self.interval)
+    }
+
+    /// There is no connection to keep, the next poll is just due right away.
+    fn connect(&mut self) -> io::Result<()> {
+        self.next_poll = Instant::now();
+
+        Ok(())
+    }
+
+    fn receive(&mut self) -> io::Result<Vec<PriceUpdate<N, E>>> {
+        thread::sleep(self.next_poll.saturating_duration_since(Instant::now()));
+        self.next_poll = Instant::now() + self.interval;
+
+        let body = http_client::get(&self.address, HOST, TARGET, self.heartbeat_timeout)?;
+
+        parse_rates(&body, &self.currencies).map_err(invalid_data)
+    }
+}
+
+/// Parse the reference rates document into `PriceUpdate`s of the currencies, of all of them if
+/// empty.
+///
+/// # `body` format
+///
+/// `<Cube><Cube time="<date>"><Cube currency="<currency>" rate="<rate>"/>...</Cube></Cube>`
+/// within the envelope, single quotes are used as well.
+fn parse_rates<N, E>(body: &str, currencies: &[String]) -> Result<Vec<PriceUpdate<N, E>>, String>
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    let mut timestamp: Option<DateTime<FixedOffset>> = None;
+    let mut price_updates = Vec::new();
+
+    for element in body.split('<').skip(1) {
+        if !element.starts_with("Cube ") {
+            continue;
+        }
+
+        if let Some(time) = get_attribute(element, "time") {
+            let date = NaiveDate::parse_from_str(time, "%Y-%m-%d")
+                .map_err(|_| format!("The reference date <{}> can not be parsed!", time))?;
+            timestamp = Some(
+                date.and_hms_opt(0, 0, 0)
+                    .unwrap_or_default()
+                    .and_utc()
+                    .fixed_offset(),
+            );
+            continue;
+        }
+
+        let (currency, rate) = match (
+            get_attribute(element, "currency"),
+            get_attribute(element, "rate"),
+        ) {
+            (Some(currency), Some(rate)) => (currency, rate),
+            _ => continue,
+        };
+        if !currencies.is_empty() && !currencies.iter().any(|allowed| allowed == currency) {
+            continue;
+        }
+
+        let timestamp = timestamp
+            .ok_or_else(|| format!("The reference rate of <{}> has no date!", currency))?;
+        match (
+            EXCHANGE.parse(),
+            BASE.parse(),
+            currency.parse(),
+            rate.parse::<E>(),
+            rate.parse::<E>(),
+        ) {
+            (Ok(exchange), Ok(base), Ok(quote), Ok(bid), Ok(ask)) => price_updates.push(
+                PriceUpdate::new(timestamp, exchange, base, quote, Quote::BidAsk { bid, ask }),
+            ),
+            _ => {
+                return Err(format!(
+                    "The reference rate <{}> of <{}> can not be parsed (wrong format)!",
+                    rate, currency
+                ))
+            }
+        }
+    }
+
+    if timestamp.is_none() {
+        return Err("The reference rates document has no rates!".to_string());
+    }
+
+    Ok(price_updates)
+}
+
+/// Get value of the attribute of the element, quoted by single or double quotes.
+fn get_attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    element.split_whitespace().find_map(|item| {
+        let (key, value) = item.split_once('=')?;
+        if key != name {
+            return None;
+        }
+
+        let value = value.trim_end_matches(['/', '>']);
+        value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .or_else(|| {
+                value
+                    .strip_prefix('\'')
+                    .and_then(|value| value.strip_suffix('\''))
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_feed::ecb::parse_rates;
+
+    #[test]
+    fn parse_reference_rates() {
+        let body = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <gesmes:Envelope xmlns:gesmes=\"http://www.gesmes.org/xml/2002-08-01\" xmlns=\"http://www.ecb.int/vocabulary/2002-08-01/eurofxref\">\n\
+            \t<gesmes:subject>Reference rates</gesmes:subject>\n\
+            \t<Cube>\n\
+            \t\t<Cube time='2019-01-18'>\n\
+            \t\t\t<Cube currency='USD' rate='1.1376'/>\n\
+            \t\t\t<Cube currency='JPY' rate='124.53'/>\n\
+            \t\t\t<Cube currency=\"GBP\" rate=\"0.88143\" />\n\
+            \t\t</Cube>\n\
+            \t</Cube>\n\
+            </gesmes:Envelope>";
+        let parse = |currencies: &[&str]| {
+            let currencies: Vec<String> = currencies
+                .iter()
+                .map(|currency| currency.to_string())
+                .collect();
+            parse_rates::<String, f32>(body, &currencies).map(|price_updates| {
+                price_updates
+                    .iter()
+                    .map(|price_update| price_update.get_line())
+                    .collect::<Vec<String>>()
+            })
+        };
+
+        assert_eq!(
+            parse(&[]).unwrap(),
+            vec![
+                "2019-01-18T00:00:00+00:00 ECB EUR USD BID_ASK 1.1376 1.1376",
+                "2019-01-18T00:00:00+00:00 ECB EUR JPY BID_ASK 124.53 124.53",
+                "2019-01-18T00:00:00+00:00 ECB EUR GBP BID_ASK 0.88143 0.88143",
+            ]
+        );
+        assert_eq!(
+            parse(&["GBP", "USD"]).unwrap(),
+            vec![
+                "2019-01-18T00:00:00+00:00 ECB EUR USD BID_ASK 1.1376 1.1376",
+                "2019-01-18T00:00:00+00:00 ECB EUR GBP BID_ASK 0.88143 0.88143",
+            ]
+        );
+
+        assert!(parse_rates::<String, f32>("<html>Maintenance</html>", &[]).is_err());
+        assert!(parse_rates::<String, f32>(
+            "<Cube><Cube time='2019-01-18'><Cube currency='USD' rate='n/a'/></Cube></Cube>",
+            &[]
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Minimal HTTP/1.1 client, `GET` requests only.
+
+use crate::exchange_feed::invalid_data;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Maximal size of a response body, in bytes, larger ones are refused instead of allocated.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Connect to the plain TCP address and get body of the target of the host.
+///
+/// TLS is not supported, an `https://` endpoint is reached through a TLS terminating proxy
+/// listening on the address. Reading fails once nothing arrives for the read timeout.
+pub fn get(
+    address: &str,
+    host: &str,
+    target: &str,
+    read_timeout: Option<Duration>,
+) -> io::Result<String> {
+    let stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(read_timeout)?;
+    write!(
+        &stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n\r\n",
+        target, host
+    )?;
+
+    read_response(&mut BufReader::new(stream))
+}
+
+/// Read the response, its body if the status is `200` or an error of the status otherwise.
+fn read_response<R: BufRead>(input: &mut R) -> io::Result<String> {
+    let mut status = String::new();
+    input.read_line(&mut status)?;
+    let status = status.trim().to_string();
+
+    let mut content_length = None;
+    let mut chunked = false;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            } else if name.trim().eq_ignore_ascii_case("transfer-encoding") {
+                chunked = value.trim().eq_ignore_ascii_case("chunked");
+            }
+        }
+    }
+
+    let body = if chunked {
+        read_chunked(input)?
+    } else {
+        let mut body = Vec::new();
+        match content_length {
+            Some(length) if length <= MAX_BODY_SIZE => {
+                body.resize(length, 0);
+                input.read_exact(&mut body)?;
+            }
+            Some(_) => return Err(invalid_data("The HTTP response is too large!".into())),
+            // The connection is closed after the body.
+            None => {
+                input.take(MAX_BODY_SIZE as u64).read_to_end(&mut body)?;
+            }
+        }
+        body
+    };
+
+    let body = String::from_utf8(body)
+        .map_err(|_| invalid_data("The HTTP response is not valid UTF-8!".into()))?;
+
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(invalid_data(format!(
+            "The HTTP request failed <{}>, response: {}!",
+            status,
+            body.trim()
+        )));
+    }
+
+    Ok(body)
+}
+
+fn read_chunked<R: BufRead>(input: &mut R) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+
+    loop {
+        let mut size = String::new();
+        input.read_line(&mut size)?;
+
+        // Chunk extensions follow the size.
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| invalid_data(format!("The HTTP chunk size <{}> is not valid!", size)))?;
+        if body.len() + size > MAX_BODY_SIZE {
+            return Err(invalid_data("The HTTP response is too large!".into()));
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        input.read_exact(&mut body[start..])?;
+
+        // Line break after the chunk, or after the trailer of the last one.
+        let mut line_break = String::new();
+        input.read_line(&mut line_break)?;
+
+        if size == 0 {
+            return Ok(body);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_feed::http_client::read_response;
+
+    #[test]
+    fn read_http_response() {
+        let read = |response: &str| read_response(&mut response.as_bytes());
+
+        assert_eq!(
+            read("HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n[]").unwrap(),
+            "[]"
+        );
+        assert_eq!(
+            read("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n[1\r\n1;a=b\r\n]\r\n0\r\n\r\n")
+                .unwrap(),
+            "[1]"
+        );
+        assert_eq!(read("HTTP/1.0 200 OK\r\n\r\n{}").unwrap(), "{}");
+
+        let error =
+            read("HTTP/1.1 400 Bad Request\r\nContent-Length: 4\r\n\r\n{\"\"}").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The HTTP request failed <HTTP/1.1 400 Bad Request>, response: {\"\"}!"
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Kraken WebSocket ticker connector.
+
+use crate::exchange_feed::websocket;
+use crate::exchange_feed::websocket::WebSocket;
+use crate::exchange_feed::{invalid_data, Connector};
+use crate::json;
+use crate::json::value::{parse, Value};
+use crate::request::price_update::{PriceUpdate, Quote};
+use chrono::{DateTime, FixedOffset, Utc};
+use std::fmt::Debug;
+use std::io;
+use std::io::BufReader;
+use std::net::TcpStream;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Host of the Kraken WebSocket API.
+const HOST: &str = "ws.kraken.com";
+
+/// Exchange identifier of the `PriceUpdate`s.
+const EXCHANGE: &str = "KRAKEN";
+
+/// `KrakenConnector` structure.
+///
+/// Subscribes to the ticker of the pairs and turns each ticker message into a bid and ask
+/// `PriceUpdate` of the pair, timestamped by its arrival.
+pub struct KrakenConnector {
+    address: String,
+    pairs: Vec<String>,
+    heartbeat_timeout: Option<Duration>,
+    socket: Option<WebSocket<BufReader<TcpStream>, TcpStream>>,
+}
+
+impl KrakenConnector {
+    /// Create a new instance of `KrakenConnector` structure.
+    ///
+    /// The address is a TLS terminating proxy of `ws.kraken.com:443`, the pairs are in the
+    /// Kraken `<base>/<quote>` format, e.g. `XBT/USD`.
+    pub fn new(address: String, pairs: Vec<String>) -> Self {
+        Self {
+            address,
+            pairs,
+            heartbeat_timeout: None,
+            socket: None,
+        }
+    }
+}
+
+impl<N, E> Connector<N, E> for KrakenConnector
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    fn get_name(&self) -> &str {
+        EXCHANGE
+    }
+
+    fn set_heartbeat_timeout(&mut self, timeout: Option<Duration>) {
+        self.heartbeat_timeout = timeout;
+    }
+
+    fn connect(&mut self) -> io::Result<()> {
+        self.socket = None;
+
+        let mut socket = websocket::connect(&self.address, HOST, "/", self.heartbeat_timeout)?;
+        socket.write_text(&get_subscribe_message(&self.pairs))?;
+        self.socket = Some(socket);
+
+        Ok(())
+    }
+
+    fn receive(&mut self) -> io::Result<Vec<PriceUpdate<N, E>>> {
+        let socket = self
+            .socket
+            .as_mut()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+
+        loop {
+            let message = socket.read_text()?;
+            let timestamp = Utc::now().fixed_offset();
+
+            match parse_message(&message, timestamp) {
+                Ok(Some(price_update)) => return Ok(vec![price_update]),
+                // Heartbeats, status and other events.
+                Ok(None) => {}
+                Err(error) => return Err(invalid_data(error)),
+            }
+        }
+    }
+}
+
+fn get_subscribe_message(pairs: &[String]) -> String {
+    let pairs: Vec<String> = pairs.iter().map(json::quote).collect();
+
+    format!(
+        "{{\"event\":\"subscribe\",\"pair\":[{}],\"subscription\":{{\"name\":\"ticker\"}}}}",
+        pairs.join(",")
+    )
+}
+
+/// Parse the message into a `PriceUpdate` if it's a ticker one.
+///
+/// # `message` format
+///
+/// `[<channel_id>, {"a": [<ask>, ...], "b": [<bid>, ...], ...}, "ticker", "<base>/<quote>"]`
+fn parse_message<N, E>(
+    message: &str,
+    timestamp: DateTime<FixedOffset>,
+) -> Result<Option<PriceUpdate<N, E>>, String>
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    let items = match parse(message)? {
+        Value::Array(items) => items,
+        event => {
+            // A rejected subscription would leave the feed silent forever.
+            if event.get("status").and_then(Value::as_text) == Some("error") {
+                let error = event
+                    .get("errorMessage")
+                    .and_then(Value::as_text)
+                    .unwrap_or_default();
+                return Err(format!("The subscription is rejected <{}>!", error));
+            }
+
+            return Ok(None);
+        }
+    };
+
+    if items.get(2).and_then(Value::as_text) != Some("ticker") {
+        return Ok(None);
+    }
+
+    let pair = items.get(3).and_then(Value::as_text).unwrap_or_default();
+    let price = |side: &str| {
+        items
+            .get(1)
+            .and_then(|ticker| ticker.get(side))
+            .and_then(|prices| match prices {
+                Value::Array(prices) => prices.first(),
+                _ => None,
+            })
+            .and_then(Value::as_text)
+            .and_then(|price| price.parse::<E>().ok())
+    };
+
+    match (pair.split_once('/'), price("b"), price("a")) {
+        (Some((base, quote)), Some(bid), Some(ask)) => {
+            match (EXCHANGE.parse(), base.parse(), quote.parse()) {
+                (Ok(exchange), Ok(base), Ok(quote)) => Ok(Some(PriceUpdate::new(
+                    timestamp,
+                    exchange,
+                    base,
+                    quote,
+                    Quote::BidAsk { bid, ask },
+                ))),
+                _ => Err(format!("The ticker pair <{}> can not be parsed!", pair)),
+            }
+        }
+        _ => Err(format!(
+            "The ticker of <{}> can not be parsed (wrong format)!",
+            pair
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_feed::kraken::{get_subscribe_message, parse_message};
+    use chrono::DateTime;
+
+    #[test]
+    fn subscribe_message() {
+        assert_eq!(
+            get_subscribe_message(&["XBT/USD".to_string(), "ETH/EUR".to_string()]),
+            "{\"event\":\"subscribe\",\"pair\":[\"XBT/USD\",\"ETH/EUR\"],\"subscription\":{\"name\":\"ticker\"}}"
+        );
+    }
+
+    #[test]
+    fn parse_ticker_message() {
+        let timestamp = DateTime::parse_from_rfc3339("2019-01-20T09:42:23+00:00").unwrap();
+        let parse = |message| parse_message::<String, f32>(message, timestamp);
+
+        let message = "[340,{\"a\":[\"1000.5\",1,\"1.0\"],\"b\":[\"999.5\",2,\"2.0\"],\"c\":[\"1000.0\",\"0.1\"]},\"ticker\",\"XBT/USD\"]";
+        assert_eq!(
+            parse(message)
+                .unwrap()
+                .map(|price_update| price_update.get_line()),
+            Some("2019-01-20T09:42:23+00:00 KRAKEN XBT USD BID_ASK 999.5 1000.5".to_string())
+        );
+
+        // Test that events are skipped, but a rejected subscription is an error.
+        assert!(parse("{\"event\":\"heartbeat\"}").unwrap().is_none());
+        assert!(parse("{\"event\":\"subscriptionStatus\",\"status\":\"error\",\"errorMessage\":\"Currency pair not supported\"}").is_err());
+
+        let message = "[340,{\"a\":[\"x\"],\"b\":[\"999.5\"]},\"ticker\",\"XBT/USD\"]";
+        assert!(parse(message).is_err());
+        assert!(parse("not json").is_err());
+    }
+}
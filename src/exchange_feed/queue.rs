@@ -0,0 +1,627 @@
+//! Bounded lock-free queue of feed events.
+
+use self::OverflowPolicy::*;
+use crate::exchange_feed::FeedEvent;
+use crate::request::price_update::PriceUpdate;
+use crate::IndexMapTrait;
+use std::cell::UnsafeCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Debug;
+use std::mem::MaybeUninit;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+/// Longest time the receiver sleeps without being woken up, a safety net of lost wake-ups.
+const PARK_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// What a full queue does with a new event.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// The new event is dropped, the queued ones are kept.
+    DropNewest,
+    /// The oldest queued event is dropped to make room for the new one.
+    #[default]
+    DropOldest,
+}
+
+impl OverflowPolicy {
+    pub fn get_label(&self) -> String {
+        match self {
+            DropNewest => "drop-newest".to_string(),
+            DropOldest => "drop-oldest".to_string(),
+        }
+    }
+
+    /// Parse the overflow policy from its label, ignoring the case.
+    pub fn parse(label: &str) -> Option<Self> {
+        let label = label.to_lowercase();
+
+        if label == DropNewest.get_label() {
+            Some(DropNewest)
+        } else if label == DropOldest.get_label() {
+            Some(DropOldest)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+/// `QueueMetrics` structure.
+///
+/// Events waiting in the queue and events dropped by its overflow policy so far.
+#[derive(Debug, Default)]
+pub struct QueueMetrics {
+    depth: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+impl QueueMetrics {
+    /// Create a new instance of `QueueMetrics` structure.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of events waiting in the queue.
+    pub fn get_depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of events dropped because the queue was full.
+    pub fn get_dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Slot of the `RingBuffer`, its sequence tells whether it's ready to be written or read.
+struct Slot<T> {
+    sequence: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Bounded lock-free multi-producer multi-consumer ring buffer, after Dmitry Vyukov.
+///
+/// Every slot has a sequence number. A slot of the position is free to write if its sequence
+/// equals the position, and holds a value to read if it equals the position plus one. Writers
+/// and readers claim positions by a compare-and-swap of their counter, so no lock is taken.
+struct RingBuffer<T> {
+    slots: Box<[Slot<T>]>,
+    enqueue_position: AtomicUsize,
+    dequeue_position: AtomicUsize,
+}
+
+// Values are moved in and out by a single thread owning the claimed position.
+unsafe impl<T: Send> Send for RingBuffer<T> {}
+unsafe impl<T: Send> Sync for RingBuffer<T> {}
+
+impl<T> RingBuffer<T> {
+    fn new(capacity: usize) -> Self {
+        let slots = (0..capacity.max(1))
+            .map(|index| Slot {
+                sequence: AtomicUsize::new(index),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+
+        Self {
+            slots,
+            enqueue_position: AtomicUsize::new(0),
+            dequeue_position: AtomicUsize::new(0),
+        }
+    }
+
+    /// Push the value, it's given back if the buffer is full.
+    fn push(&self, value: T) -> Result<(), T> {
+        let mut position = self.enqueue_position.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.slots[position % self.slots.len()];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+
+            if sequence == position {
+                match self.enqueue_position.compare_exchange_weak(
+                    position,
+                    position + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        unsafe { (*slot.value.get()).write(value) };
+                        slot.sequence.store(position + 1, Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => position = current,
+                }
+            } else if sequence < position {
+                // The slot still holds the value of the previous lap.
+                return Err(value);
+            } else {
+                position = self.enqueue_position.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Pop the oldest value, `None` if the buffer is empty.
+    fn pop(&self) -> Option<T> {
+        let mut position = self.dequeue_position.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.slots[position % self.slots.len()];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+
+            if sequence == position + 1 {
+                match self.dequeue_position.compare_exchange_weak(
+                    position,
+                    position + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let value = unsafe { (*slot.value.get()).assume_init_read() };
+                        slot.sequence
+                            .store(position + self.slots.len(), Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(current) => position = current,
+                }
+            } else if sequence < position + 1 {
+                // Nothing was written to the slot in this lap yet.
+                return None;
+            } else {
+                position = self.dequeue_position.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl<T> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+/// State shared by the senders and the receiver of the queue.
+struct Shared<N, E> {
+    buffer: RingBuffer<FeedEvent<N, E>>,
+    overflow_policy: OverflowPolicy,
+    metrics: Arc<QueueMetrics>,
+    /// Pairs of the dropped events, reported as a gap once the queue is drained.
+    lost: Mutex<HashSet<(N, N, N)>>,
+    has_lost: AtomicBool,
+    senders: AtomicUsize,
+    disconnected: AtomicBool,
+    receiver: Mutex<Option<Thread>>,
+    parked: AtomicBool,
+}
+
+impl<N, E> Shared<N, E>
+where
+    N: Clone + FromStr + IndexMapTrait,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    /// Remember pairs of the dropped event, so their quotes aren't relied on.
+    fn lose(&self, event: FeedEvent<N, E>) {
+        self.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+
+        let mut lost = self.lost.lock().unwrap_or_else(PoisonError::into_inner);
+        match event {
+            FeedEvent::PriceUpdates(price_updates) => {
+                lost.extend(price_updates.iter().map(PriceUpdate::get_index))
+            }
+            FeedEvent::Gap(indexes) => lost.extend(indexes),
+        }
+        self.has_lost.store(true, Ordering::SeqCst);
+    }
+
+    /// Wake the receiver up if it waits for an event.
+    fn wake_receiver(&self) {
+        if self.parked.swap(false, Ordering::SeqCst) {
+            if let Some(receiver) = &*self.receiver.lock().unwrap_or_else(PoisonError::into_inner) {
+                receiver.unpark();
+            }
+        }
+    }
+}
+
+/// Create a queue of the capacity, returning its sender and receiver.
+///
+/// A full queue applies the overflow policy, the pairs of dropped events are reported by a
+/// `FeedEvent::Gap` once the queue is drained, so it follows every event queued before the
+/// loss and the pairs are stale until their next price update.
+pub fn bounded<N, E>(
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+    metrics: Arc<QueueMetrics>,
+) -> (EventSender<N, E>, EventReceiver<N, E>) {
+    let shared = Arc::new(Shared {
+        buffer: RingBuffer::new(capacity),
+        overflow_policy,
+        metrics,
+        lost: Mutex::new(HashSet::new()),
+        has_lost: AtomicBool::new(false),
+        senders: AtomicUsize::new(1),
+        disconnected: AtomicBool::new(false),
+        receiver: Mutex::new(None),
+        parked: AtomicBool::new(false),
+    });
+
+    (
+        EventSender {
+            shared: Arc::clone(&shared),
+        },
+        EventReceiver { shared },
+    )
+}
+
+/// `EventSender` structure.
+///
+/// Sending half of the queue, cloned for each feed thread.
+///
+/// # `EventSender<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct EventSender<N, E> {
+    shared: Arc<Shared<N, E>>,
+}
+
+impl<N, E> EventSender<N, E>
+where
+    N: Clone + FromStr + IndexMapTrait,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    /// Queue the event without waiting, a full queue applies its overflow policy. Return
+    /// `false` if nobody listens anymore.
+    pub fn send(&self, event: FeedEvent<N, E>) -> bool {
+        let shared = &self.shared;
+        if shared.disconnected.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let mut event = event;
+        loop {
+            match shared.buffer.push(event) {
+                Ok(()) => {
+                    shared.metrics.depth.fetch_add(1, Ordering::Relaxed);
+                    break;
+                }
+                Err(rejected) if shared.overflow_policy == DropNewest => {
+                    shared.lose(rejected);
+                    break;
+                }
+                Err(rejected) => {
+                    if let Some(oldest) = shared.buffer.pop() {
+                        shared.metrics.depth.fetch_sub(1, Ordering::Relaxed);
+                        shared.lose(oldest);
+                    }
+                    event = rejected;
+                }
+            }
+        }
+        shared.wake_receiver();
+
+        true
+    }
+}
+
+impl<N, E> Clone for EventSender<N, E> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::SeqCst);
+
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<N, E> Drop for EventSender<N, E> {
+    fn drop(&mut self) {
+        // The receiver finds out there are no more events once the last sender is gone.
+        if self.shared.senders.fetch_sub(1, Ordering::SeqCst) == 1
+            && self.shared.parked.swap(false, Ordering::SeqCst)
+        {
+            if let Some(receiver) = &*self
+                .shared
+                .receiver
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+            {
+                receiver.unpark();
+            }
+        }
+    }
+}
+
+/// `EventReceiver` structure.
+///
+/// Receiving half of the queue, iterating events until all senders are gone.
+///
+/// # `EventReceiver<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct EventReceiver<N, E> {
+    shared: Arc<Shared<N, E>>,
+}
+
+impl<N, E> EventReceiver<N, E>
+where
+    N: Clone + FromStr + IndexMapTrait,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    /// Get the next event without waiting, `None` if the queue is empty.
+    pub fn try_recv(&self) -> Option<FeedEvent<N, E>> {
+        let shared = &self.shared;
+
+        if let Some(event) = shared.buffer.pop() {
+            shared.metrics.depth.fetch_sub(1, Ordering::Relaxed);
+            return Some(event);
+        }
+
+        if shared.has_lost.swap(false, Ordering::SeqCst) {
+            let lost: Vec<_> = shared
+                .lost
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .drain()
+                .collect();
+            if !lost.is_empty() {
+                return Some(FeedEvent::Gap(lost));
+            }
+        }
+
+        None
+    }
+
+    /// Wait for the next event, `None` once the queue is empty and all senders are gone.
+    pub fn recv(&self) -> Option<FeedEvent<N, E>> {
+        let shared = &self.shared;
+
+        loop {
+            if let Some(event) = self.try_recv() {
+                return Some(event);
+            }
+            if shared.senders.load(Ordering::SeqCst) == 0 {
+                return self.try_recv();
+            }
+
+            *shared
+                .receiver
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(thread::current());
+            shared.parked.store(true, Ordering::SeqCst);
+            // An event sent before the receiver was parked wouldn't wake it up.
+            if let Some(event) = self.try_recv() {
+                shared.parked.store(false, Ordering::SeqCst);
+                return Some(event);
+            }
+            if shared.senders.load(Ordering::SeqCst) > 0 {
+                thread::park_timeout(PARK_TIMEOUT);
+            }
+            shared.parked.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// Iterate the events, waiting for each of them.
+    pub fn iter(&self) -> impl Iterator<Item = FeedEvent<N, E>> + '_ {
+        std::iter::from_fn(move || self.recv())
+    }
+}
+
+impl<N, E> Iterator for EventReceiver<N, E>
+where
+    N: Clone + FromStr + IndexMapTrait,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    type Item = FeedEvent<N, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+impl<N, E> Drop for EventReceiver<N, E> {
+    fn drop(&mut self) {
+        self.shared.disconnected.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_feed::queue::{
+        bounded, OverflowPolicy, OverflowPolicy::*, QueueMetrics, RingBuffer,
+    };
+    use crate::exchange_feed::FeedEvent;
+    use crate::request::price_update::PriceUpdate;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn price_updates(second: u32) -> FeedEvent<String, f32> {
+        let line = format!(
+            "2019-01-20T09:42:{:02}+00:00 E{} BTC USD 1000.0 0.001",
+            second, second
+        );
+        FeedEvent::PriceUpdates(vec![PriceUpdate::parse_line(&line).unwrap()])
+    }
+
+    fn get_lines(event: FeedEvent<String, f32>) -> Vec<String> {
+        match event {
+            FeedEvent::PriceUpdates(price_updates) => price_updates
+                .iter()
+                .map(|price_update| price_update.get_line())
+                .collect(),
+            FeedEvent::Gap(mut indexes) => {
+                indexes.sort();
+                indexes
+                    .iter()
+                    .map(|(exchange, source, destination)| {
+                        format!("GAP {} {} {}", exchange, source, destination)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    #[test]
+    fn parse_overflow_policy() {
+        assert_eq!(OverflowPolicy::parse("DROP-NEWEST"), Some(DropNewest));
+        assert_eq!(OverflowPolicy::parse("drop-oldest"), Some(DropOldest));
+        assert_eq!(OverflowPolicy::parse("block"), None);
+        assert_eq!(OverflowPolicy::default().to_string(), "drop-oldest");
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let buffer = RingBuffer::new(2);
+
+        // Test that values are popped in their order and a full buffer rejects more.
+        assert_eq!(buffer.push(1), Ok(()));
+        assert_eq!(buffer.push(2), Ok(()));
+        assert_eq!(buffer.push(3), Err(3));
+        assert_eq!(buffer.pop(), Some(1));
+        assert_eq!(buffer.push(3), Ok(()));
+        assert_eq!(buffer.pop(), Some(2));
+        assert_eq!(buffer.pop(), Some(3));
+        assert_eq!(buffer.pop(), None);
+    }
+
+    #[test]
+    fn ring_buffer_concurrent() {
+        let buffer = Arc::new(RingBuffer::new(64));
+
+        let producers: Vec<_> = (0..4)
+            .map(|producer| {
+                let buffer = Arc::clone(&buffer);
+                thread::spawn(move || {
+                    for value in 0..10_000 {
+                        let mut value = producer * 10_000 + value;
+                        while let Err(rejected) = buffer.push(value) {
+                            value = rejected;
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        // Test that every value is received once, in the order of its producer.
+        let mut last = [None; 4];
+        let mut received = 0;
+        while received < 40_000 {
+            match buffer.pop() {
+                Some(value) => {
+                    let producer = value / 10_000;
+                    assert!(last[producer].is_none_or(|last| last < value));
+                    last[producer] = Some(value);
+                    received += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        producers
+            .into_iter()
+            .for_each(|producer| producer.join().unwrap());
+        assert_eq!(buffer.pop(), None);
+    }
+
+    #[test]
+    fn drop_newest() {
+        let metrics = Arc::new(QueueMetrics::new());
+        let (sender, receiver) = bounded(2, DropNewest, Arc::clone(&metrics));
+
+        for second in 1..5 {
+            assert!(sender.send(price_updates(second)));
+        }
+        assert_eq!(metrics.get_depth(), 2);
+        assert_eq!(metrics.get_dropped(), 2);
+        drop(sender);
+
+        // Test that the queued events are kept and the dropped pairs follow them as a gap.
+        let lines: Vec<String> = receiver.iter().flat_map(get_lines).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "2019-01-20T09:42:01+00:00 E1 BTC USD 1000 0.001",
+                "2019-01-20T09:42:02+00:00 E2 BTC USD 1000 0.001",
+                "GAP E3 BTC USD",
+                "GAP E4 BTC USD",
+            ]
+        );
+        assert_eq!(metrics.get_depth(), 0);
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let metrics = Arc::new(QueueMetrics::new());
+        let (sender, receiver) = bounded(2, DropOldest, Arc::clone(&metrics));
+
+        for second in 1..5 {
+            assert!(sender.send(price_updates(second)));
+        }
+        assert_eq!(metrics.get_dropped(), 2);
+        drop(sender);
+
+        // Test that the newest events are kept.
+        let lines: Vec<String> = receiver.iter().flat_map(get_lines).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "2019-01-20T09:42:03+00:00 E3 BTC USD 1000 0.001",
+                "2019-01-20T09:42:04+00:00 E4 BTC USD 1000 0.001",
+                "GAP E1 BTC USD",
+                "GAP E2 BTC USD",
+            ]
+        );
+    }
+
+    #[test]
+    fn send_and_receive() {
+        let metrics = Arc::new(QueueMetrics::new());
+        let (sender, receiver) = bounded(4, DropNewest, Arc::clone(&metrics));
+
+        let senders: Vec<_> = (0..3)
+            .map(|_| {
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    for second in 0..100 {
+                        assert!(sender.send(price_updates(second % 60)));
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+
+        // Test that the receiver waits for the events until all senders are gone, and that
+        // every event is either received or dropped.
+        let received = receiver
+            .iter()
+            .filter(|event| matches!(event, FeedEvent::PriceUpdates(_)))
+            .count();
+        senders
+            .into_iter()
+            .for_each(|sender| sender.join().unwrap());
+        assert_eq!(received as u64 + metrics.get_dropped(), 300);
+
+        // Test that senders find out nobody listens anymore.
+        let (sender, receiver) = bounded(4, DropNewest, Arc::new(QueueMetrics::new()));
+        drop(receiver);
+        assert!(!sender.send(price_updates(1)));
+    }
+}
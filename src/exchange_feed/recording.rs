@@ -0,0 +1,285 @@
+//! Recording of live feeds and its replay.
+
+use crate::exchange_feed::{invalid_data, Connector};
+use crate::request::price_update::PriceUpdate;
+use chrono::{DateTime, FixedOffset};
+use num_traits::Zero;
+use std::fmt::{Debug, Display};
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::Instant;
+
+/// Name of the replay connector.
+const NAME: &str = "REPLAY";
+
+/// `Recorder` structure.
+///
+/// Writes the `PriceUpdate`s received by the feeds to the output, each prefixed by the time it
+/// was received.
+///
+/// # Output format
+///
+/// One `<received> <price update line>` line per `PriceUpdate`, e.g.
+/// `2019-01-20T09:42:23.512+00:00 2019-01-20T09:42:23+00:00 KRAKEN BTC USD BID_ASK 999.5 1000.5`.
+pub struct Recorder {
+    output: Box<dyn Write + Send>,
+}
+
+impl Recorder {
+    /// Create a new instance of `Recorder` structure.
+    pub fn new(output: Box<dyn Write + Send>) -> Self {
+        Self { output }
+    }
+
+    /// Record the `PriceUpdate`s received at the time, flushed right away so the recording is
+    /// complete whenever the process stops.
+    pub fn record<N, E>(
+        &mut self,
+        received: DateTime<FixedOffset>,
+        price_updates: &[PriceUpdate<N, E>],
+    ) -> io::Result<()>
+    where
+        N: Clone + FromStr + Display,
+        <N as FromStr>::Err: Debug,
+        E: FromStr + Display,
+        <E as FromStr>::Err: Debug,
+    {
+        for price_update in price_updates.iter() {
+            writeln!(
+                self.output,
+                "{} {}",
+                received.to_rfc3339(),
+                price_update.get_line()
+            )?;
+        }
+
+        self.output.flush()
+    }
+}
+
+/// `ReplayConnector` structure.
+///
+/// Replays a recording of `Recorder` as a feed, keeping the original delays between the
+/// `PriceUpdate`s or shortening them by the speed. It's finished at the end of the recording.
+pub struct ReplayConnector {
+    path: PathBuf,
+    speed: Option<f64>,
+    records: Option<Lines<BufReader<File>>>,
+    /// Time the first record was received at and the time its replay started.
+    start: Option<(DateTime<FixedOffset>, Instant)>,
+    finished: bool,
+}
+
+impl ReplayConnector {
+    /// Create a new instance of `ReplayConnector` structure, replaying the recording at the
+    /// original speed.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            speed: Some(1.0),
+            records: None,
+            start: None,
+            finished: false,
+        }
+    }
+
+    /// Set speed of the replay, e.g. `2.0` replays twice as fast, `None` replays without any
+    /// delay.
+    pub fn set_speed(&mut self, speed: Option<f64>) {
+        self.speed = speed;
+    }
+
+    /// Wait until the record received at the time is due.
+    fn wait(&mut self, received: DateTime<FixedOffset>) {
+        let (first, start) = *self.start.get_or_insert((received, Instant::now()));
+        let speed = match self.speed {
+            Some(speed) if speed > 0.0 => speed,
+            _ => return,
+        };
+
+        let delay = (received - first).to_std().unwrap_or_default();
+        let due = start + delay.div_f64(speed);
+        thread::sleep(due.saturating_duration_since(Instant::now()));
+    }
+}
+
+impl<N, E> Connector<N, E> for ReplayConnector
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr + Zero + PartialOrd,
+    <E as FromStr>::Err: Debug,
+{
+    fn get_name(&self) -> &str {
+        NAME
+    }
+
+    /// The recording is opened once, connecting again continues where the replay stopped.
+    fn connect(&mut self) -> io::Result<()> {
+        if self.records.is_none() {
+            self.records = Some(BufReader::new(File::open(&self.path)?).lines());
+        }
+
+        Ok(())
+    }
+
+    fn receive(&mut self) -> io::Result<Vec<PriceUpdate<N, E>>> {
+        let records = self
+            .records
+            .as_mut()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+
+        let record = loop {
+            match records.next().transpose()? {
+                Some(record) if record.trim().is_empty() => {}
+                Some(record) => break record,
+                None => {
+                    self.finished = true;
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "The recording is replayed!",
+                    ));
+                }
+            }
+        };
+
+        let (received, price_update) = parse_record(&record).map_err(invalid_data)?;
+        self.wait(received);
+
+        Ok(vec![price_update])
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+/// Parse the record into the time it was received and its `PriceUpdate`.
+///
+/// # `record` format
+///
+/// `<received> <timestamp> <exchange> <source_currency> <destination_currency> <quote>`
+fn parse_record<N, E>(record: &str) -> Result<(DateTime<FixedOffset>, PriceUpdate<N, E>), String>
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr + Zero + PartialOrd,
+    <E as FromStr>::Err: Debug,
+{
+    let (received, line) = record.trim().split_once(' ').unwrap_or((record, ""));
+    let received = DateTime::parse_from_rfc3339(received).map_err(|_| {
+        format!(
+            "The record time <{}> can not be parsed (wrong format)!",
+            received
+        )
+    })?;
+    let price_update = PriceUpdate::parse_line(line).map_err(|errors| {
+        format!(
+            "The record <{}> can not be parsed, errors: {:?}!",
+            record, errors
+        )
+    })?;
+
+    Ok((received, price_update))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_feed::recording::{parse_record, Recorder, ReplayConnector};
+    use crate::exchange_feed::Connector;
+    use crate::request::price_update::PriceUpdate;
+    use chrono::DateTime;
+    use std::fs;
+    use std::io;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Output shared with the test.
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn record() {
+        let output = SharedOutput::default();
+        let mut recorder = Recorder::new(Box::new(output.clone()));
+        let received = DateTime::parse_from_rfc3339("2019-01-20T09:42:23.512+00:00").unwrap();
+        let price_updates: Vec<PriceUpdate<String, f32>> = vec![
+            "2019-01-20T09:42:23+00:00 KRAKEN BTC USD BID_ASK 999.5 1000.5",
+            "2019-01-20T09:42:22+00:00 KRAKEN ETH USD 99 0.0101",
+        ]
+        .into_iter()
+        .map(|line| PriceUpdate::parse_line(line).unwrap())
+        .collect();
+
+        recorder.record(received, &price_updates).unwrap();
+
+        let recording = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            recording,
+            "2019-01-20T09:42:23.512+00:00 2019-01-20T09:42:23+00:00 KRAKEN BTC USD BID_ASK 999.5 1000.5\n\
+             2019-01-20T09:42:23.512+00:00 2019-01-20T09:42:22+00:00 KRAKEN ETH USD 99 0.0101\n"
+        );
+
+        // Test that the records are parsed back.
+        for (record, price_update) in recording.lines().zip(price_updates.iter()) {
+            let (record_received, record_price_update) =
+                parse_record::<String, f32>(record).unwrap();
+            assert_eq!(record_received, received);
+            assert_eq!(record_price_update.get_line(), price_update.get_line());
+        }
+
+        assert!(parse_record::<String, f32>("2019-01-20T09:42:23+00:00").is_err());
+        assert!(parse_record::<String, f32>(
+            "09:42:23 2019-01-20T09:42:23+00:00 KRAKEN BTC USD BID_ASK 999.5 1000.5"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn replay() {
+        let path =
+            std::env::temp_dir().join(format!("exchange-rate-{}.replay", std::process::id()));
+        fs::write(
+            &path,
+            "2019-01-20T09:42:23+00:00 2019-01-20T09:42:23+00:00 KRAKEN BTC USD BID_ASK 999.5 1000.5\n\n\
+             2019-01-20T09:42:24+00:00 2019-01-20T09:42:24+00:00 KRAKEN BTC USD BID_ASK 998.5 999.5\n",
+        )
+        .unwrap();
+
+        let mut connector = ReplayConnector::new(path.clone());
+        connector.set_speed(Some(1000.0));
+        let receive = |connector: &mut ReplayConnector| {
+            Connector::<String, f32>::receive(connector)
+                .map(|price_updates| price_updates[0].get_line())
+        };
+        assert!(receive(&mut connector).is_err());
+
+        Connector::<String, f32>::connect(&mut connector).unwrap();
+        assert_eq!(
+            receive(&mut connector).unwrap(),
+            "2019-01-20T09:42:23+00:00 KRAKEN BTC USD BID_ASK 999.5 1000.5"
+        );
+        assert_eq!(
+            receive(&mut connector).unwrap(),
+            "2019-01-20T09:42:24+00:00 KRAKEN BTC USD BID_ASK 998.5 999.5"
+        );
+        assert!(receive(&mut connector).is_err());
+        assert!(Connector::<String, f32>::is_finished(&connector));
+
+        fs::remove_file(path).unwrap();
+    }
+}
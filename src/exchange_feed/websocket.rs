@@ -0,0 +1,296 @@
+//! Minimal WebSocket client, text messages only.
+
+use crate::exchange_feed::invalid_data;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+/// Maximal size of a message, in bytes, larger ones are refused instead of allocated.
+const MAX_MESSAGE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// `WebSocket` structure.
+///
+/// Client side of a WebSocket connection: frames written are masked, pings are answered and
+/// fragmented messages are joined.
+pub struct WebSocket<R, W> {
+    reader: R,
+    writer: W,
+}
+
+/// Connect to the plain TCP address and open a WebSocket of the host and path over it.
+///
+/// TLS is not supported, a `wss://` endpoint is reached through a TLS terminating proxy
+/// listening on the address. Reading fails once nothing arrives for the read timeout.
+pub fn connect(
+    address: &str,
+    host: &str,
+    path: &str,
+    read_timeout: Option<Duration>,
+) -> io::Result<WebSocket<BufReader<TcpStream>, TcpStream>> {
+    let stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(read_timeout)?;
+    let mut socket = WebSocket::new(BufReader::new(stream.try_clone()?), stream);
+    socket.handshake(host, path)?;
+
+    Ok(socket)
+}
+
+impl<R: BufRead, W: Write> WebSocket<R, W> {
+    /// Create a new instance of `WebSocket` structure over an already connected stream.
+    pub fn new(reader: R, writer: W) -> Self {
+        Self { reader, writer }
+    }
+
+    /// Ask the server to switch the connection to the WebSocket protocol.
+    pub fn handshake(&mut self, host: &str, path: &str) -> io::Result<()> {
+        let key = encode_base64(&random_bytes::<16>());
+        write!(
+            self.writer,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            path, host, key
+        )?;
+        self.writer.flush()?;
+
+        let mut status = String::new();
+        self.reader.read_line(&mut status)?;
+        if status.split_whitespace().nth(1) != Some("101") {
+            return Err(invalid_data(format!(
+                "The WebSocket handshake is refused by <{}>!",
+                status.trim()
+            )));
+        }
+
+        // Skip the headers.
+        loop {
+            let mut header = String::new();
+            if self.reader.read_line(&mut header)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            if header.trim().is_empty() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Read the next message, blocking until it arrives.
+    ///
+    /// Control frames are handled on the way, a closed connection is an `UnexpectedEof` error.
+    pub fn read_text(&mut self) -> io::Result<String> {
+        let mut message = Vec::new();
+
+        loop {
+            let (fin, opcode, payload) = self.read_frame()?;
+
+            match opcode {
+                PING => self.write_frame(PONG, &payload)?,
+                PONG => {}
+                CLOSE => {
+                    // Confirm the close, the connection is over either way.
+                    let _ = self.write_frame(CLOSE, &payload);
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                TEXT | BINARY | CONTINUATION => {
+                    if message.len() as u64 + payload.len() as u64 > MAX_MESSAGE_SIZE {
+                        return Err(invalid_data("The WebSocket message is too large!".into()));
+                    }
+
+                    message.extend_from_slice(&payload);
+                    if fin {
+                        return String::from_utf8(message).map_err(|_| {
+                            invalid_data("The WebSocket message is not valid UTF-8!".into())
+                        });
+                    }
+                }
+                _ => {
+                    return Err(invalid_data(format!(
+                        "The WebSocket opcode {} is not known!",
+                        opcode
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Write the text message in a single frame.
+    pub fn write_text(&mut self, text: &str) -> io::Result<()> {
+        self.write_frame(TEXT, text.as_bytes())
+    }
+
+    fn read_frame(&mut self) -> io::Result<(bool, u8, Vec<u8>)> {
+        let mut header = [0; 2];
+        self.reader.read_exact(&mut header)?;
+
+        let fin = header[0] & 0x80 != 0;
+        let opcode = header[0] & 0x0F;
+        let masked = header[1] & 0x80 != 0;
+        let length = match header[1] & 0x7F {
+            126 => {
+                let mut length = [0; 2];
+                self.reader.read_exact(&mut length)?;
+                u64::from(u16::from_be_bytes(length))
+            }
+            127 => {
+                let mut length = [0; 8];
+                self.reader.read_exact(&mut length)?;
+                u64::from_be_bytes(length)
+            }
+            length => u64::from(length),
+        };
+
+        if length > MAX_MESSAGE_SIZE {
+            return Err(invalid_data("The WebSocket message is too large!".into()));
+        }
+
+        let mut mask = [0; 4];
+        if masked {
+            self.reader.read_exact(&mut mask)?;
+        }
+
+        let mut payload = vec![0; length as usize];
+        self.reader.read_exact(&mut payload)?;
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+
+        Ok((fin, opcode, payload))
+    }
+
+    fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        let mut frame = vec![0x80 | opcode];
+
+        // Frames of a client are always masked.
+        match payload.len() {
+            length if length < 126 => frame.push(0x80 | length as u8),
+            length if length <= usize::from(u16::MAX) => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(length as u16).to_be_bytes());
+            }
+            length => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(length as u64).to_be_bytes());
+            }
+        }
+
+        let mask = random_bytes::<4>();
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            payload
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| byte ^ mask[i % 4]),
+        );
+
+        self.writer.write_all(&frame)?;
+        self.writer.flush()
+    }
+}
+
+/// Get unpredictable bytes, as the handshake key and frame masks have to be.
+fn random_bytes<const COUNT: usize>() -> [u8; COUNT] {
+    let mut bytes = [0; COUNT];
+
+    // Each `RandomState` is seeded randomly by the standard library.
+    for chunk in bytes.chunks_mut(8) {
+        let random = RandomState::new().build_hasher().finish().to_le_bytes();
+        chunk.copy_from_slice(&random[..chunk.len()]);
+    }
+
+    bytes
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (i, byte)| {
+            value | u32::from(*byte) << (16 - 8 * i)
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(value >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_feed::websocket::{encode_base64, WebSocket};
+    use std::io;
+
+    #[test]
+    fn handshake() {
+        let response = "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n";
+        let mut request = Vec::new();
+        let mut socket = WebSocket::new(response.as_bytes(), &mut request);
+        socket.handshake("ws.kraken.com", "/").unwrap();
+
+        let request = String::from_utf8(request).unwrap();
+        assert!(request.starts_with("GET / HTTP/1.1\r\nHost: ws.kraken.com\r\n"));
+        assert!(request.contains("\r\nSec-WebSocket-Version: 13\r\n"));
+
+        let response = "HTTP/1.1 403 Forbidden\r\n\r\n";
+        let mut socket = WebSocket::new(response.as_bytes(), io::sink());
+        assert!(socket.handshake("ws.kraken.com", "/").is_err());
+    }
+
+    #[test]
+    fn read_text() {
+        // A ping, a message fragmented into two frames and a close.
+        let frames: &[u8] = &[
+            0x89, 0x01, b'p', 0x01, 0x02, b'a', b'b', 0x80, 0x01, b'c', 0x88, 0x00,
+        ];
+        let mut written = Vec::new();
+        let mut socket = WebSocket::new(frames, &mut written);
+
+        assert_eq!(socket.read_text().unwrap(), "abc");
+        assert_eq!(
+            socket.read_text().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        // Test that the ping was answered by a masked pong of the same payload.
+        assert_eq!(&written[..2], &[0x8A, 0x81]);
+        assert_eq!(written[6] ^ written[2], b'p');
+    }
+
+    #[test]
+    fn write_text() {
+        let mut written = Vec::new();
+        WebSocket::new(&[][..], &mut written)
+            .write_text(&"a".repeat(200))
+            .unwrap();
+
+        assert_eq!(&written[..4], &[0x81, 0x80 | 126, 0, 200]);
+        assert_eq!(written.len(), 4 + 4 + 200);
+        assert_eq!(written[8] ^ written[4], b'a');
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!(encode_base64(b"Man"), "TWFu");
+        assert_eq!(encode_base64(b"Ma"), "TWE=");
+        assert_eq!(encode_base64(b"M"), "TQ==");
+        assert_eq!(
+            encode_base64(b"the sample nonce"),
+            "dGhlIHNhbXBsZSBub25jZQ=="
+        );
+    }
+}
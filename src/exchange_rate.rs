@@ -1,85 +1,510 @@
+use crate::algorithm::settings::Settings;
 use crate::algorithm::Algorithm;
+use crate::checkpoint;
+use crate::checkpoint::Checkpoints;
+use crate::error::Error;
+pub use crate::hash::IndexMapTrait;
+use crate::request;
+use crate::request::arbitrage_request::ArbitrageRequest;
 use crate::request::Request;
+use crate::response::arbitrage::Arbitrage;
 use crate::response::Response;
+use crate::shutdown;
+use crate::shutdown::Shutdown;
+use crate::state;
+use crate::stream::StreamProcessor;
 use floyd_warshall_alg::FloydWarshallTrait;
+use num_traits::{Num, Zero};
 use std::clone::Clone;
 use std::fmt::{Debug, Display};
-use std::hash::Hash;
-use std::io::BufRead;
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
+use std::iter;
+use std::marker::PhantomData;
+use std::mem;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Instant;
 
-/// A trait group for `IndexMap`'s structure.
-pub trait IndexMapTrait: Eq + Hash {}
+/// Size of the rendered output written out at once by the batch mode.
+const OUTPUT_CHUNK_SIZE: usize = 64 * 1024;
 
-/// Implement the `IndexMap` for all types satisfying bounds.
-impl<N> IndexMapTrait for N where N: Eq + Hash {}
+/// Number of rendered output chunks the rendering may be ahead of the writing.
+const OUTPUT_CAPACITY: usize = 16;
 
 /// `ExchangeRatePath` structure.
 ///
-/// # `ExchangeRatePath<I>` is parameterized over:
+/// # `ExchangeRatePath<I, N, E>` is parameterized over:
 ///
-/// - Index `I` for indexing of nodes `N`.
-pub struct ExchangeRatePath<I: BufRead> {
+/// - Input `I` the lines are read from.
+/// - Identifier data `N`, `String` by default.
+/// - Edge weight `E`, `f64` by default.
+pub struct ExchangeRatePath<I: BufRead, N = String, E = f64> {
     input: I,
+    include_summary: bool,
+    include_timings: bool,
+    include_stamp: bool,
+    dump_indexes: bool,
+    find_arbitrage: bool,
+    find_triangular_arbitrage: bool,
+    find_all_arbitrages: bool,
+    arbitrage_json: bool,
+    watch: bool,
+    monitor_arbitrage: bool,
+    rate_alerts: Vec<((N, N, N, N), E)>,
+    exit_on_signal: bool,
+    snapshot_path: Option<PathBuf>,
+    checkpoints: Checkpoints,
+    journal_path: Option<PathBuf>,
+    #[cfg(feature = "sqlite")]
+    storage_path: Option<PathBuf>,
+    state_path: Option<PathBuf>,
+    history: bool,
+    types: PhantomData<(N, E)>,
 }
 
 impl<I: BufRead> ExchangeRatePath<I> {
-    /// Create a new instance of ExchangeRatePath structure.
+    /// Create a new instance of ExchangeRatePath structure of `String` identifiers and `f64`
+    /// edge weights.
     ///
     /// # Examples
     /// ```
     /// use exchange_rate::ExchangeRatePath;
     ///
-    /// ExchangeRatePath::new(std::io::stdin().lock());
+    /// let input = "2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001
+    /// EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD";
+    ///
+    /// ExchangeRatePath::new(input.as_bytes()).run().unwrap();
     /// ```
     pub fn new(input: I) -> Self {
-        Self { input }
+        Self::with_types(input)
+    }
+}
+
+impl<I: BufRead, N, E> ExchangeRatePath<I, N, E> {
+    /// Create a new instance of ExchangeRatePath structure of the identifier data `N` and edge
+    /// weight `E` given.
+    ///
+    /// # Examples
+    /// ```
+    /// use exchange_rate::ExchangeRatePath;
+    ///
+    /// ExchangeRatePath::<_, String, f32>::with_types(std::io::stdin().lock());
+    /// ```
+    pub fn with_types(input: I) -> Self {
+        Self {
+            input,
+            include_summary: false,
+            include_timings: false,
+            include_stamp: false,
+            dump_indexes: false,
+            find_arbitrage: false,
+            find_triangular_arbitrage: false,
+            find_all_arbitrages: false,
+            arbitrage_json: false,
+            watch: false,
+            monitor_arbitrage: false,
+            rate_alerts: Vec::new(),
+            exit_on_signal: false,
+            snapshot_path: None,
+            checkpoints: Checkpoints::new(),
+            journal_path: None,
+            #[cfg(feature = "sqlite")]
+            storage_path: None,
+            state_path: None,
+            history: false,
+            types: PhantomData,
+        }
+    }
+
+    /// Set whether the `Summary` statistics should be appended to the output.
+    pub fn set_include_summary(&mut self, include_summary: bool) {
+        self.include_summary = include_summary;
+    }
+
+    /// Set whether the `PhaseTimings` line should be appended to the output.
+    pub fn set_include_timings(&mut self, include_timings: bool) {
+        self.include_timings = include_timings;
+    }
+
+    /// Set whether each block of the output should be stamped by an `AS_OF` line of the
+    /// timestamp of the most recent quote it was based on and the moment of its computation.
+    pub fn set_include_stamp(&mut self, include_stamp: bool) {
+        self.include_stamp = include_stamp;
+    }
+
+    /// Set whether the indexes of the graph should be dumped to the standard error output, see
+    /// `Algorithm::dump_indexes`.
+    pub fn set_dump_indexes(&mut self, dump_indexes: bool) {
+        self.dump_indexes = dump_indexes;
+    }
+
+    /// Set whether the most profitable `Arbitrage` should be searched for and written out.
+    pub fn set_find_arbitrage(&mut self, find_arbitrage: bool) {
+        self.find_arbitrage = find_arbitrage;
+    }
+
+    /// Set whether all profitable intra-exchange cycles should be searched for and written out.
+    pub fn set_find_triangular_arbitrage(&mut self, find_triangular_arbitrage: bool) {
+        self.find_triangular_arbitrage = find_triangular_arbitrage;
+    }
+
+    /// Set whether all profitable cycles up to the maximal cycle length should be searched for
+    /// and written out, ranked from the most profitable one.
+    pub fn set_find_all_arbitrages(&mut self, find_all_arbitrages: bool) {
+        self.find_all_arbitrages = find_all_arbitrages;
+    }
+
+    /// Set whether arbitrages should be written out as JSON lines instead of text blocks.
+    pub fn set_arbitrage_json(&mut self, arbitrage_json: bool) {
+        self.arbitrage_json = arbitrage_json;
+    }
+
+    /// Set whether input lines should be processed one by one as they come, answering requests
+    /// immediately instead of after the whole input was read.
+    pub fn set_watch(&mut self, watch: bool) {
+        self.watch = watch;
+    }
+
+    /// Set whether arbitrage opportunities should be re-evaluated after each price update in the
+    /// watch mode, alerting their appearance and disappearance.
+    pub fn set_monitor_arbitrage(&mut self, monitor_arbitrage: bool) {
+        self.monitor_arbitrage = monitor_arbitrage;
+    }
+
+    /// Alert moves of the best rate of the
+    /// `(source_exchange, source_currency, destination_exchange, destination_currency)` pair by
+    /// more than the relative threshold since the last alert, re-evaluated after each price
+    /// update in the watch mode.
+    pub fn add_rate_alert(&mut self, pair: (N, N, N, N), threshold: E) {
+        self.rate_alerts.push((pair, threshold));
+    }
+
+    /// Set whether the watch mode should shut down gracefully once SIGTERM or SIGINT arrives,
+    /// finishing the line in flight and flushing its output before exiting the process.
+    pub fn set_exit_on_signal(&mut self, exit_on_signal: bool) {
+        self.exit_on_signal = exit_on_signal;
+    }
+
+    /// Set path of the file the known price updates are written to on a graceful shutdown of
+    /// the watch mode, `None` for no snapshot.
+    pub fn set_snapshot_path(&mut self, snapshot_path: Option<PathBuf>) {
+        self.snapshot_path = snapshot_path;
+    }
+
+    /// Set policy of writing the snapshot periodically in the watch mode, if there is a
+    /// snapshot path. Its retention applies to the snapshot on shutdown as well.
+    pub fn set_checkpoints(&mut self, checkpoints: Checkpoints) {
+        self.checkpoints = checkpoints;
+    }
+
+    /// Set path of the journal file the price updates accepted by the watch mode are appended
+    /// to, `None` for no journal. The journal is replayed at startup if it exists, recovering the
+    /// state before a crash.
+    pub fn set_journal_path(&mut self, journal_path: Option<PathBuf>) {
+        self.journal_path = journal_path;
+    }
+
+    /// Set path of the SQLite database the price updates accepted and best rate paths answered
+    /// by the watch mode are stored to, `None` for no storage.
+    #[cfg(feature = "sqlite")]
+    pub fn set_storage_path(&mut self, storage_path: Option<PathBuf>) {
+        self.storage_path = storage_path;
+    }
+
+    /// Set path of the state JSON the watch mode starts from, `None` to start empty. Its
+    /// settings replace the customized ones and the journal is replayed on top of it.
+    pub fn set_state_path(&mut self, state_path: Option<PathBuf>) {
+        self.state_path = state_path;
+    }
+
+    /// Set whether all price updates accepted by the watch mode should be kept, answering rate
+    /// requests as of a past moment.
+    pub fn set_history(&mut self, history: bool) {
+        self.history = history;
+    }
+
+    /// Write out the human-readable JSON of the state formed by the `PriceUpdate`s of the input
+    /// and the customized algorithm `Settings`, to be imported by the watch mode later.
+    ///
+    /// Requests of the input are ignored. The `Error` of invalid input lines is returned before
+    /// anything is written.
+    pub fn export_state(&mut self, settings: &Settings<N, E>) -> Result<(), Error>
+    where
+        N: Clone + Display + FromStr + IndexMapTrait + Send,
+        <N as FromStr>::Err: Debug,
+        E: Num + Copy + Display + FromStr + PartialOrd + Send,
+        <E as FromStr>::Err: Debug,
+    {
+        let request = self.form_request()?;
+        println!(
+            "{}",
+            state::get_state_json(settings, request.get_price_updates().values())
+        );
+
+        Ok(())
     }
 
     /// Run the Exchange Rate Path application.
-    pub fn run<N, E>(&mut self)
+    ///
+    /// The `Error` of all invalid input lines is returned before anything is answered, see
+    /// `Request::try_read_pipelined`. The watch mode keeps running past invalid lines, it returns
+    /// the `Error` only if its state, journal or storage can't be opened.
+    pub fn run(&mut self) -> Result<(), Error>
     where
-        N: Clone + Display + FromStr + IndexMapTrait + Debug,
+        N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + Sync + 'static,
+        <N as FromStr>::Err: Debug,
+        E: Display + FloydWarshallTrait + FromStr + Debug + Send + Sync + 'static,
+        <E as FromStr>::Err: Debug,
+    {
+        self.run_customized(&Settings::new())
+    }
+
+    /// Run the Exchange Rate Path application using customized algorithm `Settings`, see `run`.
+    pub fn run_customized(&mut self, settings: &Settings<N, E>) -> Result<(), Error>
+    where
+        N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + Sync + 'static,
+        <N as FromStr>::Err: Debug,
+        E: Display + FloydWarshallTrait + FromStr + Debug + Send + Sync + 'static,
+        <E as FromStr>::Err: Debug,
+    {
+        if self.watch {
+            return self.run_watch(settings);
+        }
+
+        let start = Instant::now();
+        let mut request = self.form_request()?;
+        let parse_time = start.elapsed();
+        if self.dump_indexes {
+            eprint!(
+                "{}",
+                Algorithm::<N, E, u32>::from_request(&request, settings).dump_indexes()
+            );
+        }
+        if self.find_arbitrage {
+            // The same as `ARBITRAGE_REQUEST ANY ANY` input line.
+            request.add_arbitrage_request(ArbitrageRequest::new(None, None));
+        }
+
+        let triangular_arbitrages = if self.find_triangular_arbitrage {
+            Self::process_triangular_arbitrage(&request, settings)
+        } else {
+            Vec::new()
+        };
+
+        let all_arbitrages = if self.find_all_arbitrages {
+            Self::process_all_arbitrages(&request, settings)
+        } else {
+            Vec::new()
+        };
+
+        let mut response = Self::process_request(request, settings);
+        response.set_parse_time(parse_time);
+        for arbitrage in triangular_arbitrages.into_iter().chain(all_arbitrages) {
+            response.add_arbitrage(arbitrage);
+        }
+
+        self.write_response(response);
+
+        Ok(())
+    }
+
+    /// Process input lines one by one, writing out the output as soon as it's available.
+    fn run_watch(&mut self, settings: &Settings<N, E>) -> Result<(), Error>
+    where
+        N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + 'static,
+        <N as FromStr>::Err: Debug,
+        E: Display + FloydWarshallTrait + FromStr + Debug + Send + 'static,
+        <E as FromStr>::Err: Debug,
+    {
+        let mut processor = StreamProcessor::<N, E>::new(settings.clone());
+        processor.set_monitor_arbitrage(self.monitor_arbitrage);
+        for (pair, threshold) in self.rate_alerts.iter() {
+            processor.add_rate_alert(pair.clone(), *threshold);
+        }
+        processor.set_arbitrage_json(self.arbitrage_json);
+        processor.set_include_stamp(self.include_stamp);
+        processor.set_history(self.history);
+        if let Some(path) = &self.state_path {
+            fs::read_to_string(path)
+                .map_err(Error::Io)
+                .and_then(|text| processor.import_state(&text))
+                .map_err(|error| {
+                    in_context(
+                        error,
+                        format!("The state <{}> can not be imported!", path.display()),
+                    )
+                })?;
+        }
+        if let Some(path) = &self.journal_path {
+            processor.open_journal(path).map_err(|error| {
+                in_context(
+                    error,
+                    format!("The journal <{}> can not be opened!", path.display()),
+                )
+            })?;
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(path) = &self.storage_path {
+            processor.open_storage(path).map_err(|error| {
+                in_context(
+                    Error::Io(error),
+                    format!("The storage <{}> can not be opened!", path.display()),
+                )
+            })?;
+        }
+
+        let processor = Arc::new(Mutex::new(processor));
+        let shutdown = Arc::new(Shutdown::new());
+        if self.exit_on_signal {
+            shutdown::exit_on_signal(
+                Arc::clone(&shutdown),
+                Arc::clone(&processor),
+                self.snapshot_path.clone(),
+                self.checkpoints.get_retention(),
+            );
+        }
+        if let Some(path) = &self.snapshot_path {
+            checkpoint::checkpoint_periodically(
+                Arc::clone(&processor),
+                path.clone(),
+                self.checkpoints.clone(),
+            );
+        }
+
+        for line in request::valid_lines(&mut self.input) {
+            // The line is finished and its output flushed before the process exits.
+            let _activity = match shutdown.start_activity() {
+                Some(activity) => activity,
+                None => break,
+            };
+
+            let result = processor
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .process_line(&line);
+
+            match result {
+                Ok(output) => {
+                    print!("{}", output);
+                    // Make the output available without waiting for more input.
+                    let _ = io::stdout().flush();
+                }
+                // Keep watching, a single invalid line shouldn't stop the stream processing.
+                Err(error) => eprintln!(
+                    "Errors occurred while processing input line <{}>, errors: {:?}!",
+                    line,
+                    error.get_messages()
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn form_request(&mut self) -> Result<Request<N, E>, Error>
+    where
+        N: Clone + Display + FromStr + IndexMapTrait + Send,
+        <N as FromStr>::Err: Debug,
+        E: FromStr + Zero + PartialOrd + Send,
+        <E as FromStr>::Err: Debug,
+    {
+        Request::<N, E>::try_read_pipelined(&mut self.input)
+    }
+
+    fn process_request(request: Request<N, E>, settings: &Settings<N, E>) -> Response<N, E>
+    where
+        N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + Sync,
         <N as FromStr>::Err: Debug,
-        E: Display + FloydWarshallTrait + FromStr + Debug,
+        E: Display + FloydWarshallTrait + FromStr + Debug + Send + Sync + 'static,
         <E as FromStr>::Err: Debug,
     {
-        let request = self.form_request::<N, E>();
-        let response = Self::process_request::<N, E>(request);
-        Self::write_response(response);
+        Algorithm::<N, E, u32>::process_parallel(&request, settings)
     }
 
-    fn form_request<N, E>(&mut self) -> Request<N, E>
+    fn process_triangular_arbitrage(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+    ) -> Vec<Arbitrage<N, E>>
     where
-        N: Clone + FromStr + IndexMapTrait,
+        N: Clone + Display + FromStr + IndexMapTrait + Debug,
         <N as FromStr>::Err: Debug,
-        E: FromStr,
+        E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
         <E as FromStr>::Err: Debug,
     {
-        Request::<N, E>::read_from(&mut self.input)
+        Algorithm::<N, E, u32>::process_triangular_arbitrage(request, settings)
     }
 
-    fn process_request<N, E>(request: Request<N, E>) -> Response<N, E>
+    fn process_all_arbitrages(
+        request: &Request<N, E>,
+        settings: &Settings<N, E>,
+    ) -> Vec<Arbitrage<N, E>>
     where
         N: Clone + Display + FromStr + IndexMapTrait + Debug,
         <N as FromStr>::Err: Debug,
-        E: Display + FloydWarshallTrait + FromStr + Debug,
+        E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
         <E as FromStr>::Err: Debug,
     {
-        Algorithm::<N, E, u32>::process(&request)
+        Algorithm::<N, E, u32>::process_all_arbitrages(request, settings)
     }
 
-    fn write_response<N, E>(response: Response<N, E>)
+    fn write_response(&self, mut response: Response<N, E>)
     where
         N: Display + Debug,
-        E: Display,
+        E: Display + PartialOrd + Num + Copy,
     {
-        print!("{}", response.get_output());
+        response.set_include_summary(self.include_summary);
+        response.set_include_timings(self.include_timings);
+        response.set_include_stamp(self.include_stamp);
+        response.set_arbitrage_json(self.arbitrage_json);
+
+        // Parts rendered already are written by another thread while the rest is rendered.
+        let (sender, receiver) = mpsc::sync_channel::<String>(OUTPUT_CAPACITY);
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                let mut stdout = io::stdout().lock();
+                for chunk in receiver {
+                    let _ = stdout.write_all(chunk.as_bytes());
+                }
+                let _ = stdout.flush();
+            });
+
+            let mut chunk = String::new();
+            response.render(|part| {
+                chunk.push_str(&part);
+                if chunk.len() >= OUTPUT_CHUNK_SIZE {
+                    let _ = sender.send(mem::take(&mut chunk));
+                }
+            });
+            let _ = sender.send(chunk);
+            drop(sender);
+        });
+    }
+}
+
+/// Put the message of the context the `Error` occurred in before its own ones.
+fn in_context(error: Error, context: String) -> Error {
+    match error {
+        Error::Parse(messages) => Error::Parse(iter::once(context).chain(messages).collect()),
+        Error::Validation(messages) => {
+            Error::Validation(iter::once(context).chain(messages).collect())
+        }
+        Error::Io(error) => Error::Io(io::Error::new(
+            error.kind(),
+            format!("{} {}", context, error),
+        )),
+        error => error,
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::error::Error;
     use crate::exchange_rate::ExchangeRatePath;
     use crate::request::Request;
     use std::io::BufReader;
@@ -99,8 +524,8 @@ EXCHANGE_RATE_REQUEST GDAX BTC KRAKEN USD"
             .as_bytes();
         let input = BufReader::new(text_input);
 
-        let mut exchange_rate = ExchangeRatePath::new(input);
-        let request = exchange_rate.form_request::<String, f32>();
+        let mut exchange_rate = ExchangeRatePath::<_, String, f32>::with_types(input);
+        let request = exchange_rate.form_request().unwrap();
         let price_updates = request.get_price_updates();
         let rate_requests = request.get_rate_requests();
 
@@ -109,6 +534,27 @@ EXCHANGE_RATE_REQUEST GDAX BTC KRAKEN USD"
         assert_eq!(rate_requests.len(), 2);
     }
 
+    #[test]
+    fn run_invalid_lines() {
+        let text_input = "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009
+EXCHANGE_RATE_REQUEST KRAKEN BTC"
+            .as_bytes();
+
+        // Test that invalid lines are returned as the error instead of a panic.
+        let error = ExchangeRatePath::new(text_input).run().err().unwrap();
+        assert!(matches!(error, Error::Parse(_)));
+        assert!(error.get_messages()[0].starts_with("The line 2 can not be parsed"));
+
+        // Test that the context of a failure of the watch mode is reported.
+        let mut exchange_rate = ExchangeRatePath::new(&b""[..]);
+        exchange_rate.set_watch(true);
+        exchange_rate.set_state_path(Some("missing_state.json".into()));
+        let error = exchange_rate.run().err().unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("The state <missing_state.json>"));
+    }
+
     #[test]
     fn process_request() {
         // Prepare input.
@@ -120,7 +566,8 @@ EXCHANGE_RATE_REQUEST KRAKEN ETH KRAKEN BTC"
         let mut input = BufReader::new(text_input);
 
         let request = Request::<String, f32>::read_from(&mut input);
-        let response = ExchangeRatePath::<&[u8]>::process_request::<String, f32>(request);
+        let response =
+            ExchangeRatePath::<&[u8], String, f32>::process_request(request, &Settings::new());
 
         let paths = response.get_best_rate_paths();
 
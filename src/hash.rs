@@ -0,0 +1,112 @@
+//! Hasher of the node indexes of the graph.
+//!
+//! Indexes of `(exchange, currency)` nodes and identifiers are hashed on every lookup while the
+//! graph is built. By default they're hashed by the `RandomState` SipHash of the standard
+//! library, resistant to keys chosen to collide. With the `fxhash` feature they're hashed by
+//! `FxHasher`, the fast non-cryptographic hasher of the Rust compiler, for inputs from trusted
+//! sources.
+
+use std::hash::Hash;
+#[cfg(feature = "fxhash")]
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// A trait group for `IndexMap`'s structure.
+pub trait IndexMapTrait: Eq + Hash {}
+
+/// Implement the `IndexMap` for all types satisfying bounds.
+impl<N> IndexMapTrait for N where N: Eq + Hash {}
+
+/// Builder of the hashers of `IndexMap`s indexing nodes.
+#[cfg(not(feature = "fxhash"))]
+pub(crate) type NodeHashBuilder = std::collections::hash_map::RandomState;
+
+/// Builder of the hashers of `IndexMap`s indexing nodes.
+#[cfg(feature = "fxhash")]
+pub(crate) type NodeHashBuilder = BuildHasherDefault<FxHasher>;
+
+/// Multiplier of the Fx hash, an odd constant spreading the bits of each word.
+#[cfg(feature = "fxhash")]
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// `FxHasher` structure.
+///
+/// Hashes a word at a time by rotating the hash, xor-ing the word in and multiplying by a
+/// constant. Fast for short keys like integers and identifiers, but not resistant to collisions
+/// chosen on purpose.
+#[cfg(feature = "fxhash")]
+#[derive(Default, Clone, Copy)]
+pub(crate) struct FxHasher {
+    hash: u64,
+}
+
+#[cfg(feature = "fxhash")]
+impl FxHasher {
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+#[cfg(feature = "fxhash")]
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in chunks.by_ref() {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.add_to_hash(u64::from_le_bytes(word));
+        }
+
+        let remainder = chunks.remainder();
+        if !remainder.is_empty() {
+            let mut word = [0; 8];
+            word[..remainder.len()].copy_from_slice(remainder);
+            self.add_to_hash(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(u64::from(i));
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(u64::from(i));
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(u64::from(i));
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(all(test, feature = "fxhash"))]
+mod tests {
+    use crate::hash::FxHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash<T: Hash>(value: T) -> u64 {
+        let mut hasher = FxHasher::default();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn hash_keys() {
+        // Test that equal keys hash the same and that close ones don't collide.
+        assert_eq!(hash((1_u32, 2_u32)), hash((1_u32, 2_u32)));
+        assert_ne!(hash((1_u32, 2_u32)), hash((2_u32, 1_u32)));
+        assert_eq!(hash("KRAKEN"), hash(String::from("KRAKEN")));
+        assert_ne!(hash("BTC"), hash("BTCX"));
+        assert_ne!(hash("ABCDEFGH"), hash("ABCDEFGH\0"));
+    }
+}
@@ -0,0 +1,127 @@
+//! Strongly typed exchange and currency identifiers.
+//!
+//! Requests, algorithm and responses are generic over a single identifier type `N` of both
+//! exchanges and currencies, as the graph indexes both by the same map. `Exchange` and
+//! `Currency` keep them apart at the library API instead, where an exchange and a currency
+//! argument are easy to swap, and convert into `N` by `From`.
+
+use crate::request::uppercase;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Get the identifier text uppercase the same as identifiers of input lines, shared by clones.
+fn normalize(text: &str) -> Arc<str> {
+    Arc::from(uppercase(text).as_ref())
+}
+
+/// Parse the identifier text of the kind, uppercase the same as identifiers of input lines.
+fn parse(kind: &str, identifier: &str) -> Result<Arc<str>, String> {
+    if identifier.is_empty() || identifier.contains(char::is_whitespace) {
+        return Err(format!(
+            "The {} <{}> can not be parsed (wrong format)!",
+            kind, identifier
+        ));
+    }
+
+    Ok(normalize(identifier))
+}
+
+/// Exchange identifier, e.g. `KRAKEN`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Exchange(Arc<str>);
+
+impl Exchange {
+    /// Create a new instance of `Exchange` structure, the identifier is made uppercase.
+    pub fn new(exchange: &str) -> Self {
+        Self(normalize(exchange))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Exchange {
+    type Err = String;
+
+    fn from_str(exchange: &str) -> Result<Self, Self::Err> {
+        parse("exchange", exchange).map(Self)
+    }
+}
+
+impl fmt::Display for Exchange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Exchange> for String {
+    fn from(exchange: Exchange) -> Self {
+        exchange.0.to_string()
+    }
+}
+
+/// Currency identifier, e.g. `BTC`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Currency(Arc<str>);
+
+impl Currency {
+    /// Create a new instance of `Currency` structure, the identifier is made uppercase.
+    pub fn new(currency: &str) -> Self {
+        Self(normalize(currency))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(currency: &str) -> Result<Self, Self::Err> {
+        parse("currency", currency).map(Self)
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Currency> for String {
+    fn from(currency: Currency) -> Self {
+        currency.0.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::identifier::{Currency, Exchange};
+    use std::sync::Arc;
+
+    #[test]
+    fn parse() {
+        let exchange: Exchange = "kraken".parse().unwrap();
+        assert_eq!(exchange.as_str(), "KRAKEN");
+        assert_eq!(exchange.to_string(), "KRAKEN");
+        assert_eq!(String::from(exchange), "KRAKEN");
+
+        assert!("".parse::<Currency>().is_err());
+        assert_eq!(
+            "B TC".parse::<Currency>(),
+            Err("The currency <B TC> can not be parsed (wrong format)!".to_string())
+        );
+    }
+
+    #[test]
+    fn new() {
+        // Test that identifiers are equal regardless of the case and clones share their text.
+        let a = Currency::new("BTC");
+        let b = Currency::new("btc");
+        assert_eq!(a, b);
+        assert!(Arc::ptr_eq(&a.0, &a.clone().0));
+    }
+}
@@ -0,0 +1,94 @@
+//! Minimal JSON rendering helpers.
+
+use std::fmt::Display;
+
+#[cfg(feature = "std")]
+pub mod value;
+
+/// Get JSON string literal of the value, escaping all characters JSON requires.
+pub fn quote<T: Display + ?Sized>(value: &T) -> String {
+    let mut output = String::from("\"");
+
+    for c in value.to_string().chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+/// Get JSON object of the `(exchange, currency)` node.
+pub fn node<N: Display>(exchange: &N, currency: &N) -> String {
+    format!(
+        "{{\"exchange\":{},\"currency\":{}}}",
+        quote(exchange),
+        quote(currency)
+    )
+}
+
+/// Get JSON object of the error messages, e.g. of an invalid request.
+#[cfg(any(feature = "http", feature = "wasm"))]
+pub fn errors(errors: &[String]) -> String {
+    let errors: Vec<String> = errors.iter().map(quote).collect();
+
+    format!("{{\"errors\":[{}]}}", errors.join(","))
+}
+
+/// Get JSON `null` or the value, which is JSON already.
+pub fn option<T: Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "null".to_string(), |value| value.to_string())
+}
+
+/// Get JSON object of the entries keyed by identifiers, the values are JSON already.
+pub fn map<'a, K, V, I>(entries: I) -> String
+where
+    K: Display + 'a,
+    V: Display + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+{
+    let members: Vec<String> = entries
+        .into_iter()
+        .map(|(key, value)| format!("{}:{}", quote(key), value))
+        .collect();
+
+    format!("{{{}}}", members.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{map, node, option, quote};
+
+    #[test]
+    fn quote_escaped() {
+        assert_eq!(quote("KRAKEN"), "\"KRAKEN\"");
+        assert_eq!(quote("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    }
+
+    #[test]
+    fn node_object() {
+        assert_eq!(
+            node(&"KRAKEN", &"BTC"),
+            "{\"exchange\":\"KRAKEN\",\"currency\":\"BTC\"}"
+        );
+    }
+
+    #[test]
+    fn option_and_map() {
+        assert_eq!(option(Some(1.5)), "1.5");
+        assert_eq!(option(None::<f32>), "null");
+        assert_eq!(
+            map([("KRAKEN", 0.25), ("GDAX", 0.5)]
+                .iter()
+                .map(|(exchange, fee)| (exchange, fee))),
+            "{\"KRAKEN\":0.25,\"GDAX\":0.5}"
+        );
+    }
+}
@@ -0,0 +1,428 @@
+//! Minimal JSON parsing.
+
+use crate::json::quote;
+use crate::request::uppercase;
+use std::str::FromStr;
+
+/// Parsed JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    /// Number as written, to be parsed into the required type.
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    /// Members in the order of appearance.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Get value of the object member, `None` for other values or a missing member.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Get the string or the number as written, `None` for other values.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Value::String(text) | Value::Number(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Get the value as JSON text indented by two spaces, a member or an item per line.
+    pub fn get_pretty(&self) -> String {
+        let mut output = String::new();
+        self.write_pretty(&mut output, 0);
+
+        output
+    }
+
+    fn write_pretty(&self, output: &mut String, depth: usize) {
+        let items: Vec<(Option<&str>, &Value)> = match self {
+            Value::Null => return output.push_str("null"),
+            Value::Boolean(boolean) => return output.push_str(&boolean.to_string()),
+            Value::Number(number) => return output.push_str(number),
+            Value::String(string) => return output.push_str(&quote(string)),
+            Value::Array(values) => values.iter().map(|value| (None, value)).collect(),
+            Value::Object(members) => members
+                .iter()
+                .map(|(key, value)| (Some(key.as_str()), value))
+                .collect(),
+        };
+        let (open, close) = match self {
+            Value::Array(_) => ('[', ']'),
+            _ => ('{', '}'),
+        };
+
+        output.push(open);
+        for (index, (key, value)) in items.iter().enumerate() {
+            output.push_str(if index == 0 { "\n" } else { ",\n" });
+            output.push_str(&"  ".repeat(depth + 1));
+            if let Some(key) = key {
+                output.push_str(&format!("{}: ", quote(key)));
+            }
+            value.write_pretty(output, depth + 1);
+        }
+        if !items.is_empty() {
+            output.push('\n');
+            output.push_str(&"  ".repeat(depth));
+        }
+        output.push(close);
+    }
+}
+
+/// Parse the text of the member of the object, recording an error if it's missing or not valid.
+pub fn field<T, F>(item: &Value, key: &str, errors: &mut Vec<String>, parse: F) -> Option<T>
+where
+    F: Fn(&str) -> Option<T>,
+{
+    match item.get(key).and_then(Value::as_text) {
+        Some(value) => {
+            let parsed = parse(value);
+            if parsed.is_none() {
+                errors.push(format!(
+                    "The field <{}> can not be parsed (wrong format)!",
+                    key
+                ));
+            }
+            parsed
+        }
+        None => {
+            errors.push(format!("The field <{}> is missing!", key));
+            None
+        }
+    }
+}
+
+/// Parse the exchange or currency field, making it uppercase the same as input lines.
+pub fn identifier<N: FromStr>(item: &Value, key: &str, errors: &mut Vec<String>) -> Option<N> {
+    field(item, key, errors, |value| uppercase(value).parse().ok())
+}
+
+pub fn number<T: FromStr>(item: &Value, key: &str, errors: &mut Vec<String>) -> Option<T> {
+    field(item, key, errors, |value| value.parse().ok())
+}
+
+/// Parse the optional number field, `None` if it's missing or `null`.
+pub fn optional_number<T: FromStr>(item: &Value, key: &str, errors: &mut Vec<String>) -> Option<T> {
+    match item.get(key) {
+        None | Some(Value::Null) => None,
+        Some(_) => number(item, key, errors),
+    }
+}
+
+/// Parse the optional boolean field, `None` if it's missing or `null`.
+pub fn optional_boolean(item: &Value, key: &str, errors: &mut Vec<String>) -> Option<bool> {
+    match item.get(key) {
+        None | Some(Value::Null) => None,
+        Some(Value::Boolean(boolean)) => Some(*boolean),
+        Some(_) => {
+            errors.push(format!(
+                "The field <{}> can not be parsed (wrong format)!",
+                key
+            ));
+            None
+        }
+    }
+}
+
+/// Parse the optional exchange or currency field, `None` if it's missing or `null`.
+pub fn optional_identifier<N: FromStr>(
+    item: &Value,
+    key: &str,
+    errors: &mut Vec<String>,
+) -> Option<N> {
+    match item.get(key) {
+        None | Some(Value::Null) => None,
+        Some(_) => identifier(item, key, errors),
+    }
+}
+
+/// Parse the optional object field of numbers keyed by exchange or currency identifiers, e.g.
+/// `{"KRAKEN":0.0026}`, empty if it's missing.
+pub fn keyed_numbers<N, T>(item: &Value, key: &str, errors: &mut Vec<String>) -> Vec<(N, T)>
+where
+    N: FromStr,
+    T: FromStr,
+{
+    let members = match item.get(key) {
+        None => return Vec::new(),
+        Some(Value::Object(members)) => members,
+        Some(_) => {
+            errors.push(format!("The field <{}> is not an object!", key));
+            return Vec::new();
+        }
+    };
+
+    members
+        .iter()
+        .filter_map(|(name, value)| {
+            let name_value = uppercase(name).parse::<N>().ok();
+            match (
+                name_value,
+                value.as_text().and_then(|value| value.parse().ok()),
+            ) {
+                (Some(name), Some(value)) => Some((name, value)),
+                _ => {
+                    errors.push(format!(
+                        "The field <{}.{}> can not be parsed (wrong format)!",
+                        key, name
+                    ));
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Parse each item of the optional array field, empty if it's missing. Errors of an item are
+/// prefixed by the field and the item number.
+pub fn items<T, F>(item: &Value, key: &str, errors: &mut Vec<String>, parse_item: F) -> Vec<T>
+where
+    F: Fn(&Value, &mut Vec<String>) -> Option<T>,
+{
+    let values = match item.get(key) {
+        None => return Vec::new(),
+        Some(Value::Array(values)) => values,
+        Some(_) => {
+            errors.push(format!("The field <{}> is not an array!", key));
+            return Vec::new();
+        }
+    };
+
+    values
+        .iter()
+        .enumerate()
+        .filter_map(|(number, value)| {
+            let mut item_errors = Vec::new();
+            let parsed = parse_item(value, &mut item_errors);
+            errors.extend(
+                item_errors
+                    .into_iter()
+                    .map(|error| format!("The item {} of <{}>: {}", number + 1, key, error)),
+            );
+
+            parsed
+        })
+        .collect()
+}
+
+/// Parse the JSON text into a `Value`.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+    };
+
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+
+    if parser.position < parser.chars.len() {
+        return Err(parser.error("unexpected trailing characters"));
+    }
+
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        format!("The JSON is not valid at {}: {}!", self.position, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+
+        if self.next() == Some(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("<{}> expected", expected)))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => self.parse_string().map(Value::String),
+            Some('t') => self.parse_word("true", Value::Boolean(true)),
+            Some('f') => self.parse_word("false", Value::Boolean(false)),
+            Some('n') => self.parse_word("null", Value::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => Ok(self.parse_number()),
+            _ => Err(self.error("value expected")),
+        }
+    }
+
+    fn parse_word(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        for expected in word.chars() {
+            if self.next() != Some(expected) {
+                return Err(self.error(&format!("<{}> expected", word)));
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> Value {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+        {
+            self.position += 1;
+        }
+
+        Value::Number(self.chars[start..self.position].iter().collect())
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.next() {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('/') => string.push('/'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => {
+                        let code: String = (0..4).filter_map(|_| self.next()).collect();
+                        let c = u32::from_str_radix(&code, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error("unicode escape not supported"))?;
+                        string.push(c);
+                    }
+                    _ => return Err(self.error("escape sequence not valid")),
+                },
+                Some(c) => string.push(c),
+                None => return Err(self.error("string not terminated")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Value::Array(values));
+        }
+
+        loop {
+            values.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(values)),
+                _ => return Err(self.error("<,> or <]> expected")),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Value::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            members.push((key, self.parse_value()?));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(members)),
+                _ => return Err(self.error("<,> or <}> expected")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::value::{parse, Value};
+
+    #[test]
+    fn parse_value() {
+        let value = parse(" {\"a\": [1.5, \"b\\n\\u0041\", true, null], \"c\": {}} ").unwrap();
+
+        assert_eq!(
+            value.get("a"),
+            Some(&Value::Array(vec![
+                Value::Number("1.5".to_string()),
+                Value::String("b\nA".to_string()),
+                Value::Boolean(true),
+                Value::Null,
+            ]))
+        );
+        assert_eq!(value.get("c"), Some(&Value::Object(Vec::new())));
+        assert_eq!(value.get("d"), None);
+    }
+
+    #[test]
+    fn get_pretty() {
+        let value = parse("{\"a\":[1.5,\"b\"],\"c\":{},\"d\":[],\"e\":null}").unwrap();
+
+        assert_eq!(
+            value.get_pretty(),
+            "{\n  \"a\": [\n    1.5,\n    \"b\"\n  ],\n  \"c\": {},\n  \"d\": [],\n  \"e\": null\n}"
+        );
+        assert_eq!(parse(&value.get_pretty()), Ok(value));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("[1, 2").is_err());
+        assert!(parse("{\"a\" 1}").is_err());
+        assert!(parse("\"unterminated").is_err());
+        assert!(parse("[] []").is_err());
+    }
+}
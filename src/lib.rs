@@ -1,7 +1,126 @@
+#[cfg(feature = "std")]
+pub mod backtest;
+#[cfg(feature = "feed")]
+pub mod exchange_feed;
+#[cfg(feature = "std")]
 pub mod exchange_rate;
+#[cfg(feature = "std")]
+pub mod simulator;
+pub mod synthetic;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 mod algorithm;
+#[cfg(feature = "std")]
+mod checkpoint;
+mod clock;
+#[cfg(feature = "std")]
+mod config;
+mod debug_log;
+#[cfg(feature = "std")]
+mod engine;
+mod error;
+mod hash;
+mod identifier;
+mod json;
+mod memory;
+#[cfg(feature = "std")]
+mod reload;
 mod request;
 mod response;
+#[cfg(feature = "std")]
+mod server;
+#[cfg(feature = "std")]
+mod shutdown;
+#[cfg(feature = "std")]
+mod signal;
+#[cfg(feature = "std")]
+mod state;
+#[cfg(feature = "sqlite")]
+mod storage;
+#[cfg(feature = "std")]
+mod stream;
+mod trace;
 
-pub use crate::exchange_rate::{ExchangeRatePath, IndexMapTrait};
+pub use crate::algorithm::builder::AlgorithmBuilder;
+pub use crate::algorithm::cancellation::{Cancellation, Cancelled};
+pub use crate::algorithm::fee_model::{ExecutionStyle, FeeModel};
+pub use crate::algorithm::paths::Paths;
+pub use crate::algorithm::progress::{Progress, ProgressReporter};
+pub use crate::algorithm::settings::{Backend, PathSemantics, Settings};
+pub use crate::algorithm::slippage_model::SlippageModel;
+pub use crate::algorithm::solver::{BruteForceSolver, FloydWarshallSolver, Solver};
+pub use crate::algorithm::trading_rules::TradingRules;
+pub use crate::algorithm::transfer_model::TransferModel;
+pub use crate::algorithm::{Algorithm, Solution};
+#[cfg(feature = "std")]
+pub use crate::checkpoint::Checkpoints;
+#[cfg(feature = "std")]
+pub use crate::config::Config;
+pub use crate::debug_log::set_debug_logger;
+#[cfg(feature = "std")]
+pub use crate::engine::background::BackgroundEngine;
+#[cfg(feature = "std")]
+pub use crate::engine::exchange_rate_engine::ExchangeRateEngine;
+#[cfg(feature = "std")]
+pub use crate::engine::Engine;
+pub use crate::error::Error;
+#[cfg(feature = "std")]
+pub use crate::exchange_rate::ExchangeRatePath;
+pub use crate::hash::IndexMapTrait;
+pub use crate::identifier::{Currency, Exchange};
+#[cfg(feature = "memory")]
+pub use crate::memory::{take_memory_report, PhaseMemory, TrackingAllocator};
+pub use crate::request::exchange_rate_request::ExchangeRateRequest;
+pub use crate::request::price_update::{PriceUpdate, Quote};
+pub use crate::request::read_hooks::ReadHooks;
+pub use crate::request::{Line, Request};
+pub use crate::response::best_rate_path::BestRatePath;
+pub use crate::response::instruction::Instruction;
+pub use crate::response::phase_timings::PhaseTimings;
+pub use crate::response::unanswered_request::{Reason, UnansweredRequest};
+pub use crate::response::Response;
+#[cfg(feature = "std")]
+pub use crate::server::Server;
+#[cfg(feature = "async")]
+pub use crate::stream::asynchronous::{
+    poll_lines, poll_output, AsyncLines, AsyncOutput, NextOutput, Outputs, PollLines, PollOutput,
+    WriteOutputs,
+};
+#[cfg(feature = "std")]
+pub use crate::stream::metrics::Metrics;
+#[cfg(feature = "std")]
+pub use crate::stream::StreamProcessor;
+#[cfg(feature = "trace")]
+pub use crate::trace::set_trace_sink;
+
+/// Compile-time assertions that the types can cross thread boundaries, for any identifier data
+/// `N` and edge weight `E` that can.
+///
+/// `FloydWarshall` of `floyd-warshall-alg` holds its customized operations as boxed closures
+/// without `Send + Sync`, so it's never stored, only the `Paths` of a `Solver` are.
+#[allow(dead_code)]
+fn assert_thread_safety<N: Send + Sync, E: Send + Sync>() {
+    fn send<T: Send>() {}
+    fn send_sync<T: Send + Sync>() {}
+
+    send_sync::<Request<N, E>>();
+    send_sync::<ExchangeRateRequest<N, E>>();
+    send_sync::<PriceUpdate<N, E>>();
+    send_sync::<Response<N, E>>();
+    send_sync::<Settings<N, E>>();
+    send_sync::<safe_graph::Graph<(u32, u32), E>>();
+    send_sync::<Paths<u32, E>>();
+    send_sync::<Algorithm<N, E, u32>>();
+    send_sync::<Solution<N, E, u32>>();
+    #[cfg(feature = "std")]
+    {
+        send_sync::<Engine<N, E>>();
+        send_sync::<BackgroundEngine<N, E>>();
+        send_sync::<ExchangeRateEngine<N, E>>();
+        // Shared behind a `Mutex`, the storage connection is `Send` only.
+        send::<StreamProcessor<N, E>>();
+    }
+}
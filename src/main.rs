@@ -1,6 +1,971 @@
-use exchange_rate::ExchangeRatePath;
+use exchange_rate::backtest::Backtest;
+#[cfg(feature = "feed")]
+use exchange_rate::exchange_feed::{
+    BinanceConnector, CoinbaseConnector, EcbConnector, KrakenConnector, OverflowPolicy, Recorder,
+    ReplayConnector,
+};
+use exchange_rate::simulator::Simulator;
+use exchange_rate::{
+    set_debug_logger, Backend, Checkpoints, Config, Error, ExchangeRatePath, PathSemantics,
+    ProgressReporter, Server, Settings,
+};
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs::File;
+#[cfg(feature = "feed")]
+use std::fs::OpenOptions;
 use std::io;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Allocations are counted for the `--memory` report.
+#[cfg(feature = "memory")]
+#[global_allocator]
+static ALLOCATOR: exchange_rate::TrackingAllocator = exchange_rate::TrackingAllocator;
+
+/// Prefix of the environment variables giving options, e.g. `EXCHANGE_RATE_MAX_HOPS=3` gives
+/// `--max-hops 3`.
+const ENV_PREFIX: &str = "EXCHANGE_RATE_";
 
 fn main() {
-    ExchangeRatePath::new(io::stdin().lock()).run::<String, f32>();
+    let options = Options::from_env(env::args().collect());
+
+    let mut settings = Settings::<String, f32>::new();
+    let profile = options.get_value("--profile");
+    if profile.is_some() && options.get_value("--config").is_none() {
+        eprintln!("The --profile option needs the --config option!");
+        std::process::exit(1);
+    }
+    if let Some(path) = options.get_value("--config") {
+        let mut config = Config::<String, f32>::new();
+        let result = File::open(path)
+            .map_err(Error::Io)
+            .and_then(|file| config.read_from(&mut BufReader::new(file)))
+            .and_then(|()| profile.map_or(Ok(()), |profile| config.select_profile(profile)));
+
+        if let Err(error) = result {
+            eprintln!(
+                "The config <{}> can not be read, errors: {:?}!",
+                path,
+                error.get_messages()
+            );
+            std::process::exit(1);
+        }
+
+        // Options given on the command line refine the config.
+        config.apply_to(&mut settings);
+    }
+    apply_options(&options, &mut settings);
+    let monitor_arbitrage = options.has_flag("--monitor-arbitrage");
+
+    if options.get_value("--storage").is_some() && !cfg!(feature = "sqlite") {
+        eprintln!("The --storage option needs the crate built with the sqlite feature!");
+        std::process::exit(1);
+    }
+    if options.has_flag("--memory") && !cfg!(feature = "memory") {
+        eprintln!("The --memory option needs the crate built with the memory feature!");
+        std::process::exit(1);
+    }
+
+    let checkpoints = parse_checkpoints(&options);
+
+    if options.has_flag("--debug-log") {
+        set_debug_logger(|record| eprintln!("{}", record));
+    }
+
+    if options.get_arg(1) == Some("simulate") {
+        simulate(&options);
+        return;
+    }
+
+    if options.get_arg(1) == Some("backtest") {
+        backtest(&options, settings);
+        return;
+    }
+
+    if options.get_arg(1) == Some("serve") {
+        serve(&options, settings, monitor_arbitrage, checkpoints);
+        return;
+    }
+
+    let mut exchange_rate_path = ExchangeRatePath::<_, String, f32>::with_types(io::stdin().lock());
+    if options.get_arg(1) == Some("export-state") {
+        if let Err(error) = exchange_rate_path.export_state(&settings) {
+            eprintln!(
+                "Errors occurred while processing input lines, errors: {:?}!",
+                error.get_messages()
+            );
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // The imported state is watched, as there is nothing to answer in it yet.
+    let state_path = match options.get_arg(1) {
+        Some("import-state") => match options.get_arg(2).filter(|path| !path.starts_with("--")) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => {
+                eprintln!("The import-state command needs the path of the state!");
+                std::process::exit(1);
+            }
+        },
+        _ => None,
+    };
+
+    exchange_rate_path.set_include_summary(options.has_flag("--summary"));
+    exchange_rate_path.set_include_timings(options.has_flag("--timings"));
+    exchange_rate_path.set_include_stamp(options.has_flag("--stamp"));
+    exchange_rate_path.set_dump_indexes(options.has_flag("--dump-indexes"));
+    exchange_rate_path.set_find_arbitrage(options.has_flag("--arbitrage"));
+    exchange_rate_path.set_find_triangular_arbitrage(options.has_flag("--triangular-arbitrage"));
+    exchange_rate_path.set_find_all_arbitrages(options.has_flag("--all-arbitrages"));
+    exchange_rate_path.set_arbitrage_json(options.has_flag("--arbitrage-json"));
+    let rate_alerts = parse_rate_alerts(&options);
+    exchange_rate_path.set_watch(
+        options.has_flag("--watch")
+            || monitor_arbitrage
+            || !rate_alerts.is_empty()
+            || state_path.is_some(),
+    );
+    exchange_rate_path.set_monitor_arbitrage(monitor_arbitrage);
+    for (pair, threshold) in rate_alerts {
+        exchange_rate_path.add_rate_alert(pair, threshold);
+    }
+    exchange_rate_path.set_exit_on_signal(true);
+    exchange_rate_path.set_snapshot_path(options.get_value("--snapshot").map(PathBuf::from));
+    exchange_rate_path.set_checkpoints(checkpoints);
+    exchange_rate_path.set_journal_path(options.get_value("--journal").map(PathBuf::from));
+    exchange_rate_path.set_history(options.has_flag("--history"));
+    #[cfg(feature = "sqlite")]
+    exchange_rate_path.set_storage_path(options.get_value("--storage").map(PathBuf::from));
+    exchange_rate_path.set_state_path(state_path);
+    if let Err(error) = exchange_rate_path.run_customized(&settings) {
+        eprintln!(
+            "The application can not be run, errors: {:?}!",
+            error.get_messages()
+        );
+        std::process::exit(1);
+    }
+
+    #[cfg(feature = "memory")]
+    if options.has_flag("--memory") {
+        for phase_memory in exchange_rate::take_memory_report() {
+            eprint!("{}", phase_memory.get_output());
+        }
+    }
+}
+
+/// Write price update lines of a simulated market to the standard output.
+fn simulate(options: &Options) {
+    let value_or = |option, default| options.get_value(option).unwrap_or(default);
+    let updates: usize = parse_option_value("--updates", value_or("--updates", "1000"));
+    let exchanges = parse_option_value("--exchanges", value_or("--exchanges", "3"));
+    let currencies = parse_option_value("--currencies", value_or("--currencies", "5"));
+    let density = parse_option_value("--density", value_or("--density", "0.5"));
+    let seed = parse_option_value("--seed", value_or("--seed", "0"));
+    let volatility = parse_option_value("--volatility", value_or("--volatility", "0.001"));
+    let arbitrage_probability = parse_option_value(
+        "--arbitrage-probability",
+        value_or("--arbitrage-probability", "0"),
+    );
+
+    let simulator = Simulator::new(exchanges, currencies, density, seed)
+        .with_volatility(volatility)
+        .with_arbitrage_probability(arbitrage_probability);
+
+    let mut stdout = io::stdout().lock();
+    for line in simulator.take(updates) {
+        if writeln!(stdout, "{}", line).is_err() {
+            // The reader is gone.
+            return;
+        }
+    }
+}
+
+/// Replay the historical input of the path and write statistics of rates of its standing
+/// requests.
+fn backtest(options: &Options, settings: Settings<String, f32>) {
+    let path = match options.get_arg(2).filter(|path| !path.starts_with("--")) {
+        Some(path) => path,
+        None => {
+            eprintln!("The backtest command needs the path of the historical input!");
+            std::process::exit(1);
+        }
+    };
+    let cadence: i64 =
+        parse_option_value("--cadence", options.get_value("--cadence").unwrap_or("60"));
+
+    let backtest = Backtest::new(settings, chrono::Duration::seconds(cadence));
+    let result = File::open(path)
+        .map_err(Error::Io)
+        .and_then(|file| backtest.run(&mut BufReader::new(file)));
+    match result {
+        Ok(report) => print!("{}", report.get_output()),
+        Err(error) => {
+            eprintln!("The backtest of <{}> failed, errors: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Serve clients connecting over the network until the process is stopped.
+fn serve(
+    options: &Options,
+    settings: Settings<String, f32>,
+    monitor_arbitrage: bool,
+    checkpoints: Checkpoints,
+) {
+    let tcp_address = options.get_value("--tcp");
+    let uds_path = options.get_value("--uds");
+    let http_address = options.get_value("--http");
+    let metrics_address = options.get_value("--metrics");
+
+    // A replay alone is run through to its end, e.g. to check the output it causes.
+    let replay_path = options.get_value("--replay");
+    if tcp_address.is_none()
+        && uds_path.is_none()
+        && http_address.is_none()
+        && replay_path.is_none()
+    {
+        eprintln!(
+            "The serve mode needs the --tcp <address>, --uds <path>, --http <address> or --replay <path> option!"
+        );
+        std::process::exit(1);
+    }
+
+    if uds_path.is_some() && !cfg!(unix) {
+        eprintln!("The --uds option is supported on Unix platforms only!");
+        std::process::exit(1);
+    }
+
+    if http_address.is_some() && !cfg!(feature = "http") {
+        eprintln!("The --http option needs the crate built with the http feature!");
+        std::process::exit(1);
+    }
+
+    let has_feed = ["--kraken", "--binance", "--coinbase", "--ecb", "--replay"]
+        .iter()
+        .any(|option| options.get_value(option).is_some());
+    if (has_feed || options.get_value("--record").is_some()) && !cfg!(feature = "feed") {
+        eprintln!(
+            "The --kraken, --binance, --coinbase, --ecb, --record and --replay options need the crate built with the feed feature!"
+        );
+        std::process::exit(1);
+    }
+
+    let mut server = Server::<String, f32>::new(settings);
+    server.set_monitor_arbitrage(monitor_arbitrage);
+    for (pair, threshold) in parse_rate_alerts(options) {
+        server.add_rate_alert(pair, threshold);
+    }
+    server.set_arbitrage_json(options.has_flag("--arbitrage-json"));
+    server.set_include_stamp(options.has_flag("--stamp"));
+    server.set_isolated_sessions(options.has_flag("--isolated"));
+    if let Some(max) = options.get_value("--max-updates-per-second") {
+        server
+            .set_max_updates_per_second(Some(parse_option_value("--max-updates-per-second", max)));
+    }
+    if let Some(max) = options.get_value("--max-in-flight-requests") {
+        server
+            .set_max_in_flight_requests(Some(parse_option_value("--max-in-flight-requests", max)));
+    }
+    if let Some(max) = options.get_value("--max-graph-size") {
+        server.set_max_graph_size(Some(parse_option_value("--max-graph-size", max)));
+    }
+    if let Some(seconds) = options.get_value("--aggregation-interval") {
+        server.set_aggregation_interval(Some(chrono::Duration::seconds(parse_option_value(
+            "--aggregation-interval",
+            seconds,
+        ))));
+    }
+    if let Some(deadline) = options.get_value("--compute-deadline") {
+        server.set_compute_deadline(Some(Duration::from_millis(parse_option_value(
+            "--compute-deadline",
+            deadline,
+        ))));
+    }
+    if let Some(path) = options.get_value("--tokens") {
+        let result = File::open(path)
+            .map_err(Error::Io)
+            .and_then(|file| server.read_tokens_from(&mut BufReader::new(file)));
+
+        if let Err(error) = result {
+            eprintln!(
+                "The tokens <{}> can not be read, errors: {:?}!",
+                path,
+                error.get_messages()
+            );
+            std::process::exit(1);
+        }
+    }
+    server.set_history(options.has_flag("--history"));
+    if let Some(path) = options.get_value("--journal") {
+        if let Err(error) = server.open_journal(Path::new(path)) {
+            eprintln!(
+                "The journal <{}> can not be opened, errors: {:?}!",
+                path,
+                error.get_messages()
+            );
+            std::process::exit(1);
+        }
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = options.get_value("--storage") {
+        if let Err(error) = server.open_storage(Path::new(path)) {
+            eprintln!(
+                "The storage <{}> can not be opened, error: {}!",
+                path, error
+            );
+            std::process::exit(1);
+        }
+    }
+    #[cfg(feature = "feed")]
+    add_connectors(options, &mut server);
+    server.set_snapshot_path(options.get_value("--snapshot").map(PathBuf::from));
+    server.set_checkpoints(checkpoints);
+    server.exit_on_signal();
+    server.checkpoint_periodically();
+    if let Some(path) = options.get_value("--config") {
+        // Options given on the command line and the environment refine the reloaded config as
+        // well.
+        let profile = options.get_value("--profile").map(String::from);
+        let options = options.clone();
+        server.reload_config_on_change(
+            PathBuf::from(path),
+            profile,
+            Settings::new(),
+            move |settings| apply_options(&options, settings),
+        );
+    }
+
+    let exit_on_error = |address: &str, result: std::io::Result<()>| {
+        if let Err(error) = result {
+            eprintln!(
+                "The server can not listen on <{}>, error: {}!",
+                address, error
+            );
+            std::process::exit(1);
+        }
+    };
+
+    // All modes share the server, so prices streamed over TCP answer HTTP requests and back.
+    let (server, exit_on_error) = (&server, &exit_on_error);
+    std::thread::scope(|scope| {
+        if let Some(address) = tcp_address {
+            scope.spawn(move || exit_on_error(address, server.serve_tcp(address)));
+        }
+
+        #[cfg(unix)]
+        if let Some(path) = uds_path {
+            scope.spawn(move || exit_on_error(path, server.serve_uds(path)));
+        }
+
+        if let Some(address) = metrics_address {
+            scope.spawn(move || exit_on_error(address, server.serve_metrics(address)));
+        }
+
+        #[cfg(feature = "http")]
+        if let Some(address) = http_address {
+            scope.spawn(move || exit_on_error(address, server.serve_http(address)));
+        }
+
+        #[cfg(feature = "feed")]
+        if has_feed {
+            scope.spawn(move || {
+                if let Err(error) = server.run_feeds(&mut io::stdout()) {
+                    eprintln!("The feeds can not be run, error: {}!", error);
+                    std::process::exit(1);
+                }
+            });
+        }
+    });
+}
+
+/// Add connectors of the live exchange feeds given by command line arguments to the server.
+#[cfg(feature = "feed")]
+fn add_connectors(options: &Options, server: &mut Server<String, f32>) {
+    if let Some(address) = options.get_value("--kraken") {
+        let pairs = get_connector_pairs(options, "--kraken-pairs")
+            .into_iter()
+            .map(str::to_uppercase)
+            .collect();
+        server.add_connector(Box::new(KrakenConnector::new(address.to_string(), pairs)));
+    }
+
+    if let Some(address) = options.get_value("--binance") {
+        let pairs = get_connector_pairs(options, "--binance-pairs")
+            .into_iter()
+            .map(|pair| parse_trade("--binance-pairs", pair))
+            .collect();
+        let mut connector = BinanceConnector::new(address.to_string(), pairs);
+        if let Some(interval) = options.get_value("--binance-interval") {
+            connector.set_interval(Duration::from_millis(parse_option_value(
+                "--binance-interval",
+                interval,
+            )));
+        }
+        server.add_connector(Box::new(connector));
+    }
+
+    if let Some(address) = options.get_value("--coinbase") {
+        let pairs = get_connector_pairs(options, "--coinbase-pairs")
+            .into_iter()
+            .map(|pair| parse_trade("--coinbase-pairs", pair))
+            .collect();
+        server.add_connector(Box::new(CoinbaseConnector::new(address.to_string(), pairs)));
+    }
+
+    if let Some(address) = options.get_value("--ecb") {
+        // All the currencies without the option.
+        let currencies = options
+            .get_value("--ecb-currencies")
+            .map(|currencies| currencies.split(',').map(str::to_uppercase).collect())
+            .unwrap_or_default();
+        let mut connector = EcbConnector::new(address.to_string(), currencies);
+        if let Some(interval) = options.get_value("--ecb-interval") {
+            connector.set_interval(Duration::from_secs(parse_option_value(
+                "--ecb-interval",
+                interval,
+            )));
+        }
+        server.add_connector(Box::new(connector));
+    }
+
+    if let Some(path) = options.get_value("--replay") {
+        let mut connector = ReplayConnector::new(PathBuf::from(path));
+        if let Some(speed) = options.get_value("--replay-speed") {
+            // Zero replays without any delay.
+            let speed: f64 = parse_option_value("--replay-speed", speed);
+            connector.set_speed(Some(speed).filter(|speed| *speed > 0.0));
+        }
+        server.add_connector(Box::new(connector));
+    }
+
+    if let Some(path) = options.get_value("--record") {
+        match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => server.set_feed_recorder(Some(Recorder::new(Box::new(file)))),
+            Err(error) => {
+                eprintln!(
+                    "The recording <{}> can not be opened, error: {}!",
+                    path, error
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(aliases) = options.get_value("--symbol-alias") {
+        for (alias, symbol) in parse_pairs("--symbol-alias", aliases) {
+            server.add_symbol_alias(alias.to_uppercase(), symbol.to_uppercase());
+        }
+    }
+
+    if let Some(timeout) = options.get_value("--feed-quarantine-timeout") {
+        // Zero relies on quotes of quiet feeds forever.
+        let timeout: u64 = parse_option_value("--feed-quarantine-timeout", timeout);
+        server.set_feed_quarantine_timeout(
+            Some(Duration::from_secs(timeout)).filter(|timeout| !timeout.is_zero()),
+        );
+    }
+
+    if let Some(capacity) = options.get_value("--feed-queue-capacity") {
+        server.set_feed_queue_capacity(parse_option_value("--feed-queue-capacity", capacity));
+    }
+
+    if let Some(label) = options.get_value("--feed-overflow") {
+        match OverflowPolicy::parse(label) {
+            Some(overflow_policy) => server.set_feed_overflow_policy(overflow_policy),
+            None => {
+                eprintln!(
+                    "The value <{}> of the option --feed-overflow is not valid!",
+                    label
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(timeout) = options.get_value("--feed-heartbeat-timeout") {
+        // Zero waits forever.
+        let timeout: u64 = parse_option_value("--feed-heartbeat-timeout", timeout);
+        server.set_feed_heartbeat_timeout(
+            Some(Duration::from_secs(timeout)).filter(|timeout| !timeout.is_zero()),
+        );
+    }
+}
+
+/// Get comma separated pairs of the connector, exit with an error message if the option is
+/// missing.
+#[cfg(feature = "feed")]
+fn get_connector_pairs<'a>(options: &'a Options, option: &str) -> Vec<&'a str> {
+    match options.get_value(option) {
+        Some(pairs) => pairs.split(',').collect(),
+        None => {
+            eprintln!("The connector needs the {} <pairs> option!", option);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parse the checkpoint policy of the snapshot, the options need the snapshot path.
+fn parse_checkpoints(options: &Options) -> Checkpoints {
+    let mut checkpoints = Checkpoints::new();
+    if let Some(minutes) = options.get_value("--checkpoint-interval") {
+        let minutes: u64 = parse_option_value("--checkpoint-interval", minutes);
+        checkpoints.set_interval(Some(Duration::from_secs(minutes * 60)));
+    }
+    if let Some(updates) = options.get_value("--checkpoint-updates") {
+        checkpoints.set_updates(Some(parse_option_value("--checkpoint-updates", updates)));
+    }
+    if let Some(retention) = options.get_value("--checkpoint-retention") {
+        checkpoints.set_retention(parse_option_value("--checkpoint-retention", retention));
+    }
+
+    if checkpoints.is_enabled() && options.get_value("--snapshot").is_none() {
+        eprintln!(
+            "The --checkpoint-interval and --checkpoint-updates options need the --snapshot <path> option!"
+        );
+        std::process::exit(1);
+    }
+
+    checkpoints
+}
+
+/// Parse comma separated
+/// `<source_exchange>:<source_currency>:<destination_exchange>:<destination_currency>:<threshold>`
+/// rate alerts, exit with an error message if any is not valid.
+fn parse_rate_alerts(options: &Options) -> Vec<((String, String, String, String), f32)> {
+    let value = match options.get_value("--rate-alert") {
+        Some(value) => value,
+        None => return Vec::new(),
+    };
+
+    value
+        .split(',')
+        .map(|alert| {
+            let items: Vec<&str> = alert.split(':').collect();
+            let threshold = items.get(4).and_then(|threshold| threshold.parse().ok());
+            match (items.len(), threshold) {
+                (5, Some(threshold)) => (
+                    (
+                        items[0].to_uppercase(),
+                        items[1].to_uppercase(),
+                        items[2].to_uppercase(),
+                        items[3].to_uppercase(),
+                    ),
+                    threshold,
+                ),
+                _ => {
+                    eprintln!(
+                        "The value <{}> of the option --rate-alert is not valid, <source_exchange>:<source_currency>:<destination_exchange>:<destination_currency>:<threshold> alerts are expected!",
+                        alert
+                    );
+                    std::process::exit(1);
+                }
+            }
+        })
+        .collect()
+}
+
+/// Apply the settings options of the command line and the environment to the `Settings`,
+/// refining the config.
+fn apply_options(options: &Options, settings: &mut Settings<String, f32>) {
+    if let Some(min_profit_factor) = options.get_value("--min-profit-factor") {
+        settings
+            .set_min_profit_factor(parse_option_value("--min-profit-factor", min_profit_factor));
+    }
+    if let Some(max_cycle_length) = options.get_value("--max-cycle-length") {
+        settings.set_max_cycle_length(parse_option_value("--max-cycle-length", max_cycle_length));
+    }
+    if let Some(fee) = options.get_value("--trade-fee") {
+        settings
+            .get_fee_model_mut()
+            .set_default_fee(parse_option_value("--trade-fee", fee));
+    }
+    if let Some(exchange_fees) = options.get_value("--exchange-fee") {
+        for (exchange, fee) in parse_pairs("--exchange-fee", exchange_fees) {
+            settings.get_fee_model_mut().set_exchange_fee(
+                exchange.to_uppercase(),
+                parse_option_value("--exchange-fee", fee),
+            );
+        }
+    }
+    if let Some(fee) = options.get_value("--maker-fee") {
+        settings
+            .get_fee_model_mut()
+            .set_default_maker_fee(Some(parse_option_value("--maker-fee", fee)));
+    }
+    if let Some(exchange_fees) = options.get_value("--exchange-maker-fee") {
+        for (exchange, fee) in parse_pairs("--exchange-maker-fee", exchange_fees) {
+            settings.get_fee_model_mut().set_exchange_maker_fee(
+                exchange.to_uppercase(),
+                parse_option_value("--exchange-maker-fee", fee),
+            );
+        }
+    }
+    if let Some(transfer_penalty) = options.get_value("--transfer-penalty") {
+        settings.set_transfer_penalty(parse_option_value("--transfer-penalty", transfer_penalty));
+    }
+    if let Some(quote_half_life) = options.get_value("--quote-half-life") {
+        settings.set_quote_half_life(Some(parse_option_value(
+            "--quote-half-life",
+            quote_half_life,
+        )));
+    }
+    if let Some(max_hops) = options.get_value("--max-hops") {
+        settings.set_max_hops(Some(parse_option_value("--max-hops", max_hops)));
+    }
+    if let Some(epsilon) = options.get_value("--comparison-epsilon") {
+        settings.set_comparison_epsilon(parse_option_value("--comparison-epsilon", epsilon));
+    }
+    if let Some(backend) = options.get_value("--backend") {
+        match Backend::parse(backend) {
+            Some(backend) => settings.set_backend(backend),
+            None => {
+                eprintln!(
+                    "The value <{}> of the option --backend is not valid!",
+                    backend
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(semantics) = options.get_value("--path-semantics") {
+        match PathSemantics::parse(semantics) {
+            Some(semantics) => settings.set_path_semantics(semantics),
+            None => {
+                eprintln!(
+                    "The value <{}> of the option --path-semantics is not valid!",
+                    semantics
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(keep_loops) = options.get_flag("--keep-loops") {
+        settings.set_discard_loops(!keep_loops);
+    }
+    if options.has_flag("--progress") {
+        settings.set_progress_reporter(Some(ProgressReporter::new(
+            Duration::from_secs(1),
+            |progress| eprint!("{}", progress.get_output()),
+        )));
+    }
+    if let Some(pairs) = options.get_value("--disable-pair") {
+        for (exchange, pair) in parse_pairs("--disable-pair", pairs) {
+            let (base, quote) = parse_trade("--disable-pair", pair);
+            settings.get_trading_rules_mut().set_pair_enabled(
+                exchange.to_uppercase(),
+                base,
+                quote,
+                false,
+            );
+        }
+    }
+    if let Some(min_amounts) = options.get_value("--min-trade-amount") {
+        for (exchange, min_amount) in parse_pairs("--min-trade-amount", min_amounts) {
+            let (trade, amount) = min_amount.split_once(':').unwrap_or((min_amount, ""));
+            let (source, destination) = parse_trade("--min-trade-amount", trade);
+            settings.get_trading_rules_mut().set_min_trade_amount(
+                exchange.to_uppercase(),
+                source,
+                destination,
+                parse_option_value("--min-trade-amount", amount),
+            );
+        }
+    }
+    if let Some(fee) = options.get_value("--transfer-fee") {
+        settings
+            .get_transfer_model_mut()
+            .set_default_fee(parse_option_value("--transfer-fee", fee));
+    }
+    if let Some(currency_fees) = options.get_value("--currency-transfer-fee") {
+        for (currency, fee) in parse_pairs("--currency-transfer-fee", currency_fees) {
+            settings.get_transfer_model_mut().set_currency_fee(
+                currency.to_uppercase(),
+                parse_option_value("--currency-transfer-fee", fee),
+            );
+        }
+    }
+    if let Some(latency) = options.get_value("--transfer-latency") {
+        settings
+            .get_transfer_model_mut()
+            .set_default_latency(parse_option_value("--transfer-latency", latency));
+    }
+    if let Some(currency_latencies) = options.get_value("--currency-transfer-latency") {
+        for (currency, latency) in parse_pairs("--currency-transfer-latency", currency_latencies) {
+            settings.get_transfer_model_mut().set_currency_latency(
+                currency.to_uppercase(),
+                parse_option_value("--currency-transfer-latency", latency),
+            );
+        }
+    }
+    if let Some(currencies) = options.get_value("--disable-transfer") {
+        for currency in currencies.split(',') {
+            settings
+                .get_transfer_model_mut()
+                .set_transfer_enabled(currency.to_uppercase(), false);
+        }
+    }
+    if let Some(path) = options.get_value("--transfer-fee-schedule") {
+        let result = File::open(path).map_err(Error::Io).and_then(|file| {
+            settings
+                .get_transfer_model_mut()
+                .read_schedule_from(&mut BufReader::new(file))
+        });
+
+        if let Err(error) = result {
+            eprintln!(
+                "The transfer fee schedule <{}> can not be read, errors: {:?}!",
+                path,
+                error.get_messages()
+            );
+            std::process::exit(1);
+        }
+    }
+    if let Some(depth) = options.get_value("--liquidity") {
+        settings
+            .get_slippage_model_mut()
+            .set_default_depth(Some(parse_option_value("--liquidity", depth)));
+    }
+    if let Some(exchange_depths) = options.get_value("--exchange-liquidity") {
+        for (exchange, depth) in parse_pairs("--exchange-liquidity", exchange_depths) {
+            settings.get_slippage_model_mut().set_exchange_depth(
+                exchange.to_uppercase(),
+                parse_option_value("--exchange-liquidity", depth),
+            );
+        }
+    }
+    if let Some(alert_debounce) = options.get_value("--alert-debounce") {
+        settings.set_alert_debounce(parse_option_value("--alert-debounce", alert_debounce));
+    }
+    if let Some(funding) = options.get_value("--funding") {
+        settings.set_funding(parse_funding(funding));
+    }
+}
+
+/// Options of the command line over the ones of `EXCHANGE_RATE_*` environment variables.
+///
+/// An option is taken from the command line if it's given there, from the environment otherwise,
+/// so the precedence is defaults < config < environment < command line. Environment variable
+/// names map onto the options lowercase with dashes, e.g. `EXCHANGE_RATE_MAX_HOPS=3` gives
+/// `--max-hops 3`. A flag is set by `true`, `1`, `yes` or an empty value and unset by `false`,
+/// `0` or `no`, e.g. `EXCHANGE_RATE_KEEP_LOOPS=false` turns `--keep-loops` of the config off.
+#[derive(Clone, Debug)]
+struct Options {
+    args: Vec<String>,
+    env: HashMap<String, String>,
+}
+
+impl Options {
+    /// Create `Options` of the command line arguments and the environment variables, variables
+    /// without the prefix or not valid Unicode are ignored.
+    fn new<V>(args: Vec<String>, vars: V) -> Self
+    where
+        V: IntoIterator<Item = (OsString, OsString)>,
+    {
+        let env = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let name = name.into_string().ok()?;
+                let option = name
+                    .strip_prefix(ENV_PREFIX)
+                    .filter(|option| !option.is_empty())?;
+                Some((
+                    format!("--{}", option.to_lowercase().replace('_', "-")),
+                    value.into_string().ok()?,
+                ))
+            })
+            .collect();
+
+        Self { args, env }
+    }
+
+    /// Create `Options` of the command line arguments and the environment of the process.
+    fn from_env(args: Vec<String>) -> Self {
+        Self::new(args, env::vars_os())
+    }
+
+    /// Get the command line argument at the position, e.g. the command at 1.
+    fn get_arg(&self, position: usize) -> Option<&str> {
+        self.args.get(position).map(String::as_str)
+    }
+
+    /// Check whether the flag is set, see `get_flag`.
+    fn has_flag(&self, flag: &str) -> bool {
+        self.get_flag(flag).unwrap_or(false)
+    }
+
+    /// Get whether the flag is set on the command line or by the environment, `None` if it's
+    /// given by neither. Exit with an error message if the environment value isn't a boolean.
+    fn get_flag(&self, flag: &str) -> Option<bool> {
+        if self.args.iter().any(|arg| arg == flag) {
+            return Some(true);
+        }
+
+        self.env
+            .get(flag)
+            .map(|value| match value.to_lowercase().as_str() {
+                "" | "true" | "1" | "yes" => true,
+                "false" | "0" | "no" => false,
+                _ => {
+                    eprintln!(
+                        "The value <{}> of the option {} is not valid, a boolean is expected!",
+                        value, flag
+                    );
+                    std::process::exit(1);
+                }
+            })
+    }
+
+    /// Get value following the option among command line arguments, or the environment value.
+    fn get_value(&self, option: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .position(|arg| arg == option)
+            .and_then(|position| self.args.get(position + 1))
+            .or_else(|| self.env.get(option))
+            .map(String::as_str)
+    }
+}
+
+/// Parse value of the option, exit with an error message if it can't be parsed.
+fn parse_option_value<T: FromStr>(option: &str, value: &str) -> T {
+    value.parse::<T>().unwrap_or_else(|_| {
+        eprintln!(
+            "The value <{}> of the option {} is not valid!",
+            value, option
+        );
+        std::process::exit(1);
+    })
+}
+
+/// Parse comma separated `<exchange>:<currency>` funding nodes, `ANY` matches any identifier.
+fn parse_funding(value: &str) -> Vec<(Option<String>, Option<String>)> {
+    let parse_item = |item: &str| {
+        let item = item.to_uppercase();
+        if item == "ANY" {
+            None
+        } else {
+            Some(item)
+        }
+    };
+
+    parse_pairs("--funding", value)
+        .into_iter()
+        .map(|(exchange, currency)| (parse_item(exchange), parse_item(currency)))
+        .collect()
+}
+
+/// Parse comma separated `<key>:<value>` pairs of the option, exit with an error message if any
+/// pair is not valid.
+fn parse_pairs<'a>(option: &str, value: &'a str) -> Vec<(&'a str, &'a str)> {
+    value
+        .split(',')
+        .map(|pair| {
+            pair.split_once(':').unwrap_or_else(|| {
+                eprintln!(
+                    "The value <{}> of the option {} is not valid, <key>:<value> pairs are expected!",
+                    pair, option
+                );
+                std::process::exit(1);
+            })
+        })
+        .collect()
+}
+
+/// Parse `<source_currency>/<destination_currency>` trade of the option, exit with an error
+/// message if it's not valid.
+fn parse_trade(option: &str, value: &str) -> (String, String) {
+    match value.split_once('/') {
+        Some((source, destination)) => (source.to_uppercase(), destination.to_uppercase()),
+        None => {
+            eprintln!(
+                "The value <{}> of the option {} is not valid, <currency>/<currency> is expected!",
+                value, option
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{apply_options, Options};
+    use exchange_rate::Settings;
+    use std::ffi::OsString;
+
+    fn options(args: &[&str], vars: &[(&str, &str)]) -> Options {
+        Options::new(
+            args.iter().map(|arg| arg.to_string()).collect(),
+            vars.iter()
+                .map(|(name, value)| (OsString::from(name), OsString::from(value))),
+        )
+    }
+
+    #[test]
+    fn options_of_environment() {
+        let options = options(
+            &["exchange-rate", "serve", "--max-hops", "4", "--stamp"],
+            &[
+                ("EXCHANGE_RATE_MAX_HOPS", "3"),
+                ("EXCHANGE_RATE_TRADE_FEE", "0.001"),
+                ("EXCHANGE_RATE_WATCH", ""),
+                ("EXCHANGE_RATE_STAMP", "false"),
+                ("EXCHANGE_RATE_KEEP_LOOPS", "false"),
+                ("EXCHANGE_RATE_", "1"),
+                ("OTHER_MAX_HOPS", "5"),
+            ],
+        );
+
+        // Test that the command line wins over the environment.
+        assert_eq!(options.get_value("--max-hops"), Some("4"));
+        assert!(options.has_flag("--stamp"));
+
+        // Test that the environment gives options missing on the command line.
+        assert_eq!(options.get_value("--trade-fee"), Some("0.001"));
+        assert_eq!(options.get_flag("--watch"), Some(true));
+        assert_eq!(options.get_flag("--keep-loops"), Some(false));
+        assert_eq!(options.get_flag("--history"), None);
+        assert_eq!(options.get_arg(1), Some("serve"));
+        assert_eq!(options.get_value("--"), None);
+    }
+
+    #[test]
+    fn apply_options_over_config() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.set_discard_loops(false);
+        settings.set_max_hops(Some(2));
+
+        // Test that the environment refines the config and turns its flag off.
+        apply_options(
+            &options(
+                &["exchange-rate"],
+                &[
+                    ("EXCHANGE_RATE_KEEP_LOOPS", "false"),
+                    ("EXCHANGE_RATE_MAX_HOPS", "3"),
+                ],
+            ),
+            &mut settings,
+        );
+        assert!(settings.get_discard_loops());
+        assert_eq!(settings.get_max_hops(), Some(3));
+
+        // Test that the command line refines the environment.
+        apply_options(
+            &options(
+                &["exchange-rate", "--keep-loops", "--max-hops", "4"],
+                &[
+                    ("EXCHANGE_RATE_KEEP_LOOPS", "false"),
+                    ("EXCHANGE_RATE_MAX_HOPS", "3"),
+                ],
+            ),
+            &mut settings,
+        );
+        assert!(!settings.get_discard_loops());
+        assert_eq!(settings.get_max_hops(), Some(4));
+    }
 }
@@ -0,0 +1,232 @@
+//! Peak memory and allocation counts of the pipeline phases.
+//!
+//! With the `memory` feature, the `TrackingAllocator` installed as the global allocator counts
+//! allocations and bytes allocated, and each phase records the peak bytes allocated and the
+//! allocations made while it runs. Phases run more than once, e.g. a graph built again for maker
+//! orders, are added up into the same `PhaseMemory`. Allocations of all threads are counted.
+//! Without the feature phases cost nothing.
+
+#[cfg(feature = "memory")]
+use std::alloc::{GlobalAlloc, Layout, System};
+#[cfg(feature = "memory")]
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+#[cfg(feature = "memory")]
+use std::sync::{Mutex, PoisonError};
+
+/// Bytes allocated at the moment.
+#[cfg(feature = "memory")]
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Most bytes allocated at once since the innermost running phase started.
+#[cfg(feature = "memory")]
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Count of all allocations.
+#[cfg(feature = "memory")]
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Phases recorded since the last report, in the order they first finished.
+#[cfg(feature = "memory")]
+static PHASES: Mutex<Vec<PhaseMemory>> = Mutex::new(Vec::new());
+
+/// `TrackingAllocator` structure.
+///
+/// Global allocator passing allocations to the system one and counting them, e.g.
+/// `#[global_allocator] static ALLOCATOR: TrackingAllocator = TrackingAllocator;`.
+#[cfg(feature = "memory")]
+pub struct TrackingAllocator;
+
+#[cfg(feature = "memory")]
+impl TrackingAllocator {
+    fn add(size: usize) {
+        let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "memory")]
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            Self::add(layout.size());
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = System.realloc(pointer, layout, new_size);
+        if !new_pointer.is_null() {
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            Self::add(new_size);
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        new_pointer
+    }
+}
+
+/// `PhaseMemory` structure.
+///
+/// Memory used by a phase: the most bytes allocated at once while it ran, including the ones
+/// allocated before it started, and the count of allocations it made.
+#[cfg(feature = "memory")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseMemory {
+    phase: &'static str,
+    peak_bytes: usize,
+    allocations: u64,
+}
+
+#[cfg(feature = "memory")]
+impl PhaseMemory {
+    /// Get name of the phase, e.g. `construct_graph`.
+    pub fn get_phase(&self) -> &str {
+        self.phase
+    }
+
+    /// Get the most bytes allocated at once while the phase ran.
+    pub fn get_peak_bytes(&self) -> usize {
+        self.peak_bytes
+    }
+
+    /// Get count of allocations made while the phase ran.
+    pub fn get_allocations(&self) -> u64 {
+        self.allocations
+    }
+
+    /// Get printable output representing the PhaseMemory.
+    ///
+    /// # Format
+    ///
+    /// PHASE_MEMORY <phase> <peak_bytes> <allocations>
+    pub fn get_output(&self) -> String {
+        format!(
+            "PHASE_MEMORY <{}> <{}> <{}>\n",
+            self.phase, self.peak_bytes, self.allocations
+        )
+    }
+}
+
+/// Take the `PhaseMemory` of all phases recorded since the last report.
+///
+/// They're empty unless the `TrackingAllocator` is the global allocator.
+#[cfg(feature = "memory")]
+pub fn take_memory_report() -> Vec<PhaseMemory> {
+    std::mem::take(&mut *PHASES.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// `Phase` structure.
+///
+/// Guard of a phase recorded once it's dropped.
+#[cfg(feature = "memory")]
+pub(crate) struct Phase {
+    name: &'static str,
+    outer_peak: usize,
+    allocations: u64,
+}
+
+/// `Phase` structure.
+///
+/// Guard of a phase, doing nothing without the `memory` feature.
+#[cfg(not(feature = "memory"))]
+pub(crate) struct Phase;
+
+/// Start the phase of the name.
+#[cfg(feature = "memory")]
+pub(crate) fn phase(name: &'static str) -> Phase {
+    // The peak of the phase starts at the bytes allocated now, the outer one is restored later.
+    let outer_peak = PEAK.swap(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+
+    Phase {
+        name,
+        outer_peak,
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+    }
+}
+
+/// Start the phase of the name.
+#[cfg(not(feature = "memory"))]
+pub(crate) fn phase(_name: &'static str) -> Phase {
+    Phase
+}
+
+#[cfg(feature = "memory")]
+impl Drop for Phase {
+    /// Finish the phase and record its memory.
+    fn drop(&mut self) {
+        let peak_bytes = PEAK.fetch_max(self.outer_peak, Ordering::Relaxed);
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - self.allocations;
+
+        let mut phases = PHASES.lock().unwrap_or_else(PoisonError::into_inner);
+        match phases.iter_mut().find(|phase| phase.phase == self.name) {
+            Some(phase) => {
+                phase.peak_bytes = phase.peak_bytes.max(peak_bytes);
+                phase.allocations += allocations;
+            }
+            None => phases.push(PhaseMemory {
+                phase: self.name,
+                peak_bytes,
+                allocations,
+            }),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "memory"))]
+mod tests {
+    use crate::memory::{phase, take_memory_report, PhaseMemory, TrackingAllocator};
+
+    #[global_allocator]
+    static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+    #[test]
+    fn get_output() {
+        let phase_memory = PhaseMemory {
+            phase: "construct_graph",
+            peak_bytes: 4096,
+            allocations: 12,
+        };
+
+        assert_eq!(
+            phase_memory.get_output(),
+            "PHASE_MEMORY <construct_graph> <4096> <12>\n"
+        );
+    }
+
+    #[test]
+    fn nested_phases() {
+        {
+            let _outer = phase("test_outer");
+            {
+                let _inner = phase("test_inner");
+                let buffer = vec![0_u8; 1 << 20];
+                drop(buffer);
+            }
+            let _buffer = Vec::<u8>::with_capacity(1 << 10);
+        }
+
+        let report = take_memory_report();
+        let find = |name: &str| {
+            report
+                .iter()
+                .find(|phase_memory| phase_memory.get_phase() == name)
+                .cloned()
+                .unwrap()
+        };
+        let (outer, inner) = (find("test_outer"), find("test_inner"));
+
+        // Test that the peak of the inner phase is included in the outer one.
+        assert!(inner.get_peak_bytes() >= 1 << 20);
+        assert!(outer.get_peak_bytes() >= inner.get_peak_bytes());
+        assert!(inner.get_allocations() >= 1);
+        assert!(outer.get_allocations() > inner.get_allocations());
+    }
+}
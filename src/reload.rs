@@ -0,0 +1,188 @@
+//! Reload of the config while serving.
+
+use crate::algorithm::settings::Settings;
+use crate::config::Config;
+use crate::error::Error;
+use crate::signal;
+use crate::signal::SIGHUP;
+use crate::stream::StreamProcessor;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::fs;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Time between two checks whether the config file was modified.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Read the `Config` of the file at the path and select its profile of the name, if any.
+pub fn read_config<N, E>(path: &Path, profile: Option<&str>) -> Result<Config<N, E>, Error>
+where
+    N: IndexMapTrait + Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    let mut config = Config::new();
+    let file = File::open(path)?;
+    config.read_from(&mut BufReader::new(file))?;
+    if let Some(profile) = profile {
+        config.select_profile(profile)?;
+    }
+
+    Ok(config)
+}
+
+/// Set the `Settings` of the processor to the base ones with the `Config` applied and refined,
+/// e.g. by options of the command line, the same as a fresh start with the config. Settings
+/// removed from the config fall back to the base ones. The known `PriceUpdate`s are kept.
+pub fn apply_config<N, E, F>(
+    processor: &mut StreamProcessor<N, E>,
+    base: &Settings<N, E>,
+    config: &Config<N, E>,
+    refine: F,
+) where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+    F: FnOnce(&mut Settings<N, E>),
+{
+    let mut settings = base.clone();
+    config.clone().apply_to(&mut settings);
+    refine(&mut settings);
+    processor.set_settings(settings);
+}
+
+/// Reload the config file of the path whenever it's modified or SIGHUP arrives, from a thread
+/// of its own until the process exits, and apply it with the profile of the name, if any, to the
+/// processors on top of the base `Settings` by `apply_config`.
+///
+/// A config that can't be read is reported and the settings in use are kept, until the file is
+/// modified again.
+pub fn reload_on_change<N, E, P, F>(
+    path: PathBuf,
+    profile: Option<String>,
+    base: Settings<N, E>,
+    processors: P,
+    refine: F,
+) where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + 'static,
+    <E as FromStr>::Err: Debug,
+    P: Fn() -> Vec<Arc<Mutex<StreamProcessor<N, E>>>> + Send + 'static,
+    F: Fn(&mut Settings<N, E>) + Send + 'static,
+{
+    signal::flag_on(SIGHUP);
+    let get_modified =
+        |path: &Path| -> Option<SystemTime> { fs::metadata(path).ok()?.modified().ok() };
+    let mut last_modified = get_modified(&path);
+
+    thread::spawn(move || loop {
+        thread::sleep(CHECK_INTERVAL);
+
+        let modified = get_modified(&path);
+        if !signal::take(SIGHUP) && modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        match read_config::<N, E>(&path, profile.as_deref()) {
+            Ok(config) => {
+                for processor in processors() {
+                    let mut processor = processor.lock().unwrap_or_else(PoisonError::into_inner);
+                    apply_config(&mut processor, &base, &config, &refine);
+                }
+                eprintln!("The config <{}> is reloaded.", path.display());
+            }
+            Err(error) => eprintln!(
+                "The config <{}> can not be reloaded, errors: {:?}!",
+                path.display(),
+                error.get_messages()
+            ),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::config::Config;
+    use crate::reload::apply_config;
+    use crate::stream::StreamProcessor;
+    use std::io::BufReader;
+
+    fn read_config(text: &str) -> Config<String, f32> {
+        let mut config = Config::new();
+        config
+            .read_from(&mut BufReader::new(text.as_bytes()))
+            .unwrap();
+        config
+    }
+
+    #[test]
+    fn apply_config_keeping_price_updates() {
+        let mut base = Settings::<String, f32>::new();
+        base.set_max_hops(Some(3));
+        let mut processor = StreamProcessor::new(base.clone());
+        processor
+            .process_line("2017-11-01T09:42:23+00:00 E1 BTC USD 1000.0 0.0009")
+            .unwrap();
+
+        let config = read_config("[trading]\nfee = 0.5\n");
+        apply_config(&mut processor, &base, &config, |settings| {
+            settings.set_transfer_penalty(0.9)
+        });
+
+        // Test that the config and the refinement are applied on the base settings.
+        assert_eq!(
+            processor
+                .get_settings()
+                .get_fee_model()
+                .get_fee(&"E9".to_string()),
+            0.5
+        );
+        assert_eq!(*processor.get_settings().get_transfer_penalty(), 0.9);
+        assert_eq!(processor.get_settings().get_max_hops(), Some(3));
+
+        // Test that the known price update answers by the new fee.
+        let output = processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .unwrap();
+        assert!(output.starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <500>\n"));
+    }
+
+    #[test]
+    fn apply_config_with_removed_key() {
+        let base = Settings::<String, f32>::new();
+        let mut processor = StreamProcessor::new(base.clone());
+
+        apply_config(
+            &mut processor,
+            &base,
+            &read_config("[trading]\nfee = 0.5\n"),
+            |_| {},
+        );
+        apply_config(&mut processor, &base, &read_config("[trading]\n"), |_| {});
+
+        // Test that the removed fee falls back to the base one, as on a fresh start.
+        let fresh = StreamProcessor::new(base);
+        assert_eq!(
+            processor
+                .get_settings()
+                .get_fee_model()
+                .get_fee(&"E9".to_string()),
+            fresh
+                .get_settings()
+                .get_fee_model()
+                .get_fee(&"E9".to_string())
+        );
+    }
+}
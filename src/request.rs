@@ -1,100 +1,449 @@
 //! Exchange Rate Path Request.
 
+use self::arbitrage_request::ArbitrageRequest;
 use self::exchange_rate_request::ExchangeRateRequest;
 use self::price_update::PriceUpdate;
+use self::read_hooks::ReadHooks;
+use crate::algorithm::settings::Settings;
+use crate::algorithm::Algorithm;
+use crate::debug_log;
+use crate::error::Error;
+use crate::memory;
+use crate::response::Response;
+use crate::trace;
 use crate::IndexMapTrait;
+use chrono::{DateTime, FixedOffset};
+use floyd_warshall_alg::FloydWarshallTrait;
 use indexmap::map::{Entry, IndexMap};
+use num_traits::Zero;
+use std::borrow::Cow;
 use std::clone::Clone;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
+use std::io;
 use std::io::BufRead;
+use std::mem;
+use std::panic;
 use std::str::FromStr;
+use std::sync::mpsc;
+use std::thread;
 
-mod exchange_rate_request;
-mod price_update;
+pub mod arbitrage_request;
+pub mod exchange_rate_request;
+pub mod price_update;
+pub mod read_hooks;
+
+/// Number of input lines passed between the stages of the pipelined reading at once.
+const PIPELINE_BATCH_LINES: usize = 1024;
+
+/// Number of batches of input lines a stage of the pipelined reading may be ahead of the next.
+const PIPELINE_CAPACITY: usize = 16;
+
+/// Get lines of the input, skipping the ones that aren't valid UTF-8, until the input ends or
+/// fails to be read.
+pub(crate) fn valid_lines<I: BufRead>(input: I) -> impl Iterator<Item = String> {
+    input
+        .lines()
+        .filter(|line| !matches!(line, Err(error) if error.kind() == io::ErrorKind::InvalidData))
+        .map_while(Result::ok)
+}
+
+/// Get the line item uppercase, borrowed if it's uppercase already.
+///
+/// Items are parsed from slices of the line, so an identifier is allocated once it's parsed into
+/// its owned form to be stored, not again by making it uppercase.
+pub(crate) fn uppercase(item: &str) -> Cow<'_, str> {
+    let is_uppercase = item.chars().all(|c| {
+        let mut uppercase = c.to_uppercase();
+        uppercase.next() == Some(c) && uppercase.next().is_none()
+    });
+
+    if is_uppercase {
+        Cow::Borrowed(item)
+    } else {
+        Cow::Owned(item.to_uppercase())
+    }
+}
+
+/// A single parsed input line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line<N, E> {
+    PriceUpdate(PriceUpdate<N, E>),
+    RateRequest(ExchangeRateRequest<N, E>),
+    ArbitrageRequest(ArbitrageRequest<N>),
+}
 
 /// Exchange Rate Path `Request` structure.
 ///
+/// Holds the most recent `PriceUpdate` of each pair together with the requests to be answered
+/// against them.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::{ExchangeRateRequest, Request, Settings};
+///
+/// let mut request = Request::<String, f32>::new();
+/// request.add_line(
+///     Request::parse_line("2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001")
+///         .unwrap()
+///         .unwrap(),
+/// );
+/// request.add_rate_request(ExchangeRateRequest::new(
+///     "KRAKEN".to_string(),
+///     "BTC".to_string(),
+///     "KRAKEN".to_string(),
+///     "USD".to_string(),
+/// ));
+///
+/// let response = request.answer(&Settings::new());
+/// let best_rate_path = &response.get_best_rate_paths()[0];
+/// assert_eq!(*best_rate_path.get_rate(), 1000.0);
+/// assert_eq!(best_rate_path.get_path().len(), 2);
+/// ```
+///
 /// # `Request<N, E>` is parameterized over:
 ///
 /// - Identifier data `N`.
 /// - Edge weight `E`.
+#[derive(Debug, Clone)]
 pub struct Request<N, E> {
     price_updates: IndexMap<(N, N, N), PriceUpdate<N, E>>,
-    rate_requests: IndexMap<(N, N, N, N), ExchangeRateRequest<N>>,
+    rate_requests: IndexMap<(N, N, N, N), ExchangeRateRequest<N, E>>,
+    arbitrage_requests: IndexMap<(Option<N>, Option<N>), ArbitrageRequest<N>>,
 }
 
 impl<N, E> Request<N, E>
 where
-    N: Clone + FromStr + IndexMapTrait,
+    N: Clone + Display + FromStr + IndexMapTrait,
     <N as FromStr>::Err: Debug,
-    E: FromStr,
+    E: FromStr + Zero + PartialOrd,
     <E as FromStr>::Err: Debug,
 {
     /// Create a new instance of empty `Request` structure.
-    fn new() -> Self {
+    pub fn new() -> Self {
         let price_updates = IndexMap::new();
         let rate_requests = IndexMap::new();
+        let arbitrage_requests = IndexMap::new();
 
         Self {
             price_updates,
             rate_requests,
+            arbitrage_requests,
         }
     }
 
+    /// Read all input lines into a new `Request` like `try_read_from`, panicking by the `Error`
+    /// instead of returning it.
+    ///
+    /// Lines that aren't valid UTF-8 are skipped rather than reported.
     pub fn read_from<I: BufRead>(input: &mut I) -> Self {
+        Self::expect_valid(Self::read_numbered_lines(input.lines(), false))
+    }
+
+    /// Read all input lines into a new `Request`, or the `Error` of all invalid lines.
+    ///
+    /// Lines that can't be parsed or aren't valid UTF-8 are reported at once by the `Parse`
+    /// error, by their numbers. A failed reading is reported by the `Io` error.
+    pub fn try_read_from<I: BufRead>(input: &mut I) -> Result<Self, Error> {
+        Self::read_numbered_lines(input.lines(), true)
+    }
+
+    /// Read all input lines into a new `Request` like `read_from`, passing each parsed line
+    /// through the `ReadHooks` before it's added.
+    pub fn read_with_hooks<I: BufRead>(input: &mut I, hooks: &mut ReadHooks<N, E>) -> Self {
+        Self::from_lines_with_hooks(valid_lines(input), hooks)
+    }
+
+    /// Collect the lines into a new `Request` like `try_from_lines`, panicking by the `Error`
+    /// instead of returning it, e.g. lines held in memory.
+    ///
+    /// # Examples
+    /// ```
+    /// use exchange_rate::Request;
+    ///
+    /// let request = Request::<String, f32>::from_lines(vec![
+    ///     "2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001",
+    ///     "EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD",
+    /// ]);
+    /// assert_eq!(request.get_price_updates().len(), 1);
+    /// assert_eq!(request.get_rate_requests().len(), 1);
+    /// ```
+    pub fn from_lines<L>(lines: L) -> Self
+    where
+        L: IntoIterator,
+        L::Item: AsRef<str>,
+    {
+        Self::expect_valid(Self::try_from_lines(lines))
+    }
+
+    /// Collect the lines into a new `Request` like `try_read_from`.
+    pub fn try_from_lines<L>(lines: L) -> Result<Self, Error>
+    where
+        L: IntoIterator,
+        L::Item: AsRef<str>,
+    {
+        Self::read_numbered_lines(lines.into_iter().map(Ok::<_, io::Error>), true)
+    }
+
+    /// Collect the read lines into a new `Request`, reporting lines that aren't valid UTF-8 if
+    /// it's strict, or skipping them otherwise.
+    fn read_numbered_lines<L, S>(lines: L, strict: bool) -> Result<Self, Error>
+    where
+        L: Iterator<Item = io::Result<S>>,
+        S: AsRef<str>,
+    {
+        let _span = trace::span("read_from");
+        let _phase = memory::phase("read_from");
         let mut request = Self::new();
+        let mut errors = Vec::new();
 
-        // Read all input and process it.
-        for line in input.lines() {
-            if let Ok(s) = line {
-                request.process_line(&s);
+        for (number, line) in lines.enumerate() {
+            match line.map(|line| Self::parse_numbered_line(number, line.as_ref())) {
+                Ok(Ok(Some(line))) => request.add_line(line),
+                Ok(Ok(None)) => {}
+                Ok(Err(message)) => errors.push(message),
+                Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                    if strict {
+                        errors.push(format!("The line {} is not valid UTF-8!", number + 1))
+                    }
+                }
+                Err(error) => return Err(Error::Io(error)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(request)
+        } else {
+            Err(Error::Parse(errors))
+        }
+    }
+
+    /// Get the read `Request`, panicking by the messages of the `Error` if there's one.
+    fn expect_valid(result: Result<Self, Error>) -> Self {
+        result.unwrap_or_else(|error| {
+            panic!(
+                "Errors occurred while processing input lines, errors: {:?}!",
+                error.get_messages()
+            )
+        })
+    }
+
+    /// Collect the lines into a new `Request` like `read_with_hooks`.
+    pub fn from_lines_with_hooks<L>(lines: L, hooks: &mut ReadHooks<N, E>) -> Self
+    where
+        L: IntoIterator,
+        L::Item: AsRef<str>,
+    {
+        let _span = trace::span("read_from");
+        let _phase = memory::phase("read_from");
+        let mut request = Self::new();
+
+        for line in lines {
+            let line = line.as_ref();
+            match Self::parse_line(line) {
+                Ok(Some(parsed)) => {
+                    if let Some(parsed) = hooks.apply(parsed) {
+                        request.add_line(parsed);
+                    }
+                }
+                Ok(None) => {}
+                Err(error) => hooks.handle_error(line, error),
             }
         }
 
         request
     }
 
-    fn process_line(&mut self, line: &str) {
-        let mut iter = line.split_whitespace();
+    /// Read all input like `read_from`, but as a pipeline of stages overlapping on huge inputs.
+    ///
+    /// Lines read by the current thread are parsed by another one, and the parsed lines are
+    /// added to the `Request` by a third one. Lines are passed between the stages in batches
+    /// through bounded channels, so a slow stage holds back the reading instead of piling up
+    /// lines in memory.
+    ///
+    /// It panics by the `Error` like `read_from` once the input is read, lines that aren't valid
+    /// UTF-8 are skipped, see `try_read_pipelined` for the reading returning it.
+    pub fn read_pipelined<I: BufRead>(input: &mut I) -> Self
+    where
+        N: Send,
+        E: Send,
+    {
+        Self::expect_valid(Self::read_pipelined_lines(input, false))
+    }
 
-        // Process the first line item if it exists.
-        if let Some(first_item) = iter.next() {
-            // Match the line type based on the first line item.
-            // The line item is used as uppercase to be more robust.
-            match first_item.to_uppercase().as_ref() {
-                ExchangeRateRequest::<N>::LINE_TYPE => {
-                    match ExchangeRateRequest::<N>::parse_line(line) {
-                        Ok(rate_request) => self.add_rate_request(rate_request),
-                        // The errors handling can be done better. Probably using logging mechanism
-                        // or just outputting it to the `std::io::stderr`, letting the process continue
-                        // and thus being more robust.
-                        Err(errors) => panic!(
-                            "Errors occurred while processing input lines, errors: {:?}!",
-                            errors
-                        ),
+    /// Read all input like `read_pipelined`, or the `Error` of all invalid lines like
+    /// `try_read_from`.
+    pub fn try_read_pipelined<I: BufRead>(input: &mut I) -> Result<Self, Error>
+    where
+        N: Send,
+        E: Send,
+    {
+        Self::read_pipelined_lines(input, true)
+    }
+
+    /// Read all input by the pipeline, reporting lines that aren't valid UTF-8 if it's strict.
+    fn read_pipelined_lines<I: BufRead>(input: &mut I, strict: bool) -> Result<Self, Error>
+    where
+        N: Send,
+        E: Send,
+    {
+        let _span = trace::span("read_from");
+        let _phase = memory::phase("read_from");
+        let (line_sender, line_receiver) =
+            mpsc::sync_channel::<Vec<(usize, String)>>(PIPELINE_CAPACITY);
+        let (parsed_sender, parsed_receiver) = mpsc::sync_channel(PIPELINE_CAPACITY);
+        let mut errors = Vec::new();
+        let mut failure = None;
+
+        let (request, parse_errors) = thread::scope(|scope| {
+            scope.spawn(move || {
+                for lines in line_receiver {
+                    let parsed: Vec<_> = lines
+                        .iter()
+                        .map(|(number, line)| (*number, Self::parse_numbered_line(*number, line)))
+                        .collect();
+                    if parsed_sender.send(parsed).is_err() {
+                        break;
+                    }
+                }
+            });
+            let builder = scope.spawn(move || {
+                let mut request = Self::new();
+                let mut errors = Vec::new();
+                for (number, line) in parsed_receiver.into_iter().flatten() {
+                    match line {
+                        Ok(Some(line)) => request.add_line(line),
+                        Ok(None) => {}
+                        Err(message) => errors.push((number, message)),
+                    }
+                }
+                (request, errors)
+            });
+
+            let mut lines = Vec::with_capacity(PIPELINE_BATCH_LINES);
+            for (number, line) in input.lines().enumerate() {
+                match line {
+                    Ok(line) => lines.push((number, line)),
+                    Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                        if strict {
+                            errors.push((
+                                number,
+                                format!("The line {} is not valid UTF-8!", number + 1),
+                            ));
+                        }
+                        continue;
+                    }
+                    Err(error) => {
+                        failure = Some(error);
+                        break;
+                    }
+                }
+                if lines.len() == PIPELINE_BATCH_LINES {
+                    let batch = mem::replace(&mut lines, Vec::with_capacity(PIPELINE_BATCH_LINES));
+                    // The parsing stage is gone only if it panicked, the scope resumes it.
+                    if line_sender.send(batch).is_err() {
+                        break;
                     }
                 }
-                _ => match PriceUpdate::<N, E>::parse_line(line) {
-                    Ok(price_update) => self.add_price_update(price_update),
-                    // The errors handling can be done better. Probably using logging mechanism
-                    // or just outputting it to the `std::io::stderr`, letting the process continue
-                    // and thus being more robust.
-                    Err(errors) => panic!(
-                        "Errors occurred while processing input lines, errors: {:?}!",
-                        errors
-                    ),
-                },
             }
+            let _ = line_sender.send(lines);
+            drop(line_sender);
+
+            builder
+                .join()
+                .unwrap_or_else(|error| panic::resume_unwind(error))
+        });
+
+        if let Some(error) = failure {
+            return Err(Error::Io(error));
+        }
+        errors.extend(parse_errors);
+        errors.sort_by_key(|(number, _)| *number);
+        if errors.is_empty() {
+            Ok(request)
+        } else {
+            Err(Error::Parse(
+                errors.into_iter().map(|(_, message)| message).collect(),
+            ))
+        }
+    }
+
+    #[allow(dead_code)]
+    fn process_line(&mut self, line: &str) -> Result<(), Error> {
+        if let Some(line) = Self::parse_line(line)? {
+            self.add_line(line);
         }
+
+        Ok(())
     }
 
-    fn add_rate_request(&mut self, rate_request: ExchangeRateRequest<N>) {
+    /// Parse the input line of the number, counted from zero, or get the message of its errors.
+    fn parse_numbered_line(number: usize, line: &str) -> Result<Option<Line<N, E>>, String> {
+        Self::parse_line(line).map_err(|error| {
+            format!(
+                "The line {} can not be parsed, errors: {:?}!",
+                number + 1,
+                error.get_messages()
+            )
+        })
+    }
+
+    /// Parse a single input line, `None` is returned for an empty line.
+    pub fn parse_line(line: &str) -> Result<Option<Line<N, E>>, Error> {
+        let mut iter = line.split_whitespace();
+
+        // Process the first line item if it exists.
+        let first_item = match iter.next() {
+            Some(first_item) => first_item,
+            None => return Ok(None),
+        };
+
+        // Match the line type based on the first line item.
+        // The line item is used as uppercase to be more robust.
+        let line = match uppercase(first_item).as_ref() {
+            ExchangeRateRequest::<N, E>::LINE_TYPE => {
+                Line::RateRequest(ExchangeRateRequest::<N, E>::parse_line(line)?)
+            }
+            ArbitrageRequest::<N>::LINE_TYPE => {
+                Line::ArbitrageRequest(ArbitrageRequest::<N>::parse_line(line)?)
+            }
+            _ => Line::PriceUpdate(PriceUpdate::<N, E>::parse_line(line)?),
+        };
+
+        Ok(Some(line))
+    }
+
+    /// Add the item of an already parsed input line.
+    pub fn add_line(&mut self, line: Line<N, E>) {
+        match line {
+            Line::PriceUpdate(price_update) => {
+                self.add_price_update(price_update);
+            }
+            Line::RateRequest(rate_request) => self.add_rate_request(rate_request),
+            Line::ArbitrageRequest(arbitrage_request) => {
+                self.add_arbitrage_request(arbitrage_request)
+            }
+        }
+    }
+
+    /// Add the `ExchangeRateRequest`, replacing an earlier one for the same nodes.
+    pub fn add_rate_request(&mut self, rate_request: ExchangeRateRequest<N, E>) {
         // Use the latest.
         self.rate_requests
             .insert(rate_request.get_index(), rate_request);
     }
 
-    fn add_price_update(&mut self, price_update: PriceUpdate<N, E>) {
+    /// Add the `ArbitrageRequest`, replacing an earlier one for the same exchange and currency.
+    pub fn add_arbitrage_request(&mut self, arbitrage_request: ArbitrageRequest<N>) {
+        // Use the latest.
+        self.arbitrage_requests
+            .insert(arbitrage_request.get_index(), arbitrage_request);
+    }
+
+    /// Add the `PriceUpdate` unless a more recent one for the same pair is already known.
+    ///
+    /// Return whether the `PriceUpdate` was accepted. Both the dropped and the overridden
+    /// `PriceUpdate`s are logged by the debug logger, see `set_debug_logger`.
+    pub fn add_price_update(&mut self, price_update: PriceUpdate<N, E>) -> bool {
         let entry = self.price_updates.entry(price_update.get_index());
 
         match entry {
@@ -105,30 +454,163 @@ where
                 // The newly provided `PriceUpdate` is more recent and thus
                 // it should replace the already existing entry.
                 if price_update.get_timestamp() > existing.get_timestamp() {
+                    debug_log::debug(|| {
+                        format!(
+                            "PRICE_UPDATE_OVERRIDDEN <{}> <{}> <{}> <{}> <{}>",
+                            price_update.get_exchange(),
+                            price_update.get_source_currency(),
+                            price_update.get_destination_currency(),
+                            existing.get_timestamp().to_rfc3339(),
+                            price_update.get_timestamp().to_rfc3339()
+                        )
+                    });
+
                     // Replace the existing entry with a new one (the new `PriceUpdate`).
                     *o.into_mut() = price_update;
+                    true
+                } else {
+                    debug_log::debug(|| {
+                        format!(
+                            "PRICE_UPDATE_DROPPED <{}> <{}> <{}> <{}> <older_than_existing> <{}>",
+                            price_update.get_exchange(),
+                            price_update.get_source_currency(),
+                            price_update.get_destination_currency(),
+                            price_update.get_timestamp().to_rfc3339(),
+                            existing.get_timestamp().to_rfc3339()
+                        )
+                    });
+
+                    false
                 }
             }
             // The 'PriceUpdate' with the same id is not yet present in the collection, insert it.
             Entry::Vacant(v) => {
                 v.insert(price_update);
+                true
+            }
+        }
+    }
+
+    /// Remove the known `PriceUpdate` of the index, returning it.
+    pub fn remove_price_update(&mut self, index: &(N, N, N)) -> Option<PriceUpdate<N, E>> {
+        self.price_updates.shift_remove(index)
+    }
+
+    /// Mark the known `PriceUpdate`s of the indexes stale, until a more recent one arrives.
+    ///
+    /// Return the count of `PriceUpdate`s marked.
+    #[cfg(feature = "feed")]
+    pub fn mark_stale(&mut self, indexes: &[(N, N, N)]) -> usize {
+        let mut count = 0;
+
+        for index in indexes {
+            if let Some(price_update) = self.price_updates.get_mut(index) {
+                price_update.set_stale(true);
+                count += 1;
             }
         }
+
+        count
     }
 
+    /// Get the known `PriceUpdate`s indexed by their exchange and currencies.
     pub fn get_price_updates(&self) -> &IndexMap<(N, N, N), PriceUpdate<N, E>> {
         &self.price_updates
     }
 
-    pub fn get_rate_requests(&self) -> &IndexMap<(N, N, N, N), ExchangeRateRequest<N>> {
+    /// Get the timestamp of the most recent quote that isn't stale, `None` if there's none.
+    pub fn get_latest_timestamp(&self) -> Option<DateTime<FixedOffset>> {
+        self.price_updates
+            .values()
+            .filter(|price_update| !price_update.is_stale())
+            .map(|price_update| *price_update.get_timestamp())
+            .max()
+    }
+
+    /// Get the `ExchangeRateRequest`s indexed by their source and destination nodes.
+    pub fn get_rate_requests(&self) -> &IndexMap<(N, N, N, N), ExchangeRateRequest<N, E>> {
         &self.rate_requests
     }
+
+    /// Get the `ArbitrageRequest`s indexed by their exchange and currency filters.
+    pub fn get_arbitrage_requests(&self) -> &IndexMap<(Option<N>, Option<N>), ArbitrageRequest<N>> {
+        &self.arbitrage_requests
+    }
+
+    /// Remove all Exchange Rate Requests and Arbitrage Requests, keeping the `PriceUpdate`s.
+    pub fn clear_requests(&mut self) {
+        self.rate_requests.clear();
+        self.arbitrage_requests.clear();
+    }
+}
+
+impl<N, E> Default for Request<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait,
+    <N as FromStr>::Err: Debug,
+    E: FromStr + Zero + PartialOrd,
+    <E as FromStr>::Err: Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, E> Request<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Answer the requests against the known `PriceUpdate`s using customized `Settings`.
+    pub fn answer(&self, settings: &Settings<N, E>) -> Response<N, E> {
+        Algorithm::<N, E, u32>::process_customized(self, settings)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::request::Request;
-    use std::io::BufReader;
+    use crate::debug_log::set_debug_logger;
+    use crate::error::Error;
+    use crate::request::price_update::PriceUpdate;
+    use crate::request::{uppercase, Request};
+    use std::borrow::Cow;
+    use std::io;
+    use std::io::{BufReader, Read};
+    use std::sync::{Mutex, PoisonError};
+
+    /// Input failing to be read after the lines.
+    fn failing_input(lines: &'static [u8]) -> BufReader<impl Read> {
+        struct Failing;
+
+        impl Read for Failing {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("The input failed!"))
+            }
+        }
+
+        BufReader::new(lines.chain(Failing))
+    }
+
+    static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn collect(record: &str) {
+        RECORDS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(record.to_string());
+    }
+
+    #[test]
+    fn uppercase_items() {
+        // Test that uppercase items are borrowed and others made uppercase.
+        assert!(matches!(uppercase("KRAKEN"), Cow::Borrowed("KRAKEN")));
+        assert!(matches!(uppercase("BID_ASK-2.5"), Cow::Borrowed(_)));
+        assert_eq!(uppercase("Kraken"), "KRAKEN");
+        assert_eq!(uppercase("straße"), "STRASSE");
+        assert_eq!(uppercase("ǅ"), "Ǆ");
+    }
 
     #[test]
     fn process_line() {
@@ -137,7 +619,7 @@ mod tests {
         // Test adding ProcessUpdate line.
         let price_update_line =
             String::from("2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009");
-        request.process_line(&price_update_line);
+        request.process_line(&price_update_line).unwrap();
 
         // Test counts of PriceUpdate items and ExchangeRateRequest items.
         assert_eq!(request.price_updates.len(), 1);
@@ -145,11 +627,51 @@ mod tests {
 
         // Test adding ExchangeRateRequest line.
         let price_update_line = String::from("EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH");
-        request.process_line(&price_update_line);
+        request.process_line(&price_update_line).unwrap();
 
         // Test counts of PriceUpdate items and ExchangeRateRequest items.
         assert_eq!(request.price_updates.len(), 1);
         assert_eq!(request.rate_requests.len(), 1);
+
+        // Test adding ArbitrageRequest line.
+        let arbitrage_request_line = String::from("ARBITRAGE_REQUEST ANY BTC");
+        request.process_line(&arbitrage_request_line).unwrap();
+
+        // Test counts of all items.
+        assert_eq!(request.price_updates.len(), 1);
+        assert_eq!(request.rate_requests.len(), 1);
+        assert_eq!(request.arbitrage_requests.len(), 1);
+    }
+
+    #[test]
+    fn add_price_update_debug_log() {
+        set_debug_logger(collect);
+        let mut request = Request::<String, f32>::from_lines(vec![
+            "2019-01-20T09:42:23+00:00 LOGGED BTC USD 1000 0.001",
+            "2019-01-20T09:42:24+00:00 LOGGED BTC USD 1001 0.001",
+            "2019-01-20T09:42:22+00:00 LOGGED BTC USD 999 0.001",
+        ]);
+
+        // Test that both the overridden and the dropped `PriceUpdate` are logged with the key.
+        let records: Vec<String> = RECORDS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|record| record.contains("<LOGGED>"))
+            .cloned()
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                "PRICE_UPDATE_OVERRIDDEN <LOGGED> <BTC> <USD> \
+                 <2019-01-20T09:42:23+00:00> <2019-01-20T09:42:24+00:00>"
+                    .to_string(),
+                "PRICE_UPDATE_DROPPED <LOGGED> <BTC> <USD> <2019-01-20T09:42:22+00:00> \
+                 <older_than_existing> <2019-01-20T09:42:24+00:00>"
+                    .to_string(),
+            ]
+        );
+        assert!(!request.add_price_update(request.get_price_updates()[0].clone()));
     }
 
     #[test]
@@ -192,4 +714,140 @@ EXCHANGE_RATE_REQUEST GDAX BTC KRAKEN USD"
         assert_eq!(request.price_updates.len(), 2);
         assert_eq!(request.rate_requests.len(), 4);
     }
+
+    #[test]
+    fn read_from_skip_invalid_utf8_lines() {
+        let text_input: &[u8] = b"2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009\n\
+            \xff\xfe\n\
+            EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD\n";
+
+        // Test that lines after a line not valid UTF-8 are read as well.
+        let request = Request::<String, f32>::read_from(&mut BufReader::new(text_input));
+        assert_eq!(request.price_updates.len(), 1);
+        assert_eq!(request.rate_requests.len(), 1);
+
+        let request = Request::<String, f32>::read_pipelined(&mut BufReader::new(text_input));
+        assert_eq!(request.rate_requests.len(), 1);
+    }
+
+    #[test]
+    fn from_lines() {
+        let lines = vec![
+            "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009".to_string(),
+            String::new(),
+            "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH".to_string(),
+        ];
+
+        // Test creation of Request from owned and borrowed lines.
+        let request = Request::<String, f32>::from_lines(&lines);
+        assert_eq!(request.price_updates.len(), 1);
+        assert_eq!(request.rate_requests.len(), 1);
+
+        let request = Request::<String, f32>::from_lines(["ARBITRAGE_REQUEST ANY ANY"]);
+        assert_eq!(request.arbitrage_requests.len(), 1);
+    }
+
+    #[test]
+    fn read_pipelined() {
+        let mut text_input = String::new();
+        for second in 0..3000 {
+            text_input.push_str(&format!(
+                "2019-01-20T09:{:02}:{:02}+00:00 E{} BTC USD {} 0.001\n\nEXCHANGE_RATE_REQUEST E{} BTC E0 USD\n",
+                second / 60,
+                second % 60,
+                second % 7,
+                1000 + second,
+                second % 11
+            ));
+        }
+
+        let request =
+            Request::<String, f32>::read_pipelined(&mut BufReader::new(text_input.as_bytes()));
+        let expected =
+            Request::<String, f32>::read_from(&mut BufReader::new(text_input.as_bytes()));
+
+        // Test that the lines spanning multiple batches are added in their order.
+        let get_lines = |request: &Request<String, f32>| {
+            request
+                .price_updates
+                .values()
+                .map(PriceUpdate::get_line)
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(request.price_updates.len(), 7);
+        assert_eq!(get_lines(&request), get_lines(&expected));
+        assert!(request
+            .rate_requests
+            .keys()
+            .eq(expected.rate_requests.keys()));
+    }
+
+    #[test]
+    fn try_read_from_invalid_lines() {
+        let text_input: &[u8] = b"2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009\n\
+            EXCHANGE_RATE_REQUEST KRAKEN BTC\n\
+            \xff\xfe\n\
+            EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD\n";
+        let expected = Error::Parse(vec![
+            "The line 2 can not be parsed, errors: [\"The line item <source_exchange> is \
+             missing!\", \"The line item <destination_exchange> is missing!\"]!"
+                .to_string(),
+            "The line 3 is not valid UTF-8!".to_string(),
+        ]);
+
+        // Test that all invalid lines are reported by their numbers instead of a panic.
+        assert_eq!(
+            Request::<String, f32>::try_read_from(&mut BufReader::new(text_input)).err(),
+            Some(expected)
+        );
+        let error = Request::<String, f32>::try_read_pipelined(&mut BufReader::new(text_input))
+            .err()
+            .unwrap();
+        assert_eq!(error.get_messages().len(), 2);
+        assert!(
+            Request::<String, f32>::try_from_lines(["EXCHANGE_RATE_REQUEST KRAKEN BTC"]).is_err()
+        );
+
+        // Test that valid lines are read.
+        let request = Request::<String, f32>::try_from_lines([
+            "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009",
+            "EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD",
+        ])
+        .unwrap();
+        assert_eq!(request.price_updates.len(), 1);
+        assert_eq!(request.rate_requests.len(), 1);
+    }
+
+    #[test]
+    fn try_read_failing_input() {
+        let text_input: &[u8] = b"2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009\n";
+
+        // Test that the input failing to be read is reported instead of reading it partially.
+        assert!(matches!(
+            Request::<String, f32>::try_read_from(&mut failing_input(text_input)),
+            Err(Error::Io(_))
+        ));
+        assert!(matches!(
+            Request::<String, f32>::try_read_pipelined(&mut failing_input(text_input)),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn read_pipelined_failing_input() {
+        let text_input: &[u8] = b"2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009\n";
+
+        Request::<String, f32>::read_pipelined(&mut failing_input(text_input));
+    }
+
+    #[test]
+    #[should_panic]
+    fn read_pipelined_invalid_line() {
+        let text_input = "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009
+EXCHANGE_RATE_REQUEST KRAKEN BTC"
+            .as_bytes();
+
+        Request::<String, f32>::read_pipelined(&mut BufReader::new(text_input));
+    }
 }
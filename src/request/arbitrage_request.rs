@@ -0,0 +1,309 @@
+//! Arbitrage Request.
+
+use self::Items::*;
+use crate::error::Error;
+use crate::request::uppercase;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Eq, PartialEq, Hash)]
+pub enum Items {
+    LineType,
+    Exchange,
+    Currency,
+    FundingExchange,
+    FundingCurrency,
+}
+
+impl Items {
+    pub fn get_label(&self) -> String {
+        match self {
+            LineType => "ARBITRAGE_REQUEST".to_string(),
+            Exchange => "exchange".to_string(),
+            Currency => "currency".to_string(),
+            FundingExchange => "funding_exchange".to_string(),
+            FundingCurrency => "funding_currency".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Items {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+/// Funding `(exchange, currency)` nodes, `None` stands for any exchange or any currency.
+pub type Funding<N> = Vec<(Option<N>, Option<N>)>;
+
+/// `ArbitrageRequest` structure.
+///
+/// Request for the most profitable cycle starting and ending at the `(exchange, currency)` node.
+/// Any of the two identifiers can be `None` meaning any exchange or any currency.
+///
+/// The cycle can be further restricted to pass through one of the funding `(exchange, currency)`
+/// nodes, where balances are actually held. No funding nodes means no such restriction.
+///
+/// # `ArbitrageRequest<N>` is parameterized over:
+///
+/// - Identifier data `N`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArbitrageRequest<N> {
+    exchange: Option<N>,
+    currency: Option<N>,
+    funding: Funding<N>,
+}
+
+impl<N> ArbitrageRequest<N>
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: fmt::Debug,
+{
+    // The type of a line that can be parsed into the `ArbitrageRequest` structure.
+    pub const LINE_TYPE: &'static str = "ARBITRAGE_REQUEST";
+
+    // The wildcard matching any exchange or any currency.
+    pub const ANY: &'static str = "ANY";
+
+    /// Create a new instance of `ArbitrageRequest` structure.
+    pub fn new(exchange: Option<N>, currency: Option<N>) -> Self {
+        Self {
+            exchange,
+            currency,
+            funding: Vec::new(),
+        }
+    }
+
+    /// Get Index identifying current instance by its primary keys.
+    pub fn get_index(&self) -> (Option<N>, Option<N>) {
+        (self.exchange.clone(), self.currency.clone())
+    }
+
+    pub fn get_exchange(&self) -> Option<&N> {
+        self.exchange.as_ref()
+    }
+
+    pub fn get_currency(&self) -> Option<&N> {
+        self.currency.as_ref()
+    }
+
+    /// Set the funding `(exchange, currency)` nodes a cycle must pass through one of.
+    pub fn set_funding(&mut self, funding: Funding<N>) {
+        self.funding = funding;
+    }
+
+    pub fn get_funding(&self) -> &[(Option<N>, Option<N>)] {
+        &self.funding
+    }
+
+    /// Parse input line and form a new `ArbitrageRequest` struct from it.
+    ///
+    /// # `line` format
+    ///
+    /// ARBITRAGE_REQUEST <exchange> <currency> [<funding_exchange> <funding_currency>]...
+    ///
+    /// Any `<exchange>` and `<currency>` item can be `ANY`.
+    ///
+    /// ## Example
+    ///
+    /// ARBITRAGE_REQUEST BITFINEX BTC
+    /// ARBITRAGE_REQUEST ANY ANY
+    /// ARBITRAGE_REQUEST ANY ANY KRAKEN USD ANY BTC
+    pub fn parse_line(line: &str) -> Result<ArbitrageRequest<N>, Error> {
+        let mut iter = line.split_whitespace();
+        let mut values = HashMap::new();
+        let mut errors: Vec<String> = Vec::new();
+
+        // Collect raw values.
+        for item in &[LineType, Exchange, Currency] {
+            let value: Option<&str> = iter.next();
+
+            match value {
+                Some(s) => {
+                    values.insert(item, s);
+                }
+                None => {
+                    errors.push(format!("The line item <{}> is missing!", item));
+                }
+            }
+        }
+
+        // Continue only if none of the collected values is missing (no errors are present).
+        if !errors.is_empty() {
+            return Err(Error::Parse(errors));
+        }
+
+        // Validate line type.
+        let line_type = uppercase(values[&LineType]);
+        if line_type != Self::LINE_TYPE {
+            errors.push(format!(
+                "The line item type identifier at the beginning of the line {} is wrong!",
+                Self::LINE_TYPE
+            ));
+            return Err(Error::Parse(errors));
+        }
+
+        // Parse values, also making it all uppercase to be more robust.
+        let exchange = Self::parse_optional_item(values[&Exchange]);
+        if exchange.is_err() {
+            errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &Exchange
+            ));
+        }
+
+        let currency = Self::parse_optional_item(values[&Currency]);
+        if currency.is_err() {
+            errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &Currency
+            ));
+        }
+
+        // Continue only if all values were parsed successfully (no errors are present).
+        if !errors.is_empty() {
+            return Err(Error::Parse(errors));
+        }
+
+        let mut arbitrage_request = Self::new(exchange.unwrap(), currency.unwrap());
+        arbitrage_request.set_funding(Self::parse_funding(iter).map_err(Error::Parse)?);
+
+        Ok(arbitrage_request)
+    }
+
+    /// Parse the remaining line items as pairs of funding exchange and currency.
+    fn parse_funding<'a>(iter: impl Iterator<Item = &'a str>) -> Result<Funding<N>, Vec<String>> {
+        let values: Vec<&str> = iter.collect();
+        let mut funding = Vec::new();
+        let mut errors: Vec<String> = Vec::new();
+
+        for pair in values.chunks(2) {
+            let exchange = Self::parse_optional_item(pair[0]);
+            if exchange.is_err() {
+                errors.push(format!(
+                    "The line item <{}> can not be parsed (wrong format)!",
+                    &FundingExchange
+                ));
+            }
+
+            let currency = match pair.get(1) {
+                Some(value) => Self::parse_optional_item(value),
+                None => {
+                    errors.push(format!("The line item <{}> is missing!", FundingCurrency));
+                    continue;
+                }
+            };
+            if currency.is_err() {
+                errors.push(format!(
+                    "The line item <{}> can not be parsed (wrong format)!",
+                    &FundingCurrency
+                ));
+            }
+
+            if let (Ok(exchange), Ok(currency)) = (exchange, currency) {
+                funding.push((exchange, currency));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(funding)
+    }
+
+    /// Parse line item value, the `ANY` wildcard is parsed as `None`.
+    fn parse_optional_item(value: &str) -> Result<Option<N>, <N as FromStr>::Err> {
+        let value = uppercase(value);
+
+        if value == Self::ANY {
+            Ok(None)
+        } else {
+            value.parse::<N>().map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request::arbitrage_request::ArbitrageRequest;
+    use crate::request::arbitrage_request::Items::*;
+
+    #[test]
+    fn parse_line() {
+        let line = "ARBITRAGE_REQUEST KRAKEN btc";
+        let arbitrage_request = ArbitrageRequest::<String>::parse_line(line);
+
+        // Test that the line was parsed properly.
+        assert!(arbitrage_request.is_ok());
+
+        // It is safe to unwrap now.
+        let arbitrage_request = arbitrage_request.unwrap();
+
+        // Test properly parsed line items.
+        assert_eq!(arbitrage_request.exchange, Some("KRAKEN".to_string()));
+        assert_eq!(arbitrage_request.currency, Some("BTC".to_string()));
+    }
+
+    #[test]
+    fn parse_line_with_wildcards() {
+        let line = "ARBITRAGE_REQUEST ANY any";
+        let arbitrage_request = ArbitrageRequest::<String>::parse_line(line).unwrap();
+
+        // Test that wildcards were parsed as `None`.
+        assert_eq!(arbitrage_request.exchange, None);
+        assert_eq!(arbitrage_request.currency, None);
+    }
+
+    #[test]
+    fn parse_line_with_funding() {
+        let line = "ARBITRAGE_REQUEST ANY ANY KRAKEN usd ANY BTC";
+        let arbitrage_request = ArbitrageRequest::<String>::parse_line(line).unwrap();
+
+        // Test that funding pairs were parsed.
+        assert_eq!(
+            arbitrage_request.get_funding(),
+            &[
+                (Some("KRAKEN".to_string()), Some("USD".to_string())),
+                (None, Some("BTC".to_string())),
+            ]
+        );
+
+        // Test that an incomplete funding pair is an error.
+        let line = "ARBITRAGE_REQUEST ANY ANY KRAKEN";
+        let errors = ArbitrageRequest::<String>::parse_line(line)
+            .err()
+            .unwrap()
+            .get_messages();
+        assert_eq!(
+            errors,
+            vec![format!("The line item <{}> is missing!", FundingCurrency)]
+        );
+    }
+
+    #[test]
+    fn parse_line_with_missing_values() {
+        let line = "ARBITRAGE_REQUEST";
+        let arbitrage_request = ArbitrageRequest::<String>::parse_line(line);
+
+        // Test that the line could not be parsed properly.
+        assert!(arbitrage_request.is_err());
+
+        // Unwrap errors as they should exist.
+        let mut errors = arbitrage_request.err().unwrap().get_messages();
+
+        // Test that all errors are present.
+        assert_eq!(
+            errors.pop().unwrap(),
+            format!("The line item <{}> is missing!", Currency)
+        );
+        assert_eq!(
+            errors.pop().unwrap(),
+            format!("The line item <{}> is missing!", Exchange)
+        );
+
+        // No other error is expected.
+        assert!(errors.pop().is_none());
+    }
+}
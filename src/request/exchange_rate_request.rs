@@ -1,6 +1,10 @@
 //! Exchange Rate Request.
 
 use self::Items::*;
+use crate::algorithm::fee_model::ExecutionStyle;
+use crate::error::Error;
+use crate::request::uppercase;
+use chrono::{DateTime, FixedOffset};
 use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
@@ -12,6 +16,10 @@ pub enum Items {
     SourceCurrency,
     DestinationExchange,
     DestinationCurrency,
+    Amount,
+    Style,
+    TimeBudget,
+    AsOf,
 }
 
 impl Items {
@@ -22,6 +30,10 @@ impl Items {
             SourceCurrency => "source_currency".to_string(),
             DestinationExchange => "source_exchange".to_string(),
             DestinationCurrency => "destination_exchange".to_string(),
+            Amount => "amount".to_string(),
+            Style => "execution_style".to_string(),
+            TimeBudget => "time_budget".to_string(),
+            AsOf => "as_of".to_string(),
         }
     }
 }
@@ -34,24 +46,43 @@ impl fmt::Display for Items {
 
 /// `ExchangeRateRequest` structure.
 ///
-/// # `ExchangeRateRequest<N>` is parameterized over:
+/// An optional amount of the source currency asks for the best rate of converting the whole
+/// amount instead of the marginal rate. An optional execution style decides whether maker or
+/// taker fees apply, taker by default. An optional time budget asks for the best rate of paths
+/// settling within the count of minutes. An optional moment asks for the best rate as of the
+/// moment, using the price updates known then.
+///
+/// # `ExchangeRateRequest<N, E>` is parameterized over:
 ///
 /// - Identifier data `N`.
-pub struct ExchangeRateRequest<N> {
+/// - Edge weight `E`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeRateRequest<N, E> {
     source_exchange: N,
     source_currency: N,
     destination_exchange: N,
     destination_currency: N,
+    amount: Option<E>,
+    execution_style: ExecutionStyle,
+    time_budget: Option<u32>,
+    as_of: Option<DateTime<FixedOffset>>,
 }
 
-impl<N> ExchangeRateRequest<N>
+impl<N, E> ExchangeRateRequest<N, E>
 where
     N: Clone + FromStr,
     <N as FromStr>::Err: fmt::Debug,
+    E: FromStr,
 {
     // The type of a line that can be parsed into the `ExchangeRateRequest` structure.
     pub const LINE_TYPE: &'static str = "EXCHANGE_RATE_REQUEST";
 
+    // The keyword preceding the time budget.
+    pub const WITHIN: &'static str = "WITHIN";
+
+    // The keyword preceding the moment the rate is asked as of.
+    pub const AS_OF: &'static str = "AS_OF";
+
     /// Create a new instance of `ExchangeRateRequest` structure.
     pub fn new(
         source_exchange: N,
@@ -64,9 +95,48 @@ where
             source_currency,
             destination_exchange,
             destination_currency,
+            amount: None,
+            execution_style: ExecutionStyle::default(),
+            time_budget: None,
+            as_of: None,
         }
     }
 
+    /// Set the amount of the source currency to be converted.
+    pub fn set_amount(&mut self, amount: Option<E>) {
+        self.amount = amount;
+    }
+
+    pub fn get_amount(&self) -> Option<&E> {
+        self.amount.as_ref()
+    }
+
+    pub fn set_execution_style(&mut self, execution_style: ExecutionStyle) {
+        self.execution_style = execution_style;
+    }
+
+    pub fn get_execution_style(&self) -> ExecutionStyle {
+        self.execution_style
+    }
+
+    /// Set the count of minutes all transfers of the path have to settle within.
+    pub fn set_time_budget(&mut self, time_budget: Option<u32>) {
+        self.time_budget = time_budget;
+    }
+
+    pub fn get_time_budget(&self) -> Option<u32> {
+        self.time_budget
+    }
+
+    /// Set the moment the best rate is asked as of, `None` for the current one.
+    pub fn set_as_of(&mut self, as_of: Option<DateTime<FixedOffset>>) {
+        self.as_of = as_of;
+    }
+
+    pub fn get_as_of(&self) -> Option<&DateTime<FixedOffset>> {
+        self.as_of.as_ref()
+    }
+
     /// Get Index identifying current instance by its primary keys.
     pub fn get_index(&self) -> (N, N, N, N) {
         (
@@ -97,12 +167,16 @@ where
     ///
     /// # `line` format
     ///
-    /// EXCHANGE_RATE_REQUEST <source_exchange> <source_currency> <destination_exchange> <destination_currency>
+    /// EXCHANGE_RATE_REQUEST <source_exchange> <source_currency> <destination_exchange> <destination_currency> [<amount>] [MAKER|TAKER] [WITHIN <minutes>] [AS_OF <timestamp>]
     ///
     /// ## Example
     ///
     /// EXCHANGE_RATE_REQUEST BITFINEX ETH BINANCE BTC
-    pub fn parse_line(line: &str) -> Result<ExchangeRateRequest<N>, Vec<String>> {
+    /// EXCHANGE_RATE_REQUEST BITFINEX ETH BINANCE BTC 250.0
+    /// EXCHANGE_RATE_REQUEST BITFINEX ETH BINANCE BTC 250.0 MAKER
+    /// EXCHANGE_RATE_REQUEST BITFINEX ETH BINANCE BTC MAKER WITHIN 30
+    /// EXCHANGE_RATE_REQUEST BITFINEX ETH BINANCE BTC AS_OF 2019-01-20T14:02:00+00:00
+    pub fn parse_line(line: &str) -> Result<ExchangeRateRequest<N, E>, Error> {
         let mut iter = line.split_whitespace();
         let mut values = HashMap::new();
         let mut errors: Vec<String> = Vec::new();
@@ -129,21 +203,21 @@ where
 
         // Continue only if none of the collected values is missing (no errors are present).
         if !errors.is_empty() {
-            return Err(errors);
+            return Err(Error::Parse(errors));
         }
 
         // Validate line type.
-        let line_type = values[&LineType].to_uppercase();
+        let line_type = uppercase(values[&LineType]);
         if line_type != Self::LINE_TYPE {
             errors.push(format!(
                 "The line item type identifier at the beginning of the line {} is wrong!",
                 Self::LINE_TYPE
             ));
-            return Err(errors);
+            return Err(Error::Parse(errors));
         }
 
         // Parse values, also making it all uppercase to be more robust.
-        let source_exchange = values[&SourceExchange].to_uppercase().parse::<N>();
+        let source_exchange = uppercase(values[&SourceExchange]).parse::<N>();
         if source_exchange.is_err() {
             errors.push(format!(
                 "The line item <{}> can not be parsed (wrong format)!",
@@ -151,7 +225,7 @@ where
             ));
         }
 
-        let source_currency = values[&SourceCurrency].to_uppercase().parse::<N>();
+        let source_currency = uppercase(values[&SourceCurrency]).parse::<N>();
         if source_currency.is_err() {
             errors.push(format!(
                 "The line item <{}> can not be parsed (wrong format)!",
@@ -159,7 +233,7 @@ where
             ));
         }
 
-        let destination_exchange = values[&DestinationExchange].to_uppercase().parse::<N>();
+        let destination_exchange = uppercase(values[&DestinationExchange]).parse::<N>();
         if destination_exchange.is_err() {
             errors.push(format!(
                 "The line item <{}> can not be parsed (wrong format)!",
@@ -167,7 +241,7 @@ where
             ));
         }
 
-        let destination_currency = values[&DestinationCurrency].to_uppercase().parse::<N>();
+        let destination_currency = uppercase(values[&DestinationCurrency]).parse::<N>();
         if destination_currency.is_err() {
             errors.push(format!(
                 "The line item <{}> can not be parsed (wrong format)!",
@@ -175,29 +249,100 @@ where
             ));
         }
 
+        let is_within = |value: &str| uppercase(value) == Self::WITHIN;
+        let is_as_of = |value: &str| uppercase(value) == Self::AS_OF;
+        let is_keyword = |value: &str| is_within(value) || is_as_of(value);
+
+        // The amount is optional, it's missing if followed directly by the execution style, the
+        // time budget or the moment.
+        let mut value = iter.next();
+        let amount = match value {
+            Some(amount) if ExecutionStyle::parse(amount).is_none() && !is_keyword(amount) => {
+                value = iter.next();
+                Some(amount.parse::<E>())
+            }
+            _ => None,
+        };
+        if let Some(Err(_)) = amount {
+            errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &Amount
+            ));
+        }
+
+        // The execution style is optional.
+        let execution_style = match value {
+            Some(style) if !is_keyword(style) => {
+                value = iter.next();
+                Some(ExecutionStyle::parse(style))
+            }
+            _ => None,
+        };
+        if let Some(None) = execution_style {
+            errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &Style
+            ));
+        }
+
+        // The time budget is optional, following the `WITHIN` keyword.
+        let time_budget = value.filter(|keyword| is_within(keyword)).map(|_| {
+            let minutes = iter.next();
+            value = iter.next();
+            minutes.map(|minutes| minutes.parse::<u32>())
+        });
+        match time_budget {
+            Some(None) => errors.push(format!("The line item <{}> is missing!", &TimeBudget)),
+            Some(Some(Err(_))) => errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &TimeBudget
+            )),
+            _ => {}
+        }
+
+        // The moment is optional, following the `AS_OF` keyword.
+        let as_of = value
+            .filter(|keyword| is_as_of(keyword))
+            .map(|_| iter.next().map(DateTime::parse_from_rfc3339));
+        match as_of {
+            Some(None) => errors.push(format!("The line item <{}> is missing!", &AsOf)),
+            Some(Some(Err(_))) => errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &AsOf
+            )),
+            _ => {}
+        }
+
         // Continue only if all values were parsed successfully (no errors are present).
         if !errors.is_empty() {
-            return Err(errors);
+            return Err(Error::Parse(errors));
         }
 
-        Ok(Self::new(
+        let mut rate_request = Self::new(
             source_exchange.unwrap(),
             source_currency.unwrap(),
             destination_exchange.unwrap(),
             destination_currency.unwrap(),
-        ))
+        );
+        rate_request.set_amount(amount.and_then(Result::ok));
+        rate_request.set_execution_style(execution_style.flatten().unwrap_or_default());
+        rate_request.set_time_budget(time_budget.flatten().and_then(Result::ok));
+        rate_request.set_as_of(as_of.flatten().and_then(Result::ok));
+
+        Ok(rate_request)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::algorithm::fee_model::ExecutionStyle::*;
     use crate::request::exchange_rate_request::ExchangeRateRequest;
     use crate::request::exchange_rate_request::Items::*;
 
     #[test]
     fn parse_line() {
         let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH";
-        let rate_request = ExchangeRateRequest::<String>::parse_line(&line.to_string());
+        let rate_request = ExchangeRateRequest::<String, f32>::parse_line(line);
 
         // Test that the line was parsed properly.
         assert!(rate_request.is_ok());
@@ -210,25 +355,155 @@ mod tests {
         assert_eq!(rate_request.source_currency, "BTC");
         assert_eq!(rate_request.destination_exchange, "GDAX");
         assert_eq!(rate_request.destination_currency, "ETH");
+        assert_eq!(rate_request.amount, None);
+        assert_eq!(rate_request.execution_style, Taker);
+    }
+
+    #[test]
+    fn parse_line_with_execution_style() {
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH maker";
+        let rate_request = ExchangeRateRequest::<String, f32>::parse_line(line).unwrap();
+        assert_eq!(rate_request.get_amount(), None);
+        assert_eq!(rate_request.get_execution_style(), Maker);
+
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH 2.5 MAKER";
+        let rate_request = ExchangeRateRequest::<String, f32>::parse_line(line).unwrap();
+        assert_eq!(rate_request.get_amount(), Some(&2.5));
+        assert_eq!(rate_request.get_execution_style(), Maker);
+
+        // Test that a wrong execution style is reported.
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH 2.5 LIMIT";
+        let errors = ExchangeRateRequest::<String, f32>::parse_line(line)
+            .err()
+            .unwrap()
+            .get_messages();
+        assert_eq!(
+            errors,
+            vec![format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                Style
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_line_with_amount() {
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH 2.5";
+        let rate_request = ExchangeRateRequest::<String, f32>::parse_line(line).unwrap();
+
+        assert_eq!(rate_request.get_amount(), Some(&2.5));
+
+        // Test that a wrong amount is reported.
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH many";
+        let errors = ExchangeRateRequest::<String, f32>::parse_line(line)
+            .err()
+            .unwrap()
+            .get_messages();
+        assert_eq!(
+            errors,
+            vec![format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                Amount
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_line_with_time_budget() {
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH within 30";
+        let rate_request = ExchangeRateRequest::<String, f32>::parse_line(line).unwrap();
+        assert_eq!(rate_request.get_amount(), None);
+        assert_eq!(rate_request.get_time_budget(), Some(30));
+
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH 2.5 MAKER WITHIN 30";
+        let rate_request = ExchangeRateRequest::<String, f32>::parse_line(line).unwrap();
+        assert_eq!(rate_request.get_amount(), Some(&2.5));
+        assert_eq!(rate_request.get_execution_style(), Maker);
+        assert_eq!(rate_request.get_time_budget(), Some(30));
+
+        // Test that a missing and a wrong time budget are reported.
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH WITHIN";
+        let errors = ExchangeRateRequest::<String, f32>::parse_line(line)
+            .err()
+            .unwrap()
+            .get_messages();
+        assert_eq!(
+            errors,
+            vec![format!("The line item <{}> is missing!", TimeBudget)]
+        );
+
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH TAKER WITHIN soon";
+        let errors = ExchangeRateRequest::<String, f32>::parse_line(line)
+            .err()
+            .unwrap()
+            .get_messages();
+        assert_eq!(
+            errors,
+            vec![format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                TimeBudget
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_line_with_as_of() {
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH as_of 2019-01-20T14:02:00+00:00";
+        let rate_request = ExchangeRateRequest::<String, f32>::parse_line(line).unwrap();
+        assert_eq!(rate_request.get_amount(), None);
+        assert_eq!(
+            rate_request.get_as_of().unwrap().to_rfc3339(),
+            "2019-01-20T14:02:00+00:00"
+        );
+
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH 2.5 WITHIN 30 AS_OF 2019-01-20T14:02:00+00:00";
+        let rate_request = ExchangeRateRequest::<String, f32>::parse_line(line).unwrap();
+        assert_eq!(rate_request.get_amount(), Some(&2.5));
+        assert_eq!(rate_request.get_time_budget(), Some(30));
+        assert!(rate_request.get_as_of().is_some());
+
+        // Test that a missing and a wrong moment are reported.
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH AS_OF";
+        let errors = ExchangeRateRequest::<String, f32>::parse_line(line)
+            .err()
+            .unwrap()
+            .get_messages();
+        assert_eq!(
+            errors,
+            vec![format!("The line item <{}> is missing!", AsOf)]
+        );
+
+        let line = "EXCHANGE_RATE_REQUEST KRAKEN BTC GDAX ETH MAKER AS_OF yesterday";
+        let errors = ExchangeRateRequest::<String, f32>::parse_line(line)
+            .err()
+            .unwrap()
+            .get_messages();
+        assert_eq!(
+            errors,
+            vec![format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                AsOf
+            )]
+        );
     }
 
     #[test]
     fn parse_line_with_wrong_line_type() {
         let line = "WRONG_LINE_TYPE KRAKEN BTC GDAX ETH";
-        let price_update = ExchangeRateRequest::<String>::parse_line(&line.to_string());
+        let price_update = ExchangeRateRequest::<String, f32>::parse_line(line);
 
         // Test that the line could not be parsed properly.
         assert!(price_update.is_err());
 
         // Unwrap errors as they should exist.
-        let mut errors = price_update.err().unwrap();
+        let mut errors = price_update.err().unwrap().get_messages();
 
         // Test that all errors are present.
         assert_eq!(
             errors.pop().unwrap(),
             format!(
                 "The line item type identifier at the beginning of the line {} is wrong!",
-                ExchangeRateRequest::<String>::LINE_TYPE
+                ExchangeRateRequest::<String, f32>::LINE_TYPE
             )
         );
 
@@ -239,13 +514,13 @@ mod tests {
     #[test]
     fn parse_line_with_missing_values() {
         let line = "";
-        let price_update = ExchangeRateRequest::<String>::parse_line(&line.to_string());
+        let price_update = ExchangeRateRequest::<String, f32>::parse_line(line);
 
         // Test that the line could not be parsed properly.
         assert!(price_update.is_err());
 
         // Unwrap errors as they should exist.
-        let mut errors = price_update.err().unwrap();
+        let mut errors = price_update.err().unwrap().get_messages();
 
         // Test that all errors are present.
         assert_eq!(
@@ -1,8 +1,15 @@
 //! Price Update.
 
 use self::Items::*;
+use crate::error::Error;
+use crate::json;
+#[cfg(feature = "std")]
+use crate::json::value::{field, identifier, number, Value};
+use crate::request::uppercase;
 use chrono::{DateTime, FixedOffset};
+use num_traits::{Num, Zero};
 use std::clone::Clone;
+use std::cmp::Ordering::Greater;
 use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
@@ -15,6 +22,8 @@ pub enum Items {
     DestinationCurrency,
     ForwardFactor,
     BackwardFactor,
+    Bid,
+    Ask,
 }
 
 impl Items {
@@ -26,6 +35,8 @@ impl Items {
             DestinationCurrency => "destination_currency".to_string(),
             ForwardFactor => "forward_factor".to_string(),
             BackwardFactor => "backward_factor".to_string(),
+            Bid => "bid".to_string(),
+            Ask => "ask".to_string(),
         }
     }
 }
@@ -36,19 +47,30 @@ impl fmt::Display for Items {
     }
 }
 
+/// Quote of a `PriceUpdate`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quote<E> {
+    /// Rates of converting the source currency to the destination one and back.
+    Factors { forward: E, backward: E },
+    /// Prices of the source (base) currency in the destination (quote) currency. The source
+    /// currency is sold for the bid and bought for the ask price.
+    BidAsk { bid: E, ask: E },
+}
+
 /// Exchange Rate Path `PriceUpdate` structure.
 ///
 /// # `PriceUpdate<N, E>` is parameterized over:
 ///
 /// - Identifier data `N`.
 /// - Edge weight `E`.
+#[derive(Debug, Clone, PartialEq)]
 pub struct PriceUpdate<N, E> {
     timestamp: DateTime<FixedOffset>,
     exchange: N,
     source_currency: N,
     destination_currency: N,
-    forward_factor: E,
-    backward_factor: E,
+    quote: Quote<E>,
+    stale: bool,
 }
 
 impl<N, E> PriceUpdate<N, E>
@@ -58,22 +80,24 @@ where
     E: FromStr,
     <E as FromStr>::Err: fmt::Debug,
 {
+    // The marker of a quote formed by bid and ask prices.
+    pub const BID_ASK: &'static str = "BID_ASK";
+
     /// Create a new instance of `PriceUpdate` structure.
     pub fn new(
         timestamp: DateTime<FixedOffset>,
         exchange: N,
         source_currency: N,
         destination_currency: N,
-        forward_factor: E,
-        backward_factor: E,
+        quote: Quote<E>,
     ) -> Self {
         Self {
             timestamp,
             exchange,
             source_currency,
             destination_currency,
-            forward_factor,
-            backward_factor,
+            quote,
+            stale: false,
         }
     }
 
@@ -103,12 +127,112 @@ where
         &self.destination_currency
     }
 
-    pub fn get_forward_factor(&self) -> &E {
-        &self.forward_factor
+    pub fn get_quote(&self) -> &Quote<E> {
+        &self.quote
+    }
+
+    pub fn set_source_currency(&mut self, source_currency: N) {
+        self.source_currency = source_currency;
+    }
+
+    pub fn set_destination_currency(&mut self, destination_currency: N) {
+        self.destination_currency = destination_currency;
+    }
+
+    /// Check whether the quote is stale, i.e. the price may have moved since without notice.
+    pub fn is_stale(&self) -> bool {
+        self.stale
     }
 
-    pub fn get_backward_factor(&self) -> &E {
-        &self.backward_factor
+    /// Set whether the quote is stale, stale quotes give no rate until a newer one arrives.
+    pub fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+impl<N, E> PriceUpdate<N, E>
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: fmt::Debug,
+    E: FromStr + Zero + PartialOrd,
+    <E as FromStr>::Err: fmt::Debug,
+{
+    /// Parse JSON object and form a new `PriceUpdate` struct from it, collecting all errors.
+    ///
+    /// # `item` format
+    ///
+    /// {"timestamp":<timestamp>,"exchange":<exchange>,"source_currency":<source_currency>,
+    /// "destination_currency":<destination_currency>,"forward_factor":<number>,
+    /// "backward_factor":<number>}
+    ///
+    /// The `"bid"` and `"ask"` numbers are used instead of factors if present and the optional
+    /// `"stale"` boolean marks the quote stale.
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_json(item: &Value) -> Result<PriceUpdate<N, E>, Vec<String>> {
+        let mut errors = Vec::new();
+
+        let timestamp = field(item, "timestamp", &mut errors, |value| {
+            DateTime::parse_from_rfc3339(value).ok()
+        });
+        let exchange = identifier::<N>(item, "exchange", &mut errors);
+        let source_currency = identifier::<N>(item, "source_currency", &mut errors);
+        let destination_currency = identifier::<N>(item, "destination_currency", &mut errors);
+
+        // Bid and ask prices are used instead of factors if present.
+        let quote = if item.get("bid").is_some() || item.get("ask").is_some() {
+            match (
+                number::<E>(item, "bid", &mut errors),
+                number::<E>(item, "ask", &mut errors),
+            ) {
+                (Some(bid), Some(ask)) => Self::validate_bid_ask(bid, ask, &mut errors),
+                _ => None,
+            }
+        } else {
+            match (
+                number::<E>(item, "forward_factor", &mut errors),
+                number::<E>(item, "backward_factor", &mut errors),
+            ) {
+                (Some(forward), Some(backward)) => Some(Quote::Factors { forward, backward }),
+                _ => None,
+            }
+        };
+
+        let stale = match item.get("stale") {
+            Some(Value::Boolean(stale)) => *stale,
+            Some(_) => {
+                errors.push("The field <stale> can not be parsed (wrong format)!".to_string());
+                false
+            }
+            None => false,
+        };
+
+        match (
+            timestamp,
+            exchange,
+            source_currency,
+            destination_currency,
+            quote,
+        ) {
+            (
+                Some(timestamp),
+                Some(exchange),
+                Some(source_currency),
+                Some(destination_currency),
+                Some(quote),
+            ) if errors.is_empty() => {
+                let mut price_update = PriceUpdate::new(
+                    timestamp,
+                    exchange,
+                    source_currency,
+                    destination_currency,
+                    quote,
+                );
+                price_update.set_stale(stale);
+
+                Ok(price_update)
+            }
+            _ => Err(errors),
+        }
     }
 
     /// Parse input line and form a new `PriceUpdate` struct from it.
@@ -116,11 +240,13 @@ where
     /// # `line` format
     ///
     /// <timestamp> <exchange> <source_currency> <destination_currency> <forward_factor> <backward_factor>
+    /// <timestamp> <exchange> <source_currency> <destination_currency> BID_ASK <bid> <ask>
     ///
     /// ## Example
     ///
     /// 2019-08-01T08:42:22+00:00 BITFINEX BTC USD 1000.0 0.0009
-    pub fn parse_line(line: &str) -> Result<PriceUpdate<N, E>, Vec<String>> {
+    /// 2019-08-01T08:42:22+00:00 BITFINEX BTC USD BID_ASK 999.5 1000.5
+    pub fn parse_line(line: &str) -> Result<PriceUpdate<N, E>, Error> {
         let mut iter = line.split_whitespace();
         let mut values = HashMap::new();
         let mut errors: Vec<String> = Vec::new();
@@ -148,7 +274,7 @@ where
 
         // Continue only if none of the collected values is missing (no errors are present).
         if !errors.is_empty() {
-            return Err(errors);
+            return Err(Error::Parse(errors));
         }
 
         // Parse values.
@@ -160,24 +286,15 @@ where
             ));
         }
 
-        let forward_factor = values[&ForwardFactor].parse::<E>();
-        if forward_factor.is_err() {
-            errors.push(format!(
-                "The line item <{}> can not be parsed (wrong format)!",
-                &ForwardFactor
-            ));
-        }
-
-        let backward_factor = values[&BackwardFactor].parse::<E>();
-        if backward_factor.is_err() {
-            errors.push(format!(
-                "The line item <{}> can not be parsed (wrong format)!",
-                &BackwardFactor
-            ));
-        }
+        // Parse the quote, either bid and ask prices or forward and backward factors.
+        let quote = if uppercase(values[&ForwardFactor]) == Self::BID_ASK {
+            Self::parse_bid_ask(values[&BackwardFactor], iter.next(), &mut errors)
+        } else {
+            Self::parse_factors(values[&ForwardFactor], values[&BackwardFactor], &mut errors)
+        };
 
         // Making the rest of values uppercase to be more robust.
-        let exchange = values[&Exchange].to_uppercase().parse::<N>();
+        let exchange = uppercase(values[&Exchange]).parse::<N>();
         if exchange.is_err() {
             errors.push(format!(
                 "The line item <{}> can not be parsed (wrong format)!",
@@ -185,7 +302,7 @@ where
             ));
         }
 
-        let source_currency = values[&SourceCurrency].to_uppercase().parse::<N>();
+        let source_currency = uppercase(values[&SourceCurrency]).parse::<N>();
         if source_currency.is_err() {
             errors.push(format!(
                 "The line item <{}> can not be parsed (wrong format)!",
@@ -193,7 +310,7 @@ where
             ));
         }
 
-        let destination_currency = values[&DestinationCurrency].to_uppercase().parse::<N>();
+        let destination_currency = uppercase(values[&DestinationCurrency]).parse::<N>();
         if destination_currency.is_err() {
             errors.push(format!(
                 "The line item <{}> can not be parsed (wrong format)!",
@@ -203,7 +320,7 @@ where
 
         // Continue only if all values were parsed successfully (no errors are present).
         if !errors.is_empty() {
-            return Err(errors);
+            return Err(Error::Parse(errors));
         }
 
         Ok(Self::new(
@@ -211,21 +328,168 @@ where
             exchange.unwrap(),
             source_currency.unwrap(),
             destination_currency.unwrap(),
-            forward_factor.unwrap(),
-            backward_factor.unwrap(),
+            quote.unwrap(),
         ))
     }
+
+    /// Parse forward and backward factors, collecting errors.
+    fn parse_factors(forward: &str, backward: &str, errors: &mut Vec<String>) -> Option<Quote<E>> {
+        let forward = forward.parse::<E>();
+        if forward.is_err() {
+            errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &ForwardFactor
+            ));
+        }
+
+        let backward = backward.parse::<E>();
+        if backward.is_err() {
+            errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &BackwardFactor
+            ));
+        }
+
+        match (forward, backward) {
+            (Ok(forward), Ok(backward)) => Some(Quote::Factors { forward, backward }),
+            _ => None,
+        }
+    }
+
+    /// Parse bid and ask prices, collecting errors.
+    fn parse_bid_ask(bid: &str, ask: Option<&str>, errors: &mut Vec<String>) -> Option<Quote<E>> {
+        let bid = bid.parse::<E>();
+        if bid.is_err() {
+            errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &Bid
+            ));
+        }
+
+        let ask = match ask {
+            Some(ask) => ask.parse::<E>().ok(),
+            None => {
+                errors.push(format!("The line item <{}> is missing!", &Ask));
+                return None;
+            }
+        };
+        if ask.is_none() {
+            errors.push(format!(
+                "The line item <{}> can not be parsed (wrong format)!",
+                &Ask
+            ));
+        }
+
+        match (bid, ask) {
+            (Ok(bid), Some(ask)) => Self::validate_bid_ask(bid, ask, errors),
+            _ => None,
+        }
+    }
+
+    /// Validate bid and ask prices, both positive and not crossed, collecting errors.
+    fn validate_bid_ask(bid: E, ask: E, errors: &mut Vec<String>) -> Option<Quote<E>> {
+        let count = errors.len();
+        for (item, price) in [(Bid, &bid), (Ask, &ask)] {
+            // NaN isn't positive either.
+            if price.partial_cmp(&E::zero()) != Some(Greater) {
+                errors.push(format!("The line item <{}> is not positive!", item));
+            }
+        }
+        if errors.len() == count && bid > ask {
+            errors.push(format!(
+                "The line item <{}> is over the line item <{}> (crossed quote)!",
+                Bid, Ask
+            ));
+        }
+
+        if errors.len() == count {
+            Some(Quote::BidAsk { bid, ask })
+        } else {
+            None
+        }
+    }
+}
+
+impl<N, E> PriceUpdate<N, E>
+where
+    E: Num + Copy,
+{
+    /// Get rate of converting the source currency to the destination one.
+    ///
+    /// The bid price is used for the bid and ask quote.
+    pub fn get_forward_factor(&self) -> E {
+        match self.quote {
+            Quote::Factors { forward, .. } => forward,
+            Quote::BidAsk { bid, .. } => bid,
+        }
+    }
+
+    /// Get rate of converting the destination currency to the source one.
+    ///
+    /// The inverse of the ask price is used for the bid and ask quote.
+    pub fn get_backward_factor(&self) -> E {
+        match self.quote {
+            Quote::Factors { backward, .. } => backward,
+            Quote::BidAsk { ask, .. } => E::one() / ask,
+        }
+    }
+}
+
+impl<N, E> PriceUpdate<N, E>
+where
+    N: Clone + FromStr + fmt::Display,
+    <N as FromStr>::Err: fmt::Debug,
+    E: FromStr + fmt::Display,
+    <E as FromStr>::Err: fmt::Debug,
+{
+    /// Get input line of the `PriceUpdate`, in the format `parse_line` accepts.
+    pub fn get_line(&self) -> String {
+        let quote = match &self.quote {
+            Quote::Factors { forward, backward } => format!("{} {}", forward, backward),
+            Quote::BidAsk { bid, ask } => format!("{} {} {}", Self::BID_ASK, bid, ask),
+        };
+
+        format!(
+            "{} {} {} {} {}",
+            self.timestamp.to_rfc3339(),
+            self.exchange,
+            self.source_currency,
+            self.destination_currency,
+            quote
+        )
+    }
+
+    /// Get JSON representation of the `PriceUpdate`, in the format `parse_json` accepts.
+    pub fn get_json(&self) -> String {
+        let quote = match &self.quote {
+            Quote::Factors { forward, backward } => format!(
+                "\"forward_factor\":{},\"backward_factor\":{}",
+                forward, backward
+            ),
+            Quote::BidAsk { bid, ask } => format!("\"bid\":{},\"ask\":{}", bid, ask),
+        };
+
+        format!(
+            "{{\"timestamp\":{},\"exchange\":{},\"source_currency\":{},             \"destination_currency\":{},{},\"stale\":{}}}",
+            json::quote(&self.timestamp.to_rfc3339()),
+            json::quote(&self.exchange),
+            json::quote(&self.source_currency),
+            json::quote(&self.destination_currency),
+            quote,
+            self.stale
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::request::price_update::Items::*;
-    use crate::request::price_update::PriceUpdate;
+    use crate::request::price_update::{PriceUpdate, Quote};
 
     #[test]
     fn parse_line() {
         let line = "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009";
-        let price_update = PriceUpdate::<String, f32>::parse_line(&line.to_string());
+        let price_update = PriceUpdate::<String, f32>::parse_line(line);
 
         // Test that the line was parsed properly.
         assert!(price_update.is_ok());
@@ -241,20 +505,98 @@ mod tests {
         assert_eq!(price_update.exchange, "KRAKEN");
         assert_eq!(price_update.source_currency, "BTC");
         assert_eq!(price_update.destination_currency, "USD");
-        assert_eq!(price_update.forward_factor, 1000.0);
-        assert_eq!(price_update.backward_factor, 0.0009);
+        assert_eq!(
+            price_update.quote,
+            Quote::Factors {
+                forward: 1000.0,
+                backward: 0.0009
+            }
+        );
+
+        // Test that the same line parses to an equal price update.
+        assert_eq!(PriceUpdate::parse_line(line).unwrap(), price_update.clone());
+    }
+
+    #[test]
+    fn parse_line_with_bid_ask() {
+        let line = "2017-11-01T09:42:23+00:00 KRAKEN BTC USD bid_ask 999.0 1000.0";
+        let price_update = PriceUpdate::<String, f32>::parse_line(line).unwrap();
+
+        assert_eq!(
+            price_update.quote,
+            Quote::BidAsk {
+                bid: 999.0,
+                ask: 1000.0
+            }
+        );
+
+        // Test that the source currency is sold for the bid and bought for the ask price.
+        assert_eq!(price_update.get_forward_factor(), 999.0);
+        assert_eq!(price_update.get_backward_factor(), 0.001);
+
+        // Test that the missing ask price is reported.
+        let line = "2017-11-01T09:42:23+00:00 KRAKEN BTC USD BID_ASK 999.0";
+        let errors = PriceUpdate::<String, f32>::parse_line(line)
+            .err()
+            .unwrap()
+            .get_messages();
+        assert_eq!(errors, vec![format!("The line item <{}> is missing!", Ask)]);
+    }
+
+    #[test]
+    fn parse_line_with_invalid_bid_ask() {
+        let errors = |line: &str| {
+            PriceUpdate::<String, f32>::parse_line(line)
+                .err()
+                .unwrap()
+                .get_messages()
+        };
+
+        // Test that prices not positive are rejected.
+        assert_eq!(
+            errors("2017-11-01T09:42:23+00:00 KRAKEN BTC USD BID_ASK 1000 0"),
+            vec![format!("The line item <{}> is not positive!", Ask)]
+        );
+        assert_eq!(
+            errors("2017-11-01T09:42:23+00:00 KRAKEN BTC USD BID_ASK -1 NaN"),
+            vec![
+                format!("The line item <{}> is not positive!", Bid),
+                format!("The line item <{}> is not positive!", Ask)
+            ]
+        );
+
+        // Test that a crossed quote is rejected.
+        assert_eq!(
+            errors("2017-11-01T09:42:23+00:00 KRAKEN BTC USD BID_ASK 1001 1000"),
+            vec![format!(
+                "The line item <{}> is over the line item <{}> (crossed quote)!",
+                Bid, Ask
+            )]
+        );
+    }
+
+    #[test]
+    fn get_line() {
+        for line in [
+            "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000 0.0009",
+            "2017-11-01T09:42:23+00:00 KRAKEN BTC USD BID_ASK 999 1000.5",
+        ] {
+            let price_update = PriceUpdate::<String, f32>::parse_line(line).unwrap();
+
+            assert_eq!(price_update.get_line(), line);
+        }
     }
 
     #[test]
     fn parse_line_with_missing_values() {
         let line = "";
-        let price_update = PriceUpdate::<String, f32>::parse_line(&line.to_string());
+        let price_update = PriceUpdate::<String, f32>::parse_line(line);
 
         // Test that the line could not be parsed properly.
         assert!(price_update.is_err());
 
         // Unwrap errors as they should exist.
-        let mut errors = price_update.err().unwrap();
+        let mut errors = price_update.err().unwrap().get_messages();
 
         // Test that all errors are present.
         assert_eq!(
@@ -297,7 +639,7 @@ mod tests {
         assert!(price_update.is_err());
 
         // Unwrap errors as they should exist.
-        let mut errors = price_update.err().unwrap();
+        let mut errors = price_update.err().unwrap().get_messages();
 
         // Test that all errors are present.
         assert_eq!(
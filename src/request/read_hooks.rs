@@ -0,0 +1,182 @@
+//! Hooks observing the parsed input lines.
+
+use crate::error::Error;
+use crate::request::exchange_rate_request::ExchangeRateRequest;
+use crate::request::price_update::PriceUpdate;
+use crate::request::Line;
+
+/// Hook of a parsed `PriceUpdate`, `None` vetoes it.
+type PriceUpdateHook<'a, N, E> =
+    Box<dyn FnMut(PriceUpdate<N, E>) -> Option<PriceUpdate<N, E>> + 'a>;
+
+/// Hook of a parsed `ExchangeRateRequest`, `None` vetoes it.
+type RateRequestHook<'a, N, E> =
+    Box<dyn FnMut(ExchangeRateRequest<N, E>) -> Option<ExchangeRateRequest<N, E>> + 'a>;
+
+/// Hook of an input line that can't be parsed.
+type ErrorHook<'a> = Box<dyn FnMut(&str, Error) + 'a>;
+
+/// `ReadHooks` structure.
+///
+/// Callbacks of `Request::read_with_hooks` called for each parsed input line before it's added.
+/// A callback may observe the record, transform it by returning another one, or veto it by
+/// returning `None`. Lines that can't be parsed are passed to the error callback and skipped,
+/// without it the reading panics like `Request::read_from`.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::{ReadHooks, Request};
+/// use std::io::BufReader;
+///
+/// let text_input = "2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001\n\
+///                   2019-01-20T09:42:23+00:00 GDAX BTC USD 1001 0.001\n\
+///                   EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD\n\
+///                   EXCHANGE_RATE_REQUEST KRAKEN\n";
+///
+/// let mut errors = 0;
+/// let mut hooks = ReadHooks::<String, f32>::new()
+///     .on_price_update(|price_update| {
+///         Some(price_update).filter(|price_update| price_update.get_exchange() == "KRAKEN")
+///     })
+///     .on_error(|_, _| errors += 1);
+/// let request = Request::read_with_hooks(&mut BufReader::new(text_input.as_bytes()), &mut hooks);
+/// drop(hooks);
+///
+/// assert_eq!(request.get_price_updates().len(), 1);
+/// assert_eq!(request.get_rate_requests().len(), 1);
+/// assert_eq!(errors, 1);
+/// ```
+///
+/// # `ReadHooks<'a, N, E>` is parameterized over:
+///
+/// - Lifetime `'a` of the data the callbacks borrow.
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct ReadHooks<'a, N, E> {
+    on_price_update: Option<PriceUpdateHook<'a, N, E>>,
+    on_rate_request: Option<RateRequestHook<'a, N, E>>,
+    on_error: Option<ErrorHook<'a>>,
+}
+
+impl<'a, N, E> ReadHooks<'a, N, E> {
+    /// Create a new instance of `ReadHooks` structure without any callback.
+    pub fn new() -> Self {
+        Self {
+            on_price_update: None,
+            on_rate_request: None,
+            on_error: None,
+        }
+    }
+
+    /// Set the callback of each parsed `PriceUpdate`, the one returned is added instead.
+    pub fn on_price_update<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(PriceUpdate<N, E>) -> Option<PriceUpdate<N, E>> + 'a,
+    {
+        self.on_price_update = Some(Box::new(hook));
+        self
+    }
+
+    /// Set the callback of each parsed `ExchangeRateRequest`, the one returned is added instead.
+    pub fn on_rate_request<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(ExchangeRateRequest<N, E>) -> Option<ExchangeRateRequest<N, E>> + 'a,
+    {
+        self.on_rate_request = Some(Box::new(hook));
+        self
+    }
+
+    /// Set the callback of each input line that can't be parsed, together with its error.
+    pub fn on_error<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&str, Error) + 'a,
+    {
+        self.on_error = Some(Box::new(hook));
+        self
+    }
+
+    /// Pass the parsed line to its callback, `None` is returned if it's vetoed.
+    pub(crate) fn apply(&mut self, line: Line<N, E>) -> Option<Line<N, E>> {
+        match line {
+            Line::PriceUpdate(price_update) => match &mut self.on_price_update {
+                Some(hook) => hook(price_update).map(Line::PriceUpdate),
+                None => Some(Line::PriceUpdate(price_update)),
+            },
+            Line::RateRequest(rate_request) => match &mut self.on_rate_request {
+                Some(hook) => hook(rate_request).map(Line::RateRequest),
+                None => Some(Line::RateRequest(rate_request)),
+            },
+            line => Some(line),
+        }
+    }
+
+    /// Pass the error of the input line to its callback, panicking if there's none.
+    pub(crate) fn handle_error(&mut self, line: &str, error: Error) {
+        match &mut self.on_error {
+            Some(hook) => hook(line, error),
+            // The errors handling can be done better. Probably using logging mechanism
+            // or just outputting it to the `std::io::stderr`, letting the process continue
+            // and thus being more robust.
+            None => panic!(
+                "Errors occurred while processing input lines, errors: {:?}!",
+                error.get_messages()
+            ),
+        }
+    }
+}
+
+impl<'a, N, E> Default for ReadHooks<'a, N, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request::read_hooks::ReadHooks;
+    use crate::request::Request;
+    use std::io::BufReader;
+
+    #[test]
+    fn read_with_hooks() {
+        let text_input = "2019-01-20T09:42:23+00:00 E1 XBT USD 1000 0.001
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD
+EXCHANGE_RATE_REQUEST E1 BTC E2 USD
+wrong line";
+        let mut input = BufReader::new(text_input.as_bytes());
+
+        let mut lines = Vec::new();
+        let mut hooks = ReadHooks::<String, f32>::new()
+            .on_price_update(|mut price_update| {
+                if price_update.get_source_currency() == "XBT" {
+                    price_update.set_source_currency("BTC".to_string());
+                }
+                Some(price_update)
+            })
+            .on_rate_request(|rate_request| {
+                Some(rate_request)
+                    .filter(|rate_request| rate_request.get_destination_exchange() == "E1")
+            })
+            .on_error(|line, _| lines.push(line.to_string()));
+        let request = Request::read_with_hooks(&mut input, &mut hooks);
+        drop(hooks);
+
+        // Test that the currency of the price update is renamed and the request to E2 vetoed.
+        let price_updates = request.get_price_updates();
+        assert!(price_updates.contains_key(&(
+            "E1".to_string(),
+            "BTC".to_string(),
+            "USD".to_string()
+        )));
+        assert_eq!(request.get_rate_requests().len(), 1);
+        assert_eq!(lines, vec!["wrong line".to_string()]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn read_with_hooks_without_error_hook() {
+        let mut input = BufReader::new("wrong line".as_bytes());
+
+        Request::<String, f32>::read_with_hooks(&mut input, &mut ReadHooks::new());
+    }
+}
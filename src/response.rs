@@ -1,9 +1,33 @@
 //! Exchange Rate Path Response.
 
+pub mod arbitrage;
+#[cfg(feature = "std")]
+pub mod arbitrage_alert;
 pub mod best_rate_path;
+pub mod instruction;
+pub mod phase_timings;
+#[cfg(feature = "std")]
+pub mod rate_alert;
+#[cfg(feature = "std")]
+pub mod rejection;
+pub mod summary;
+pub mod unanswered_request;
 
+use self::arbitrage::{Arbitrage, UnansweredArbitrageRequest};
 use self::best_rate_path::BestRatePath;
+use self::phase_timings::PhaseTimings;
+use self::summary::Summary;
+use self::unanswered_request::UnansweredRequest;
+use crate::clock::Instant;
+use crate::json;
+use crate::memory;
+use chrono::{DateTime, FixedOffset};
+use num_traits::Num;
+use std::fmt;
 use std::fmt::{Debug, Display};
+use std::slice::Iter;
+use std::time::Duration;
+use std::vec::IntoIter;
 
 /// Exchange Rate Path `Response` structure.
 ///
@@ -11,43 +35,608 @@ use std::fmt::{Debug, Display};
 ///
 /// - Identifier data `N`.
 /// - Edge weight `E`.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Response<N, E> {
     best_rate_paths: Vec<BestRatePath<N, E>>,
+    unanswered_requests: Vec<UnansweredRequest<N>>,
+    arbitrages: Vec<Arbitrage<N, E>>,
+    unanswered_arbitrage_requests: Vec<UnansweredArbitrageRequest<N>>,
+    compute_time: Duration,
+    graph_build_time: Option<Duration>,
+    parse_time: Option<Duration>,
+    solve_time: Option<Duration>,
+    graph_size: Option<(usize, usize)>,
+    include_summary: bool,
+    include_timings: bool,
+    include_stamp: bool,
+    arbitrage_json: bool,
+    version: u64,
+    as_of: Option<DateTime<FixedOffset>>,
+    computed_at: Option<DateTime<FixedOffset>>,
 }
 
 impl<N, E> Response<N, E>
 where
     N: Display + Debug,
-    E: Display,
+    E: Display + PartialOrd + Num + Copy,
 {
+    /// Create a new instance of empty `Response` structure.
     pub fn new() -> Self {
         Self {
             best_rate_paths: Vec::new(),
+            unanswered_requests: Vec::new(),
+            arbitrages: Vec::new(),
+            unanswered_arbitrage_requests: Vec::new(),
+            compute_time: Duration::default(),
+            graph_build_time: None,
+            parse_time: None,
+            solve_time: None,
+            graph_size: None,
+            include_summary: false,
+            include_timings: false,
+            include_stamp: false,
+            arbitrage_json: false,
+            version: 0,
+            as_of: None,
+            computed_at: None,
         }
     }
 
+    /// Add the `BestRatePath` answering an Exchange Rate Request.
     pub fn add_best_rate_path(&mut self, best_rate_path: BestRatePath<N, E>) {
         self.best_rate_paths.push(best_rate_path);
     }
 
-    #[allow(dead_code)]
-    pub fn get_best_rate_paths(&self) -> &Vec<BestRatePath<N, E>> {
+    /// Add an Exchange Rate Request that could not be answered, together with the reason.
+    pub fn add_unanswered_request(&mut self, unanswered_request: UnansweredRequest<N>) {
+        self.unanswered_requests.push(unanswered_request);
+    }
+
+    /// Add all answers of the other `Response`, e.g. of requests answered against another state.
+    pub fn extend(&mut self, response: Response<N, E>) {
+        self.best_rate_paths.extend(response.best_rate_paths);
+        self.unanswered_requests
+            .extend(response.unanswered_requests);
+        self.arbitrages.extend(response.arbitrages);
+        self.unanswered_arbitrage_requests
+            .extend(response.unanswered_arbitrage_requests);
+        self.compute_time += response.compute_time;
+        self.graph_build_time = add_times(self.graph_build_time, response.graph_build_time);
+        self.parse_time = add_times(self.parse_time, response.parse_time);
+        self.solve_time = add_times(self.solve_time, response.solve_time);
+        self.graph_size = self.graph_size.max(response.graph_size);
+        self.version = self.version.max(response.version);
+        self.as_of = self.as_of.max(response.as_of);
+        self.computed_at = self.computed_at.max(response.computed_at);
+    }
+
+    pub fn add_arbitrage(&mut self, arbitrage: Arbitrage<N, E>) {
+        self.arbitrages.push(arbitrage);
+    }
+
+    /// Add an Arbitrage Request for which no profitable cycle was found.
+    pub fn add_unanswered_arbitrage_request(
+        &mut self,
+        unanswered_arbitrage_request: UnansweredArbitrageRequest<N>,
+    ) {
+        self.unanswered_arbitrage_requests
+            .push(unanswered_arbitrage_request);
+    }
+
+    /// Set total time spent by computing the Response.
+    pub fn set_compute_time(&mut self, compute_time: Duration) {
+        self.compute_time = compute_time;
+    }
+
+    /// Get total time spent by computing the Response.
+    pub fn get_compute_time(&self) -> Duration {
+        self.compute_time
+    }
+
+    /// Set time spent by building the graph, a part of the compute time.
+    pub fn set_graph_build_time(&mut self, graph_build_time: Duration) {
+        self.graph_build_time = Some(graph_build_time);
+    }
+
+    /// Get time spent by building the graph, `None` if no graph was needed.
+    pub fn get_graph_build_time(&self) -> Option<Duration> {
+        self.graph_build_time
+    }
+
+    /// Set time spent by parsing the input lines of the `Request`.
+    pub fn set_parse_time(&mut self, parse_time: Duration) {
+        self.parse_time = Some(parse_time);
+    }
+
+    /// Get time spent by parsing the input lines, `None` if the `Request` wasn't parsed by the
+    /// caller of the computation.
+    pub fn get_parse_time(&self) -> Option<Duration> {
+        self.parse_time
+    }
+
+    /// Set time spent by solving the best rate paths, a part of the compute time.
+    pub fn set_solve_time(&mut self, solve_time: Duration) {
+        self.solve_time = Some(solve_time);
+    }
+
+    /// Get time spent by solving the best rate paths, `None` if no graph was needed. Paths of the
+    /// `BellmanFord` backend are solved on their first use, so while answering the requests.
+    pub fn get_solve_time(&self) -> Option<Duration> {
+        self.solve_time
+    }
+
+    /// Set counts of the nodes and the edges of the graph the Response was answered from.
+    pub fn set_graph_size(&mut self, node_count: usize, edge_count: usize) {
+        self.graph_size = Some((node_count, edge_count));
+    }
+
+    /// Get counts of the nodes and the edges of the graph, `None` if no graph was needed.
+    pub fn get_graph_size(&self) -> Option<(usize, usize)> {
+        self.graph_size
+    }
+
+    /// Get `PhaseTimings` of the Response, the render time is known to the printable output only.
+    pub fn get_phase_timings(&self) -> PhaseTimings {
+        PhaseTimings::new(
+            self.parse_time,
+            self.graph_build_time,
+            self.solve_time,
+            None,
+        )
+    }
+
+    /// Set version of the engine state the Response was answered from.
+    pub fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+
+    /// Get version of the engine state the Response was answered from, `0` if it wasn't answered
+    /// by an engine.
+    pub fn get_version(&self) -> u64 {
+        self.version
+    }
+
+    /// Set the timestamp of the most recent quote the Response was answered from.
+    pub fn set_as_of(&mut self, as_of: Option<DateTime<FixedOffset>>) {
+        self.as_of = as_of;
+    }
+
+    /// Get the timestamp of the most recent quote the Response was answered from, `None` if it
+    /// wasn't answered from any quote.
+    pub fn get_as_of(&self) -> Option<&DateTime<FixedOffset>> {
+        self.as_of.as_ref()
+    }
+
+    /// Set the moment the Response was computed at.
+    pub fn set_computed_at(&mut self, computed_at: DateTime<FixedOffset>) {
+        self.computed_at = Some(computed_at);
+    }
+
+    /// Get the moment the Response was computed at, `None` if it wasn't answered from a graph.
+    pub fn get_computed_at(&self) -> Option<&DateTime<FixedOffset>> {
+        self.computed_at.as_ref()
+    }
+
+    /// Set whether the `Summary` should be appended to the printable output.
+    pub fn set_include_summary(&mut self, include_summary: bool) {
+        self.include_summary = include_summary;
+    }
+
+    /// Set whether the `PhaseTimings` line should be appended to the printable output.
+    pub fn set_include_timings(&mut self, include_timings: bool) {
+        self.include_timings = include_timings;
+    }
+
+    /// Set whether the `AS_OF` line stamping the block by the timestamp of the most recent quote
+    /// and the moment of the computation should be appended to the printable output, e.g. for
+    /// archived results to be audited.
+    pub fn set_include_stamp(&mut self, include_stamp: bool) {
+        self.include_stamp = include_stamp;
+    }
+
+    /// Set whether `Arbitrage`s should be written out as JSON lines instead of text blocks.
+    pub fn set_arbitrage_json(&mut self, arbitrage_json: bool) {
+        self.arbitrage_json = arbitrage_json;
+    }
+
+    /// Get `Summary` statistics of the Response.
+    pub fn get_summary(&self) -> Summary<E> {
+        let mut best_rate: Option<E> = None;
+        let mut worst_rate: Option<E> = None;
+        let mut total_path_length = 0;
+
+        for best_rate_path in self.best_rate_paths.iter() {
+            let rate = *best_rate_path.get_rate();
+
+            if best_rate.is_none_or(|best| rate > best) {
+                best_rate = Some(rate);
+            }
+            if worst_rate.is_none_or(|worst| rate < worst) {
+                worst_rate = Some(rate);
+            }
+
+            total_path_length += best_rate_path.get_path().len();
+        }
+
+        let answered = self.best_rate_paths.len();
+        let average_path_length = if answered > 0 {
+            total_path_length as f64 / answered as f64
+        } else {
+            0.0
+        };
+
+        Summary::new(
+            answered,
+            self.unanswered_requests.len(),
+            best_rate,
+            worst_rate,
+            average_path_length,
+            self.compute_time,
+        )
+    }
+
+    /// Get the `BestRatePath`s answering Exchange Rate Requests, in their order.
+    pub fn get_best_rate_paths(&self) -> &[BestRatePath<N, E>] {
         &self.best_rate_paths
     }
 
+    /// Get the Exchange Rate Requests that could not be answered, together with the reasons.
+    pub fn get_unanswered_requests(&self) -> &[UnansweredRequest<N>] {
+        &self.unanswered_requests
+    }
+
+    /// Get the `Arbitrage`s found.
+    pub fn get_arbitrages(&self) -> &[Arbitrage<N, E>] {
+        &self.arbitrages
+    }
+
+    /// Get the Arbitrage Requests that could not be answered.
+    pub fn get_unanswered_arbitrage_requests(&self) -> &[UnansweredArbitrageRequest<N>] {
+        &self.unanswered_arbitrage_requests
+    }
+
+    /// Get an iterator over all `BestRatePath`s of the Response.
+    pub fn iter(&self) -> Iter<'_, BestRatePath<N, E>> {
+        self.best_rate_paths.iter()
+    }
+
     /// Get printable output representing the Response.
     ///
-    /// Concatenate all outputs of `BestRatePath`s.
+    /// Concatenate all outputs of `BestRatePath`s, `UnansweredRequest`s, `Arbitrage`s (text or
+    /// JSON) and `UnansweredArbitrageRequest`s, optionally followed by the `Summary` output and
+    /// the `PhaseTimings` line.
     pub fn get_output(&self) -> String {
         let mut output = String::new();
+        self.render(|part| output.push_str(&part));
+
+        output
+    }
+
+    /// Get JSON object of best rate paths and unanswered requests, as the HTTP API answers.
+    ///
+    /// # Format
+    ///
+    /// {"best_rate_paths":[{"rate":<rate>,"path":[{"exchange":<exchange>,"currency":<currency>},
+    /// ...]}, ...],"unanswered_requests":[{"source":<node>,"destination":<node>,"reason":<reason>},
+    /// ...]}
+    pub fn get_json(&self) -> String {
+        let nodes = |path: &[(N, N)]| -> Vec<String> {
+            path.iter()
+                .map(|(exchange, currency)| json::node(exchange, currency))
+                .collect()
+        };
+
+        let best_rate_paths: Vec<String> = self
+            .get_best_rate_paths()
+            .iter()
+            .map(|path| {
+                format!(
+                    "{{\"rate\":{},\"path\":[{}]}}",
+                    path.get_rate(),
+                    nodes(path.get_path()).join(",")
+                )
+            })
+            .collect();
+
+        let unanswered_requests: Vec<String> = self
+            .get_unanswered_requests()
+            .iter()
+            .map(|request| {
+                let (source_exchange, source_currency) = request.get_source();
+                let (destination_exchange, destination_currency) = request.get_destination();
+
+                format!(
+                    "{{\"source\":{},\"destination\":{},\"reason\":{}}}",
+                    json::node(source_exchange, source_currency),
+                    json::node(destination_exchange, destination_currency),
+                    json::quote(&request.get_reason())
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"best_rate_paths\":[{}],\"unanswered_requests\":[{}]}}",
+            best_rate_paths.join(","),
+            unanswered_requests.join(",")
+        )
+    }
+
+    /// Render the output part by part in its order, e.g. to write the parts rendered already
+    /// while the rest is being rendered.
+    pub fn render(&self, mut write: impl FnMut(String)) {
+        let _phase = memory::phase("render");
+        let start = Instant::now();
 
         for best_rate_path in self.best_rate_paths.iter() {
-            output.push_str(&best_rate_path.get_output());
+            write(best_rate_path.get_output());
         }
 
+        for unanswered_request in self.unanswered_requests.iter() {
+            write(unanswered_request.get_output());
+        }
+
+        for arbitrage in self.arbitrages.iter() {
+            if self.arbitrage_json {
+                write(arbitrage.get_json() + "\n");
+            } else {
+                write(arbitrage.get_output());
+            }
+        }
+
+        for unanswered_arbitrage_request in self.unanswered_arbitrage_requests.iter() {
+            write(unanswered_arbitrage_request.get_output());
+        }
+
+        if self.include_summary {
+            write(self.get_summary().get_output());
+        }
+
+        if self.include_timings {
+            let phase_timings = PhaseTimings::new(
+                self.parse_time,
+                self.graph_build_time,
+                self.solve_time,
+                Some(start.elapsed()),
+            );
+            write(phase_timings.get_output());
+        }
+
+        if self.include_stamp && !self.is_empty() {
+            write(self.get_stamp_output());
+        }
+    }
+
+    /// Check whether it has no answer at all, so there's no block of output to stamp.
+    fn is_empty(&self) -> bool {
+        self.best_rate_paths.is_empty()
+            && self.unanswered_requests.is_empty()
+            && self.arbitrages.is_empty()
+            && self.unanswered_arbitrage_requests.is_empty()
+    }
+
+    /// Get printable output of the `AS_OF` line.
+    ///
+    /// # Format
+    ///
+    /// AS_OF <quotes, timestamp> <computed, timestamp>
+    ///
+    /// The timestamp of the most recent quote and the moment of the computation are omitted if
+    /// they're not known.
+    fn get_stamp_output(&self) -> String {
+        let mut output = String::from("AS_OF");
+        if let Some(as_of) = self.as_of {
+            output.push_str(&format!(" <quotes, {}>", as_of.to_rfc3339()));
+        }
+        if let Some(computed_at) = self.computed_at {
+            output.push_str(&format!(" <computed, {}>", computed_at.to_rfc3339()));
+        }
+        output.push('\n');
+
         output
     }
 }
 
+/// Add up times of two phases, `None` if neither of them was timed.
+fn add_times(time: Option<Duration>, other_time: Option<Duration>) -> Option<Duration> {
+    match (time, other_time) {
+        (Some(time), Some(other_time)) => Some(time + other_time),
+        (time, other_time) => time.or(other_time),
+    }
+}
+
+impl<N, E> Default for Response<N, E>
+where
+    N: Display + Debug,
+    E: Display + PartialOrd + Num + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, E> fmt::Display for Response<N, E>
+where
+    N: Display + Debug,
+    E: Display + PartialOrd + Num + Copy,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_output())
+    }
+}
+
+impl<N, E> IntoIterator for Response<N, E> {
+    type Item = BestRatePath<N, E>;
+    type IntoIter = IntoIter<BestRatePath<N, E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.best_rate_paths.into_iter()
+    }
+}
+
+impl<'a, N, E> IntoIterator for &'a Response<N, E> {
+    type Item = &'a BestRatePath<N, E>;
+    type IntoIter = Iter<'a, BestRatePath<N, E>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.best_rate_paths.iter()
+    }
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use crate::response::arbitrage::Arbitrage;
+    use crate::response::best_rate_path::BestRatePath;
+    use crate::response::unanswered_request::Reason::NoPath;
+    use crate::response::unanswered_request::UnansweredRequest;
+    use crate::response::Response;
+    use std::time::Duration;
+
+    fn prepare_response() -> Response<String, f32> {
+        let mut response = Response::<String, f32>::new();
+
+        response.add_best_rate_path(BestRatePath::new(
+            10.2,
+            vec![
+                ("a".to_string(), "b".to_string()),
+                ("c".to_string(), "d".to_string()),
+            ],
+        ));
+        response.add_best_rate_path(BestRatePath::new(
+            1.0,
+            vec![
+                ("c".to_string(), "d".to_string()),
+                ("e".to_string(), "d".to_string()),
+            ],
+        ));
+
+        response
+    }
+
+    #[test]
+    fn display() {
+        let response = prepare_response();
+
+        assert_eq!(format!("{}", response), response.get_output());
+    }
+
+    #[test]
+    fn into_iter() {
+        let response = prepare_response();
+
+        // Test iteration over borrowed `Response`.
+        let rates: Vec<f32> = (&response).into_iter().map(|p| *p.get_rate()).collect();
+        assert_eq!(rates, vec![10.2, 1.0]);
+
+        // Test iteration over owned `Response`.
+        let paths: Vec<BestRatePath<String, f32>> = response.into_iter().collect();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(
+            paths[1].get_end_node(),
+            Some(&("e".to_string(), "d".to_string()))
+        );
+    }
+
+    #[test]
+    fn get_output_with_stamp() {
+        let mut response = prepare_response();
+        let output = response.get_output();
+        let timestamp = |text| chrono::DateTime::parse_from_rfc3339(text).unwrap();
+        response.set_as_of(Some(timestamp("2019-01-20T09:42:23+00:00")));
+        response.set_computed_at(timestamp("2019-01-20T10:00:00+00:00"));
+
+        // Test that the stamp is not included by default.
+        assert_eq!(response.get_output(), output);
+
+        response.set_include_stamp(true);
+        assert_eq!(
+            response.get_output(),
+            format!(
+                "{}AS_OF <quotes, 2019-01-20T09:42:23+00:00> <computed, 2019-01-20T10:00:00+00:00>\n",
+                output
+            )
+        );
+
+        // Test that an empty response is not stamped.
+        let mut response = Response::<String, f32>::new();
+        response.set_include_stamp(true);
+        assert_eq!(response.get_output(), "");
+    }
+
+    #[test]
+    fn get_output_with_arbitrage_json() {
+        let mut response = Response::<String, f32>::new();
+        response.add_arbitrage(Arbitrage::new(
+            2.0,
+            vec![
+                ("a".to_string(), "b".to_string()),
+                ("a".to_string(), "c".to_string()),
+                ("a".to_string(), "b".to_string()),
+            ],
+            vec![4.0, 0.5],
+        ));
+
+        assert!(response.get_output().starts_with("ARBITRAGE_BEGIN"));
+
+        response.set_arbitrage_json(true);
+        assert_eq!(
+            response.get_output(),
+            format!("{}\n", response.get_arbitrages()[0].get_json())
+        );
+    }
+
+    #[test]
+    fn get_summary() {
+        let mut response = prepare_response();
+        response.add_unanswered_request(UnansweredRequest::new(
+            ("a".to_string(), "b".to_string()),
+            ("x".to_string(), "y".to_string()),
+            NoPath,
+        ));
+
+        let summary = response.get_summary();
+
+        assert_eq!(summary.get_answered(), 2);
+        assert_eq!(summary.get_unanswered(), 1);
+        assert_eq!(summary.get_best_rate(), Some(&10.2));
+        assert_eq!(summary.get_worst_rate(), Some(&1.0));
+        assert_eq!(summary.get_average_path_length(), 2.0);
+    }
+
+    #[test]
+    fn get_output_with_timings() {
+        let mut response = prepare_response();
+        let output = response.get_output();
+        response.set_parse_time(Duration::from_micros(12));
+        response.set_graph_build_time(Duration::from_micros(30));
+
+        // Test that the timings are not included by default.
+        assert_eq!(response.get_output(), output);
+        assert_eq!(
+            response.get_phase_timings().get_output(),
+            "PHASE_TIMINGS <parse_us, 12> <graph_build_us, 30>\n"
+        );
+
+        response.set_include_timings(true);
+
+        let output_with_timings = response.get_output();
+        assert!(output_with_timings.starts_with(&output));
+        assert!(output_with_timings[output.len()..]
+            .starts_with("PHASE_TIMINGS <parse_us, 12> <graph_build_us, 30> <render_us, "));
+    }
+
+    #[test]
+    fn get_output_with_summary() {
+        let mut response = prepare_response();
+        let output = response.get_output();
+
+        // Test that the summary is not included by default.
+        assert!(!output.contains("SUMMARY_BEGIN"));
+
+        response.set_include_summary(true);
+
+        assert_eq!(
+            response.get_output(),
+            format!("{}{}", output, response.get_summary().get_output())
+        );
+    }
+}
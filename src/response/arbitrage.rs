@@ -0,0 +1,285 @@
+//! Arbitrage.
+
+use crate::json;
+use num_traits::Num;
+use std::fmt;
+use std::fmt::{Debug, Display};
+
+/// Exchange `Arbitrage` structure.
+///
+/// A cycle of `(exchange, currency)` nodes starting and ending at the same node whose rates
+/// product (the profit factor) exceeds one. Rates of the cycle edges are kept as well.
+///
+/// # `Arbitrage<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arbitrage<N, E> {
+    profit_factor: E,
+    cycle: Vec<(N, N)>,
+    rates: Vec<E>,
+}
+
+impl<N, E> Arbitrage<N, E>
+where
+    N: Display + Debug,
+    E: Display + Num + Copy,
+{
+    pub fn new(profit_factor: E, cycle: Vec<(N, N)>, rates: Vec<E>) -> Self {
+        Self {
+            profit_factor,
+            cycle,
+            rates,
+        }
+    }
+
+    pub fn get_profit_factor(&self) -> &E {
+        &self.profit_factor
+    }
+
+    /// Get the cycle, the first and the last node are the same.
+    pub fn get_cycle(&self) -> &[(N, N)] {
+        &self.cycle
+    }
+
+    /// Get rates of the cycle edges, the `i`-th rate belongs to the edge leaving the `i`-th node.
+    pub fn get_rates(&self) -> &[E] {
+        &self.rates
+    }
+
+    /// Get estimated profit of a single unit of the start currency traded along the cycle.
+    pub fn get_profit_per_unit(&self) -> E {
+        self.profit_factor - E::one()
+    }
+
+    pub fn get_start_node(&self) -> Option<&(N, N)> {
+        self.cycle.first()
+    }
+
+    /// Get printable output representing the Arbitrage.
+    ///
+    /// # Format
+    ///
+    /// ARBITRAGE_BEGIN <start_exchange> <start_currency> <profit_factor>
+    /// <start_exchange, start_currency>
+    /// <exchange, currency>
+    /// ...
+    /// <start_exchange, start_currency>
+    /// ARBITRAGE_END
+    ///
+    /// ## Example
+    ///
+    /// ARBITRAGE_BEGIN <a> <b> <1.02>
+    /// <a, b>
+    /// <c, d>
+    /// <e, f>
+    /// <a, b>
+    /// ARBITRAGE_END
+    pub fn get_output(&self) -> String {
+        // Return empty string in case the cycle is not valid.
+        if self.cycle.len() < 2 {
+            return String::new();
+        }
+
+        let (start_exchange, start_currency) = self.cycle.first().unwrap();
+
+        format!(
+            "ARBITRAGE_BEGIN <{}> <{}> <{}>\n\
+             {}\
+             ARBITRAGE_END\n",
+            start_exchange,
+            start_currency,
+            self.get_profit_factor(),
+            self.get_cycle_output(),
+        )
+    }
+
+    /// Get JSON representation of the Arbitrage, a single line without the trailing newline.
+    ///
+    /// # Format
+    ///
+    /// {"exchange":<start_exchange>,"currency":<start_currency>,"profit_factor":<number>,
+    /// "profit_per_unit":<number>,"cycle":[<node>, ...],
+    /// "edges":[{"from":<node>,"to":<node>,"rate":<number>}, ...]}
+    ///
+    /// Where <node> is {"exchange":<exchange>,"currency":<currency>}.
+    pub fn get_json(&self) -> String {
+        // Return empty object in case the cycle is not valid.
+        if self.cycle.len() < 2 {
+            return String::from("{}");
+        }
+
+        let (start_exchange, start_currency) = self.cycle.first().unwrap();
+
+        let cycle: Vec<String> = self
+            .cycle
+            .iter()
+            .map(|(exchange, currency)| json::node(exchange, currency))
+            .collect();
+
+        let edges: Vec<String> = self
+            .cycle
+            .windows(2)
+            .zip(self.rates.iter())
+            .map(|(pair, rate)| {
+                format!(
+                    "{{\"from\":{},\"to\":{},\"rate\":{}}}",
+                    json::node(&pair[0].0, &pair[0].1),
+                    json::node(&pair[1].0, &pair[1].1),
+                    rate
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"exchange\":{},\"currency\":{},\"profit_factor\":{},\"profit_per_unit\":{},\
+             \"cycle\":[{}],\"edges\":[{}]}}",
+            json::quote(start_exchange),
+            json::quote(start_currency),
+            self.profit_factor,
+            self.get_profit_per_unit(),
+            cycle.join(","),
+            edges.join(","),
+        )
+    }
+
+    /// Get printable output of the cycle.
+    pub fn get_cycle_output(&self) -> String {
+        let mut output = String::new();
+
+        for (a, b) in self.get_cycle() {
+            output.push_str(&format!("<{}, {}>\n", a, b));
+        }
+
+        output
+    }
+}
+
+/// `UnansweredArbitrageRequest` structure.
+///
+/// An Arbitrage Request for which no profitable cycle was found. `None` identifiers stand for
+/// any exchange or any currency.
+///
+/// # `UnansweredArbitrageRequest<N>` is parameterized over:
+///
+/// - Identifier data `N`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnansweredArbitrageRequest<N> {
+    exchange: Option<N>,
+    currency: Option<N>,
+}
+
+impl<N> UnansweredArbitrageRequest<N>
+where
+    N: Display,
+{
+    pub fn new(exchange: Option<N>, currency: Option<N>) -> Self {
+        Self { exchange, currency }
+    }
+
+    pub fn get_exchange(&self) -> Option<&N> {
+        self.exchange.as_ref()
+    }
+
+    pub fn get_currency(&self) -> Option<&N> {
+        self.currency.as_ref()
+    }
+
+    /// Get printable output representing the Unanswered Arbitrage Request.
+    ///
+    /// # Format
+    ///
+    /// ARBITRAGE_UNAVAILABLE <exchange> <currency>
+    ///
+    /// ## Example
+    ///
+    /// ARBITRAGE_UNAVAILABLE <KRAKEN> <ANY>
+    pub fn get_output(&self) -> String {
+        format!(
+            "ARBITRAGE_UNAVAILABLE <{}> <{}>\n",
+            Self::get_item_output(self.exchange.as_ref()),
+            Self::get_item_output(self.currency.as_ref()),
+        )
+    }
+
+    fn get_item_output(item: Option<&N>) -> String {
+        match item {
+            Some(item) => item.to_string(),
+            None => "ANY".to_string(),
+        }
+    }
+}
+
+impl<N, E> fmt::Display for Arbitrage<N, E>
+where
+    N: Display + Debug,
+    E: Display + Num + Copy,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_output())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::response::arbitrage::{Arbitrage, UnansweredArbitrageRequest};
+
+    #[test]
+    fn get_output() {
+        let cycle: Vec<(String, String)> = vec![
+            ("a".to_string(), "b".to_string()),
+            ("c".to_string(), "d".to_string()),
+            ("e".to_string(), "f".to_string()),
+            ("a".to_string(), "b".to_string()),
+        ];
+
+        let arbitrage = Arbitrage::<String, f32>::new(1.02, cycle, vec![2.0, 0.5, 1.02]);
+
+        assert_eq!(
+            arbitrage.get_output(),
+            String::from(
+                "ARBITRAGE_BEGIN <a> <b> <1.02>\n\
+                 <a, b>\n\
+                 <c, d>\n\
+                 <e, f>\n\
+                 <a, b>\n\
+                 ARBITRAGE_END\n"
+            )
+        );
+    }
+
+    #[test]
+    fn get_json() {
+        let cycle: Vec<(String, String)> = vec![
+            ("a".to_string(), "b".to_string()),
+            ("c".to_string(), "d".to_string()),
+            ("a".to_string(), "b".to_string()),
+        ];
+
+        let arbitrage = Arbitrage::<String, f32>::new(1.5, cycle, vec![3.0, 0.5]);
+
+        assert_eq!(
+            arbitrage.get_json(),
+            String::from(
+                "{\"exchange\":\"a\",\"currency\":\"b\",\"profit_factor\":1.5,\"profit_per_unit\":0.5,\
+                 \"cycle\":[{\"exchange\":\"a\",\"currency\":\"b\"},{\"exchange\":\"c\",\"currency\":\"d\"},\
+                 {\"exchange\":\"a\",\"currency\":\"b\"}],\
+                 \"edges\":[{\"from\":{\"exchange\":\"a\",\"currency\":\"b\"},\
+                 \"to\":{\"exchange\":\"c\",\"currency\":\"d\"},\"rate\":3},\
+                 {\"from\":{\"exchange\":\"c\",\"currency\":\"d\"},\
+                 \"to\":{\"exchange\":\"a\",\"currency\":\"b\"},\"rate\":0.5}]}"
+            )
+        );
+    }
+
+    #[test]
+    fn get_unanswered_output() {
+        let unanswered = UnansweredArbitrageRequest::new(Some("KRAKEN".to_string()), None);
+
+        assert_eq!(
+            unanswered.get_output(),
+            String::from("ARBITRAGE_UNAVAILABLE <KRAKEN> <ANY>\n")
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Arbitrage Alert.
+
+use self::Change::*;
+use std::fmt;
+use std::fmt::{Debug, Display};
+
+/// The change of an arbitrage opportunity being alerted.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Change {
+    Appeared,
+    Disappeared,
+}
+
+impl Change {
+    pub fn get_label(&self) -> String {
+        match self {
+            Appeared => "ARBITRAGE_APPEARED".to_string(),
+            Disappeared => "ARBITRAGE_DISAPPEARED".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+/// `ArbitrageAlert` structure.
+///
+/// An arbitrage opportunity (a profitable cycle) which newly appeared or disappeared while
+/// monitoring `PriceUpdate`s.
+///
+/// # `ArbitrageAlert<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct ArbitrageAlert<N, E> {
+    change: Change,
+    profit_factor: E,
+    cycle: Vec<(N, N)>,
+}
+
+impl<N, E> ArbitrageAlert<N, E>
+where
+    N: Display + Debug,
+    E: Display,
+{
+    pub fn new(change: Change, profit_factor: E, cycle: Vec<(N, N)>) -> Self {
+        Self {
+            change,
+            profit_factor,
+            cycle,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn get_change(&self) -> Change {
+        self.change
+    }
+
+    /// Get printable output representing the Arbitrage Alert.
+    ///
+    /// # Format
+    ///
+    /// <change> <profit_factor> <start_exchange, start_currency> <exchange, currency> ...
+    /// <start_exchange, start_currency>
+    ///
+    /// ## Example
+    ///
+    /// ARBITRAGE_APPEARED <1.02> <a, b> <c, d> <a, b>
+    pub fn get_output(&self) -> String {
+        let mut output = format!("{} <{}>", self.change, self.profit_factor);
+
+        for (a, b) in self.cycle.iter() {
+            output.push_str(&format!(" <{}, {}>", a, b));
+        }
+        output.push('\n');
+
+        output
+    }
+}
+
+impl<N, E> fmt::Display for ArbitrageAlert<N, E>
+where
+    N: Display + Debug,
+    E: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_output())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::response::arbitrage_alert::ArbitrageAlert;
+    use crate::response::arbitrage_alert::Change::*;
+
+    #[test]
+    fn get_output() {
+        let cycle: Vec<(String, String)> = vec![
+            ("a".to_string(), "b".to_string()),
+            ("c".to_string(), "d".to_string()),
+            ("a".to_string(), "b".to_string()),
+        ];
+
+        let alert = ArbitrageAlert::<String, f32>::new(Appeared, 1.02, cycle.clone());
+        assert_eq!(
+            alert.get_output(),
+            String::from("ARBITRAGE_APPEARED <1.02> <a, b> <c, d> <a, b>\n")
+        );
+
+        let alert = ArbitrageAlert::<String, f32>::new(Disappeared, 0.98, cycle);
+        assert_eq!(
+            alert.get_output(),
+            String::from("ARBITRAGE_DISAPPEARED <0.98> <a, b> <c, d> <a, b>\n")
+        );
+    }
+}
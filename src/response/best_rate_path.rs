@@ -1,45 +1,105 @@
 //! Best Rate Path.
 
+use crate::response::instruction::Instruction;
+use std::fmt;
 use std::fmt::{Debug, Display};
 
-pub struct BestRatePath<N, E> {
-    rate: E,
-    path: Vec<(N, N)>,
-}
-
 /// Exchange `BestRatePath` structure.
 ///
+/// The best rate of converting the source node to the destination one, together with the path
+/// of `(exchange, currency)` nodes achieving it.
+///
 /// # `BestRatePath<N, E>` is parameterized over:
 ///
 /// - Identifier data `N`.
 /// - Edge weight `E`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestRatePath<N, E> {
+    rate: E,
+    path: Vec<(N, N)>,
+    rates: Vec<E>,
+}
+
 impl<N, E> BestRatePath<N, E>
 where
     N: Display + Debug,
     E: Display,
 {
+    /// Create a new instance of `BestRatePath` structure.
     pub fn new(rate: E, path: Vec<(N, N)>) -> Self {
-        Self { rate, path }
+        Self {
+            rate,
+            path,
+            rates: Vec::new(),
+        }
     }
 
+    /// Get the best rate of the path.
     pub fn get_rate(&self) -> &E {
         &self.rate
     }
 
-    pub fn get_path(&self) -> &Vec<(N, N)> {
+    /// Get the `(exchange, currency)` nodes of the path, from the source to the destination one.
+    pub fn get_path(&self) -> &[(N, N)] {
         &self.path
     }
 
-    #[allow(dead_code)]
+    /// Get the source node, `None` for an empty path.
     pub fn get_start_node(&self) -> Option<&(N, N)> {
         self.path.first()
     }
 
-    #[allow(dead_code)]
+    /// Get the destination node, `None` for an empty path.
     pub fn get_end_node(&self) -> Option<&(N, N)> {
         self.path.last()
     }
 
+    /// Set rates of the path edges, the `i`-th rate belongs to the edge leaving the `i`-th node.
+    pub fn set_rates(&mut self, rates: Vec<E>) {
+        self.rates = rates;
+    }
+
+    /// Get rates of the path edges, empty if they aren't known.
+    pub fn get_rates(&self) -> &[E] {
+        &self.rates
+    }
+
+    /// Take the best rate and the `(exchange, currency)` nodes of the path.
+    pub fn into_parts(self) -> (E, Vec<(N, N)>) {
+        (self.rate, self.path)
+    }
+
+    /// Convert the path to the `Instruction`s executing it, a trade for each edge between
+    /// currencies of the same exchange and a transfer for each edge between exchanges.
+    pub fn to_instructions(&self) -> Vec<Instruction<N, E>>
+    where
+        N: Clone + PartialEq,
+        E: Copy,
+    {
+        self.path
+            .windows(2)
+            .enumerate()
+            .map(|(index, edge)| {
+                let ((x_exchange, x_currency), (y_exchange, y_currency)) = (&edge[0], &edge[1]);
+
+                if x_exchange == y_exchange {
+                    Instruction::Trade {
+                        exchange: x_exchange.clone(),
+                        sell: x_currency.clone(),
+                        buy: y_currency.clone(),
+                        rate: self.rates.get(index).copied(),
+                    }
+                } else {
+                    Instruction::Transfer {
+                        currency: x_currency.clone(),
+                        from: x_exchange.clone(),
+                        to: y_exchange.clone(),
+                    }
+                }
+            })
+            .collect()
+    }
+
     /// Get printable output representing the Best Rated Path.
     ///
     /// # Format
@@ -64,8 +124,8 @@ where
     pub fn get_output(&self) -> String {
         let path = self.get_path();
 
-        // Return empty string nn case the path is not valid.
-        if path.len() < 2 {
+        // Return empty string nn case the path is not valid, a single node is the identity path.
+        if path.is_empty() {
             return String::new();
         }
 
@@ -105,18 +165,30 @@ where
     }
 }
 
+impl<N, E> fmt::Display for BestRatePath<N, E>
+where
+    N: Display + Debug,
+    E: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_output())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::response::best_rate_path::BestRatePath;
+    use crate::response::instruction::Instruction;
 
     #[test]
     fn get_path_output() {
         let rate = 10.0;
-        let mut path: Vec<(String, String)> = Vec::with_capacity(4);
-        path.push(("a".to_string(), "b".to_string()));
-        path.push(("c".to_string(), "d".to_string()));
-        path.push(("e".to_string(), "f".to_string()));
-        path.push(("g".to_string(), "h".to_string()));
+        let path: Vec<(String, String)> = vec![
+            ("a".to_string(), "b".to_string()),
+            ("c".to_string(), "d".to_string()),
+            ("e".to_string(), "f".to_string()),
+            ("g".to_string(), "h".to_string()),
+        ];
 
         let best_rate_path = BestRatePath::<String, f32>::new(rate, path);
 
@@ -126,14 +198,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn into_parts() {
+        let path = vec![
+            ("a".to_string(), "b".to_string()),
+            ("c".to_string(), "d".to_string()),
+        ];
+        let best_rate_path = BestRatePath::<String, f32>::new(10.0, path.clone());
+
+        // Test that a clone is equal and keeps the original usable.
+        let copy = best_rate_path.clone();
+        assert_eq!(copy, best_rate_path);
+        assert_eq!(copy.into_parts(), (10.0, path));
+        assert_ne!(best_rate_path, BestRatePath::new(10.5, Vec::new()));
+    }
+
+    #[test]
+    fn to_instructions() {
+        let path = vec![("E1", "BTC"), ("E2", "BTC"), ("E2", "USD"), ("E1", "USD")];
+        let mut best_rate_path = BestRatePath::<&str, f32>::new(1100.0, path);
+
+        // Test that trades are told apart from transfers, without rates if they aren't known.
+        let instructions = best_rate_path.to_instructions();
+        assert_eq!(
+            instructions[0],
+            Instruction::Transfer {
+                currency: "BTC",
+                from: "E1",
+                to: "E2",
+            }
+        );
+        assert_eq!(
+            instructions[1],
+            Instruction::Trade {
+                exchange: "E2",
+                sell: "BTC",
+                buy: "USD",
+                rate: None,
+            }
+        );
+        assert_eq!(instructions.len(), 3);
+
+        best_rate_path.set_rates(vec![1.0, 1100.0, 1.0]);
+        assert_eq!(
+            best_rate_path.to_instructions()[1],
+            Instruction::Trade {
+                exchange: "E2",
+                sell: "BTC",
+                buy: "USD",
+                rate: Some(1100.0),
+            }
+        );
+    }
+
     #[test]
     fn get_output() {
         let rate = 10.2;
-        let mut path: Vec<(String, String)> = Vec::with_capacity(4);
-        path.push(("a".to_string(), "b".to_string()));
-        path.push(("c".to_string(), "d".to_string()));
-        path.push(("e".to_string(), "f".to_string()));
-        path.push(("g".to_string(), "h".to_string()));
+        let path: Vec<(String, String)> = vec![
+            ("a".to_string(), "b".to_string()),
+            ("c".to_string(), "d".to_string()),
+            ("e".to_string(), "f".to_string()),
+            ("g".to_string(), "h".to_string()),
+        ];
 
         let best_rate_path = BestRatePath::<String, f32>::new(rate, path);
 
@@ -148,5 +274,17 @@ mod tests {
                  BEST_RATES_END\n"
             )
         );
+
+        // Test that the identity path of a single node is written as well.
+        let best_rate_path =
+            BestRatePath::<String, f32>::new(1.0, vec![("a".to_string(), "b".to_string())]);
+        assert_eq!(
+            best_rate_path.get_output(),
+            String::from(
+                "BEST_RATES_BEGIN <a> <b> <a> <b> <1>\n\
+                 <a, b>\n\
+                 BEST_RATES_END\n"
+            )
+        );
     }
 }
@@ -0,0 +1,75 @@
+//! Instructions executing a Best Rate Path.
+
+use std::fmt;
+use std::fmt::Display;
+
+/// A single step of executing a `BestRatePath`, formed by an edge between consecutive nodes.
+///
+/// # `Instruction<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction<N, E> {
+    /// Sell a currency for another one at the exchange, `rate` is `None` if the path doesn't
+    /// know its edge rates.
+    Trade {
+        exchange: N,
+        sell: N,
+        buy: N,
+        rate: Option<E>,
+    },
+    /// Move the currency from an exchange to another one.
+    Transfer { currency: N, from: N, to: N },
+}
+
+impl<N, E> fmt::Display for Instruction<N, E>
+where
+    N: Display,
+    E: Display,
+{
+    /// Format it the same as the output lines, e.g. `TRADE <KRAKEN> <BTC> <USD> <1000>` or
+    /// `TRANSFER <BTC> <KRAKEN> <GDAX>`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Trade {
+                exchange,
+                sell,
+                buy,
+                rate: Some(rate),
+            } => write!(f, "TRADE <{}> <{}> <{}> <{}>", exchange, sell, buy, rate),
+            Instruction::Trade {
+                exchange,
+                sell,
+                buy,
+                rate: None,
+            } => write!(f, "TRADE <{}> <{}> <{}>", exchange, sell, buy),
+            Instruction::Transfer { currency, from, to } => {
+                write!(f, "TRANSFER <{}> <{}> <{}>", currency, from, to)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::response::instruction::Instruction;
+
+    #[test]
+    fn fmt() {
+        let trade = Instruction::<&str, f32>::Trade {
+            exchange: "KRAKEN",
+            sell: "BTC",
+            buy: "USD",
+            rate: Some(1000.0),
+        };
+        assert_eq!(trade.to_string(), "TRADE <KRAKEN> <BTC> <USD> <1000>");
+
+        let transfer = Instruction::<&str, f32>::Transfer {
+            currency: "BTC",
+            from: "KRAKEN",
+            to: "GDAX",
+        };
+        assert_eq!(transfer.to_string(), "TRANSFER <BTC> <KRAKEN> <GDAX>");
+    }
+}
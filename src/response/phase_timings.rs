@@ -0,0 +1,108 @@
+//! Response Phase Timings.
+
+use std::time::Duration;
+
+/// Exchange Rate Path `PhaseTimings` structure.
+///
+/// Wall-clock durations of the phases forming a `Response`, `None` for a phase that wasn't
+/// timed, e.g. the parsing of a `Request` built by the caller.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseTimings {
+    parse_time: Option<Duration>,
+    graph_build_time: Option<Duration>,
+    solve_time: Option<Duration>,
+    render_time: Option<Duration>,
+}
+
+impl PhaseTimings {
+    /// Create a new instance of `PhaseTimings` structure.
+    pub fn new(
+        parse_time: Option<Duration>,
+        graph_build_time: Option<Duration>,
+        solve_time: Option<Duration>,
+        render_time: Option<Duration>,
+    ) -> Self {
+        Self {
+            parse_time,
+            graph_build_time,
+            solve_time,
+            render_time,
+        }
+    }
+
+    /// Get time spent by parsing the input lines.
+    pub fn get_parse_time(&self) -> Option<Duration> {
+        self.parse_time
+    }
+
+    /// Get time spent by building the graph.
+    pub fn get_graph_build_time(&self) -> Option<Duration> {
+        self.graph_build_time
+    }
+
+    /// Get time spent by solving the best rate paths.
+    pub fn get_solve_time(&self) -> Option<Duration> {
+        self.solve_time
+    }
+
+    /// Get time spent by rendering the output.
+    pub fn get_render_time(&self) -> Option<Duration> {
+        self.render_time
+    }
+
+    /// Get printable output representing the PhaseTimings.
+    ///
+    /// # Format
+    ///
+    /// PHASE_TIMINGS <parse_us, microseconds> <graph_build_us, microseconds>
+    /// <solve_us, microseconds> <render_us, microseconds>
+    ///
+    /// All on a single line, phases that weren't timed are omitted.
+    pub fn get_output(&self) -> String {
+        let mut output = String::from("PHASE_TIMINGS");
+
+        let phases = [
+            ("parse_us", self.parse_time),
+            ("graph_build_us", self.graph_build_time),
+            ("solve_us", self.solve_time),
+            ("render_us", self.render_time),
+        ];
+        for (name, time) in phases.iter() {
+            if let Some(time) = time {
+                output.push_str(&format!(" <{}, {}>", name, time.as_micros()));
+            }
+        }
+        output.push('\n');
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::response::phase_timings::PhaseTimings;
+    use std::time::Duration;
+
+    #[test]
+    fn get_output() {
+        let phase_timings = PhaseTimings::new(
+            Some(Duration::from_micros(12)),
+            Some(Duration::from_micros(30)),
+            Some(Duration::from_micros(100)),
+            Some(Duration::from_micros(5)),
+        );
+
+        assert_eq!(
+            phase_timings.get_output(),
+            "PHASE_TIMINGS <parse_us, 12> <graph_build_us, 30> <solve_us, 100> <render_us, 5>\n"
+        );
+
+        // Test that the phases which weren't timed are omitted.
+        let phase_timings = PhaseTimings::new(None, Some(Duration::from_micros(30)), None, None);
+
+        assert_eq!(
+            phase_timings.get_output(),
+            "PHASE_TIMINGS <graph_build_us, 30>\n"
+        );
+    }
+}
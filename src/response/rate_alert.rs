@@ -0,0 +1,106 @@
+//! Rate Alert.
+
+use std::fmt;
+use std::fmt::Display;
+
+/// `RateAlert` structure.
+///
+/// The best rate of a watched pair of nodes which moved more than its threshold away from the
+/// rate of the last alert while monitoring `PriceUpdate`s.
+///
+/// # `RateAlert<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct RateAlert<N, E> {
+    pair: (N, N, N, N),
+    last_rate: E,
+    rate: E,
+}
+
+impl<N, E> RateAlert<N, E>
+where
+    N: Display,
+    E: Display,
+{
+    pub fn new(pair: (N, N, N, N), last_rate: E, rate: E) -> Self {
+        Self {
+            pair,
+            last_rate,
+            rate,
+        }
+    }
+
+    /// Get the `(source_exchange, source_currency, destination_exchange, destination_currency)`
+    /// pair of the rate.
+    #[allow(dead_code)]
+    pub fn get_pair(&self) -> &(N, N, N, N) {
+        &self.pair
+    }
+
+    #[allow(dead_code)]
+    pub fn get_last_rate(&self) -> &E {
+        &self.last_rate
+    }
+
+    #[allow(dead_code)]
+    pub fn get_rate(&self) -> &E {
+        &self.rate
+    }
+
+    /// Get printable output representing the Rate Alert.
+    ///
+    /// # Format
+    ///
+    /// RATE_ALERT <source_exchange> <source_currency> <destination_exchange>
+    /// <destination_currency> <last_rate> <rate>
+    ///
+    /// ## Example
+    ///
+    /// RATE_ALERT <KRAKEN> <BTC> <GDAX> <USD> <1000> <1006>
+    pub fn get_output(&self) -> String {
+        let (source_exchange, source_currency, destination_exchange, destination_currency) =
+            &self.pair;
+
+        format!(
+            "RATE_ALERT <{}> <{}> <{}> <{}> <{}> <{}>\n",
+            source_exchange,
+            source_currency,
+            destination_exchange,
+            destination_currency,
+            self.last_rate,
+            self.rate
+        )
+    }
+}
+
+impl<N, E> fmt::Display for RateAlert<N, E>
+where
+    N: Display,
+    E: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_output())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::response::rate_alert::RateAlert;
+
+    #[test]
+    fn get_output() {
+        let pair = (
+            "KRAKEN".to_string(),
+            "BTC".to_string(),
+            "GDAX".to_string(),
+            "USD".to_string(),
+        );
+
+        let alert = RateAlert::<String, f32>::new(pair, 1000.0, 1006.0);
+        assert_eq!(
+            alert.get_output(),
+            String::from("RATE_ALERT <KRAKEN> <BTC> <GDAX> <USD> <1000> <1006>\n")
+        );
+    }
+}
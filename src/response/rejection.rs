@@ -0,0 +1,63 @@
+//! Rejected Line.
+
+use self::Rejection::*;
+use std::fmt;
+
+/// The reason why an input line was rejected instead of being processed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Rejection {
+    /// The client sends price updates faster than allowed.
+    UpdateRateExceeded,
+    /// Too many requests are being answered already.
+    TooManyRequests,
+    /// The price update would add a new pair to the graph over its maximal size.
+    GraphSizeExceeded,
+    /// The client didn't present a valid access token.
+    Unauthorized,
+    /// The line is longer than the server reads.
+    LineTooLong,
+}
+
+impl Rejection {
+    pub fn get_label(&self) -> String {
+        match self {
+            UpdateRateExceeded => "update_rate_exceeded".to_string(),
+            TooManyRequests => "too_many_requests".to_string(),
+            GraphSizeExceeded => "graph_size_exceeded".to_string(),
+            Unauthorized => "unauthorized".to_string(),
+            LineTooLong => "line_too_long".to_string(),
+        }
+    }
+
+    /// Get printable output representing the Rejection.
+    ///
+    /// # Format
+    ///
+    /// LINE_REJECTED <reason>
+    ///
+    /// ## Example
+    ///
+    /// LINE_REJECTED <too_many_requests>
+    pub fn get_output(&self) -> String {
+        format!("LINE_REJECTED <{}>\n", self)
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::response::rejection::Rejection::*;
+
+    #[test]
+    fn get_output() {
+        assert_eq!(
+            GraphSizeExceeded.get_output(),
+            "LINE_REJECTED <graph_size_exceeded>\n"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! Response Summary.
+
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Exchange Rate Path `Summary` structure.
+///
+/// Statistics collected over all answered and unanswered Exchange Rate Requests of a `Response`.
+///
+/// # `Summary<E>` is parameterized over:
+///
+/// - Edge weight `E`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary<E> {
+    answered: usize,
+    unanswered: usize,
+    best_rate: Option<E>,
+    worst_rate: Option<E>,
+    average_path_length: f64,
+    compute_time: Duration,
+}
+
+impl<E> Summary<E>
+where
+    E: Display + PartialOrd + Copy,
+{
+    /// Create a new instance of `Summary` structure.
+    pub fn new(
+        answered: usize,
+        unanswered: usize,
+        best_rate: Option<E>,
+        worst_rate: Option<E>,
+        average_path_length: f64,
+        compute_time: Duration,
+    ) -> Self {
+        Self {
+            answered,
+            unanswered,
+            best_rate,
+            worst_rate,
+            average_path_length,
+            compute_time,
+        }
+    }
+
+    /// Get count of answered Exchange Rate Requests.
+    pub fn get_answered(&self) -> usize {
+        self.answered
+    }
+
+    /// Get count of Exchange Rate Requests without any answer.
+    pub fn get_unanswered(&self) -> usize {
+        self.unanswered
+    }
+
+    /// Get the best (the highest) rate of all answered requests.
+    pub fn get_best_rate(&self) -> Option<&E> {
+        self.best_rate.as_ref()
+    }
+
+    /// Get the worst (the lowest) rate of all answered requests.
+    pub fn get_worst_rate(&self) -> Option<&E> {
+        self.worst_rate.as_ref()
+    }
+
+    /// Get average count of `(exchange, currency)` nodes of all answered paths.
+    pub fn get_average_path_length(&self) -> f64 {
+        self.average_path_length
+    }
+
+    /// Get total time spent by computing the `Response`.
+    pub fn get_compute_time(&self) -> &Duration {
+        &self.compute_time
+    }
+
+    /// Get printable output representing the Summary.
+    ///
+    /// # Format
+    ///
+    /// SUMMARY_BEGIN
+    /// <answered, count>
+    /// <unanswered, count>
+    /// <best_rate, rate>
+    /// <worst_rate, rate>
+    /// <average_path_length, length>
+    /// <compute_time_us, microseconds>
+    /// SUMMARY_END
+    ///
+    /// The best and the worst rate lines are omitted if no request was answered.
+    pub fn get_output(&self) -> String {
+        let mut output = String::from("SUMMARY_BEGIN\n");
+
+        output.push_str(&format!("<answered, {}>\n", self.answered));
+        output.push_str(&format!("<unanswered, {}>\n", self.unanswered));
+        if let Some(best_rate) = self.best_rate {
+            output.push_str(&format!("<best_rate, {}>\n", best_rate));
+        }
+        if let Some(worst_rate) = self.worst_rate {
+            output.push_str(&format!("<worst_rate, {}>\n", worst_rate));
+        }
+        output.push_str(&format!(
+            "<average_path_length, {}>\n",
+            self.average_path_length
+        ));
+        output.push_str(&format!(
+            "<compute_time_us, {}>\n",
+            self.compute_time.as_micros()
+        ));
+        output.push_str("SUMMARY_END\n");
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::response::summary::Summary;
+    use std::time::Duration;
+
+    #[test]
+    fn get_output() {
+        let summary =
+            Summary::<f32>::new(2, 1, Some(10.5), Some(0.5), 3.5, Duration::from_micros(42));
+
+        assert_eq!(
+            summary.get_output(),
+            String::from(
+                "SUMMARY_BEGIN\n\
+                 <answered, 2>\n\
+                 <unanswered, 1>\n\
+                 <best_rate, 10.5>\n\
+                 <worst_rate, 0.5>\n\
+                 <average_path_length, 3.5>\n\
+                 <compute_time_us, 42>\n\
+                 SUMMARY_END\n"
+            )
+        );
+    }
+
+    #[test]
+    fn get_output_without_rates() {
+        let summary = Summary::<f32>::new(0, 3, None, None, 0.0, Duration::from_micros(7));
+
+        assert_eq!(
+            summary.get_output(),
+            String::from(
+                "SUMMARY_BEGIN\n\
+                 <answered, 0>\n\
+                 <unanswered, 3>\n\
+                 <average_path_length, 0>\n\
+                 <compute_time_us, 7>\n\
+                 SUMMARY_END\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Unanswered Request.
+
+use self::Reason::*;
+use std::fmt;
+use std::fmt::Display;
+
+/// The reason why an Exchange Rate Request could not be answered.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Reason {
+    UnknownSourceExchange,
+    UnknownSourceCurrency,
+    UnknownDestinationExchange,
+    UnknownDestinationCurrency,
+    NoPath,
+    NoPathWithinTime,
+    NoPathWithinHops,
+    NoHistory,
+    /// The computation was cancelled, e.g. its deadline passed, before answering it.
+    Cancelled,
+}
+
+impl Reason {
+    pub fn get_label(&self) -> String {
+        match self {
+            UnknownSourceExchange => "unknown_source_exchange".to_string(),
+            UnknownSourceCurrency => "unknown_source_currency".to_string(),
+            UnknownDestinationExchange => "unknown_destination_exchange".to_string(),
+            UnknownDestinationCurrency => "unknown_destination_currency".to_string(),
+            NoPath => "no_path".to_string(),
+            NoPathWithinTime => "no_path_within_time".to_string(),
+            NoPathWithinHops => "no_path_within_hops".to_string(),
+            NoHistory => "no_history".to_string(),
+            Cancelled => "cancelled".to_string(),
+        }
+    }
+
+    /// Get whether the exchange or currency of the source or destination node is not known.
+    pub fn is_unknown_node(&self) -> bool {
+        matches!(
+            self,
+            UnknownSourceExchange
+                | UnknownSourceCurrency
+                | UnknownDestinationExchange
+                | UnknownDestinationCurrency
+        )
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_label())
+    }
+}
+
+/// `UnansweredRequest` structure.
+///
+/// # `UnansweredRequest<N>` is parameterized over:
+///
+/// - Identifier data `N`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnansweredRequest<N> {
+    source: (N, N),
+    destination: (N, N),
+    reason: Reason,
+}
+
+impl<N> UnansweredRequest<N>
+where
+    N: Display,
+{
+    /// Create a new instance of `UnansweredRequest` structure.
+    pub fn new(source: (N, N), destination: (N, N), reason: Reason) -> Self {
+        Self {
+            source,
+            destination,
+            reason,
+        }
+    }
+
+    /// Get the requested source `(exchange, currency)` node.
+    pub fn get_source(&self) -> &(N, N) {
+        &self.source
+    }
+
+    /// Get the requested destination `(exchange, currency)` node.
+    pub fn get_destination(&self) -> &(N, N) {
+        &self.destination
+    }
+
+    pub fn get_reason(&self) -> Reason {
+        self.reason
+    }
+
+    /// Get printable output representing the Unanswered Request.
+    ///
+    /// # Format
+    ///
+    /// BEST_RATES_UNAVAILABLE <source_exchange> <source_currency> <destination_exchange>
+    /// <destination_currency> <reason>
+    ///
+    /// ## Example
+    ///
+    /// BEST_RATES_UNAVAILABLE <KRAKEN> <BTC> <GDAX> <DOGE> <unknown_destination_currency>
+    pub fn get_output(&self) -> String {
+        let (source_exchange, source_currency) = &self.source;
+        let (destination_exchange, destination_currency) = &self.destination;
+
+        format!(
+            "BEST_RATES_UNAVAILABLE <{}> <{}> <{}> <{}> <{}>\n",
+            source_exchange,
+            source_currency,
+            destination_exchange,
+            destination_currency,
+            self.reason,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::response::unanswered_request::Reason::*;
+    use crate::response::unanswered_request::UnansweredRequest;
+
+    #[test]
+    fn get_output() {
+        let unanswered_request = UnansweredRequest::new(
+            ("KRAKEN".to_string(), "BTC".to_string()),
+            ("GDAX".to_string(), "DOGE".to_string()),
+            UnknownDestinationCurrency,
+        );
+
+        assert_eq!(
+            unanswered_request.get_output(),
+            String::from(
+                "BEST_RATES_UNAVAILABLE <KRAKEN> <BTC> <GDAX> <DOGE> <unknown_destination_currency>\n"
+            )
+        );
+    }
+}
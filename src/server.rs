@@ -0,0 +1,859 @@
+//! Long-running server speaking the line protocol.
+
+use self::limits::{Limits, UpdateRate};
+use self::namespaces::{parse_auth_line, Namespaces, SharedProcessor};
+use self::session::Session;
+use crate::algorithm::settings::Settings;
+use crate::checkpoint;
+use crate::checkpoint::Checkpoints;
+use crate::error::Error;
+#[cfg(feature = "feed")]
+use crate::exchange_feed::Aggregator;
+use crate::reload;
+use crate::request::arbitrage_request::ArbitrageRequest;
+use crate::request::exchange_rate_request::ExchangeRateRequest;
+use crate::response::rejection::Rejection;
+use crate::shutdown;
+use crate::shutdown::Shutdown;
+use crate::stream::StreamProcessor;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "feed")]
+mod feed;
+#[cfg(feature = "http")]
+mod http;
+mod http_message;
+mod limits;
+mod metrics;
+mod namespaces;
+mod session;
+
+/// Maximal length of an input line of a connection in bytes, longer lines are rejected.
+const MAX_LINE_LENGTH: usize = 64 * 1024;
+
+/// `Server` structure.
+///
+/// Clients connect and stream input lines the same as in the watch mode, the output caused by
+/// their lines is written back to the same connection. All connections share the most recent
+/// `PriceUpdate`s, so a request is answered using prices streamed by any client.
+///
+/// A connection can work with `PriceUpdate`s of its own instead, isolated from other
+/// connections. It's selected for all connections at startup and switched per connection by
+/// `SESSION <SHARED|ISOLATED>` line.
+///
+/// If access tokens are added, a connection has to present one by `AUTH <token>` line first.
+/// A token may grant access to a namespace, connections of the same namespace share its
+/// `PriceUpdate`s only, so teams sharing the server don't see or clobber each other's prices.
+///
+/// # `Server<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct Server<N, E> {
+    processor: SharedProcessor<N, E>,
+    namespaces: Arc<Namespaces<N, E>>,
+    isolated_sessions: bool,
+    limits: Limits,
+    shutdown: Arc<Shutdown>,
+    snapshot_path: Option<PathBuf>,
+    checkpoints: Checkpoints,
+    #[cfg(feature = "feed")]
+    feeds: Arc<Aggregator<N, E>>,
+}
+
+impl<N, E> Server<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of `Server` structure.
+    pub fn new(settings: Settings<N, E>) -> Self {
+        let processor = Arc::new(Mutex::new(StreamProcessor::new(settings)));
+
+        Self {
+            namespaces: Arc::new(Namespaces::new(Arc::clone(&processor))),
+            processor,
+            isolated_sessions: false,
+            limits: Limits::default(),
+            shutdown: Arc::new(Shutdown::new()),
+            snapshot_path: None,
+            checkpoints: Checkpoints::new(),
+            #[cfg(feature = "feed")]
+            feeds: Arc::new(Aggregator::new()),
+        }
+    }
+
+    /// Set whether arbitrage opportunities should be re-evaluated after each price update and
+    /// their changes alerted to the connection the price update came from.
+    pub fn set_monitor_arbitrage(&mut self, monitor_arbitrage: bool) {
+        self.lock_processor()
+            .set_monitor_arbitrage(monitor_arbitrage);
+    }
+
+    /// Alert moves of the best rate of the
+    /// `(source_exchange, source_currency, destination_exchange, destination_currency)` pair by
+    /// more than the relative threshold since the last alert to the connection the price update
+    /// came from.
+    pub fn add_rate_alert(&mut self, pair: (N, N, N, N), threshold: E) {
+        self.lock_processor().add_rate_alert(pair, threshold);
+    }
+
+    /// Set whether arbitrages should be written out as JSON lines instead of text blocks.
+    pub fn set_arbitrage_json(&mut self, arbitrage_json: bool) {
+        self.lock_processor().set_arbitrage_json(arbitrage_json);
+    }
+
+    /// Set whether each block of the output should be stamped by an `AS_OF` line of the
+    /// timestamp of the most recent quote it was based on and the moment of its computation.
+    pub fn set_include_stamp(&mut self, include_stamp: bool) {
+        self.lock_processor().set_include_stamp(include_stamp);
+    }
+
+    /// Set whether connections start isolated from each other instead of sharing
+    /// `PriceUpdate`s.
+    pub fn set_isolated_sessions(&mut self, isolated_sessions: bool) {
+        self.isolated_sessions = isolated_sessions;
+    }
+
+    /// Set maximal count of price updates a connection can send per second, `None` for no
+    /// limit. Price updates over the limit are rejected.
+    pub fn set_max_updates_per_second(&mut self, max_updates_per_second: Option<u32>) {
+        self.limits.max_updates_per_second = max_updates_per_second;
+    }
+
+    /// Set maximal count of requests being answered at once by all connections, `None` for no
+    /// limit. Requests over the limit are rejected instead of waiting for their turn.
+    pub fn set_max_in_flight_requests(&mut self, max_in_flight_requests: Option<usize>) {
+        self.limits
+            .in_flight_requests
+            .set_max(max_in_flight_requests);
+    }
+
+    /// Set maximal count of currency pairs with a known price update, `None` for no limit.
+    /// Price updates adding a new pair over the limit are rejected.
+    pub fn set_max_graph_size(&mut self, max_graph_size: Option<usize>) {
+        self.lock_processor().set_max_graph_size(max_graph_size);
+    }
+
+    /// Set the interval price updates of the same pair are combined within, `None` to take each
+    /// as it comes. A price update enters the graph quoting the time-weighted average of the
+    /// quotes of its pair within the interval before it.
+    pub fn set_aggregation_interval(&mut self, interval: Option<chrono::Duration>) {
+        self.lock_processor().set_aggregation_interval(interval);
+    }
+
+    /// Set maximal time of answering the requests of a line, `None` for no limit. Rate requests
+    /// not answered in time are unanswered for the `cancelled` reason.
+    pub fn set_compute_deadline(&mut self, compute_deadline: Option<Duration>) {
+        self.lock_processor().set_compute_deadline(compute_deadline);
+    }
+
+    /// Set whether all accepted price updates should be kept, answering rate requests as of a
+    /// past moment. It should be set before the journal is opened.
+    pub fn set_history(&mut self, history: bool) {
+        self.lock_processor().set_history(history);
+    }
+
+    /// Add an access token, clients have to present one once any is added.
+    ///
+    /// The token grants access to the namespace, or to the default state shared by clients of
+    /// tokens without any namespace if `None`.
+    pub fn add_token(&mut self, token: String, namespace: Option<String>) {
+        self.namespaces.add_token(token, namespace);
+    }
+
+    /// Read access tokens from the input.
+    ///
+    /// # Input format
+    ///
+    /// One `<token> [<namespace>]` line per token, empty lines and lines starting with `#` are
+    /// skipped. Tokens without a namespace grant access to the default state.
+    ///
+    /// ## Example
+    ///
+    /// \# Desk A shares the default state, desk B has its own namespace.
+    /// 3f9c1a desk-a
+    /// 77d0e2
+    pub fn read_tokens_from<R: BufRead>(&mut self, input: &mut R) -> Result<(), Error> {
+        let mut errors: Vec<String> = Vec::new();
+
+        for (number, line) in input.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let items: Vec<&str> = line.split_whitespace().collect();
+            match items.as_slice() {
+                [token] => self.add_token(token.to_string(), None),
+                [token, namespace] => {
+                    self.add_token(token.to_string(), Some(namespace.to_string()))
+                }
+                _ => errors.push(format!(
+                    "The tokens line {} doesn't have 1 or 2 items!",
+                    number + 1
+                )),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(Error::Parse(errors));
+        }
+
+        Ok(())
+    }
+
+    /// Set path of the file the known price updates are written to on a graceful shutdown,
+    /// `None` for no snapshot.
+    pub fn set_snapshot_path(&mut self, snapshot_path: Option<PathBuf>) {
+        self.snapshot_path = snapshot_path;
+    }
+
+    /// Set policy of writing the snapshot periodically, applied once `checkpoint_periodically`
+    /// is called. Its retention applies to the snapshot on shutdown as well.
+    pub fn set_checkpoints(&mut self, checkpoints: Checkpoints) {
+        self.checkpoints = checkpoints;
+    }
+
+    /// Replay the journal file of the path if it exists, recovering the default state before a
+    /// crash, and append price updates accepted by the default state to it from now on. Return
+    /// count of the price updates known after the replay.
+    pub fn open_journal(&mut self, path: &Path) -> Result<usize, Error> {
+        self.lock_processor().open_journal(path)
+    }
+
+    /// Open the SQLite database of the path, creating it if it doesn't exist, and store price
+    /// updates accepted and best rate paths answered by the default state to it from now on.
+    #[cfg(feature = "sqlite")]
+    pub fn open_storage(&mut self, path: &Path) -> io::Result<()> {
+        self.lock_processor().open_storage(path)
+    }
+
+    /// Shut the server down gracefully once SIGTERM or SIGINT arrives, exiting the process.
+    ///
+    /// New lines aren't processed then, but lines in flight are finished and their output
+    /// flushed, before the snapshot is written.
+    pub fn exit_on_signal(&self) {
+        shutdown::exit_on_signal(
+            Arc::clone(&self.shutdown),
+            Arc::clone(&self.processor),
+            self.snapshot_path.clone(),
+            self.checkpoints.get_retention(),
+        );
+    }
+
+    /// Write the default state to the snapshot file periodically by the checkpoint policy, if
+    /// there is a snapshot path.
+    pub fn checkpoint_periodically(&self) {
+        if let Some(path) = &self.snapshot_path {
+            checkpoint::checkpoint_periodically(
+                Arc::clone(&self.processor),
+                path.clone(),
+                self.checkpoints.clone(),
+            );
+        }
+    }
+
+    /// Reload the config file of the path whenever it's modified or SIGHUP arrives, apply it
+    /// with its profile of the name, if any, on top of the base `Settings`, e.g. the defaults,
+    /// and refine it by `refine`, e.g. by the options of the command line. Settings removed from
+    /// the config fall back to the base ones, as on a fresh start.
+    ///
+    /// Fees, trading rules and transfers of the default state and of all namespaces change
+    /// without a restart and the known `PriceUpdate`s are kept. Isolated sessions keep the
+    /// settings they started with.
+    pub fn reload_config_on_change<F>(
+        &self,
+        path: PathBuf,
+        profile: Option<String>,
+        base: Settings<N, E>,
+        refine: F,
+    ) where
+        F: Fn(&mut Settings<N, E>) + Send + 'static,
+    {
+        let namespaces = Arc::clone(&self.namespaces);
+        reload::reload_on_change(
+            path,
+            profile,
+            base,
+            move || namespaces.get_processors(),
+            refine,
+        );
+    }
+
+    /// Listen for TCP connections on the address (e.g. `127.0.0.1:7070`) and serve them until
+    /// the process is stopped.
+    pub fn serve_tcp(&self, address: &str) -> io::Result<()> {
+        self.serve(TcpListener::bind(address)?.incoming())
+    }
+
+    /// Listen for Unix domain socket connections on the path and serve them the same as TCP ones
+    /// until the process is stopped.
+    ///
+    /// A socket left at the path by a previous run is replaced, any other file is kept.
+    #[cfg(unix)]
+    pub fn serve_uds<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Ok(metadata) = path.symlink_metadata() {
+            if metadata.file_type().is_socket() {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        self.serve(UnixListener::bind(path)?.incoming())
+    }
+
+    /// Serve incoming connections of a listener, each one in a thread of its own.
+    fn serve<S, I>(&self, incoming: I) -> io::Result<()>
+    where
+        I: Iterator<Item = io::Result<S>>,
+        S: Send + 'static,
+        for<'a> &'a S: Read + Write,
+    {
+        for stream in incoming {
+            let stream = match stream {
+                Ok(stream) => stream,
+                // Keep serving, a single failed connection shouldn't stop the server.
+                Err(error) => {
+                    eprintln!("A connection can not be accepted, error: {}!", error);
+                    continue;
+                }
+            };
+
+            let namespaces = Arc::clone(&self.namespaces);
+            let session = if self.isolated_sessions {
+                Session::Isolated
+            } else {
+                Session::Shared
+            };
+            let limits = self.limits.clone();
+            let shutdown = Arc::clone(&self.shutdown);
+            thread::spawn(move || {
+                let input = BufReader::new(&stream);
+                let result = Self::handle_connection(
+                    &namespaces,
+                    session,
+                    &limits,
+                    &shutdown,
+                    input,
+                    &stream,
+                );
+
+                if let Err(error) = result {
+                    eprintln!("A connection failed, error: {}!", error);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Process input lines of a connection, writing the output they caused back.
+    fn handle_connection<R: BufRead, W: Write>(
+        namespaces: &Namespaces<N, E>,
+        mut session: Session,
+        limits: &Limits,
+        shutdown: &Shutdown,
+        mut input: R,
+        mut output: W,
+    ) -> io::Result<()> {
+        let new_session = |processor: &SharedProcessor<N, E>| {
+            processor
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .new_session()
+        };
+        // The processor of the namespace, `None` until a valid token is presented if required.
+        let mut shared = namespaces.get_anonymous();
+        // The processor of an isolated session, `None` for the shared one.
+        let mut isolated = match (session, shared.as_ref()) {
+            (Session::Isolated, Some(shared)) => Some(new_session(shared)),
+            _ => None,
+        };
+        let mut update_rate = UpdateRate::new(limits.max_updates_per_second);
+
+        while let Some(line) = read_line(&mut input)? {
+            // The line is finished and its output flushed before the process exits.
+            let _activity = match shutdown.start_activity() {
+                Some(activity) => activity,
+                None => break,
+            };
+            let line = match line {
+                Ok(line) => line,
+                Err(rejection) => {
+                    Self::write_output(&mut output, &rejection.get_output())?;
+                    continue;
+                }
+            };
+
+            let result = match parse_auth_line(&line) {
+                Some(Ok(token)) => match namespaces.authorize(token) {
+                    Some(processor) => {
+                        isolated = match session {
+                            Session::Isolated => Some(new_session(&processor)),
+                            Session::Shared => None,
+                        };
+                        shared = Some(processor);
+                        Ok(String::new())
+                    }
+                    None => Ok(Rejection::Unauthorized.get_output()),
+                },
+                Some(Err(errors)) => Err(Error::Parse(errors)),
+                None => Self::process_line(
+                    &line,
+                    shared.as_ref(),
+                    &mut session,
+                    &mut isolated,
+                    limits,
+                    &mut update_rate,
+                ),
+            };
+
+            match result {
+                Ok(text) => Self::write_output(&mut output, &text)?,
+                // Keep serving, a single invalid line shouldn't close the connection.
+                Err(error) => eprintln!(
+                    "Errors occurred while processing input line <{}>, errors: {:?}!",
+                    line,
+                    error.get_messages()
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Process a line of an authorized connection, unless it's over the limits.
+    fn process_line(
+        line: &str,
+        shared: Option<&SharedProcessor<N, E>>,
+        session: &mut Session,
+        isolated: &mut Option<StreamProcessor<N, E>>,
+        limits: &Limits,
+        update_rate: &mut UpdateRate,
+    ) -> Result<String, Error> {
+        let shared = match shared {
+            Some(shared) => shared,
+            None => return Ok(Rejection::Unauthorized.get_output()),
+        };
+
+        match Session::parse_line(line) {
+            Some(Ok(Session::Isolated)) => {
+                // The isolated state is kept if the session is isolated already.
+                if isolated.is_none() {
+                    *isolated = Some(
+                        shared
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .new_session(),
+                    );
+                }
+                *session = Session::Isolated;
+                return Ok(String::new());
+            }
+            Some(Ok(Session::Shared)) => {
+                *isolated = None;
+                *session = Session::Shared;
+                return Ok(String::new());
+            }
+            Some(Err(errors)) => return Err(Error::Parse(errors)),
+            None => {}
+        }
+
+        // Rejected lines are answered right away, without waiting for the shared state.
+        let _in_flight = if Self::is_request_line(line) {
+            match limits.in_flight_requests.try_acquire() {
+                Some(guard) => Some(guard),
+                None => return Ok(Rejection::TooManyRequests.get_output()),
+            }
+        } else {
+            None
+        };
+        if !line.trim().is_empty() && !Self::is_request_line(line) && !update_rate.allow() {
+            match isolated.as_mut() {
+                Some(isolated) => isolated.record_rejected_price_update(),
+                None => shared
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .record_rejected_price_update(),
+            }
+            return Ok(Rejection::UpdateRateExceeded.get_output());
+        }
+
+        match isolated.as_mut() {
+            Some(isolated) => isolated.process_line(line),
+            // Lines are processed whole, so a panic of another connection can't leave the
+            // shared state half updated.
+            None => shared
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .process_line(line),
+        }
+    }
+
+    fn write_output<W: Write>(output: &mut W, text: &str) -> io::Result<()> {
+        output.write_all(text.as_bytes())?;
+        output.flush()
+    }
+
+    /// Check whether the line is an exchange rate or arbitrage request.
+    fn is_request_line(line: &str) -> bool {
+        line.split_whitespace()
+            .next()
+            .map(str::to_uppercase)
+            .is_some_and(|line_type| {
+                line_type == ExchangeRateRequest::<N, E>::LINE_TYPE
+                    || line_type == ArbitrageRequest::<N>::LINE_TYPE
+            })
+    }
+
+    fn lock_processor(&self) -> MutexGuard<'_, StreamProcessor<N, E>> {
+        self.processor
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Read a line of at most `MAX_LINE_LENGTH` bytes without its line ending, `None` at the end of
+/// the input.
+///
+/// A longer line is skipped up to its end without holding it in memory and rejected.
+fn read_line<R: BufRead>(input: &mut R) -> io::Result<Option<Result<String, Rejection>>> {
+    let mut line = Vec::new();
+    let length = input
+        .by_ref()
+        .take(MAX_LINE_LENGTH as u64 + 1)
+        .read_until(b'\n', &mut line)?;
+    if length == 0 {
+        return Ok(None);
+    }
+
+    if line.last() == Some(&b'\n') {
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+    } else if length > MAX_LINE_LENGTH {
+        skip_line(input)?;
+        return Ok(Some(Err(Rejection::LineTooLong)));
+    }
+
+    String::from_utf8(line)
+        .map(|line| Some(Ok(line)))
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Skip the rest of the line, including its line ending.
+fn skip_line<R: BufRead>(input: &mut R) -> io::Result<()> {
+    loop {
+        let buffer = input.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        match buffer.iter().position(|&byte| byte == b'\n') {
+            Some(position) => {
+                input.consume(position + 1);
+                return Ok(());
+            }
+            None => {
+                let length = buffer.len();
+                input.consume(length);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::error::Error;
+    use crate::server::session::Session;
+    use crate::server::{Server, MAX_LINE_LENGTH};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    #[test]
+    fn handle_connection() {
+        let server = Server::<String, f32>::new(Settings::new());
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+EXCHANGE_RATE_REQUEST E1
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD"
+            .as_bytes();
+
+        let mut output = Vec::new();
+        Server::handle_connection(
+            &server.namespaces,
+            Session::Shared,
+            &server.limits,
+            &server.shutdown,
+            BufReader::new(text_input),
+            &mut output,
+        )
+        .unwrap();
+
+        // Test that the invalid line is skipped and the request is answered.
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+    }
+
+    #[test]
+    fn handle_isolated_connection() {
+        let server = Server::<String, f32>::new(Settings::new());
+        let handle = |session, text_input: &str| {
+            let mut output = Vec::new();
+            Server::handle_connection(
+                &server.namespaces,
+                session,
+                &server.limits,
+                &server.shutdown,
+                BufReader::new(text_input.as_bytes()),
+                &mut output,
+            )
+            .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        // Test that a price update of an isolated session is not shared.
+        let output = handle(
+            Session::Isolated,
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD",
+        );
+        assert!(output.starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+        assert!(
+            handle(Session::Shared, "EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+                .starts_with("BEST_RATES_UNAVAILABLE")
+        );
+
+        // Test that the session can be switched by a line.
+        let output = handle(
+            Session::Shared,
+            "SESSION ISOLATED
+2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+SESSION SHARED
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD",
+        );
+        assert!(output.starts_with("BEST_RATES_UNAVAILABLE"));
+    }
+
+    #[test]
+    fn handle_connection_with_limits() {
+        let mut server = Server::<String, f32>::new(Settings::new());
+        server.set_max_updates_per_second(Some(1));
+        server.set_max_in_flight_requests(Some(0));
+        let text_input = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:24+00:00 E1 BTC USD 1000.0 0.001
+EXCHANGE_RATE_REQUEST E1 BTC E1 USD"
+            .as_bytes();
+
+        let mut output = Vec::new();
+        Server::handle_connection(
+            &server.namespaces,
+            Session::Shared,
+            &server.limits,
+            &server.shutdown,
+            BufReader::new(text_input),
+            &mut output,
+        )
+        .unwrap();
+
+        // Test that lines over the limits are rejected.
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "LINE_REJECTED <update_rate_exceeded>\nLINE_REJECTED <too_many_requests>\n"
+        );
+    }
+
+    #[test]
+    fn handle_connection_with_long_line() {
+        let server = Server::<String, f32>::new(Settings::new());
+        let handle = |text_input: &str| {
+            let mut output = Vec::new();
+            Server::handle_connection(
+                &server.namespaces,
+                Session::Shared,
+                &server.limits,
+                &server.shutdown,
+                BufReader::with_capacity(1024, text_input.as_bytes()),
+                &mut output,
+            )
+            .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let long_line = "A".repeat(MAX_LINE_LENGTH + 10);
+
+        // Test that the long line is rejected and the lines after it are processed.
+        let output = handle(&format!(
+            "{}\r\n2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001\n\
+             EXCHANGE_RATE_REQUEST E1 BTC E1 USD\n",
+            long_line
+        ));
+        assert!(output.starts_with(
+            "LINE_REJECTED <line_too_long>\nBEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"
+        ));
+
+        // Test that a line of the maximal length isn't rejected.
+        let output = handle(&format!("{}\n", &long_line[..MAX_LINE_LENGTH]));
+        assert!(!output.contains("LINE_REJECTED"));
+
+        // Test that the long line is rejected at the end of the input as well.
+        assert_eq!(handle(&long_line), "LINE_REJECTED <line_too_long>\n");
+    }
+
+    #[test]
+    fn handle_connection_with_tokens() {
+        let mut server = Server::<String, f32>::new(Settings::new());
+        server.add_token("a".to_string(), Some("A".to_string()));
+        server.add_token("b".to_string(), Some("B".to_string()));
+        let handle = |text_input: &str| {
+            let mut output = Vec::new();
+            Server::handle_connection(
+                &server.namespaces,
+                Session::Shared,
+                &server.limits,
+                &server.shutdown,
+                BufReader::new(text_input.as_bytes()),
+                &mut output,
+            )
+            .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        // Test that a token is required.
+        assert_eq!(
+            handle("EXCHANGE_RATE_REQUEST E1 BTC E1 USD\nAUTH wrong"),
+            "LINE_REJECTED <unauthorized>\nLINE_REJECTED <unauthorized>\n"
+        );
+
+        // Test that price updates are shared within the namespace only.
+        handle("AUTH a\n2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001");
+        assert!(handle("AUTH a\nEXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+        assert!(handle("AUTH b\nEXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .starts_with("BEST_RATES_UNAVAILABLE"));
+    }
+
+    #[test]
+    fn read_tokens() {
+        let mut server = Server::<String, f32>::new(Settings::new());
+        let mut input = BufReader::new("# Tokens.\n\na A\nb\nc C extra".as_bytes());
+
+        assert_eq!(
+            server.read_tokens_from(&mut input),
+            Err(Error::Parse(vec![
+                "The tokens line 5 doesn't have 1 or 2 items!".to_string()
+            ]))
+        );
+        assert!(server.namespaces.is_auth_required());
+        assert!(server.namespaces.authorize("a").is_some());
+        assert!(server.namespaces.authorize("b").is_some());
+        assert!(server.namespaces.authorize("c").is_none());
+    }
+
+    #[test]
+    fn handle_connection_on_shutdown() {
+        let server = Server::<String, f32>::new(Settings::new());
+        server.shutdown.request();
+
+        let mut output = Vec::new();
+        Server::handle_connection(
+            &server.namespaces,
+            Session::Shared,
+            &server.limits,
+            &server.shutdown,
+            BufReader::new("2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001".as_bytes()),
+            &mut output,
+        )
+        .unwrap();
+
+        // Test that no new line is processed.
+        assert!(server
+            .lock_processor()
+            .get_metrics()
+            .get_output()
+            .contains("\nexchange_rate_price_updates_total 0\n"));
+    }
+
+    #[test]
+    fn serve() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            Server::<String, f32>::new(Settings::new())
+                .serve(listener.incoming())
+                .unwrap()
+        });
+
+        // Test that a price update of one connection is used by a request of another one.
+        let mut feeder = TcpStream::connect(address).unwrap();
+        feeder
+            .write_all(b"2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001\n")
+            .unwrap();
+        feeder
+            .write_all(b"EXCHANGE_RATE_REQUEST E1 BTC E1 USD\n")
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(&feeder).read_line(&mut line).unwrap();
+
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .write_all(b"EXCHANGE_RATE_REQUEST E1 USD E1 BTC\n")
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(&client).read_line(&mut line).unwrap();
+
+        assert_eq!(line, "BEST_RATES_BEGIN <E1> <USD> <E1> <BTC> <0.001>\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn serve_uds() {
+        use std::os::unix::net::UnixStream;
+
+        let path = std::env::temp_dir().join(format!("exchange-rate-{}.sock", std::process::id()));
+        let server_path = path.clone();
+        thread::spawn(move || {
+            Server::<String, f32>::new(Settings::new())
+                .serve_uds(server_path)
+                .unwrap()
+        });
+
+        // Test that the connection is served the same as a TCP one.
+        let mut client = loop {
+            match UnixStream::connect(&path) {
+                Ok(client) => break client,
+                Err(_) => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+        client
+            .write_all(b"2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001\n")
+            .unwrap();
+        client
+            .write_all(b"EXCHANGE_RATE_REQUEST E1 BTC E1 USD\n")
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(&client).read_line(&mut line).unwrap();
+
+        assert_eq!(line, "BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>\n");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+//! Live exchange feeds of the server.
+
+use crate::exchange_feed::{Connector, FeedEvent, FeedHealth, OverflowPolicy, Recorder};
+use crate::server::Server;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::io;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+impl<N, E> Server<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Add the connector of a live exchange feed, it's started by `run_feeds`.
+    pub fn add_connector(&mut self, connector: Box<dyn Connector<N, E> + Send>) {
+        self.feeds.add_connector(connector);
+    }
+
+    /// Add an alias of the currency symbol used by an exchange feed, e.g. `USDT` of `USD`, the
+    /// alias is replaced by the symbol.
+    pub fn add_symbol_alias(&mut self, alias: N, symbol: N) {
+        self.feeds.add_symbol(alias, symbol);
+    }
+
+    /// Get health of the connectors, in the order they were added.
+    pub fn get_feed_health(&self) -> Vec<FeedHealth> {
+        self.feeds.get_health()
+    }
+
+    /// Set time without any message after which a feed connection is considered lost, `None`
+    /// to wait forever.
+    pub fn set_feed_heartbeat_timeout(&mut self, heartbeat_timeout: Option<Duration>) {
+        self.feeds.set_heartbeat_timeout(heartbeat_timeout);
+    }
+
+    /// Set time without any price update after which quotes of a connected feed are
+    /// quarantined, i.e. marked stale, `None` to rely on them forever.
+    pub fn set_feed_quarantine_timeout(&mut self, quarantine_timeout: Option<Duration>) {
+        self.feeds.set_quarantine_timeout(quarantine_timeout);
+    }
+
+    /// Set the number of feed events waiting to be processed by the default state.
+    pub fn set_feed_queue_capacity(&mut self, capacity: usize) {
+        self.feeds.set_queue_capacity(capacity);
+    }
+
+    /// Set what happens to a new feed event when the queue of the default state is full.
+    pub fn set_feed_overflow_policy(&mut self, overflow_policy: OverflowPolicy) {
+        self.feeds.set_overflow_policy(overflow_policy);
+    }
+
+    /// Set recorder of the price updates received by the connectors, `None` for no recording.
+    /// A recording is replayed by `ReplayConnector`.
+    pub fn set_feed_recorder(&mut self, recorder: Option<Recorder>) {
+        self.feeds.set_recorder(recorder);
+    }
+
+    /// Start the connectors and add the `PriceUpdate`s they receive to the default state, until
+    /// the server shuts down or all of them are finished. Quotes of a connector that lost its connection are marked stale,
+    /// until it connects again and receives fresh ones.
+    ///
+    /// The output they caused, i.e. arbitrage alerts and rejections, is written to the output.
+    pub fn run_feeds<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        for event in self.feeds.start() {
+            let _activity = match self.shutdown.start_activity() {
+                Some(activity) => activity,
+                None => return Ok(()),
+            };
+
+            output.write_all(self.process_feed_event(event).as_bytes())?;
+            output.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Process the feed event by the default state and get the output it caused.
+    fn process_feed_event(&self, event: FeedEvent<N, E>) -> String {
+        let mut processor = self.lock_processor();
+
+        match event {
+            FeedEvent::PriceUpdates(price_updates) => {
+                let mut text = String::new();
+                for price_update in price_updates {
+                    match processor.add_price_update(price_update) {
+                        Ok(alerts) => text.push_str(&alerts.unwrap_or_default()),
+                        Err(rejection) => text.push_str(&rejection.get_output()),
+                    }
+                }
+                text
+            }
+            FeedEvent::Gap(indexes) => processor.mark_stale(&indexes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::exchange_feed::FeedEvent;
+    use crate::request::price_update::PriceUpdate;
+    use crate::server::Server;
+
+    #[test]
+    fn process_feed_event() {
+        let mut server = Server::<String, f32>::new(Settings::new());
+        server.set_max_graph_size(Some(1));
+        let price_updates = vec![
+            "2019-01-20T09:42:23+00:00 E1 BTC USD BID_ASK 999.0 1001.0",
+            "2019-01-20T09:42:23+00:00 E1 ETH USD BID_ASK 99.0 101.0",
+        ]
+        .into_iter()
+        .map(|line| PriceUpdate::parse_line(line).unwrap())
+        .collect();
+        let request = "EXCHANGE_RATE_REQUEST E1 BTC E1 USD";
+
+        assert_eq!(
+            server.process_feed_event(FeedEvent::PriceUpdates(price_updates)),
+            "LINE_REJECTED <graph_size_exceeded>\n"
+        );
+        assert!(server
+            .lock_processor()
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <999>"));
+
+        // Test that no rate is given by quotes of a lost connection.
+        server.process_feed_event(FeedEvent::Gap(vec![(
+            "E1".to_string(),
+            "BTC".to_string(),
+            "USD".to_string(),
+        )]));
+        assert!(server
+            .lock_processor()
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_UNAVAILABLE"));
+    }
+}
@@ -0,0 +1,525 @@
+//! HTTP REST API of the server.
+//!
+//! Requests and responses have JSON bodies mapped onto `PriceUpdate`s, `ExchangeRateRequest`s
+//! and the `Response`. Each connection carries a single request.
+
+use crate::algorithm::fee_model::ExecutionStyle;
+use crate::json;
+use crate::json::value::{field, identifier, number, Value};
+use crate::request::exchange_rate_request::ExchangeRateRequest;
+use crate::request::price_update::PriceUpdate;
+use crate::request::Line;
+use crate::response::rejection::Rejection;
+use crate::server::http_message::{read_request, write_response, HttpRequest};
+use crate::server::limits::Limits;
+use crate::server::metrics::{get_metrics, METRICS};
+use crate::server::namespaces::Namespaces;
+use crate::server::Server;
+use crate::stream::StreamProcessor;
+use crate::IndexMapTrait;
+use chrono::DateTime;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::io;
+use std::io::BufReader;
+use std::net::{IpAddr, TcpListener};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+/// Content type of JSON bodies.
+const JSON: &str = "application/json";
+
+impl<N, E> Server<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Listen for HTTP connections on the address and serve the REST API until the process is
+    /// stopped.
+    ///
+    /// # Endpoints
+    ///
+    /// - `POST /price-updates` with a price update object or an array of them, answered by
+    ///   `{"accepted":<count>,"outdated":<count>,"rejected":<count>}`. Price updates of a client
+    ///   over its maximal count per second are rejected all at once by status `429`.
+    /// - `POST /rate-requests` with a rate request object or an array of them, answered by
+    ///   `{"best_rate_paths":[...],"unanswered_requests":[...]}`.
+    /// - `GET /best-rate?from=<exchange>:<currency>&to=<exchange>:<currency>` with optional
+    ///   `amount`, `execution_style`, `time_budget` and `as_of` parameters, answered the same
+    ///   way.
+    pub fn serve_http(&self, address: &str) -> io::Result<()> {
+        self.serve_http_listener(TcpListener::bind(address)?)
+    }
+
+    fn serve_http_listener(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                // Keep serving, a single failed connection shouldn't stop the server.
+                Err(error) => {
+                    eprintln!("A connection can not be accepted, error: {}!", error);
+                    continue;
+                }
+            };
+
+            let namespaces = Arc::clone(&self.namespaces);
+            let limits = self.limits.clone();
+            let shutdown = Arc::clone(&self.shutdown);
+            thread::spawn(move || {
+                let result = stream
+                    .peer_addr()
+                    .and_then(|address| Ok((address.ip(), stream.try_clone()?)))
+                    .and_then(|(client, input)| {
+                        Ok((client, read_request(&mut BufReader::new(input))?))
+                    })
+                    .and_then(|(client, request)| {
+                        // The request is finished and answered before the process exits.
+                        let activity = shutdown.start_activity();
+                        let (status, content_type, body) = match request {
+                            _ if activity.is_none() => (
+                                503,
+                                JSON,
+                                json::errors(&["The server is shutting down!".to_string()]),
+                            ),
+                            Ok(request) => Self::respond(&namespaces, &limits, client, &request),
+                            Err(invalid) => {
+                                let (status, message) = invalid.get_status();
+                                (status, JSON, json::errors(&[message.to_string()]))
+                            }
+                        };
+                        write_response(&mut stream, status, content_type, &body)
+                    });
+
+                if let Err(error) = result {
+                    eprintln!("A connection failed, error: {}!", error);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Get status, content type and body answering the request of a client presenting a valid
+    /// token, if required.
+    fn respond(
+        namespaces: &Namespaces<N, E>,
+        limits: &Limits,
+        client: IpAddr,
+        request: &HttpRequest,
+    ) -> (u16, &'static str, String) {
+        let processor = match request.token.as_deref() {
+            Some(token) => namespaces.authorize(token),
+            None => namespaces.get_anonymous(),
+        };
+
+        match processor {
+            Some(processor) => Self::route(&processor, limits, client, request),
+            None => {
+                let errors = [format!(
+                    "The request is rejected <{}>, a valid bearer token is required!",
+                    Rejection::Unauthorized
+                )];
+                (401, JSON, json::errors(&errors))
+            }
+        }
+    }
+
+    /// Get status, content type and body answering the request of the client.
+    fn route(
+        processor: &Mutex<StreamProcessor<N, E>>,
+        limits: &Limits,
+        client: IpAddr,
+        request: &HttpRequest,
+    ) -> (u16, &'static str, String) {
+        let (path, query) = request
+            .target
+            .split_once('?')
+            .unwrap_or((&request.target, ""));
+
+        // Requests over the limit are rejected right away, without waiting for the shared state.
+        let _in_flight = match (request.method.as_str(), path) {
+            ("POST", "/rate-requests") | ("GET", "/best-rate") => {
+                match limits.in_flight_requests.try_acquire() {
+                    Some(guard) => Some(guard),
+                    None => {
+                        let errors = [format!(
+                            "The request is rejected <{}>, retry later!",
+                            Rejection::TooManyRequests
+                        )];
+                        return (503, JSON, json::errors(&errors));
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let result = match (request.method.as_str(), path) {
+            ("POST", "/price-updates") => {
+                match parse_items(&request.body, PriceUpdate::<N, E>::parse_json) {
+                    Ok(price_updates)
+                        if !limits.client_update_rates.allow_many(
+                            limits.max_updates_per_second,
+                            client,
+                            price_updates.len(),
+                        ) =>
+                    {
+                        let mut processor =
+                            processor.lock().unwrap_or_else(PoisonError::into_inner);
+                        for _ in 0..price_updates.len() {
+                            processor.record_rejected_price_update();
+                        }
+                        let errors = [format!(
+                            "The request is rejected <{}>, retry later!",
+                            Rejection::UpdateRateExceeded
+                        )];
+                        return (429, JSON, json::errors(&errors));
+                    }
+                    price_updates => price_updates
+                        .map(|price_updates| Self::post_price_updates(processor, price_updates)),
+                }
+            }
+            ("POST", "/rate-requests") => Self::post_rate_requests(processor, &request.body),
+            ("GET", "/best-rate") => Self::get_best_rate(processor, query),
+            ("GET", "/metrics") => return (200, METRICS, get_metrics(processor)),
+            (_, "/price-updates") | (_, "/rate-requests") | (_, "/best-rate") | (_, "/metrics") => {
+                let errors = ["The method is not allowed!".to_string()];
+                return (405, JSON, json::errors(&errors));
+            }
+            _ => {
+                let errors = ["The endpoint is not known!".to_string()];
+                return (404, JSON, json::errors(&errors));
+            }
+        };
+
+        match result {
+            Ok(body) => (200, JSON, body),
+            Err(errors) => {
+                processor
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .record_parse_error();
+
+                (400, JSON, json::errors(&errors))
+            }
+        }
+    }
+
+    fn post_price_updates(
+        processor: &Mutex<StreamProcessor<N, E>>,
+        price_updates: Vec<PriceUpdate<N, E>>,
+    ) -> String {
+        let (mut accepted, mut outdated, mut rejected) = (0, 0, 0);
+
+        let mut processor = processor.lock().unwrap_or_else(PoisonError::into_inner);
+        for price_update in price_updates {
+            match processor.add_price_update(price_update) {
+                Ok(Some(_)) => accepted += 1,
+                Ok(None) => outdated += 1,
+                Err(_) => rejected += 1,
+            }
+        }
+
+        format!(
+            "{{\"accepted\":{},\"outdated\":{},\"rejected\":{}}}",
+            accepted, outdated, rejected
+        )
+    }
+
+    fn post_rate_requests(
+        processor: &Mutex<StreamProcessor<N, E>>,
+        body: &str,
+    ) -> Result<String, Vec<String>> {
+        let rate_requests = parse_items(body, parse_rate_request::<N, E>)?;
+
+        Ok(Self::answer(processor, rate_requests))
+    }
+
+    fn get_best_rate(
+        processor: &Mutex<StreamProcessor<N, E>>,
+        query: &str,
+    ) -> Result<String, Vec<String>> {
+        let mut members = Vec::new();
+
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            let value = decode_query(value);
+
+            match key {
+                "from" | "to" => {
+                    let (exchange, currency) = value.split_once(':').ok_or_else(|| {
+                        vec![format!(
+                            "The parameter <{}> is not valid, <exchange>:<currency> is expected!",
+                            key
+                        )]
+                    })?;
+                    let prefix = if key == "from" {
+                        "source"
+                    } else {
+                        "destination"
+                    };
+
+                    members.push((format!("{}_exchange", prefix), text(exchange)));
+                    members.push((format!("{}_currency", prefix), text(currency)));
+                }
+                _ => members.push((key.to_string(), text(&value))),
+            }
+        }
+
+        let rate_request = parse_rate_request::<N, E>(&Value::Object(members))?;
+
+        Ok(Self::answer(processor, vec![rate_request]))
+    }
+
+    /// Answer the rate requests and get the JSON of the `Response`.
+    fn answer(
+        processor: &Mutex<StreamProcessor<N, E>>,
+        rate_requests: Vec<ExchangeRateRequest<N, E>>,
+    ) -> String {
+        let lines = rate_requests.into_iter().map(Line::RateRequest).collect();
+        let response = processor
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .answer(lines);
+
+        response.get_json()
+    }
+}
+
+/// Parse the body of an object or an array of objects, collecting errors of all of them.
+fn parse_items<T, F>(body: &str, parse_item: F) -> Result<Vec<T>, Vec<String>>
+where
+    F: Fn(&Value) -> Result<T, Vec<String>>,
+{
+    let value = json::value::parse(body).map_err(|error| vec![error])?;
+    let values = match value {
+        Value::Array(values) => values,
+        value => vec![value],
+    };
+
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+
+    for (number, value) in values.iter().enumerate() {
+        match parse_item(value) {
+            Ok(item) => items.push(item),
+            Err(item_errors) => errors.extend(
+                item_errors
+                    .into_iter()
+                    .map(|error| format!("Item {}: {}", number + 1, error)),
+            ),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(items)
+}
+
+fn parse_rate_request<N, E>(item: &Value) -> Result<ExchangeRateRequest<N, E>, Vec<String>>
+where
+    N: Clone + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: FromStr,
+{
+    let mut errors = Vec::new();
+
+    let source_exchange = identifier::<N>(item, "source_exchange", &mut errors);
+    let source_currency = identifier::<N>(item, "source_currency", &mut errors);
+    let destination_exchange = identifier::<N>(item, "destination_exchange", &mut errors);
+    let destination_currency = identifier::<N>(item, "destination_currency", &mut errors);
+
+    // The amount, the execution style, the time budget and the moment are optional.
+    let amount = item
+        .get("amount")
+        .and_then(|_| number::<E>(item, "amount", &mut errors));
+    let execution_style = item
+        .get("execution_style")
+        .and_then(|_| field(item, "execution_style", &mut errors, ExecutionStyle::parse));
+    let time_budget = item
+        .get("time_budget")
+        .and_then(|_| number::<u32>(item, "time_budget", &mut errors));
+    let as_of = item.get("as_of").and_then(|_| {
+        field(item, "as_of", &mut errors, |value| {
+            DateTime::parse_from_rfc3339(value).ok()
+        })
+    });
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut rate_request = ExchangeRateRequest::new(
+        source_exchange.unwrap(),
+        source_currency.unwrap(),
+        destination_exchange.unwrap(),
+        destination_currency.unwrap(),
+    );
+    rate_request.set_amount(amount);
+    rate_request.set_execution_style(execution_style.unwrap_or_default());
+    rate_request.set_time_budget(time_budget);
+    rate_request.set_as_of(as_of);
+
+    Ok(rate_request)
+}
+
+fn text(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+/// Decode `+` and `%XX` escapes of a query parameter value.
+fn decode_query(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => decoded.push(b' '),
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::server::http::decode_query;
+    use crate::server::http_message::HttpRequest;
+    use crate::server::Server;
+    use std::net::IpAddr;
+
+    fn route(server: &Server<String, f32>, method: &str, target: &str, body: &str) -> String {
+        respond(server, method, target, body, None)
+    }
+
+    fn respond(
+        server: &Server<String, f32>,
+        method: &str,
+        target: &str,
+        body: &str,
+        token: Option<&str>,
+    ) -> String {
+        let request = HttpRequest {
+            method: method.to_string(),
+            target: target.to_string(),
+            body: body.to_string(),
+            token: token.map(str::to_string),
+        };
+
+        let (status, _, body) = Server::respond(
+            &server.namespaces,
+            &server.limits,
+            IpAddr::from([127, 0, 0, 1]),
+            &request,
+        );
+        format!("{} {}", status, body)
+    }
+
+    #[test]
+    fn route_endpoints() {
+        let mut server = Server::<String, f32>::new(Settings::new());
+
+        // Test that price updates are accepted unless outdated.
+        let price_updates = "[{\"timestamp\":\"2019-01-20T09:42:23+00:00\",\"exchange\":\"e1\",\
+                    \"source_currency\":\"BTC\",\"destination_currency\":\"USD\",\
+                    \"forward_factor\":1000.0,\"backward_factor\":0.001},\
+                    {\"timestamp\":\"2019-01-20T09:42:22+00:00\",\"exchange\":\"E1\",\
+                    \"source_currency\":\"BTC\",\"destination_currency\":\"USD\",\
+                    \"bid\":\"999.0\",\"ask\":\"1001.0\"}]";
+        assert_eq!(
+            route(&server, "POST", "/price-updates", price_updates),
+            "200 {\"accepted\":1,\"outdated\":1,\"rejected\":0}"
+        );
+
+        // Test that rate requests are answered.
+        let body = "{\"source_exchange\":\"E1\",\"source_currency\":\"BTC\",\
+                    \"destination_exchange\":\"E1\",\"destination_currency\":\"EUR\"}";
+        assert_eq!(
+            route(&server, "POST", "/rate-requests", body),
+            "200 {\"best_rate_paths\":[],\"unanswered_requests\":[{\"source\":\
+             {\"exchange\":\"E1\",\"currency\":\"BTC\"},\"destination\":\
+             {\"exchange\":\"E1\",\"currency\":\"EUR\"},\"reason\":\"unknown_destination_currency\"}]}"
+        );
+        assert_eq!(
+            route(
+                &server,
+                "GET",
+                "/best-rate?from=E1:BTC&to=e1%3AUSD&amount=2",
+                ""
+            ),
+            "200 {\"best_rate_paths\":[{\"rate\":1000,\"path\":[{\"exchange\":\"E1\",\
+             \"currency\":\"BTC\"},{\"exchange\":\"E1\",\"currency\":\"USD\"}]}],\
+             \"unanswered_requests\":[]}"
+        );
+
+        // Test that wrong requests are reported.
+        assert_eq!(
+            route(
+                &server,
+                "POST",
+                "/rate-requests",
+                "{\"source_exchange\":\"E1\"}"
+            ),
+            "400 {\"errors\":[\"Item 1: The field <source_currency> is missing!\",\
+             \"Item 1: The field <destination_exchange> is missing!\",\
+             \"Item 1: The field <destination_currency> is missing!\"]}"
+        );
+        assert!(route(&server, "GET", "/best-rate?from=E1", "").starts_with("400"));
+        assert!(route(&server, "GET", "/price-updates", "").starts_with("405"));
+        assert!(route(&server, "GET", "/metrics", "")
+            .contains("\nexchange_rate_parse_errors_total 2\n"));
+        assert!(route(&server, "GET", "/", "").starts_with("404"));
+
+        // Test that price updates over the rate of the client are rejected all at once.
+        server.set_max_updates_per_second(Some(1));
+        assert!(route(&server, "POST", "/price-updates", price_updates).starts_with("429"));
+        assert!(route(&server, "GET", "/metrics", "")
+            .contains("\nexchange_rate_rejected_price_updates_total 2\n"));
+
+        // Test that requests over the in-flight limit are rejected.
+        server.set_max_in_flight_requests(Some(0));
+        assert!(route(&server, "GET", "/best-rate?from=E1:BTC&to=E1:USD", "").starts_with("503"));
+    }
+
+    #[test]
+    fn respond_with_tokens() {
+        let mut server = Server::<String, f32>::new(Settings::new());
+        server.add_token("a".to_string(), Some("A".to_string()));
+        server.add_token("b".to_string(), Some("B".to_string()));
+        let body = "{\"timestamp\":\"2019-01-20T09:42:23+00:00\",\"exchange\":\"E1\",\
+                    \"source_currency\":\"BTC\",\"destination_currency\":\"USD\",\
+                    \"forward_factor\":1000.0,\"backward_factor\":0.001}";
+        let target = "/best-rate?from=E1:BTC&to=E1:USD";
+
+        // Test that a token is required.
+        assert!(respond(&server, "POST", "/price-updates", body, None).starts_with("401"));
+        assert!(respond(&server, "POST", "/price-updates", body, Some("c")).starts_with("401"));
+
+        // Test that price updates are shared within the namespace only.
+        respond(&server, "POST", "/price-updates", body, Some("a"));
+        assert!(respond(&server, "GET", target, "", Some("a")).contains("\"rate\":1000"));
+        assert!(respond(&server, "GET", target, "", Some("b")).contains("\"best_rate_paths\":[]"));
+    }
+
+    #[test]
+    fn decode_query_value() {
+        assert_eq!(decode_query("KRAKEN%3ABTC+x%2"), "KRAKEN:BTC x%2");
+    }
+}
@@ -0,0 +1,223 @@
+//! Minimal HTTP/1.1 messages, a single request per connection.
+
+use std::io;
+use std::io::{BufRead, Read, Write};
+
+/// Parsed HTTP request.
+pub(super) struct HttpRequest {
+    pub method: String,
+    pub target: String,
+    // Only the REST API reads bodies and tokens.
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    pub body: String,
+    /// Token of the `Authorization: Bearer <token>` header.
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    pub token: Option<String>,
+}
+
+/// Maximal length of the request line and of each header line in bytes.
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+
+/// Maximal count of header lines.
+const MAX_HEADER_COUNT: usize = 100;
+
+/// Maximal size of the body in bytes.
+const MAX_BODY_SIZE: usize = 8 * 1024 * 1024;
+
+/// Reason why a request isn't read.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(super) enum Invalid {
+    /// The request isn't a valid HTTP request.
+    Malformed,
+    /// A line, the headers or the body are over their maximal size.
+    TooLarge,
+}
+
+impl Invalid {
+    /// Get status and message of the response rejecting the request.
+    pub fn get_status(&self) -> (u16, &'static str) {
+        match self {
+            Invalid::Malformed => (400, "The request is not valid!"),
+            Invalid::TooLarge => (413, "The request is too large!"),
+        }
+    }
+}
+
+/// Read the request line, headers and the body of `Content-Length`, `Invalid` if the request is
+/// malformed or too large, which is found out before anything over the maximal size is read.
+pub(super) fn read_request<R: BufRead>(input: &mut R) -> io::Result<Result<HttpRequest, Invalid>> {
+    let line = match read_line(input)? {
+        Ok(line) => line,
+        Err(invalid) => return Ok(Err(invalid)),
+    };
+
+    let mut items = line.split_whitespace();
+    let (method, target) = match (items.next(), items.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return Ok(Err(Invalid::Malformed)),
+    };
+
+    let mut content_length = 0;
+    let mut token = None;
+    for count in 0.. {
+        let header = match read_line(input)? {
+            Ok(header) => header,
+            Err(invalid) => return Ok(Err(invalid)),
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+        if count == MAX_HEADER_COUNT {
+            return Ok(Err(Invalid::TooLarge));
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                match value.trim().parse() {
+                    Ok(length) if length > MAX_BODY_SIZE => return Ok(Err(Invalid::TooLarge)),
+                    Ok(length) => content_length = length,
+                    Err(_) => return Ok(Err(Invalid::Malformed)),
+                }
+            } else if name.trim().eq_ignore_ascii_case("authorization") {
+                token = value
+                    .trim()
+                    .strip_prefix("Bearer ")
+                    .map(|token| token.trim().to_string());
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    input.read_exact(&mut body)?;
+
+    Ok(String::from_utf8(body)
+        .map(|body| HttpRequest {
+            method,
+            target,
+            body,
+            token,
+        })
+        .map_err(|_| Invalid::Malformed))
+}
+
+/// Read a line of at most `MAX_LINE_LENGTH` bytes, an empty one at the end of the input.
+fn read_line<R: BufRead>(input: &mut R) -> io::Result<Result<String, Invalid>> {
+    let mut line = String::new();
+    let length = input
+        .by_ref()
+        .take(MAX_LINE_LENGTH as u64 + 1)
+        .read_line(&mut line)?;
+
+    Ok(if length > MAX_LINE_LENGTH {
+        Err(Invalid::TooLarge)
+    } else {
+        Ok(line)
+    })
+}
+
+/// Write the response and ask the client to close the connection.
+pub(super) fn write_response<W: Write>(
+    output: &mut W,
+    status: u16,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        401 => "Unauthorized",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        429 => "Too Many Requests",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+
+    write!(
+        output,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    )?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::http_message::{read_request, write_response, Invalid};
+    use std::io::BufReader;
+
+    #[test]
+    fn read_http_request() {
+        let text_input = "POST /rate-requests HTTP/1.1\r\nHost: localhost\r\n\
+                          Authorization: Bearer secret\r\nContent-Length: 2\r\n\r\n{}"
+            .as_bytes();
+
+        let request = read_request(&mut BufReader::new(text_input))
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.target, "/rate-requests");
+        assert_eq!(request.body, "{}");
+        assert_eq!(request.token, Some("secret".to_string()));
+
+        assert_eq!(
+            read_request(&mut BufReader::new("\r\n".as_bytes()))
+                .unwrap()
+                .err(),
+            Some(Invalid::Malformed)
+        );
+    }
+
+    #[test]
+    fn reject_too_large_http_request() {
+        let read = |input: String| {
+            read_request(&mut BufReader::new(input.as_bytes()))
+                .unwrap()
+                .err()
+        };
+
+        // Test that a huge body is rejected before it's allocated.
+        assert_eq!(
+            read("POST / HTTP/1.1\r\nContent-Length: 99999999999999\r\n\r\n".to_string()),
+            Some(Invalid::TooLarge)
+        );
+
+        // Test that long lines and too many headers are rejected.
+        assert_eq!(
+            read(format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(10_000))),
+            Some(Invalid::TooLarge)
+        );
+        assert_eq!(
+            read(format!(
+                "GET / HTTP/1.1\r\nHost: {}\r\n\r\n",
+                "a".repeat(10_000)
+            )),
+            Some(Invalid::TooLarge)
+        );
+        assert_eq!(
+            read(format!(
+                "GET / HTTP/1.1\r\n{}\r\n",
+                "Host: a\r\n".repeat(101)
+            )),
+            Some(Invalid::TooLarge)
+        );
+    }
+
+    #[test]
+    fn write_http_response() {
+        let mut output = Vec::new();
+        write_response(&mut output, 404, "text/plain", "none").unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: 4\r\n\
+             Connection: close\r\n\r\nnone"
+        );
+    }
+}
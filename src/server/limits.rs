@@ -0,0 +1,181 @@
+//! Limits keeping the shared solver responsive.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Limits of connections, the in-flight requests are shared by all of them.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Limits {
+    pub max_updates_per_second: Option<u32>,
+    pub in_flight_requests: InFlightRequests,
+    /// Rates of price updates of HTTP clients, which send each request by a connection of its
+    /// own.
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    pub client_update_rates: ClientUpdateRates,
+}
+
+/// Count of requests being answered, shared by all connections.
+#[derive(Clone, Debug, Default)]
+pub(super) struct InFlightRequests {
+    max: Option<usize>,
+    count: Arc<AtomicUsize>,
+}
+
+impl InFlightRequests {
+    /// Set maximal count of requests being answered at once, `None` for no limit.
+    pub fn set_max(&mut self, max: Option<usize>) {
+        self.max = max;
+    }
+
+    /// Count a request in until the guard is dropped, `None` if the limit is reached.
+    pub fn try_acquire(&self) -> Option<InFlightGuard> {
+        let count = self.count.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlightGuard {
+            count: Arc::clone(&self.count),
+        };
+
+        // The guard counts the request out again if it's over the limit.
+        if self.max.is_some_and(|max| count >= max) {
+            return None;
+        }
+
+        Some(guard)
+    }
+}
+
+/// Request counted in `InFlightRequests` until dropped.
+pub(super) struct InFlightGuard {
+    count: Arc<AtomicUsize>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Rate of price updates of a single connection, counted in one second windows.
+#[derive(Debug)]
+pub(super) struct UpdateRate {
+    max_per_second: Option<u32>,
+    window_start: Instant,
+    count: u32,
+}
+
+impl UpdateRate {
+    pub fn new(max_per_second: Option<u32>) -> Self {
+        Self {
+            max_per_second,
+            window_start: Instant::now(),
+            count: 0,
+        }
+    }
+
+    /// Count a price update in, `false` if the rate of the current window is exceeded.
+    pub fn allow(&mut self) -> bool {
+        self.allow_many(1)
+    }
+
+    /// Count price updates in at once, `false` without counting any of them if they exceed the
+    /// rate of the current window.
+    pub fn allow_many(&mut self, count: usize) -> bool {
+        let max_per_second = match self.max_per_second {
+            Some(max_per_second) => max_per_second,
+            None => return true,
+        };
+
+        if self.is_window_over() {
+            self.window_start = Instant::now();
+            self.count = 0;
+        }
+
+        if self.count as usize + count > max_per_second as usize {
+            return false;
+        }
+
+        self.count += count as u32;
+        true
+    }
+
+    /// Check whether the current window is over, so nothing counted in it matters anymore.
+    fn is_window_over(&self) -> bool {
+        self.window_start.elapsed() >= Duration::from_secs(1)
+    }
+}
+
+/// Rates of price updates of clients identified by their address, shared by all connections.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+pub(super) struct ClientUpdateRates {
+    rates: Arc<Mutex<HashMap<IpAddr, UpdateRate>>>,
+}
+
+#[cfg_attr(not(feature = "http"), allow(dead_code))]
+impl ClientUpdateRates {
+    /// Count price updates of the client in at once, `false` without counting any of them if
+    /// they exceed the maximal count per second, see `UpdateRate::allow_many`.
+    pub fn allow_many(&self, max_per_second: Option<u32>, client: IpAddr, count: usize) -> bool {
+        if max_per_second.is_none() {
+            return true;
+        }
+
+        let mut rates = self.rates.lock().unwrap_or_else(PoisonError::into_inner);
+        // Clients quiet for a whole window are forgotten, so the rates don't pile up.
+        rates.retain(|_, rate| !rate.is_window_over());
+        rates
+            .entry(client)
+            .or_insert_with(|| UpdateRate::new(max_per_second))
+            .allow_many(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::limits::{ClientUpdateRates, InFlightRequests, UpdateRate};
+    use std::net::IpAddr;
+
+    #[test]
+    fn in_flight_requests() {
+        let mut in_flight = InFlightRequests::default();
+        in_flight.set_max(Some(1));
+
+        // Test that the request is counted out when the guard is dropped.
+        let guard = in_flight.try_acquire();
+        assert!(guard.is_some());
+        assert!(in_flight.try_acquire().is_none());
+        drop(guard);
+        assert!(in_flight.try_acquire().is_some());
+    }
+
+    #[test]
+    fn update_rate() {
+        let mut update_rate = UpdateRate::new(Some(2));
+        assert!(update_rate.allow());
+        assert!(update_rate.allow());
+        assert!(!update_rate.allow());
+
+        let mut update_rate = UpdateRate::new(None);
+        assert!((0..100).all(|_| update_rate.allow()));
+
+        // Test that price updates over the rate aren't counted in.
+        let mut update_rate = UpdateRate::new(Some(3));
+        assert!(update_rate.allow_many(2));
+        assert!(!update_rate.allow_many(2));
+        assert!(update_rate.allow_many(1));
+    }
+
+    #[test]
+    fn client_update_rates() {
+        let rates = ClientUpdateRates::default();
+        let (a, b) = (IpAddr::from([127, 0, 0, 1]), IpAddr::from([127, 0, 0, 2]));
+
+        // Test that each client is counted separately, by all its connections.
+        assert!(rates.allow_many(Some(2), a, 2));
+        assert!(!rates.clone().allow_many(Some(2), a, 1));
+        assert!(rates.allow_many(Some(2), b, 1));
+        assert!(rates.allow_many(None, a, 100));
+    }
+}
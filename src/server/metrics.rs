@@ -0,0 +1,118 @@
+//! Prometheus metrics endpoint of the server.
+
+use crate::server::http_message::{read_request, write_response};
+use crate::server::Server;
+use crate::stream::StreamProcessor;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::io;
+use std::io::BufReader;
+use std::net::TcpListener;
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+
+/// Content type of the Prometheus text exposition format.
+pub(super) const METRICS: &str = "text/plain; version=0.0.4";
+
+impl<N, E> Server<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Listen for HTTP connections on the address and answer `GET /metrics` by the processing
+    /// metrics in the Prometheus text format, until the process is stopped.
+    pub fn serve_metrics(&self, address: &str) -> io::Result<()> {
+        for stream in TcpListener::bind(address)?.incoming() {
+            // Scrapes are cheap, so they're answered one by one.
+            let result = stream.and_then(|stream| {
+                let request = read_request(&mut BufReader::new(&stream))?;
+
+                match request {
+                    Ok(request) if request.method == "GET" && request.target == "/metrics" => {
+                        write_response(&mut &stream, 200, METRICS, &self.get_metrics())
+                    }
+                    Err(invalid) => {
+                        let (status, message) = invalid.get_status();
+                        write_response(&mut &stream, status, METRICS, &format!("{}\n", message))
+                    }
+                    _ => write_response(&mut &stream, 404, METRICS, "The endpoint is not known!\n"),
+                }
+            });
+
+            // Keep serving, a single failed scrape shouldn't stop the server.
+            if let Err(error) = result {
+                eprintln!("A metrics connection failed, error: {}!", error);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get metrics of the default state and health of the exchange feeds, if any.
+    fn get_metrics(&self) -> String {
+        #[allow(unused_mut)]
+        let mut metrics = get_metrics(&self.processor);
+        #[cfg(feature = "feed")]
+        metrics.push_str(&self.feeds.get_metrics());
+
+        metrics
+    }
+}
+
+/// Get metrics of the processor in the Prometheus text format.
+pub(super) fn get_metrics<N, E>(processor: &Mutex<StreamProcessor<N, E>>) -> String
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    processor
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_metrics()
+        .get_output()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::server::Server;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    #[test]
+    fn serve_metrics() {
+        // Find a free port, the listener is bound again by the server.
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let server_address = address.clone();
+        thread::spawn(move || {
+            Server::<String, f32>::new(Settings::new())
+                .serve_metrics(&server_address)
+                .unwrap()
+        });
+
+        let mut client = loop {
+            match TcpStream::connect(&address) {
+                Ok(client) => break client,
+                Err(_) => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+        client
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\nexchange_rate_price_updates_total 0\n"));
+    }
+}
@@ -0,0 +1,168 @@
+//! Access tokens and namespaces of the server.
+
+use crate::stream::StreamProcessor;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Processor shared by connections of the same namespace.
+pub(super) type SharedProcessor<N, E> = Arc<Mutex<StreamProcessor<N, E>>>;
+
+/// `Namespaces` structure.
+///
+/// Holds access tokens and the state of namespaces they grant access to. A token without a
+/// namespace grants access to the default state, no token is needed if none is added.
+pub(super) struct Namespaces<N, E> {
+    default: SharedProcessor<N, E>,
+    tokens: Mutex<HashMap<String, Option<String>>>,
+    processors: Mutex<HashMap<String, SharedProcessor<N, E>>>,
+}
+
+impl<N, E> Namespaces<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of `Namespaces` structure without any token.
+    pub fn new(default: SharedProcessor<N, E>) -> Self {
+        Self {
+            default,
+            tokens: Mutex::new(HashMap::new()),
+            processors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add the token granting access to the namespace, `None` for the default one.
+    pub fn add_token(&self, token: String, namespace: Option<String>) {
+        self.tokens
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(token, namespace);
+    }
+
+    /// Check whether clients have to present a token.
+    pub fn is_auth_required(&self) -> bool {
+        !self
+            .tokens
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+    }
+
+    /// Get the processor of clients not presenting a token, `None` if a token is required.
+    pub fn get_anonymous(&self) -> Option<SharedProcessor<N, E>> {
+        if self.is_auth_required() {
+            None
+        } else {
+            Some(Arc::clone(&self.default))
+        }
+    }
+
+    /// Get the processor of the namespace the token grants access to, `None` if it's not valid.
+    ///
+    /// The state of a namespace is created empty by the first access, with the settings and
+    /// options of the default one.
+    pub fn authorize(&self, token: &str) -> Option<SharedProcessor<N, E>> {
+        let namespace = self
+            .tokens
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(token)
+            .cloned()?;
+
+        let namespace = match namespace {
+            Some(namespace) => namespace,
+            None => return Some(Arc::clone(&self.default)),
+        };
+
+        let mut processors = self
+            .processors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let processor = processors.entry(namespace).or_insert_with(|| {
+            let processor = self
+                .default
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .new_session();
+            Arc::new(Mutex::new(processor))
+        });
+
+        Some(Arc::clone(processor))
+    }
+
+    /// Get the processors of the default state and of all namespaces accessed so far.
+    pub fn get_processors(&self) -> Vec<SharedProcessor<N, E>> {
+        let processors = self
+            .processors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        std::iter::once(Arc::clone(&self.default))
+            .chain(processors.values().map(Arc::clone))
+            .collect()
+    }
+}
+
+/// The first item of a line presenting an access token.
+pub(super) const AUTH: &str = "AUTH";
+
+/// Parse `AUTH <token>` line, `None` is returned for other lines.
+pub(super) fn parse_auth_line(line: &str) -> Option<Result<&str, Vec<String>>> {
+    let mut iter = line.split_whitespace();
+
+    if !iter.next()?.eq_ignore_ascii_case(AUTH) {
+        return None;
+    }
+
+    match iter.next() {
+        Some(token) => Some(Ok(token)),
+        None => Some(Err(vec!["The line item <token> is missing!".to_string()])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::server::namespaces::{parse_auth_line, Namespaces};
+    use crate::stream::StreamProcessor;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn authorize() {
+        let default = Arc::new(Mutex::new(StreamProcessor::<String, f32>::new(
+            Settings::new(),
+        )));
+        let namespaces = Namespaces::new(Arc::clone(&default));
+        assert!(namespaces.get_anonymous().is_some());
+
+        namespaces.add_token("admin".to_string(), None);
+        namespaces.add_token("a1".to_string(), Some("A".to_string()));
+        namespaces.add_token("a2".to_string(), Some("A".to_string()));
+        namespaces.add_token("b".to_string(), Some("B".to_string()));
+        assert!(namespaces.get_anonymous().is_none());
+        assert!(namespaces.authorize("unknown").is_none());
+
+        // Test that tokens of the same namespace share its state only.
+        let admin = namespaces.authorize("admin").unwrap();
+        let a1 = namespaces.authorize("a1").unwrap();
+        let a2 = namespaces.authorize("a2").unwrap();
+        let b = namespaces.authorize("b").unwrap();
+        assert!(Arc::ptr_eq(&admin, &default));
+        assert!(Arc::ptr_eq(&a1, &a2));
+        assert!(!Arc::ptr_eq(&a1, &b));
+        assert!(!Arc::ptr_eq(&a1, &default));
+    }
+
+    #[test]
+    fn parse_auth() {
+        assert_eq!(parse_auth_line("auth secret"), Some(Ok("secret")));
+        assert_eq!(parse_auth_line("SESSION SHARED"), None);
+        assert!(parse_auth_line("AUTH").unwrap().is_err());
+    }
+}
@@ -0,0 +1,69 @@
+//! Session mode of a server connection.
+
+/// Whether a connection works with the state shared by all connections or with its own one.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Session {
+    /// Price updates are shared by all connections, giving a market-wide view.
+    Shared,
+    /// Price updates of the connection are its own, so batches are self-contained.
+    Isolated,
+}
+
+impl Session {
+    /// The first item of a line switching the session mode.
+    pub const LINE_TYPE: &'static str = "SESSION";
+
+    pub fn get_label(&self) -> String {
+        match self {
+            Session::Shared => "SHARED".to_string(),
+            Session::Isolated => "ISOLATED".to_string(),
+        }
+    }
+
+    /// Parse `SESSION <SHARED|ISOLATED>` line, `None` is returned for other lines.
+    pub fn parse_line(line: &str) -> Option<Result<Self, Vec<String>>> {
+        let mut iter = line.split_whitespace();
+
+        if !iter.next()?.eq_ignore_ascii_case(Self::LINE_TYPE) {
+            return None;
+        }
+
+        let session = match iter.next().map(str::to_uppercase) {
+            Some(label) if label == Session::Shared.get_label() => Session::Shared,
+            Some(label) if label == Session::Isolated.get_label() => Session::Isolated,
+            Some(_) => {
+                return Some(Err(vec![
+                    "The line item <session> can not be parsed (wrong format)!".to_string(),
+                ]))
+            }
+            None => return Some(Err(vec!["The line item <session> is missing!".to_string()])),
+        };
+
+        Some(Ok(session))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::session::Session;
+
+    #[test]
+    fn parse_line() {
+        assert_eq!(
+            Session::parse_line("SESSION ISOLATED"),
+            Some(Ok(Session::Isolated))
+        );
+        assert_eq!(
+            Session::parse_line("session shared"),
+            Some(Ok(Session::Shared))
+        );
+        assert_eq!(
+            Session::parse_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD"),
+            None
+        );
+        assert_eq!(Session::parse_line(""), None);
+
+        assert!(Session::parse_line("SESSION").unwrap().is_err());
+        assert!(Session::parse_line("SESSION PRIVATE").unwrap().is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Graceful shutdown on termination signals.
+
+use crate::checkpoint;
+use crate::signal;
+use crate::signal::{SIGINT, SIGTERM};
+use crate::stream::StreamProcessor;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// Time in-flight lines have to finish once the shutdown is requested.
+const GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// `Shutdown` structure.
+///
+/// Counts lines being processed, from reading them to writing their output, so the process
+/// exits only after the output of all of them was flushed.
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    requested: AtomicBool,
+    active: Mutex<usize>,
+    idle: Condvar,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether the shutdown was requested, no new lines should be processed then.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    pub fn request(&self) {
+        let _active = self.lock_active();
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Count a line in until the guard is dropped, `None` if the shutdown was requested.
+    pub fn start_activity(&self) -> Option<Activity<'_>> {
+        let mut active = self.lock_active();
+        if self.is_requested() {
+            return None;
+        }
+
+        *active += 1;
+        Some(Activity { shutdown: self })
+    }
+
+    /// Wait until no line is being processed, `false` if the timeout elapsed first.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let active = self.lock_active();
+        let (_active, result) = self
+            .idle
+            .wait_timeout_while(active, timeout, |active| *active > 0)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        !result.timed_out()
+    }
+
+    fn lock_active(&self) -> std::sync::MutexGuard<'_, usize> {
+        self.active.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Line counted in by `Shutdown` until dropped.
+pub struct Activity<'a> {
+    shutdown: &'a Shutdown,
+}
+
+impl Drop for Activity<'_> {
+    fn drop(&mut self) {
+        *self.shutdown.lock_active() -= 1;
+        self.shutdown.idle.notify_all();
+    }
+}
+
+/// Shut the process down gracefully once SIGTERM or SIGINT arrives.
+///
+/// No new lines are processed then, lines in flight are given the grace period to finish and
+/// flush their output, the known `PriceUpdate`s are written to the snapshot file if any and the
+/// process exits. The count of replaced snapshots is kept.
+pub fn exit_on_signal<N, E>(
+    shutdown: Arc<Shutdown>,
+    processor: Arc<Mutex<StreamProcessor<N, E>>>,
+    snapshot_path: Option<PathBuf>,
+    retention: usize,
+) where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    signal::flag_on(SIGINT);
+    signal::flag_on(SIGTERM);
+
+    thread::spawn(move || {
+        while !signal::is_received(SIGINT) && !signal::is_received(SIGTERM) {
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        shutdown.request();
+        if !shutdown.wait_idle(GRACE_PERIOD) {
+            eprintln!("Lines still in flight are dropped, the grace period is over!");
+        }
+
+        let _ = io::stdout().flush();
+
+        if let Some(path) = snapshot_path {
+            let processor = processor.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut snapshot = Vec::new();
+            let result = processor
+                .write_snapshot(&mut snapshot)
+                .and_then(|()| checkpoint::write_snapshot(&path, &snapshot, retention));
+
+            if let Err(error) = result {
+                eprintln!(
+                    "The snapshot can not be written to <{}>, error: {}!",
+                    path.display(),
+                    error
+                );
+                std::process::exit(1);
+            }
+        }
+
+        std::process::exit(0);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shutdown::Shutdown;
+    use std::time::Duration;
+
+    #[test]
+    fn start_activity() {
+        let shutdown = Shutdown::new();
+
+        // Test that the shutdown waits for the activity.
+        let activity = shutdown.start_activity();
+        assert!(activity.is_some());
+        shutdown.request();
+        assert!(shutdown.start_activity().is_none());
+        assert!(!shutdown.wait_idle(Duration::from_millis(10)));
+
+        drop(activity);
+        assert!(shutdown.wait_idle(Duration::from_millis(10)));
+    }
+}
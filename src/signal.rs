@@ -0,0 +1,52 @@
+//! Flags of Unix signals.
+//!
+//! The handler of a signal only sets its flag, which is async-signal-safe, and threads waiting
+//! for the signal poll the flag.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Hangup, asking to reload the config.
+pub(crate) const SIGHUP: i32 = 1;
+/// Interrupt from the keyboard.
+pub(crate) const SIGINT: i32 = 2;
+/// Termination request.
+pub(crate) const SIGTERM: i32 = 15;
+
+/// Count of signal numbers having a flag.
+const SIGNAL_COUNT: usize = 32;
+
+/// Whether the signal of the number arrived since its flag was last taken.
+static RECEIVED: [AtomicBool; SIGNAL_COUNT] = [const { AtomicBool::new(false) }; SIGNAL_COUNT];
+
+/// Set the flag of the signal whenever it arrives, instead of its default action.
+#[cfg(unix)]
+pub(crate) fn flag_on(signum: i32) {
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+
+    extern "C" fn handle_signal(signum: i32) {
+        if let Some(received) = RECEIVED.get(signum as usize) {
+            received.store(true, Ordering::SeqCst);
+        }
+    }
+
+    // The handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        signal(signum, handle_signal);
+    }
+}
+
+/// Signals aren't supported, the flag is never set.
+#[cfg(not(unix))]
+pub(crate) fn flag_on(_signum: i32) {}
+
+/// Check whether the signal arrived.
+pub(crate) fn is_received(signum: i32) -> bool {
+    RECEIVED[signum as usize].load(Ordering::SeqCst)
+}
+
+/// Check whether the signal arrived since the last check and clear its flag.
+pub(crate) fn take(signum: i32) -> bool {
+    RECEIVED[signum as usize].swap(false, Ordering::SeqCst)
+}
@@ -0,0 +1,224 @@
+//! Deterministic market simulator.
+//!
+//! A `Simulator` evolves a synthetic market over time and streams its price updates as input
+//! lines, e.g. to be fed to the watch mode or the server for exercising the streaming features
+//! end to end. The same arguments always give the same stream.
+
+use crate::synthetic::Random;
+use chrono::{DateTime, Duration, FixedOffset};
+
+/// Moment the simulated time starts at.
+const START: &str = "2019-01-20T00:00:00+00:00";
+
+/// `Simulator` structure.
+///
+/// Simulates exchanges named `E0`, `E1`, ... quoting pairs of currencies named `C0`, `C1`, ...
+/// Each currency has a value following a random walk, shared by all exchanges, and quotes are
+/// the ratios of the values lowered by a spread, so quotes of the same moment never form an
+/// arbitrage. The stream starts by a quote of every pair the market quotes, each price update
+/// that follows re-quotes a random pair at the current values. Occasionally, the price update
+/// is an injected arbitrage: its forward rate is raised above the inverse of the backward one,
+/// until the pair is quoted again. Quotes of different moments may form arbitrages too, as the
+/// values move in the meantime.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::simulator::Simulator;
+///
+/// let simulator = || Simulator::new(2, 4, 1.0, 42).with_arbitrage_probability(0.1);
+/// let lines: Vec<String> = simulator().take(100).collect();
+///
+/// assert_eq!(lines[0].split_whitespace().count(), 6);
+/// assert_eq!(lines, simulator().take(100).collect::<Vec<_>>());
+/// ```
+pub struct Simulator {
+    random: Random,
+    values: Vec<f64>,
+    pairs: Vec<(usize, usize, usize)>,
+    time: DateTime<FixedOffset>,
+    step: Duration,
+    volatility: f64,
+    arbitrage_probability: f64,
+    quoted: usize,
+    injected_arbitrages: usize,
+}
+
+impl Simulator {
+    /// Create a new instance of `Simulator` structure.
+    ///
+    /// Every exchange quotes each pair of currencies with the probability of the density,
+    /// between `0` and `1`, at least one pair is quoted. Price updates are a second apart, values
+    /// move by up to `0.1 %` per price update and no arbitrage is injected.
+    pub fn new(exchanges: usize, currencies: usize, density: f64, seed: u64) -> Self {
+        let mut random = Random::new(seed);
+        let currencies = currencies.max(2);
+        let values = (0..currencies)
+            .map(|_| 10_f64.powf(random.unit() * 6.0 - 3.0))
+            .collect();
+
+        let mut pairs = Vec::new();
+        for exchange in 0..exchanges.max(1) {
+            for source in 0..currencies {
+                for destination in source + 1..currencies {
+                    if random.unit() < density {
+                        pairs.push((exchange, source, destination));
+                    }
+                }
+            }
+        }
+        if pairs.is_empty() {
+            pairs.push((0, 0, 1));
+        }
+
+        Self {
+            random,
+            values,
+            pairs,
+            time: DateTime::parse_from_rfc3339(START).unwrap(),
+            step: Duration::seconds(1),
+            volatility: 0.001,
+            arbitrage_probability: 0.0,
+            quoted: 0,
+            injected_arbitrages: 0,
+        }
+    }
+
+    /// Set the time between two price updates.
+    pub fn with_step(mut self, step: Duration) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Set the most a value moves by per price update, relative to the value.
+    pub fn with_volatility(mut self, volatility: f64) -> Self {
+        self.volatility = volatility;
+        self
+    }
+
+    /// Set the probability of a price update being an injected arbitrage, between `0` and `1`.
+    pub fn with_arbitrage_probability(mut self, arbitrage_probability: f64) -> Self {
+        self.arbitrage_probability = arbitrage_probability;
+        self
+    }
+
+    /// Get the `(exchange, source_currency, destination_currency)` indexes of the quoted pairs.
+    pub fn get_pairs(&self) -> &[(usize, usize, usize)] {
+        &self.pairs
+    }
+
+    /// Get the moment of the last price update.
+    pub fn get_time(&self) -> DateTime<FixedOffset> {
+        self.time
+    }
+
+    /// Get count of the arbitrages injected so far.
+    pub fn get_injected_arbitrages(&self) -> usize {
+        self.injected_arbitrages
+    }
+
+    /// Move the values of all currencies by a random step.
+    fn walk(&mut self) {
+        for index in 0..self.values.len() {
+            let step = (self.random.unit() * 2.0 - 1.0) * self.volatility;
+            self.values[index] *= 1.0 + step;
+        }
+    }
+
+    /// Get the price update line of the pair at the current values and time.
+    fn quote(&mut self, (exchange, source, destination): (usize, usize, usize)) -> String {
+        let rate = self.values[source] / self.values[destination];
+        let spread = 0.001 + self.random.unit() * 0.009;
+        let backward_factor = (1.0 - spread) / rate;
+
+        let forward_factor = if self.random.unit() < self.arbitrage_probability {
+            self.injected_arbitrages += 1;
+            rate * (1.02 + self.random.unit() * 0.03)
+        } else {
+            rate * (1.0 - spread)
+        };
+
+        format!(
+            "{} E{} C{} C{} {} {}",
+            self.time.to_rfc3339(),
+            exchange,
+            source,
+            destination,
+            forward_factor,
+            backward_factor
+        )
+    }
+}
+
+impl Iterator for Simulator {
+    type Item = String;
+
+    /// Get the next price update line, the stream never ends.
+    fn next(&mut self) -> Option<String> {
+        let pair = if self.quoted < self.pairs.len() {
+            // The initial quotes of all pairs are of the same moment.
+            self.pairs[self.quoted]
+        } else {
+            self.time += self.step;
+            self.walk();
+            self.pairs[self.random.below(self.pairs.len())]
+        };
+        self.quoted += 1;
+
+        Some(self.quote(pair))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::simulator::Simulator;
+    use crate::stream::StreamProcessor;
+
+    /// Count the arbitrage alerts of the lines fed to a stream processor monitoring arbitrages.
+    fn count_appeared(lines: Vec<String>) -> usize {
+        let mut processor = StreamProcessor::<String, f64>::new(Settings::new());
+        processor.set_monitor_arbitrage(true);
+
+        lines
+            .iter()
+            .map(|line| processor.process_line(line).unwrap())
+            .filter(|output| output.contains("ARBITRAGE_APPEARED"))
+            .count()
+    }
+
+    #[test]
+    fn initial_quotes() {
+        let mut simulator = Simulator::new(2, 3, 1.0, 1);
+        assert_eq!(simulator.get_pairs().len(), 6);
+
+        // Test that all pairs are quoted at the start moment first.
+        let start = simulator.get_time();
+        let lines: Vec<String> = simulator.by_ref().take(6).collect();
+        assert!(lines
+            .iter()
+            .all(|line| line.starts_with("2019-01-20T00:00:00+00:00")));
+        assert_eq!(simulator.get_time(), start);
+
+        simulator.next();
+        assert_eq!(simulator.get_time(), start + chrono::Duration::seconds(1));
+    }
+
+    #[test]
+    fn stream_without_arbitrage() {
+        // Test that quotes at unchanging values never form an arbitrage.
+        let simulator = Simulator::new(3, 4, 0.8, 2).with_volatility(0.0);
+        assert_eq!(count_appeared(simulator.take(200).collect()), 0);
+    }
+
+    #[test]
+    fn stream_with_injected_arbitrage() {
+        let mut simulator = Simulator::new(3, 4, 0.8, 3)
+            .with_volatility(0.0)
+            .with_arbitrage_probability(0.05);
+        let lines: Vec<String> = simulator.by_ref().take(200).collect();
+        assert!(simulator.get_injected_arbitrages() > 0);
+
+        // Test that the injected arbitrages are alerted.
+        assert!(count_appeared(lines) > 0);
+    }
+}
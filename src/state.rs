@@ -0,0 +1,194 @@
+//! JSON export and import of the complete engine state.
+
+use crate::algorithm::settings::Settings;
+use crate::json::value;
+use crate::json::value::{items, number, Value};
+use crate::request::price_update::PriceUpdate;
+use crate::IndexMapTrait;
+use num_traits::Num;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+/// Version of the state format, increased whenever its fields change incompatibly.
+pub const VERSION: u32 = 1;
+
+/// The `Settings` and the `PriceUpdate`s the state is formed by.
+pub type State<N, E> = (Settings<N, E>, Vec<PriceUpdate<N, E>>);
+
+/// Get the human-readable JSON of the state formed by the `Settings` and the `PriceUpdate`s,
+/// in the format `parse_state` accepts.
+///
+/// # Format
+///
+/// {"version":1,"settings":<settings>,"price_updates":[<price update>, ...]}
+///
+/// Indented by two spaces, a member or an item per line.
+pub fn get_state_json<'a, N, E, I>(settings: &Settings<N, E>, price_updates: I) -> String
+where
+    N: IndexMapTrait + Clone + Display + FromStr + 'a,
+    <N as FromStr>::Err: Debug,
+    E: Num + Copy + Display + FromStr + 'a,
+    <E as FromStr>::Err: Debug,
+    I: IntoIterator<Item = &'a PriceUpdate<N, E>>,
+{
+    let price_updates: Vec<String> = price_updates
+        .into_iter()
+        .map(PriceUpdate::get_json)
+        .collect();
+    let state = format!(
+        "{{\"version\":{},\"settings\":{},\"price_updates\":[{}]}}",
+        VERSION,
+        settings.get_json(),
+        price_updates.join(",")
+    );
+
+    // The state is valid JSON by construction, the compact one is a fallback only.
+    value::parse(&state).map_or(state, |value| value.get_pretty())
+}
+
+/// Parse the state JSON into the `Settings` and the `PriceUpdate`s, collecting all errors.
+///
+/// Missing settings have their default values, the version and the price updates are required.
+pub fn parse_state<N, E>(text: &str) -> Result<State<N, E>, Vec<String>>
+where
+    N: IndexMapTrait + Clone + Display + FromStr,
+    <N as FromStr>::Err: Debug,
+    E: Num + Copy + Display + FromStr + PartialOrd,
+    <E as FromStr>::Err: Debug,
+{
+    let state = value::parse(text).map_err(|error| vec![error])?;
+    let mut errors = Vec::new();
+
+    match number::<u32>(&state, "version", &mut errors) {
+        Some(VERSION) | None => {}
+        Some(version) => {
+            return Err(vec![format!(
+                "The state version <{}> is not supported, only <{}> is!",
+                version, VERSION
+            )])
+        }
+    }
+
+    let settings = match state.get("settings") {
+        Some(item) => Settings::parse_json(item).unwrap_or_else(|settings_errors| {
+            errors.extend(settings_errors);
+            Settings::new()
+        }),
+        None => Settings::new(),
+    };
+
+    if !matches!(state.get("price_updates"), Some(Value::Array(_))) {
+        errors.push("The field <price_updates> is missing!".to_string());
+    }
+    let price_updates = items(&state, "price_updates", &mut errors, |item, errors| {
+        PriceUpdate::parse_json(item)
+            .map_err(|price_update_errors| errors.extend(price_update_errors))
+            .ok()
+    });
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok((settings, price_updates))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::request::price_update::PriceUpdate;
+    use crate::state::{get_state_json, parse_state};
+
+    #[test]
+    fn export_and_import() {
+        let mut settings = Settings::<String, f32>::new();
+        settings.set_min_profit_factor(1.002);
+        settings.set_funding(vec![(Some("KRAKEN".to_string()), None)]);
+        settings.get_fee_model_mut().set_default_fee(0.002);
+        settings
+            .get_fee_model_mut()
+            .set_exchange_maker_fee("KRAKEN".to_string(), 0.0016);
+        settings
+            .get_transfer_model_mut()
+            .set_transfer_enabled("USD".to_string(), false);
+        settings.get_transfer_model_mut().set_withdrawal_fee(
+            "KRAKEN".to_string(),
+            "BTC".to_string(),
+            0.0005,
+        );
+        settings
+            .get_slippage_model_mut()
+            .set_exchange_depth("GDAX".to_string(), 20.0);
+        settings.get_trading_rules_mut().set_pair_enabled(
+            "GDAX".to_string(),
+            "ETH".to_string(),
+            "EUR".to_string(),
+            false,
+        );
+
+        let mut price_updates: Vec<PriceUpdate<String, f32>> = vec![
+            "2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001",
+            "2019-01-20T09:42:24+00:00 GDAX ETH EUR BID_ASK 99.5 100.5",
+        ]
+        .into_iter()
+        .map(|line| PriceUpdate::parse_line(line).unwrap())
+        .collect();
+        price_updates[1].set_stale(true);
+
+        let state = get_state_json(&settings, &price_updates);
+        assert!(state.starts_with("{\n  \"version\": 1,\n  \"settings\": {\n"));
+        assert!(state.contains(
+            "\"timestamp\": \"2019-01-20T09:42:23+00:00\",\n      \"exchange\": \"KRAKEN\""
+        ));
+
+        // Test that the state survives the round trip.
+        let (imported_settings, imported_price_updates) =
+            parse_state::<String, f32>(&state).unwrap();
+        assert_eq!(imported_settings.get_json(), settings.get_json());
+        let get_lines = |price_updates: &[PriceUpdate<String, f32>]| {
+            price_updates
+                .iter()
+                .map(|price_update| (price_update.get_line(), price_update.is_stale()))
+                .collect::<Vec<(String, bool)>>()
+        };
+        assert_eq!(
+            get_lines(&imported_price_updates),
+            get_lines(&price_updates)
+        );
+        assert_eq!(
+            get_state_json(&imported_settings, &imported_price_updates),
+            state
+        );
+    }
+
+    #[test]
+    fn import_errors() {
+        // Test that missing settings have their default values.
+        let (settings, price_updates) =
+            parse_state::<String, f32>("{\"version\": 1, \"price_updates\": []}").unwrap();
+        assert_eq!(
+            settings.get_json(),
+            Settings::<String, f32>::new().get_json()
+        );
+        assert!(price_updates.is_empty());
+
+        assert!(parse_state::<String, f32>("{\"version\": 2, \"price_updates\": []}").is_err());
+        assert!(parse_state::<String, f32>("{\"version\": 1}").is_err());
+        assert_eq!(
+            parse_state::<String, f32>(
+                "{\"version\": 1, \"settings\": {\"fees\": {\"fee\": \"high\"}}, \
+                 \"price_updates\": [{\"exchange\": \"KRAKEN\"}]}"
+            )
+            .err()
+            .unwrap(),
+            vec![
+                "The field <fee> can not be parsed (wrong format)!",
+                "The item 1 of <price_updates>: The field <timestamp> is missing!",
+                "The item 1 of <price_updates>: The field <source_currency> is missing!",
+                "The item 1 of <price_updates>: The field <destination_currency> is missing!",
+                "The item 1 of <price_updates>: The field <forward_factor> is missing!",
+                "The item 1 of <price_updates>: The field <backward_factor> is missing!",
+            ]
+        );
+    }
+}
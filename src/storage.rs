@@ -0,0 +1,403 @@
+//! SQLite storage of price updates and best rate paths.
+
+use crate::request::price_update::{PriceUpdate, Quote};
+use crate::response::best_rate_path::BestRatePath;
+use chrono::{DateTime, FixedOffset};
+use num_traits::Num;
+use std::ffi::{CStr, CString};
+use std::fmt::{Debug, Display};
+use std::io;
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::Path;
+use std::ptr;
+use std::str::FromStr;
+
+/// Schema of the storage, the tables are created unless they exist already.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS price_updates (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    exchange TEXT NOT NULL,
+    source_currency TEXT NOT NULL,
+    destination_currency TEXT NOT NULL,
+    forward_factor REAL,
+    backward_factor REAL,
+    bid REAL,
+    ask REAL
+);
+CREATE TABLE IF NOT EXISTS best_rate_paths (
+    id INTEGER PRIMARY KEY,
+    answered_at TEXT NOT NULL,
+    source_exchange TEXT NOT NULL,
+    source_currency TEXT NOT NULL,
+    destination_exchange TEXT NOT NULL,
+    destination_currency TEXT NOT NULL,
+    rate REAL,
+    path TEXT NOT NULL
+);
+PRAGMA journal_mode = WAL;
+PRAGMA synchronous = NORMAL;
+";
+
+const INSERT_PRICE_UPDATE: &str = "INSERT INTO price_updates (timestamp, exchange, \
+    source_currency, destination_currency, forward_factor, backward_factor, bid, ask) \
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?)";
+
+const INSERT_BEST_RATE_PATH: &str = "INSERT INTO best_rate_paths (answered_at, \
+    source_exchange, source_currency, destination_exchange, destination_currency, rate, path) \
+    VALUES (?, ?, ?, ?, ?, ?, ?)";
+
+const SQLITE_OK: c_int = 0;
+#[cfg(test)]
+const SQLITE_ROW: c_int = 100;
+const SQLITE_DONE: c_int = 101;
+const SQLITE_OPEN_READWRITE: c_int = 0x2;
+const SQLITE_OPEN_CREATE: c_int = 0x4;
+const SQLITE_OPEN_FULLMUTEX: c_int = 0x10000;
+/// Destructor making SQLite copy a bound text right away.
+const SQLITE_TRANSIENT: isize = -1;
+
+/// Opaque SQLite connection.
+#[repr(C)]
+struct Sqlite3 {
+    _private: [u8; 0],
+}
+
+/// Opaque SQLite prepared statement.
+#[repr(C)]
+struct Sqlite3Stmt {
+    _private: [u8; 0],
+}
+
+#[link(name = "sqlite3")]
+extern "C" {
+    fn sqlite3_open_v2(
+        filename: *const c_char,
+        db: *mut *mut Sqlite3,
+        flags: c_int,
+        vfs: *const c_char,
+    ) -> c_int;
+    fn sqlite3_close(db: *mut Sqlite3) -> c_int;
+    fn sqlite3_errmsg(db: *mut Sqlite3) -> *const c_char;
+    fn sqlite3_exec(
+        db: *mut Sqlite3,
+        sql: *const c_char,
+        callback: *const c_void,
+        argument: *mut c_void,
+        errmsg: *mut *mut c_char,
+    ) -> c_int;
+    fn sqlite3_prepare_v2(
+        db: *mut Sqlite3,
+        sql: *const c_char,
+        length: c_int,
+        stmt: *mut *mut Sqlite3Stmt,
+        tail: *mut *const c_char,
+    ) -> c_int;
+    fn sqlite3_bind_text(
+        stmt: *mut Sqlite3Stmt,
+        index: c_int,
+        text: *const c_char,
+        length: c_int,
+        destructor: isize,
+    ) -> c_int;
+    fn sqlite3_bind_double(stmt: *mut Sqlite3Stmt, index: c_int, value: f64) -> c_int;
+    fn sqlite3_bind_null(stmt: *mut Sqlite3Stmt, index: c_int) -> c_int;
+    fn sqlite3_step(stmt: *mut Sqlite3Stmt) -> c_int;
+    fn sqlite3_finalize(stmt: *mut Sqlite3Stmt) -> c_int;
+    #[cfg(test)]
+    fn sqlite3_column_text(stmt: *mut Sqlite3Stmt, index: c_int) -> *const c_char;
+}
+
+/// Value bound to a parameter of a statement.
+enum Value {
+    Text(String),
+    Real(Option<f64>),
+}
+
+/// `Storage` structure.
+///
+/// Writes price updates and computed best rate paths to tables of an SQLite database, so the
+/// history can be analyzed by SQL ad hoc. Rates are stored as `REAL`, `NULL` if they can't be
+/// represented so, and a path as its comma separated `<exchange>:<currency>` nodes.
+pub struct Storage {
+    db: *mut Sqlite3,
+}
+
+// The connection is opened in the serialized threading mode, it can be used by any thread.
+unsafe impl Send for Storage {}
+
+impl Storage {
+    /// Open the database file of the path, creating it and its tables if they don't exist.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let filename = to_c_string(&path.to_string_lossy())?;
+        let mut db = ptr::null_mut();
+        let flags = SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE | SQLITE_OPEN_FULLMUTEX;
+
+        // The connection is closed by `drop` even if opening it fails.
+        let code = unsafe { sqlite3_open_v2(filename.as_ptr(), &mut db, flags, ptr::null()) };
+        let storage = Self { db };
+        storage.check(code)?;
+
+        let schema = to_c_string(SCHEMA)?;
+        let code = unsafe {
+            sqlite3_exec(
+                storage.db,
+                schema.as_ptr(),
+                ptr::null(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        storage.check(code)?;
+
+        Ok(storage)
+    }
+
+    /// Store the price update.
+    pub fn store_price_update<N, E>(&self, price_update: &PriceUpdate<N, E>) -> io::Result<()>
+    where
+        N: Clone + FromStr + Display,
+        <N as FromStr>::Err: Debug,
+        E: FromStr + Display + Num + Copy,
+        <E as FromStr>::Err: Debug,
+    {
+        let (bid, ask) = match price_update.get_quote() {
+            Quote::BidAsk { bid, ask } => (to_real(bid), to_real(ask)),
+            Quote::Factors { .. } => (None, None),
+        };
+
+        self.execute(
+            INSERT_PRICE_UPDATE,
+            &[
+                Value::Text(price_update.get_timestamp().to_rfc3339()),
+                Value::Text(price_update.get_exchange().to_string()),
+                Value::Text(price_update.get_source_currency().to_string()),
+                Value::Text(price_update.get_destination_currency().to_string()),
+                Value::Real(to_real(&price_update.get_forward_factor())),
+                Value::Real(to_real(&price_update.get_backward_factor())),
+                Value::Real(bid),
+                Value::Real(ask),
+            ],
+        )
+    }
+
+    /// Store the best rate path answered at the time.
+    pub fn store_best_rate_path<N, E>(
+        &self,
+        answered_at: DateTime<FixedOffset>,
+        best_rate_path: &BestRatePath<N, E>,
+    ) -> io::Result<()>
+    where
+        N: Display + Debug,
+        E: Display,
+    {
+        let (source, destination) = match (
+            best_rate_path.get_start_node(),
+            best_rate_path.get_end_node(),
+        ) {
+            (Some(source), Some(destination)) => (source, destination),
+            _ => return Ok(()),
+        };
+        let path: Vec<String> = best_rate_path
+            .get_path()
+            .iter()
+            .map(|(exchange, currency)| format!("{}:{}", exchange, currency))
+            .collect();
+
+        self.execute(
+            INSERT_BEST_RATE_PATH,
+            &[
+                Value::Text(answered_at.to_rfc3339()),
+                Value::Text(source.0.to_string()),
+                Value::Text(source.1.to_string()),
+                Value::Text(destination.0.to_string()),
+                Value::Text(destination.1.to_string()),
+                Value::Real(to_real(best_rate_path.get_rate())),
+                Value::Text(path.join(",")),
+            ],
+        )
+    }
+
+    /// Execute the statement with the values bound to its parameters.
+    fn execute(&self, sql: &str, values: &[Value]) -> io::Result<()> {
+        let stmt = self.prepare(sql)?;
+
+        let result = self
+            .bind(stmt, values)
+            .and_then(|()| match unsafe { sqlite3_step(stmt) } {
+                SQLITE_DONE => Ok(()),
+                code => self.check(code),
+            });
+        unsafe { sqlite3_finalize(stmt) };
+
+        result
+    }
+
+    fn prepare(&self, sql: &str) -> io::Result<*mut Sqlite3Stmt> {
+        let sql = to_c_string(sql)?;
+        let mut stmt = ptr::null_mut();
+
+        let code =
+            unsafe { sqlite3_prepare_v2(self.db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut()) };
+        self.check(code)?;
+
+        Ok(stmt)
+    }
+
+    fn bind(&self, stmt: *mut Sqlite3Stmt, values: &[Value]) -> io::Result<()> {
+        for (index, value) in values.iter().enumerate() {
+            // Parameters are indexed from 1.
+            let index = index as c_int + 1;
+
+            let code = match value {
+                Value::Text(text) => {
+                    let text = to_c_string(text)?;
+                    unsafe { sqlite3_bind_text(stmt, index, text.as_ptr(), -1, SQLITE_TRANSIENT) }
+                }
+                Value::Real(Some(real)) => unsafe { sqlite3_bind_double(stmt, index, *real) },
+                Value::Real(None) => unsafe { sqlite3_bind_null(stmt, index) },
+            };
+            self.check(code)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get text values of the first column of the rows the query selects.
+    #[cfg(test)]
+    pub fn query(&self, sql: &str) -> io::Result<Vec<String>> {
+        let stmt = self.prepare(sql)?;
+        let mut rows = Vec::new();
+
+        let result = loop {
+            match unsafe { sqlite3_step(stmt) } {
+                SQLITE_ROW => {
+                    let text = unsafe { sqlite3_column_text(stmt, 0) };
+                    rows.push(if text.is_null() {
+                        String::new()
+                    } else {
+                        unsafe { CStr::from_ptr(text) }
+                            .to_string_lossy()
+                            .into_owned()
+                    });
+                }
+                SQLITE_DONE => break Ok(rows),
+                code => break self.check(code).map(|()| rows),
+            }
+        };
+        unsafe { sqlite3_finalize(stmt) };
+
+        result
+    }
+
+    /// Turn the result code into an error with the message of the connection.
+    fn check(&self, code: c_int) -> io::Result<()> {
+        if code == SQLITE_OK {
+            return Ok(());
+        }
+
+        let message = if self.db.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(sqlite3_errmsg(self.db)) }
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        Err(io::Error::other(format!(
+            "The SQLite call failed <{}>, error: {}!",
+            code, message
+        )))
+    }
+}
+
+impl Drop for Storage {
+    fn drop(&mut self) {
+        unsafe { sqlite3_close(self.db) };
+    }
+}
+
+fn to_c_string(text: &str) -> io::Result<CString> {
+    CString::new(text).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("The text <{}> contains a NUL character!", text),
+        )
+    })
+}
+
+/// Convert the value to `REAL`, `None` if it can't be represented so.
+fn to_real<E: Display>(value: &E) -> Option<f64> {
+    value.to_string().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request::price_update::PriceUpdate;
+    use crate::response::best_rate_path::BestRatePath;
+    use crate::storage::Storage;
+    use chrono::DateTime;
+    use std::fs;
+
+    #[test]
+    fn store() {
+        let path =
+            std::env::temp_dir().join(format!("exchange-rate-{}.sqlite", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let storage = Storage::open(&path).unwrap();
+
+        for line in [
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001",
+            "2019-01-20T09:42:23+00:00 E1 ETH USD BID_ASK 99 100",
+        ] {
+            let price_update = PriceUpdate::<String, f32>::parse_line(line).unwrap();
+            storage.store_price_update(&price_update).unwrap();
+        }
+
+        let answered_at = DateTime::parse_from_rfc3339("2019-01-20T09:42:24+00:00").unwrap();
+        let best_rate_path = BestRatePath::new(
+            1000.0_f32,
+            vec![
+                ("E1".to_string(), "BTC".to_string()),
+                ("E1".to_string(), "USD".to_string()),
+            ],
+        );
+        storage
+            .store_best_rate_path(answered_at, &best_rate_path)
+            .unwrap();
+
+        // Test that the tables survive opening the database again.
+        drop(storage);
+        let storage = Storage::open(&path).unwrap();
+        assert_eq!(
+            storage
+                .query(
+                    "SELECT exchange || ' ' || source_currency || ' ' || destination_currency \
+                     || ' ' || forward_factor || ' ' || backward_factor || ' ' \
+                     || ifnull(bid, 'NULL') || ' ' || ifnull(ask, 'NULL') \
+                     FROM price_updates ORDER BY id"
+                )
+                .unwrap(),
+            vec![
+                "E1 BTC USD 1000.0 0.001 NULL NULL",
+                "E1 ETH USD 99.0 0.01 99.0 100.0",
+            ]
+        );
+        assert_eq!(
+            storage
+                .query(
+                    "SELECT answered_at || ' ' || source_exchange || ' ' || source_currency \
+                     || ' ' || destination_exchange || ' ' || destination_currency || ' ' \
+                     || rate || ' ' || path FROM best_rate_paths"
+                )
+                .unwrap(),
+            vec!["2019-01-20T09:42:24+00:00 E1 BTC E1 USD 1000.0 E1:BTC,E1:USD"]
+        );
+        assert!(storage.query("SELECT * FROM unknown").is_err());
+
+        drop(storage);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
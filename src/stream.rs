@@ -0,0 +1,1118 @@
+//! Streaming processing of input lines.
+
+use self::aggregation::Aggregation;
+use self::arbitrage_monitor::ArbitrageMonitor;
+use self::history::History;
+use self::journal::Journal;
+use self::metrics::Metrics;
+use self::rate_monitor::RateMonitor;
+use crate::algorithm::cancellation::{Cancellation, Cancelled};
+use crate::algorithm::settings::Settings;
+use crate::algorithm::{Algorithm, Solution};
+use crate::error::Error;
+use crate::request::exchange_rate_request::ExchangeRateRequest;
+use crate::request::price_update::PriceUpdate;
+use crate::request::{Line, Request};
+use crate::response::rejection::Rejection;
+use crate::response::Response;
+use crate::state;
+#[cfg(feature = "sqlite")]
+use crate::storage::Storage;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use indexmap::IndexSet;
+use std::fmt::{Debug, Display};
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+mod aggregation;
+mod arbitrage_monitor;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+mod history;
+mod journal;
+pub mod metrics;
+mod rate_monitor;
+
+/// `StreamProcessor` structure.
+///
+/// Processes input lines one by one as they come, keeping the most recent `PriceUpdate`s.
+/// Requests are answered immediately using the `PriceUpdate`s known so far.
+///
+/// It does no I/O of its own, so it can be driven by any reader and writer, e.g. asynchronous
+/// ones of a service embedding the engine.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::{Settings, StreamProcessor};
+///
+/// let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+/// processor
+///     .process_line("2019-01-20T09:42:23+00:00 KRAKEN BTC USD 1000 0.001")
+///     .unwrap();
+/// let output = processor
+///     .process_line("EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD")
+///     .unwrap();
+/// assert!(output.starts_with("BEST_RATES_BEGIN <KRAKEN> <BTC> <KRAKEN> <USD> <1000>"));
+/// ```
+///
+/// # `StreamProcessor<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct StreamProcessor<N, E> {
+    request: Request<N, E>,
+    settings: Settings<N, E>,
+    solution: Option<Solution<N, E, u32>>,
+    updated: IndexSet<(N, N, N)>,
+    arbitrage_monitor: Option<ArbitrageMonitor<N, E>>,
+    arbitrage_json: bool,
+    include_stamp: bool,
+    rate_monitor: RateMonitor<N, E>,
+    max_graph_size: Option<usize>,
+    compute_deadline: Option<Duration>,
+    aggregation: Option<Aggregation<N, E>>,
+    metrics: Metrics,
+    journal: Option<Journal>,
+    #[cfg(feature = "sqlite")]
+    storage: Option<Storage>,
+    history: Option<History<N, E>>,
+}
+
+impl<N, E> StreamProcessor<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of `StreamProcessor` structure.
+    pub fn new(settings: Settings<N, E>) -> Self {
+        Self {
+            request: Request::new(),
+            settings,
+            solution: None,
+            updated: IndexSet::new(),
+            arbitrage_monitor: None,
+            arbitrage_json: false,
+            include_stamp: false,
+            rate_monitor: RateMonitor::new(),
+            max_graph_size: None,
+            compute_deadline: None,
+            aggregation: None,
+            metrics: Metrics::new(),
+            journal: None,
+            #[cfg(feature = "sqlite")]
+            storage: None,
+            history: None,
+        }
+    }
+
+    /// Create an empty `StreamProcessor` with the same settings and options, e.g. for a session
+    /// isolated from the `PriceUpdate`s known so far.
+    pub fn new_session(&self) -> Self {
+        let mut processor = Self::new(self.settings.clone());
+        processor.set_monitor_arbitrage(self.arbitrage_monitor.is_some());
+        processor.set_arbitrage_json(self.arbitrage_json);
+        processor.set_include_stamp(self.include_stamp);
+        for (pair, threshold) in self.rate_monitor.get_thresholds() {
+            processor.add_rate_alert(pair, threshold);
+        }
+        processor.set_max_graph_size(self.max_graph_size);
+        processor.set_compute_deadline(self.compute_deadline);
+        processor
+            .set_aggregation_interval(self.aggregation.as_ref().map(Aggregation::get_interval));
+        processor.set_history(self.history.is_some());
+
+        processor
+    }
+
+    pub fn get_settings(&self) -> &Settings<N, E> {
+        &self.settings
+    }
+
+    /// Replace the `Settings`, e.g. by the reloaded config, keeping the known `PriceUpdate`s.
+    /// The kept solution is discarded, the next request is answered by the new `Settings`.
+    pub fn set_settings(&mut self, settings: Settings<N, E>) {
+        self.settings = settings;
+        self.discard_solution();
+    }
+
+    /// Set whether arbitrage opportunities should be re-evaluated after each `PriceUpdate` and
+    /// their changes alerted.
+    pub fn set_monitor_arbitrage(&mut self, monitor_arbitrage: bool) {
+        self.arbitrage_monitor = if monitor_arbitrage {
+            Some(ArbitrageMonitor::new())
+        } else {
+            None
+        };
+    }
+
+    /// Set whether arbitrages should be written out as JSON lines instead of text blocks.
+    pub fn set_arbitrage_json(&mut self, arbitrage_json: bool) {
+        self.arbitrage_json = arbitrage_json;
+    }
+
+    /// Set whether each block of the output should be stamped by an `AS_OF` line of the
+    /// timestamp of the most recent quote it was based on and the moment of its computation.
+    pub fn set_include_stamp(&mut self, include_stamp: bool) {
+        self.include_stamp = include_stamp;
+    }
+
+    /// Alert moves of the best rate of the
+    /// `(source_exchange, source_currency, destination_exchange, destination_currency)` pair by
+    /// more than the relative threshold, e.g. `0.005` for `0.5 %`, since the last alert.
+    ///
+    /// Rates are re-evaluated after each `PriceUpdate`, the first one known is not alerted.
+    pub fn add_rate_alert(&mut self, pair: (N, N, N, N), threshold: E) {
+        self.rate_monitor.add(pair, threshold);
+    }
+
+    /// Stop alerting moves of the best rate of the pair, return whether they were alerted.
+    pub fn remove_rate_alert(&mut self, pair: &(N, N, N, N)) -> bool {
+        self.rate_monitor.remove(pair)
+    }
+
+    /// Set maximal count of currency pairs with a known `PriceUpdate`, `None` for no limit.
+    ///
+    /// Price updates of known pairs are accepted even if the limit is reached.
+    pub fn set_max_graph_size(&mut self, max_graph_size: Option<usize>) {
+        self.max_graph_size = max_graph_size;
+    }
+
+    /// Set maximal time of answering the requests of a line, `None` for no limit. Rate requests
+    /// not answered in time are unanswered for the `cancelled` reason, arbitrage requests are
+    /// left out.
+    pub fn set_compute_deadline(&mut self, compute_deadline: Option<Duration>) {
+        self.compute_deadline = compute_deadline;
+    }
+
+    /// Set the interval `PriceUpdate`s of the same pair are combined within, `None` to take each
+    /// as it comes. A `PriceUpdate` enters the graph quoting the time-weighted average of the
+    /// quotes of its pair within the interval before it, smoothing out flickering quotes.
+    pub fn set_aggregation_interval(&mut self, interval: Option<chrono::Duration>) {
+        self.aggregation = interval.map(Aggregation::new);
+    }
+
+    /// Set whether all accepted `PriceUpdate`s should be kept, answering rate requests as of a
+    /// past moment. It should be set before the journal is opened, so the replayed ones are kept
+    /// as well, and the journal isn't compacted then.
+    pub fn set_history(&mut self, history: bool) {
+        self.history = if history { Some(History::new()) } else { None };
+    }
+
+    /// Replay the journal file of the path if it exists, recovering the state before a crash,
+    /// and append the accepted `PriceUpdate`s to it from now on. Return count of the
+    /// `PriceUpdate`s known after the replay.
+    ///
+    /// The journal is compacted whenever most of its lines are superseded, at startup as well.
+    /// A session created by `new_session` has no journal.
+    pub fn open_journal(&mut self, path: &Path) -> Result<usize, Error> {
+        let (length, lines) = match File::open(path) {
+            Ok(file) => self.replay_journal(&mut BufReader::new(file))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => (0, 0),
+            Err(error) => return Err(Error::Io(error)),
+        };
+        // A torn last line is dropped, so the next one doesn't continue it.
+        self.journal = Some(Journal::open(path, length, lines)?);
+        self.compact_journal()?;
+
+        Ok(self.request.get_price_updates().len())
+    }
+
+    /// Open the SQLite database of the path, creating it if it doesn't exist, and store the
+    /// accepted `PriceUpdate`s and answered `BestRatePath`s to it from now on.
+    ///
+    /// A session created by `new_session` has no storage.
+    #[cfg(feature = "sqlite")]
+    pub fn open_storage(&mut self, path: &Path) -> io::Result<()> {
+        self.storage = Some(Storage::open(path)?);
+
+        Ok(())
+    }
+
+    /// Import the state JSON exported by `export-state`, replacing the `Settings` and adding its
+    /// `PriceUpdate`s. Return count of the `PriceUpdate`s known after the import.
+    ///
+    /// Like the replayed ones, they aren't appended to the journal and cause no arbitrage alerts.
+    pub fn import_state(&mut self, text: &str) -> Result<usize, Error> {
+        let (settings, price_updates) = state::parse_state(text).map_err(Error::Parse)?;
+        self.settings = settings;
+        self.discard_solution();
+        for price_update in price_updates {
+            self.accept(price_update);
+        }
+
+        Ok(self.request.get_price_updates().len())
+    }
+
+    /// Replay `PriceUpdate`s of the journal and return length of its complete lines in bytes
+    /// and their count, a torn last line without a line break is skipped. They aren't appended
+    /// to the journal again and cause no arbitrage alerts.
+    fn replay_journal<R: BufRead>(&mut self, input: &mut R) -> Result<(u64, usize), Error> {
+        let mut errors: Vec<String> = Vec::new();
+        let mut length = 0;
+        let mut lines = 0;
+
+        for number in 1.. {
+            let mut line = Vec::new();
+            if input.read_until(b'\n', &mut line)? == 0 || line.last() != Some(&b'\n') {
+                break;
+            }
+            length += line.len() as u64;
+            lines += 1;
+
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match PriceUpdate::parse_line(&line) {
+                Ok(price_update) => {
+                    self.accept(price_update);
+                }
+                Err(error) => errors.push(format!(
+                    "The journal line {} can not be parsed, errors: {:?}!",
+                    number,
+                    error.get_messages()
+                )),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(Error::Parse(errors));
+        }
+
+        Ok((length, lines))
+    }
+
+    /// Compact the journal to the input lines of the known `PriceUpdate`s, if it's due. The
+    /// journal kept with the history isn't compacted, as it's the history at the next startup.
+    fn compact_journal(&mut self) -> io::Result<()> {
+        let price_updates = self.request.get_price_updates();
+        match self.journal.as_mut() {
+            Some(journal)
+                if self.history.is_none() && journal.is_compaction_due(price_updates.len()) =>
+            {
+                journal.compact(price_updates.values().map(PriceUpdate::get_line))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Get metrics of the processing so far.
+    pub fn get_metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Record a price update rejected before it reached the processor, e.g. over a rate limit.
+    pub fn record_rejected_price_update(&mut self) {
+        self.metrics.record_rejected_price_update();
+    }
+
+    /// Record an input that could not be parsed, e.g. by a transport of its own format.
+    #[cfg(feature = "http")]
+    pub fn record_parse_error(&mut self) {
+        self.metrics.record_parse_error();
+    }
+
+    /// Process a single input line and return the printable output it caused.
+    pub fn process_line(&mut self, line: &str) -> Result<String, Error> {
+        let parsed = Request::<N, E>::parse_line(line);
+        if parsed.is_err() {
+            self.metrics.record_parse_error();
+        }
+
+        let line = match parsed? {
+            Some(line) => line,
+            None => return Ok(String::new()),
+        };
+
+        match line {
+            // An outdated `PriceUpdate` changes nothing.
+            Line::PriceUpdate(price_update) => match self.add_price_update(price_update) {
+                Ok(output) => Ok(output.unwrap_or_default()),
+                Err(rejection) => Ok(rejection.get_output()),
+            },
+            line => {
+                let mut response = self.answer(vec![line]);
+                response.set_arbitrage_json(self.arbitrage_json);
+                response.set_include_stamp(self.include_stamp);
+
+                Ok(response.get_output())
+            }
+        }
+    }
+
+    /// Add the `PriceUpdate` and return the printable output of arbitrage and rate alerts it
+    /// caused.
+    ///
+    /// Return `None` if a more recent `PriceUpdate` of the same pair is already known, or the
+    /// `Rejection` if the pair is new and the graph has its maximal size already.
+    pub fn add_price_update(
+        &mut self,
+        price_update: PriceUpdate<N, E>,
+    ) -> Result<Option<String>, Rejection> {
+        let index = price_update.get_index();
+        let price_updates = self.request.get_price_updates();
+        if self.max_graph_size.is_some_and(|max_graph_size| {
+            price_updates.len() >= max_graph_size && !price_updates.contains_key(&index)
+        }) {
+            self.metrics.record_rejected_price_update();
+            return Err(Rejection::GraphSizeExceeded);
+        }
+
+        let price_update = match self.aggregation.as_mut() {
+            Some(aggregation) => aggregation.aggregate(price_update),
+            None => price_update,
+        };
+        let (exchange, source, destination) = index;
+
+        let line = self.journal.as_ref().map(|_| price_update.get_line());
+        let added = self.accept(price_update);
+        self.metrics.record_price_update(!added);
+        if !added {
+            return Ok(None);
+        }
+
+        if let (Some(journal), Some(line)) = (self.journal.as_mut(), line) {
+            if let Err(error) = journal.append(&line) {
+                eprintln!(
+                    "The journal can not be appended to and stops, error: {}!",
+                    error
+                );
+                self.journal = None;
+            }
+        }
+        if let Err(error) = self.compact_journal() {
+            eprintln!(
+                "The journal can not be compacted and stops, error: {}!",
+                error
+            );
+            self.journal = None;
+        }
+
+        #[cfg(feature = "sqlite")]
+        self.store(|storage, request| {
+            storage.store_price_update(
+                &request.get_price_updates()
+                    [&(exchange.clone(), source.clone(), destination.clone())],
+            )
+        });
+
+        let mut output =
+            self.monitor_arbitrage(vec![(exchange.clone(), source), (exchange, destination)]);
+        output.push_str(&self.monitor_rates());
+
+        Ok(Some(output))
+    }
+
+    /// Mark the known `PriceUpdate`s of the `(exchange, source, destination)` indexes stale and
+    /// return the printable output of arbitrage and rate alerts it caused.
+    ///
+    /// Stale pairs give no rate until a more recent `PriceUpdate` arrives.
+    #[cfg(feature = "feed")]
+    pub fn mark_stale(&mut self, indexes: &[(N, N, N)]) -> String {
+        if self.request.mark_stale(indexes) == 0 {
+            return String::new();
+        }
+        // Stale price updates lose their edges, the solution can't be updated by them.
+        self.discard_solution();
+        if let Some(aggregation) = self.aggregation.as_mut() {
+            for index in indexes {
+                aggregation.forget(index);
+            }
+        }
+
+        let updated_nodes = indexes
+            .iter()
+            .flat_map(|(exchange, source, destination)| {
+                vec![
+                    (exchange.clone(), source.clone()),
+                    (exchange.clone(), destination.clone()),
+                ]
+            })
+            .collect();
+
+        let mut output = self.monitor_arbitrage(updated_nodes);
+        output.push_str(&self.monitor_rates());
+
+        output
+    }
+
+    /// Write the known `PriceUpdate`s as input lines, so the state can be restored by reading
+    /// them again.
+    ///
+    /// Stale ones are skipped, as they would be fresh once read again.
+    pub fn write_snapshot<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        for price_update in self.request.get_price_updates().values() {
+            if !price_update.is_stale() {
+                writeln!(output, "{}", price_update.get_line())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the `PriceUpdate` to the known ones and the history, return whether it was accepted.
+    fn accept(&mut self, price_update: PriceUpdate<N, E>) -> bool {
+        let index = price_update.get_index();
+        let added = self.request.add_price_update(price_update);
+        if let (true, Some(history)) = (added, self.history.as_mut()) {
+            history.record(&self.request.get_price_updates()[&index]);
+        }
+        if added {
+            self.record_updated(index);
+        }
+
+        added
+    }
+
+    /// Record the index of a `PriceUpdate` changed since the solution was kept, to update it by.
+    fn record_updated(&mut self, index: (N, N, N)) {
+        if self.solution.is_some() {
+            self.updated.insert(index);
+        }
+    }
+
+    /// Discard the kept solution, the next requests solve the graph again.
+    fn discard_solution(&mut self) {
+        self.solution = None;
+        self.updated.clear();
+    }
+
+    /// Answer requests of the lines using the `PriceUpdate`s known so far.
+    ///
+    /// The solution of the known `PriceUpdate`s is kept, so requests that follow are answered
+    /// without solving the graph again, until a `PriceUpdate` changes them. Rate requests as of a
+    /// past moment are answered using the `PriceUpdate`s known then, if the history is kept.
+    pub fn answer(&mut self, lines: Vec<Line<N, E>>) -> Response<N, E> {
+        let mut past_requests = Vec::new();
+        for line in lines {
+            match line {
+                Line::RateRequest(rate_request)
+                    if self.history.is_some() && rate_request.get_as_of().is_some() =>
+                {
+                    past_requests.push(rate_request)
+                }
+                Line::PriceUpdate(price_update) => {
+                    let index = price_update.get_index();
+                    if self.request.add_price_update(price_update) {
+                        self.record_updated(index);
+                    }
+                }
+                line => self.request.add_line(line),
+            }
+        }
+
+        let cancellation = self.create_cancellation();
+        let (mut response, recomputed) = self.answer_kept(&cancellation);
+
+        if let Some(history) = self.history.as_ref() {
+            for mut rate_request in past_requests {
+                let mut request = match rate_request.get_as_of() {
+                    Some(as_of) => history.get_request_as_of(as_of),
+                    None => continue,
+                };
+                rate_request.set_as_of(None);
+                request.add_rate_request(rate_request);
+
+                response.extend(
+                    Algorithm::<N, E, u32>::process_cancellable(
+                        &request,
+                        &self.settings,
+                        &cancellation,
+                    )
+                    .unwrap_or_else(Cancelled::into_response),
+                );
+            }
+        }
+        self.record_response(&response, recomputed);
+
+        response
+    }
+
+    fn create_cancellation(&self) -> Cancellation {
+        let mut cancellation = Cancellation::new();
+        cancellation.set_deadline(
+            self.compute_deadline
+                .map(|compute_deadline| Instant::now() + compute_deadline),
+        );
+
+        cancellation
+    }
+
+    /// Answer the requests added to the `Request` by the kept solution and clear them, return
+    /// the `Response` and whether the solution was updated or solved again for it.
+    fn answer_kept(&mut self, cancellation: &Cancellation) -> (Response<N, E>, bool) {
+        let solved = self.solution.as_ref().map(Solution::is_solved_with_maker);
+        let is_outdated = !self.updated.is_empty();
+        let response = Algorithm::<N, E, u32>::process_kept(
+            &self.request,
+            &self.settings,
+            &mut self.solution,
+            &mut self.updated,
+            cancellation,
+        )
+        .unwrap_or_else(Cancelled::into_response);
+        self.request.clear_requests();
+        // A kept solution answered the requests unless it's been updated or solved again.
+        let recomputed = (is_outdated && self.updated.is_empty())
+            || self.solution.as_ref().map(Solution::is_solved_with_maker) != solved;
+
+        (response, recomputed)
+    }
+
+    fn record_response(&mut self, response: &Response<N, E>, recomputed: bool) {
+        for best_rate_path in response.get_best_rate_paths() {
+            self.metrics
+                .record_answered_request(best_rate_path.get_path().len());
+        }
+
+        for _ in response.get_unanswered_requests() {
+            self.metrics.record_unanswered_request();
+        }
+
+        // A response of the kept solution carries the times of the recompute recorded already.
+        if !recomputed {
+            return;
+        }
+        self.record_recompute(response);
+
+        #[cfg(feature = "sqlite")]
+        {
+            let answered_at = chrono::Utc::now().fixed_offset();
+            self.store(|storage, _| {
+                response
+                    .get_best_rate_paths()
+                    .iter()
+                    .try_for_each(|path| storage.store_best_rate_path(answered_at, path))
+            });
+        }
+    }
+
+    /// Record times and the graph size of a recompute the `Response` carries.
+    fn record_recompute(&mut self, response: &Response<N, E>) {
+        if let Some(graph_build_time) = response.get_graph_build_time() {
+            let solve_time = response.get_compute_time().saturating_sub(graph_build_time);
+            self.metrics.record_times(graph_build_time, solve_time);
+        }
+        if let (Some((node_count, edge_count)), Some(solve_time)) =
+            (response.get_graph_size(), response.get_solve_time())
+        {
+            self.metrics
+                .record_recompute(node_count, edge_count, solve_time);
+        }
+    }
+
+    /// Store to the storage if there is one, it stops on the first error.
+    #[cfg(feature = "sqlite")]
+    fn store<F>(&mut self, store: F)
+    where
+        F: FnOnce(&Storage, &Request<N, E>) -> io::Result<()>,
+    {
+        if let Some(storage) = self.storage.as_ref() {
+            if let Err(error) = store(storage, &self.request) {
+                eprintln!(
+                    "The storage can not be written to and stops, error: {}!",
+                    error
+                );
+                self.storage = None;
+            }
+        }
+    }
+
+    /// Re-evaluate arbitrage opportunities affected by change of the `updated_nodes`.
+    fn monitor_arbitrage(&mut self, updated_nodes: Vec<(N, N)>) -> String {
+        let monitor = match self.arbitrage_monitor.as_mut() {
+            Some(monitor) => monitor,
+            None => return String::new(),
+        };
+
+        let alg = Algorithm::<N, E, u32>::from_request(&self.request, &self.settings);
+
+        monitor
+            .evaluate(&alg, &updated_nodes)
+            .iter()
+            .map(|alert| alert.get_output())
+            .collect()
+    }
+
+    /// Re-evaluate best rates of the pairs alerted on, by the kept solution updated by the
+    /// `PriceUpdate`s changed meanwhile.
+    fn monitor_rates(&mut self) -> String {
+        if self.rate_monitor.is_empty() {
+            return String::new();
+        }
+
+        for ((a, b, c, d), _) in self.rate_monitor.get_thresholds() {
+            self.request
+                .add_rate_request(ExchangeRateRequest::new(a, b, c, d));
+        }
+        let cancellation = self.create_cancellation();
+        let (response, recomputed) = self.answer_kept(&cancellation);
+        if recomputed {
+            self.record_recompute(&response);
+        }
+
+        self.rate_monitor
+            .evaluate(response.get_best_rate_paths())
+            .iter()
+            .map(|alert| alert.get_output())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    #[cfg(feature = "sqlite")]
+    use crate::storage::Storage;
+    use crate::stream::StreamProcessor;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn process_line() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+
+        // Test that price updates and empty lines produce no output.
+        assert_eq!(
+            processor.process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001"),
+            Ok(String::new())
+        );
+        assert_eq!(processor.process_line("  "), Ok(String::new()));
+
+        // Test that a request is answered immediately and only once.
+        let output = processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .unwrap();
+        assert!(output.starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+        assert_eq!(
+            processor.process_line("2019-01-20T09:42:24+00:00 E1 BTC USD 900.0 0.001"),
+            Ok(String::new())
+        );
+
+        // Test that an invalid line is reported as an error.
+        assert!(processor.process_line("EXCHANGE_RATE_REQUEST E1").is_err());
+
+        // Test that the processing is recorded in metrics.
+        let output = processor.get_metrics().get_output();
+        assert!(output.contains("\nexchange_rate_price_updates_total 2\n"));
+        assert!(output.contains("\nexchange_rate_answered_requests_total 1\n"));
+        assert!(output.contains("\nexchange_rate_parse_errors_total 1\n"));
+        assert!(output.contains("\nexchange_rate_solve_seconds_count 1\n"));
+    }
+
+    #[test]
+    fn process_line_with_kept_solution() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        processor
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001")
+            .unwrap();
+
+        // Test that requests in a row are answered by the solution kept since the first one.
+        for _ in 0..3 {
+            assert!(processor
+                .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+                .unwrap()
+                .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+        }
+        assert_eq!(processor.get_metrics().get_recomputes(), 1);
+
+        // Test that an outdated price update doesn't discard the solution, a newer one does.
+        processor
+            .process_line("2019-01-20T09:42:22+00:00 E1 BTC USD 900.0 0.001")
+            .unwrap();
+        processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .unwrap();
+        assert_eq!(processor.get_metrics().get_recomputes(), 1);
+
+        processor
+            .process_line("2019-01-20T09:42:24+00:00 E1 BTC USD 900.0 0.001")
+            .unwrap();
+        assert!(processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <900>"));
+        assert_eq!(processor.get_metrics().get_recomputes(), 2);
+    }
+
+    #[test]
+    fn process_line_with_compute_deadline() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        processor.set_compute_deadline(Some(Duration::ZERO));
+
+        processor
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001")
+            .unwrap();
+
+        // Test that a request is unanswered once its deadline passes.
+        assert_eq!(
+            processor.process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD"),
+            Ok("BEST_RATES_UNAVAILABLE <E1> <BTC> <E1> <USD> <cancelled>\n".to_string())
+        );
+
+        processor.set_compute_deadline(None);
+        assert!(processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+    }
+
+    #[test]
+    fn process_line_with_max_graph_size() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        processor.set_max_graph_size(Some(1));
+
+        processor
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001")
+            .unwrap();
+
+        // Test that only a new pair is rejected.
+        assert_eq!(
+            processor.process_line("2019-01-20T09:42:23+00:00 E1 ETH USD 100.0 0.01"),
+            Ok("LINE_REJECTED <graph_size_exceeded>\n".to_string())
+        );
+        assert_eq!(
+            processor.process_line("2019-01-20T09:42:24+00:00 E1 BTC USD 900.0 0.001"),
+            Ok(String::new())
+        );
+        assert_eq!(
+            processor
+                .new_session()
+                .process_line("2019-01-20T09:42:23+00:00 E1 ETH USD 100.0 0.01"),
+            Ok(String::new())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "feed")]
+    fn mark_stale() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        let request = "EXCHANGE_RATE_REQUEST E1 BTC E1 USD";
+        processor
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001")
+            .unwrap();
+
+        let index = ("E1".to_string(), "BTC".to_string(), "USD".to_string());
+        processor.mark_stale(&[index]);
+        assert!(processor
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_UNAVAILABLE"));
+
+        let mut output = Vec::new();
+        processor.write_snapshot(&mut output).unwrap();
+        assert!(output.is_empty());
+
+        // Test that a more recent price update is fresh again.
+        processor
+            .process_line("2019-01-20T09:42:24+00:00 E1 BTC USD 1001 0.001")
+            .unwrap();
+        assert!(processor
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1001>"));
+    }
+
+    #[test]
+    fn write_snapshot() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        let lines = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001
+2019-01-20T09:42:23+00:00 E1 ETH USD BID_ASK 99 101
+";
+        for line in lines.lines() {
+            processor.process_line(line).unwrap();
+        }
+
+        let mut output = Vec::new();
+        processor.write_snapshot(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), lines);
+    }
+
+    #[test]
+    fn answer_as_of() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        let request = "EXCHANGE_RATE_REQUEST E1 BTC E1 USD AS_OF 2019-01-20T14:02:00+00:00";
+
+        // Test that past rates can't be answered without the history.
+        processor
+            .process_line("2019-01-20T14:00:00+00:00 E1 BTC USD 1000 0.001")
+            .unwrap();
+        assert_eq!(
+            processor.process_line(request).unwrap(),
+            "BEST_RATES_UNAVAILABLE <E1> <BTC> <E1> <USD> <no_history>\n"
+        );
+
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        processor.set_history(true);
+        for line in &[
+            "2019-01-20T14:00:00+00:00 E1 BTC USD 1000 0.001",
+            "2019-01-20T14:03:00+00:00 E1 BTC USD 1002 0.0009",
+        ] {
+            processor.process_line(line).unwrap();
+        }
+
+        // Test that the price update known at the moment is used, the current one otherwise.
+        assert!(processor
+            .process_line(request)
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+        assert!(processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1002>"));
+        assert!(processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD AS_OF 2019-01-20T13:00:00+00:00")
+            .unwrap()
+            .starts_with("BEST_RATES_UNAVAILABLE <E1> <BTC> <E1> <USD> <unknown_source_exchange>"));
+    }
+
+    #[test]
+    fn import_state() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        let state = "{
+  \"version\": 1,
+  \"settings\": {\"min_profit_factor\": 1.01},
+  \"price_updates\": [{
+    \"timestamp\": \"2019-01-20T09:42:23+00:00\",
+    \"exchange\": \"E1\",
+    \"source_currency\": \"BTC\",
+    \"destination_currency\": \"USD\",
+    \"forward_factor\": 1000,
+    \"backward_factor\": 0.001
+  }]
+}";
+
+        assert_eq!(processor.import_state(state), Ok(1));
+        assert_eq!(*processor.settings.get_min_profit_factor(), 1.01);
+        assert!(processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+
+        assert!(processor.import_state("{\"version\": 1}").is_err());
+    }
+
+    #[test]
+    fn journal() {
+        let path = std::env::temp_dir().join(format!(
+            "exchange-rate-{}.stream.journal",
+            std::process::id()
+        ));
+        let lines = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001
+2019-01-20T09:42:22+00:00 E1 BTC USD 999 0.001
+2019-01-20T09:42:23+00:00 E1 ETH USD BID_ASK 99 101
+";
+
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        assert_eq!(processor.open_journal(&path), Ok(0));
+        for line in lines.lines() {
+            processor.process_line(line).unwrap();
+        }
+
+        // Test that only the accepted price updates are appended.
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001
+2019-01-20T09:42:23+00:00 E1 ETH USD BID_ASK 99 101
+"
+        );
+
+        // Test that the state is recovered, skipping the torn last line.
+        fs::write(&path, format!("{}2019-01-20T09:42:24+00:00 E1 BTC", lines)).unwrap();
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        assert_eq!(processor.open_journal(&path), Ok(2));
+        assert!(processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1000>"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), lines);
+
+        fs::write(&path, "2019-01-20T09:42:23+00:00 E1 BTC\n").unwrap();
+        assert!(StreamProcessor::<String, f32>::new(Settings::new())
+            .open_journal(&path)
+            .is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn compact_journal() {
+        let path = std::env::temp_dir().join(format!(
+            "exchange-rate-{}.stream.compact.journal",
+            std::process::id()
+        ));
+        let line = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001\n";
+        fs::write(&path, line.repeat(10_000)).unwrap();
+
+        // Test that the superseded lines are dropped at startup.
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        assert_eq!(processor.open_journal(&path), Ok(1));
+        assert_eq!(fs::read_to_string(&path).unwrap(), line);
+
+        processor
+            .process_line("2019-01-20T09:42:23+00:00 E1 ETH USD BID_ASK 99 101")
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!(
+                "{}2019-01-20T09:42:23+00:00 E1 ETH USD BID_ASK 99 101\n",
+                line
+            )
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn storage() {
+        let path = std::env::temp_dir().join(format!(
+            "exchange-rate-{}.stream.sqlite",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        processor.open_storage(&path).unwrap();
+        for line in [
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001",
+            "2019-01-20T09:42:22+00:00 E1 BTC USD 999 0.001",
+            "EXCHANGE_RATE_REQUEST E1 BTC E1 USD",
+        ] {
+            processor.process_line(line).unwrap();
+        }
+        drop(processor);
+
+        // Test that only the accepted price updates and answered paths are stored.
+        let storage = Storage::open(&path).unwrap();
+        assert_eq!(
+            storage.query("SELECT count(*) FROM price_updates").unwrap(),
+            vec!["1"]
+        );
+        assert_eq!(
+            storage.query("SELECT path FROM best_rate_paths").unwrap(),
+            vec!["E1:BTC,E1:USD"]
+        );
+        drop(storage);
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn monitor_arbitrage() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        processor.set_monitor_arbitrage(true);
+
+        processor
+            .process_line("2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001")
+            .unwrap();
+
+        // Test that the opportunity appearance is alerted.
+        let output = processor
+            .process_line("2019-01-20T09:42:23+00:00 E2 BTC USD 900.0 0.00111")
+            .unwrap();
+        assert!(output.starts_with("ARBITRAGE_APPEARED <1.1099999> <E1, BTC> <E1, USD>"));
+
+        // Test that the opportunity disappearance is alerted.
+        let output = processor
+            .process_line("2019-01-20T09:42:24+00:00 E2 BTC USD 1000.0 0.001")
+            .unwrap();
+        assert!(output.starts_with("ARBITRAGE_DISAPPEARED <1>"));
+    }
+
+    #[test]
+    fn monitor_rates() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        let pair = (
+            "E1".to_string(),
+            "BTC".to_string(),
+            "E1".to_string(),
+            "USD".to_string(),
+        );
+        processor.add_rate_alert(pair.clone(), 0.005);
+
+        // Test that the first rate and moves within the threshold are not alerted.
+        for line in [
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.0009",
+            "2019-01-20T09:42:24+00:00 E1 BTC USD 1004.0 0.0009",
+        ] {
+            assert_eq!(processor.process_line(line), Ok(String::new()));
+        }
+
+        // Test that a move beyond the threshold is alerted.
+        let output = processor
+            .process_line("2019-01-20T09:42:25+00:00 E1 BTC USD 1010.0 0.0009")
+            .unwrap();
+        assert_eq!(output, "RATE_ALERT <E1> <BTC> <E1> <USD> <1000> <1010>\n");
+
+        // Test that the watched pairs are not answered as requests.
+        let output = processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 USD E1 BTC")
+            .unwrap();
+        assert!(output.starts_with("BEST_RATES_BEGIN <E1> <USD> <E1> <BTC> <0.0009>"));
+        assert!(!output.contains("<E1> <BTC> <E1> <USD>"));
+
+        assert!(processor.remove_rate_alert(&pair));
+        assert_eq!(
+            processor.process_line("2019-01-20T09:42:26+00:00 E1 BTC USD 2000.0 0.0004"),
+            Ok(String::new())
+        );
+    }
+
+    #[test]
+    fn process_line_with_aggregation_interval() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        processor.set_aggregation_interval(Some(chrono::Duration::seconds(10)));
+
+        for line in [
+            "2019-01-20T09:42:00+00:00 E1 BTC USD 1000.0 0.0009",
+            "2019-01-20T09:42:05+00:00 E1 BTC USD 1200.0 0.0009",
+            "2019-01-20T09:42:10+00:00 E1 BTC USD 1000.0 0.0009",
+        ] {
+            processor.process_line(line).unwrap();
+        }
+
+        // Test that the rate is the time-weighted average of the quotes within the interval.
+        let output = processor
+            .process_line("EXCHANGE_RATE_REQUEST E1 BTC E1 USD")
+            .unwrap();
+        assert!(output.starts_with("BEST_RATES_BEGIN <E1> <BTC> <E1> <USD> <1100>"));
+    }
+
+    #[test]
+    fn process_line_with_stamp() {
+        let mut processor = StreamProcessor::<String, f32>::new(Settings::new());
+        processor.set_include_stamp(true);
+
+        // Test that the stamp tells the most recent quote of the kept solution, updated or not.
+        for (line, as_of) in [
+            ("2019-01-20T09:42:00+00:00 E1 BTC USD 1000.0 0.0009", None),
+            ("2019-01-20T09:41:00+00:00 E1 ETH USD 100.0 0.009", None),
+            (
+                "EXCHANGE_RATE_REQUEST E1 BTC E1 USD",
+                Some("2019-01-20T09:42:00+00:00"),
+            ),
+            ("2019-01-20T09:43:00+00:00 E1 ETH USD 101.0 0.009", None),
+            (
+                "EXCHANGE_RATE_REQUEST E1 BTC E1 USD",
+                Some("2019-01-20T09:43:00+00:00"),
+            ),
+        ] {
+            let output = processor.process_line(line).unwrap();
+            match as_of {
+                Some(as_of) => assert!(output.contains(&format!("AS_OF <quotes, {}>", as_of))),
+                None => assert_eq!(output, ""),
+            }
+        }
+    }
+}
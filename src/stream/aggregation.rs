@@ -0,0 +1,222 @@
+//! Aggregation of quotes within an interval.
+
+use crate::request::price_update::{PriceUpdate, Quote};
+use crate::IndexMapTrait;
+use chrono::{DateTime, Duration, FixedOffset};
+use num_traits::Num;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::str::FromStr;
+
+/// Quotes of a pair and their timestamps, the oldest first.
+type Quotes<E> = VecDeque<(DateTime<FixedOffset>, Quote<E>)>;
+
+/// `Aggregation` structure.
+///
+/// Combines `PriceUpdate`s of the same pair within the interval before the most recent one into
+/// their time-weighted average, so a quote flickering for a moment barely moves the rate. Each
+/// quote is weighted by the time it was in force within the interval, until the next quote of
+/// the pair. The most recent quote has been in force for no time yet, so it weighs in once the
+/// next one arrives, and it's used as it is only if no other quote of the interval is known.
+///
+/// Price updates carry no traded volume, so quotes are weighted by time only.
+///
+/// # `Aggregation<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct Aggregation<N, E> {
+    interval: Duration,
+    quotes: HashMap<(N, N, N), Quotes<E>>,
+}
+
+impl<N, E> Aggregation<N, E>
+where
+    N: Clone + FromStr + IndexMapTrait,
+    <N as FromStr>::Err: Debug,
+    E: Copy + Num + FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of `Aggregation` structure of the interval length.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            quotes: HashMap::new(),
+        }
+    }
+
+    pub fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Record the quote of the `PriceUpdate` and return the `PriceUpdate` of its pair quoting the
+    /// time-weighted average of the interval instead.
+    ///
+    /// A `PriceUpdate` not more recent than the last one of its pair is returned as it is, it's
+    /// outdated. Quotes of bid and ask prices and of factors aren't averaged together, a change of
+    /// the kind starts the aggregation over.
+    pub fn aggregate(&mut self, price_update: PriceUpdate<N, E>) -> PriceUpdate<N, E> {
+        let timestamp = *price_update.get_timestamp();
+        let quote = *price_update.get_quote();
+        let quotes = self.quotes.entry(price_update.get_index()).or_default();
+
+        match quotes.back() {
+            Some((last, _)) if *last >= timestamp => return price_update,
+            Some((_, last_quote)) if !is_same_kind(last_quote, &quote) => quotes.clear(),
+            _ => {}
+        }
+        quotes.push_back((timestamp, quote));
+
+        // Drop quotes replaced before the start of the interval.
+        let start = timestamp - self.interval;
+        while quotes.len() > 1 && quotes[1].0 <= start {
+            quotes.pop_front();
+        }
+
+        match average(quotes, start) {
+            Some(average) => PriceUpdate::new(
+                timestamp,
+                price_update.get_exchange().clone(),
+                price_update.get_source_currency().clone(),
+                price_update.get_destination_currency().clone(),
+                average,
+            ),
+            None => price_update,
+        }
+    }
+
+    /// Forget quotes of the pair, the next `PriceUpdate` starts the aggregation over, e.g. once
+    /// its quote went stale.
+    #[cfg(feature = "feed")]
+    pub fn forget(&mut self, index: &(N, N, N)) {
+        self.quotes.remove(index);
+    }
+}
+
+/// Check whether the quotes are of the same kind, both bid and ask prices or both factors.
+fn is_same_kind<E>(a: &Quote<E>, b: &Quote<E>) -> bool {
+    matches!(
+        (a, b),
+        (Quote::Factors { .. }, Quote::Factors { .. })
+            | (Quote::BidAsk { .. }, Quote::BidAsk { .. })
+    )
+}
+
+/// Get the average of the quotes weighted by the time each was in force since the start, `None`
+/// if none of them was in force for any time.
+fn average<E>(quotes: &Quotes<E>, start: DateTime<FixedOffset>) -> Option<Quote<E>>
+where
+    E: Copy + Num + FromStr,
+    <E as FromStr>::Err: Debug,
+{
+    let mut total = E::zero();
+    let (mut first, mut second) = (E::zero(), E::zero());
+
+    for ((since, quote), (until, _)) in quotes.iter().zip(quotes.iter().skip(1)) {
+        let milliseconds = (*until - (*since).max(start)).num_milliseconds();
+        let weight = milliseconds
+            .to_string()
+            .parse::<E>()
+            .expect("A count of milliseconds is a valid edge weight!");
+
+        let (a, b) = match *quote {
+            Quote::Factors { forward, backward } => (forward, backward),
+            Quote::BidAsk { bid, ask } => (bid, ask),
+        };
+        first = first + a * weight;
+        second = second + b * weight;
+        total = total + weight;
+    }
+
+    if total == E::zero() {
+        return None;
+    }
+
+    let (first, second) = (first / total, second / total);
+    Some(match quotes.back()?.1 {
+        Quote::Factors { .. } => Quote::Factors {
+            forward: first,
+            backward: second,
+        },
+        Quote::BidAsk { .. } => Quote::BidAsk {
+            bid: first,
+            ask: second,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request::price_update::{PriceUpdate, Quote};
+    use crate::stream::aggregation::Aggregation;
+
+    fn aggregate(aggregation: &mut Aggregation<String, f64>, line: &str) -> Quote<f64> {
+        *aggregation
+            .aggregate(PriceUpdate::parse_line(line).unwrap())
+            .get_quote()
+    }
+
+    #[test]
+    fn aggregate_time_weighted() {
+        let mut aggregation = Aggregation::new(chrono::Duration::seconds(10));
+        let factors = |forward, backward| Quote::Factors { forward, backward };
+
+        // Test that the first quote is used as it is.
+        assert_eq!(
+            aggregate(
+                &mut aggregation,
+                "2019-01-20T09:42:00+00:00 E1 BTC USD 1000 0.001"
+            ),
+            factors(1000.0, 0.001)
+        );
+
+        // Test that a flickering quote weighs in by the time it was in force.
+        assert_eq!(
+            aggregate(
+                &mut aggregation,
+                "2019-01-20T09:42:08+00:00 E1 BTC USD 1100 0.0009"
+            ),
+            factors(1000.0, 0.001)
+        );
+        match aggregate(
+            &mut aggregation,
+            "2019-01-20T09:42:10+00:00 E1 BTC USD 1000 0.001",
+        ) {
+            Quote::Factors { forward, backward } => {
+                assert_eq!(forward, 1020.0);
+                assert!((backward - 0.00098).abs() < 1e-12);
+            }
+            quote => panic!("Unexpected quote {:?}!", quote),
+        }
+
+        // Test that quotes replaced before the interval are dropped.
+        assert_eq!(
+            aggregate(
+                &mut aggregation,
+                "2019-01-20T09:42:20+00:00 E1 BTC USD 1000 0.001"
+            ),
+            factors(1000.0, 0.001)
+        );
+
+        // Test that an outdated quote is returned as it is.
+        assert_eq!(
+            aggregate(
+                &mut aggregation,
+                "2019-01-20T09:42:15+00:00 E1 BTC USD 900 0.001"
+            ),
+            factors(900.0, 0.001)
+        );
+
+        // Test that a quote of another kind starts the aggregation over.
+        assert_eq!(
+            aggregate(
+                &mut aggregation,
+                "2019-01-20T09:42:21+00:00 E1 BTC USD BID_ASK 999 1001"
+            ),
+            Quote::BidAsk {
+                bid: 999.0,
+                ask: 1001.0
+            }
+        );
+    }
+}
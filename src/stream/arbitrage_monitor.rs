@@ -0,0 +1,179 @@
+//! Arbitrage Monitor.
+
+use crate::algorithm::Algorithm;
+use crate::response::arbitrage_alert::ArbitrageAlert;
+use crate::response::arbitrage_alert::Change::*;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use indexmap::IndexMap;
+use num_traits::Num;
+use safe_graph::NodeTrait;
+use std::fmt::{Debug, Display};
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+/// State of a monitored cycle.
+struct TrackedArbitrage<E> {
+    profit_factor: E,
+    // Whether the cycle was alerted as an existing opportunity.
+    reported: bool,
+    // Count of consecutive evaluations contradicting the `reported` state.
+    streak: usize,
+}
+
+/// `ArbitrageMonitor` structure.
+///
+/// Keeps track of profitable cycles and alerts appearance or disappearance of an opportunity
+/// once the change persisted for the debounce count of consecutive evaluations.
+///
+/// # `ArbitrageMonitor<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct ArbitrageMonitor<N, E> {
+    tracked: IndexMap<Vec<(N, N)>, TrackedArbitrage<E>>,
+}
+
+impl<N, E> ArbitrageMonitor<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    pub fn new() -> Self {
+        Self {
+            tracked: IndexMap::new(),
+        }
+    }
+
+    /// Evaluate opportunities after edges of the `updated_nodes` changed.
+    ///
+    /// New cycles can only pass through the updated nodes, so just those are searched for,
+    /// while the already tracked cycles are re-evaluated. Return alerts of confirmed changes.
+    pub fn evaluate<I>(
+        &mut self,
+        alg: &Algorithm<N, E, I>,
+        updated_nodes: &[(N, N)],
+    ) -> Vec<ArbitrageAlert<N, E>>
+    where
+        I: NodeTrait + Num + AddAssign,
+    {
+        let settings = alg.get_settings();
+        let mut profitable: IndexMap<Vec<(N, N)>, E> = IndexMap::new();
+
+        for (exchange, currency) in updated_nodes.iter() {
+            for arbitrage in alg.find_arbitrages_through(exchange, currency) {
+                profitable.insert(
+                    arbitrage.get_cycle().to_vec(),
+                    *arbitrage.get_profit_factor(),
+                );
+            }
+        }
+
+        // Start tracking newly found cycles.
+        for (cycle, profit_factor) in profitable.iter() {
+            if !self.tracked.contains_key(cycle) {
+                self.tracked.insert(
+                    cycle.clone(),
+                    TrackedArbitrage {
+                        profit_factor: *profit_factor,
+                        reported: false,
+                        streak: 0,
+                    },
+                );
+            }
+        }
+
+        let debounce = settings.get_alert_debounce();
+        let min_profit_factor = *settings.get_min_profit_factor();
+        let mut alerts = Vec::new();
+
+        self.tracked.retain(|cycle, tracked| {
+            let profit_factor = match profitable.get(cycle) {
+                Some(profit_factor) => Some(*profit_factor),
+                None => alg.get_cycle_profit_factor(cycle),
+            };
+            if let Some(profit_factor) = profit_factor {
+                tracked.profit_factor = profit_factor;
+            }
+
+            let is_profitable = profit_factor.is_some_and(|p| p > min_profit_factor);
+
+            // The state is confirmed, reset the streak and stop tracking unprofitable cycles.
+            if is_profitable == tracked.reported {
+                tracked.streak = 0;
+                return tracked.reported;
+            }
+
+            tracked.streak += 1;
+            if tracked.streak > debounce {
+                let change = if is_profitable { Appeared } else { Disappeared };
+                alerts.push(ArbitrageAlert::new(
+                    change,
+                    tracked.profit_factor,
+                    cycle.clone(),
+                ));
+
+                tracked.reported = is_profitable;
+                tracked.streak = 0;
+                return is_profitable;
+            }
+
+            true
+        });
+
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::algorithm::Algorithm;
+    use crate::request::Request;
+    use crate::response::arbitrage_alert::Change::*;
+    use crate::stream::arbitrage_monitor::ArbitrageMonitor;
+    use std::io::BufReader;
+
+    fn prepare_algorithm(e2_btc_usd: &str, debounce: usize) -> Algorithm<String, f32, u32> {
+        let text_input = format!(
+            "2019-01-20T09:42:23+00:00 E1 BTC USD 1000.0 0.001
+2019-01-20T09:42:23+00:00 E2 BTC USD {}",
+            e2_btc_usd
+        );
+
+        let mut input = BufReader::new(text_input.as_bytes());
+        let request = Request::<String, f32>::read_from(&mut input);
+
+        let mut settings = Settings::new();
+        settings.set_alert_debounce(debounce);
+
+        Algorithm::from_request(&request, &settings)
+    }
+
+    #[test]
+    fn evaluate_with_debounce() {
+        let mut monitor = ArbitrageMonitor::<String, f32>::new();
+        let updated_nodes = vec![("E2".to_string(), "BTC".to_string())];
+        let profitable = prepare_algorithm("900.0 0.00111", 1);
+        let unprofitable = prepare_algorithm("1000.0 0.001", 1);
+
+        // Test that the appearance is alerted only after it persisted.
+        assert!(monitor.evaluate(&profitable, &updated_nodes).is_empty());
+        let alerts = monitor.evaluate(&profitable, &updated_nodes);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].get_change(), Appeared);
+
+        // Test that a short flap is not alerted at all.
+        assert!(monitor.evaluate(&unprofitable, &updated_nodes).is_empty());
+        assert!(monitor.evaluate(&profitable, &updated_nodes).is_empty());
+
+        // Test that the persisting disappearance is alerted once.
+        assert!(monitor.evaluate(&unprofitable, &updated_nodes).is_empty());
+        let alerts = monitor.evaluate(&unprofitable, &updated_nodes);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].get_change(), Disappeared);
+        assert!(monitor.evaluate(&unprofitable, &updated_nodes).is_empty());
+    }
+}
@@ -0,0 +1,541 @@
+//! Asynchronous processing of input lines.
+//!
+//! `StreamProcessor::outputs` turns asynchronously read input lines into a stream of their
+//! outputs, and `Outputs::write_all` writes them to an asynchronous output, so the engine is
+//! embedded in async services the same as the watch mode reads stdin and writes stdout. It's
+//! independent of any runtime: lines are polled by `AsyncLines`, e.g. `tokio::io::Lines` wrapped
+//! by `poll_lines`, and written by `AsyncOutput`, e.g. `tokio::io::AsyncWrite` wrapped by
+//! `poll_output`.
+//!
+//! Lines are processed by a worker thread owning the `StreamProcessor`, which wakes the polling
+//! task once a line is processed, so building and solving the graph never blocks the executor.
+
+use crate::error::Error;
+use crate::stream::StreamProcessor;
+use crate::IndexMapTrait;
+use floyd_warshall_alg::FloydWarshallTrait;
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+
+/// Source of input lines read asynchronously.
+pub trait AsyncLines {
+    /// Poll the next line, `None` at the end of the input.
+    fn poll_next_line(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<Option<String>>>;
+}
+
+/// Output written asynchronously.
+pub trait AsyncOutput {
+    /// Poll writing of the bytes, giving the count of them written.
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>>;
+
+    /// Poll flushing of the bytes written.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+}
+
+/// `PollLines` structure.
+///
+/// `AsyncLines` polling lines by the function, see `poll_lines`.
+pub struct PollLines<F> {
+    poll: F,
+}
+
+/// Get `AsyncLines` polling lines by the function, e.g. of `tokio::io::Lines`:
+/// `poll_lines(move |cx| Pin::new(&mut lines).poll_next_line(cx))`.
+pub fn poll_lines<F>(poll: F) -> PollLines<F>
+where
+    F: FnMut(&mut Context<'_>) -> Poll<io::Result<Option<String>>> + Unpin,
+{
+    PollLines { poll }
+}
+
+impl<F> AsyncLines for PollLines<F>
+where
+    F: FnMut(&mut Context<'_>) -> Poll<io::Result<Option<String>>> + Unpin,
+{
+    fn poll_next_line(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<Option<String>>> {
+        (self.get_mut().poll)(cx)
+    }
+}
+
+/// `PollOutput` structure.
+///
+/// `AsyncOutput` writing to the writer by the functions, see `poll_output`.
+pub struct PollOutput<W, F, G> {
+    writer: W,
+    write: F,
+    flush: G,
+}
+
+/// Get `AsyncOutput` writing to the writer by the functions, e.g. of `tokio::io::AsyncWrite`:
+/// `poll_output(writer, |writer, cx, buf| Pin::new(writer).poll_write(cx, buf),
+/// |writer, cx| Pin::new(writer).poll_flush(cx))`.
+pub fn poll_output<W, F, G>(writer: W, write: F, flush: G) -> PollOutput<W, F, G>
+where
+    W: Unpin,
+    F: FnMut(&mut W, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>> + Unpin,
+    G: FnMut(&mut W, &mut Context<'_>) -> Poll<io::Result<()>> + Unpin,
+{
+    PollOutput {
+        writer,
+        write,
+        flush,
+    }
+}
+
+impl<W, F, G> AsyncOutput for PollOutput<W, F, G>
+where
+    W: Unpin,
+    F: FnMut(&mut W, &mut Context<'_>, &[u8]) -> Poll<io::Result<usize>> + Unpin,
+    G: FnMut(&mut W, &mut Context<'_>) -> Poll<io::Result<()>> + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let output = self.get_mut();
+        (output.write)(&mut output.writer, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let output = self.get_mut();
+        (output.flush)(&mut output.writer, cx)
+    }
+}
+
+/// Result of the line processed by the worker, and the waker of the task waiting for it.
+#[derive(Default)]
+struct Processed {
+    result: Option<Result<String, Error>>,
+    waker: Option<Waker>,
+    /// The worker is gone, it panicked if the result is missing.
+    stopped: bool,
+}
+
+/// Marks the worker stopped and wakes the waiting task once it's dropped, even by a panic.
+struct StopGuard(Arc<Mutex<Processed>>);
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        let mut processed = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        processed.stopped = true;
+        if let Some(waker) = processed.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// `Outputs` structure.
+///
+/// Stream of the outputs of the input lines processed by a `StreamProcessor` on a worker
+/// thread, see `StreamProcessor::outputs`.
+///
+/// # `Outputs<L, N, E>` is parameterized over:
+///
+/// - Input lines `L`.
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct Outputs<L, N, E> {
+    lines: L,
+    sender: Option<Sender<String>>,
+    worker: Option<JoinHandle<StreamProcessor<N, E>>>,
+    processed: Arc<Mutex<Processed>>,
+    /// A line was sent to the worker and its result wasn't taken yet.
+    processing: bool,
+}
+
+impl<L, N, E> Outputs<L, N, E>
+where
+    L: AsyncLines + Unpin,
+{
+    /// Poll the output of the next input line causing any, `None` at the end of the input.
+    ///
+    /// A line that can't be processed gives its `Error` and the stream goes on, a failed reading
+    /// gives the `Io` error.
+    pub fn poll_next_output(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<String, Error>>> {
+        let outputs = self.get_mut();
+
+        loop {
+            if outputs.processing {
+                let mut processed = outputs
+                    .processed
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                match processed.result.take() {
+                    Some(result) => {
+                        outputs.processing = false;
+                        match result {
+                            Ok(output) if output.is_empty() => continue,
+                            result => return Poll::Ready(Some(result)),
+                        }
+                    }
+                    None if processed.stopped => {
+                        outputs.processing = false;
+                        return Poll::Ready(Some(Err(Error::Io(io::Error::other(
+                            "The line can not be processed, the worker panicked!",
+                        )))));
+                    }
+                    None => {
+                        processed.waker = Some(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                }
+            }
+
+            let line = match Pin::new(&mut outputs.lines).poll_next_line(cx) {
+                Poll::Ready(Ok(Some(line))) => line,
+                Poll::Ready(Ok(None)) => return Poll::Ready(None),
+                Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(Error::Io(error)))),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            // The worker is gone only if it panicked, it's marked stopped then and the next
+            // poll reports it.
+            if let Some(sender) = &outputs.sender {
+                let _ = sender.send(line);
+            }
+            outputs.processing = true;
+        }
+    }
+
+    /// Get the future of the next output, see `poll_next_output`.
+    pub fn next_output(&mut self) -> NextOutput<'_, L, N, E> {
+        NextOutput { outputs: self }
+    }
+
+    /// Get the future writing all outputs to the output, each one flushed as soon as it's
+    /// written, see `WriteOutputs`.
+    pub fn write_all<W>(self, output: W) -> WriteOutputs<L, W, N, E>
+    where
+        W: AsyncOutput + Unpin,
+    {
+        WriteOutputs {
+            outputs: self,
+            output,
+            pending: None,
+        }
+    }
+
+    /// Stop the worker and take the `StreamProcessor` back, after the line being processed if
+    /// there's one. `None` is returned if the worker panicked.
+    pub fn into_processor(mut self) -> Option<StreamProcessor<N, E>> {
+        self.sender.take();
+        self.worker.take()?.join().ok()
+    }
+}
+
+impl<L, N, E> Drop for Outputs<L, N, E> {
+    /// The worker stops once the sender is gone, after the line being processed if there's one.
+    fn drop(&mut self) {
+        self.sender.take();
+    }
+}
+
+/// `NextOutput` structure.
+///
+/// Future of the next output of `Outputs`, see `Outputs::next_output`.
+pub struct NextOutput<'a, L, N, E> {
+    outputs: &'a mut Outputs<L, N, E>,
+}
+
+impl<'a, L, N, E> Future for NextOutput<'a, L, N, E>
+where
+    L: AsyncLines + Unpin,
+{
+    type Output = Option<Result<String, Error>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.get_mut().outputs).poll_next_output(cx)
+    }
+}
+
+/// `WriteOutputs` structure.
+///
+/// Future writing all outputs of `Outputs` to an `AsyncOutput` until the input ends, see
+/// `Outputs::write_all`. Lines that can't be processed are reported to stderr and skipped, the
+/// same as by the watch mode, a failed reading or writing ends it by the `Io` error.
+///
+/// # `WriteOutputs<L, W, N, E>` is parameterized over:
+///
+/// - Input lines `L`.
+/// - Output `W`.
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct WriteOutputs<L, W, N, E> {
+    outputs: Outputs<L, N, E>,
+    output: W,
+    /// Output being written and the count of its bytes written already.
+    pending: Option<(String, usize)>,
+}
+
+impl<L, W, N, E> WriteOutputs<L, W, N, E>
+where
+    W: AsyncOutput + Unpin,
+{
+    /// Poll writing and flushing of the pending output, if there's one.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let (text, written) = match &mut self.pending {
+            Some(pending) => pending,
+            None => return Poll::Ready(Ok(())),
+        };
+
+        while *written < text.len() {
+            match Pin::new(&mut self.output).poll_write(cx, &text.as_bytes()[*written..]) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Poll::Ready(Ok(count)) => *written += count,
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        // Make the output available without waiting for more input.
+        match Pin::new(&mut self.output).poll_flush(cx) {
+            Poll::Ready(Ok(())) => {
+                self.pending = None;
+                Poll::Ready(Ok(()))
+            }
+            poll => poll,
+        }
+    }
+}
+
+impl<L, W, N, E> Future for WriteOutputs<L, W, N, E>
+where
+    L: AsyncLines + Unpin,
+    W: AsyncOutput + Unpin,
+{
+    type Output = Result<(), Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let write_outputs = self.get_mut();
+
+        loop {
+            match write_outputs.poll_pending(cx) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(Error::Io(error))),
+                Poll::Pending => return Poll::Pending,
+            }
+
+            match Pin::new(&mut write_outputs.outputs).poll_next_output(cx) {
+                Poll::Ready(Some(Ok(output))) => write_outputs.pending = Some((output, 0)),
+                Poll::Ready(Some(Err(Error::Io(error)))) => {
+                    return Poll::Ready(Err(Error::Io(error)))
+                }
+                // Keep writing, a single invalid line shouldn't stop the stream processing.
+                Poll::Ready(Some(Err(error))) => eprintln!(
+                    "Errors occurred while processing input line, errors: {:?}!",
+                    error.get_messages()
+                ),
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<N, E> StreamProcessor<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait + Debug + Send + 'static,
+    <N as FromStr>::Err: Debug,
+    E: Display + FloydWarshallTrait + FromStr + Debug + Send + 'static,
+    <E as FromStr>::Err: Debug,
+{
+    /// Get the stream of the outputs of the asynchronously read input lines, processed like
+    /// `process_line` as they come. Lines causing no output, e.g. price updates, are skipped.
+    ///
+    /// The `StreamProcessor` is moved to a worker thread processing the lines one by one, so the
+    /// polling task isn't blocked while a line is answered. It's taken back by
+    /// `Outputs::into_processor`.
+    pub fn outputs<L>(self, lines: L) -> Outputs<L, N, E>
+    where
+        L: AsyncLines + Unpin,
+    {
+        let (sender, receiver) = mpsc::channel::<String>();
+        let processed = Arc::new(Mutex::new(Processed::default()));
+        let shared = Arc::clone(&processed);
+
+        let mut processor = self;
+        let worker = thread::spawn(move || {
+            let _guard = StopGuard(Arc::clone(&shared));
+            for line in receiver {
+                let result = processor.process_line(&line);
+
+                let mut processed = shared.lock().unwrap_or_else(PoisonError::into_inner);
+                processed.result = Some(result);
+                if let Some(waker) = processed.waker.take() {
+                    waker.wake();
+                }
+            }
+
+            processor
+        });
+
+        Outputs {
+            lines,
+            sender: Some(sender),
+            worker: Some(worker),
+            processed,
+            processing: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::settings::Settings;
+    use crate::stream::asynchronous::{poll_lines, poll_output};
+    use crate::stream::StreamProcessor;
+    use std::collections::VecDeque;
+    use std::future::Future;
+    use std::io;
+    use std::pin::pin;
+    use std::sync::{Arc, Condvar, Mutex, PoisonError};
+    use std::task::{Context, Poll, Wake, Waker};
+
+    /// Waker unparking the thread blocked on the future.
+    #[derive(Default)]
+    struct Unpark {
+        woken: Mutex<bool>,
+        condvar: Condvar,
+    }
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            *self.woken.lock().unwrap_or_else(PoisonError::into_inner) = true;
+            self.condvar.notify_one();
+        }
+    }
+
+    /// Poll the future to its end, waiting for a wake-up whenever it's pending.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let unpark = Arc::new(Unpark::default());
+        let waker = Waker::from(Arc::clone(&unpark));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+
+            let mut woken = unpark.woken.lock().unwrap_or_else(PoisonError::into_inner);
+            while !*woken {
+                woken = unpark
+                    .condvar
+                    .wait(woken)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            *woken = false;
+        }
+    }
+
+    /// Get lines ready or pending by turns, waking the task when pending.
+    fn pending_lines(lines: Vec<&'static str>) -> impl crate::stream::asynchronous::AsyncLines {
+        let mut lines: VecDeque<&str> = VecDeque::from(lines);
+        let mut pending = false;
+        poll_lines(move |cx| {
+            pending = !pending;
+            if pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            Poll::Ready(Ok(lines.pop_front().map(String::from)))
+        })
+    }
+
+    #[test]
+    fn outputs() {
+        let lines = pending_lines(vec![
+            "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009",
+            "EXCHANGE_RATE_REQUEST KRAKEN BTC",
+            "EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD",
+        ]);
+
+        let processor = StreamProcessor::<String, f32>::new(Settings::new());
+        let mut outputs = processor.outputs(lines);
+
+        // Test that an invalid line gives its error, price updates give no output.
+        assert!(block_on(outputs.next_output()).unwrap().is_err());
+        assert!(block_on(outputs.next_output())
+            .unwrap()
+            .unwrap()
+            .starts_with("BEST_RATES_BEGIN <KRAKEN> <BTC> <KRAKEN> <USD> <1000>\n"));
+        assert!(block_on(outputs.next_output()).is_none());
+
+        // Test that the processor is taken back with the state of the lines processed.
+        let processor = outputs.into_processor().unwrap();
+        assert_eq!(processor.get_metrics().get_price_updates(), 1);
+    }
+
+    #[test]
+    fn write_all() {
+        let lines = pending_lines(vec![
+            "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009",
+            "EXCHANGE_RATE_REQUEST KRAKEN BTC",
+            "EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD",
+            "EXCHANGE_RATE_REQUEST KRAKEN USD KRAKEN BTC",
+        ]);
+
+        // The output takes at most 8 bytes at once and is pending by turns.
+        let written = Arc::new(Mutex::new((Vec::new(), 0)));
+        let writer = Arc::clone(&written);
+        let mut pending = false;
+        let output = poll_output(
+            writer,
+            move |writer: &mut Arc<Mutex<(Vec<u8>, usize)>>, cx, buf: &[u8]| {
+                pending = !pending;
+                if pending {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                let count = buf.len().min(8);
+                let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+                writer.0.extend_from_slice(&buf[..count]);
+                Poll::Ready(Ok(count))
+            },
+            |writer: &mut Arc<Mutex<(Vec<u8>, usize)>>, _| {
+                writer.lock().unwrap_or_else(PoisonError::into_inner).1 += 1;
+                Poll::Ready(Ok(()))
+            },
+        );
+
+        let processor = StreamProcessor::<String, f32>::new(Settings::new());
+        block_on(processor.outputs(lines).write_all(output)).unwrap();
+
+        // Test that the outputs are written whole, each one flushed, invalid lines skipped.
+        let (bytes, flushes) = &*written.lock().unwrap_or_else(PoisonError::into_inner);
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert!(text.starts_with("BEST_RATES_BEGIN <KRAKEN> <BTC> <KRAKEN> <USD> <1000>\n"));
+        assert!(text.contains("BEST_RATES_END\nBEST_RATES_BEGIN <KRAKEN> <USD> <KRAKEN> <BTC>"));
+        assert_eq!(*flushes, 2);
+
+        // Test that a failed writing ends it by the error.
+        let lines = pending_lines(vec!["EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD"]);
+        let output = poll_output(
+            (),
+            |_: &mut (), _, _: &[u8]| Poll::Ready(Err(io::Error::other("The output failed!"))),
+            |_: &mut (), _| Poll::Ready(Ok(())),
+        );
+        let processor = StreamProcessor::<String, f32>::new(Settings::new());
+        assert!(block_on(processor.outputs(lines).write_all(output)).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! History of accepted price updates.
+
+use crate::request::price_update::{PriceUpdate, Quote};
+use crate::request::Request;
+use crate::IndexMapTrait;
+use chrono::{DateTime, FixedOffset};
+use indexmap::IndexMap;
+use num_traits::Zero;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+/// Quotes of a pair with their timestamps, from the oldest one.
+type Quotes<E> = Vec<(DateTime<FixedOffset>, Quote<E>)>;
+
+/// `History` structure.
+///
+/// Keeps all accepted quotes of each pair in the order of their timestamps, so the state as of
+/// any past moment can be reconstructed.
+///
+/// # `History<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct History<N, E> {
+    quotes: IndexMap<(N, N, N), Quotes<E>>,
+}
+
+impl<N, E> History<N, E>
+where
+    N: Clone + Display + FromStr + IndexMapTrait,
+    <N as FromStr>::Err: Debug,
+    E: Copy + FromStr + Zero + PartialOrd,
+    <E as FromStr>::Err: Debug,
+{
+    /// Create a new instance of empty `History` structure.
+    pub fn new() -> Self {
+        Self {
+            quotes: IndexMap::new(),
+        }
+    }
+
+    /// Record the accepted `PriceUpdate`, more recent than any recorded one of the same pair.
+    pub fn record(&mut self, price_update: &PriceUpdate<N, E>) {
+        self.quotes
+            .entry(price_update.get_index())
+            .or_default()
+            .push((*price_update.get_timestamp(), *price_update.get_quote()));
+    }
+
+    /// Get the `Request` of the most recent `PriceUpdate` of each pair as of the moment, pairs
+    /// first quoted after it are left out.
+    pub fn get_request_as_of(&self, as_of: &DateTime<FixedOffset>) -> Request<N, E> {
+        let mut request = Request::new();
+
+        for ((exchange, source, destination), quotes) in &self.quotes {
+            let count = quotes.partition_point(|(timestamp, _)| timestamp <= as_of);
+            if let Some((timestamp, quote)) = count.checked_sub(1).map(|index| quotes[index]) {
+                request.add_price_update(PriceUpdate::new(
+                    timestamp,
+                    exchange.clone(),
+                    source.clone(),
+                    destination.clone(),
+                    quote,
+                ));
+            }
+        }
+
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request::price_update::PriceUpdate;
+    use crate::stream::history::History;
+    use chrono::DateTime;
+
+    #[test]
+    fn get_request_as_of() {
+        let mut history = History::<String, f32>::new();
+        for line in &[
+            "2019-01-20T14:00:00+00:00 KRAKEN BTC USD 1000 0.001",
+            "2019-01-20T14:01:00+00:00 GDAX BTC USD 1001 0.0009",
+            "2019-01-20T14:03:00+00:00 KRAKEN BTC USD 1002 0.0009",
+        ] {
+            history.record(&PriceUpdate::parse_line(line).unwrap());
+        }
+
+        let get_lines = |as_of: &str| {
+            history
+                .get_request_as_of(&DateTime::parse_from_rfc3339(as_of).unwrap())
+                .get_price_updates()
+                .values()
+                .map(PriceUpdate::get_line)
+                .collect::<Vec<String>>()
+        };
+
+        // Test that the most recent price update of each pair as of the moment is used.
+        assert_eq!(
+            get_lines("2019-01-20T14:02:00+00:00"),
+            vec![
+                "2019-01-20T14:00:00+00:00 KRAKEN BTC USD 1000 0.001",
+                "2019-01-20T14:01:00+00:00 GDAX BTC USD 1001 0.0009",
+            ]
+        );
+        assert_eq!(
+            get_lines("2019-01-20T14:03:00+00:00")[0],
+            "2019-01-20T14:03:00+00:00 KRAKEN BTC USD 1002 0.0009"
+        );
+
+        // Test that pairs quoted after the moment are left out.
+        assert!(get_lines("2019-01-20T13:59:59+00:00").is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! Append-only journal of accepted price updates.
+
+use crate::checkpoint;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Least count of lines the journal is compacted at, so small journals aren't rewritten over
+/// and over.
+const COMPACTION_MIN_LINES: usize = 10_000;
+
+/// `Journal` structure.
+///
+/// Appends input lines of the accepted `PriceUpdate`s to the file as they come, so the state
+/// can be recovered after a crash by replaying them. Each line is written at once and flushed,
+/// a crash can tear the last line only.
+///
+/// Once most of its lines are superseded by more recent ones, the journal is compacted to the
+/// most recent line per pair, bounding the replay at startup.
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    lines: usize,
+}
+
+impl Journal {
+    /// Open the journal file of the path for appending, creating it if it doesn't exist, and
+    /// truncate it to the length, holding the count of lines.
+    pub fn open(path: &Path, length: u64, lines: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.set_len(length)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            lines,
+        })
+    }
+
+    /// Append the input line of a `PriceUpdate`.
+    pub fn append(&mut self, line: &str) -> io::Result<()> {
+        self.file.write_all(format!("{}\n", line).as_bytes())?;
+        self.lines += 1;
+        self.file.flush()
+    }
+
+    /// Check whether the journal should be compacted, with the count of pairs it holds a line
+    /// of. It is once at least half of its lines are superseded.
+    pub fn is_compaction_due(&self, pairs: usize) -> bool {
+        self.lines >= COMPACTION_MIN_LINES.max(2 * pairs)
+    }
+
+    /// Replace the journal by the input lines of the most recent `PriceUpdate` per pair.
+    ///
+    /// The journal is replaced atomically the same as the snapshot, so a crash leaves either
+    /// the old journal or the compacted one in place.
+    pub fn compact<I>(&mut self, lines: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut content = String::new();
+        let mut count = 0;
+        for line in lines {
+            content.push_str(&line);
+            content.push('\n');
+            count += 1;
+        }
+
+        checkpoint::write_snapshot(&self.path, content.as_bytes(), 0)?;
+        // The file replaced is appended to no more.
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.lines = count;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stream::journal::{Journal, COMPACTION_MIN_LINES};
+    use std::fs;
+
+    #[test]
+    fn append() {
+        let path =
+            std::env::temp_dir().join(format!("exchange-rate-{}.journal", std::process::id()));
+        let line = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001";
+
+        // Test that lines are appended to the existing ones.
+        Journal::open(&path, 0, 0).unwrap().append(line).unwrap();
+        let length = fs::metadata(&path).unwrap().len();
+        Journal::open(&path, length, 1)
+            .unwrap()
+            .append(line)
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{}\n{}\n", line, line)
+        );
+
+        // Test that the rest after the length is dropped.
+        Journal::open(&path, length, 1)
+            .unwrap()
+            .append(line)
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{}\n{}\n", line, line)
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn compact() {
+        let path = std::env::temp_dir().join(format!(
+            "exchange-rate-{}.compact.journal",
+            std::process::id()
+        ));
+        let line = "2019-01-20T09:42:23+00:00 E1 BTC USD 1000 0.001";
+
+        let mut journal = Journal::open(&path, 0, COMPACTION_MIN_LINES - 1).unwrap();
+        assert!(!journal.is_compaction_due(1));
+        journal
+            .append("2019-01-20T09:42:22+00:00 E1 BTC USD 999 0.001")
+            .unwrap();
+        assert!(journal.is_compaction_due(1));
+        // Test that a journal of mostly distinct pairs isn't compacted.
+        assert!(!journal.is_compaction_due(COMPACTION_MIN_LINES));
+
+        // Test that the compacted journal is appended to.
+        journal.compact(vec![line.to_string()]).unwrap();
+        assert!(!journal.is_compaction_due(1));
+        journal.append(line).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{}\n{}\n", line, line)
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,354 @@
+//! Processing metrics in the Prometheus text format.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Upper bounds of time histogram buckets, in seconds.
+const TIME_BUCKETS: [f64; 10] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Upper bounds of path length histogram buckets, in nodes.
+const PATH_LENGTH_BUCKETS: [f64; 7] = [2.0, 3.0, 4.0, 5.0, 6.0, 8.0, 10.0];
+
+/// Histogram of observed values, counted into cumulative buckets.
+#[derive(Clone, Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(self.counts.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn write_output(&self, output: &mut String, name: &str, help: &str) {
+        let _ = writeln!(output, "# HELP {} {}", name, help);
+        let _ = writeln!(output, "# TYPE {} histogram", name);
+
+        for (bound, count) in self.bounds.iter().zip(self.counts.iter()) {
+            let _ = writeln!(output, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+
+        let _ = writeln!(output, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count);
+        let _ = writeln!(output, "{}_sum {}", name, self.sum);
+        let _ = writeln!(output, "{}_count {}", name, self.count);
+    }
+}
+
+/// `Metrics` structure.
+///
+/// Counts processed price updates, answered requests, parse errors, recomputes of the graph and
+/// queries answered from a cache, gauges the size of the last recomputed graph and its solve
+/// time, and keeps histograms of graph build time, solve time and length of best rate paths.
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    price_updates: u64,
+    outdated_price_updates: u64,
+    rejected_price_updates: u64,
+    expired_price_updates: u64,
+    answered_requests: u64,
+    unanswered_requests: u64,
+    parse_errors: u64,
+    recomputes: u64,
+    cache_hits: u64,
+    node_count: usize,
+    edge_count: usize,
+    last_solve_time: Duration,
+    graph_build_time: Histogram,
+    solve_time: Histogram,
+    path_length: Histogram,
+}
+
+impl Metrics {
+    /// Create a new instance of `Metrics` structure with all values zero.
+    pub fn new() -> Self {
+        Self {
+            price_updates: 0,
+            outdated_price_updates: 0,
+            rejected_price_updates: 0,
+            expired_price_updates: 0,
+            answered_requests: 0,
+            unanswered_requests: 0,
+            parse_errors: 0,
+            recomputes: 0,
+            cache_hits: 0,
+            node_count: 0,
+            edge_count: 0,
+            last_solve_time: Duration::default(),
+            graph_build_time: Histogram::new(&TIME_BUCKETS),
+            solve_time: Histogram::new(&TIME_BUCKETS),
+            path_length: Histogram::new(&PATH_LENGTH_BUCKETS),
+        }
+    }
+
+    /// Record a price update, `outdated` if it was skipped as a more recent one is known.
+    pub fn record_price_update(&mut self, outdated: bool) {
+        if outdated {
+            self.outdated_price_updates += 1;
+        } else {
+            self.price_updates += 1;
+        }
+    }
+
+    /// Record a price update rejected before it was considered, e.g. over a limit.
+    pub fn record_rejected_price_update(&mut self) {
+        self.rejected_price_updates += 1;
+    }
+
+    /// Get count of the price updates ingested.
+    pub fn get_price_updates(&self) -> u64 {
+        self.price_updates
+    }
+
+    /// Get count of all price updates received, ingested, outdated or rejected.
+    pub fn get_received_price_updates(&self) -> u64 {
+        self.price_updates + self.outdated_price_updates + self.rejected_price_updates
+    }
+
+    /// Get count of the price updates skipped as a more recent one was known.
+    pub fn get_outdated_price_updates(&self) -> u64 {
+        self.outdated_price_updates
+    }
+
+    /// Get count of the price updates rejected before they were considered.
+    pub fn get_rejected_price_updates(&self) -> u64 {
+        self.rejected_price_updates
+    }
+
+    /// Record price updates removed as their quotes left the window of recent ones.
+    pub fn record_expired_price_updates(&mut self, count: usize) {
+        self.expired_price_updates += count as u64;
+    }
+
+    /// Get count of the price updates removed as their quotes left the window.
+    pub fn get_expired_price_updates(&self) -> u64 {
+        self.expired_price_updates
+    }
+
+    /// Record a request answered by a best rate path of the length, in nodes.
+    pub fn record_answered_request(&mut self, path_length: usize) {
+        self.answered_requests += 1;
+        self.path_length.observe(path_length as f64);
+    }
+
+    /// Record a request that could not be answered.
+    pub fn record_unanswered_request(&mut self) {
+        self.unanswered_requests += 1;
+    }
+
+    /// Record an input that could not be parsed.
+    pub fn record_parse_error(&mut self) {
+        self.parse_errors += 1;
+    }
+
+    /// Record times of building the graph and solving it.
+    pub fn record_times(&mut self, graph_build_time: Duration, solve_time: Duration) {
+        self.graph_build_time
+            .observe(graph_build_time.as_secs_f64());
+        self.solve_time.observe(solve_time.as_secs_f64());
+    }
+
+    /// Record a recompute of the graph of the node and edge counts, solved in the time.
+    pub fn record_recompute(&mut self, node_count: usize, edge_count: usize, solve_time: Duration) {
+        self.recomputes += 1;
+        self.node_count = node_count;
+        self.edge_count = edge_count;
+        self.last_solve_time = solve_time;
+    }
+
+    /// Get count of the recomputes of the graph.
+    pub fn get_recomputes(&self) -> u64 {
+        self.recomputes
+    }
+
+    /// Record a query answered by a best rate path cached since an earlier identical query.
+    pub fn record_cache_hit(&mut self) {
+        self.cache_hits += 1;
+    }
+
+    /// Get count of the queries answered from a cache.
+    pub fn get_cache_hits(&self) -> u64 {
+        self.cache_hits
+    }
+
+    /// Get count of the `(exchange, currency)` nodes of the last recomputed graph.
+    pub fn get_node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Get count of the edges of the last recomputed graph.
+    pub fn get_edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    /// Get time of solving the last recomputed graph.
+    pub fn get_last_solve_time(&self) -> Duration {
+        self.last_solve_time
+    }
+
+    /// Get the metrics in the Prometheus text exposition format.
+    pub fn get_output(&self) -> String {
+        let mut output = String::new();
+
+        let counters = [
+            (
+                "exchange_rate_price_updates_total",
+                "Price updates ingested.",
+                self.price_updates,
+            ),
+            (
+                "exchange_rate_outdated_price_updates_total",
+                "Price updates skipped as a more recent one was known.",
+                self.outdated_price_updates,
+            ),
+            (
+                "exchange_rate_rejected_price_updates_total",
+                "Price updates rejected before they were considered.",
+                self.rejected_price_updates,
+            ),
+            (
+                "exchange_rate_expired_price_updates_total",
+                "Price updates removed as their quotes left the window.",
+                self.expired_price_updates,
+            ),
+            (
+                "exchange_rate_answered_requests_total",
+                "Rate requests answered by a best rate path.",
+                self.answered_requests,
+            ),
+            (
+                "exchange_rate_unanswered_requests_total",
+                "Rate requests that could not be answered.",
+                self.unanswered_requests,
+            ),
+            (
+                "exchange_rate_parse_errors_total",
+                "Inputs that could not be parsed.",
+                self.parse_errors,
+            ),
+            (
+                "exchange_rate_recomputes_total",
+                "Recomputes of the graph.",
+                self.recomputes,
+            ),
+            (
+                "exchange_rate_cache_hits_total",
+                "Queries answered from a cache.",
+                self.cache_hits,
+            ),
+        ];
+
+        for (name, help, value) in counters.iter() {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} counter", name);
+            let _ = writeln!(output, "{} {}", name, value);
+        }
+
+        let gauges = [
+            (
+                "exchange_rate_nodes",
+                "Nodes of the last recomputed graph.",
+                self.node_count as f64,
+            ),
+            (
+                "exchange_rate_edges",
+                "Edges of the last recomputed graph.",
+                self.edge_count as f64,
+            ),
+            (
+                "exchange_rate_last_solve_seconds",
+                "Time of solving the last recomputed graph.",
+                self.last_solve_time.as_secs_f64(),
+            ),
+        ];
+
+        for (name, help, value) in gauges.iter() {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} gauge", name);
+            let _ = writeln!(output, "{} {}", name, value);
+        }
+
+        self.graph_build_time.write_output(
+            &mut output,
+            "exchange_rate_graph_build_seconds",
+            "Time of building the graph.",
+        );
+        self.solve_time.write_output(
+            &mut output,
+            "exchange_rate_solve_seconds",
+            "Time of solving the graph and forming the response.",
+        );
+        self.path_length.write_output(
+            &mut output,
+            "exchange_rate_path_length",
+            "Length of best rate paths, in nodes.",
+        );
+
+        output
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stream::metrics::Metrics;
+    use std::time::Duration;
+
+    #[test]
+    fn get_output() {
+        let mut metrics = Metrics::new();
+        metrics.record_price_update(false);
+        metrics.record_price_update(true);
+        metrics.record_answered_request(3);
+        metrics.record_parse_error();
+        metrics.record_times(Duration::from_millis(2), Duration::from_millis(20));
+        metrics.record_rejected_price_update();
+        metrics.record_expired_price_updates(2);
+        metrics.record_recompute(4, 6, Duration::from_millis(5));
+        assert_eq!(metrics.get_received_price_updates(), 3);
+
+        let output = metrics.get_output();
+        assert!(output.contains("# TYPE exchange_rate_price_updates_total counter\n"));
+        assert!(output.contains("\nexchange_rate_price_updates_total 1\n"));
+        assert!(output.contains("\nexchange_rate_outdated_price_updates_total 1\n"));
+        assert!(output.contains("\nexchange_rate_unanswered_requests_total 0\n"));
+        assert!(output.contains("\nexchange_rate_parse_errors_total 1\n"));
+        assert!(output.contains("\nexchange_rate_rejected_price_updates_total 1\n"));
+        assert!(output.contains("\nexchange_rate_expired_price_updates_total 2\n"));
+        assert!(output.contains("\nexchange_rate_recomputes_total 1\n"));
+        assert!(output.contains("# TYPE exchange_rate_nodes gauge\nexchange_rate_nodes 4\n"));
+        assert!(output.contains("\nexchange_rate_edges 6\n"));
+        assert!(output.contains("\nexchange_rate_last_solve_seconds 0.005\n"));
+
+        // Test that histogram buckets are cumulative.
+        assert!(output.contains("\nexchange_rate_path_length_bucket{le=\"2\"} 0\n"));
+        assert!(output.contains("\nexchange_rate_path_length_bucket{le=\"3\"} 1\n"));
+        assert!(output.contains("\nexchange_rate_path_length_bucket{le=\"+Inf\"} 1\n"));
+        assert!(output.contains("\nexchange_rate_solve_seconds_bucket{le=\"0.01\"} 0\n"));
+        assert!(output.contains("\nexchange_rate_solve_seconds_bucket{le=\"0.05\"} 1\n"));
+        assert!(output.contains("\nexchange_rate_graph_build_seconds_count 1\n"));
+    }
+}
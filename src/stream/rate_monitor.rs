@@ -0,0 +1,160 @@
+//! Rate Monitor.
+
+use crate::response::best_rate_path::BestRatePath;
+use crate::response::rate_alert::RateAlert;
+use floyd_warshall_alg::FloydWarshallTrait;
+use indexmap::IndexMap;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// State of a watched pair.
+struct WatchedRate<E> {
+    threshold: E,
+    // Rate of the last alert, or the first one known.
+    last_rate: Option<E>,
+}
+
+/// `RateMonitor` structure.
+///
+/// Watches best rates of pairs of nodes and alerts a rate once it moved more than the threshold
+/// of its pair, relative to the rate of the last alert. The first rate known of a pair is the
+/// one the following are compared to, it's not alerted.
+///
+/// # `RateMonitor<N, E>` is parameterized over:
+///
+/// - Identifier data `N`.
+/// - Edge weight `E`.
+pub struct RateMonitor<N, E> {
+    watched: IndexMap<(N, N, N, N), WatchedRate<E>>,
+}
+
+impl<N, E> RateMonitor<N, E>
+where
+    N: Clone + Display + Debug + Eq + Hash,
+    E: Display + Debug + FloydWarshallTrait,
+{
+    pub fn new() -> Self {
+        Self {
+            watched: IndexMap::new(),
+        }
+    }
+
+    /// Watch the `(source_exchange, source_currency, destination_exchange, destination_currency)`
+    /// pair, alerting moves of its rate by more than the relative threshold, e.g. `0.005` for
+    /// `0.5 %`. The rate of an already watched pair is compared to the next one known again.
+    pub fn add(&mut self, pair: (N, N, N, N), threshold: E) {
+        self.watched.insert(
+            pair,
+            WatchedRate {
+                threshold,
+                last_rate: None,
+            },
+        );
+    }
+
+    /// Stop watching the pair, return whether it was watched.
+    pub fn remove(&mut self, pair: &(N, N, N, N)) -> bool {
+        self.watched.shift_remove(pair).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watched.is_empty()
+    }
+
+    /// Get the watched pairs and their thresholds.
+    pub fn get_thresholds(&self) -> Vec<((N, N, N, N), E)> {
+        self.watched
+            .iter()
+            .map(|(pair, watched)| (pair.clone(), watched.threshold))
+            .collect()
+    }
+
+    /// Evaluate the best rate paths of the watched pairs, return alerts of the moved rates.
+    ///
+    /// Pairs without a best rate path keep the rate of their last alert.
+    pub fn evaluate(&mut self, best_rate_paths: &[BestRatePath<N, E>]) -> Vec<RateAlert<N, E>> {
+        let mut alerts = Vec::new();
+
+        for best_rate_path in best_rate_paths {
+            let pair = match (
+                best_rate_path.get_start_node(),
+                best_rate_path.get_end_node(),
+            ) {
+                (Some((a, b)), Some((c, d))) => (a.clone(), b.clone(), c.clone(), d.clone()),
+                _ => continue,
+            };
+            let watched = match self.watched.get_mut(&pair) {
+                Some(watched) => watched,
+                None => continue,
+            };
+
+            let rate = *best_rate_path.get_rate();
+            let last_rate = match watched.last_rate {
+                Some(last_rate) => last_rate,
+                None => {
+                    watched.last_rate = Some(rate);
+                    continue;
+                }
+            };
+
+            let change = if rate > last_rate {
+                rate - last_rate
+            } else {
+                last_rate - rate
+            };
+            if change > last_rate * watched.threshold {
+                watched.last_rate = Some(rate);
+                alerts.push(RateAlert::new(pair, last_rate, rate));
+            }
+        }
+
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::response::best_rate_path::BestRatePath;
+    use crate::stream::rate_monitor::RateMonitor;
+
+    fn best_rate_path(rate: f32) -> BestRatePath<String, f32> {
+        BestRatePath::new(
+            rate,
+            vec![
+                ("KRAKEN".to_string(), "BTC".to_string()),
+                ("GDAX".to_string(), "USD".to_string()),
+            ],
+        )
+    }
+
+    #[test]
+    fn evaluate() {
+        let mut monitor = RateMonitor::<String, f32>::new();
+        let pair = (
+            "KRAKEN".to_string(),
+            "BTC".to_string(),
+            "GDAX".to_string(),
+            "USD".to_string(),
+        );
+        monitor.add(pair.clone(), 0.005);
+
+        // Test that the first rate is not alerted, nor moves within the threshold.
+        assert!(monitor.evaluate(&[best_rate_path(1000.0)]).is_empty());
+        assert!(monitor.evaluate(&[best_rate_path(1004.0)]).is_empty());
+        assert!(monitor.evaluate(&[best_rate_path(996.0)]).is_empty());
+
+        // Test that a move beyond the threshold is alerted and becomes the new reference.
+        let alerts = monitor.evaluate(&[best_rate_path(1006.0)]);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].get_pair(), &pair);
+        assert_eq!(*alerts[0].get_last_rate(), 1000.0);
+        assert_eq!(*alerts[0].get_rate(), 1006.0);
+        assert!(monitor.evaluate(&[best_rate_path(1002.0)]).is_empty());
+        assert_eq!(monitor.evaluate(&[best_rate_path(1000.0)]).len(), 1);
+
+        // Test that unwatched pairs are not alerted.
+        assert!(monitor.remove(&pair));
+        assert!(monitor.is_empty());
+        assert!(monitor.evaluate(&[best_rate_path(2000.0)]).is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+//! Synthetic markets for benchmarks.
+//!
+//! A market is generated from a seed, so the same arguments always give the same input lines and
+//! runs before and after a change are measured on the same input.
+
+/// `Random` structure.
+///
+/// Seeded xorshift64* pseudo-random generator, good enough for generating inputs.
+pub(crate) struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// Create a new instance of `Random` structure.
+    pub(crate) fn new(seed: u64) -> Self {
+        Self {
+            // The state of xorshift can't be zero.
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Get the next pseudo-random number.
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Get a pseudo-random number less than the bound.
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Get a pseudo-random number between `0` inclusive and `1` exclusive.
+    pub(crate) fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+/// Generate input lines of a synthetic market.
+///
+/// Exchanges are named `E0`, `E1`, ... and currencies `C0`, `C1`, .... Every exchange quotes each
+/// pair of currencies with the probability of the density, between `0` and `1`. Currencies have
+/// a random value each and quotes are close to the ratio of the values, with a spread, so the
+/// market has few arbitrages. Price updates are followed by an exchange rate request from `C0`
+/// on `E0` to every other currency on the last exchange.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::synthetic;
+/// use exchange_rate::Request;
+///
+/// let lines = synthetic::market(3, 10, 0.5, 42);
+/// assert_eq!(lines, synthetic::market(3, 10, 0.5, 42));
+///
+/// let request = Request::<String, f64>::from_lines(lines);
+/// assert_eq!(request.get_rate_requests().len(), 9);
+/// ```
+pub fn market(exchanges: usize, currencies: usize, density: f64, seed: u64) -> Vec<String> {
+    let mut random = Random::new(seed);
+    let values: Vec<f64> = (0..currencies)
+        .map(|_| 10_f64.powf(random.unit() * 6.0 - 3.0))
+        .collect();
+
+    let mut lines = Vec::new();
+    for exchange in 0..exchanges {
+        for source in 0..currencies {
+            for destination in source + 1..currencies {
+                if random.unit() >= density {
+                    continue;
+                }
+
+                let rate = values[source] / values[destination] * (0.99 + random.unit() * 0.02);
+                let spread = 1.0 - random.unit() * 0.01;
+                lines.push(format!(
+                    "2019-01-20T{:02}:{:02}:{:02}+00:00 E{} C{} C{} {} {}",
+                    random.below(24),
+                    random.below(60),
+                    random.below(60),
+                    exchange,
+                    source,
+                    destination,
+                    rate,
+                    spread / rate
+                ));
+            }
+        }
+    }
+
+    let last_exchange = exchanges.saturating_sub(1);
+    for destination in 1..currencies {
+        lines.push(format!(
+            "EXCHANGE_RATE_REQUEST E0 C0 E{} C{}",
+            last_exchange, destination
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request::Request;
+    use crate::synthetic::market;
+
+    #[test]
+    fn market_density() {
+        // Test that a full density quotes every pair on every exchange.
+        let lines = market(2, 5, 1.0, 1);
+        let request = Request::<String, f64>::from_lines(lines);
+        assert_eq!(request.get_price_updates().len(), 2 * 10);
+        assert_eq!(request.get_rate_requests().len(), 4);
+
+        let lines = market(2, 5, 0.0, 1);
+        assert_eq!(lines.len(), 4);
+
+        // Test that other seeds give other markets.
+        assert_ne!(market(2, 5, 0.5, 1), market(2, 5, 0.5, 2));
+    }
+
+    #[test]
+    fn answer_market() {
+        let request = Request::<String, f64>::from_lines(market(3, 6, 1.0, 7));
+        let response = request.answer(&Default::default());
+
+        // Test that all currencies are reachable in a fully quoted market.
+        assert_eq!(response.get_best_rate_paths().len(), 5);
+        assert!(response.get_unanswered_requests().is_empty());
+    }
+}
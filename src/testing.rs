@@ -0,0 +1,292 @@
+//! Generators of input lines for property tests and scenarios for regression tests.
+//!
+//! A `Generator` produces valid and adversarial `PriceUpdate` and `ExchangeRateRequest` lines
+//! and whole `Request`s from a seed, so a property is checked over many generated inputs and a
+//! failing one is reproduced by its seed. It's a plain pseudo-random generator rather than
+//! `proptest` strategies, the crate doesn't depend on `proptest`, and failing inputs aren't
+//! shrunk.
+//!
+//! A `Scenario` bundles input lines with the output expected of them and notes describing them.
+
+pub mod scenario;
+
+pub use self::scenario::Scenario;
+
+use crate::request::exchange_rate_request::ExchangeRateRequest;
+use crate::request::price_update::PriceUpdate;
+use crate::request::Request;
+use crate::synthetic::Random;
+use crate::IndexMapTrait;
+use num_traits::Zero;
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+/// `Generator` structure.
+///
+/// Generates lines of the identifiers `E0`, `E1`, ... of exchanges and `C0`, `C1`, ... of
+/// currencies, so generated price updates and requests share their nodes.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::testing::Generator;
+/// use exchange_rate::Request;
+///
+/// let mut generator = Generator::new(7);
+/// for _ in 0..100 {
+///     // Adversarial lines are rejected by an error, never by a panic.
+///     let _ = Request::<String, f64>::parse_line(&generator.adversarial_line());
+/// }
+///
+/// let request = generator.request::<String, f64>(20, 5);
+/// assert_eq!(request.get_rate_requests().len() <= 5, true);
+/// ```
+pub struct Generator {
+    random: Random,
+    exchanges: usize,
+    currencies: usize,
+}
+
+impl Generator {
+    /// Create a new instance of `Generator` structure of 3 exchanges and 4 currencies.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            random: Random::new(seed),
+            exchanges: 3,
+            currencies: 4,
+        }
+    }
+
+    /// Set counts of the exchanges and currencies the lines are generated of, at least 1 and 2.
+    pub fn with_identifiers(mut self, exchanges: usize, currencies: usize) -> Self {
+        self.exchanges = exchanges.max(1);
+        self.currencies = currencies.max(2);
+        self
+    }
+
+    /// Get the next pseudo-random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.random.next_u64()
+    }
+
+    /// Get a pseudo-random number less than the bound.
+    fn below(&mut self, bound: usize) -> usize {
+        self.random.below(bound)
+    }
+
+    /// Get a pseudo-random rate between `0.001` and `1000`.
+    fn rate(&mut self) -> f64 {
+        10_f64.powf(self.below(6000) as f64 / 1000.0 - 3.0)
+    }
+
+    fn exchange(&mut self) -> String {
+        format!("E{}", self.below(self.exchanges))
+    }
+
+    fn currency(&mut self) -> String {
+        format!("C{}", self.below(self.currencies))
+    }
+
+    /// Get a valid price update line of distinct currencies, its backward rate isn't better than
+    /// the inverse of the forward one, e.g.
+    /// `2019-01-20T09:42:23+00:00 E1 C0 C2 12.5 0.0795`.
+    pub fn price_update_line(&mut self) -> String {
+        let timestamp = format!(
+            "2019-01-20T{:02}:{:02}:{:02}+00:00",
+            self.below(24),
+            self.below(60),
+            self.below(60)
+        );
+        let exchange = self.exchange();
+        let source = self.currency();
+        let destination = loop {
+            let currency = self.currency();
+            if currency != source {
+                break currency;
+            }
+        };
+
+        let rate = self.rate();
+        let spread = 1.0 - self.below(100) as f64 / 10_000.0;
+        let quote = if self.below(4) == 0 {
+            format!(
+                "{} {} {}",
+                PriceUpdate::<String, f64>::BID_ASK,
+                rate * spread,
+                rate
+            )
+        } else {
+            format!("{} {}", rate, spread / rate)
+        };
+
+        format!(
+            "{} {} {} {} {}",
+            timestamp, exchange, source, destination, quote
+        )
+    }
+
+    /// Get a valid exchange rate request line, e.g. `EXCHANGE_RATE_REQUEST E0 C1 E2 C3`.
+    pub fn rate_request_line(&mut self) -> String {
+        format!(
+            "EXCHANGE_RATE_REQUEST {} {} {} {}",
+            self.exchange(),
+            self.currency(),
+            self.exchange(),
+            self.currency()
+        )
+    }
+
+    /// Get an adversarial line, a valid one broken at random: items dropped, duplicated or
+    /// swapped, replaced by garbage, extreme or non-numeric values, or by other whitespace. It
+    /// may still be valid, it's meant to stress the parsing.
+    pub fn adversarial_line(&mut self) -> String {
+        const GARBAGE: [&str; 12] = [
+            "",
+            "-1",
+            "0",
+            "NaN",
+            "inf",
+            "1e309",
+            "-0",
+            "BID_ASK",
+            "EXCHANGE_RATE_REQUEST",
+            "2019-13-45T99:99:99+00:00",
+            "ü€",
+            "0x10",
+        ];
+
+        let line = if self.below(2) == 0 {
+            self.price_update_line()
+        } else {
+            self.rate_request_line()
+        };
+        let mut items: Vec<String> = line.split(' ').map(String::from).collect();
+
+        for _ in 0..=self.below(3) {
+            let index = self.below(items.len());
+            match self.below(6) {
+                0 => {
+                    items.remove(index);
+                }
+                1 => items.insert(index, items[index].clone()),
+                2 => {
+                    let other = self.below(items.len());
+                    items.swap(index, other);
+                }
+                3 => items[index] = GARBAGE[self.below(GARBAGE.len())].to_string(),
+                4 => items[index] = items[index].to_lowercase(),
+                _ => items.truncate(index),
+            }
+            if items.is_empty() {
+                break;
+            }
+        }
+
+        let separator = ["  ", "\t", " "][self.below(3)];
+        items.join(separator)
+    }
+
+    /// Get the valid lines of the count of price updates and then rate requests.
+    pub fn lines(&mut self, price_updates: usize, rate_requests: usize) -> Vec<String> {
+        let mut lines: Vec<String> = (0..price_updates)
+            .map(|_| self.price_update_line())
+            .collect();
+        lines.extend((0..rate_requests).map(|_| self.rate_request_line()));
+
+        lines
+    }
+
+    /// Get a valid `PriceUpdate`.
+    pub fn price_update<N, E>(&mut self) -> PriceUpdate<N, E>
+    where
+        N: Clone + FromStr,
+        <N as FromStr>::Err: Debug,
+        E: FromStr + Zero + PartialOrd,
+        <E as FromStr>::Err: Debug,
+    {
+        PriceUpdate::parse_line(&self.price_update_line())
+            .expect("The generated price update line is valid!")
+    }
+
+    /// Get a valid `ExchangeRateRequest`.
+    pub fn rate_request<N, E>(&mut self) -> ExchangeRateRequest<N, E>
+    where
+        N: Clone + FromStr,
+        <N as FromStr>::Err: Debug,
+        E: FromStr,
+        <E as FromStr>::Err: Debug,
+    {
+        ExchangeRateRequest::parse_line(&self.rate_request_line())
+            .expect("The generated rate request line is valid!")
+    }
+
+    /// Get a `Request` of the valid lines, see `lines`. Price updates and rate requests of the
+    /// same nodes replace earlier ones, so it may hold less of them.
+    pub fn request<N, E>(&mut self, price_updates: usize, rate_requests: usize) -> Request<N, E>
+    where
+        N: Clone + Display + FromStr + IndexMapTrait,
+        <N as FromStr>::Err: Debug,
+        E: FromStr + Zero + PartialOrd,
+        <E as FromStr>::Err: Debug,
+    {
+        Request::from_lines(self.lines(price_updates, rate_requests))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request::price_update::PriceUpdate;
+    use crate::request::Request;
+    use crate::testing::Generator;
+
+    #[test]
+    fn parse_adversarial_lines() {
+        let mut generator = Generator::new(1);
+
+        // Test that no line makes the parsing panic.
+        for _ in 0..2000 {
+            let _ = Request::<String, f64>::parse_line(&generator.adversarial_line());
+        }
+    }
+
+    #[test]
+    fn price_update_line_round_trip() {
+        let mut generator = Generator::new(2);
+
+        for _ in 0..500 {
+            let price_update = generator.price_update::<String, f64>();
+            assert_eq!(
+                PriceUpdate::parse_line(&price_update.get_line()).unwrap(),
+                price_update
+            );
+        }
+    }
+
+    #[test]
+    fn solve_generated_requests() {
+        for seed in 0..20 {
+            let mut generator = Generator::new(seed).with_identifiers(4, 5);
+            let request = generator.request::<String, f64>(30, 10);
+            let response = request.answer(&Default::default());
+
+            // Test that each request is answered by a path between its nodes, or left unanswered.
+            assert_eq!(
+                response.get_best_rate_paths().len() + response.get_unanswered_requests().len(),
+                request.get_rate_requests().len()
+            );
+            for best_rate_path in response.get_best_rate_paths() {
+                let (source_exchange, source_currency) = best_rate_path.get_start_node().unwrap();
+                let (destination_exchange, destination_currency) =
+                    best_rate_path.get_end_node().unwrap();
+                let index = (
+                    source_exchange.clone(),
+                    source_currency.clone(),
+                    destination_exchange.clone(),
+                    destination_currency.clone(),
+                );
+
+                assert!(request.get_rate_requests().contains_key(&index));
+                assert!(*best_rate_path.get_rate() > 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Scenarios of input with the expected output.
+
+use crate::algorithm::settings::Settings;
+use crate::error::Error;
+use crate::request::Request;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Bundle the fixture files of the scenario name.
+macro_rules! bundled {
+    ($name:literal) => {
+        (
+            $name,
+            include_str!(concat!("../../tests/fixtures/", $name, ".txt")),
+            include_str!(concat!("../../tests/fixtures/", $name, ".out")),
+            include_str!(concat!("../../tests/fixtures/", $name, ".md")),
+        )
+    };
+}
+
+/// Scenarios bundled with the crate: name, input, expected output and notes.
+const BUNDLED: [(&str, &str, &str, &str); 4] = [
+    bundled!("arbitrage"),
+    bundled!("bid_ask"),
+    bundled!("exchange_rate"),
+    bundled!("unanswered"),
+];
+
+/// `Scenario` structure.
+///
+/// Input lines together with the output expected of them and notes describing what the scenario
+/// shows. The scenarios of the crate's own golden file tests are bundled with it and loaded by
+/// name, a regression suite of another crate loads its own ones from a directory of the same
+/// layout: `<name>.txt` of the input, `<name>.out` of the expected output and an optional
+/// `<name>.md` of the notes.
+///
+/// # Examples
+/// ```
+/// use exchange_rate::testing::Scenario;
+///
+/// let scenario = Scenario::load("unanswered").unwrap();
+/// assert!(scenario.get_notes().contains("DOGE"));
+/// assert_eq!(scenario.run(), scenario.get_expected_output());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    name: String,
+    input: String,
+    expected_output: String,
+    notes: String,
+}
+
+impl Scenario {
+    /// Create a new instance of `Scenario` structure.
+    pub fn new(name: &str, input: &str, expected_output: &str, notes: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            input: input.to_string(),
+            expected_output: expected_output.to_string(),
+            notes: notes.to_string(),
+        }
+    }
+
+    /// Load the bundled scenario of the name, `None` if there's no such scenario.
+    pub fn load(name: &str) -> Option<Self> {
+        BUNDLED
+            .iter()
+            .find(|(bundled_name, ..)| *bundled_name == name)
+            .map(|(name, input, expected_output, notes)| {
+                Self::new(name, input, expected_output, notes)
+            })
+    }
+
+    /// Load all bundled scenarios, ordered by their names.
+    pub fn load_all() -> Vec<Self> {
+        BUNDLED
+            .iter()
+            .map(|(name, input, expected_output, notes)| {
+                Self::new(name, input, expected_output, notes)
+            })
+            .collect()
+    }
+
+    /// Load the scenario of the name from the files of the directory.
+    pub fn load_from(directory: &Path, name: &str) -> Result<Self, Error> {
+        let read = |extension| fs::read_to_string(directory.join(name).with_extension(extension));
+
+        let notes = match read("md") {
+            Ok(notes) => notes,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(Error::Io(error)),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            input: read("txt").map_err(Error::Io)?,
+            expected_output: read("out").map_err(Error::Io)?,
+            notes,
+        })
+    }
+
+    /// Get name of the scenario.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get input lines of the scenario.
+    pub fn get_input(&self) -> &str {
+        &self.input
+    }
+
+    /// Get output expected of the input.
+    pub fn get_expected_output(&self) -> &str {
+        &self.expected_output
+    }
+
+    /// Get notes describing the scenario.
+    pub fn get_notes(&self) -> &str {
+        &self.notes
+    }
+
+    /// Run the input through the whole pipeline, answering it by default `Settings` of `String`
+    /// identifiers and `f64` rates, and get its output.
+    pub fn run(&self) -> String {
+        Request::<String, f64>::from_lines(self.input.lines())
+            .answer(&Settings::new())
+            .get_output()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::scenario::Scenario;
+    use std::path::Path;
+
+    #[test]
+    fn load() {
+        let scenario = Scenario::load("arbitrage").unwrap();
+        assert_eq!(scenario.get_name(), "arbitrage");
+        assert!(scenario
+            .get_input()
+            .contains("ARBITRAGE_REQUEST KRAKEN BTC"));
+        assert!(!scenario.get_notes().is_empty());
+
+        assert_eq!(Scenario::load("missing"), None);
+
+        // Test that a bundled scenario is the same as the one of the fixture files.
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        assert_eq!(
+            Scenario::load_from(&directory, "arbitrage").unwrap(),
+            scenario
+        );
+        assert!(Scenario::load_from(&directory, "missing").is_err());
+    }
+}
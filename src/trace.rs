@@ -0,0 +1,141 @@
+//! Timing spans of the pipeline stages.
+//!
+//! With the `trace` feature, every finished span writes a line of the folded stacks format read
+//! by flame graph tools, e.g. `read_from;construct_graph 1250`: the names of the spans open on
+//! the thread, outermost first, and the microseconds spent in the innermost one outside of its
+//! nested spans. Lines are written to the standard error output, unless another sink is set.
+//! Without the feature spans cost nothing.
+
+#[cfg(feature = "trace")]
+use std::cell::RefCell;
+#[cfg(feature = "trace")]
+use std::sync::OnceLock;
+#[cfg(feature = "trace")]
+use std::time::Instant;
+
+/// Sink of the written lines, set once.
+#[cfg(feature = "trace")]
+static SINK: OnceLock<fn(&str)> = OnceLock::new();
+
+#[cfg(feature = "trace")]
+thread_local! {
+    /// Spans open on the thread, outermost first, with microseconds spent in their nested ones.
+    static STACK: RefCell<Vec<(&'static str, u128)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Set the sink the lines of finished spans are written to instead of the standard error output.
+///
+/// Return `false` if a sink was set already, it's kept then.
+#[cfg(feature = "trace")]
+pub fn set_trace_sink(sink: fn(&str)) -> bool {
+    SINK.set(sink).is_ok()
+}
+
+/// `Span` structure.
+///
+/// Guard of a span open until it's dropped.
+#[cfg(feature = "trace")]
+pub(crate) struct Span {
+    start: Instant,
+}
+
+/// `Span` structure.
+///
+/// Guard of a span, doing nothing without the `trace` feature.
+#[cfg(not(feature = "trace"))]
+pub(crate) struct Span;
+
+/// Open a span of the name on the current thread.
+#[cfg(feature = "trace")]
+pub(crate) fn span(name: &'static str) -> Span {
+    STACK.with(|stack| stack.borrow_mut().push((name, 0)));
+
+    Span {
+        start: Instant::now(),
+    }
+}
+
+/// Open a span of the name on the current thread.
+#[cfg(not(feature = "trace"))]
+pub(crate) fn span(_name: &'static str) -> Span {
+    Span
+}
+
+#[cfg(feature = "trace")]
+impl Drop for Span {
+    /// Close the span and write the line of the time spent in it.
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed().as_micros();
+
+        let line = STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            let names: Vec<&str> = stack.iter().map(|(name, _)| *name).collect();
+            let (_, nested) = stack.pop()?;
+            if let Some((_, parent_nested)) = stack.last_mut() {
+                *parent_nested += elapsed;
+            }
+
+            Some(format!(
+                "{} {}",
+                names.join(";"),
+                elapsed.saturating_sub(nested)
+            ))
+        });
+
+        if let Some(line) = line {
+            match SINK.get() {
+                Some(sink) => sink(&line),
+                None => eprintln!("{}", line),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "trace"))]
+mod tests {
+    use crate::trace::{set_trace_sink, span};
+    use std::sync::{Mutex, PoisonError};
+    use std::thread;
+    use std::time::Duration;
+
+    static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn collect(line: &str) {
+        LINES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(line.to_string());
+    }
+
+    #[test]
+    fn nested_spans() {
+        set_trace_sink(collect);
+
+        // Spans of another thread form stacks of their own.
+        thread::spawn(|| {
+            let _outer = span("test_outer");
+            {
+                let _inner = span("test_inner");
+                thread::sleep(Duration::from_millis(20));
+            }
+        })
+        .join()
+        .unwrap();
+
+        let lines: Vec<String> = LINES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|line| line.starts_with("test_outer"))
+            .cloned()
+            .collect();
+        assert_eq!(lines.len(), 2);
+
+        // Test that the time of the nested span is not counted to the outer one again.
+        let micros = |line: &str| line.rsplit(' ').next().unwrap().parse::<u128>().unwrap();
+        assert!(lines[0].starts_with("test_outer;test_inner "));
+        assert!(micros(&lines[0]) >= 20_000);
+        assert!(lines[1].starts_with("test_outer "));
+        assert!(micros(&lines[1]) < 20_000);
+    }
+}
@@ -0,0 +1,121 @@
+//! WebAssembly API of the core: parsing and the algorithm, without stdin and stdout.
+//!
+//! `solve` answers the whole input text by a JSON object, the same the HTTP API answers. Built
+//! for `wasm32-unknown-unknown` as a `cdylib`, it's exported over the C ABI along with the
+//! allocation of the input, as there's no `wasm-bindgen` dependency, and the module imports
+//! nothing. The raw ABI, all lengths in bytes:
+//!
+//! - `alloc(length) -> pointer` allocates memory for the input, the JavaScript side writes the
+//!   UTF-8 input text into it.
+//! - `solve(pointer, length) -> pointer` answers the input and gives pointer to the UTF-8 JSON
+//!   output, owned by the module and valid until the next `solve`. The input stays allocated.
+//! - `output_length() -> length` gives length of the output of the last `solve`.
+//! - `dealloc(pointer, length)` frees the input, by the pointer and length of its `alloc`.
+//!
+//! `tests/wasm/solve.mjs` drives the built module by Node.js the same way, see the README.
+
+use crate::algorithm::settings::Settings;
+use crate::algorithm::Algorithm;
+use crate::json;
+use crate::request::Request;
+
+/// Answer the input text of price updates and requests by the JSON object of best rate paths
+/// and unanswered requests, see `Response::get_json`.
+///
+/// Invalid lines aren't skipped, the input is answered by `{"errors":[...]}` of their messages.
+pub fn solve(input_text: &str) -> String {
+    let mut request = Request::<String, f64>::new();
+    let mut errors = vec![];
+
+    for (number, line) in input_text.lines().enumerate() {
+        match Request::parse_line(line) {
+            Ok(Some(line)) => request.add_line(line),
+            Ok(None) => {}
+            Err(error) => errors.extend(
+                error
+                    .get_messages()
+                    .iter()
+                    .map(|message| format!("Line {}: {}", number + 1, message)),
+            ),
+        }
+    }
+
+    if !errors.is_empty() {
+        return json::errors(&errors);
+    }
+
+    Algorithm::<String, f64, u32>::process_customized(&request, &Settings::new()).get_json()
+}
+
+#[cfg(target_arch = "wasm32")]
+mod exports {
+    use std::cell::RefCell;
+    use std::{slice, str};
+
+    thread_local! {
+        /// Output of the last `solve`, kept until the next one.
+        static OUTPUT: RefCell<String> = const { RefCell::new(String::new()) };
+    }
+
+    /// Allocate memory for the input of the length.
+    #[no_mangle]
+    pub extern "C" fn alloc(length: usize) -> *mut u8 {
+        let mut buffer = Vec::<u8>::with_capacity(length);
+        let pointer = buffer.as_mut_ptr();
+        std::mem::forget(buffer);
+        pointer
+    }
+
+    /// Free memory of the input allocated by `alloc`.
+    ///
+    /// # Safety
+    ///
+    /// The pointer and length are of a single `alloc` call.
+    #[no_mangle]
+    pub unsafe extern "C" fn dealloc(pointer: *mut u8, length: usize) {
+        drop(Vec::from_raw_parts(pointer, 0, length));
+    }
+
+    /// Solve the UTF-8 input text and get pointer to the output, of `output_length` bytes.
+    ///
+    /// # Safety
+    ///
+    /// The pointer and length address the input written into memory of `alloc`.
+    #[no_mangle]
+    pub unsafe extern "C" fn solve(pointer: *const u8, length: usize) -> *const u8 {
+        let output = match str::from_utf8(slice::from_raw_parts(pointer, length)) {
+            Ok(input_text) => super::solve(input_text),
+            Err(_) => crate::json::errors(&["The input is not valid UTF-8!".to_string()]),
+        };
+
+        OUTPUT.with(|cell| {
+            *cell.borrow_mut() = output;
+            cell.borrow().as_ptr()
+        })
+    }
+
+    /// Get length of the output of the last `solve` in bytes.
+    #[no_mangle]
+    pub extern "C" fn output_length() -> usize {
+        OUTPUT.with(|cell| cell.borrow().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wasm::solve;
+
+    #[test]
+    fn solve_input_text() {
+        let output = solve(
+            "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009\n\
+             \n\
+             EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD\n",
+        );
+        assert!(output.starts_with("{\"best_rate_paths\":[{\"rate\":1000,"));
+
+        // Test that invalid lines are reported by their numbers.
+        let output = solve("EXCHANGE_RATE_REQUEST KRAKEN BTC\n");
+        assert!(output.starts_with("{\"errors\":[\"Line 1: "));
+    }
+}
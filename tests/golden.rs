@@ -0,0 +1,73 @@
+//! Golden file tests of the whole pipeline.
+//!
+//! Every `tests/fixtures/<name>.txt` input is read, answered and rendered, and the output is
+//! compared to the expected one stored in `tests/fixtures/<name>.out`. Run with the
+//! `GOLDEN_REGENERATE=1` environment variable to write the current outputs as the expected ones
+//! instead, then review the changes of the `.out` files.
+
+use exchange_rate::{Request, Settings};
+use std::env;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Environment variable turning on the regeneration of the expected outputs.
+const REGENERATE: &str = "GOLDEN_REGENERATE";
+
+fn fixtures() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut inputs: Vec<PathBuf> = fs::read_dir(directory)
+        .expect("The fixtures directory is readable!")
+        .map(|entry| entry.expect("The fixture is readable!").path())
+        .filter(|path| path.extension() == Some("txt".as_ref()))
+        .collect();
+    inputs.sort();
+
+    inputs
+}
+
+fn run(input: &Path) -> String {
+    let file = fs::File::open(input).expect("The input is readable!");
+    let request = Request::<String, f64>::read_from(&mut BufReader::new(file));
+
+    request.answer(&Settings::new()).get_output()
+}
+
+#[test]
+fn golden_files() {
+    let regenerate = env::var_os(REGENERATE).is_some();
+    let mut mismatches = Vec::new();
+
+    for input in fixtures() {
+        let expected_path = input.with_extension("out");
+        let output = run(&input);
+
+        if regenerate {
+            fs::write(&expected_path, &output).expect("The expected output is writable!");
+            continue;
+        }
+
+        let expected = fs::read_to_string(&expected_path).unwrap_or_else(|_| {
+            panic!(
+                "The expected output <{}> is missing, run with {}=1 to write it!",
+                expected_path.display(),
+                REGENERATE
+            )
+        });
+        if output != expected {
+            mismatches.push(format!(
+                "<{}>\n--- expected\n{}--- actual\n{}",
+                input.display(),
+                expected,
+                output
+            ));
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "Outputs differ from the expected ones, run with {}=1 to accept them:\n{}",
+        REGENERATE,
+        mismatches.join("\n")
+    );
+}
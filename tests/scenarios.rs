@@ -0,0 +1,19 @@
+//! Scenarios bundled with the crate, checked against their expected outputs.
+
+use exchange_rate::testing::Scenario;
+
+#[test]
+fn bundled_scenarios() {
+    let scenarios = Scenario::load_all();
+    assert!(!scenarios.is_empty());
+
+    for scenario in scenarios {
+        assert_eq!(
+            scenario.run(),
+            scenario.get_expected_output(),
+            "The scenario <{}> differs: {}",
+            scenario.get_name(),
+            scenario.get_notes()
+        );
+    }
+}
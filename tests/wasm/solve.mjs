@@ -0,0 +1,56 @@
+// Drives the raw ABI of the WebAssembly module the way the README shows, run after building it:
+// `cargo rustc --release --target wasm32-unknown-unknown --lib --no-default-features --features wasm --crate-type cdylib`
+// `node tests/wasm/solve.mjs`
+import assert from "assert/strict";
+import { readFileSync } from "fs";
+
+const bytes = readFileSync(
+  new URL("../../target/wasm32-unknown-unknown/release/exchange_rate.wasm", import.meta.url),
+);
+assert.deepEqual(WebAssembly.Module.imports(new WebAssembly.Module(bytes)), []);
+
+const { instance } = await WebAssembly.instantiate(bytes);
+const { memory, alloc, dealloc, solve, output_length } = instance.exports;
+
+function run(inputText) {
+  const input = new TextEncoder().encode(inputText);
+  const pointer = alloc(input.length);
+  new Uint8Array(memory.buffer, pointer, input.length).set(input);
+  const output = solve(pointer, input.length);
+  const json = JSON.parse(
+    new TextDecoder().decode(new Uint8Array(memory.buffer, output, output_length())),
+  );
+  dealloc(pointer, input.length);
+  return json;
+}
+
+assert.deepEqual(
+  run(
+    "2017-11-01T09:42:23+00:00 KRAKEN BTC USD 1000.0 0.0009\n" +
+      "2017-11-01T09:43:23+00:00 GDAX BTC USD 1001.0 0.0008\n" +
+      "EXCHANGE_RATE_REQUEST GDAX BTC KRAKEN USD\n",
+  ).best_rate_paths,
+  [
+    {
+      rate: 1001,
+      path: [
+        { exchange: "GDAX", currency: "BTC" },
+        { exchange: "GDAX", currency: "USD" },
+        { exchange: "KRAKEN", currency: "USD" },
+      ],
+    },
+  ],
+);
+
+const unanswered = run("EXCHANGE_RATE_REQUEST KRAKEN BTC KRAKEN USD\n");
+assert.deepEqual(unanswered.best_rate_paths, []);
+assert.equal(unanswered.unanswered_requests.length, 1);
+
+assert.deepEqual(run("EXCHANGE_RATE_REQUEST KRAKEN BTC\n").errors, [
+  "Line 1: The line item <source_exchange> is missing!",
+  "Line 1: The line item <destination_exchange> is missing!",
+]);
+
+assert.deepEqual(run(""), { best_rate_paths: [], unanswered_requests: [] });
+
+console.log("wasm: ok");